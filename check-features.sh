@@ -0,0 +1,23 @@
+#!/bin/sh
+# Baut jede Kombination der Features mit clippy, wie cargo hack
+# --feature-powerset, ohne das Werkzeug zu brauchen. Bricht beim ersten
+# Fehler ab.
+set -e
+features="charts http-api mqtt storage-sqlite"
+count=$(echo $features | wc -w)
+
+mask=0
+while [ $mask -lt $((1 << count)) ]; do
+    selected=""
+    bit=0
+    for feature in $features; do
+        if [ $((mask >> bit & 1)) -eq 1 ]; then
+            selected="${selected:+$selected,}$feature"
+        fi
+        bit=$((bit + 1))
+    done
+    echo "== ${selected:-keine Features}"
+    cargo clippy --quiet --all-targets --no-default-features ${selected:+--features "$selected"} -- -D warnings
+    mask=$((mask + 1))
+done
+echo "Alle $((1 << count)) Kombinationen bauen"
@@ -0,0 +1,86 @@
+// Bot eingebettet in eine andere Anwendung: Messwerte aus einer eigenen
+// Quelle, Nachrichten ins Terminal statt an Telegram, Zustand nur im Speicher.
+//
+//     cargo run --example embedded
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use telegrambot::{
+    BoxFuture, FetchError, History, Messenger, OutgoingMessage, SendError, SensorBot, SensorData, SensorSource, Settings, Store, UptimeLog, UserConfig,
+};
+
+// Feste Messwerte, wie sie z.B. aus einem Hausautomations-Bus kämen
+struct FakeSource;
+
+impl SensorSource for FakeSource {
+    fn fetch(&self) -> BoxFuture<'_, Result<Vec<SensorData>, FetchError>> {
+        Box::pin(async {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+            Ok(vec![
+                SensorData { device_id: "Wohnzimmer".into(), sensor_type: "temperature".into(), value: 21.5, timestamp: now },
+                SensorData { device_id: "Wohnzimmer".into(), sensor_type: "humidity".into(), value: 48.0, timestamp: now },
+            ])
+        })
+    }
+}
+
+struct PrintMessenger;
+
+impl Messenger for PrintMessenger {
+    fn send<'a>(&'a self, message: &'a OutgoingMessage) -> BoxFuture<'a, Result<(), SendError>> {
+        Box::pin(async move {
+            println!("→ Chat {}: {}", message.chat_id, message.text);
+            Ok(())
+        })
+    }
+}
+
+// Nichts auf der Platte; Benutzerkonfiguration bleibt bis zum Ende im Speicher
+#[derive(Default)]
+struct MemoryStore {
+    users: Mutex<HashMap<i64, UserConfig>>,
+}
+
+impl Store for MemoryStore {
+    fn load_users(&self) -> HashMap<i64, UserConfig> {
+        self.users.lock().unwrap().clone()
+    }
+
+    fn save_users(&self, users: &HashMap<i64, UserConfig>) {
+        *self.users.lock().unwrap() = users.clone();
+    }
+
+    fn load_history(&self) -> History {
+        History::default()
+    }
+
+    fn save_history(&self, _history: &History) {}
+
+    fn load_uptime(&self) -> UptimeLog {
+        UptimeLog::default()
+    }
+
+    fn save_uptime(&self, _uptime: &UptimeLog) {}
+}
+
+#[tokio::main]
+async fn main() {
+    let bot = SensorBot::builder()
+        .messenger(PrintMessenger)
+        .source(FakeSource)
+        .store(MemoryStore::default())
+        .settings(Settings { lock_file: None, ..Settings::default() })
+        .run()
+        .await
+        .expect("Bot startet nicht");
+
+    // Erster Überwachungsdurchlauf läuft sofort nach dem Start
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    for reading in bot.snapshot().await {
+        println!("{} {}: {:.1}", reading.device_id, reading.sensor_type, reading.value);
+    }
+    println!("Gestartet: {:?}", bot.health().await.started_at);
+
+    bot.shutdown().await;
+}
@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Freigabe oder Sperre eines Chats durch den Admin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Grant {
+    pub allowed: bool,
+    pub since: DateTime<Utc>,
+    pub by: String, // Name oder Chat-ID des Admins
+}
+
+// Per /allow und /deny freigegebene oder gesperrte Chats. Ein Eintrag hier
+// geht ALLOWED_CHAT_IDS vor, /deny sperrt also auch dort genannte Chats.
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AccessList {
+    chats: BTreeMap<i64, Grant>,
+}
+
+impl AccessList {
+    // Some(true|false), wenn der Admin über den Chat entschieden hat
+    pub fn decision(&self, chat_id: i64) -> Option<bool> {
+        self.chats.get(&chat_id).map(|grant| grant.allowed)
+    }
+
+    // Gibt es überhaupt Freigaben? Ohne sie und ohne ALLOWED_CHAT_IDS ist der
+    // Bot für alle offen wie bisher.
+    pub fn restricts(&self) -> bool {
+        self.chats.values().any(|grant| grant.allowed)
+    }
+
+    // false, wenn der Chat schon so eingetragen war
+    pub fn set(&mut self, chat_id: i64, allowed: bool, by: String, now: DateTime<Utc>) -> bool {
+        if self.decision(chat_id) == Some(allowed) {
+            return false;
+        }
+        self.chats.insert(chat_id, Grant { allowed, since: now, by });
+        true
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&i64, &Grant)> {
+        self.chats.iter()
+    }
+}
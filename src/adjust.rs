@@ -0,0 +1,206 @@
+use crate::i18n::{self, Lang};
+use crate::sensor::{ThresholdDirection, ThresholdKey};
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardButtonKind, InlineKeyboardMarkup};
+
+// Telegram erlaubt höchstens 64 Byte Callback-Daten
+const MAX_CALLBACK_BYTES: usize = 64;
+const PREFIX: &str = "thr";
+
+// Was ein Button unter einer Warnung mit der Schwelle macht
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Adjust {
+    Shift(f64),        // um eine Anzeigeeinheit verschieben
+    Ask,               // neuen Wert im Dialog erfragen
+    Set(f64),          // auf einen vorgeschlagenen Wert setzen
+    Disable,           // Schwelle löschen
+    Acknowledge,       // Warnung bestätigen ("✅ OK")
+    Snooze(SnoozeFor), // Schwellen der Warnung stummschalten
+}
+
+// Dauer der Stummschaltung per Button unter einer Warnung
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnoozeFor {
+    Hours(i64),
+    Tomorrow, // bis zum nächsten Morgen, Ortszeit
+}
+
+const SNOOZE_OPTIONS: [(&str, SnoozeFor); 3] = [("z1", SnoozeFor::Hours(1)), ("z6", SnoozeFor::Hours(6)), ("zm", SnoozeFor::Tomorrow)];
+
+fn snooze_label(span: SnoozeFor, lang: Lang) -> String {
+    match span {
+        SnoozeFor::Hours(hours) => format!("😴 {}h", hours),
+        SnoozeFor::Tomorrow => i18n::message(lang, "snooze_until_tomorrow").to_string(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdjustRequest {
+    pub adjust: Adjust,
+    pub device_id: String,
+    pub key: ThresholdKey,
+}
+
+fn encode(op: &str, device_id: &str, key: &ThresholdKey) -> String {
+    format!("{}:{}:{}:{}", PREFIX, op, key, device_id)
+}
+
+// Zeile "😴 1h", "😴 6h", "😴 bis morgen"; stummgeschaltet werden alle
+// Schwellen der Warnung, die Schwelle im Button ist nur Rückfall
+fn snooze_row(device_id: &str, key: &ThresholdKey, lang: Lang) -> Option<Vec<InlineKeyboardButton>> {
+    SNOOZE_OPTIONS
+        .iter()
+        .map(|(op, span)| {
+            let data = encode(op, device_id, key);
+            (data.len() <= MAX_CALLBACK_BYTES).then(|| InlineKeyboardButton::callback(snooze_label(*span, lang), data))
+        })
+        .collect()
+}
+
+// "−1", "+1", "Schwelle anpassen…", "✅ OK" und die Stummschaltung für eine
+// Warnung. None, wenn die Geräte-ID für die Callback-Daten zu lang ist.
+pub fn buttons(device_id: &str, key: &ThresholdKey, lang: Lang) -> Option<InlineKeyboardMarkup> {
+    let data = [encode("-1", device_id, key), encode("+1", device_id, key), encode("ask", device_id, key), encode("ack", device_id, key)];
+    if data.iter().any(|d| d.len() > MAX_CALLBACK_BYTES) {
+        return None;
+    }
+    let [minus, plus, ask, ack] = data;
+    Some(InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback("−1", minus), InlineKeyboardButton::callback("+1", plus)],
+        vec![InlineKeyboardButton::callback(i18n::message(lang, "adjust_button"), ask), InlineKeyboardButton::callback("✅ OK", ack)],
+        snooze_row(device_id, key, lang)?,
+    ]))
+}
+
+// Für zusammengefasste Warnungen: eine Zeile je Schwelle (Schlüssel,
+// Beschriftung), darunter "✅ OK" und die Stummschaltung für die ganze Warnung
+pub fn group_buttons(device_id: &str, keys: &[(ThresholdKey, &str)], lang: Lang) -> Option<InlineKeyboardMarkup> {
+    let mut rows = Vec::new();
+    for (key, label) in keys {
+        let data = [encode("-1", device_id, key), encode("+1", device_id, key), encode("ask", device_id, key)];
+        if data.iter().any(|d| d.len() > MAX_CALLBACK_BYTES) {
+            return None;
+        }
+        let [minus, plus, ask] = data;
+        rows.push(vec![
+            InlineKeyboardButton::callback(format!("{} −1", label), minus),
+            InlineKeyboardButton::callback(format!("{} +1", label), plus),
+            InlineKeyboardButton::callback(format!("{} …", label), ask),
+        ]);
+    }
+    if let Some((key, _)) = keys.first() {
+        let ack = encode("ack", device_id, key);
+        if ack.len() > MAX_CALLBACK_BYTES {
+            return None;
+        }
+        rows.push(vec![InlineKeyboardButton::callback("✅ OK", ack)]);
+        rows.push(snooze_row(device_id, key, lang)?);
+    }
+    Some(InlineKeyboardMarkup::new(rows))
+}
+
+// Nur "✅ OK" und die Stummschaltung für eine Sammelwarnung über mehrere
+// Geräte; beides gilt für alle Schwellen der Nachricht, die Schwelle im
+// Button ist nur Rückfall
+pub fn acknowledge_button(device_id: &str, key: &ThresholdKey, lang: Lang) -> Option<InlineKeyboardMarkup> {
+    let ack = encode("ack", device_id, key);
+    if ack.len() > MAX_CALLBACK_BYTES {
+        return None;
+    }
+    Some(InlineKeyboardMarkup::new(vec![vec![InlineKeyboardButton::callback("✅ OK", ack)], snooze_row(device_id, key, lang)?]))
+}
+
+// Vorschlag nach langer Verletzung: auf `suggested` (°C) setzen oder
+// löschen; beschriftet mit `shown` in der Einheit des Chats
+pub fn review_buttons(device_id: &str, key: &ThresholdKey, suggested: f64, shown: f64, lang: Lang) -> Option<InlineKeyboardMarkup> {
+    let data = [encode(&format!("={:.1}", suggested), device_id, key), encode("off", device_id, key)];
+    if data.iter().any(|d| d.len() > MAX_CALLBACK_BYTES) {
+        return None;
+    }
+    let [set, off] = data;
+    Some(InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback(i18n::message_with(lang, "review_set", &[("value", &format!("{:.1}", shown))]), set)],
+        vec![InlineKeyboardButton::callback(i18n::message(lang, "review_delete"), off)],
+    ]))
+}
+
+// Ohne den "✅ OK"-Button, nachdem die Warnung bestätigt wurde
+pub fn without_acknowledge(markup: &InlineKeyboardMarkup) -> InlineKeyboardMarkup {
+    let ack = format!("{}:ack:", PREFIX);
+    let rows = markup
+        .inline_keyboard
+        .iter()
+        .map(|row| {
+            row.iter()
+                .filter(|button| !matches!(&button.kind, InlineKeyboardButtonKind::CallbackData(data) if data.starts_with(&ack)))
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .filter(|row| !row.is_empty())
+        .collect::<Vec<_>>();
+    InlineKeyboardMarkup::new(rows)
+}
+
+pub fn parse(data: &str) -> Option<AdjustRequest> {
+    let mut parts = data.splitn(4, ':');
+    if parts.next()? != PREFIX {
+        return None;
+    }
+    let adjust = match parts.next()? {
+        "-1" => Adjust::Shift(-1.0),
+        "+1" => Adjust::Shift(1.0),
+        "ask" => Adjust::Ask,
+        "off" => Adjust::Disable,
+        "ack" => Adjust::Acknowledge,
+        op if op.starts_with('z') => Adjust::Snooze(SNOOZE_OPTIONS.iter().find(|(code, _)| *code == op)?.1),
+        op => Adjust::Set(op.strip_prefix('=')?.parse().ok()?),
+    };
+    let key = parts.next()?.parse().ok()?;
+    let device_id = parts.next()?.to_string();
+    Some(AdjustRequest { adjust, device_id, key })
+}
+
+// MIN muss unter MAX bleiben und umgekehrt
+pub fn check_opposite(direction: ThresholdDirection, value: f64, opposite: Option<f64>, unit: &str, lang: Lang) -> Result<(), String> {
+    let error =
+        |key: &str, other: f64| i18n::message_with(lang, key, &[("value", &format!("{:.1}", value)), ("other", &format!("{:.1}", other)), ("unit", unit)]);
+    match opposite {
+        Some(max) if direction == ThresholdDirection::Min && value >= max => Err(error("threshold_below_max", max)),
+        Some(min) if direction == ThresholdDirection::Max && value <= min => Err(error("threshold_above_min", min)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::SensorKind;
+
+    fn callbacks(markup: &InlineKeyboardMarkup) -> Vec<&str> {
+        markup
+            .inline_keyboard
+            .iter()
+            .flatten()
+            .filter_map(|button| match &button.kind {
+                InlineKeyboardButtonKind::CallbackData(data) => Some(data.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn review_suggestion_comes_back_as_the_stored_value() {
+        let key = ThresholdKey::new(SensorKind::Temperature, ThresholdDirection::Max);
+        // In Fahrenheit angezeigt, in °C gespeichert
+        let markup = review_buttons("sensor1", &key, 25.5, 77.9, Lang::En).unwrap();
+        let requests: Vec<AdjustRequest> = callbacks(&markup).into_iter().map(|data| parse(data).unwrap()).collect();
+        assert_eq!(requests.iter().map(|r| r.adjust).collect::<Vec<_>>(), [Adjust::Set(25.5), Adjust::Disable]);
+        assert!(requests.iter().all(|r| r.device_id == "sensor1" && r.key == key));
+        assert_eq!(markup.inline_keyboard[0][0].text, i18n::message_with(Lang::En, "review_set", &[("value", "77.9")]));
+    }
+
+    #[test]
+    fn too_long_device_id_gets_no_buttons() {
+        let key = ThresholdKey::new(SensorKind::Temperature, ThresholdDirection::Min);
+        assert!(review_buttons(&"x".repeat(MAX_CALLBACK_BYTES), &key, 18.0, 18.0, Lang::De).is_none());
+    }
+}
@@ -0,0 +1,92 @@
+use crate::i18n::{self, Lang};
+use crate::monitor::{EventKind, ThresholdEvent};
+use crate::sensor::{SensorKind, ThresholdDirection};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+// Je Chat höchstens so viele Einträge und keine älteren als MAX_AGE_DAYS
+const MAX_ENTRIES: usize = 200;
+const MAX_AGE_DAYS: i64 = 30;
+
+// Warum ein Ereignis nicht sofort zugestellt wurde
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+#[serde(rename_all = "lowercase")]
+pub enum Suppressed {
+    Muted,   // /mute-all
+    Quiet,   // Ruhezeit, kommt gesammelt danach
+    Snoozed, // /snooze oder 😴
+}
+
+impl Suppressed {
+    pub fn label(self, lang: Lang) -> &'static str {
+        match self {
+            Suppressed::Muted => i18n::message(lang, "suppressed_muted"),
+            Suppressed::Quiet => i18n::message(lang, "suppressed_quiet"),
+            Suppressed::Snoozed => i18n::message(lang, "suppressed_snoozed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+pub struct Entry {
+    pub timestamp: i64, // des auslösenden Messwerts
+    pub device_id: String,
+    pub sensor_type: SensorKind,
+    pub direction: ThresholdDirection,
+    pub recovered: bool, // sonst Alarm
+    pub value: f64,
+    pub threshold: Option<f64>,
+    #[serde(default)]
+    pub suppressed: Option<Suppressed>,
+}
+
+impl Entry {
+    pub fn of(event: &ThresholdEvent, suppressed: Option<Suppressed>) -> Entry {
+        Entry {
+            timestamp: event.timestamp,
+            device_id: event.device_id.clone(),
+            sensor_type: event.sensor_type.clone(),
+            direction: event.direction,
+            recovered: event.kind != EventKind::Alarm,
+            value: event.value,
+            threshold: event.threshold,
+            suppressed,
+        }
+    }
+}
+
+// Alarme und Entwarnungen eines Chats für /alarms, älteste zuerst; auch
+// solche, die Stummschaltung oder Ruhezeit zurückgehalten haben
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(Debug, proptest_derive::Arbitrary))]
+#[serde(transparent)]
+pub struct AlarmLog {
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::few::<Entry, _>()"))]
+    entries: VecDeque<Entry>,
+}
+
+impl AlarmLog {
+    pub fn record(&mut self, entry: Entry, now: i64) {
+        self.entries.push_back(entry);
+        let oldest = now - MAX_AGE_DAYS * 24 * 60 * 60;
+        while self.entries.len() > MAX_ENTRIES || self.entries.front().is_some_and(|e| e.timestamp < oldest) {
+            self.entries.pop_front();
+        }
+    }
+
+    // Die letzten `n`, neueste zuerst
+    pub fn latest(&self, n: usize) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().rev().take(n)
+    }
+
+    // Alle ab `from`, älteste zuerst
+    pub fn since(&self, from: i64) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().filter(move |e| e.timestamp >= from)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,234 @@
+use crate::i18n::Lang;
+use crate::routing::Severity;
+use crate::sensor::ThresholdDirection;
+
+// Obergrenze für Alarmtexte, damit sie auf dem Sperrbildschirm lesbar bleiben
+const MAX_ALERT_CHARS: usize = 400;
+
+/// Alle Angaben für eine Schwellwert-Warnung.
+///
+/// Stabilität: Teil der öffentlichen Schnittstelle; neue Felder kommen nur
+/// mit neuer Hauptversion hinzu.
+pub struct Alert<'a> {
+    pub room: &'a str,
+    pub type_label: &'a str,
+    pub unit: &'a str,
+    pub direction: ThresholdDirection,
+    pub value: f64,
+    pub threshold: f64,
+    pub trend_since: Option<(String, f64)>, // (Uhrzeit, Wert) Beginn des Trends
+    pub source: Option<&'a str>,            // Befehl, mit dem die Schwelle gesetzt wurde
+    pub tip: Option<&'a str>,
+}
+
+/// Baut den Alarmtext (Klartext, ohne Parse-Mode). Zusatzzeilen werden
+/// weggelassen, sobald der Text zu lang würde.
+pub fn format_alert(alert: &Alert) -> String {
+    format_alert_in(alert, Lang::De)
+}
+
+/// Wie [`format_alert`], in der Sprache des Chats
+pub fn format_alert_in(alert: &Alert, lang: Lang) -> String {
+    let min = alert.direction.is_min();
+    let richtung = if min { "MIN" } else { "MAX" };
+    let mut text = match lang {
+        Lang::De => format!(
+            "⚠ {} im {} ist {} deine {}-Schwelle {}: {:.1} {} (Schwelle: {:.1} {})",
+            alert.type_label,
+            alert.room,
+            if min { "unter" } else { "über" },
+            richtung,
+            if min { "gefallen" } else { "gestiegen" },
+            alert.value,
+            alert.unit,
+            alert.threshold,
+            alert.unit
+        ),
+        Lang::En => format!(
+            "⚠ {} in {} {} your {} threshold: {:.1} {} (threshold: {:.1} {})",
+            alert.type_label,
+            alert.room,
+            if min { "dropped below" } else { "rose above" },
+            richtung,
+            alert.value,
+            alert.unit,
+            alert.threshold,
+            alert.unit
+        ),
+    };
+
+    let mut extras = Vec::new();
+    if let Some((since, from)) = &alert.trend_since {
+        extras.push(match lang {
+            Lang::De => format!("{} seit {} (von {:.1} {})", if min { "📉 fällt" } else { "📈 steigt" }, since, from, alert.unit),
+            Lang::En => format!("{} since {} (from {:.1} {})", if min { "📉 falling" } else { "📈 rising" }, since, from, alert.unit),
+        });
+    }
+    if let Some(tip) = alert.tip {
+        extras.push(match lang {
+            Lang::De => format!("💡 Tipp: {}", tip),
+            Lang::En => format!("💡 Tip: {}", tip),
+        });
+    }
+    if let Some(source) = alert.source {
+        extras.push(match lang {
+            Lang::De => format!("🛠 Gesetzt mit: {}", source),
+            Lang::En => format!("🛠 Set with: {}", source),
+        });
+    }
+
+    for line in extras {
+        if text.chars().count() + 1 + line.chars().count() > MAX_ALERT_CHARS {
+            continue;
+        }
+        text.push('\n');
+        text.push_str(&line);
+    }
+
+    if text.chars().count() > MAX_ALERT_CHARS {
+        text = text.chars().take(MAX_ALERT_CHARS - 1).collect::<String>() + "…";
+    }
+    text
+}
+
+// Stufe und eigener Text einer Schwelle: ℹ️ statt ⚠ bei Infos, 🚨 vor
+// kritischen Warnungen, der eigene Text als erste Zeile
+pub fn styled_alert(text: String, severity: Severity, custom: Option<&str>, lang: Lang) -> String {
+    let text = match severity {
+        Severity::Info => text.replacen('⚠', "ℹ️", 1),
+        _ => text,
+    };
+    let text = match custom {
+        Some(custom) => format!("{}\n{}", custom, text),
+        None => text,
+    };
+    match (severity, lang) {
+        (Severity::Critical, Lang::De) => format!("🚨 Kritisch: {}", text),
+        (Severity::Critical, Lang::En) => format!("🚨 Critical: {}", text),
+        _ => text,
+    }
+}
+
+// Eine Zeile für die Sammelwarnung, wenn mehrere Schwellen gleichzeitig verletzt sind
+pub fn format_alert_line(alert: &Alert, lang: Lang) -> String {
+    let min = alert.direction.is_min();
+    let richtung = if min { "MIN" } else { "MAX" };
+    match lang {
+        Lang::De => format!(
+            "• {} im {}: {:.1} {} {} {} {:.1} {}",
+            alert.type_label,
+            alert.room,
+            alert.value,
+            alert.unit,
+            if min { "unter" } else { "über" },
+            richtung,
+            alert.threshold,
+            alert.unit
+        ),
+        Lang::En => format!(
+            "• {} in {}: {:.1} {} {} {} {:.1} {}",
+            alert.type_label,
+            alert.room,
+            alert.value,
+            alert.unit,
+            if min { "below" } else { "above" },
+            richtung,
+            alert.threshold,
+            alert.unit
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arbitrary::extreme;
+    use proptest::prelude::*;
+
+    fn alert(direction: ThresholdDirection) -> Alert<'static> {
+        let (value, threshold) = if direction.is_min() { (16.4, 17.0) } else { (26.25, 25.0) };
+        Alert { room: "Wohnzimmer", type_label: "Temperatur", unit: "°C", direction, value, threshold, trend_since: None, source: None, tip: None }
+    }
+
+    #[test]
+    fn alert_text_per_direction_and_language() {
+        assert_eq!(
+            format_alert_in(&alert(ThresholdDirection::Max), Lang::De),
+            "⚠ Temperatur im Wohnzimmer ist über deine MAX-Schwelle gestiegen: 26.2 °C (Schwelle: 25.0 °C)"
+        );
+        assert_eq!(
+            format_alert_in(&alert(ThresholdDirection::Min), Lang::De),
+            "⚠ Temperatur im Wohnzimmer ist unter deine MIN-Schwelle gefallen: 16.4 °C (Schwelle: 17.0 °C)"
+        );
+        assert_eq!(
+            format_alert_in(&alert(ThresholdDirection::Max), Lang::En),
+            "⚠ Temperatur in Wohnzimmer rose above your MAX threshold: 26.2 °C (threshold: 25.0 °C)"
+        );
+        assert_eq!(
+            format_alert_in(&alert(ThresholdDirection::Min), Lang::En),
+            "⚠ Temperatur in Wohnzimmer dropped below your MIN threshold: 16.4 °C (threshold: 17.0 °C)"
+        );
+    }
+
+    #[test]
+    fn alert_text_with_trend_tip_and_source() {
+        let alert = Alert {
+            trend_since: Some(("14:05".into(), 15.0)),
+            tip: Some("Heizung prüfen"),
+            source: Some("/setmin wohnzimmer temperature 17"),
+            ..alert(ThresholdDirection::Min)
+        };
+        assert_eq!(
+            format_alert_in(&alert, Lang::De),
+            "⚠ Temperatur im Wohnzimmer ist unter deine MIN-Schwelle gefallen: 16.4 °C (Schwelle: 17.0 °C)\n\
+             📉 fällt seit 14:05 (von 15.0 °C)\n\
+             💡 Tipp: Heizung prüfen\n\
+             🛠 Gesetzt mit: /setmin wohnzimmer temperature 17"
+        );
+        assert_eq!(format_alert_line(&alert, Lang::De), "• Temperatur im Wohnzimmer: 16.4 °C unter MIN 17.0 °C");
+        assert_eq!(format_alert_line(&alert, Lang::En), "• Temperatur in Wohnzimmer: 16.4 °C below MIN 17.0 °C");
+    }
+
+    #[test]
+    fn alert_text_per_severity() {
+        let text = || format_alert_in(&alert(ThresholdDirection::Max), Lang::De);
+        assert_eq!(styled_alert(text(), Severity::Warning, None, Lang::De), text());
+        assert!(styled_alert(text(), Severity::Info, None, Lang::De).starts_with("ℹ️ Temperatur im Wohnzimmer ist über"));
+        assert_eq!(styled_alert(text(), Severity::Critical, Some("Fenster zu!"), Lang::De), format!("🚨 Kritisch: Fenster zu!\n{}", text()));
+        assert!(styled_alert(text(), Severity::Critical, None, Lang::En).starts_with("🚨 Critical: ⚠ "));
+    }
+
+    proptest! {
+        // Ohne Parse-Mode genügt die Länge; die Zusatzzeilen fallen vorher weg
+        #[test]
+        fn alerts_stay_short(
+            value in extreme(),
+            threshold in extreme(),
+            trend_since in proptest::option::of((any::<String>(), extreme())),
+            room in any::<String>(),
+            source in proptest::option::of(any::<String>()),
+            tip in proptest::option::of(any::<String>()),
+            direction in any::<ThresholdDirection>(),
+            severity in any::<Severity>(),
+            lang in any::<Lang>(),
+        ) {
+            let alert = Alert {
+                room: &room,
+                type_label: "Temperatur",
+                unit: "°C",
+                direction,
+                value,
+                threshold,
+                trend_since,
+                source: source.as_deref(),
+                tip: tip.as_deref(),
+            };
+            let text = format_alert_in(&alert, lang);
+            prop_assert!(text.chars().count() <= MAX_ALERT_CHARS, "{:?}", text);
+            let styled = styled_alert(text, severity, Some("Fenster zu!"), lang);
+            prop_assert!(styled.chars().count() <= MAX_ALERT_CHARS + 40, "{:?}", styled);
+            let line = format_alert_line(&alert, lang);
+            prop_assert!(line.contains(alert.room));
+        }
+    }
+}
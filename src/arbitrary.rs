@@ -0,0 +1,77 @@
+// Zufallswerte für die Eigenschaftstests (proptest). Die gespeicherten Typen
+// leiten `Arbitrary` in Testbuilds ab, ein neues Feld in `UserConfig` braucht
+// also selbst eins; hier stehen nur Strategien für fremde Typen und für
+// Werte, die es nur in gespeicherter Form gibt (Uhrzeiten auf die Minute).
+
+use chrono::{DateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use proptest::collection::{btree_set, hash_map, vec};
+use proptest::prelude::*;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+// Zeitpunkt zwischen 1970 und 2100, auf die Nanosekunde
+pub fn datetime() -> impl Strategy<Value = DateTime<Utc>> {
+    (0i64..4_102_444_800, 0u32..1_000_000_000).prop_map(|(secs, nanos)| DateTime::from_timestamp(secs, nanos).unwrap())
+}
+
+pub fn optional_datetime() -> impl Strategy<Value = Option<DateTime<Utc>>> {
+    proptest::option::of(datetime())
+}
+
+// Uhrzeit auf die Minute, wie /schedule und Zeitfenster sie speichern
+pub fn minute() -> impl Strategy<Value = NaiveTime> {
+    (0u32..24, 0u32..60).prop_map(|(hour, minute)| NaiveTime::from_hms_opt(hour, minute, 0).unwrap())
+}
+
+pub fn timezone() -> impl Strategy<Value = Option<Tz>> {
+    proptest::option::of(proptest::sample::select(chrono_tz::TZ_VARIANTS.to_vec()))
+}
+
+// Uhrzeiten je Wochentag, sortiert und ohne doppelte
+pub fn week() -> impl Strategy<Value = [Vec<NaiveTime>; 7]> {
+    proptest::array::uniform7(btree_set(minute(), 0..3).prop_map(|times| times.into_iter().collect()))
+}
+
+// Meist beliebige Geräte-IDs, sonst der eingebaute Raum unter Name oder ID,
+// damit migrate_configs etwas umzustellen hat
+pub fn device() -> impl Strategy<Value = String> {
+    prop_oneof![
+        3 => any::<String>(),
+        1 => proptest::sample::select(vec!["Wohnzimmer", "wohnzimmer", "sensor1"]).prop_map(str::to_string),
+    ]
+}
+
+// Einträge je (Gerät, Schlüssel) wie in storage::keyed_map
+pub fn keyed<K, V>(value: V) -> impl Strategy<Value = HashMap<(String, K), V::Value>>
+where
+    K: Arbitrary + Hash + Eq + Debug,
+    V: Strategy,
+    V::Value: Debug,
+{
+    hash_map((device(), any::<K>()), value, 0..4)
+}
+
+pub fn stamped() -> impl Strategy<Value = Vec<(DateTime<Utc>, String)>> {
+    vec((datetime(), any::<String>()), 0..4)
+}
+
+// Wenige Einträge; mit den üblichen bis zu 100 je Ebene wären die
+// verschachtelten Listen (Rückgängig-Liste mit Schwellen mit Einträgen) zu groß
+pub fn few<T, C>() -> impl Strategy<Value = C>
+where
+    T: Arbitrary,
+    C: FromIterator<T> + Debug,
+{
+    vec(any::<T>(), 0..4).prop_map(|items| items.into_iter().collect())
+}
+
+// Beliebige Zahlen samt den Grenzfällen beim Formatieren: größte und
+// kleinste, negative Null, subnormale, unendliche und NaN
+pub fn extreme() -> impl Strategy<Value = f64> {
+    prop_oneof![
+        any::<f64>(),
+        proptest::sample::select(vec![f64::MAX, f64::MIN, -0.0, f64::MIN_POSITIVE / 2.0, -f64::from_bits(1), f64::INFINITY, f64::NEG_INFINITY, f64::NAN]),
+    ]
+}
@@ -0,0 +1,132 @@
+use crate::UserConfig;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// So lange lässt sich /clear all mit /undo-clear zurücknehmen
+pub const UNDO_WINDOW_DAYS: i64 = 7;
+
+/// Gelöschte Konfiguration eines Chats mit Zeitpunkt des Löschens
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Archived {
+    pub deleted_at: DateTime<Utc>,
+    pub config: UserConfig,
+}
+
+impl Archived {
+    pub fn restorable_until(&self) -> DateTime<Utc> {
+        self.deleted_at + Duration::days(UNDO_WINDOW_DAYS)
+    }
+}
+
+// Mit /clear all gelöschte Konfigurationen je Chat, bis sie wiederhergestellt
+// oder nach Ablauf der Frist endgültig entfernt werden. Die Überwachung sieht
+// nur die aktiven Konfigurationen, archivierte bleiben außen vor.
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Archive {
+    entries: BTreeMap<i64, Archived>,
+}
+
+pub enum Restore {
+    Restored(Box<UserConfig>),
+    Expired,
+    Missing,
+}
+
+impl Archive {
+    // Ein erneutes Löschen ersetzt das ältere Archiv des Chats
+    pub fn insert(&mut self, chat_id: i64, config: UserConfig, now: DateTime<Utc>) -> &Archived {
+        self.entries.insert(chat_id, Archived { deleted_at: now, config });
+        &self.entries[&chat_id]
+    }
+
+    pub fn take(&mut self, chat_id: i64, now: DateTime<Utc>) -> Restore {
+        match self.entries.remove(&chat_id) {
+            Some(archived) if archived.restorable_until() > now => Restore::Restored(Box::new(archived.config)),
+            Some(_) => Restore::Expired,
+            None => Restore::Missing,
+        }
+    }
+
+    pub fn contains(&self, chat_id: i64) -> bool {
+        self.entries.contains_key(&chat_id)
+    }
+
+    // Endgültig entfernen (/forgetme); true, wenn es ein Archiv gab
+    pub fn forget(&mut self, chat_id: i64) -> bool {
+        self.entries.remove(&chat_id).is_some()
+    }
+
+    // Archive nach Ablauf der Frist entfernen; liefert die Zahl entfernter
+    pub fn purge(&mut self, now: DateTime<Utc>) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|_, archived| archived.restorable_until() > now);
+        before - self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn config(name: &str) -> UserConfig {
+        UserConfig { first_name: Some(name.into()), ..UserConfig::default() }
+    }
+
+    fn deleted() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()
+    }
+
+    fn restored_name(restore: Restore) -> Option<String> {
+        match restore {
+            Restore::Restored(config) => config.first_name,
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn archived_config_comes_back_once_within_the_window() {
+        let mut archive = Archive::default();
+        let archived = archive.insert(1, config("Ada"), deleted());
+        assert_eq!(archived.restorable_until(), deleted() + Duration::days(UNDO_WINDOW_DAYS));
+        assert!(archive.contains(1) && !archive.contains(2));
+
+        let last_moment = deleted() + Duration::days(UNDO_WINDOW_DAYS) - Duration::seconds(1);
+        assert_eq!(restored_name(archive.take(1, last_moment)).as_deref(), Some("Ada"));
+        assert!(!archive.contains(1));
+        assert!(matches!(archive.take(1, last_moment), Restore::Missing));
+    }
+
+    #[test]
+    fn deleting_again_replaces_the_older_archive() {
+        let mut archive = Archive::default();
+        archive.insert(1, config("Ada"), deleted());
+        archive.insert(1, config("Grace"), deleted() + Duration::days(6));
+        // Die Frist läuft ab dem zweiten Löschen
+        assert_eq!(restored_name(archive.take(1, deleted() + Duration::days(10))).as_deref(), Some("Grace"));
+    }
+
+    #[test]
+    fn expired_archive_is_neither_restored_nor_kept() {
+        let mut archive = Archive::default();
+        archive.insert(1, config("Ada"), deleted());
+        assert!(matches!(archive.take(1, deleted() + Duration::days(UNDO_WINDOW_DAYS)), Restore::Expired));
+        assert!(!archive.contains(1));
+    }
+
+    #[test]
+    fn purge_removes_only_expired_archives() {
+        let mut archive = Archive::default();
+        archive.insert(1, config("Ada"), deleted());
+        archive.insert(2, config("Grace"), deleted() + Duration::days(3));
+        assert_eq!(archive.purge(deleted() + Duration::days(5)), 0);
+        assert_eq!(archive.purge(deleted() + Duration::days(UNDO_WINDOW_DAYS)), 1);
+        assert!(!archive.contains(1) && archive.contains(2));
+
+        assert!(archive.forget(2));
+        assert!(!archive.forget(2));
+        assert_eq!(archive.purge(deleted() + Duration::days(30)), 0);
+    }
+}
@@ -0,0 +1,620 @@
+// Hintergrundaufgaben des laufenden Bots, von `SensorBotBuilder::run`
+// gestartet. Jede läuft bis zum Beenden.
+
+use crate::i18n::Lang;
+use crate::messenger::{self, Messenger, OutgoingMessage, SendError};
+use crate::notify::{MessageKind, NotificationMode};
+use crate::outbox::{Delivery, Outbox};
+use crate::records::NewRecord;
+use crate::routing::Severity;
+use crate::schedule::{WeeklySchedule, merges_with};
+use crate::selftest::SelfTest;
+#[cfg(feature = "http-api")]
+use crate::state::SharedCharts;
+use crate::state::{AlertRecord, Held, QuietQueue, Shared};
+use crate::storage::{self, Store};
+use crate::thresholds::TimeWindow;
+use crate::timeformat::TimeFormat;
+use crate::units::TempUnit;
+use crate::{
+    COMPACT_AT, FLEET_REPORT_AT, FLEET_REPORT_NOW, MAX_ALERT_RECORDS, OUTDOOR_CHECK_AT, ROUTE_CHECKS, SCHEDULER_TICK_IN_SECONDS, Trends, admin_lang,
+    bot_status, chat_timezone, daily_summary, escape_markdown, fetch_sensor_data, fill_name, fleet_report, format_alert_digest, format_digest,
+    format_new_records, format_notes, format_status, hand_over_unacknowledged, i18n, in_local_window, last_fire, last_weekly_fire, local_time, next_fire,
+    note_reached, note_unreachable, outdoor_evening, purge_archive, purge_blocked, redact, refresh, room_name, rooms, selftest, settings, snooze, status_list,
+    status_trends, tenant_of, timeutil, unmute_summary, visible_readings, weekly_report,
+};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use log::{error, info, warn};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+use teloxide::prelude::*;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+// Selbsttest nach dem Start; das Ergebnis geht an den Admin
+pub async fn report_self_test(test: SelfTest, outbox: Outbox, lang: Lang) {
+    let admin_chat = settings().admin_chat.map(ChatId);
+    let outcomes = test.run().await;
+    for outcome in &outcomes {
+        if let Err(err) = &outcome.result {
+            warn!("Selbsttest {} fehlgeschlagen: {}", outcome.name, err);
+        }
+    }
+    match admin_chat {
+        Some(admin) => outbox.send(admin, selftest::summary(&outcomes, lang)),
+        None => info!("{}", selftest::summary(&outcomes, Lang::default())),
+    }
+}
+
+// Zusätzliche Ziele aus [routing] mit einer stillen Testnachricht prüfen,
+// damit ein vertippter Kanal nicht erst beim ersten Alarm auffällt
+pub async fn check_routing(targets: BTreeMap<i64, Severity>, messenger: Arc<dyn Messenger>, storage: Arc<dyn Store>, outbox: Outbox, lang: Lang) {
+    let admin_chat = settings().admin_chat.map(ChatId);
+    for (target, severity) in targets {
+        let message = OutgoingMessage {
+            chat_id: target,
+            // Kanäle aus [routing] haben keine eigenen Einstellungen
+            text: i18n::message_with(Lang::default(), "delivery_test", &[("severity", severity.label(Lang::default()))]),
+            markdown: false,
+            html: false,
+            buttons: None,
+            silent: true,
+            reply_to: None,
+            room_image: None,
+        };
+        let result = messenger.send(&message).await;
+        match &result {
+            Ok(()) => note_reached(storage.as_ref(), target),
+            Err(SendError::Unreachable(reason)) => note_unreachable(storage.as_ref(), target, reason),
+            Err(_) => {}
+        }
+        match &result {
+            Ok(()) => info!("Ziel {} für Stufe \"{}\" erreichbar", redact::chat(target), severity),
+            Err(err) => {
+                warn!("Ziel {} für Stufe \"{}\" nicht erreichbar: {}", redact::chat(target), severity, messenger::send_error_in(err, Lang::default()));
+                if let Some(admin) = admin_chat {
+                    let reason = messenger::send_error_in(err, lang);
+                    outbox.send(admin, i18n::message_with(lang, "routing_unreachable", &[("chat", &redact::chat(target)), ("reason", &reason)]));
+                }
+            }
+        }
+        ROUTE_CHECKS.lock().unwrap_or_else(|e| e.into_inner()).insert(target, result);
+    }
+}
+
+// Geplante Statusberichte und verpasste Warnungen nach der Ruhezeit
+pub async fn scheduled_reports(shared: Shared, outbox: Outbox, quiet_queue: QuietQueue) {
+    let merge_window = chrono::Duration::minutes(settings().digest_merge_minutes);
+    // Pro Benutzer: Zeitplan und nächster Termin. Ändert sich der Zeitplan,
+    // wird der Termin neu berechnet.
+    let mut next: HashMap<i64, (WeeklySchedule, Option<DateTime<Utc>>)> = HashMap::new();
+
+    loop {
+        let now = Utc::now();
+        let mut due = Vec::new();
+        let mut quiet: HashMap<i64, Option<TimeWindow>> = HashMap::new();
+        let mut muted: Vec<i64> = Vec::new();
+        let mut messages: Vec<(i64, String)> = Vec::new();
+        let mut notes: HashMap<i64, BTreeMap<String, String>> = HashMap::new();
+        let mut names: HashMap<i64, String> = HashMap::new();
+        let mut langs: HashMap<i64, (Lang, TempUnit, Option<Tz>, TimeFormat)> = HashMap::new();
+        let mut battery_lows: HashMap<i64, f64> = HashMap::new();
+        let mut notifications: HashMap<i64, NotificationMode> = HashMap::new();
+        {
+            let mut configs = shared.configs.lock().await;
+            next.retain(|user_id, _| configs.get(user_id).is_some_and(|c| c.report_schedule.is_some()));
+
+            // Abgelaufene Stummschaltungen beenden
+            let mut unmuted = false;
+            for (&user_id, config) in configs.iter_mut() {
+                if config.muted_until.is_some_and(|until| until <= now) {
+                    messages.push((user_id, unmute_summary(config)));
+                    unmuted = true;
+                }
+                // Per Befehl stummgeschaltete Räume melden sich zurück
+                let snoozes = config.snoozed.len();
+                for device in &snooze::expire(&mut config.snoozed, now) {
+                    messages.push((user_id, escape_markdown(&i18n::message_with(config.lang, "snooze_ended", &[("room", &room_name(device))]))));
+                }
+                unmuted |= config.snoozed.len() != snoozes;
+            }
+            if unmuted {
+                shared.storage.save_users(&configs);
+            }
+
+            for (&user_id, config) in configs.iter() {
+                quiet.insert(user_id, config.quiet_hours);
+                if config.is_muted(now) {
+                    muted.push(user_id);
+                }
+                langs.insert(user_id, (config.lang, config.units, chat_timezone(Some(config)), config.time_format));
+                notifications.insert(user_id, config.notifications);
+                battery_lows.insert(user_id, config.battery_low());
+                let Some(schedule) = &config.report_schedule else { continue };
+                notes.insert(user_id, config.notes.clone());
+                if let Some(name) = &config.first_name {
+                    names.insert(user_id, name.clone());
+                }
+                let entry = next.entry(user_id).or_insert_with(|| (schedule.clone(), next_fire(schedule, now)));
+                if entry.0 != *schedule {
+                    *entry = (schedule.clone(), next_fire(schedule, now));
+                }
+                if entry.1.is_some_and(|at| at <= now) {
+                    // stumm geschaltet: Bericht entfällt
+                    if !config.is_muted(now) {
+                        due.push(user_id);
+                    }
+                    // strikt nach jetzt, damit ein Termin nicht zweimal feuert
+                    entry.1 = next_fire(schedule, now);
+                }
+            }
+        }
+        bot_status().next_reports = next.iter().filter_map(|(&user_id, (_, at))| Some((user_id, (*at)?))).collect();
+
+        let sensor_data = if due.is_empty() { None } else { fetch_sensor_data().await.ok() };
+        // Rekorde des letzten Tages kommen in jeden Bericht
+        let new_records = if due.is_empty() { Vec::new() } else { shared.records.lock().await.broken_since((now - chrono::Duration::days(1)).timestamp()) };
+        let trends = match &sensor_data {
+            Some(sensor_data) => status_trends(&*shared.history.lock().await, sensor_data),
+            None => Trends::new(),
+        };
+
+        {
+            let mut queue = quiet_queue.lock().await;
+
+            for user_id in due {
+                let (lang, units, tz, times) = langs.get(&user_id).copied().unwrap_or_default();
+                let missed = missed_for_report(&mut queue, user_id, quiet.get(&user_id).copied().flatten(), now, merge_window);
+                let status = match &sensor_data {
+                    Some(sensor_data) => {
+                        let battery_low = battery_lows.get(&user_id).copied().unwrap_or(settings().battery_low);
+                        let sensor_data = status_list(visible_readings(user_id, sensor_data), battery_low);
+                        let tenant = tenant_of(user_id);
+                        let new_records: Vec<NewRecord> =
+                            new_records.iter().filter(|record| rooms().visible(tenant.as_deref(), &record.device_id)).cloned().collect();
+                        format!(
+                            "{}{}{}",
+                            format_status(&sensor_data, &trends, lang, units, tz, times),
+                            format_notes(notes.get(&user_id).unwrap_or(&BTreeMap::new()), &sensor_data),
+                            format_new_records(&new_records, lang)
+                        )
+                    }
+                    None => i18n::message(lang, "fetch_failed").to_string(),
+                };
+                let greeting = fill_name(i18n::message(lang, "report_greeting"), names.get(&user_id).map(String::as_str));
+                messages.push((user_id, format!("{}\n\n{}", escape_markdown(&greeting), format_digest(&missed, Some(&status), tz))));
+            }
+
+            let report = |user_id| next.get(&user_id).and_then(|(_, at)| *at);
+            for (user_id, missed) in release_quiet(&mut queue, &quiet, &muted, report, now, merge_window) {
+                messages.push((user_id, format_digest(&missed, None, langs.get(&user_id).and_then(|p| p.2))));
+            }
+        }
+
+        for (user_id, text) in messages {
+            let silent = notifications.get(&user_id).copied().unwrap_or_default().silent(MessageKind::Digest);
+            outbox.send_digest(ChatId(user_id), text, silent);
+        }
+
+        tokio::time::sleep(tokio::time::Duration::from_secs(SCHEDULER_TICK_IN_SECONDS)).await;
+    }
+}
+
+// Verpasste Warnungen, die der fällige Bericht mitnimmt: alle, wenn die
+// Ruhezeit vorbei ist oder in Kürze endet, sonst bleiben sie bis danach liegen
+fn missed_for_report(queue: &mut HashMap<i64, Held>, user_id: i64, window: Option<TimeWindow>, now: DateTime<Utc>, merge_window: chrono::Duration) -> Held {
+    let quiet_end = window.filter(|w| in_local_window(w, now)).and_then(|w| next_fire(&WeeklySchedule::daily(w.end), now));
+    let take = quiet_end.is_none() || merges_with(now, quiet_end, merge_window);
+    if take { queue.remove(&user_id).unwrap_or_default() } else { Vec::new() }
+}
+
+// Ruhezeit vorbei: gesammelte Warnungen, die jetzt als eigene Meldung gehen.
+// Sie bleiben liegen, solange Ruhezeit oder Stummschaltung gilt oder der
+// Bericht ohnehin gleich kommt und sie mitnimmt.
+fn release_quiet(
+    queue: &mut HashMap<i64, Held>,
+    quiet: &HashMap<i64, Option<TimeWindow>>,
+    muted: &[i64],
+    report: impl Fn(i64) -> Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    merge_window: chrono::Duration,
+) -> Vec<(i64, Held)> {
+    let ready: Vec<i64> = queue
+        .keys()
+        .copied()
+        .filter(|user_id| {
+            let window = quiet.get(user_id).copied().flatten();
+            let still_quiet = window.is_some_and(|w| in_local_window(&w, now));
+            !still_quiet && !muted.contains(user_id) && !merges_with(now, report(*user_id), merge_window)
+        })
+        .collect();
+    let mut released: Vec<_> = ready.into_iter().filter_map(|user_id| queue.remove(&user_id).filter(|m| !m.is_empty()).map(|m| (user_id, m))).collect();
+    released.sort_by_key(|(user_id, _)| *user_id);
+    released
+}
+
+// Sammelmeldungen (/alert-mode digest). Ein leerer Puffer schickt nichts;
+// Ruhezeit und Stummschaltung halten die Meldung bis danach zurück.
+pub async fn alert_digests(shared: Shared, outbox: Outbox) {
+    loop {
+        let now = Utc::now();
+        {
+            let mut configs = shared.configs.lock().await;
+            let mut changed = false;
+            for (&chat_id, config) in configs.iter_mut() {
+                let Some(interval) = config.alert_mode.interval() else { continue };
+                if config.is_muted(now) || config.quiet_hours.is_some_and(|w| in_local_window(&w, now)) {
+                    continue;
+                }
+                let due = config.digest.due;
+                if let Some(entries) = config.digest.take_due(interval, now) {
+                    let silent = config.notifications.silent(MessageKind::Digest);
+                    outbox.send_digest(ChatId(chat_id), format_alert_digest(&entries, chat_timezone(Some(config))), silent);
+                }
+                changed |= config.digest.due != due;
+            }
+            if changed {
+                shared.storage.save_users(&configs);
+            }
+        }
+        tokio::time::sleep(tokio::time::Duration::from_secs(SCHEDULER_TICK_IN_SECONDS)).await;
+    }
+}
+
+// Sonntags Geräte-Wochenbericht an den Admin, wenn eingeschaltet
+pub async fn fleet_reports(shared: Shared, outbox: Outbox, admin: ChatId) {
+    let schedule: WeeklySchedule = FLEET_REPORT_AT.parse().expect("eingebauter Zeitplan ist gültig");
+    while let Some(at) = next_fire(&schedule, Utc::now()) {
+        let scheduled = tokio::select! {
+            _ = tokio::time::sleep((at - Utc::now()).to_std().unwrap_or_default()) => true,
+            _ = FLEET_REPORT_NOW.notified() => false,
+        };
+        let (enabled, lang) = shared.configs.lock().await.get(&admin.0).map(|c| (c.fleet_report, c.lang)).unwrap_or_default();
+        if scheduled && !enabled {
+            continue;
+        }
+        for text in fleet_report(&*shared.history.lock().await, lang) {
+            outbox.send_markdown(admin, text);
+        }
+    }
+}
+
+// Abends Frost- und Gießhinweise für Räume im Freien
+pub async fn outdoor_hints(shared: Shared, outbox: Outbox) {
+    let schedule = WeeklySchedule::daily(OUTDOOR_CHECK_AT);
+    while let Some(at) = next_fire(&schedule, Utc::now()) {
+        tokio::time::sleep((at - Utc::now()).to_std().unwrap_or_default()).await;
+        let configs = shared.configs.lock().await;
+        for (chat_id, text) in outdoor_evening(&configs, &*shared.history.lock().await, Utc::now()) {
+            outbox.send_markdown(ChatId(chat_id), text);
+        }
+    }
+}
+
+// Tageszusammenfassungen. Fällig ist die letzte Uhrzeit davor, wenn an
+// ihrem Tag noch keine ging; last_summary wird mit der Konfiguration
+// gespeichert, so schickt auch ein Neustart oder eine geänderte Uhrzeit
+// keine zweite am selben Tag.
+pub async fn daily_summaries(shared: Shared, outbox: Outbox) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(SCHEDULER_TICK_IN_SECONDS));
+    loop {
+        interval.tick().await;
+        let now = Utc::now();
+        let mut configs = shared.configs.lock().await;
+        let due: Vec<(i64, DateTime<Utc>)> = configs
+            .iter()
+            .filter_map(|(&chat_id, config)| {
+                let at = last_fire(config.daily_summary.as_ref()?, now)?;
+                let last = config.last_summary.unwrap_or(DateTime::<Utc>::MIN_UTC);
+                let same_day = timeutil::local_date_of(last, settings().timezone) == timeutil::local_date_of(at, settings().timezone);
+                (last < at && !same_day).then_some((chat_id, last))
+            })
+            .collect();
+        if due.is_empty() {
+            continue;
+        }
+        let history = shared.history.lock().await;
+        for (chat_id, last) in due {
+            let Some(config) = configs.get_mut(&chat_id) else { continue };
+            config.last_summary = Some(now);
+            // Stumm geschaltet: die Zusammenfassung entfällt
+            if config.is_muted(now) {
+                continue;
+            }
+            let since = last.max(now - chrono::Duration::days(1));
+            let silent = config.notifications.silent(MessageKind::Digest);
+            outbox.send_digest(ChatId(chat_id), daily_summary(config, chat_id, &history, since, now), silent);
+        }
+        shared.storage.save_users(&configs);
+    }
+}
+
+// Profile nach Zeitplan umschalten; danach gleich neu prüfen, damit
+// die neuen Schwellen sofort gelten. Die Alarm-Zustände bleiben, so
+// endet oder beginnt ein Alarm nur, wo die neuen Werte es verlangen.
+pub async fn scheduled_profiles(shared: Shared, outbox: Outbox) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(SCHEDULER_TICK_IN_SECONDS));
+    loop {
+        interval.tick().await;
+        let mut configs = shared.configs.lock().await;
+        let mut switched = false;
+        for (&chat_id, config) in configs.iter_mut() {
+            let Some(previous) = config.profiles.switch(local_time()) else { continue };
+            switched = true;
+            let text = match (&config.profiles.active, previous) {
+                (Some(active), _) => format!("🗂 Profil {} jetzt aktiv.", active),
+                (None, Some(previous)) => format!("🗂 Profil {} beendet, es gelten wieder deine Schwellen.", previous),
+                (None, None) => continue,
+            };
+            outbox.send_plain(ChatId(chat_id), text, config.notifications.silent(MessageKind::Digest));
+        }
+        if switched {
+            shared.storage.save_users(&configs);
+            tokio::spawn(refresh::request());
+        }
+    }
+}
+
+// Unbestätigte Warnungen an den Chat aus /escalate-to weitergeben
+pub async fn hand_over(shared: Shared, outbox: Outbox) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(SCHEDULER_TICK_IN_SECONDS));
+    loop {
+        interval.tick().await;
+        let mut configs = shared.configs.lock().await;
+        let flags = shared.flags.lock().await;
+        if hand_over_unacknowledged(&mut configs, &flags, &outbox, Utc::now()) {
+            shared.storage.save_users(&configs);
+        }
+    }
+}
+
+// Wochenberichte, wie die Tageszusammenfassungen: last_weekly verhindert
+// nach einem Neustart einen zweiten zum selben Termin. Das Diagramm
+// geht direkt über den Bot, die Warteschlange kennt keine Bilder.
+#[cfg_attr(not(feature = "charts"), allow(unused_variables))]
+pub async fn weekly_reports(shared: Shared, outbox: Outbox, bot: Option<Bot>) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(SCHEDULER_TICK_IN_SECONDS));
+    loop {
+        interval.tick().await;
+        let now = Utc::now();
+        let mut configs = shared.configs.lock().await;
+        let due: Vec<i64> = configs
+            .iter()
+            .filter(|(_, config)| {
+                let last = config.last_weekly.unwrap_or(DateTime::<Utc>::MIN_UTC);
+                config.weekly_report.as_ref().and_then(|schedule| last_weekly_fire(schedule, now)).is_some_and(|at| last < at)
+            })
+            .map(|(&chat_id, _)| chat_id)
+            .collect();
+        if due.is_empty() {
+            continue;
+        }
+        #[cfg(feature = "charts")]
+        let mut charts: Vec<(i64, Vec<u8>, bool, Lang)> = Vec::new();
+        {
+            let history = shared.history.lock().await;
+            for chat_id in due {
+                let Some(config) = configs.get_mut(&chat_id) else { continue };
+                config.last_weekly = Some(now);
+                // Stumm geschaltet: der Bericht entfällt
+                if config.is_muted(now) {
+                    continue;
+                }
+                let silent = config.notifications.silent(MessageKind::Digest);
+                outbox.send_digest(ChatId(chat_id), weekly_report(config, chat_id, &history, now), silent);
+                #[cfg(feature = "charts")]
+                if bot.is_some()
+                    && let Some(png) = crate::weekly_chart(config, chat_id, &history, now)
+                {
+                    charts.push((chat_id, png, silent, config.lang));
+                }
+            }
+        }
+        shared.storage.save_users(&configs);
+        drop(configs);
+        #[cfg(feature = "charts")]
+        if let Some(bot) = &bot {
+            for (chat_id, png, silent, lang) in charts {
+                let photo = teloxide::types::InputFile::memory(png);
+                let caption = format!("📈 {}", i18n::message(lang, "weekly_chart_title"));
+                if let Err(err) = bot.send_photo(ChatId(chat_id), photo).caption(caption).disable_notification(silent).await {
+                    warn!("Wochendiagramm an Chat {} nicht gesendet: {}", redact::chat(chat_id), err);
+                }
+            }
+        }
+    }
+}
+
+// Nächtlich ältere Messwerte verdichten (Rohwerte → 5 Minuten → Stunden)
+// und gelöschte Konfigurationen sowie Chats, die den Bot blockiert
+// haben, nach Ablauf der Frist entfernen
+pub async fn nightly_maintenance(shared: Shared) {
+    let schedule = WeeklySchedule::daily(COMPACT_AT);
+    while let Some(at) = next_fire(&schedule, Utc::now()) {
+        tokio::time::sleep((at - Utc::now()).to_std().unwrap_or_default()).await;
+        let mut history = shared.history.lock().await;
+        let changed = history.compact(Utc::now().timestamp());
+        if changed > 0 {
+            shared.storage.save_history(&history);
+            info!("Verlauf verdichtet: {} Einträge umgerechnet oder entfernt", changed);
+        }
+        drop(history);
+        purge_archive(shared.storage.as_ref());
+        purge_blocked(&shared.configs, &shared.flags, shared.storage.as_ref()).await;
+    }
+}
+
+// Nächtliche Sicherung der Zustandsdateien
+pub async fn nightly_backup(shared: Shared, outbox: Outbox, dir: PathBuf) {
+    let admin_chat = settings().admin_chat.map(ChatId);
+    let schedule = WeeklySchedule::daily(settings().backup_at);
+    while let Some(at) = next_fire(&schedule, Utc::now()) {
+        tokio::time::sleep((at - Utc::now()).to_std().unwrap_or_default()).await;
+        match storage::backup(&shared.storage, &dir, settings().backup_keep).await {
+            Ok(path) => info!("Sicherung angelegt: {}", path.display()),
+            Err(err) => {
+                error!("Nächtliche Sicherung fehlgeschlagen: {}", err);
+                if let Some(admin) = admin_chat {
+                    let lang = admin_lang(&*shared.configs.lock().await);
+                    outbox.send(admin, i18n::message_with(lang, "nightly_backup_failed", &[("error", &err.to_string())]));
+                }
+            }
+        }
+    }
+}
+
+// Zugestellte Warnungen merken, damit Reaktionen und Folgemeldungen sie finden
+pub async fn track_deliveries(shared: Shared, mut delivery_rx: UnboundedReceiver<Delivery>) {
+    while let Some(delivery) = delivery_rx.recv().await {
+        let mut configs = shared.configs.lock().await;
+        let sent = match delivery {
+            Delivery::Alert(sent) => sent,
+            Delivery::Reached { chat_id } => {
+                note_reached(shared.storage.as_ref(), chat_id);
+                continue;
+            }
+            Delivery::Unreachable { chat_id, reason } => {
+                note_unreachable(shared.storage.as_ref(), chat_id, &reason);
+                continue;
+            }
+            // Warnung gelöscht: spätere Folgemeldungen nicht mehr darauf beziehen
+            Delivery::ReplyTargetMissing { chat_id, message_id } => {
+                if let Some(config) = configs.get_mut(&chat_id) {
+                    for episode in config.episodes.values_mut().filter(|e| e.message_id == Some(message_id)) {
+                        episode.message_id = None;
+                    }
+                    shared.storage.save_users(&configs);
+                }
+                continue;
+            }
+        };
+        // Kopien in zusätzlichen Chats gehören allen Besitzern der Schwelle
+        for owner in &sent.alert.owners {
+            let Some(config) = configs.get_mut(owner) else { continue };
+            if sent.chat_id == *owner {
+                for key in &sent.alert.keys {
+                    // Folgemeldungen beziehen sich auf die erste Warnung
+                    if let Some(episode) = config.episodes.get_mut(&(sent.alert.device_id.clone(), key.clone()))
+                        && episode.message_id.is_none()
+                    {
+                        episode.message_id = Some(sent.message_id);
+                    }
+                }
+            }
+            config.alerts.push_back(AlertRecord {
+                chat_id: Some(sent.chat_id).filter(|chat| chat != owner),
+                message_id: sent.message_id,
+                device_id: sent.alert.device_id.clone(),
+                keys: sent.alert.keys.clone(),
+                observed_at: Some(sent.alert.observed_at),
+                delivered_at: Some(sent.delivered_at),
+            });
+            while config.alerts.len() > MAX_ALERT_RECORDS {
+                config.alerts.pop_front();
+            }
+        }
+        shared.storage.save_users(&configs);
+    }
+}
+
+// Abgelaufene Diagramme regelmäßig aus dem Speicher entfernen
+#[cfg(feature = "http-api")]
+pub async fn purge_charts(charts: SharedCharts) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+    loop {
+        interval.tick().await;
+        let purged = charts.lock().await.purge(tokio::time::Instant::now());
+        if purged > 0 {
+            info!("{} abgelaufene Diagramme entfernt", purged);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    // Ortszeit wie die Ruhezeit (ohne DEFAULT_TZ die des Systems)
+    fn local(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Local.with_ymd_and_hms(2024, 1, day, hour, minute, 0).single().unwrap().with_timezone(&Utc)
+    }
+
+    fn queued() -> HashMap<i64, Held> {
+        HashMap::from([(1, vec![(local(15, 23, 10), "🔺 Wohnzimmer".to_string()), (local(16, 2, 40), "🔻 Küche".to_string())])])
+    }
+
+    fn overnight() -> HashMap<i64, Option<TimeWindow>> {
+        HashMap::from([(1, Some("22:00-07:00".parse().unwrap()))])
+    }
+
+    #[test]
+    fn quiet_alerts_wait_across_midnight() {
+        let merge = chrono::Duration::minutes(10);
+        let mut queue = queued();
+        for now in [local(15, 23, 30), local(16, 0, 0), local(16, 6, 59)] {
+            assert!(release_quiet(&mut queue, &overnight(), &[], |_| None, now, merge).is_empty());
+        }
+        let released = release_quiet(&mut queue, &overnight(), &[], |_| None, local(16, 7, 0), merge);
+        assert_eq!(released, [(1, queued().remove(&1).unwrap())]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn quiet_alerts_stay_while_muted_or_before_a_report() {
+        let merge = chrono::Duration::minutes(10);
+        let now = local(16, 7, 30);
+        let mut queue = queued();
+        assert!(release_quiet(&mut queue, &overnight(), &[1], |_| None, now, merge).is_empty());
+        // Der Bericht in fünf Minuten nimmt sie mit
+        assert!(release_quiet(&mut queue, &overnight(), &[], |_| Some(now + chrono::Duration::minutes(5)), now, merge).is_empty());
+        assert_eq!(release_quiet(&mut queue, &overnight(), &[], |_| Some(now + chrono::Duration::hours(5)), now, merge).len(), 1);
+    }
+
+    #[test]
+    fn quiet_alerts_released_once_quiet_hours_are_off() {
+        let mut queue = queued();
+        let released = release_quiet(&mut queue, &HashMap::from([(1, None)]), &[], |_| None, local(15, 23, 30), chrono::Duration::minutes(10));
+        assert_eq!(released.len(), 1);
+    }
+
+    // Bericht mitten in der Ruhezeit: er kommt ohne die Warnungen, die erst
+    // mit dem Ende der Ruhezeit folgen
+    #[test]
+    fn report_inside_quiet_hours_leaves_the_alerts_for_later() {
+        let merge = chrono::Duration::minutes(10);
+        let mut queue = queued();
+        let window = overnight()[&1];
+        assert!(missed_for_report(&mut queue, 1, window, local(16, 3, 0), merge).is_empty());
+        assert_eq!(queue.len(), 1);
+        let next_report = |_| Some(local(17, 3, 0));
+        assert!(release_quiet(&mut queue, &overnight(), &[], next_report, local(16, 6, 59), merge).is_empty());
+        let released = release_quiet(&mut queue, &overnight(), &[], next_report, local(16, 7, 0), merge);
+        assert_eq!(released, [(1, queued().remove(&1).unwrap())]);
+    }
+
+    // Kurz vor dem Ende der Ruhezeit nimmt der Bericht sie gleich mit
+    #[test]
+    fn report_just_before_the_end_takes_the_alerts() {
+        let merge = chrono::Duration::minutes(10);
+        let mut queue = queued();
+        assert_eq!(missed_for_report(&mut queue, 1, overnight()[&1], local(16, 6, 55), merge), queued().remove(&1).unwrap());
+        assert!(queue.is_empty());
+        assert!(release_quiet(&mut queue, &overnight(), &[], |_| None, local(16, 7, 0), merge).is_empty());
+    }
+
+    // Ohne Ruhezeit nimmt jeder Bericht mit, was liegt; ohne Bericht geht es
+    // mit dem Ende der Ruhezeit allein
+    #[test]
+    fn report_without_quiet_hours_and_quiet_hours_without_report() {
+        let merge = chrono::Duration::minutes(10);
+        let mut queue = queued();
+        assert_eq!(missed_for_report(&mut queue, 1, None, local(16, 3, 0), merge).len(), 2);
+        assert!(missed_for_report(&mut queue, 1, None, local(16, 3, 0), merge).is_empty());
+
+        let mut queue = queued();
+        assert!(release_quiet(&mut queue, &overnight(), &[], |_| None, local(16, 6, 50), merge).is_empty());
+        assert_eq!(release_quiet(&mut queue, &overnight(), &[], |_| None, local(16, 7, 0), merge).len(), 1);
+    }
+}
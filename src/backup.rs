@@ -0,0 +1,213 @@
+use crate::UserConfig;
+use crate::access::AccessList;
+use crate::history::History;
+use crate::ignored::IgnoreList;
+use crate::known_chats::KnownChats;
+use crate::records::Records;
+use crate::uptime::UptimeLog;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub const USAGE: &str = "Verwendung: restore <sicherungsverzeichnis>";
+
+const PREFIX: &str = "backup-";
+
+// Datei anlegen oder ersetzen, ohne dass ein Leser je einen halben Stand sieht.
+// Der Inhalt liegt vor dem Umbenennen auf der Platte, sonst kann nach einem
+// Stromausfall eine leere Datei am Ziel stehen.
+pub fn write_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let tmp = write_synced(path, content)?;
+    fs::rename(&tmp, path)?;
+    sync_dir(path);
+    Ok(())
+}
+
+// In die temporäre Datei neben `path` schreiben und auf die Platte bringen
+pub fn write_synced(path: &Path, content: &[u8]) -> std::io::Result<PathBuf> {
+    let tmp = tmp_path(path);
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    Ok(tmp)
+}
+
+// Umbenennungen im Verzeichnis festschreiben; nicht überall möglich
+pub fn sync_dir(path: &Path) {
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    if let Ok(dir) = fs::File::open(dir) {
+        dir.sync_all().ok();
+    }
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+// Sicherung der Zustandsdateien (Name in der Sicherung, Quelle) in
+// <dir>/backup-<zeitstempel>/. Fehlende Dateien werden übersprungen, die
+// Kopie wird vor dem Behalten geprüft. Danach bleiben nur die neuesten
+// `keep` Sicherungen.
+pub fn create(files: &[(&str, &Path)], dir: &Path, keep: usize, now: DateTime<Utc>) -> Result<PathBuf, String> {
+    let target = dir.join(format!("{}{}", PREFIX, now.format("%Y%m%d-%H%M%S")));
+    fs::create_dir_all(&target).map_err(|err| format!("{} kann nicht angelegt werden: {}", target.display(), err))?;
+
+    for (name, file) in files.iter().filter(|(_, file)| file.exists()) {
+        let content = fs::read(file).map_err(|err| format!("{} kann nicht gelesen werden: {}", file.display(), err))?;
+        let copy = target.join(name);
+        write_atomic(&copy, &content).map_err(|err| format!("{} kann nicht geschrieben werden: {}", copy.display(), err))?;
+    }
+    if let Err(err) = validate(&target) {
+        fs::remove_dir_all(&target).ok();
+        return Err(err);
+    }
+
+    rotate(dir, keep)?;
+    Ok(target)
+}
+
+// Älteste Sicherungen löschen, bis höchstens `keep` übrig sind
+pub fn rotate(dir: &Path, keep: usize) -> Result<Vec<PathBuf>, String> {
+    let mut backups = list(dir)?;
+    let excess = backups.len().saturating_sub(keep.max(1));
+    let removed: Vec<PathBuf> = backups.drain(..excess).collect();
+    for backup in &removed {
+        fs::remove_dir_all(backup).map_err(|err| format!("{} kann nicht gelöscht werden: {}", backup.display(), err))?;
+    }
+    Ok(removed)
+}
+
+// Sicherungen im Verzeichnis, älteste zuerst (der Name enthält den Zeitstempel)
+pub fn list(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(dir).map_err(|err| format!("{} kann nicht gelesen werden: {}", dir.display(), err))?;
+    let mut backups: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir() && path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with(PREFIX)))
+        .collect();
+    backups.sort();
+    Ok(backups)
+}
+
+// Alle enthaltenen Dateien müssen sich lesen lassen
+pub fn validate(backup: &Path) -> Result<(), String> {
+    parse_if_present::<HashMap<i64, UserConfig>>(&backup.join("state.json"))?;
+    parse_if_present::<History>(&backup.join("history.json"))?;
+    parse_if_present::<UptimeLog>(&backup.join("uptime.json"))?;
+    parse_if_present::<Records>(&backup.join("records.json"))?;
+    parse_if_present::<IgnoreList>(&backup.join("ignored.json"))?;
+    parse_if_present::<AccessList>(&backup.join("access.json"))?;
+    parse_if_present::<KnownChats>(&backup.join("known_chats.json"))?;
+    Ok(())
+}
+
+fn parse<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let content = fs::read_to_string(path).map_err(|err| format!("{} kann nicht gelesen werden: {}", path.display(), err))?;
+    serde_json::from_str(&content).map_err(|err| format!("{} ist fehlerhaft: {}", path.display(), err))
+}
+
+fn parse_if_present<T: DeserializeOwned>(path: &Path) -> Result<(), String> {
+    if path.exists() {
+        parse::<T>(path)?;
+    }
+    Ok(())
+}
+
+// Geprüfte Sicherung zurückspielen: (Datei in der Sicherung, Ziel).
+// Ohne state.json gilt sie nicht als Sicherung. Erst wenn alles lesbar ist,
+// werden die Ziele nacheinander ersetzt.
+pub fn restore(backup: &Path, targets: &[(&str, &Path)]) -> Result<Vec<PathBuf>, String> {
+    if !backup.join("state.json").exists() {
+        return Err(format!("{} enthält keine state.json", backup.display()));
+    }
+    validate(backup)?;
+    let mut restored = Vec::new();
+    for (name, target) in targets {
+        let source = backup.join(name);
+        if !source.exists() {
+            continue;
+        }
+        let content = fs::read(&source).map_err(|err| format!("{} kann nicht gelesen werden: {}", source.display(), err))?;
+        write_atomic(target, &content).map_err(|err| format!("{} kann nicht ersetzt werden: {}", target.display(), err))?;
+        restored.push(target.to_path_buf());
+    }
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Lang;
+    use crate::storage::{JsonStore, Store};
+    use chrono::TimeZone;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, hour, 0, 0).unwrap()
+    }
+
+    fn chats(ids: &[i64]) -> KnownChats {
+        let mut chats = KnownChats::default();
+        for &id in ids {
+            chats.command(id, at(0));
+        }
+        chats
+    }
+
+    #[test]
+    fn rotation_keeps_the_newest_and_deletes_the_oldest() {
+        let (data, dir) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let file = data.path().join("known_chats.json");
+        fs::write(&file, serde_json::to_vec(&chats(&[1])).unwrap()).unwrap();
+
+        let created: Vec<PathBuf> = (1..=4).map(|hour| create(&[("known_chats.json", &file)], dir.path(), 2, at(hour)).unwrap()).collect();
+        assert_eq!(list(dir.path()).unwrap(), created[2..]);
+        assert!(!created[0].exists() && !created[1].exists());
+        assert!(created[3].join("known_chats.json").exists());
+    }
+
+    #[test]
+    fn restore_brings_back_the_saved_state() {
+        let (data, dir) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let store = JsonStore::in_dir(data.path());
+        store.save_users(&HashMap::new());
+        store.save_known_chats(&chats(&[1, 2]));
+        let (state, known) = (data.path().join("state.json"), data.path().join("known_chats.json"));
+        let backup = create(&[("state.json", &state), ("known_chats.json", &known)], dir.path(), 3, at(1)).unwrap();
+
+        store.save_known_chats(&chats(&[3]));
+        let restored = store.restore(&backup).unwrap();
+        assert!(restored.contains(&known));
+        let mut ids: Vec<i64> = store.load_known_chats().iter().map(|(id, _)| *id).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn damaged_backup_is_neither_kept_nor_restored() {
+        let (data, dir) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let file = data.path().join("known_chats.json");
+        fs::write(&file, "{kaputt").unwrap();
+        assert!(create(&[("known_chats.json", &file)], dir.path(), 3, at(1)).is_err());
+        assert!(list(dir.path()).unwrap().is_empty());
+
+        // Ohne state.json ist es keine Sicherung; die Ziele bleiben unberührt
+        let store = JsonStore::in_dir(data.path());
+        assert!(store.restore(dir.path()).unwrap_err().contains("state.json"));
+        assert_eq!(fs::read_to_string(&file).unwrap(), "{kaputt");
+    }
+
+    #[test]
+    fn newer_backup_version_is_rejected() {
+        let config = serde_json::to_value(UserConfig::default()).unwrap();
+        let file = |version: u32| serde_json::to_vec(&serde_json::json!({"version": version, "chat_id": 1, "created_at": at(1), "config": config})).unwrap();
+
+        assert!(crate::parse_config_backup(&file(crate::BACKUP_VERSION), Lang::De).is_ok());
+        let err = crate::parse_config_backup(&file(crate::BACKUP_VERSION + 1), Lang::De).err().unwrap();
+        assert!(err.contains(&format!("Version {}", crate::BACKUP_VERSION + 1)), "{}", err);
+        assert!(crate::parse_config_backup(b"{\"chat_id\": 1}", Lang::De).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+use crate::history::History;
+use crate::timeutil::seconds_between;
+use std::collections::{BTreeMap, VecDeque};
+
+// Anzahl der letzten Abstände für den gleitenden Median
+const WINDOW: usize = 24;
+// Ohne so viele Abstände gibt es noch keine Vergleichsbasis
+const MIN_INTERVALS: usize = 5;
+
+// Meldeabstände je Gerät, ermittelt aus den Zeitstempeln neuer Messwerte
+#[derive(Default)]
+struct DeviceCadence {
+    last_arrival: Option<i64>,
+    intervals: VecDeque<i64>,
+    warned: bool,
+}
+
+impl DeviceCadence {
+    fn median(&self) -> Option<i64> {
+        if self.intervals.len() < MIN_INTERVALS {
+            return None;
+        }
+        let mut sorted: Vec<i64> = self.intervals.iter().copied().collect();
+        sorted.sort_unstable();
+        Some(sorted[sorted.len() / 2])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CadenceEvent {
+    // Aktuelle Lücke ist deutlich länger als üblich
+    Slowed { device_id: String, gap: i64, median: i64 },
+    // Nach einer Warnung kam wieder ein neuer Messwert
+    Resumed { device_id: String, gap: i64 },
+}
+
+// Erkennt, wenn ein Gerät seltener meldet als gewohnt (z.B. schwaches WLAN),
+// noch bevor es ganz ausfällt.
+pub struct CadenceTracker {
+    devices: BTreeMap<String, DeviceCadence>,
+    // Warnung ab Lücke > factor × Median
+    factor: f64,
+    // Der Bot sieht neue Werte nur beim Abruf; kürzere Abweichungen sind Rauschen
+    poll_interval: i64,
+}
+
+impl CadenceTracker {
+    pub fn new(factor: f64, poll_interval: i64) -> CadenceTracker {
+        CadenceTracker { devices: BTreeMap::new(), factor, poll_interval }
+    }
+
+    // Vergleichsbasis aus dem gespeicherten Verlauf, damit nach einem Neustart
+    // nicht erst wieder gesammelt werden muss
+    pub fn seed(&mut self, history: &History) {
+        // zeitlich sortiert; mehrere Typen mit gleichem Zeitstempel zählen einmal
+        for (device_id, _, timestamp, _) in history.samples_since(i64::MIN) {
+            self.observe(device_id, timestamp);
+        }
+    }
+
+    // Neuester Zeitstempel eines Geräts aus dem aktuellen Abruf.
+    // Liefert Resumed, wenn das Gerät nach einer Warnung wieder meldet.
+    pub fn observe(&mut self, device_id: &str, timestamp: i64) -> Option<CadenceEvent> {
+        let device = self.devices.entry(device_id.to_string()).or_default();
+        let last = device.last_arrival;
+        if last.is_some_and(|last| timestamp <= last) {
+            return None;
+        }
+        device.last_arrival = Some(timestamp);
+        let gap = seconds_between(last?, timestamp);
+
+        device.intervals.push_back(gap);
+        if device.intervals.len() > WINDOW {
+            device.intervals.pop_front();
+        }
+        if std::mem::take(&mut device.warned) {
+            return Some(CadenceEvent::Resumed { device_id: device_id.to_string(), gap });
+        }
+        None
+    }
+
+    // Prüft alle Geräte auf eine zu lange Lücke bis `now`. Je Lücke wird nur einmal gewarnt.
+    pub fn check(&mut self, now: i64) -> Vec<CadenceEvent> {
+        let mut events = Vec::new();
+        for (device_id, device) in &mut self.devices {
+            let (Some(last), Some(median)) = (device.last_arrival, device.median()) else { continue };
+            let gap = seconds_between(last, now);
+            let limit = (median as f64 * self.factor).max((median + self.poll_interval) as f64);
+            if !device.warned && gap as f64 > limit {
+                device.warned = true;
+                events.push(CadenceEvent::Slowed { device_id: device_id.clone(), gap, median });
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Meldungen alle `every` Sekunden ab 0, `count` Stück
+    fn regular(tracker: &mut CadenceTracker, every: i64, count: i64) -> i64 {
+        for n in 0..count {
+            tracker.observe("sensor1", n * every);
+        }
+        (count - 1) * every
+    }
+
+    #[test]
+    fn median_of_the_intervals() {
+        let mut device = DeviceCadence::default();
+        device.intervals.extend([300, 900, 60, 300, 600]);
+        assert_eq!(device.median(), Some(300));
+        // Ein Ausreißer verschiebt den Median nicht
+        device.intervals.push_back(86_400);
+        assert_eq!(device.median(), Some(600));
+    }
+
+    #[test]
+    fn only_the_last_intervals_count() {
+        let mut tracker = CadenceTracker::new(3.0, 60);
+        let last = regular(&mut tracker, 60, 10);
+        // Danach nur noch alle 10 Minuten: der Median folgt, sobald die
+        // alten Abstände aus dem Fenster gefallen sind
+        for n in 1..=WINDOW as i64 {
+            tracker.observe("sensor1", last + n * 600);
+        }
+        assert_eq!(tracker.devices["sensor1"].intervals.len(), WINDOW);
+        assert_eq!(tracker.devices["sensor1"].median(), Some(600));
+    }
+
+    #[test]
+    fn no_warning_before_enough_intervals() {
+        let mut tracker = CadenceTracker::new(3.0, 60);
+        let last = regular(&mut tracker, 300, MIN_INTERVALS as i64);
+        assert_eq!(tracker.devices["sensor1"].median(), None);
+        assert!(tracker.check(last + 100 * 300).is_empty());
+
+        tracker.observe("sensor1", last + 300);
+        assert_eq!(tracker.devices["sensor1"].median(), Some(300));
+    }
+
+    #[test]
+    fn late_device_is_reported_once_and_resumes() {
+        let mut tracker = CadenceTracker::new(3.0, 60);
+        let last = regular(&mut tracker, 300, 10);
+        // Grenze: 3 × 300 s
+        assert!(tracker.check(last + 900).is_empty());
+        let events = tracker.check(last + 901);
+        assert_eq!(events, [CadenceEvent::Slowed { device_id: "sensor1".into(), gap: 901, median: 300 }]);
+        assert!(tracker.check(last + 5000).is_empty());
+
+        assert_eq!(tracker.observe("sensor1", last + 6000), Some(CadenceEvent::Resumed { device_id: "sensor1".into(), gap: 6000 }));
+        assert_eq!(tracker.observe("sensor1", last + 6300), None);
+    }
+
+    // Bei kurzen Abständen zählt mindestens ein Abrufabstand als Spielraum
+    #[test]
+    fn poll_interval_is_the_least_tolerance() {
+        let mut tracker = CadenceTracker::new(2.0, 600);
+        let last = regular(&mut tracker, 60, 10);
+        assert!(tracker.check(last + 660).is_empty());
+        assert_eq!(tracker.check(last + 661).len(), 1);
+    }
+
+    #[test]
+    fn repeated_or_older_timestamps_are_ignored() {
+        let mut tracker = CadenceTracker::new(3.0, 60);
+        let last = regular(&mut tracker, 300, 4);
+        tracker.observe("sensor1", last);
+        tracker.observe("sensor1", last - 100);
+        assert_eq!(tracker.devices["sensor1"].intervals, [300, 300, 300]);
+    }
+
+    // Mehrere Typen mit demselben Zeitstempel ergeben einen Abstand
+    #[test]
+    fn seeded_from_the_history() {
+        let mut history = History::default();
+        for n in 0..8 {
+            history.record("sensor1", "temperature", n * 300, 21.0);
+            history.record("sensor1", "humidity", n * 300, 50.0);
+        }
+        history.record("sensor2", "temperature", 0, 19.0);
+        let mut tracker = CadenceTracker::new(3.0, 60);
+        tracker.seed(&history);
+        assert_eq!(tracker.devices["sensor1"].intervals.len(), 7);
+        assert_eq!(tracker.devices["sensor1"].median(), Some(300));
+        assert_eq!(tracker.devices["sensor2"].last_arrival, Some(0));
+        assert_eq!(tracker.check(7 * 300 + 901), [CadenceEvent::Slowed { device_id: "sensor1".into(), gap: 901, median: 300 }]);
+    }
+}
@@ -0,0 +1,118 @@
+// Ohne Diagramme oder ohne HTTP-Server bleibt der Zwischenspeicher leer
+#![cfg_attr(not(all(feature = "charts", feature = "http-api")), allow(dead_code))]
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::time::{Duration, Instant};
+
+struct Entry {
+    png: Arc<Vec<u8>>,
+    expires: Instant,
+    used: u64,
+}
+
+// Zwischenspeicher für Diagramme in voller Auflösung, erreichbar über
+// GET /charts/<token>.png. Links verfallen nach `ttl`; übersteigt der
+// Inhalt `max_bytes`, fliegen die am längsten nicht abgerufenen zuerst.
+pub struct ChartCache {
+    ttl: Duration,
+    max_bytes: usize,
+    entries: HashMap<String, Entry>,
+    clock: u64,
+}
+
+impl ChartCache {
+    pub fn new(ttl: Duration, max_bytes: usize) -> ChartCache {
+        ChartCache { ttl, max_bytes, entries: HashMap::new(), clock: 0 }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    // Bild ablegen; liefert das Token für den Link oder None, wenn es allein
+    // schon zu groß ist
+    pub fn insert(&mut self, png: Vec<u8>, now: Instant) -> Option<String> {
+        if png.len() > self.max_bytes {
+            return None;
+        }
+        self.purge(now);
+        while self.size() + png.len() > self.max_bytes {
+            self.evict();
+        }
+        let token = crate::random_token();
+        self.clock += 1;
+        self.entries.insert(token.clone(), Entry { png: Arc::new(png), expires: now + self.ttl, used: self.clock });
+        Some(token)
+    }
+
+    // Abruf zählt als Benutzung für die Verdrängung, verlängert den Link aber nicht
+    pub fn get(&mut self, token: &str, now: Instant) -> Option<Arc<Vec<u8>>> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(token).filter(|entry| entry.expires > now)?;
+        entry.used = self.clock;
+        Some(entry.png.clone())
+    }
+
+    // Abgelaufene Bilder entfernen; liefert deren Anzahl
+    pub fn purge(&mut self, now: Instant) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|_, entry| entry.expires > now);
+        before - self.entries.len()
+    }
+
+    pub fn size(&self) -> usize {
+        self.entries.values().map(|entry| entry.png.len()).sum()
+    }
+
+    fn evict(&mut self) {
+        let oldest = self.entries.iter().min_by_key(|(_, entry)| entry.used).map(|(token, _)| token.clone());
+        if let Some(token) = oldest {
+            self.entries.remove(&token);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expired_links_are_gone_and_purged() {
+        let (mut cache, start) = (ChartCache::new(Duration::from_secs(60), 100), Instant::now());
+        let early = cache.insert(vec![1; 10], start).unwrap();
+        let late = cache.insert(vec![2; 10], start + Duration::from_secs(30)).unwrap();
+
+        let expired = start + Duration::from_secs(60);
+        assert!(cache.get(&early, expired).is_none());
+        assert_eq!(cache.get(&late, expired).as_deref(), Some(&vec![2; 10]));
+        assert_eq!(cache.purge(expired), 1);
+        assert_eq!(cache.size(), 10);
+        // Abruf verlängert den Link nicht
+        assert!(cache.get(&late, start + Duration::from_secs(90)).is_none());
+    }
+
+    #[test]
+    fn least_recently_used_is_evicted_first() {
+        let (mut cache, now) = (ChartCache::new(Duration::from_secs(60), 30), Instant::now());
+        let first = cache.insert(vec![1; 10], now).unwrap();
+        let second = cache.insert(vec![2; 10], now).unwrap();
+        let third = cache.insert(vec![3; 10], now).unwrap();
+        cache.get(&first, now).unwrap();
+
+        let fourth = cache.insert(vec![4; 10], now).unwrap();
+        assert!(cache.get(&second, now).is_none());
+        for token in [&first, &third, &fourth] {
+            assert!(cache.get(token, now).is_some());
+        }
+        assert_eq!(cache.size(), 30);
+    }
+
+    #[test]
+    fn image_larger_than_the_cache_is_refused() {
+        let (mut cache, now) = (ChartCache::new(Duration::from_secs(60), 30), Instant::now());
+        let kept = cache.insert(vec![1; 10], now).unwrap();
+        assert!(cache.insert(vec![0; 31], now).is_none());
+        assert!(cache.get(&kept, now).is_some());
+    }
+}
@@ -0,0 +1,2415 @@
+use crate::archive::Restore;
+use crate::digest::AlertMode;
+use crate::episode::Episode;
+use crate::fetch::{fetch_sensor_data_for, status_readings};
+use crate::history::History;
+use crate::i18n::Lang;
+use crate::layout::{Layout, format_status_table};
+use crate::messenger::Messenger;
+use crate::monitor::EventKind;
+use crate::notify::{MessageKind, NotificationMode};
+use crate::outbox::Outbox;
+use crate::outdoor::Watering;
+use crate::rate::RateRule;
+use crate::relative::RelativeRule;
+use crate::routing::Severity;
+use crate::schedule::WeeklySchedule;
+use crate::sensor::{SensorKind, ThresholdDirection, ThresholdKey};
+use crate::snapshot::Snapshot;
+use crate::state::Shared;
+use crate::storage::{self, Store};
+use crate::thresholds::{ThresholdArgs, TimeWindow};
+use crate::timeformat::TimeFormat;
+use crate::units::TempUnit;
+use crate::{
+    BACKUP_VERSION, CHART_DEFAULT_HOURS, ConfigBackup, DEFAULT_DIGEST_MINUTES, FLEET_REPORT_NOW, HANDLER_PANICS, HISTORY_DEFAULT_HOURS, LiveStatus,
+    MAX_NOTE_CHARS, MAX_UNDO, MAX_WATERING_DAYS, MIN_DIGEST_MINUTES, OUTBOX, OUTDOOR_CHECK_AT, PENDING_IMAGES, POLL_INTERVAL, POLL_INTERVAL_CHANGED,
+    PendingChange, ROUTE_CHECKS, SensorData, Setter, UserConfig, UserConfigs, access, admitted, alert_message, apply_default, archive, ask_confirmation,
+    backup_summary, blocked, bot_status, broadcast, change_threshold, chat_timezone, check_delivery, config_summary, configure, copy_thresholds, database_file,
+    delivery_latencies, device_kinds, escape_markdown, export, fill_name, format_alarm_entry, format_alert_digest, format_alert_mode, format_device_thresholds,
+    format_diff, format_duration, format_handover, format_health, format_help, format_history, format_local, format_local_in, format_notes,
+    format_poll_interval, format_profiles, format_reading_time, format_records, format_recovery, format_routing, format_schedule, format_sensors,
+    format_settings, format_snoozes, format_stats, format_status, format_tenant, format_thresholds, format_timestamp, handover, i18n, ignored, inject,
+    introspect, known_chat_list, known_chats, known_devices, last_fire, last_weekly_fire, latest, layout, live_readings, live_text, live_values, local_time,
+    max_history_hours, monitor, mute_chat, next_fire, note_reached, notes_for, notification_summary, offer_restore, outdoor_comparison, parse_chart_hours,
+    parse_daily, parse_duration, parse_weekly, poll_interval, post_live, profiles, purge_chat, random_token, rate, record_setter, recorded_samples, redact,
+    refresh, relative, reload_rooms, remove_threshold, reply, resolve_device, resolve_device_in, room_images, room_name, room_status, rooms, self_test,
+    selftest, send_chart, send_room_status, settings, snooze_room, status_footer, status_list, status_targets, status_trends, telemetry, tenant_of,
+    threshold_holders, thresholds, timezone_name, type_label_in, unit_in, unknown_room, unmute_summary, validate_threshold, visible_readings, weather,
+    wohnzimmer_key,
+};
+use chrono::Utc;
+use chrono_tz::Tz;
+use log::{info, warn};
+use teloxide::prelude::*;
+use teloxide::types::{MessageId, ParseMode};
+use teloxide::utils::command::BotCommands;
+
+// Telegram-Befehle
+#[derive(BotCommands, Clone)]
+#[command(rename_rule = "kebab-case", description = "Verfügbare Befehle:")]
+pub enum Command {
+    #[command(description = "Startet den Bot.")]
+    Start,
+    #[command(description = "Zeigt diese Hilfe an.")]
+    Help,
+    #[command(description = "Zeigt alle aktuellen Sensordaten, optional nur für einen Raum: [raum]")]
+    Status(String),
+    #[command(description = "Sofort neue Messwerte abrufen und auswerten.")]
+    Refresh,
+    #[command(description = "Gemeldete Geräte mit Raum, Messgrößen und Alter des letzten Werts.")]
+    Sensors,
+    #[command(description = "Verlauf als Zahlen: <gerät> <typ> [stunden], Standard 6 Stunden")]
+    History(String),
+    #[command(description = "Min, Max und Mittel der letzten 24 Stunden und 7 Tage: <gerät> <typ>")]
+    Stats(String),
+    #[command(description = "Letzte Alarme und Entwarnungen, neueste zuerst: [anzahl], Standard 10")]
+    Alarms(String),
+    #[command(description = "Diagramm anzeigen: <raum> [typ] [dauer, z.B. 12h]")]
+    Chart(String),
+    #[command(description = "Messwerte als CSV-Datei: <gerät> <typ> [tage], Standard 7 Tage")]
+    Export(String),
+    #[command(description = "Temperaturverlauf Wohnzimmer.")]
+    WohnzimmerTdia,
+    #[command(description = "Luftfeuchtigkeitsverlauf Wohnzimmer.")]
+    WohnzimmerHdia,
+    #[command(description = "Alarm, wenn Temperatur unter Wert fällt.")]
+    WohnzimmerTmin(f64),
+    #[command(description = "Alarm, wenn Temperatur über Wert steigt.")]
+    WohnzimmerTmax(f64),
+    #[command(description = "Alarm, wenn Luftfeuchtigkeit unter Wert fällt.")]
+    WohnzimmerHmin(f64),
+    #[command(description = "Alarm, wenn Luftfeuchtigkeit über Wert steigt.")]
+    WohnzimmerHmax(f64),
+    #[command(description = "MIN-Schwelle setzen: <gerät> <typ> <wert> [HH:MM-HH:MM] [info|warn|critical] [\"Text\"]")]
+    Setmin(ThresholdArgs),
+    #[command(description = "MAX-Schwelle setzen: <gerät> <typ> <wert> [HH:MM-HH:MM] [info|warn|critical] [\"Text\"]")]
+    Setmax(ThresholdArgs),
+    #[command(description = "Zeigt deine Schwellwerte.")]
+    Thresholds,
+    #[command(rename = "copy-thresholds", description = "Alle Schwellen eines Raums auf einen anderen übertragen: <von> <nach>")]
+    CopyThresholds(String),
+    #[command(rename = "apply-default", description = "Übliche Schwellen für einen Raum setzen (18–26 °C, 35–65 %): <raum>")]
+    ApplyDefault(String),
+    #[command(description = "Statusbericht planen, z.B. 'mo-fr 06:30; sa,so 09:00' oder 'off'.")]
+    Schedule(String),
+    #[command(description = "Zeigt deine geplanten Berichte.")]
+    Schedules,
+    #[command(description = "Tageszusammenfassung: <HH:MM> oder Wochenplan, z.B. 'mo-fr 06:30; sa,so 09:00'.")]
+    SubscribeDaily(String),
+    #[command(description = "Tageszusammenfassung abbestellen.")]
+    UnsubscribeDaily,
+    #[command(rename = "subscribe-weekly", description = "Wochenbericht mit Diagramm: <wochentag> <HH:MM>, z.B. 'so 18:00'")]
+    SubscribeWeekly(String),
+    #[command(rename = "unsubscribe-weekly", description = "Wochenbericht abbestellen.")]
+    UnsubscribeWeekly,
+    #[command(description = "Ruhezeit für Warnungen, z.B. '22:00 07:00' oder 'off'.")]
+    QuietHours(String),
+    #[command(rename = "alert-mode", description = "Warnungen sofort (instant) oder gesammelt, z.B. 'digest 1h'; kritische kommen immer sofort.")]
+    AlertMode(String),
+    #[command(description = "Benachrichtigungston: loud, normal (Antworten lautlos) oder quiet (nur Kritisches mit Ton).")]
+    Notifications(String),
+    #[command(description = "Alle Benachrichtigungen stummschalten, z.B. '4h'.")]
+    MuteAll(String),
+    #[command(description = "Stummschalten: <dauer> für alles, <raum> <dauer> für einen Raum, z.B. '2h'.")]
+    Mute(String),
+    #[command(description = "Stummschaltungen vorzeitig beenden.")]
+    Unmute,
+    #[command(description = "Zeigt deine Einstellungen.")]
+    Settings,
+    #[command(description = "Darstellung von /status: classic (compact) oder table.")]
+    Layout(String),
+    #[command(description = "Darstellung von /status: compact oder table (wie /layout).")]
+    Format(String),
+    #[command(description = "Zeitangaben in /status und Warnungen: relative, absolute oder both.")]
+    Timeformat(String),
+    #[command(description = "Angeheftete Übersicht, die sich nach jedem Abruf aktualisiert: on oder off.")]
+    Live(String),
+    #[command(description = "Sprache der Antworten: de oder en.")]
+    Language(String),
+    #[command(description = "Temperatureinheit: celsius oder fahrenheit.")]
+    Units(String),
+    #[command(description = "Warnung bei schwacher Batterie: <prozent>, off oder default; ohne Angabe die Ladestände.")]
+    Battery(String),
+    #[command(description = "Zeitzone für Uhrzeiten, z.B. Europe/Berlin.")]
+    Timezone(String),
+    #[command(description = "Änderungen seit deinem letzten /status oder /diff.")]
+    Diff,
+    #[command(description = "Zeigt Laufzeit und Ausfälle des Bots.")]
+    Health,
+    #[command(description = "Letzte Schwellwert-Änderung zurücknehmen.")]
+    Undo,
+    #[command(description = "Neues Token für den Kalender-Link erzeugen.")]
+    ApiToken,
+    #[command(description = "Notiz zu einem Gerät: <raum> <text> oder <raum> clear")]
+    Note(String),
+    #[command(description = "Sofort eine Sicherung anlegen (nur Admin).")]
+    BackupNow,
+    #[command(description = "Tiefst- und Höchstwerte, optional nur für einen Raum.")]
+    Records(String),
+    #[command(description = "Interner Zustand und Zustellung, optional zu einem Haushalt (nur Admin).")]
+    Debug(String),
+    #[command(description = "Raumbild festlegen: <raum>, danach ein Foto senden; <raum> off entfernt es (nur Admin).")]
+    Setimage(String),
+    #[command(description = "Raumbilder bei kritischen Warnungen und /status <raum>: on oder off.")]
+    RoomImages(String),
+    #[command(description = "Schwellen-Profile: create, set, activate, schedule, delete (ohne Angabe: Übersicht).")]
+    Profile(String),
+    #[command(rename = "escalate-to", description = "Unbestätigte Warnungen weitergeben: <chat-id> after <dauer> [warn|critical].")]
+    EscalateTo(String),
+    #[command(rename = "accept-escalations-from", description = "Weitergegebene Warnungen eines Chats annehmen: <chat-id>, <chat-id> off beendet das.")]
+    AcceptEscalationsFrom(String),
+    #[command(description = "Weitergabe aus /escalate-to anzeigen; off beendet sie.")]
+    Escalation(String),
+    #[command(description = "Testwert einspeisen: <gerät> <typ> <wert> [alter] [--no-store] (nur Admin).")]
+    Inject(String),
+    #[command(rename = "test-alarm", description = "Probealarm knapp hinter einer deiner Schwellen, mit Entwarnung: [gerät] [typ].")]
+    TestAlarm(String),
+    #[command(description = "Alle deine Einstellungen löschen: 'all'. 7 Tage lang mit /undo-clear umkehrbar.")]
+    Clear(String),
+    #[command(description = "Mit /clear all gelöschte Einstellungen wiederherstellen.")]
+    UndoClear,
+    #[command(description = "Alle deine Daten sofort und endgültig löschen.")]
+    Forgetme,
+    #[command(description = "Deine Einstellungen als JSON-Datei schicken.")]
+    Backup,
+    #[command(description = "Datei von /backup mit der Bildunterschrift /restore zurückschicken (oder mit /restore darauf antworten).")]
+    Restore,
+    #[command(description = "Geräte-Wochenbericht sonntags: on, off oder now (nur Admin).")]
+    FleetReport(String),
+    #[command(description = "Abfrageintervall ändern: <sekunden> oder z.B. 15m (nur Admin, bis zum Neustart).")]
+    SetInterval(String),
+    #[command(rename = "purge-user", description = "Alle Daten eines Chats sofort löschen: <chat_id> (nur Admin).")]
+    PurgeUser(String),
+    #[command(rename = "reload-rooms", description = "Raumdatei (ROOMS_FILE) neu einlesen (nur Admin).")]
+    ReloadRooms,
+    #[command(description = "Telegram, Sensoren, Speicher und Dateien prüfen (nur Admin).")]
+    Selftest,
+    #[command(rename = "weather-location", description = "Standort für das Wetter in /status: <breite>,<länge> oder off (nur Admin, bis zum Neustart).")]
+    WeatherLocation(String),
+    #[command(description = "Gerät ignorieren: keine Messwerte, Warnungen oder Statuszeilen mehr (nur Admin).")]
+    Ignore(String),
+    #[command(description = "Ignoriertes Gerät wieder überwachen (nur Admin).")]
+    Unignore(String),
+    #[command(description = "Schwellen eines Raums stummschalten, z.B. /snooze Wohnzimmer 2h; /snooze clear hebt alle auf.")]
+    Snooze(String),
+    #[command(description = "Aktive Stummschaltungen mit Restzeit.")]
+    Snoozes,
+    #[command(description = "Ignorierte Geräte mit Zeitpunkt und Admin (nur Admin).")]
+    Ignored,
+    #[command(description = "Chat freischalten: <chat_id>; ohne Angabe alle Freigaben (nur Admin).")]
+    Allow(String),
+    #[command(description = "Chat sperren: <chat_id> (nur Admin).")]
+    Deny(String),
+    #[command(description = "Nachricht an alle bekannten Chats senden: <text> (nur Admin).")]
+    Broadcast(String),
+    #[command(description = "Bekannte Chats mit Schwellen und letztem Befehl (nur Admin).")]
+    Users,
+    #[command(description = "Schwelle entfernen: <gerät> <typ> <min|max>")]
+    ClearThreshold(String),
+    #[command(description = "Alle deine Schwellwerte entfernen; übrige Einstellungen bleiben.")]
+    ClearAll,
+    #[command(description = "Schwelle Schritt für Schritt per Tastatur einrichten.")]
+    Configure,
+    #[command(description = "Abstand, ab dem ein Alarm als erholt gilt: <gerät> <typ> <min|max> <wert> oder default.")]
+    Hysteresis(String),
+    #[command(description = "Warnung wiederholen, solange der Alarm besteht: <gerät> <typ> <min|max> <dauer> oder off.")]
+    Repeat(String),
+    #[command(description = "Alarm bei schneller Änderung: <gerät> <typ> <änderung> <dauer>, z.B. -3 15m, oder <gerät> <typ> off.")]
+    Rate(String),
+    #[command(
+        description = "Schwelle relativ zu einem anderen Sensor: <gerät> <typ> max-rel|min-rel <gerät> <typ> <abstand>, oder <gerät> <typ> max-rel|min-rel off."
+    )]
+    Set(String),
+    #[command(description = "Gießerinnerung für Räume im Freien: <°C> [tage], z.B. /watering 28 3, oder off.")]
+    Watering(String),
+}
+
+// Netzwerkzugriff ohne die Sperre der Konfigurationen: freigeben, warten
+// und neu sperren. Verweise in die Konfigurationen überleben das nicht,
+// was danach noch gebraucht wird, muss vorher kopiert werden. Vor einem
+// return wird umsonst neu gesperrt, das ist billig.
+macro_rules! unlocked {
+    ($guard:ident, $configs:expr, $future:expr) => {{
+        drop($guard);
+        let result = $future.await;
+        #[allow(unused_assignments)]
+        {
+            $guard = $configs.lock().await;
+        }
+        result
+    }};
+}
+
+// Legacy-Markdown bis zur Umstellung auf MarkdownV2; die Parameter injiziert dptree
+#[allow(deprecated, clippy::too_many_arguments)]
+pub async fn answer(bot: Bot, msg: Message, cmd: Command, shared: Shared) -> ResponseResult<()> {
+    let Shared { configs, flags, history, records, storage, uptime, escalation, charts, replies: messenger } = shared;
+    let replies = messenger.as_ref();
+    let user_id = msg.chat.id;
+    let source = msg.text().unwrap_or_default().to_string();
+    info!(
+        "Befehl {} von Chat {} ({})",
+        source.split_whitespace().next().unwrap_or_default(),
+        redact::chat(user_id.0),
+        redact::name(msg.from().map(|u| u.first_name.as_str()).unwrap_or("?"))
+    );
+    // Wer einen Befehl schickt, hat den Bot wieder entsperrt
+    if blocked(user_id.0) {
+        info!("{} hat den Bot wieder entsperrt", redact::chat(user_id.0));
+        note_reached(storage.as_ref(), user_id.0);
+    }
+    known_chats().command(user_id.0, Utc::now());
+    storage.save_known_chats(&known_chats());
+    if let Some(name) = source.split_whitespace().next() {
+        telemetry::command(tenant_of(user_id.0).as_deref(), name.trim_start_matches('/').split('@').next().unwrap_or_default());
+    }
+    let mut user_configs = configs.lock().await;
+    // In Gruppen gehört die Konfiguration dem Chat, nicht dem Absender
+    if msg.chat.is_private()
+        && let Some(user) = msg.from()
+        && user_configs.entry(user_id.0).or_default().remember_name(user)
+    {
+        info!("Name für Chat {} gespeichert: {}", redact::chat(user_id.0), redact::name(&user.first_name));
+    }
+
+    let lang = user_configs.get(&user_id.0).map(|c| c.lang).unwrap_or_default();
+    let admin_only = i18n::message(lang, "admin_only");
+    let units = user_configs.get(&user_id.0).map(|c| c.units).unwrap_or_default();
+    let tz = chat_timezone(user_configs.get(&user_id.0));
+    let times = user_configs.get(&user_id.0).map(|c| c.time_format).unwrap_or_default();
+    let mode = user_configs.get(&user_id.0).map(|c| c.notifications).unwrap_or_default();
+    // In Gruppen merkt sich eine neue Schwelle, wer sie gesetzt hat
+    let setter = msg.from().filter(|_| !msg.chat.is_private()).map(Setter::of);
+
+    match cmd {
+        Command::Start => {
+            let name = user_configs.get(&user_id.0).and_then(|c| c.first_name.as_deref());
+            let text = fill_name(i18n::message(lang, "welcome"), name);
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Help => {
+            unlocked!(
+                user_configs,
+                configs,
+                reply(replies, user_id, mode, format!("{}\n{}", i18n::message(lang, "help_title"), escape_markdown(&format_help(lang))))
+                    .parse_mode(ParseMode::Markdown)
+            )?;
+        }
+
+        Command::Refresh => {
+            let tenant = tenant_of(user_id.0);
+            let text = match unlocked!(user_configs, configs, refresh::request()) {
+                Ok(devices) => match devices.iter().filter(|device| rooms().visible(tenant.as_deref(), device)).count() {
+                    0 => i18n::message(lang, "refresh_none").to_string(),
+                    1 => i18n::message(lang, "refresh_one").to_string(),
+                    count => i18n::message_with(lang, "refresh_many", &[("count", &count.to_string())]),
+                },
+                Err(err) => i18n::message_with(lang, "refresh_failed", &[("error", &err.to_string())]),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+            return Ok(());
+        }
+
+        Command::Status(room) if !room.trim().is_empty() => {
+            let config = user_configs.get(&user_id.0);
+            let notes = config.map(|c| c.notes.clone()).unwrap_or_default();
+            let with_image = config.is_some_and(|c| c.room_images);
+            let (text, device) =
+                unlocked!(user_configs, configs, room_status(user_id.0, &room, &history, &notes, lang, units, tz, times)).unwrap_or_else(|| {
+                    (
+                        i18n::message_with(
+                            lang,
+                            "unknown_room",
+                            &[("room", &escape_markdown(room.trim())), ("rooms", &escape_markdown(&status_targets(user_id.0)))],
+                        ),
+                        None,
+                    )
+                });
+            unlocked!(user_configs, configs, send_room_status(replies, user_id, mode, text, device.as_deref().filter(|_| with_image)))?;
+        }
+
+        Command::Sensors => {
+            let text = match unlocked!(user_configs, configs, status_readings(user_id.0)) {
+                Ok((sensor_data, stale)) => {
+                    let mut text = format_sensors(&sensor_data, Utc::now().timestamp(), lang);
+                    if let Some(stale) = stale {
+                        text.push_str(&format!("\n{}", stale.in_lang(lang)));
+                    }
+                    text
+                }
+                Err(err) => format!("❌ {}", err.text().in_lang(lang)),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Status(_) => {
+            match unlocked!(user_configs, configs, status_readings(user_id.0)) {
+                Ok((sensor_data, stale)) => {
+                    let outdoor = unlocked!(user_configs, configs, weather::current());
+                    user_configs.entry(user_id.0).or_default().last_viewed = Some(Snapshot::capture(&sensor_data, Utc::now()));
+                    let config = user_configs.get(&user_id.0);
+                    let sensor_data = status_list(sensor_data, config.map_or(settings().battery_low, UserConfig::battery_low));
+                    let mut footer = status_footer(config, user_id.0, Utc::now());
+                    footer.extend(stale.map(|stale| stale.in_lang(lang)));
+                    footer.extend(outdoor_comparison(&sensor_data, outdoor, lang, units));
+                    // Tabelle nur, wenn sie schmal genug ist, sonst klassisch
+                    let notes = config.map(|c| c.notes.clone()).unwrap_or_default();
+                    let table =
+                        config.filter(|c| c.layout == Layout::Table).and_then(|_| format_status_table(&sensor_data, units, tz, times, Utc::now().timestamp()));
+                    match table {
+                        Some(table) => {
+                            let notes: String = notes_for(&notes, &sensor_data)
+                                .map(|(room, note)| format!("<i>📝 {}: {}</i>\n", layout::escape_html(&room), layout::escape_html(note)))
+                                .collect();
+                            let footer: String = footer.iter().map(|line| format!("\n{}", layout::escape_html(line))).collect();
+                            unlocked!(
+                                user_configs,
+                                configs,
+                                reply(replies, user_id, mode, format!("{}\n{}{}", table, notes, footer)).parse_mode(ParseMode::Html)
+                            )?;
+                        }
+                        None => {
+                            let trends = status_trends(&*history.lock().await, &sensor_data);
+                            let status = format!("{}{}", format_status(&sensor_data, &trends, lang, units, tz, times), format_notes(&notes, &sensor_data));
+                            let footer: String = footer.iter().map(|line| format!("\n{}", escape_markdown(line))).collect();
+                            unlocked!(user_configs, configs, reply(replies, user_id, mode, format!("{}{}", status, footer)).parse_mode(ParseMode::Markdown))?;
+                        }
+                    }
+                }
+                Err(err) => {
+                    unlocked!(user_configs, configs, reply(replies, user_id, mode, format!("❌ {}", err.text().in_lang(lang))))?;
+                }
+            }
+        }
+
+        Command::History(spec) => {
+            let parts: Vec<&str> = spec.split_whitespace().collect();
+            let hours = match parts.get(2).map(|h| h.parse::<i64>()) {
+                None => Ok(HISTORY_DEFAULT_HOURS),
+                Some(Ok(hours)) if (1..=max_history_hours()).contains(&hours) => Ok(hours),
+                Some(_) => Err(i18n::message_with(lang, "hours_range", &[("max", &max_history_hours().to_string())])),
+            };
+            let text = match (parts.as_slice(), hours) {
+                ([device, sensor_type] | [device, sensor_type, _], Ok(hours)) => match resolve_device_in(user_id.0, device) {
+                    None => format!("❌ {}", unknown_room(lang, user_id.0, device)),
+                    Some(device) => {
+                        let sensor_type = SensorKind::from(*sensor_type);
+                        let now = Utc::now().timestamp();
+                        let samples = recorded_samples(&*history.lock().await, &device, sensor_type.as_str(), now - hours * 60 * 60);
+                        format_history(&samples, &device, sensor_type.as_str(), hours, now, lang, units, tz)
+                    }
+                },
+                (_, Err(err)) => err,
+                _ => i18n::message(lang, "history_usage").to_string(),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Alarms(spec) => {
+            let count = match spec.trim() {
+                "" => Ok(10),
+                n => n.parse::<usize>().ok().filter(|n| (1..=50).contains(n)).ok_or(i18n::message(lang, "alarms_usage")),
+            };
+            let text = match (count, user_configs.get(&user_id.0).map(|c| &c.alarm_log)) {
+                (Err(usage), _) => usage.to_string(),
+                (Ok(_), None) => i18n::message(lang, "alarms_none").to_string(),
+                (Ok(_), Some(log)) if log.is_empty() => i18n::message(lang, "alarms_none").to_string(),
+                (Ok(count), Some(log)) => {
+                    let mut text = i18n::message(lang, "alarms_title").to_string();
+                    for entry in log.latest(count) {
+                        text.push_str(&format!("\n{}", format_alarm_entry(entry, lang, units, tz)));
+                    }
+                    text
+                }
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Stats(spec) => {
+            let parts: Vec<&str> = spec.split_whitespace().collect();
+            let text = match parts.as_slice() {
+                [device, sensor_type] => match resolve_device_in(user_id.0, device) {
+                    None => format!("❌ {}", unknown_room(lang, user_id.0, device)),
+                    Some(device) => {
+                        let sensor_type = SensorKind::from(*sensor_type);
+                        let now = Utc::now().timestamp();
+                        let samples = recorded_samples(&*history.lock().await, &device, sensor_type.as_str(), now - 7 * 24 * 60 * 60);
+                        format_stats(&samples, &device, sensor_type.as_str(), now, lang, units, tz)
+                    }
+                },
+                _ => i18n::message(lang, "stats_usage").to_string(),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Export(spec) => {
+            let parts: Vec<&str> = spec.split_whitespace().collect();
+            let max_days = max_history_hours() / 24;
+            let days = match parts.get(2).map(|d| d.parse::<i64>()) {
+                None => Ok(export::DEFAULT_DAYS.min(max_days)),
+                Some(Ok(days)) if (1..=max_days).contains(&days) => Ok(days),
+                Some(_) => Err(i18n::message_with(lang, "days_range", &[("max", &max_days.to_string())])),
+            };
+            let (device, sensor_type, days) = match (parts.as_slice(), days) {
+                ([device, sensor_type] | [device, sensor_type, _], Ok(days)) => match resolve_device_in(user_id.0, device) {
+                    Some(resolved) => (resolved, SensorKind::from(*sensor_type), days),
+                    None => {
+                        unlocked!(user_configs, configs, reply(replies, user_id, mode, format!("❌ {}", unknown_room(lang, user_id.0, device))))?;
+                        return Ok(());
+                    }
+                },
+                (_, Err(err)) => {
+                    unlocked!(user_configs, configs, reply(replies, user_id, mode, err))?;
+                    return Ok(());
+                }
+                _ => {
+                    unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message(lang, "export_usage")))?;
+                    return Ok(());
+                }
+            };
+            let now = Utc::now().timestamp();
+            let since = now - days * 24 * 60 * 60;
+            let samples = recorded_samples(&*history.lock().await, &device, sensor_type.as_str(), since);
+            let typ = type_label_in(lang, sensor_type.as_str()).0;
+            if samples.is_empty() {
+                let text = i18n::message_with(lang, "export_empty", &[("type", typ), ("room", &room_name(&device)), ("days", &days.to_string())]);
+                unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+                return Ok(());
+            }
+            let csv = export::csv(&samples, &sensor_type, units, unit_in(units, sensor_type.as_str()), tz);
+            if csv.len() > export::MAX_BYTES {
+                unlocked!(
+                    user_configs,
+                    configs,
+                    reply(
+                        replies,
+                        user_id,
+                        mode,
+                        i18n::message_with(
+                            lang,
+                            "export_too_large",
+                            &[("size", &format!("{:.1}", csv.len() as f64 / (1024.0 * 1024.0))), ("max", &(export::MAX_BYTES / (1024 * 1024)).to_string())]
+                        )
+                    )
+                )?;
+                return Ok(());
+            }
+            let name = export::file_name(&device, sensor_type.as_str(), since, now, tz);
+            unlocked!(
+                user_configs,
+                configs,
+                bot.send_document(user_id, teloxide::types::InputFile::memory(csv.into_bytes()).file_name(name))
+                    .caption(i18n::message_with(
+                        lang,
+                        "export_caption",
+                        &[
+                            ("type", typ),
+                            ("room", &room_name(&device)),
+                            ("days", &days.to_string()),
+                            ("count", &samples.len().to_string()),
+                            ("zone", &timezone_name(tz))
+                        ]
+                    ))
+                    .disable_notification(mode.silent(MessageKind::Reply))
+            )?;
+        }
+
+        Command::Chart(args) => {
+            let mut parts = args.split_whitespace();
+            let room = parts.next().unwrap_or_default();
+            let mut typ = None;
+            let mut hours = CHART_DEFAULT_HOURS;
+            for part in parts {
+                match parse_chart_hours(part) {
+                    Some(h) => hours = h,
+                    None if typ.is_none() && !part.starts_with(|c: char| c.is_ascii_digit()) => typ = Some(part.to_lowercase()),
+                    None => {
+                        unlocked!(
+                            user_configs,
+                            configs,
+                            reply(
+                                replies,
+                                user_id,
+                                mode,
+                                i18n::message_with(lang, "chart_period", &[("value", part), ("max", &(max_history_hours() / 24).to_string())])
+                            )
+                        )?;
+                        return Ok(());
+                    }
+                }
+            }
+            let config = user_configs.get(&user_id.0).cloned();
+            unlocked!(user_configs, configs, send_chart(&bot, &charts, &history, config.as_ref(), user_id, room, typ.as_deref(), hours))?;
+        }
+
+        Command::WohnzimmerTdia => {
+            let config = user_configs.get(&user_id.0).cloned();
+            unlocked!(
+                user_configs,
+                configs,
+                send_chart(&bot, &charts, &history, config.as_ref(), user_id, "Wohnzimmer", Some("temperature"), CHART_DEFAULT_HOURS)
+            )?;
+        }
+
+        Command::WohnzimmerHdia => {
+            let config = user_configs.get(&user_id.0).cloned();
+            unlocked!(
+                user_configs,
+                configs,
+                send_chart(&bot, &charts, &history, config.as_ref(), user_id, "Wohnzimmer", Some("humidity"), CHART_DEFAULT_HOURS)
+            )?;
+        }
+
+        Command::WohnzimmerTmin(value) => {
+            unlocked!(
+                user_configs,
+                configs,
+                set_wohnzimmer_threshold(
+                    replies,
+                    user_id,
+                    &configs,
+                    SensorKind::Temperature,
+                    ThresholdDirection::Min,
+                    value,
+                    source,
+                    setter.clone(),
+                    storage.as_ref()
+                )
+            )?;
+        }
+
+        Command::WohnzimmerTmax(value) => {
+            unlocked!(
+                user_configs,
+                configs,
+                set_wohnzimmer_threshold(
+                    replies,
+                    user_id,
+                    &configs,
+                    SensorKind::Temperature,
+                    ThresholdDirection::Max,
+                    value,
+                    source,
+                    setter.clone(),
+                    storage.as_ref()
+                )
+            )?;
+        }
+
+        Command::WohnzimmerHmin(value) => {
+            unlocked!(
+                user_configs,
+                configs,
+                set_wohnzimmer_threshold(
+                    replies,
+                    user_id,
+                    &configs,
+                    SensorKind::Humidity,
+                    ThresholdDirection::Min,
+                    value,
+                    source,
+                    setter.clone(),
+                    storage.as_ref()
+                )
+            )?;
+        }
+
+        Command::WohnzimmerHmax(value) => {
+            unlocked!(
+                user_configs,
+                configs,
+                set_wohnzimmer_threshold(
+                    replies,
+                    user_id,
+                    &configs,
+                    SensorKind::Humidity,
+                    ThresholdDirection::Max,
+                    value,
+                    source,
+                    setter.clone(),
+                    storage.as_ref()
+                )
+            )?;
+        }
+
+        Command::Setmin(args) => {
+            unlocked!(
+                user_configs,
+                configs,
+                set_threshold(replies, user_id, &configs, args, ThresholdDirection::Min, source, setter.clone(), storage.as_ref())
+            )?;
+        }
+
+        Command::Setmax(args) => {
+            unlocked!(
+                user_configs,
+                configs,
+                set_threshold(replies, user_id, &configs, args, ThresholdDirection::Max, source, setter.clone(), storage.as_ref())
+            )?;
+        }
+
+        Command::CopyThresholds(args) => {
+            let text = match args.split_whitespace().collect::<Vec<_>>().as_slice() {
+                [from, to] => match (resolve_device_in(user_id.0, from), resolve_device_in(user_id.0, to)) {
+                    (Some(from), Some(to)) if from == to => i18n::message(lang, "copy_same_room").to_string(),
+                    (Some(from), Some(to)) => match unlocked!(user_configs, configs, device_kinds(user_id.0, &to, lang)) {
+                        Ok(_) => {
+                            let config = user_configs.entry(user_id.0).or_default();
+                            match copy_thresholds(config, &from, &to, setter.clone()) {
+                                Ok(overwritten) => {
+                                    let copied = i18n::message_with(lang, "copy_done", &[("room", &room_name(&from))]);
+                                    format!("{}\n{}", copied, format_device_thresholds(config, &to, &overwritten))
+                                }
+                                Err(err) => format!("❌ {}", err),
+                            }
+                        }
+                        Err(err) => err,
+                    },
+                    (None, _) => format!("❌ {}", unknown_room(lang, user_id.0, from)),
+                    (_, None) => format!("❌ {}", unknown_room(lang, user_id.0, to)),
+                },
+                _ => i18n::message(lang, "copy_usage").to_string(),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::ApplyDefault(room) => {
+            let room = room.trim();
+            let text = match resolve_device_in(user_id.0, room).filter(|_| !room.is_empty()) {
+                Some(device) => match unlocked!(user_configs, configs, device_kinds(user_id.0, &device, lang)) {
+                    Ok(kinds) => {
+                        let config = user_configs.entry(user_id.0).or_default();
+                        match apply_default(config, &device, kinds.as_deref(), source.clone(), setter.clone()) {
+                            Ok(overwritten) => {
+                                format!("{}\n{}", i18n::message(lang, "default_applied"), format_device_thresholds(config, &device, &overwritten))
+                            }
+                            Err(err) => format!("❌ {}", err),
+                        }
+                    }
+                    Err(err) => err,
+                },
+                None if room.is_empty() => i18n::message(lang, "apply_default_usage").to_string(),
+                None => format!("❌ {}", unknown_room(lang, user_id.0, room)),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Thresholds => {
+            let text = match user_configs.get(&user_id.0) {
+                Some(config)
+                    if !config.thresholds.is_empty() || !config.rates.is_empty() || !config.relations.is_empty() || config.profiles.active().is_some() =>
+                {
+                    format_thresholds(config, user_id.0)
+                }
+                _ => i18n::message(lang, "thresholds_none").to_string(),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text).parse_mode(ParseMode::Markdown))?;
+        }
+
+        Command::Schedule(spec) => {
+            let spec = spec.trim();
+            let config = user_configs.entry(user_id.0).or_default();
+
+            if spec.is_empty() {
+                unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message(lang, "schedule_usage")))?;
+            } else if spec.eq_ignore_ascii_case("off") {
+                config.report_schedule = None;
+                unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message(lang, "schedule_off")))?;
+            } else {
+                match spec.parse::<WeeklySchedule>() {
+                    Ok(schedule) => {
+                        let naechster = next_fire(&schedule, Utc::now()).map(|at| format_local(at, "%d.%m.%Y %H:%M")).unwrap_or_else(|| "–".into());
+                        let text = i18n::message_with(lang, "schedule_set", &[("schedule", &schedule.to_string()), ("next", &naechster)]);
+                        config.report_schedule = Some(schedule);
+                        unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+                    }
+                    Err(err) => {
+                        unlocked!(user_configs, configs, reply(replies, user_id, mode, format!("❌ {}", err.in_lang(lang))))?;
+                    }
+                }
+            }
+        }
+
+        Command::QuietHours(spec) => {
+            let spec = spec.trim();
+            let config = user_configs.entry(user_id.0).or_default();
+
+            if spec.eq_ignore_ascii_case("off") {
+                config.quiet_hours = None;
+                unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message(lang, "quiet_off")))?;
+            } else {
+                // "22:00 07:00" und "22:00-07:00" sind beide erlaubt
+                match spec.split_whitespace().collect::<Vec<_>>().join("-").parse::<TimeWindow>() {
+                    Ok(window) => {
+                        config.quiet_hours = Some(window);
+                        unlocked!(
+                            user_configs,
+                            configs,
+                            reply(replies, user_id, mode, i18n::message_with(lang, "quiet_set", &[("window", &window.to_string())]))
+                        )?;
+                    }
+                    Err(err) => {
+                        unlocked!(
+                            user_configs,
+                            configs,
+                            reply(replies, user_id, mode, format!("❌ {}\n{}", err.in_lang(lang), i18n::message(lang, "quiet_usage")))
+                        )?;
+                    }
+                }
+            }
+        }
+
+        // Beim Wechsel geht Gesammeltes sofort raus, damit nichts im Puffer
+        // liegen bleibt oder unter dem neuen Intervall verspätet kommt
+        Command::AlertMode(spec) => {
+            let words: Vec<String> = spec.split_whitespace().map(str::to_lowercase).collect();
+            let alert_mode = match words.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+                ["instant" | "sofort"] => Some(AlertMode::Instant),
+                ["digest" | "sammeln", interval @ ..] if interval.len() <= 1 => interval
+                    .first()
+                    .map_or(Some(chrono::Duration::minutes(DEFAULT_DIGEST_MINUTES)), |s| parse_duration(s))
+                    .filter(|interval| *interval >= chrono::Duration::minutes(MIN_DIGEST_MINUTES))
+                    .map(|interval| AlertMode::Digest { minutes: interval.num_minutes() }),
+                _ => None,
+            };
+            match alert_mode {
+                Some(alert_mode) => {
+                    let config = user_configs.entry(user_id.0).or_default();
+                    let pending = std::mem::take(&mut config.digest.entries);
+                    config.alert_mode = alert_mode;
+                    config.digest.due = alert_mode.interval().map(|interval| Utc::now() + interval);
+                    if !pending.is_empty() {
+                        unlocked!(user_configs, configs, reply(replies, user_id, mode, format_alert_digest(&pending, tz)).parse_mode(ParseMode::Markdown))?;
+                    }
+                    let text = match alert_mode {
+                        AlertMode::Instant => i18n::message(lang, "alert_mode_instant").to_string(),
+                        AlertMode::Digest { minutes } => i18n::message_with(lang, "alert_mode_digest", &[("interval", &format_duration(minutes * 60))]),
+                    };
+                    unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+                }
+                None => {
+                    let current = user_configs.get(&user_id.0).map(|c| c.alert_mode).unwrap_or_default();
+                    unlocked!(
+                        user_configs,
+                        configs,
+                        reply(
+                            replies,
+                            user_id,
+                            mode,
+                            i18n::message_with(
+                                lang,
+                                "alert_mode_usage",
+                                &[("min", &MIN_DIGEST_MINUTES.to_string()), ("current", &format_alert_mode(current, lang))]
+                            )
+                        )
+                    )?;
+                }
+            }
+        }
+
+        Command::Notifications(spec) => {
+            let text = match spec.parse::<NotificationMode>() {
+                Ok(notifications) => {
+                    user_configs.entry(user_id.0).or_default().notifications = notifications;
+                    let set = i18n::message_with(lang, "notifications_set", &[("mode", &notifications.to_string())]);
+                    format!("{} {}", set, notification_summary(notifications, lang))
+                }
+                Err(err) if spec.trim().is_empty() => {
+                    let current = i18n::message_with(lang, "notifications_current", &[("mode", &mode.to_string())]);
+                    format!("{}\n{} {}", err.in_lang(lang), current, notification_summary(mode, lang))
+                }
+                Err(err) => err.in_lang(lang),
+            };
+            // Schon im neuen Modus antworten
+            let now_mode = user_configs.get(&user_id.0).map(|c| c.notifications).unwrap_or_default();
+            unlocked!(user_configs, configs, reply(replies, user_id, now_mode, text))?;
+        }
+
+        Command::MuteAll(spec) => {
+            let text = mute_chat(user_configs.entry(user_id.0).or_default(), &spec).unwrap_or_else(|| i18n::message(lang, "mute_all_usage").to_string());
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        // /mute 2h wie /mute-all, /mute <raum> 2h wie /snooze
+        Command::Mute(spec) => {
+            let config = user_configs.entry(user_id.0).or_default();
+            let text = match spec.split_whitespace().count() {
+                1 => mute_chat(config, &spec),
+                _ => snooze_room(config, user_id.0, &spec),
+            };
+            let text = text.unwrap_or_else(|| i18n::message(lang, "mute_usage").to_string());
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Unmute => {
+            let now = Utc::now();
+            let text = match user_configs.get_mut(&user_id.0) {
+                Some(config) => {
+                    let snoozed = config.snoozed.values().filter(|snooze| snooze.until > now).count();
+                    config.snoozed.clear();
+                    match (config.muted_until.is_some(), snoozed) {
+                        (true, 0) => unmute_summary(config),
+                        (true, n) => format!("{} {}", unmute_summary(config), i18n::message_with(lang, "unmute_rooms", &[("count", &n.to_string())])),
+                        (false, 0) => i18n::message(lang, "unmute_none").to_string(),
+                        (false, n) => i18n::message_with(lang, "unmute_snoozes", &[("count", &n.to_string())]),
+                    }
+                }
+                None => i18n::message(lang, "unmute_none").to_string(),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Settings => {
+            let text = format_settings(user_configs.get(&user_id.0).unwrap_or(&UserConfig::default()));
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text).parse_mode(ParseMode::Markdown))?;
+        }
+
+        Command::Diff => {
+            let sensor_data = match unlocked!(user_configs, configs, fetch_sensor_data_for(user_id.0)) {
+                Ok(sensor_data) => sensor_data,
+                Err(err) => {
+                    unlocked!(user_configs, configs, reply(replies, user_id, mode, format!("❌ {}", err.text().in_lang(lang))))?;
+                    return Ok(());
+                }
+            };
+            let current = Snapshot::capture(&sensor_data, Utc::now());
+            let config = user_configs.entry(user_id.0).or_default();
+            let text = match config.last_viewed.replace(current.clone()) {
+                Some(previous) => format_diff(&previous, &current, lang, config.units),
+                None => i18n::message(lang, "diff_first").to_string(),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text).parse_mode(ParseMode::Markdown))?;
+        }
+
+        Command::Undo => {
+            let config = user_configs.entry(user_id.0).or_default();
+            let text = match config.undo.pop() {
+                Some(entry) => {
+                    let key = (entry.device_id, entry.key);
+                    let text = i18n::message_with(
+                        lang,
+                        "undo_done",
+                        &[
+                            ("direction", &key.1.direction.as_str().to_uppercase()),
+                            ("type", type_label_in(lang, key.1.kind.as_str()).0),
+                            ("room", &room_name(&key.0)),
+                        ],
+                    );
+                    match entry.previous {
+                        Some(previous) => config.thresholds.insert(key, previous),
+                        None => config.thresholds.remove(&key),
+                    };
+                    text
+                }
+                None => i18n::message(lang, "undo_none").to_string(),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::ApiToken => {
+            // Neues Token macht alte Links ungültig
+            let token = random_token();
+            user_configs.entry(user_id.0).or_default().api_token = Some(token.clone());
+            let base = &settings().http_public_url;
+            let text = if !cfg!(feature = "http-api") {
+                i18n::message(lang, "http_not_built").to_string()
+            } else if settings().http_addr.is_none() {
+                i18n::message(lang, "http_disabled").to_string()
+            } else {
+                i18n::message_with(lang, "api_token", &[("base", base.trim_end_matches('/')), ("token", &token)])
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Note(args) => {
+            let args = args.trim();
+            let config = user_configs.entry(user_id.0).or_default();
+            let text = match args.split_once(char::is_whitespace) {
+                None if args.is_empty() && !config.notes.is_empty() => {
+                    let mut text = format!("{}\n", i18n::message(lang, "notes_title"));
+                    for (device, note) in &config.notes {
+                        text.push_str(&format!("{}: {}\n", room_name(device), note));
+                    }
+                    text
+                }
+                None => i18n::message(lang, "note_usage").to_string(),
+                Some((room, note)) => {
+                    let device = resolve_device_in(user_id.0, room).unwrap_or_default();
+                    let note = note.trim().trim_matches(['"', '„', '“', '”']).trim();
+                    if device.is_empty() {
+                        unknown_room(lang, user_id.0, room)
+                    } else if note.eq_ignore_ascii_case("clear") {
+                        match config.notes.remove(&device) {
+                            Some(_) => i18n::message_with(lang, "note_removed", &[("room", &room_name(&device))]),
+                            None => i18n::message_with(lang, "note_missing", &[("room", &room_name(&device))]),
+                        }
+                    } else if note.is_empty() {
+                        i18n::message(lang, "note_empty").to_string()
+                    } else if note.chars().count() > MAX_NOTE_CHARS {
+                        i18n::message_with(lang, "note_too_long", &[("max", &MAX_NOTE_CHARS.to_string())])
+                    } else {
+                        config.notes.insert(device.clone(), note.to_string());
+                        i18n::message_with(lang, "note_saved", &[("room", &room_name(&device))])
+                    }
+                }
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::BackupNow => {
+            let text = if settings().admin_chat != Some(user_id.0) {
+                admin_only.to_string()
+            } else {
+                match &settings().backup_dir {
+                    None => i18n::message(lang, "backup_disabled").to_string(),
+                    Some(dir) => match storage::backup(&storage, dir, settings().backup_keep).await {
+                        Ok(path) => i18n::message_with(lang, "backup_done", &[("path", &path.display().to_string())]),
+                        Err(err) => i18n::message_with(lang, "backup_failed", &[("error", &err.to_string())]),
+                    },
+                }
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Records(room) => {
+            let room = room.trim();
+            let tenant = tenant_of(user_id.0);
+            let text = match rooms().find_in(tenant.as_deref(), room).filter(|_| !room.is_empty()) {
+                Some(found) => format_records(&*records.lock().await, tenant.as_deref(), Some(&found.device), lang),
+                None if room.is_empty() => format_records(&*records.lock().await, tenant.as_deref(), None, lang),
+                None => escape_markdown(&unknown_room(lang, user_id.0, room)),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text).parse_mode(ParseMode::Markdown))?;
+        }
+
+        Command::Debug(tenant) => {
+            let tenant = tenant.trim();
+            let text = if settings().admin_chat != Some(user_id.0) {
+                admin_only.to_string()
+            } else if tenant.is_empty() {
+                let files = storage.file_sizes().into_iter().chain(database_file()).collect();
+                let snapshot = introspect::DebugSnapshot {
+                    user_configs: user_configs.len(),
+                    threshold_flags: flags.lock().await.len(),
+                    history: history.lock().await.entries_per_device(),
+                    queued: OUTBOX.get().map(Outbox::queued),
+                    files,
+                    rss_kib: introspect::rss_kib(),
+                    last_iteration: bot_status().last_iteration,
+                };
+                let routing = format_routing(&ROUTE_CHECKS.lock().unwrap_or_else(|e| e.into_inner()), lang);
+                unlocked!(
+                    user_configs,
+                    configs,
+                    reply(replies, user_id, mode, format!("{}\n{}\n\n{}", i18n::message(lang, "debug_title"), snapshot.format(), escape_markdown(&routing)))
+                        .parse_mode(ParseMode::Markdown)
+                )?;
+                storage.save_users(&user_configs);
+                return Ok(());
+            } else {
+                match rooms().tenants().iter().find(|t| t.id.eq_ignore_ascii_case(tenant)) {
+                    Some(tenant) => format_tenant(tenant, lang),
+                    None => {
+                        let tenants = rooms().tenants().iter().map(|t| t.id.as_str()).collect::<Vec<_>>().join(", ");
+                        i18n::message_with(lang, "unknown_tenant", &[("tenant", tenant), ("tenants", &tenants)])
+                    }
+                }
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Setimage(spec) => {
+            let spec = spec.trim();
+            let (room, off) = match spec.rsplit_once(' ') {
+                Some((room, off)) if off.eq_ignore_ascii_case("off") => (room.trim(), true),
+                _ => (spec, false),
+            };
+            let text = if settings().admin_chat != Some(user_id.0) {
+                admin_only.to_string()
+            } else {
+                match rooms().find(room).filter(|_| !room.is_empty()) {
+                    None => {
+                        let all = rooms().rooms().iter().map(|r| r.name.as_str()).collect::<Vec<_>>().join(", ");
+                        i18n::message_with(lang, "unknown_room", &[("room", room), ("rooms", &all)])
+                    }
+                    Some(found) if off => match room_images::remove(&found.device) {
+                        Ok(true) => i18n::message_with(lang, "image_removed", &[("room", &found.name)]),
+                        Ok(false) => i18n::message_with(lang, "image_missing", &[("room", &found.name)]),
+                        Err(err) => format!("❌ {}", err),
+                    },
+                    Some(found) => {
+                        PENDING_IMAGES.lock().unwrap_or_else(|e| e.into_inner()).insert(user_id.0, (found.device.clone(), std::time::Instant::now()));
+                        i18n::message_with(lang, "image_send", &[("room", &found.name)])
+                    }
+                }
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Inject(args) => {
+            let text = if settings().admin_chat != Some(user_id.0) {
+                warn!("/inject von {} abgelehnt (kein Admin)", redact::chat(user_id.0));
+                admin_only.to_string()
+            } else {
+                match inject::parse(&args, Utc::now().timestamp(), parse_duration) {
+                    Ok(mut injection) => {
+                        injection.reading.device_id = resolve_device(&injection.reading.device_id);
+                        let reading = &injection.reading;
+                        let text = i18n::message_with(
+                            lang,
+                            if injection.store { "injected" } else { "injected_unsaved" },
+                            &[
+                                ("room", &room_name(&reading.device_id)),
+                                ("type", type_label_in(lang, reading.sensor_type.as_str()).0),
+                                ("value", &format!("{:.1}", reading.value)),
+                                ("time", &format_timestamp(reading.timestamp, "%d.%m.%Y %H:%M")),
+                            ],
+                        );
+                        inject::push(injection);
+                        text
+                    }
+                    Err(err) => err.in_lang(lang),
+                }
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::TestAlarm(spec) => {
+            let parts: Vec<&str> = spec.split_whitespace().collect();
+            let (device, kind) = match parts.as_slice() {
+                [] => (None, None),
+                [device] => (Some(*device), None),
+                [device, kind] => (Some(*device), Some(SensorKind::from(*kind))),
+                _ => (None, None),
+            };
+            let device = device.map(|d| resolve_device_in(user_id.0, d).ok_or(d));
+            let text = match (parts.len() > 2, device) {
+                (true, _) => i18n::message(lang, "test_alarm_usage").to_string(),
+                (_, Some(Err(device))) => format!("❌ {}", unknown_room(lang, user_id.0, device)),
+                (_, device) => {
+                    let device = device.and_then(Result::ok);
+                    // Testlauf auf einer Kopie der Einstellungen mit eigenen
+                    // Alarm-Zuständen: echte Zustände, Verlauf und Episoden
+                    // bleiben unberührt
+                    let config = user_configs.get(&user_id.0).cloned().unwrap_or_default();
+                    let local = local_time();
+                    let mut candidates: Vec<((String, ThresholdKey), f64)> = config
+                        .thresholds
+                        .iter()
+                        .filter(|((d, k), _)| device.as_ref().is_none_or(|device| device == d) && kind.as_ref().is_none_or(|kind| *kind == k.kind))
+                        .filter_map(|(id, _)| config.active_threshold(id, local).map(|entry| (id.clone(), entry.value)))
+                        .collect();
+                    candidates.sort_by_key(|((d, k), _)| (d.clone(), k.to_string()));
+                    match candidates.into_iter().next() {
+                        None => i18n::message(lang, "test_alarm_none").to_string(),
+                        Some(((device_id, key), threshold)) => {
+                            let outward = if key.direction.is_min() { -1.0 } else { 1.0 };
+                            let margin = config.hysteresis_for(&(device_id.clone(), key.clone())).max(0.0) + 1.0;
+                            let now = Utc::now().timestamp();
+                            let reading =
+                                |value: f64, timestamp: i64| SensorData { device_id: device_id.clone(), sensor_type: key.kind.clone(), value, timestamp };
+                            let mut flags = monitor::Flags::new();
+                            let alarm =
+                                monitor::evaluate_threshold(user_id.0, &config, &reading(threshold + outward, now - 60), key.direction, &mut flags, local);
+                            let recovered =
+                                monitor::evaluate_threshold(user_id.0, &config, &reading(threshold - outward * margin, now), key.direction, &mut flags, local);
+                            match (alarm, recovered) {
+                                (Some(alarm), Some(recovered)) if alarm.kind == EventKind::Alarm && recovered.kind == EventKind::Recovered => {
+                                    let (text, _, severity) = alert_message(Some(&config), &alarm, &History::default(), false);
+                                    let episode = Episode::resume_or_start(None, alarm.direction, alarm.timestamp, alarm.value);
+                                    let recovery = format_recovery(&recovered, &episode, config.lang, config.units, chat_timezone(Some(&config)));
+                                    let messages = [
+                                        (format!("🧪 TEST\n{}", text), config.notifications.silent(MessageKind::Alert(severity))),
+                                        (format!("🧪 TEST\n{}", recovery), config.notifications.silent(MessageKind::Alert(Severity::Info))),
+                                    ];
+                                    // Über die Warteschlange wie echte Warnungen, aber nur an diesen
+                                    // Chat und vor anstehenden Hintergrund-Nachrichten
+                                    match OUTBOX.get() {
+                                        Some(outbox) => {
+                                            for (text, silent) in messages {
+                                                outbox.send_interactive(user_id, text, silent);
+                                            }
+                                        }
+                                        None => {
+                                            for (text, _) in messages {
+                                                unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+                                            }
+                                        }
+                                    }
+                                    i18n::message_with(
+                                        lang,
+                                        "test_alarm_sent",
+                                        &[
+                                            ("type", type_label_in(lang, key.kind.as_str()).0),
+                                            ("room", &room_name(&device_id)),
+                                            ("direction", key.direction.as_str()),
+                                            ("severity", severity.label(lang)),
+                                        ],
+                                    )
+                                }
+                                _ => i18n::message(lang, "test_alarm_silent").to_string(),
+                            }
+                        }
+                    }
+                }
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Clear(spec) => {
+            if !spec.trim().eq_ignore_ascii_case("all") {
+                unlocked!(
+                    user_configs,
+                    configs,
+                    reply(replies, user_id, mode, i18n::message_with(lang, "clear_usage", &[("days", &archive::UNDO_WINDOW_DAYS.to_string())]))
+                )?;
+            } else {
+                match user_configs.get(&user_id.0) {
+                    Some(config) => {
+                        let preview = i18n::message_with(
+                            lang,
+                            "clear_preview",
+                            &[("summary", &config_summary(config, lang)), ("days", &archive::UNDO_WINDOW_DAYS.to_string())],
+                        );
+                        unlocked!(user_configs, configs, ask_confirmation(replies, &msg, mode, lang, PendingChange::ClearAll, preview))?;
+                    }
+                    None => {
+                        unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message(lang, "clear_nothing")))?;
+                    }
+                }
+            }
+        }
+
+        Command::UndoClear => {
+            let mut archive = storage.load_archive();
+            let text = match archive.take(user_id.0, Utc::now()) {
+                Restore::Restored(archived) => {
+                    let current = user_configs.remove(&user_id.0).unwrap_or_default();
+                    user_configs.insert(user_id.0, current.restore_from(*archived));
+                    info!("Konfiguration von {} wiederhergestellt", redact::chat(user_id.0));
+                    i18n::message(lang, "undo_clear_done")
+                }
+                Restore::Expired => i18n::message(lang, "undo_clear_expired"),
+                Restore::Missing => i18n::message(lang, "undo_clear_missing"),
+            };
+            storage.save_archive(&archive);
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Backup => {
+            // Nur Einstellungen: laufende Alarme, Rückgängig-Liste und API-Token gehören nicht in eine Datei
+            let config = user_configs.get(&user_id.0).cloned().unwrap_or_default().with_state_of(UserConfig::default());
+            let backup = ConfigBackup { version: BACKUP_VERSION, chat_id: user_id.0, created_at: Utc::now(), config };
+            match serde_json::to_vec_pretty(&backup) {
+                Ok(json) => {
+                    let name = format!("telegrambot-{}-{}.json", user_id.0, format_local_in(backup.created_at, "%Y%m%d", tz));
+                    unlocked!(
+                        user_configs,
+                        configs,
+                        bot.send_document(user_id, teloxide::types::InputFile::memory(json).file_name(name))
+                            .caption(i18n::message_with(lang, "backup_caption", &[("summary", &backup_summary(&backup.config, lang))]))
+                            .disable_notification(mode.silent(MessageKind::Reply))
+                    )?;
+                }
+                Err(err) => {
+                    warn!("Sicherung für {} nicht erstellt: {}", redact::chat(user_id.0), err);
+                    unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message(lang, "backup_not_created")))?;
+                }
+            }
+        }
+
+        Command::Restore => match msg.reply_to_message().and_then(|reply| reply.document()) {
+            Some(document) => unlocked!(user_configs, configs, offer_restore(&bot, &msg, document, tz, mode, lang))?,
+            None => {
+                unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message(lang, "restore_usage")))?;
+            }
+        },
+
+        Command::Forgetme => {
+            let mut preview = i18n::message(lang, "forget_preview").to_string();
+            if let Some(config) = user_configs.get(&user_id.0) {
+                preview.push_str(&format!("\n{}", config_summary(config, lang)));
+            }
+            if storage.load_archive().contains(user_id.0) {
+                preview.push_str(&format!("\n{}", i18n::message(lang, "forget_archive")));
+            }
+            unlocked!(user_configs, configs, ask_confirmation(replies, &msg, mode, lang, PendingChange::Forget, preview))?;
+        }
+
+        Command::RoomImages(spec) => {
+            let config = user_configs.entry(user_id.0).or_default();
+            let text = match spec.trim().to_lowercase().as_str() {
+                "on" => {
+                    config.room_images = true;
+                    i18n::message(lang, "room_images_on")
+                }
+                "off" => {
+                    config.room_images = false;
+                    i18n::message(lang, "room_images_off")
+                }
+                _ => i18n::message(lang, "room_images_usage"),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Profile(spec) => {
+            let parts: Vec<&str> = spec.split_whitespace().collect();
+            let config = user_configs.entry(user_id.0).or_default();
+            let before = config.profiles.active.clone();
+            let text = match parts.as_slice() {
+                [] | ["list"] => format_profiles(&config.profiles, lang),
+                ["create", name] => match profiles::parse_name(name, lang) {
+                    Err(err) => err,
+                    Ok(name) if config.profiles.profiles.contains_key(&name) => i18n::message_with(lang, "profile_exists", &[("name", &name)]),
+                    Ok(_) if config.profiles.profiles.len() >= profiles::MAX_PROFILES => {
+                        i18n::message_with(lang, "profile_limit", &[("max", &profiles::MAX_PROFILES.to_string())])
+                    }
+                    Ok(name) => {
+                        let text = i18n::message_with(lang, "profile_created", &[("name", &name)]);
+                        config.profiles.profiles.insert(name, Default::default());
+                        text
+                    }
+                },
+                ["delete", name] => match config.profiles.profiles.remove(&name.to_lowercase()) {
+                    Some(_) => {
+                        if config.profiles.manual.as_deref() == Some(name.to_lowercase().as_str()) {
+                            config.profiles.manual = None;
+                        }
+                        i18n::message_with(lang, "profile_deleted", &[("name", &name.to_lowercase())])
+                    }
+                    None => i18n::message_with(lang, "profile_missing", &[("name", name)]),
+                },
+                ["set", name, device, sensor_type, direction, value] => {
+                    let kind = SensorKind::from(*sensor_type);
+                    let number = value.replace(',', ".").parse::<f64>().ok().filter(|v| v.is_finite());
+                    match (
+                        config.profiles.profiles.get_mut(&name.to_lowercase()),
+                        resolve_device_in(user_id.0, device),
+                        direction.parse::<ThresholdDirection>(),
+                    ) {
+                        (None, _, _) => i18n::message_with(lang, "profile_create_first", &[("name", name)]),
+                        (_, None, _) => format!("❌ {}", unknown_room(lang, user_id.0, device)),
+                        (_, _, Err(err)) => format!("❌ {}", err.in_lang(lang)),
+                        (Some(profile), Some(device), Ok(direction)) => {
+                            let key = (device, ThresholdKey::new(kind.clone(), direction));
+                            let label = format!("{} {} {}", type_label_in(lang, kind.as_str()).0, room_name(&key.0), direction);
+                            let name = name.to_lowercase();
+                            if value.eq_ignore_ascii_case("off") {
+                                profile.thresholds.insert(key, None);
+                                i18n::message_with(lang, "profile_value_off", &[("threshold", &label), ("name", &name)])
+                            } else {
+                                match number.map(|n| units.parse(&kind, n)) {
+                                    None => i18n::message(lang, "profile_value_invalid").to_string(),
+                                    Some(v) if thresholds::plausible_range(&kind).is_some_and(|(lo, hi)| !(lo..=hi).contains(&v)) => i18n::message_with(
+                                        lang,
+                                        "profile_value_implausible",
+                                        &[("value", value), ("type", type_label_in(lang, kind.as_str()).0)],
+                                    ),
+                                    Some(v) => {
+                                        profile.thresholds.insert(key, Some(v));
+                                        i18n::message_with(
+                                            lang,
+                                            "profile_value_set",
+                                            &[
+                                                ("threshold", &label),
+                                                ("name", &name),
+                                                ("value", &format!("{:.1}", units.show(&kind, v))),
+                                                ("unit", unit_in(units, kind.as_str())),
+                                            ],
+                                        )
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                ["activate", "auto"] => {
+                    config.profiles.manual = None;
+                    i18n::message(lang, "profile_auto").to_string()
+                }
+                ["activate", name] => match profiles::parse_name(name, lang) {
+                    Ok(name) if config.profiles.profiles.contains_key(&name) => {
+                        let text = i18n::message_with(lang, "profile_activated", &[("name", &name)]);
+                        config.profiles.manual = Some(name);
+                        text
+                    }
+                    _ => i18n::message_with(lang, "profile_missing", &[("name", name)]),
+                },
+                ["schedule", name, window] => {
+                    let name = name.to_lowercase();
+                    let window = if window.eq_ignore_ascii_case("off") { Ok(None) } else { window.parse::<TimeWindow>().map(Some) };
+                    let overlapping = window.as_ref().ok().copied().flatten().and_then(|w| config.profiles.overlapping(&name, &w)).map(str::to_string);
+                    match (window, overlapping, config.profiles.profiles.get_mut(&name)) {
+                        (_, _, None) => i18n::message_with(lang, "profile_missing", &[("name", &name)]),
+                        (Err(err), _, _) => format!("❌ {}", err.in_lang(lang)),
+                        (_, Some(other), _) => i18n::message_with(lang, "profile_overlap", &[("name", &other)]),
+                        (Ok(None), _, Some(profile)) => {
+                            profile.window = None;
+                            i18n::message_with(lang, "profile_unscheduled", &[("name", &name)])
+                        }
+                        (Ok(Some(window)), _, Some(profile)) => {
+                            profile.window = Some(window);
+                            i18n::message_with(lang, "profile_scheduled", &[("name", &name), ("window", &window.to_string())])
+                        }
+                    }
+                }
+                _ => profiles::usage(lang).to_string(),
+            };
+            // Sofort umschalten und neu prüfen, nicht erst beim nächsten Takt
+            config.profiles.switch(local_time());
+            if config.profiles.active != before {
+                tokio::spawn(refresh::request());
+            }
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::EscalateTo(spec) => {
+            let text = match handover::parse(&spec, lang, |s| parse_duration(s).map(|d| d.num_minutes())) {
+                Err(err) => err,
+                Ok(handover) if handover.chat_id == user_id.0 => i18n::message(lang, "handover_self").to_string(),
+                Ok(handover) if !user_configs.get(&handover.chat_id).is_some_and(|c| c.escalations_from.contains(&user_id.0)) => {
+                    i18n::message_with(lang, "handover_not_accepted", &[("chat", &handover.chat_id.to_string()), ("own", &user_id.0.to_string())])
+                }
+                Ok(handover) => {
+                    user_configs.entry(user_id.0).or_default().escalate_to = Some(handover);
+                    i18n::message_with(lang, "handover_active", &[("handover", &format_handover(&handover, lang))])
+                }
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::AcceptEscalationsFrom(spec) => {
+            let parts: Vec<&str> = spec.split_whitespace().collect();
+            let (owner, off) = match parts.as_slice() {
+                [owner] => (owner.parse::<i64>().ok(), false),
+                [owner, off] if off.eq_ignore_ascii_case("off") => (owner.parse::<i64>().ok(), true),
+                _ => (None, false),
+            };
+            let text = match owner {
+                None => i18n::message(lang, "accept_usage").to_string(),
+                Some(owner) if owner == user_id.0 => i18n::message(lang, "handover_self").to_string(),
+                Some(owner) if off => {
+                    let removed = user_configs.entry(user_id.0).or_default().escalations_from.remove(&owner);
+                    // Eine bestehende Weitergabe hierher endet damit
+                    if let Some(config) = user_configs.get_mut(&owner).filter(|c| c.escalate_to.is_some_and(|h| h.chat_id == user_id.0)) {
+                        config.escalate_to = None;
+                        if let Some(outbox) = OUTBOX.get() {
+                            outbox.send(ChatId(owner), i18n::message_with(config.lang, "handover_revoked", &[("chat", &user_id.0.to_string())]));
+                        }
+                    }
+                    if removed {
+                        i18n::message_with(lang, "accept_off", &[("chat", &owner.to_string())])
+                    } else {
+                        i18n::message_with(lang, "accept_missing", &[("chat", &owner.to_string())])
+                    }
+                }
+                Some(owner) => {
+                    user_configs.entry(user_id.0).or_default().escalations_from.insert(owner);
+                    i18n::message_with(lang, "accept_on", &[("chat", &owner.to_string()), ("own", &user_id.0.to_string())])
+                }
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Escalation(spec) => {
+            let config = user_configs.entry(user_id.0).or_default();
+            let text = match (spec.trim().to_lowercase().as_str(), config.escalate_to) {
+                ("off", Some(_)) => {
+                    config.escalate_to = None;
+                    i18n::message(lang, "handover_ended").to_string()
+                }
+                ("off", None) | ("", None) => i18n::message(lang, "handover_none").to_string(),
+                ("", Some(handover)) => i18n::message_with(lang, "handover_active", &[("handover", &format_handover(&handover, lang))]),
+                _ => i18n::message(lang, "escalation_usage").to_string(),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::FleetReport(spec) => {
+            let text = if settings().admin_chat != Some(user_id.0) {
+                admin_only
+            } else {
+                let config = user_configs.entry(user_id.0).or_default();
+                match spec.trim().to_lowercase().as_str() {
+                    "on" => {
+                        config.fleet_report = true;
+                        i18n::message(lang, "fleet_report_on")
+                    }
+                    "off" => {
+                        config.fleet_report = false;
+                        i18n::message(lang, "fleet_report_off")
+                    }
+                    "now" => {
+                        FLEET_REPORT_NOW.notify_one();
+                        i18n::message(lang, "fleet_report_now")
+                    }
+                    _ => i18n::message(lang, "fleet_report_usage"),
+                }
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::SetInterval(spec) => {
+            let spec = spec.trim();
+            let seconds = spec.parse::<u64>().ok().or_else(|| parse_duration(spec).map(|d| d.num_seconds() as u64));
+            let text = if settings().admin_chat != Some(user_id.0) {
+                admin_only.to_string()
+            } else {
+                match seconds {
+                    None => i18n::message_with(lang, "interval_usage", &[("current", &format_poll_interval(poll_interval()))]),
+                    Some(seconds) if seconds < settings::MIN_POLL_INTERVAL_SECONDS => {
+                        i18n::message_with(lang, "interval_too_short", &[("min", &settings::MIN_POLL_INTERVAL_SECONDS.to_string())])
+                    }
+                    Some(seconds) if seconds > 24 * 60 * 60 => i18n::message(lang, "interval_too_long").to_string(),
+                    Some(seconds) => {
+                        let previous = POLL_INTERVAL.swap(seconds, std::sync::atomic::Ordering::Relaxed);
+                        POLL_INTERVAL_CHANGED.notify_one();
+                        info!("Abfrageintervall von {} s auf {} s geändert", previous, seconds);
+                        i18n::message_with(lang, "interval_set", &[("interval", &format_poll_interval(seconds)), ("previous", &format_poll_interval(previous))])
+                    }
+                }
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::WeatherLocation(spec) => {
+            let spec = spec.trim();
+            let current = weather::location().map_or(i18n::message(lang, "off").to_string(), |location| location.to_string());
+            let text = if settings().admin_chat != Some(user_id.0) {
+                admin_only.to_string()
+            } else if spec.is_empty() {
+                i18n::message_with(lang, "weather_usage", &[("current", &current)])
+            } else if spec.eq_ignore_ascii_case("off") {
+                weather::set_location(None);
+                i18n::message(lang, "weather_off").to_string()
+            } else {
+                match spec.parse::<weather::Location>() {
+                    Ok(location) => {
+                        weather::set_location(Some(location));
+                        info!("Standort für das Wetter: {} (vorher {})", location, current);
+                        let check = match unlocked!(user_configs, configs, weather::current()) {
+                            Some(now) => i18n::message_with(
+                                lang,
+                                "weather_now",
+                                &[("temperature", &format!("{:.1}", now.temperature)), ("humidity", &format!("{:.0}", now.humidity))],
+                            ),
+                            None => i18n::message(lang, "weather_unavailable").to_string(),
+                        };
+                        i18n::message_with(lang, "weather_set", &[("location", &location.to_string()), ("check", &check)])
+                    }
+                    Err(err) => format!("❌ {}", err.in_lang(lang)),
+                }
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::ReloadRooms => {
+            let text = if settings().admin_chat != Some(user_id.0) { admin_only.to_string() } else { reload_rooms(lang) };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Selftest => {
+            let text = if settings().admin_chat != Some(user_id.0) {
+                admin_only.to_string()
+            } else {
+                selftest::summary(&unlocked!(user_configs, configs, self_test(Some(bot.clone()), storage.clone()).run()), lang)
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::PurgeUser(chat) => {
+            let text = if settings().admin_chat != Some(user_id.0) {
+                admin_only.to_string()
+            } else {
+                match chat.trim().parse::<i64>() {
+                    Err(_) => i18n::message(lang, "purge_usage").to_string(),
+                    Ok(chat_id) if chat_id == user_id.0 => i18n::message(lang, "purge_self").to_string(),
+                    Ok(chat_id) => {
+                        let mut flags = flags.lock().await;
+                        match purge_chat(chat_id, &mut user_configs, &mut flags, storage.as_ref()) {
+                            Some(thresholds) => {
+                                info!("Daten von {} auf Anweisung des Admins gelöscht", redact::chat(chat_id));
+                                i18n::message_with(lang, "purge_done", &[("chat", &chat_id.to_string()), ("count", &thresholds.to_string())])
+                            }
+                            None => i18n::message_with(lang, "purge_missing", &[("chat", &chat_id.to_string())]),
+                        }
+                    }
+                }
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Ignore(_) | Command::Unignore(_) if settings().admin_chat != Some(user_id.0) => {
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, admin_only))?;
+        }
+
+        Command::Ignore(device) if device.trim().is_empty() => {
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message(lang, "ignore_usage")))?;
+        }
+
+        Command::Ignore(device) => {
+            let device = resolve_device(device.trim());
+            if ignored().contains(&device) {
+                unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message_with(lang, "ignore_already", &[("room", &room_name(&device))])))?;
+            } else {
+                let by = msg
+                    .from()
+                    .map(|user| user.username.clone().map(|name| format!("@{}", name)).unwrap_or_else(|| user.first_name.clone()))
+                    .unwrap_or_else(|| user_id.0.to_string());
+                let affected = threshold_holders(&user_configs, &device, user_id.0);
+                let thresholds: usize = affected.iter().map(|(_, count)| count).sum();
+                let preview = i18n::message_with(
+                    lang,
+                    "ignore_preview",
+                    &[("room", &room_name(&device)), ("count", &thresholds.to_string()), ("chats", &affected.len().to_string())],
+                );
+                unlocked!(user_configs, configs, ask_confirmation(replies, &msg, mode, lang, PendingChange::Ignore { device, by }, preview))?;
+            }
+        }
+
+        Command::Unignore(device) => {
+            let device = resolve_device(device.trim());
+            let removed = ignored().remove(&device);
+            let text = if removed {
+                storage.save_ignored(&ignored());
+                info!("Gerät {} wird wieder überwacht", device);
+                i18n::message_with(lang, "unignore_done", &[("room", &room_name(&device))])
+            } else {
+                i18n::message_with(lang, "unignore_missing", &[("room", &room_name(&device))])
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Allow(_) | Command::Deny(_) if settings().admin_chat != Some(user_id.0) => {
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, admin_only))?;
+        }
+
+        Command::Allow(spec) if spec.trim().is_empty() => {
+            let listed = &settings().allowed_chats;
+            let mut text = i18n::message(lang, "access_title").to_string();
+            if listed.is_empty() && !access().restricts() {
+                text.push_str(&format!("\n{}", i18n::message(lang, "access_open")));
+            }
+            if !listed.is_empty() {
+                let ids: Vec<String> = listed.iter().map(i64::to_string).collect();
+                text.push_str(&format!("\nALLOWED_CHAT_IDS: {}", ids.join(", ")));
+            }
+            for (chat_id, grant) in access().iter() {
+                let line = i18n::message_with(
+                    lang,
+                    "since_by",
+                    &[("what", &chat_id.to_string()), ("since", &format_local(grant.since, "%d.%m.%Y %H:%M")), ("by", &grant.by)],
+                );
+                text.push_str(&format!("\n{} {}", if grant.allowed { "✅" } else { "⛔" }, line));
+            }
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Allow(spec) | Command::Deny(spec) if spec.trim().parse::<i64>().is_err() => {
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message(lang, "allow_usage")))?;
+        }
+
+        Command::Allow(ref spec) | Command::Deny(ref spec) => {
+            let allow = matches!(cmd, Command::Allow(_));
+            let chat_id: i64 = spec.trim().parse().unwrap_or_default();
+            let text = if chat_id == user_id.0 {
+                i18n::message(lang, "allow_admin").to_string()
+            } else {
+                let by = msg
+                    .from()
+                    .map(|user| user.username.clone().map(|name| format!("@{}", name)).unwrap_or_else(|| user.first_name.clone()))
+                    .unwrap_or_else(|| user_id.0.to_string());
+                let changed = access().set(chat_id, allow, by, Utc::now());
+                if changed {
+                    storage.save_access(&access());
+                    info!("Chat {} {}", redact::chat(chat_id), if allow { "freigegeben" } else { "gesperrt" });
+                }
+                let key = match (allow, changed) {
+                    (true, true) => "allow_done",
+                    (true, false) => "allow_already",
+                    (false, true) => "deny_done",
+                    (false, false) => "deny_already",
+                };
+                i18n::message_with(lang, key, &[("chat", &chat_id.to_string())])
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Broadcast(_) | Command::Users if settings().admin_chat != Some(user_id.0) => {
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, admin_only))?;
+        }
+
+        Command::Broadcast(text) if text.trim().is_empty() => {
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message(lang, "broadcast_usage")))?;
+        }
+
+        Command::Broadcast(text) => {
+            // Gesperrte Chats bekommen auch hierüber nichts, der eigene hat den Text schon
+            let targets: Vec<i64> = known_chats().iter().map(|(&chat_id, _)| chat_id).filter(|&chat_id| chat_id != user_id.0 && admitted(chat_id)).collect();
+            unlocked!(
+                user_configs,
+                configs,
+                reply(replies, user_id, mode, i18n::message_with(lang, "broadcast_started", &[("count", &targets.len().to_string())]))
+            )?;
+            info!("Rundnachricht an {} Chats", targets.len());
+            // Im Hintergrund, damit die Konfiguration währenddessen nicht gesperrt ist
+            let bot = bot.clone();
+            let storage = storage.clone();
+            let messenger = messenger.clone();
+            tokio::spawn(async move {
+                let report = broadcast(&bot, &targets, text.trim(), storage.as_ref(), lang).await;
+                if let Err(err) = reply(messenger.as_ref(), user_id, mode, report).await {
+                    warn!("Bericht zur Rundnachricht nicht zugestellt: {}", err);
+                }
+            });
+        }
+
+        Command::Users => {
+            for text in known_chat_list(&user_configs, tz, lang) {
+                unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+            }
+        }
+
+        Command::Ignored => {
+            let text = if settings().admin_chat != Some(user_id.0) {
+                admin_only.to_string()
+            } else if ignored().is_empty() {
+                i18n::message(lang, "ignored_none").to_string()
+            } else {
+                let mut text = i18n::message(lang, "ignored_title").to_string();
+                for (device, entry) in ignored().iter() {
+                    let what = format!("{} ({})", room_name(device), device);
+                    let line =
+                        i18n::message_with(lang, "since_by", &[("what", &what), ("since", &format_local(entry.since, "%d.%m.%Y %H:%M")), ("by", &entry.by)]);
+                    text.push_str(&format!("\n{}", line));
+                }
+                text
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Snooze(spec) => {
+            let spec = spec.trim();
+            let now = Utc::now();
+            let config = user_configs.entry(user_id.0).or_default();
+            let text = if spec.eq_ignore_ascii_case("clear") {
+                let active = config.snoozed.values().filter(|snooze| snooze.until > now).count();
+                config.snoozed.clear();
+                match active {
+                    0 => i18n::message(lang, "snooze_none").to_string(),
+                    n => i18n::message_with(lang, "unmute_snoozes", &[("count", &n.to_string())]),
+                }
+            } else {
+                snooze_room(config, user_id.0, spec).unwrap_or_else(|| i18n::message(lang, "snooze_usage").to_string())
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::ClearThreshold(spec) => {
+            let parts: Vec<&str> = spec.split_whitespace().collect();
+            let text = match parts.as_slice() {
+                [device, sensor_type, direction] => match (resolve_device_in(user_id.0, device), direction.parse::<ThresholdDirection>()) {
+                    (None, _) => format!("❌ {}", unknown_room(lang, user_id.0, device)),
+                    (_, Err(err)) => format!("❌ {}", err.in_lang(lang)),
+                    (Some(device), Ok(direction)) => {
+                        let key = ThresholdKey::new(SensorKind::from(*sensor_type), direction);
+                        let (typ, _) = type_label_in(lang, key.kind.as_str());
+                        let removed = user_configs.get_mut(&user_id.0).is_some_and(|config| remove_threshold(config, (device.clone(), key.clone())));
+                        if removed {
+                            flags.lock().await.remove(&(user_id.0, device.clone(), key.clone()));
+                            i18n::message_with(
+                                lang,
+                                "threshold_removed",
+                                &[("direction", &direction.as_str().to_uppercase()), ("type", typ), ("room", &room_name(&device))],
+                            )
+                        } else {
+                            i18n::message_with(
+                                lang,
+                                "threshold_not_set",
+                                &[("type", typ), ("room", &room_name(&device)), ("direction", &direction.as_str().to_uppercase())],
+                            )
+                        }
+                    }
+                },
+                _ => i18n::message(lang, "clear_threshold_usage").to_string(),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::ClearAll => {
+            let keys: Vec<(String, ThresholdKey)> = user_configs.get(&user_id.0).map(|config| config.thresholds.keys().cloned().collect()).unwrap_or_default();
+            let text = if keys.is_empty() {
+                i18n::message(lang, "clear_all_nothing").to_string()
+            } else {
+                let config = user_configs.entry(user_id.0).or_default();
+                let mut flags = flags.lock().await;
+                for key in &keys {
+                    remove_threshold(config, key.clone());
+                    flags.remove(&(user_id.0, key.0.clone(), key.1.clone()));
+                }
+                let key = if keys.len() == 1 { "clear_all_one" } else { "clear_all_many" };
+                i18n::message_with(lang, key, &[("count", &keys.len().to_string()), ("max", &MAX_UNDO.to_string())])
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Configure => {
+            let readings = match unlocked!(user_configs, configs, status_readings(user_id.0)) {
+                Ok((readings, _)) => readings,
+                Err(err) => {
+                    unlocked!(user_configs, configs, reply(replies, user_id, mode, format!("❌ {}", err.text().in_lang(lang))))?;
+                    return Ok(());
+                }
+            };
+            let mut devices: Vec<String> = Vec::new();
+            for reading in &readings {
+                if !devices.contains(&reading.device_id) && !ignored().contains(&reading.device_id) {
+                    devices.push(reading.device_id.clone());
+                }
+            }
+            if devices.is_empty() {
+                unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message(lang, "configure_no_devices")))?;
+                return Ok(());
+            }
+            let choices = devices.into_iter().map(|device_id| (room_name(&device_id).to_string(), configure::Step::Device { device_id })).collect();
+            unlocked!(
+                user_configs,
+                configs,
+                reply(replies, user_id, mode, i18n::message(lang, "configure_device")).reply_markup(configure::keyboard(Utc::now().timestamp(), choices, lang))
+            )?;
+        }
+
+        Command::Hysteresis(spec) => {
+            let parts: Vec<&str> = spec.split_whitespace().collect();
+            let text = match parts.as_slice() {
+                [device, sensor_type, direction, value] => match (resolve_device_in(user_id.0, device), direction.parse::<ThresholdDirection>()) {
+                    (None, _) => format!("❌ {}", unknown_room(lang, user_id.0, device)),
+                    (_, Err(err)) => format!("❌ {}", err.in_lang(lang)),
+                    (Some(device), Ok(direction)) => {
+                        let key = (device, ThresholdKey::new(SensorKind::from(*sensor_type), direction));
+                        let typ = type_label_in(lang, key.1.kind.as_str()).0;
+                        let einheit = unit_in(units, key.1.kind.as_str());
+                        let config = user_configs.entry(user_id.0).or_default();
+                        if !config.thresholds.contains_key(&key) {
+                            i18n::message_with(
+                                lang,
+                                "threshold_not_set",
+                                &[("type", typ), ("room", &room_name(&key.0)), ("direction", &direction.as_str().to_uppercase())],
+                            )
+                        } else if value.eq_ignore_ascii_case("default") {
+                            config.hysteresis.remove(&key);
+                            i18n::message_with(
+                                lang,
+                                "hysteresis_default",
+                                &[
+                                    ("type", typ),
+                                    ("room", &room_name(&key.0)),
+                                    ("value", &format!("{:.1}", units.show_delta(&key.1.kind, settings().hysteresis))),
+                                    ("unit", einheit),
+                                ],
+                            )
+                        } else {
+                            match value.replace(',', ".").parse::<f64>() {
+                                Ok(hysteresis) if hysteresis.is_finite() && hysteresis >= 0.0 => {
+                                    let text = i18n::message_with(
+                                        lang,
+                                        "hysteresis_set",
+                                        &[("type", typ), ("room", &room_name(&key.0)), ("value", &format!("{:.1}", hysteresis)), ("unit", einheit)],
+                                    );
+                                    let hysteresis = units.parse_delta(&key.1.kind, hysteresis);
+                                    config.hysteresis.insert(key, hysteresis);
+                                    text
+                                }
+                                _ => i18n::message(lang, "hysteresis_invalid").to_string(),
+                            }
+                        }
+                    }
+                },
+                _ => i18n::message(lang, "hysteresis_usage").to_string(),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Repeat(spec) => {
+            let parts: Vec<&str> = spec.split_whitespace().collect();
+            let text = match parts.as_slice() {
+                [device, sensor_type, direction, interval] => match (resolve_device_in(user_id.0, device), direction.parse::<ThresholdDirection>()) {
+                    (None, _) => format!("❌ {}", unknown_room(lang, user_id.0, device)),
+                    (_, Err(err)) => format!("❌ {}", err.in_lang(lang)),
+                    (Some(device), Ok(direction)) => {
+                        let key = (device, ThresholdKey::new(SensorKind::from(*sensor_type), direction));
+                        let (typ, _) = type_label_in(lang, key.1.kind.as_str());
+                        let config = user_configs.entry(user_id.0).or_default();
+                        let min = chrono::Duration::seconds(poll_interval() as i64);
+                        if !config.thresholds.contains_key(&key) {
+                            i18n::message_with(
+                                lang,
+                                "threshold_not_set",
+                                &[("type", typ), ("room", &room_name(&key.0)), ("direction", &direction.as_str().to_uppercase())],
+                            )
+                        } else if interval.eq_ignore_ascii_case("off") {
+                            config.repeat.remove(&key);
+                            i18n::message_with(lang, "repeat_off", &[("type", typ), ("room", &room_name(&key.0))])
+                        } else {
+                            match parse_duration(interval) {
+                                Some(every) if every >= min && every <= chrono::Duration::days(1) => {
+                                    let text = i18n::message_with(
+                                        lang,
+                                        "repeat_set",
+                                        &[("type", typ), ("room", &room_name(&key.0)), ("interval", &format_duration(every.num_seconds()))],
+                                    );
+                                    config.repeat.insert(key, every.num_minutes());
+                                    text
+                                }
+                                _ => i18n::message_with(lang, "repeat_interval", &[("min", &format_duration(min.num_seconds()))]),
+                            }
+                        }
+                    }
+                },
+                _ => i18n::message(lang, "repeat_usage").to_string(),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Rate(spec) => {
+            let usage = i18n::message(lang, "rate_usage");
+            let parts: Vec<&str> = spec.split_whitespace().collect();
+            let text = match parts.as_slice() {
+                [device, sensor_type, rest @ ..] if !rest.is_empty() && rest.len() <= 2 => match resolve_device_in(user_id.0, device) {
+                    None => format!("❌ {}", unknown_room(lang, user_id.0, device)),
+                    Some(device) => {
+                        let key = (device, SensorKind::from(*sensor_type));
+                        let typ = type_label_in(lang, key.1.as_str()).0;
+                        let einheit = unit_in(units, key.1.as_str());
+                        let config = user_configs.entry(user_id.0).or_default();
+                        match rest {
+                            [off] if off.eq_ignore_ascii_case("off") => match config.rates.remove(&key) {
+                                Some(_) => i18n::message_with(lang, "rate_off", &[("type", typ), ("room", &room_name(&key.0))]),
+                                None => i18n::message_with(lang, "rate_missing", &[("type", typ), ("room", &room_name(&key.0))]),
+                            },
+                            [delta, window] => {
+                                let delta = delta.replace(',', ".").parse::<f64>().ok().filter(|d| d.is_finite() && *d != 0.0);
+                                let minutes = parse_duration(window).map(|d| d.num_minutes());
+                                match (delta, minutes) {
+                                    (None, _) => i18n::message(lang, "rate_delta_invalid").to_string(),
+                                    (_, Some(minutes)) if !(1..=rate::MAX_WINDOW_MINUTES).contains(&minutes) => {
+                                        i18n::message(lang, "rate_window_range").to_string()
+                                    }
+                                    (_, None) => i18n::message(lang, "rate_window_invalid").to_string(),
+                                    (Some(delta), Some(minutes)) => {
+                                        config.rates.insert(key.clone(), RateRule { delta: units.parse_delta(&key.1, delta), minutes });
+                                        i18n::message_with(
+                                            lang,
+                                            if delta < 0.0 { "rate_set_falling" } else { "rate_set_rising" },
+                                            &[
+                                                ("type", typ),
+                                                ("room", &room_name(&key.0)),
+                                                ("window", &format_duration(minutes * 60)),
+                                                ("change", &format!("{:.1}", delta.abs())),
+                                                ("unit", einheit),
+                                            ],
+                                        )
+                                    }
+                                }
+                            }
+                            _ => usage.to_string(),
+                        }
+                    }
+                },
+                _ => usage.to_string(),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Set(spec) => {
+            let usage = i18n::message(lang, "set_relative_usage");
+            let parts: Vec<&str> = spec.split_whitespace().collect();
+            let text = match parts.as_slice() {
+                [device, sensor_type, direction, rest @ ..] if rest.len() == 1 || rest.len() == 3 => {
+                    match (resolve_device_in(user_id.0, device), relative::parse_direction(direction)) {
+                        (None, _) => format!("❌ {}", unknown_room(lang, user_id.0, device)),
+                        (_, None) => usage.to_string(),
+                        (Some(device), Some(direction)) => {
+                            let key = (device, ThresholdKey::new(SensorKind::from(*sensor_type), direction));
+                            let typ = type_label_in(lang, key.1.kind.as_str()).0;
+                            let einheit = unit_in(units, key.1.kind.as_str());
+                            let config = user_configs.entry(user_id.0).or_default();
+                            match rest {
+                                [off] if off.eq_ignore_ascii_case("off") => match config.relations.remove(&key) {
+                                    Some(_) => i18n::message_with(
+                                        lang,
+                                        "relation_off",
+                                        &[("type", typ), ("room", &room_name(&key.0)), ("direction", &direction.to_string())],
+                                    ),
+                                    None => i18n::message_with(
+                                        lang,
+                                        "relation_missing",
+                                        &[("type", typ), ("room", &room_name(&key.0)), ("direction", &direction.to_string())],
+                                    ),
+                                },
+                                [other_device, other_type, offset] => {
+                                    let offset = offset.replace(',', ".").parse::<f64>().ok().filter(|o| o.is_finite());
+                                    match (resolve_device_in(user_id.0, other_device), offset) {
+                                        (None, _) => format!("❌ {}", unknown_room(lang, user_id.0, other_device)),
+                                        (_, None) => i18n::message(lang, "relation_offset_invalid").to_string(),
+                                        (Some(other_device), _) if other_device == key.0 && SensorKind::from(*other_type) == key.1.kind => {
+                                            i18n::message(lang, "relation_self").to_string()
+                                        }
+                                        (Some(other_device), Some(offset)) => {
+                                            let rule = RelativeRule {
+                                                other_device,
+                                                other_kind: SensorKind::from(*other_type),
+                                                offset: units.parse_delta(&key.1.kind, offset),
+                                            };
+                                            let text = i18n::message_with(
+                                                lang,
+                                                if direction == ThresholdDirection::Max { "relation_set_above" } else { "relation_set_below" },
+                                                &[
+                                                    ("type", typ),
+                                                    ("room", &room_name(&key.0)),
+                                                    ("other_type", type_label_in(lang, rule.other_kind.as_str()).0),
+                                                    ("other_room", &room_name(&rule.other_device)),
+                                                    ("offset", &format!("{:+.1}", offset)),
+                                                    ("unit", einheit),
+                                                ],
+                                            );
+                                            config.relations.insert(key, rule);
+                                            text
+                                        }
+                                    }
+                                }
+                                _ => usage.to_string(),
+                            }
+                        }
+                    }
+                }
+                _ => usage.to_string(),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Watering(spec) => {
+            let config = user_configs.entry(user_id.0).or_default();
+            let parts: Vec<&str> = spec.split_whitespace().collect();
+            let text = match parts.as_slice() {
+                [off] if off.eq_ignore_ascii_case("off") => {
+                    config.watering = None;
+                    i18n::message(lang, "watering_off").to_string()
+                }
+                [above, rest @ ..] if rest.len() <= 1 => {
+                    let above = above.replace(',', ".").parse::<f64>().ok().filter(|v| v.is_finite());
+                    let days = match rest.first() {
+                        Some(days) => days.parse::<u32>().ok().filter(|d| (1..=MAX_WATERING_DAYS).contains(d)),
+                        None => Some(3),
+                    };
+                    match (above, days) {
+                        (Some(above), Some(days)) => {
+                            config.watering = Some(Watering { above, days });
+                            let outdoor = rooms().rooms_in(tenant_of(user_id.0).as_deref()).any(|r| r.outdoor);
+                            let mut text = i18n::message_with(
+                                lang,
+                                "watering_on",
+                                &[("time", &OUTDOOR_CHECK_AT.format("%H:%M").to_string()), ("days", &days.to_string()), ("above", &format!("{:.1}", above))],
+                            );
+                            if !outdoor {
+                                text.push_str(&format!("\n{}", i18n::message(lang, "watering_no_outdoor")));
+                            }
+                            text
+                        }
+                        _ => i18n::message_with(lang, "watering_usage", &[("max", &MAX_WATERING_DAYS.to_string())]),
+                    }
+                }
+                _ => i18n::message_with(lang, "watering_usage", &[("max", &MAX_WATERING_DAYS.to_string())]),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Snoozes => {
+            let text = match user_configs.get(&user_id.0) {
+                Some(config) => format_snoozes(config, Utc::now()),
+                None => i18n::message(lang, "snoozes_none").to_string(),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Health => {
+            let latencies = user_configs.get(&user_id.0).map(delivery_latencies).unwrap_or_default();
+            let mut text =
+                format_health(&*uptime.lock().await, &*escalation.lock().await, tenant_of(user_id.0).as_deref(), &latencies, Utc::now().timestamp(), lang);
+            if settings().admin_chat == Some(user_id.0) {
+                let panics = HANDLER_PANICS.load(std::sync::atomic::Ordering::Relaxed);
+                if panics > 0 {
+                    text.push_str(&format!("{}\n", i18n::message_with(lang, "health_panics", &[("count", &panics.to_string())])));
+                }
+                for (tenant, count) in bot_status().skipped.iter().filter(|(_, count)| **count > 0) {
+                    let tenant = escape_markdown(tenant.as_deref().unwrap_or(i18n::message(lang, "main_tenant")));
+                    text.push_str(&format!("{}\n", i18n::message_with(lang, "health_skipped", &[("tenant", &tenant), ("count", &count.to_string())])));
+                }
+            }
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text).parse_mode(ParseMode::Markdown))?;
+        }
+
+        Command::Language(spec) => {
+            let text = match spec.parse::<Lang>() {
+                Ok(chosen) => {
+                    user_configs.entry(user_id.0).or_default().lang = chosen;
+                    i18n::message(chosen, "language_set").to_string()
+                }
+                Err(_) => i18n::message(lang, "language_usage").to_string(),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Units(spec) => {
+            let text = match spec.parse::<TempUnit>() {
+                Ok(chosen) => {
+                    user_configs.entry(user_id.0).or_default().units = chosen;
+                    i18n::message_with(lang, "units_set", &[("unit", chosen.symbol())])
+                }
+                Err(_) => i18n::message_with(lang, "units_usage", &[("unit", units.symbol())]),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Battery(spec) => {
+            let current = user_configs.get(&user_id.0).map_or(settings().battery_low, UserConfig::battery_low);
+            let text = match spec.trim() {
+                "" => {
+                    let mut text = if current > 0.0 {
+                        i18n::message_with(lang, "battery_threshold", &[("value", &format!("{:.0}", current))])
+                    } else {
+                        i18n::message(lang, "battery_disabled").to_string()
+                    };
+                    let readings = latest().as_ref().map(|snapshot| visible_readings(user_id.0, &snapshot.readings)).unwrap_or_default();
+                    let batteries: Vec<&SensorData> = readings.iter().filter(|r| r.sensor_type == SensorKind::Battery).collect();
+                    if batteries.is_empty() {
+                        text.push_str(&format!("\n{}", i18n::message(lang, "battery_no_devices")));
+                    }
+                    for reading in batteries {
+                        text.push_str(&format!(
+                            "\n{} {}: {:.0} %",
+                            if reading.value < current { "🪫" } else { "🔋" },
+                            room_name(&reading.device_id),
+                            reading.value
+                        ));
+                    }
+                    text
+                }
+                "off" => {
+                    user_configs.entry(user_id.0).or_default().battery_low = Some(0.0);
+                    i18n::message(lang, "battery_off").to_string()
+                }
+                "default" => {
+                    user_configs.entry(user_id.0).or_default().battery_low = None;
+                    i18n::message_with(lang, "battery_default", &[("value", &format!("{:.0}", settings().battery_low))])
+                }
+                spec => match spec.trim_end_matches('%').trim().replace(',', ".").parse::<f64>() {
+                    Ok(percent) if percent > 0.0 && percent < 100.0 => {
+                        user_configs.entry(user_id.0).or_default().battery_low = Some(percent);
+                        i18n::message_with(lang, "battery_set", &[("value", &format!("{:.0}", percent))])
+                    }
+                    _ => i18n::message(lang, "battery_usage").to_string(),
+                },
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Live(spec) => match spec.trim() {
+            "on" if user_configs.get(&user_id.0).is_some_and(|c| c.live.is_some()) => {
+                unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message(lang, "live_running")))?;
+            }
+            "on" => match unlocked!(user_configs, configs, status_readings(user_id.0)) {
+                Ok((readings, _)) => {
+                    let config = user_configs.entry(user_id.0).or_default();
+                    let readings = live_readings(user_id.0, config, readings);
+                    let trends = status_trends(&*history.lock().await, &readings);
+                    let text = live_text(&readings, &trends, config);
+                    let (message_id, pinned) = unlocked!(user_configs, configs, post_live(&bot, user_id, &text))?;
+                    user_configs.entry(user_id.0).or_default().live = Some(LiveStatus { message_id: message_id.0, values: live_values(&readings) });
+                    storage.save_users(&user_configs);
+                    if !pinned {
+                        unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message(lang, "live_not_pinned")))?;
+                    }
+                }
+                Err(err) => {
+                    unlocked!(user_configs, configs, reply(replies, user_id, mode, format!("❌ {}", err.text().in_lang(lang))))?;
+                }
+            },
+            "off" => {
+                let text = match user_configs.get_mut(&user_id.0).and_then(|c| c.live.take()) {
+                    Some(live) => {
+                        if let Err(err) = unlocked!(user_configs, configs, bot.unpin_chat_message(user_id).message_id(MessageId(live.message_id))) {
+                            warn!("Live-Übersicht in {} nicht gelöst: {}", redact::chat(user_id.0), err);
+                        }
+                        i18n::message(lang, "live_off")
+                    }
+                    None => i18n::message(lang, "live_none"),
+                };
+                unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+            }
+            _ => {
+                unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message(lang, "live_usage")))?;
+            }
+        },
+
+        Command::Timezone(spec) => {
+            let text = match spec.trim() {
+                "" => i18n::message_with(lang, "timezone_current", &[("zone", &timezone_name(tz))]),
+                "default" => {
+                    user_configs.entry(user_id.0).or_default().timezone = None;
+                    i18n::message_with(lang, "timezone_default", &[("zone", &timezone_name(chat_timezone(None)))])
+                }
+                name => match name.parse::<Tz>() {
+                    Ok(zone) => {
+                        user_configs.entry(user_id.0).or_default().timezone = Some(zone);
+                        i18n::message_with(lang, "timezone_set", &[("zone", zone.name()), ("time", &format_local_in(Utc::now(), "%H:%M", Some(zone)))])
+                    }
+                    Err(_) => i18n::message_with(lang, "timezone_unknown", &[("zone", name)]),
+                },
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::Timeformat(spec) => match spec.parse::<TimeFormat>() {
+            Ok(times) => {
+                user_configs.entry(user_id.0).or_default().time_format = times;
+                let example = format_reading_time(Utc::now().timestamp() - 180, lang.datetime_format(), tz, times, lang);
+                unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message_with(lang, "timeformat_set", &[("example", &example)])))?;
+            }
+            Err(err) => {
+                unlocked!(user_configs, configs, reply(replies, user_id, mode, err.in_lang(lang)))?;
+            }
+        },
+
+        Command::Layout(spec) | Command::Format(spec) => match spec.parse::<Layout>() {
+            Ok(layout) => {
+                user_configs.entry(user_id.0).or_default().layout = layout;
+                let key = if layout == Layout::Table { "layout_table" } else { "layout_classic" };
+                unlocked!(user_configs, configs, reply(replies, user_id, mode, i18n::message(lang, key)))?;
+            }
+            Err(err) => {
+                unlocked!(user_configs, configs, reply(replies, user_id, mode, err.in_lang(lang)))?;
+            }
+        },
+
+        Command::Schedules => {
+            let text = match user_configs.get(&user_id.0).and_then(|c| c.report_schedule.as_ref()) {
+                Some(schedule) => format_schedule(schedule, lang),
+                None => i18n::message(lang, "schedules_none").to_string(),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text).parse_mode(ParseMode::Markdown))?;
+        }
+
+        Command::SubscribeDaily(spec) => {
+            let text = match parse_daily(&spec, lang) {
+                Ok(schedule) => {
+                    let config = user_configs.entry(user_id.0).or_default();
+                    // Ist die Uhrzeit heute schon vorbei, kommt die erste am nächsten Termin
+                    if let Some(passed) = last_fire(&schedule, Utc::now()) {
+                        config.last_summary = Some(config.last_summary.map_or(passed, |last| last.max(passed)));
+                    }
+                    let text = i18n::message_with(lang, "daily_on", &[("schedule", &schedule.to_string())]);
+                    config.daily_summary = Some(schedule);
+                    text
+                }
+                Err(err) => format!("{}\n{}", err, i18n::message(lang, "daily_usage")),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::UnsubscribeDaily => {
+            let text = match user_configs.get_mut(&user_id.0).and_then(|c| c.daily_summary.take()) {
+                Some(_) => i18n::message(lang, "daily_off"),
+                None => i18n::message(lang, "daily_none"),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::SubscribeWeekly(spec) => {
+            let text = match parse_weekly(&spec, lang) {
+                Ok(schedule) => {
+                    let config = user_configs.entry(user_id.0).or_default();
+                    let shown = schedule.to_string();
+                    // Ist der Termin diese Woche schon vorbei, kommt der erste nächste Woche
+                    if let Some(passed) = last_weekly_fire(&schedule, Utc::now()) {
+                        config.last_weekly = Some(config.last_weekly.map_or(passed, |last| last.max(passed)));
+                    }
+                    config.weekly_report = Some(schedule);
+                    i18n::message_with(lang, "weekly_on", &[("schedule", &shown)])
+                }
+                Err(err) => format!("{}\n{}", err, i18n::message(lang, "weekly_usage")),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+
+        Command::UnsubscribeWeekly => {
+            let text = match user_configs.get_mut(&user_id.0).and_then(|c| c.weekly_report.take()) {
+                Some(_) => i18n::message(lang, "weekly_off"),
+                None => i18n::message(lang, "weekly_none"),
+            };
+            unlocked!(user_configs, configs, reply(replies, user_id, mode, text))?;
+        }
+    }
+
+    storage.save_users(&user_configs);
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn set_threshold(
+    replies: &dyn Messenger,
+    user_id: ChatId,
+    configs: &UserConfigs,
+    args: ThresholdArgs,
+    direction: ThresholdDirection,
+    source: String,
+    setter: Option<Setter>,
+    storage: &dyn Store,
+) -> ResponseResult<()> {
+    let (mode, lang) = configs.lock().await.get(&user_id.0).map(|c| (c.notifications, c.lang)).unwrap_or_default();
+    let Some(device) = resolve_device_in(user_id.0, &args.device) else {
+        reply(replies, user_id, mode, format!("❌ {}", unknown_room(lang, user_id.0, &args.device))).await?;
+        return Ok(());
+    };
+    // Nur prüfbar, wenn die Quelle antwortet; sonst meldet sich später die
+    // Prüfung auf Schwellen ohne Messwerte
+    if let Ok(readings) = fetch_sensor_data_for(user_id.0).await
+        && !readings.is_empty()
+        && !readings.iter().any(|r| r.device_id == device && r.sensor_type == args.sensor_type)
+    {
+        reply(
+            replies,
+            user_id,
+            mode,
+            i18n::message_with(
+                lang,
+                "threshold_no_readings",
+                &[("room", &room_name(&device)), ("kind", args.sensor_type.as_str()), ("devices", &known_devices(&readings))],
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
+    let key = (device.clone(), ThresholdKey::new(args.sensor_type.clone(), direction));
+    let mut user_configs = configs.lock().await;
+    let config = user_configs.entry(user_id.0).or_default();
+    let units = config.units;
+    // Eingabe in der Einheit des Chats, gespeichert wird in °C
+    let value = units.parse(&args.sensor_type, args.value);
+
+    if let Err(err) =
+        validate_threshold(config, &key, value, args.window).and_then(|()| change_threshold(config, key.clone(), |s| s.set(value, args.window, source)))
+    {
+        drop(user_configs);
+        reply(replies, user_id, mode, format!("❌ {}", err)).await?;
+        return Ok(());
+    }
+    // Stufe und Text gelten für die Schwelle, nicht je Zeitfenster; ohne
+    // Angabe bleibt, was schon eingestellt ist
+    if !args.style.is_empty() {
+        config.alert_styles.insert(key.clone(), args.style.clone());
+    }
+    record_setter(config, key, setter);
+    drop(user_configs);
+
+    let symbol = if direction.is_min() { "🔻" } else { "🔺" };
+    let mut text = format!(
+        "{} {}",
+        symbol,
+        i18n::message_with(
+            lang,
+            "threshold_set",
+            &[
+                ("direction", &direction.as_str().to_uppercase()),
+                ("type", type_label_in(lang, args.sensor_type.as_str()).0),
+                ("room", &room_name(&device)),
+                ("value", &format!("{:.1}", args.value)),
+                ("unit", unit_in(units, args.sensor_type.as_str())),
+            ],
+        )
+    );
+    if let Some(window) = args.window {
+        text.push_str(&format!(" {}", i18n::message_with(lang, "threshold_window", &[("window", &window.to_string())])));
+    }
+    if let Some(severity) = args.style.severity {
+        text.push_str(&format!("\n{}", i18n::message_with(lang, "threshold_severity", &[("severity", severity.label(lang))])));
+    }
+    if let Some(custom) = &args.style.text {
+        text.push_str(&format!("\n💬 {}", custom));
+    }
+    reply(replies, user_id, mode, text).await?;
+    check_delivery(replies, user_id, configs, storage).await
+}
+
+// Die alten Wohnzimmer-Befehle setzen nur den Standardwert ohne Zeitfenster
+#[allow(clippy::too_many_arguments)]
+pub async fn set_wohnzimmer_threshold(
+    replies: &dyn Messenger,
+    user_id: ChatId,
+    configs: &UserConfigs,
+    kind: SensorKind,
+    direction: ThresholdDirection,
+    value: f64,
+    source: String,
+    setter: Option<Setter>,
+    storage: &dyn Store,
+) -> ResponseResult<()> {
+    let key = wohnzimmer_key(user_id.0, kind.clone(), direction);
+    let mut user_configs = configs.lock().await;
+    let config = user_configs.entry(user_id.0).or_default();
+    let mode = config.notifications;
+    let units = config.units;
+    let lang = config.lang;
+    let shown = value;
+    let value = units.parse(&kind, shown);
+
+    if let Err(err) = validate_threshold(config, &key, value, None) {
+        drop(user_configs);
+        reply(replies, user_id, mode, format!("❌ {}", err)).await?;
+        return Ok(());
+    }
+    change_threshold(config, key.clone(), |s| {
+        s.set_default(value, source);
+        Ok(())
+    })
+    .ok();
+    record_setter(config, key, setter);
+    drop(user_configs);
+
+    let symbol = if direction.is_min() { "🔻" } else { "🔺" };
+    let text = i18n::message_with(
+        lang,
+        "threshold_set",
+        &[
+            ("direction", &direction.as_str().to_uppercase()),
+            ("type", type_label_in(lang, kind.as_str()).0),
+            ("room", "Wohnzimmer"),
+            ("value", &format!("{:.1}", shown)),
+            ("unit", unit_in(units, kind.as_str())),
+        ],
+    );
+    reply(replies, user_id, mode, format!("{} {}", symbol, text)).await?;
+    check_delivery(replies, user_id, configs, storage).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status_argument(text: &str) -> Option<String> {
+        match Command::parse(text, "sensorbot") {
+            Ok(Command::Status(room)) => Some(room),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn status_parses_with_and_without_a_room() {
+        assert_eq!(status_argument("/status").as_deref(), Some(""));
+        assert_eq!(status_argument("/status@sensorbot").as_deref(), Some(""));
+        assert_eq!(status_argument("/status wohnzimmer").as_deref(), Some("wohnzimmer"));
+        assert_eq!(status_argument("/status Wohnzimmer oben").as_deref(), Some("Wohnzimmer oben"));
+    }
+}
@@ -0,0 +1,84 @@
+use crate::i18n::{self, Lang};
+use crate::sensor::{SensorKind, ThresholdDirection};
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+
+// Telegram erlaubt höchstens 64 Byte Callback-Daten
+const MAX_CALLBACK_BYTES: usize = 64;
+const PREFIX: &str = "cfg";
+// Danach zählen Klicks auf alte Tastaturen von /configure nicht mehr
+pub const TIMEOUT_SECONDS: i64 = 10 * 60;
+
+// Ein Schritt von /configure. Der bisher gewählte Stand steckt vollständig
+// in den Callback-Daten, gespeichert wird erst die Eingabe des Werts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    Device { device_id: String },
+    Kind { device_id: String, kind: SensorKind },
+    Direction { device_id: String, kind: SensorKind, direction: ThresholdDirection },
+    Cancel,
+}
+
+// Klick mit dem Startzeitpunkt des Dialogs (Unix-Sekunden)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Click {
+    pub started: i64,
+    pub step: Step,
+}
+
+fn encode(started: i64, step: &Step) -> String {
+    match step {
+        Step::Device { device_id } => format!("{}:{}:d:{}", PREFIX, started, device_id),
+        Step::Kind { device_id, kind } => format!("{}:{}:k:{}:{}", PREFIX, started, kind, device_id),
+        Step::Direction { device_id, kind, direction } => format!("{}:{}:m:{}:{}:{}", PREFIX, started, kind, direction, device_id),
+        Step::Cancel => format!("{}:{}:x", PREFIX, started),
+    }
+}
+
+fn cancel_row(started: i64, lang: Lang) -> Vec<InlineKeyboardButton> {
+    vec![InlineKeyboardButton::callback(i18n::message(lang, "configure_cancel"), encode(started, &Step::Cancel))]
+}
+
+// Nur "Abbrechen", solange auf den Wert gewartet wird
+pub fn cancel_keyboard(started: i64, lang: Lang) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![cancel_row(started, lang)])
+}
+
+// Zwei Buttons je Zeile, darunter "Abbrechen". Schritte, deren Daten zu lang
+// würden (sehr lange Geräte-IDs), fallen weg.
+pub fn keyboard(started: i64, choices: Vec<(String, Step)>, lang: Lang) -> InlineKeyboardMarkup {
+    let buttons: Vec<InlineKeyboardButton> = choices
+        .into_iter()
+        .map(|(label, step)| (label, encode(started, &step)))
+        .filter(|(_, data)| data.len() <= MAX_CALLBACK_BYTES)
+        .map(|(label, data)| InlineKeyboardButton::callback(label, data))
+        .collect();
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = buttons.chunks(2).map(|row| row.to_vec()).collect();
+    rows.push(cancel_row(started, lang));
+    InlineKeyboardMarkup::new(rows)
+}
+
+pub fn parse(data: &str) -> Option<Click> {
+    let mut parts = data.splitn(3, ':');
+    if parts.next()? != PREFIX {
+        return None;
+    }
+    let started = parts.next()?.parse().ok()?;
+    let rest = parts.next()?;
+    let (op, args) = rest.split_once(':').unwrap_or((rest, ""));
+    let step = match op {
+        "x" => Step::Cancel,
+        "d" => Step::Device { device_id: args.to_string() },
+        "k" => {
+            let (kind, device_id) = args.split_once(':')?;
+            Step::Kind { device_id: device_id.to_string(), kind: SensorKind::from(kind) }
+        }
+        "m" => {
+            let mut args = args.splitn(3, ':');
+            let kind = SensorKind::from(args.next()?);
+            let direction = args.next()?.parse().ok()?;
+            Step::Direction { device_id: args.next()?.to_string(), kind, direction }
+        }
+        _ => return None,
+    };
+    Some(Click { started, step })
+}
@@ -0,0 +1,156 @@
+use crate::i18n::{self, Lang};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+
+// So lange gilt eine Vorschau, danach muss der Befehl neu gesendet werden
+pub const CONFIRM_SECONDS: u64 = 120;
+// Mehr offene Bestätigungen hält der Bot nicht vor; die älteste fällt heraus
+const MAX_PENDING: usize = 64;
+const PREFIX: &str = "cfm";
+
+struct Pending<T> {
+    owner: i64, // nur wer den Befehl gesendet hat, darf bestätigen
+    change: T,
+    since: Instant,
+}
+
+// Ergebnis eines Klicks auf "Anwenden" oder "Abbrechen"
+pub enum Taken<T> {
+    Apply(T),
+    Cancelled,
+    Expired,
+    Missing, // schon erledigt, abgebrochen oder verdrängt
+    Foreign, // Klick von jemand anderem, die Änderung bleibt offen
+}
+
+// Vorgemerkte Änderungen bis zur Bestätigung, je Token. Herausnehmen und
+// Anwenden passieren unter derselben Sperre, ein doppelter Klick findet
+// die Änderung nicht mehr.
+pub struct Confirmations<T> {
+    pending: BTreeMap<String, Pending<T>>,
+}
+
+impl<T> Confirmations<T> {
+    pub const fn new() -> Self {
+        Confirmations { pending: BTreeMap::new() }
+    }
+
+    pub fn insert(&mut self, token: String, owner: i64, change: T, now: Instant) {
+        let ttl = Duration::from_secs(CONFIRM_SECONDS);
+        self.pending.retain(|_, pending| now.duration_since(pending.since) <= ttl);
+        while self.pending.len() >= MAX_PENDING {
+            let oldest = self.pending.iter().min_by_key(|(_, pending)| pending.since).map(|(token, _)| token.clone());
+            match oldest {
+                Some(token) => self.pending.remove(&token),
+                None => break,
+            };
+        }
+        self.pending.insert(token, Pending { owner, change, since: now });
+    }
+
+    pub fn take(&mut self, token: &str, user: i64, apply: bool, now: Instant) -> Taken<T> {
+        match self.pending.get(token) {
+            None => return Taken::Missing,
+            Some(pending) if pending.owner != user => return Taken::Foreign,
+            Some(_) => {}
+        }
+        let Some(pending) = self.pending.remove(token) else {
+            return Taken::Missing;
+        };
+        if now.duration_since(pending.since) > Duration::from_secs(CONFIRM_SECONDS) {
+            Taken::Expired
+        } else if apply {
+            Taken::Apply(pending.change)
+        } else {
+            Taken::Cancelled
+        }
+    }
+}
+
+pub fn buttons(token: &str, lang: Lang) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback(i18n::message(lang, "confirm_apply"), format!("{}:ok:{}", PREFIX, token)),
+        InlineKeyboardButton::callback(i18n::message(lang, "confirm_cancel"), format!("{}:no:{}", PREFIX, token)),
+    ]])
+}
+
+// (anwenden?, Token)
+pub fn parse(data: &str) -> Option<(bool, &str)> {
+    let mut parts = data.splitn(3, ':');
+    if parts.next()? != PREFIX {
+        return None;
+    }
+    let apply = match parts.next()? {
+        "ok" => true,
+        "no" => false,
+        _ => return None,
+    };
+    Some((apply, parts.next()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(taken: Taken<&str>) -> String {
+        match taken {
+            Taken::Apply(change) => format!("apply {}", change),
+            Taken::Cancelled => "cancelled".into(),
+            Taken::Expired => "expired".into(),
+            Taken::Missing => "missing".into(),
+            Taken::Foreign => "foreign".into(),
+        }
+    }
+
+    fn pending(now: Instant) -> Confirmations<&'static str> {
+        let mut confirmations = Confirmations::new();
+        confirmations.insert("t1".into(), 1, "setmax", now);
+        confirmations
+    }
+
+    #[test]
+    fn confirm_applies_and_cancel_drops_the_change() {
+        let now = Instant::now();
+        assert_eq!(outcome(pending(now).take("t1", 1, true, now)), "apply setmax");
+        assert_eq!(outcome(pending(now).take("t1", 1, false, now)), "cancelled");
+    }
+
+    #[test]
+    fn preview_expires_after_the_confirm_window() {
+        let now = Instant::now();
+        let last_moment = now + Duration::from_secs(CONFIRM_SECONDS);
+        assert_eq!(outcome(pending(now).take("t1", 1, true, last_moment)), "apply setmax");
+        let mut confirmations = pending(now);
+        assert_eq!(outcome(confirmations.take("t1", 1, true, last_moment + Duration::from_secs(1))), "expired");
+        assert_eq!(outcome(confirmations.take("t1", 1, true, last_moment)), "missing");
+    }
+
+    #[test]
+    fn second_click_finds_nothing_and_others_cannot_take_it() {
+        let (now, mut confirmations) = (Instant::now(), pending(Instant::now()));
+        assert_eq!(outcome(confirmations.take("t1", 2, true, now)), "foreign");
+        assert_eq!(outcome(confirmations.take("t1", 1, true, now)), "apply setmax");
+        assert_eq!(outcome(confirmations.take("t1", 1, true, now)), "missing");
+        assert_eq!(outcome(confirmations.take("t1", 1, false, now)), "missing");
+    }
+
+    #[test]
+    fn oldest_pending_change_is_displaced_when_full() {
+        let (now, mut confirmations) = (Instant::now(), Confirmations::new());
+        for n in 0..=MAX_PENDING {
+            confirmations.insert(format!("t{}", n), 1, "change", now + Duration::from_millis(n as u64));
+        }
+        let later = now + Duration::from_secs(1);
+        assert_eq!(outcome(confirmations.take("t0", 1, true, later)), "missing");
+        assert_eq!(outcome(confirmations.take("t1", 1, true, later)), "apply change");
+    }
+
+    #[test]
+    fn button_data_round_trips() {
+        assert_eq!(parse("cfm:ok:abc"), Some((true, "abc")));
+        assert_eq!(parse("cfm:no:a:b"), Some((false, "a:b")));
+        assert_eq!(parse("cfm:maybe:abc"), None);
+        assert_eq!(parse("ack:ok:abc"), None);
+    }
+}
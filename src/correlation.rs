@@ -0,0 +1,68 @@
+use crate::sensor::{SensorKind, ThresholdDirection, ThresholdKey};
+
+// Regel für zusammengehörige Alarme eines Geräts, z.B. Temperatur und
+// Luftfeuchtigkeit zugleich zu hoch: "schwüle Außenluft"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub name: String,
+    pub keys: Vec<ThresholdKey>, // alle müssen verletzt sein
+    pub window: i64,             // Sekunden zwischen erstem und letztem Messwert
+    pub hint: String,
+}
+
+// Eingebaute Regeln, per [[rule]] in der Raumdatei überschreib- oder abschaltbar
+pub fn default_rules() -> Vec<Rule> {
+    vec![Rule {
+        name: "schwuel".into(),
+        keys: vec![ThresholdKey::new(SensorKind::Temperature, ThresholdDirection::Max), ThresholdKey::new(SensorKind::Humidity, ThresholdDirection::Max)],
+        window: 10 * 60,
+        hint: "Typisch für schwüle Außenluft – Fenster schließen".into(),
+    }]
+}
+
+// Regeln, deren Schwellen in diesem Durchlauf alle verletzt wurden.
+// `alarms` sind die Alarme eines Geräts: (Schlüssel, Zeitstempel des Messwerts).
+pub fn matching<'a>(rules: impl IntoIterator<Item = &'a Rule>, alarms: &[(ThresholdKey, i64)]) -> Vec<&'a Rule> {
+    rules
+        .into_iter()
+        .filter(|rule| {
+            let times: Option<Vec<i64>> = rule.keys.iter().map(|key| alarms.iter().find(|(k, _)| k == key).map(|(_, ts)| *ts)).collect();
+            times.is_some_and(|times| {
+                let first = times.iter().min().copied().unwrap_or_default();
+                let last = times.iter().max().copied().unwrap_or_default();
+                last - first <= rule.window
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(kind: SensorKind) -> ThresholdKey {
+        ThresholdKey::new(kind, ThresholdDirection::Max)
+    }
+
+    #[test]
+    fn all_keys_within_the_window_match() {
+        let rules = default_rules();
+        let alarms = [(key(SensorKind::Humidity), 1_000), (key(SensorKind::Temperature), 1_000 + 10 * 60)];
+        assert_eq!(matching(&rules, &alarms), vec![&rules[0]]);
+    }
+
+    #[test]
+    fn missing_key_or_other_direction_does_not_match() {
+        let rules = default_rules();
+        assert!(matching(&rules, &[(key(SensorKind::Temperature), 1_000)]).is_empty());
+        let low = ThresholdKey::new(SensorKind::Humidity, ThresholdDirection::Min);
+        assert!(matching(&rules, &[(key(SensorKind::Temperature), 1_000), (low, 1_000)]).is_empty());
+    }
+
+    #[test]
+    fn readings_further_apart_than_the_window_do_not_match() {
+        let rules = default_rules();
+        let alarms = [(key(SensorKind::Temperature), 1_000), (key(SensorKind::Humidity), 1_000 + 10 * 60 + 1)];
+        assert!(matching(&rules, &alarms).is_empty());
+    }
+}
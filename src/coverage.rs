@@ -0,0 +1,51 @@
+use crate::history::History;
+use crate::sensor::ThresholdKey;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Ohne Messwert in dieser Zeit gilt eine Schwelle als nicht überwachbar;
+// kürzere Ausfälle eines Sensors fallen darunter nicht auf
+pub const SEEN_WITHIN_SECONDS: i64 = 24 * 60 * 60;
+
+// Schwelle, zu deren (Gerät, Typ) seit `since` kein Messwert kommt
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+pub struct Unmonitored {
+    pub since: i64,
+    pub notified: bool, // Hinweis an den Besitzer ist raus
+}
+
+pub fn is_seen(history: &History, device_id: &str, sensor_type: &str, now: i64) -> bool {
+    history.series(device_id, sensor_type).and_then(|series| series.back()).is_some_and(|&(ts, _)| ts >= now - SEEN_WITHIN_SECONDS)
+}
+
+// Prüft die Schwellen eines Chats (Gerät, Schwelle) gegen den Verlauf.
+// Markierungen entstehen, verschwinden mit dem ersten Messwert wieder und
+// werden nach `grace` Sekunden einmalig gemeldet. Liefert (geändert, zu melden).
+pub fn check<'a>(
+    thresholds: impl Iterator<Item = &'a (String, ThresholdKey)>,
+    unmonitored: &mut HashMap<(String, ThresholdKey), Unmonitored>,
+    history: &History,
+    now: i64,
+    grace: i64,
+) -> (bool, Vec<(String, ThresholdKey)>) {
+    let mut changed = false;
+    let mut notify = Vec::new();
+    let mut current = HashMap::new();
+    for key in thresholds {
+        if is_seen(history, &key.0, key.1.kind.as_str(), now) {
+            continue;
+        }
+        let mut flag = unmonitored.get(key).copied().unwrap_or(Unmonitored { since: now, notified: false });
+        if !flag.notified && now - flag.since >= grace {
+            flag.notified = true;
+            notify.push(key.clone());
+        }
+        changed |= unmonitored.get(key) != Some(&flag);
+        current.insert(key.clone(), flag);
+    }
+    // Wieder gesehene oder gelöschte Schwellen verlieren ihre Markierung
+    changed |= current.len() != unmonitored.len();
+    *unmonitored = current;
+    (changed, notify)
+}
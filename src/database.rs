@@ -0,0 +1,73 @@
+use crate::SensorData;
+use crate::history_store::HistoryStore;
+use rusqlite::{Connection, params};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// Rohwerte ohne Verdichtung, je (Gerät, Typ, Zeitstempel) einmal. Der
+// Primärschlüssel dient zugleich als Index für Zeitraum-Abfragen.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS readings (
+        device_id   TEXT    NOT NULL,
+        sensor_type TEXT    NOT NULL,
+        timestamp   INTEGER NOT NULL,
+        value       REAL    NOT NULL,
+        PRIMARY KEY (device_id, sensor_type, timestamp)
+    ) WITHOUT ROWID;
+";
+
+// Messwerte in SQLite (DATABASE_PATH), zusätzlich zum JSON-Verlauf. Anders
+// als dort bleiben die Rohwerte dauerhaft erhalten.
+pub struct Database {
+    conn: Mutex<Connection>,
+    path: PathBuf,
+}
+
+impl Database {
+    pub fn open(path: &Path) -> Result<Database, String> {
+        let conn = Connection::open(path).map_err(|e| format!("{} lässt sich nicht öffnen: {}", path.display(), e))?;
+        conn.execute_batch(SCHEMA).map_err(|e| format!("{}: Tabelle nicht angelegt: {}", path.display(), e))?;
+        Ok(Database { conn: Mutex::new(conn), path: path.to_path_buf() })
+    }
+}
+
+impl HistoryStore for Database {
+    // Schreibsperre holen und gleich wieder freigeben
+    fn check_writable(&self) -> Option<Result<(), String>> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        Some(conn.execute_batch("BEGIN IMMEDIATE; ROLLBACK;").map_err(|e| e.to_string()))
+    }
+
+    fn size(&self) -> Option<u64> {
+        std::fs::metadata(&self.path).ok().map(|m| m.len())
+    }
+
+    fn insert(&self, readings: &[&SensorData]) -> Result<usize, String> {
+        let mut conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let mut inserted = 0;
+        {
+            let mut insert = tx
+                .prepare_cached("INSERT OR IGNORE INTO readings (device_id, sensor_type, timestamp, value) VALUES (?1, ?2, ?3, ?4)")
+                .map_err(|e| e.to_string())?;
+            for r in readings {
+                inserted += insert.execute(params![r.device_id, r.sensor_type.as_str(), r.timestamp, r.value]).map_err(|e| e.to_string())?;
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(inserted)
+    }
+
+    fn range(&self, device_id: &str, sensor_type: &str, since: i64) -> Result<Vec<(i64, f64)>, String> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut query = conn
+            .prepare_cached(
+                "SELECT timestamp, value FROM readings
+                 WHERE device_id = ?1 AND sensor_type = ?2 AND timestamp >= ?3
+                 ORDER BY timestamp",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = query.query_map(params![device_id, sensor_type, since], |row| Ok((row.get(0)?, row.get(1)?))).map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,49 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+// Zustellung der Warnungen eines Chats (/alert-mode)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+#[serde(rename_all = "lowercase")]
+pub enum AlertMode {
+    #[default]
+    Instant,
+    // Nicht kritische Warnungen und Entwarnungen gesammelt alle so viele Minuten
+    Digest {
+        minutes: i64,
+    },
+}
+
+impl AlertMode {
+    pub fn interval(self) -> Option<Duration> {
+        match self {
+            AlertMode::Instant => None,
+            AlertMode::Digest { minutes } => Some(Duration::minutes(minutes)),
+        }
+    }
+}
+
+// Gesammelte Meldungen eines Chats im Sammelmodus: (Zeitpunkt, Zeile) und
+// der nächste Versand
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+#[serde(default)]
+pub struct DigestBuffer {
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::stamped()"))]
+    pub entries: Vec<(DateTime<Utc>, String)>,
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::optional_datetime()"))]
+    pub due: Option<DateTime<Utc>>,
+}
+
+impl DigestBuffer {
+    // Fällig und nicht leer: Inhalt herausnehmen und den nächsten Termin
+    // setzen. Leere Puffer verschieben nur den Termin.
+    pub fn take_due(&mut self, interval: Duration, now: DateTime<Utc>) -> Option<Vec<(DateTime<Utc>, String)>> {
+        let due = *self.due.get_or_insert(now + interval);
+        if due > now {
+            return None;
+        }
+        self.due = Some(now + interval);
+        Some(std::mem::take(&mut self.entries)).filter(|entries| !entries.is_empty())
+    }
+}
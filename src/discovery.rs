@@ -0,0 +1,71 @@
+use crate::sensor::SensorKind;
+use std::collections::{BTreeMap, BTreeSet};
+
+type Pair = (String, SensorKind);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SensorEvent {
+    // Gerät und Messgröße zum ersten Mal überhaupt gesehen
+    New { device_id: String, kind: SensorKind },
+    // Seit `missing_after` Abfragen fehlen diese Messgrößen; `all`, wenn vom
+    // Gerät gar nichts mehr kommt
+    Gone { device_id: String, kinds: Vec<SensorKind>, all: bool },
+    // Nach einer Meldung als verschwunden wieder da
+    Returned { device_id: String, kind: SensorKind },
+}
+
+// Erkennt neue und verschwundene Sensoren anhand der regulären Abfragen.
+// Als verschwunden gilt nur, was in diesem Lauf schon geliefert hat und dann
+// `missing_after` erfolgreiche Abfragen in Folge fehlt; ein einzelner
+// Fehlschlag der ganzen Quelle zählt gar nicht, weil er keine Abfrage liefert.
+pub struct SensorWatch {
+    missing_after: u32,
+    known: BTreeSet<Pair>,     // je gesehen, auch aus dem gespeicherten Verlauf
+    live: BTreeMap<Pair, u32>, // in diesem Lauf gesehen -> Abfragen ohne Wert in Folge
+    gone: BTreeSet<Pair>,
+}
+
+impl SensorWatch {
+    // missing_after 0 schaltet die Meldungen ab
+    pub fn new(missing_after: u32, known: impl IntoIterator<Item = Pair>) -> SensorWatch {
+        SensorWatch { missing_after, known: known.into_iter().collect(), live: BTreeMap::new(), gone: BTreeSet::new() }
+    }
+
+    // Alle Paare einer erfolgreichen regulären Abfrage
+    pub fn observe(&mut self, readings: impl IntoIterator<Item = Pair>) -> Vec<SensorEvent> {
+        if self.missing_after == 0 {
+            return Vec::new();
+        }
+        let seen: BTreeSet<Pair> = readings.into_iter().collect();
+        // Eine leere Antwort ist eher ein Fehler der Quelle als ein Ausfall aller Sensoren
+        if seen.is_empty() {
+            return Vec::new();
+        }
+        let mut events = Vec::new();
+        for pair in &seen {
+            if self.known.insert(pair.clone()) {
+                events.push(SensorEvent::New { device_id: pair.0.clone(), kind: pair.1.clone() });
+            } else if self.gone.remove(pair) {
+                events.push(SensorEvent::Returned { device_id: pair.0.clone(), kind: pair.1.clone() });
+            }
+            self.live.insert(pair.clone(), 0);
+        }
+        let mut missing: BTreeMap<String, Vec<SensorKind>> = BTreeMap::new();
+        for (pair, misses) in self.live.iter_mut().filter(|(pair, _)| !seen.contains(*pair)) {
+            *misses += 1;
+            if *misses == self.missing_after {
+                missing.entry(pair.0.clone()).or_default().push(pair.1.clone());
+            }
+        }
+        for (device_id, kinds) in missing {
+            for kind in &kinds {
+                let pair = (device_id.clone(), kind.clone());
+                self.live.remove(&pair);
+                self.gone.insert(pair);
+            }
+            let all = !self.live.keys().any(|(device, _)| *device == device_id);
+            events.push(SensorEvent::Gone { device_id, kinds, all });
+        }
+        events
+    }
+}
@@ -0,0 +1,148 @@
+use crate::routing::Severity;
+use crate::sensor::ThresholdDirection;
+use serde::{Deserialize, Serialize};
+
+// Nach einem Neustart läuft eine Verletzung weiter, wenn ihr letzter
+// Messwert außerhalb des Bereichs höchstens so lange zurückliegt
+const RESUME_WITHIN_SECONDS: i64 = 60 * 60;
+
+// Laufende Verletzung einer Schwelle vom ersten Alarm bis zur Erholung,
+// gespeichert mit der Benutzerkonfiguration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+pub struct Episode {
+    pub started: i64,
+    pub worst: f64,
+    pub worst_at: i64,
+    pub last_seen: i64, // letzter Messwert außerhalb des Bereichs
+    #[serde(default)]
+    pub message_id: Option<i32>, // erste Warnung, Folgemeldungen antworten darauf
+    #[serde(default)]
+    pub escalated: bool, // Gerät wurde währenddessen häufiger abgefragt
+    #[serde(default)]
+    pub reviewed: bool, // Vorschlag nach langer Verletzung ist schon verschickt
+    #[serde(default)]
+    pub reminded_at: Option<i64>, // letzte Erinnerung (/repeat), sonst zählt der Beginn
+    #[serde(default)]
+    pub reminders: u32, // bisher verschickte Erinnerungen
+    // Text und Stufe der Warnung, nur mit /escalate-to gespeichert
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alert: Option<(String, Severity)>,
+    #[serde(default)]
+    pub handed_over: bool, // an den Chat aus /escalate-to weitergegeben
+}
+
+impl Episode {
+    pub fn start(timestamp: i64, value: f64) -> Episode {
+        Episode {
+            started: timestamp,
+            worst: value,
+            worst_at: timestamp,
+            last_seen: timestamp,
+            message_id: None,
+            escalated: false,
+            reviewed: false,
+            reminded_at: None,
+            reminders: 0,
+            alert: None,
+            handed_over: false,
+        }
+    }
+
+    // Neuer Alarm für dieselbe Schwelle: nach einem Neustart die bestehende
+    // Verletzung fortsetzen, sonst neu beginnen
+    pub fn resume_or_start(existing: Option<Episode>, direction: ThresholdDirection, timestamp: i64, value: f64) -> Episode {
+        match existing {
+            Some(mut episode) if timestamp - episode.last_seen <= RESUME_WITHIN_SECONDS => {
+                episode.observe(direction, timestamp, value);
+                episode
+            }
+            _ => Episode::start(timestamp, value),
+        }
+    }
+
+    // Neuer Messwert während der Verletzung; schlimmer ist bei MIN tiefer,
+    // bei MAX höher. false, wenn der Messwert schon bekannt war.
+    pub fn observe(&mut self, direction: ThresholdDirection, timestamp: i64, value: f64) -> bool {
+        if timestamp <= self.last_seen {
+            return false;
+        }
+        let worse = if direction.is_min() { value < self.worst } else { value > self.worst };
+        if worse {
+            self.worst = value;
+            self.worst_at = timestamp;
+        }
+        self.last_seen = timestamp;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Lang;
+    use crate::monitor::{EventKind, ThresholdEvent};
+    use crate::sensor::SensorKind;
+    use crate::units::TempUnit;
+
+    const START: i64 = 1_700_000_000;
+
+    #[test]
+    fn worst_value_follows_the_direction() {
+        let mut high = Episode::start(START, 26.0);
+        assert!(high.observe(ThresholdDirection::Max, START + 60, 27.5));
+        assert!(high.observe(ThresholdDirection::Max, START + 120, 26.5));
+        assert_eq!((high.worst, high.worst_at, high.last_seen), (27.5, START + 60, START + 120));
+
+        let mut low = Episode::start(START, 16.0);
+        assert!(low.observe(ThresholdDirection::Min, START + 60, 17.0));
+        assert_eq!((low.worst, low.worst_at), (16.0, START));
+    }
+
+    #[test]
+    fn repeated_reading_is_counted_once() {
+        let mut episode = Episode::start(START, 26.0);
+        assert!(episode.observe(ThresholdDirection::Max, START + 60, 28.0));
+        assert!(!episode.observe(ThresholdDirection::Max, START + 60, 28.0));
+        assert!(!episode.observe(ThresholdDirection::Max, START + 30, 30.0));
+        assert_eq!((episode.worst, episode.last_seen), (28.0, START + 60));
+    }
+
+    #[test]
+    fn restart_resumes_a_recent_episode_only() {
+        let episode = Episode { reminders: 2, message_id: Some(7), ..Episode::start(START, 26.0) };
+        let resumed = Episode::resume_or_start(Some(episode.clone()), ThresholdDirection::Max, START + RESUME_WITHIN_SECONDS, 27.0);
+        assert_eq!((resumed.started, resumed.reminders, resumed.message_id, resumed.worst), (START, 2, Some(7), 27.0));
+
+        let fresh = Episode::resume_or_start(Some(episode), ThresholdDirection::Max, START + RESUME_WITHIN_SECONDS + 1, 27.0);
+        assert_eq!((fresh.started, fresh.reminders, fresh.message_id), (START + RESUME_WITHIN_SECONDS + 1, 0, None));
+    }
+
+    fn recovery(reminders: u32) -> String {
+        let mut episode = Episode::start(START, 26.0);
+        episode.observe(ThresholdDirection::Max, START + 600, 27.5);
+        episode.reminders = reminders;
+        let event = ThresholdEvent {
+            kind: EventKind::Recovered,
+            chat_id: 1,
+            device_id: "sensor1".into(),
+            sensor_type: SensorKind::Temperature,
+            direction: ThresholdDirection::Max,
+            value: 24.0,
+            threshold: Some(25.0),
+            source: String::new(),
+            timestamp: START + 90 * 60,
+        };
+        crate::format::format_recovery(&event, &episode, Lang::De, TempUnit::Celsius, Some(chrono_tz::Europe::Berlin))
+    }
+
+    #[test]
+    fn recovery_sums_up_duration_worst_value_and_reminders() {
+        assert_eq!(
+            recovery(0),
+            "✅ Temperatur im Wohnzimmer wieder im Normalbereich: 24.0 °C (Max 25.0 °C)\n⏱ Dauer: 1 h 30 min · Höchstwert: 27.5 °C um 23:23"
+        );
+        assert!(recovery(1).ends_with("\n🔁 1 Erinnerung verschickt"));
+        assert!(recovery(3).ends_with("\n🔁 3 Erinnerungen verschickt"));
+    }
+}
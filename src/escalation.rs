@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+use tokio::time::{Duration, Instant};
+
+struct Window {
+    until: Instant,
+    next: Instant,
+}
+
+// Nach einem Alarm wird das betroffene Gerät eine Zeit lang häufiger
+// abgefragt, damit man schneller sieht, ob es schlimmer wird.
+// Mehrere Alarme desselben Geräts teilen sich ein Fenster; es endet mit der
+// Erholung oder spätestens nach der eingestellten Dauer. Die reguläre Abfrage
+// aller Geräte läuft unabhängig davon weiter.
+pub struct Escalation {
+    interval: Duration,
+    duration: Duration,
+    devices: BTreeMap<String, Window>,
+}
+
+impl Escalation {
+    // Intervall oder Dauer 0 schaltet die Erhöhung ab
+    pub fn new(interval: Duration, duration: Duration) -> Escalation {
+        Escalation { interval, duration, devices: BTreeMap::new() }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    // Alarm für ein Gerät; true, wenn damit ein neues Fenster beginnt.
+    // Ein laufendes Fenster wird nicht verlängert.
+    pub fn trigger(&mut self, device_id: &str, now: Instant) -> bool {
+        if self.interval.is_zero() || self.duration.is_zero() || self.devices.contains_key(device_id) {
+            return false;
+        }
+        let window = Window { until: now + self.duration, next: now + self.interval };
+        self.devices.insert(device_id.to_string(), window);
+        true
+    }
+
+    // Alle Alarme des Geräts sind vorbei; true, wenn ein Fenster lief
+    pub fn recover(&mut self, device_id: &str) -> bool {
+        self.devices.remove(device_id).is_some()
+    }
+
+    // Abgelaufene Fenster entfernen; liefert die betroffenen Geräte
+    pub fn expire(&mut self, now: Instant) -> Vec<String> {
+        let expired: Vec<String> = self.devices.iter().filter(|(_, window)| window.until <= now).map(|(device, _)| device.clone()).collect();
+        for device in &expired {
+            self.devices.remove(device);
+        }
+        expired
+    }
+
+    // Geräte, deren nächste Abfrage fällig ist; ihr Termin rückt um ein Intervall weiter
+    pub fn take_due(&mut self, now: Instant) -> Vec<String> {
+        let mut due = Vec::new();
+        for (device, window) in self.devices.iter_mut() {
+            if window.next <= now {
+                due.push(device.clone());
+                window.next = now + self.interval;
+            }
+        }
+        due
+    }
+
+    // Frühester Termin eines erhöhten Geräts
+    pub fn next_due(&self) -> Option<Instant> {
+        self.devices.values().map(|window| window.next.min(window.until)).min()
+    }
+
+    // Geräte mit erhöhter Abfrage und verbleibender Zeit
+    pub fn active(&self, now: Instant) -> Vec<(String, Duration)> {
+        self.devices.iter().map(|(device, window)| (device.clone(), window.until.saturating_duration_since(now))).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time;
+
+    const INTERVAL: Duration = Duration::from_secs(60);
+    const DURATION: Duration = Duration::from_secs(5 * 60);
+
+    // Die Überwachung schläft bis next_due; so weit vorspulen und abfragen
+    async fn wait_for_due(escalation: &mut Escalation) -> (Duration, Vec<String>) {
+        let started = Instant::now();
+        time::sleep_until(escalation.next_due().unwrap()).await;
+        (started.elapsed(), escalation.take_due(Instant::now()))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn elevated_polls_every_interval_until_the_window_ends() {
+        let mut escalation = Escalation::new(INTERVAL, DURATION);
+        assert!(escalation.trigger("sensor1", Instant::now()));
+        assert_eq!(escalation.next_due(), Some(Instant::now() + INTERVAL));
+
+        time::advance(Duration::from_secs(59)).await;
+        assert!(escalation.take_due(Instant::now()).is_empty());
+        time::advance(Duration::from_secs(1)).await;
+        assert_eq!(escalation.take_due(Instant::now()), ["sensor1"]);
+
+        for _ in 0..3 {
+            assert_eq!(wait_for_due(&mut escalation).await, (INTERVAL, vec!["sensor1".to_string()]));
+        }
+        assert_eq!(escalation.active(Instant::now()), [("sensor1".to_string(), Duration::from_secs(60))]);
+        assert!(escalation.expire(Instant::now()).is_empty());
+        time::advance(INTERVAL).await;
+        assert_eq!(escalation.expire(Instant::now()), ["sensor1"]);
+        assert_eq!(escalation.next_due(), None);
+    }
+
+    // Ein zweiter Alarm verlängert nicht, ein neuer nach der Erholung beginnt von vorn
+    #[tokio::test(start_paused = true)]
+    async fn window_is_shared_and_restarts_after_recovery() {
+        let mut escalation = Escalation::new(INTERVAL, DURATION);
+        assert!(escalation.trigger("sensor1", Instant::now()));
+        time::advance(Duration::from_secs(4 * 60)).await;
+        assert!(!escalation.trigger("sensor1", Instant::now()));
+        assert_eq!(escalation.active(Instant::now()), [("sensor1".to_string(), Duration::from_secs(60))]);
+
+        assert!(escalation.recover("sensor1"));
+        assert!(!escalation.recover("sensor1"));
+        assert!(escalation.trigger("sensor1", Instant::now()));
+        assert_eq!(escalation.active(Instant::now()), [("sensor1".to_string(), DURATION)]);
+    }
+
+    // Das Fenster endet auch zwischen zwei Abfragen pünktlich
+    #[tokio::test(start_paused = true)]
+    async fn next_due_is_the_earlier_of_poll_and_end() {
+        let mut escalation = Escalation::new(Duration::from_secs(120), Duration::from_secs(150));
+        let start = Instant::now();
+        escalation.trigger("sensor1", start);
+        time::advance(Duration::from_secs(30)).await;
+        escalation.trigger("sensor2", Instant::now());
+        assert_eq!(escalation.next_due(), Some(start + Duration::from_secs(120)));
+
+        assert_eq!(wait_for_due(&mut escalation).await, (Duration::from_secs(90), vec!["sensor1".to_string()]));
+        // sensor1 endet nach 150 s, vor seiner nächsten Abfrage
+        assert_eq!(escalation.next_due(), Some(start + Duration::from_secs(150)));
+        assert_eq!(wait_for_due(&mut escalation).await, (Duration::from_secs(30), vec!["sensor2".to_string()]));
+        assert_eq!(escalation.expire(Instant::now()), ["sensor1"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn zero_interval_or_duration_disables_it() {
+        for (interval, duration) in [(Duration::ZERO, DURATION), (INTERVAL, Duration::ZERO)] {
+            let mut escalation = Escalation::new(interval, duration);
+            assert!(!escalation.trigger("sensor1", Instant::now()));
+            assert_eq!(escalation.next_due(), None);
+        }
+    }
+}
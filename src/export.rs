@@ -0,0 +1,24 @@
+use crate::format_timestamp_in;
+use crate::sensor::SensorKind;
+use crate::units::TempUnit;
+use chrono_tz::Tz;
+
+// Größere Dateien lehnt /export ab, statt sie hochzuladen
+pub const MAX_BYTES: usize = 5 * 1024 * 1024;
+pub const DEFAULT_DAYS: i64 = 7;
+
+// Zeitpunkt in der Zeitzone des Chats, Wert mit Punkt als Dezimaltrenner
+pub fn csv(samples: &[(i64, f64)], kind: &SensorKind, units: TempUnit, unit: &str, tz: Option<Tz>) -> String {
+    let mut csv = format!("timestamp,value ({})\n", unit);
+    for &(ts, value) in samples {
+        csv.push_str(&format!("{},{}\n", format_timestamp_in(ts, "%Y-%m-%d %H:%M:%S", tz), units.show(kind, value)));
+    }
+    csv
+}
+
+// z.B. sensor1_temperature_20260101-20260107.csv; Präfixe weiterer
+// Haushalte und andere Sonderzeichen werden zu '-'
+pub fn file_name(device_id: &str, sensor_type: &str, from: i64, to: i64, tz: Option<Tz>) -> String {
+    let clean = |text: &str| -> String { text.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '-' }).collect() };
+    format!("{}_{}_{}-{}.csv", clean(device_id), clean(sensor_type), format_timestamp_in(from, "%Y%m%d", tz), format_timestamp_in(to, "%Y%m%d", tz))
+}
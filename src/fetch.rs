@@ -0,0 +1,145 @@
+use crate::i18n::Text;
+use crate::source::{FetchError, HttpSource, SensorSource};
+use crate::{SOURCES, SensorData, bot_status, format_duration, ignored, latest, metrics, rooms, settings, telemetry, tenant_of};
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+fn sources() -> &'static [Arc<dyn SensorSource>] {
+    SOURCES.get_or_init(|| vec![Arc::new(HttpSource::default())])
+}
+
+// Quellen je Haushalt: die eingebauten gehören dem Haupthaushalt, weitere
+// Haushalte haben je einen Sensor-Webserver
+pub type TenantSources = Vec<(Option<String>, Arc<dyn SensorSource>)>;
+
+pub fn tenant_sources(tenant: Option<&rooms::Tenant>) -> TenantSources {
+    match tenant {
+        None => sources().iter().map(|source| (None, source.clone())).collect(),
+        Some(tenant) => {
+            let source = HttpSource::new(tenant.source.clone());
+            let source = match tenant.poll_interval_seconds {
+                Some(seconds) => source.with_interval(std::time::Duration::from_secs(seconds)),
+                None => source,
+            };
+            vec![(Some(tenant.id.clone()), Arc::new(source))]
+        }
+    }
+}
+
+// Quellen aller Haushalte
+pub fn all_sources() -> TenantSources {
+    let mut all = tenant_sources(None);
+    let registry = rooms();
+    for tenant in registry.tenants() {
+        all.extend(tenant_sources(Some(tenant)));
+    }
+    all
+}
+
+// Nur die Quellen des Haushalts, zu dem der Chat gehört
+pub async fn fetch_sensor_data_for(chat_id: i64) -> Result<Vec<SensorData>, FetchError> {
+    fetch_from(tenant_sources(rooms().tenant_of(chat_id))).await
+}
+
+// Messwerte für /status: der letzte Stand der Überwachung, solange er jünger
+// als STATUS_MAX_AGE_SECONDS ist, sonst neu abgerufen. Antwortet die Quelle
+// nicht, gilt der letzte Stand mit einem Hinweis auf sein Alter.
+pub async fn status_readings(chat_id: i64) -> Result<(Vec<SensorData>, Option<Text>), FetchError> {
+    let tenant = tenant_of(chat_id);
+    let cached = latest().as_ref().map(|snapshot| {
+        let readings: Vec<SensorData> = snapshot.readings.iter().filter(|r| rooms().visible(tenant.as_deref(), &r.device_id)).cloned().collect();
+        (readings, snapshot.fetched_at)
+    });
+    let cached = cached.filter(|(readings, _)| !readings.is_empty());
+    let now = Utc::now();
+    if let Some((readings, fetched_at)) = &cached
+        && (now - *fetched_at).num_seconds() < settings().status_max_age_seconds
+    {
+        return Ok((readings.clone(), None));
+    }
+    match fetch_sensor_data_for(chat_id).await {
+        Ok(readings) => Ok((readings, None)),
+        Err(err) => match cached {
+            Some((readings, fetched_at)) => {
+                let key = match err {
+                    FetchError::Request(_) => "status_cached_unreachable",
+                    FetchError::Parse { .. } => "status_cached_unreadable",
+                };
+                let age = format_duration((now - fetched_at).num_seconds().max(60));
+                Ok((readings, Some(Text::new(key, &[("age", &age)]))))
+            }
+            None => Err(err),
+        },
+    }
+}
+
+// Messwerte aller Haushalte für die Überwachung
+pub async fn fetch_sensor_data() -> Result<Vec<SensorData>, FetchError> {
+    fetch_from(all_sources()).await
+}
+
+// Einzelne Quellen der Überwachung gleichzeitig abrufen, mit dem Ergebnis je
+// Quelle (Nummer in `sources`)
+pub async fn fetch_each(sources: &TenantSources, indices: &[usize]) -> Vec<(usize, Result<Vec<SensorData>, FetchError>)> {
+    let results = futures::future::join_all(indices.iter().map(|&index| fetch_from(vec![sources[index].clone()]))).await;
+    indices.iter().copied().zip(results).collect()
+}
+
+// Liefern mehrere Quellen dieselbe Messgröße eines Geräts, gilt der neueste Wert
+pub fn merge_newest(readings: &mut Vec<SensorData>, more: impl IntoIterator<Item = SensorData>) {
+    for reading in more {
+        match readings.iter_mut().find(|r| r.device_id == reading.device_id && r.sensor_type == reading.sensor_type) {
+            Some(known) if known.timestamp >= reading.timestamp => {}
+            Some(known) => *known = reading,
+            None => readings.push(reading),
+        }
+    }
+}
+
+// Messwerte der Quellen zusammen, gleichzeitig abgerufen; ein Fehler zählt
+// nur, wenn keine Quelle antwortet. Geräte weiterer Haushalte bekommen deren
+// Präfix. Liefern mehrere Quellen dieselbe Messgröße eines Geräts, gilt der
+// neueste Wert.
+async fn fetch_from(sources: TenantSources) -> Result<Vec<SensorData>, FetchError> {
+    let mut readings: Vec<SensorData> = Vec::new();
+    let mut first_error = None;
+    let mut answered = false;
+    let mut skipped: BTreeMap<Option<String>, usize> = BTreeMap::new();
+    let results = futures::future::join_all(sources.iter().map(|(tenant, source)| async move {
+        let started = std::time::Instant::now();
+        let result = source.fetch().await;
+        telemetry::fetch(tenant.as_deref(), started.elapsed(), result.is_ok());
+        result
+    }))
+    .await;
+    for ((tenant, source), result) in sources.iter().zip(results) {
+        let tenant = tenant.as_deref();
+        match result {
+            Ok(data) => {
+                *skipped.entry(tenant.map(String::from)).or_default() += source.skipped();
+                let data = data.into_iter().map(|mut reading| {
+                    if let Some(tenant) = tenant {
+                        reading.device_id = rooms::qualify(tenant, &reading.device_id);
+                    }
+                    reading
+                });
+                // Ignorierte Geräte fallen gleich hier heraus
+                let ignored = ignored();
+                merge_newest(&mut readings, data.filter(|reading| !ignored.contains(&reading.device_id)));
+                answered = true;
+            }
+            Err(err) => {
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+    bot_status().skipped.extend(skipped);
+    match first_error {
+        Some(err) if !answered => Err(err),
+        _ => {
+            readings.extend(metrics::derive(&readings));
+            Ok(readings)
+        }
+    }
+}
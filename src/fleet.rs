@@ -0,0 +1,262 @@
+use crate::history::History;
+use crate::i18n::{self, Lang};
+use crate::{escape_markdown, format_duration, format_timestamp, room_name};
+use std::collections::BTreeMap;
+use unicode_width::UnicodeWidthStr;
+
+// Telegram erlaubt 4096 Zeichen je Nachricht; Rest für Kopf und Codeblock
+const MAX_CHUNK_CHARS: usize = 3500;
+// Ohne neuen Messwert in dieser Zeit gilt ein Gerät als verstummt
+const SILENT_AFTER_SECONDS: i64 = 24 * 60 * 60;
+// Ohne so viele Abstände lässt sich kein üblicher Takt bestimmen
+const MIN_INTERVALS: usize = 5;
+
+// Woche eines Geräts aus dem Rohverlauf
+pub struct DeviceWeek {
+    pub device_id: String,
+    pub median: Option<i64>,       // üblicher Abstand zwischen Messwerten
+    pub availability: Option<f64>, // Anteil der Zeit ohne Ausfall, 0..1
+    pub outages: usize,            // Lücken deutlich über dem üblichen Abstand
+    pub longest_gap: i64,
+    pub new: bool,    // erster Messwert überhaupt in dieser Woche
+    pub silent: bool, // seit SILENT_AFTER_SECONDS kein Messwert
+}
+
+// Verfügbarkeit je Gerät zwischen `start` und `end`: Eine Lücke über
+// max(factor × Median, Median + Abrufabstand) zählt als Ausfall, und zwar
+// mit der Zeit, die über den üblichen Abstand hinausgeht. Lücken am Anfang
+// und Ende der Woche zählen mit, bei neuen Geräten erst ab ihrem ersten Wert.
+// Geräte, die in der Vorwoche noch gemeldet haben und jetzt gar nicht mehr,
+// erscheinen mit 0 % als verstummt.
+pub fn summarize(history: &History, start: i64, end: i64, factor: f64, poll_interval: i64) -> Vec<DeviceWeek> {
+    // Geräte, die schon vor der Woche Messwerte hatten (auch verdichtet),
+    // mit ihrem letzten Zeitpunkt davor
+    let mut known_before: BTreeMap<&str, i64> = BTreeMap::new();
+    for (device, _, bucket) in history.points_since(i64::MIN) {
+        if bucket.start < start {
+            let last = known_before.entry(device).or_insert(bucket.start);
+            *last = (*last).max(bucket.start);
+        }
+    }
+
+    let mut arrivals: BTreeMap<&str, Vec<i64>> = BTreeMap::new();
+    for (device, _, timestamp, _) in history.samples_since(start) {
+        if timestamp <= end {
+            arrivals.entry(device).or_default().push(timestamp);
+        }
+    }
+
+    let week = end - start;
+    let retired: Vec<DeviceWeek> = known_before
+        .iter()
+        .filter(|(device, last)| **last >= start - week && !arrivals.contains_key(*device))
+        .map(|(device, _)| DeviceWeek {
+            device_id: device.to_string(),
+            median: None,
+            availability: Some(0.0),
+            outages: 1,
+            longest_gap: week,
+            new: false,
+            silent: true,
+        })
+        .collect();
+
+    let mut weeks: Vec<DeviceWeek> = arrivals
+        .into_iter()
+        .map(|(device, mut timestamps)| {
+            timestamps.sort_unstable();
+            timestamps.dedup();
+            let new = !known_before.contains_key(device);
+            let intervals: Vec<i64> = timestamps.windows(2).map(|pair| pair[1] - pair[0]).collect();
+            let median = (intervals.len() >= MIN_INTERVALS).then(|| {
+                let mut sorted = intervals.clone();
+                sorted.sort_unstable();
+                sorted[sorted.len() / 2]
+            });
+
+            let first = timestamps[0];
+            let last = timestamps[timestamps.len() - 1];
+            let period_start = if new { first } else { start };
+            let mut gaps = intervals;
+            if !new {
+                gaps.push(first - start);
+            }
+            gaps.push(end - last);
+
+            let (availability, outages) = match median {
+                Some(median) if end > period_start => {
+                    let limit = (median as f64 * factor).max((median + poll_interval) as f64);
+                    let outage_gaps: Vec<i64> = gaps.iter().copied().filter(|gap| *gap as f64 > limit).collect();
+                    let down: i64 = outage_gaps.iter().map(|gap| gap - median).sum();
+                    let availability = 1.0 - down as f64 / (end - period_start) as f64;
+                    (Some(availability.clamp(0.0, 1.0)), outage_gaps.len())
+                }
+                _ => (None, 0),
+            };
+
+            DeviceWeek {
+                device_id: device.to_string(),
+                median,
+                availability,
+                outages,
+                longest_gap: gaps.into_iter().max().unwrap_or_default(),
+                new,
+                silent: end - last > SILENT_AFTER_SECONDS,
+            }
+        })
+        .collect();
+    weeks.extend(retired);
+    weeks
+}
+
+fn pad(text: &str, width: usize, right: bool) -> String {
+    let fill = " ".repeat(width.saturating_sub(text.width()));
+    if right { format!("{}{}", fill, text) } else { format!("{}{}", text, fill) }
+}
+
+// Bericht als Legacy-Markdown mit Codeblock; bei vielen Geräten auf mehrere
+// Nachrichten verteilt, jede mit eigenem Tabellenkopf
+pub fn format_report(weeks: &[DeviceWeek], start: i64, end: i64, lang: Lang) -> Vec<String> {
+    let title = i18n::message_with(lang, "fleet_title", &[("from", &format_timestamp(start, "%d.%m.")), ("to", &format_timestamp(end, "%d.%m.%Y"))]);
+    if weeks.is_empty() {
+        return vec![format!("{}\n\n{}", title, i18n::message(lang, "fleet_empty"))];
+    }
+
+    // Spaltenköpfe im Katalog durch | getrennt
+    let header: Vec<String> = i18n::message(lang, "fleet_header").split('|').map(String::from).collect();
+    let rows: Vec<Vec<String>> = weeks
+        .iter()
+        .map(|week| {
+            vec![
+                room_name(&week.device_id).replace('`', "'"),
+                week.availability.map(|a| format!("{:.1}%", a * 100.0)).unwrap_or_else(|| "–".into()),
+                week.outages.to_string(),
+                week.median.map(format_duration).unwrap_or_else(|| "–".into()).replace(' ', ""),
+                format_duration(week.longest_gap).replace(' ', ""),
+            ]
+        })
+        .collect();
+    let widths: Vec<usize> = (0..header.len()).map(|col| rows.iter().chain([&header]).map(|row| row[col].width()).max().unwrap_or(0)).collect();
+    let line = |row: &[String]| {
+        row.iter().zip(&widths).enumerate().map(|(col, (cell, width))| pad(cell, *width, col > 0)).collect::<Vec<_>>().join(" ").trim_end().to_string()
+    };
+
+    let mut chunks: Vec<Vec<String>> = vec![Vec::new()];
+    let mut size = 0;
+    for row in &rows {
+        let row = line(row);
+        if size + row.len() > MAX_CHUNK_CHARS && !chunks[chunks.len() - 1].is_empty() {
+            chunks.push(Vec::new());
+            size = 0;
+        }
+        size += row.len() + 1;
+        let last = chunks.len() - 1;
+        chunks[last].push(row);
+    }
+
+    let count = chunks.len();
+    let mut messages: Vec<String> = chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, rows)| {
+            let part = if count > 1 { format!(" ({}/{})", index + 1, count) } else { String::new() };
+            format!("{}{}\n```\n{}\n{}\n```", title, part, line(&header), rows.join("\n"))
+        })
+        .collect();
+
+    let names = |filter: fn(&DeviceWeek) -> bool| weeks.iter().filter(|w| filter(w)).map(|w| escape_markdown(&room_name(&w.device_id))).collect::<Vec<_>>();
+    let mut footer = format!("\n{}", i18n::message(lang, "fleet_legend"));
+    let new = names(|w| w.new);
+    if !new.is_empty() {
+        footer.push_str(&format!("\n{}", i18n::message_with(lang, "fleet_new", &[("rooms", &new.join(", "))])));
+    }
+    let silent = names(|w| w.silent);
+    if !silent.is_empty() {
+        footer.push_str(&format!("\n{}", i18n::message_with(lang, "fleet_silent", &[("rooms", &silent.join(", "))])));
+    }
+    if let Some(last) = messages.last_mut() {
+        last.push_str(&footer);
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: i64 = 1_700_000_000;
+    const END: i64 = START + 7 * 24 * 60 * 60;
+    const STEP: i64 = 300;
+    const HOUR: i64 = 60 * 60;
+
+    // Alle fünf Minuten ein Messwert von `from` bis `to`, außer in den Lücken
+    fn readings(history: &mut History, device: &str, sensor_type: &str, from: i64, to: i64, gaps: &[(i64, i64)]) {
+        for ts in (from..=to).step_by(STEP as usize) {
+            if !gaps.iter().any(|&(gap_start, gap_end)| ts > gap_start && ts < gap_end) {
+                history.record(device, sensor_type, ts, 21.0);
+            }
+        }
+    }
+
+    fn week<'a>(weeks: &'a [DeviceWeek], device: &str) -> &'a DeviceWeek {
+        weeks.iter().find(|w| w.device_id == device).unwrap()
+    }
+
+    fn summary(history: &History) -> Vec<DeviceWeek> {
+        summarize(history, START, END, 3.0, 60)
+    }
+
+    #[test]
+    fn outages_are_counted_with_the_time_beyond_the_usual_interval() {
+        let mut history = History::default();
+        let gaps = [(START + 24 * HOUR, START + 26 * HOUR), (START + 48 * HOUR, START + 48 * HOUR + 30 * 60)];
+        readings(&mut history, "sensor1", "temperature", START - STEP, END, &gaps);
+
+        let weeks = summary(&history);
+        let device = week(&weeks, "sensor1");
+        assert_eq!((device.median, device.outages, device.longest_gap), (Some(STEP), 2, 2 * HOUR));
+        let down = (2 * HOUR - STEP) + (30 * 60 - STEP);
+        assert!((device.availability.unwrap() - (1.0 - down as f64 / (END - START) as f64)).abs() < 1e-9);
+        assert!(!device.new && !device.silent);
+    }
+
+    #[test]
+    fn overlapping_gaps_of_one_device_count_once() {
+        let mut history = History::default();
+        // Feuchte überbrückt die erste Lücke der Temperatur; die zweiten
+        // überschneiden sich, ausgefallen ist das Gerät nur, solange beide fehlen
+        let shared = (START + 48 * HOUR, START + 50 * HOUR);
+        readings(&mut history, "sensor1", "temperature", START - STEP, END, &[(START + 24 * HOUR, START + 26 * HOUR), shared]);
+        readings(&mut history, "sensor1", "humidity", START - STEP, END, &[(START + 49 * HOUR, START + 51 * HOUR), shared]);
+
+        let weeks = summary(&history);
+        assert_eq!(weeks.len(), 1);
+        let device = week(&weeks, "sensor1");
+        assert_eq!((device.outages, device.longest_gap), (1, 2 * HOUR));
+    }
+
+    #[test]
+    fn overlapping_outages_of_two_devices_are_counted_per_device() {
+        let mut history = History::default();
+        readings(&mut history, "sensor1", "temperature", START - STEP, END, &[(START + 24 * HOUR, START + 27 * HOUR)]);
+        readings(&mut history, "sensor2", "temperature", START - STEP, END, &[(START + 25 * HOUR, START + 26 * HOUR)]);
+
+        let weeks = summary(&history);
+        assert_eq!((week(&weeks, "sensor1").outages, week(&weeks, "sensor1").longest_gap), (1, 3 * HOUR));
+        assert_eq!((week(&weeks, "sensor2").outages, week(&weeks, "sensor2").longest_gap), (1, HOUR));
+    }
+
+    #[test]
+    fn new_device_counts_from_its_first_reading_and_a_vanished_one_is_silent() {
+        let mut history = History::default();
+        readings(&mut history, "sensor2", "temperature", START - 7 * 24 * HOUR, START - 2 * HOUR, &[]);
+        readings(&mut history, "sensor3", "temperature", START + 72 * HOUR, END, &[]);
+
+        let weeks = summary(&history);
+        let new = week(&weeks, "sensor3");
+        assert!(new.new && new.outages == 0 && new.availability == Some(1.0));
+        let vanished = week(&weeks, "sensor2");
+        assert!(vanished.silent && vanished.availability == Some(0.0));
+        assert_eq!((vanished.outages, vanished.longest_gap), (1, END - START));
+    }
+}
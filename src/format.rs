@@ -0,0 +1,1090 @@
+// Texte der Antworten und Meldungen: Status, Verlauf, Einstellungen,
+// Warnungen. Reine Formatierung ohne Zugriff auf Telegram.
+
+use crate::alerts::{Alert, format_alert_in};
+use crate::digest::AlertMode;
+use crate::episode::Episode;
+use crate::escalation::Escalation;
+use crate::fetch::all_sources;
+use crate::handover::Handover;
+use crate::history::History;
+use crate::i18n::Lang;
+use crate::layout::Layout;
+use crate::messenger::{self, SendError};
+use crate::metrics::AiringEffect;
+use crate::monitor::ThresholdEvent;
+use crate::notify::NotificationMode;
+use crate::profiles::Profiles;
+use crate::records::{NewRecord, Records};
+use crate::routing::Severity;
+use crate::schedule::{WeeklySchedule, weekday_name};
+use crate::sensor::{SensorKind, ThresholdKey};
+use crate::snapshot::{Change, Snapshot};
+use crate::state::UserConfig;
+use crate::timeformat::TimeFormat;
+use crate::units::TempUnit;
+use crate::uptime::UptimeLog;
+use crate::{
+    HISTORY_GAP_SECONDS, SensorData, Trends, alarm_log, alerts, blocked, bot_status, chat_timezone, format_duration, format_local, format_local_in,
+    format_poll_interval, format_reading_time, format_timestamp, format_timestamp_in, i18n, known_chats, latest, local_year, metrics, next_fire, poll_interval,
+    profiles, quantities, records, room_name, rooms, settings, snapshot, snooze, stats, tenant_of, timeutil, timezone_name, type_label_in, unit_in, uptime,
+    weather,
+};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::collections::{BTreeMap, HashMap};
+
+// Was /clear all und /forgetme löschen würden, als Aufzählung
+pub fn config_summary(config: &UserConfig, lang: Lang) -> String {
+    let count = |key: &str, n: usize| i18n::message_with(lang, key, &[("count", &n.to_string())]);
+    let mut lines = vec![count("summary_thresholds", config.thresholds.len())];
+    if !config.notes.is_empty() {
+        lines.push(count("summary_notes", config.notes.len()));
+    }
+    if config.report_schedule.is_some() {
+        lines.push(i18n::message(lang, "summary_report").to_string());
+    }
+    if config.quiet_hours.is_some() {
+        lines.push(i18n::message(lang, "summary_quiet").to_string());
+    }
+    if config.api_token.is_some() {
+        lines.push(i18n::message(lang, "summary_api_token").to_string());
+    }
+    lines.iter().map(|line| format!("• {}", line)).collect::<Vec<_>>().join("\n")
+}
+
+// Inhalt einer Sicherung für /backup und die Vorschau von /restore
+pub fn backup_summary(config: &UserConfig, lang: Lang) -> String {
+    let count = |key: &str, n: usize| i18n::message_with(lang, key, &[("count", &n.to_string())]);
+    let mut lines = vec![count("summary_thresholds", config.thresholds.len())];
+    if !config.rates.is_empty() {
+        lines.push(count("summary_rates", config.rates.len()));
+    }
+    if !config.relations.is_empty() {
+        lines.push(count("summary_relations", config.relations.len()));
+    }
+    if config.report_schedule.is_some() {
+        lines.push(i18n::message(lang, "summary_report").to_string());
+    }
+    if let Some(schedule) = &config.daily_summary {
+        lines.push(i18n::message_with(lang, "summary_daily", &[("schedule", &schedule.to_string())]));
+    }
+    if let Some(schedule) = &config.weekly_report {
+        lines.push(i18n::message_with(lang, "summary_weekly", &[("schedule", &schedule.to_string())]));
+    }
+    if config.quiet_hours.is_some() {
+        lines.push(i18n::message(lang, "summary_quiet").to_string());
+    }
+    if !config.notes.is_empty() {
+        lines.push(count("summary_notes", config.notes.len()));
+    }
+    if config.watering.is_some() {
+        lines.push(i18n::message(lang, "summary_watering").to_string());
+    }
+    lines.push(i18n::message_with(
+        lang,
+        "summary_locale",
+        &[("lang", config.lang.code()), ("units", config.units.symbol()), ("zone", &timezone_name(config.timezone.or(settings().timezone)))],
+    ));
+    lines.iter().map(|line| format!("• {}", line)).collect::<Vec<_>>().join("\n")
+}
+
+// /users: bekannte Chats, zuletzt aktive zuerst, in Nachrichten unter der
+// Längengrenze von Telegram
+pub fn known_chat_list(configs: &HashMap<i64, UserConfig>, tz: Option<Tz>, lang: Lang) -> Vec<String> {
+    let known = known_chats();
+    let mut chats: Vec<_> = known.iter().collect();
+    chats.sort_by_key(|(_, chat)| std::cmp::Reverse(chat.last_command));
+    let mut messages = vec![i18n::message_with(lang, "users_title", &[("count", &chats.len().to_string())])];
+    for (&chat_id, chat) in chats {
+        let config = configs.get(&chat_id);
+        let name = config.and_then(|c| c.first_name.as_deref()).map(|name| format!(" ({})", name)).unwrap_or_default();
+        let last = match chat.last_command {
+            Some(at) => i18n::message_with(lang, "users_last", &[("time", &format_local_in(at, "%d.%m.%Y %H:%M", tz))]),
+            None => i18n::message(lang, "users_no_command").to_string(),
+        };
+        let line = i18n::message_with(
+            lang,
+            "users_line",
+            &[("chat", &chat_id.to_string()), ("name", &name), ("thresholds", &config.map_or(0, |c| c.thresholds.len()).to_string()), ("last", &last)],
+        );
+        let line = format!("\n{}{}", line, if blocked(chat_id) { i18n::message(lang, "users_blocked") } else { "" });
+        let current = messages.last_mut().expect("mindestens die Kopfzeile");
+        if current.len() + line.len() > 3500 {
+            messages.push(line.trim_start().to_string());
+        } else {
+            current.push_str(&line);
+        }
+    }
+    messages
+}
+
+// Text einer Warnung für den Chat des Ereignisses, dazu die Zeile für
+// Sammelwarnungen und die Stufe. `synthetic` markiert Testwerte (/inject).
+pub fn alert_message(config: Option<&UserConfig>, event: &ThresholdEvent, history: &History, synthetic: bool) -> (String, String, Severity) {
+    let key = ThresholdKey::new(event.sensor_type.clone(), event.direction);
+    let (lang, units, times) = config.map(|c| (c.lang, c.units, c.time_format)).unwrap_or_default();
+    let tz = chat_timezone(config);
+    let type_label = type_label_in(lang, event.sensor_type.as_str()).0;
+    let unit = unit_in(units, event.sensor_type.as_str());
+    let show = |value: f64| units.show(&event.sensor_type, value);
+    let trend_since = history
+        .trend_start(&event.device_id, event.sensor_type.as_str(), !event.direction.is_min())
+        .map(|(ts, value)| (format_timestamp_in(ts, "%H:%M", tz), show(value)));
+    let registry = rooms();
+    let room = room_name(&event.device_id);
+    let alert = Alert {
+        room: &room,
+        type_label,
+        unit,
+        direction: event.direction,
+        value: show(event.value),
+        threshold: show(event.threshold.unwrap_or_default()),
+        trend_since,
+        source: Some(event.source.as_str()).filter(|s| !s.is_empty()),
+        tip: registry.tip(&event.device_id, &key),
+    };
+    // Eigene Stufe der Schwelle vor der Einstufung nach [routing]
+    let style = config.and_then(|c| c.alert_styles.get(&(event.device_id.clone(), key.clone())));
+    let severity =
+        style.and_then(|s| s.severity).unwrap_or_else(|| registry.routing().severity(&event.sensor_type, event.value, event.threshold.unwrap_or_default()));
+    let custom = style.and_then(|s| s.text.as_deref());
+    let text = alerts::styled_alert(format_alert_in(&alert, lang), severity, custom, lang);
+    let line = alerts::format_alert_line(&alert, lang);
+    let line = match custom {
+        Some(custom) => format!("{} – {}", line, custom),
+        None => line,
+    };
+    let text = if synthetic { format!("{}\n{}", text, i18n::message(lang, "alert_synthetic")) } else { text };
+    let text = match times {
+        TimeFormat::Absolute => text,
+        _ => format!("{}\n🕒 {}", text, format_reading_time(event.timestamp, "%H:%M", tz, times, lang)),
+    };
+    let text = match config.and_then(|c| c.notes.get(&event.device_id)) {
+        Some(note) => format!("{}\n📝 {}", text, note),
+        None => text,
+    };
+    let text = match config.and_then(|c| c.configured_by.get(&(event.device_id.clone(), key.clone()))) {
+        Some(setter) => format!("{}\n👤 {}", text, setter.mention()),
+        None => text,
+    };
+    (text, line, severity)
+}
+
+// Entwarnung mit Zusammenfassung: Dauer und schlimmster Wert der Verletzung
+pub fn format_recovery(event: &ThresholdEvent, episode: &Episode, lang: Lang, units: TempUnit, tz: Option<Tz>) -> String {
+    let type_label = type_label_in(lang, event.sensor_type.as_str()).0;
+    let unit = unit_in(units, event.sensor_type.as_str());
+    let show = |value: f64| format!("{:.1}", units.show(&event.sensor_type, value));
+    let duration = timeutil::seconds_between(episode.started, event.timestamp);
+    let at = format_timestamp_in(episode.worst_at, if duration >= 24 * 60 * 60 { "%d.%m. %H:%M" } else { "%H:%M" }, tz);
+    let mut text =
+        i18n::message_with(lang, "recovery", &[("type", type_label), ("room", &room_name(&event.device_id)), ("value", &show(event.value)), ("unit", unit)]);
+    if let Some(threshold) = event.threshold {
+        let bound = if event.direction.is_min() { "Min" } else { "Max" };
+        text.push_str(&format!(" ({} {} {})", bound, show(threshold), unit));
+    }
+    let summary = if event.direction.is_min() { "recovery_lowest" } else { "recovery_highest" };
+    text.push('\n');
+    text.push_str(&i18n::message_with(
+        lang,
+        summary,
+        &[("duration", &format_duration(duration)), ("worst", &show(episode.worst)), ("unit", unit), ("at", &at)],
+    ));
+    match episode.reminders {
+        0 => {}
+        1 => text.push_str(&format!("\n{}", i18n::message(lang, "recovery_reminder"))),
+        n => text.push_str(&format!("\n{}", i18n::message_with(lang, "recovery_reminders", &[("count", &n.to_string())]))),
+    }
+    if episode.escalated {
+        text.push_str(&format!("\n{}", i18n::message(lang, "recovery_escalated")));
+    }
+    text
+}
+
+// Angezeigte Werte ohne Zeitstempel: nur wenn sie sich ändern, wird bearbeitet
+pub fn live_values(readings: &[SensorData]) -> String {
+    readings
+        .iter()
+        .map(|r| {
+            let decimals = quantities::lookup(r.sensor_type.as_str()).map_or(1, |q| q.decimals);
+            format!("{}/{}={:.*}", r.device_id, r.sensor_type, decimals, r.value)
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+pub fn live_text(readings: &[SensorData], trends: &Trends, config: &UserConfig) -> String {
+    let tz = chat_timezone(Some(config));
+    format!(
+        "{}\n{}",
+        format_status(readings, trends, config.lang, config.units, tz, config.time_format),
+        escape_markdown(&format!("🔄 zuletzt aktualisiert: {}", format_local_in(Utc::now(), "%d.%m. %H:%M", tz)))
+    )
+}
+
+// Notizen der angezeigten Geräte in Reihenfolge der Messwerte: (Raum, Notiz)
+pub fn notes_for<'a>(notes: &'a BTreeMap<String, String>, sensor_data: &'a [SensorData]) -> impl Iterator<Item = (String, &'a str)> {
+    let mut seen: Vec<&str> = Vec::new();
+    sensor_data.iter().filter_map(move |entry| {
+        if seen.contains(&entry.device_id.as_str()) {
+            return None;
+        }
+        seen.push(&entry.device_id);
+        notes.get(&entry.device_id).map(|note| (room_name(&entry.device_id), note.as_str()))
+    })
+}
+
+// Zusätzliche Ziele je Stufe mit dem Ergebnis der Testnachricht, für /debug
+pub fn format_routing(checks: &BTreeMap<i64, Result<(), SendError>>, lang: Lang) -> String {
+    let registry = rooms();
+    let routing = registry.routing();
+    let mut text = format!("{}\n", i18n::message(lang, "routing_title"));
+    for (severity, targets) in [(Severity::Warning, &routing.warning), (Severity::Critical, &routing.critical)] {
+        text.push_str(&format!("\n{}", i18n::message_with(lang, "routing_level", &[("severity", severity.label(lang))])));
+        if targets.is_empty() {
+            text.push_str(&format!(" {}", i18n::message(lang, "routing_owners_only")));
+        }
+        for target in targets {
+            let status = match checks.get(target) {
+                Some(Ok(())) => i18n::message(lang, "routing_reachable").to_string(),
+                Some(Err(err)) => format!("❌ {}", messenger::send_error_in(err, lang)),
+                None => i18n::message(lang, "routing_unchecked").to_string(),
+            };
+            text.push_str(&format!("\n  {} – {}", target, status));
+        }
+    }
+    text.push_str(&format!("\n\n{}", i18n::message(lang, "routing_margin")));
+    for (sensor_type, margin) in &routing.critical_margin {
+        let (label, unit) = type_label_in(lang, sensor_type);
+        text.push_str(&format!("\n  {}: {:.1}{}", label, margin, unit));
+    }
+    if !registry.tenants().is_empty() {
+        let ids: Vec<&str> = registry.tenants().iter().map(|t| t.id.as_str()).collect();
+        text.push_str(&format!("\n\n{}", i18n::message_with(lang, "routing_tenants", &[("tenants", &ids.join(", "))])));
+    }
+    text
+}
+
+// Quelle, Chats und Räume eines weiteren Haushalts, für /debug <haushalt>
+pub fn format_tenant(tenant: &rooms::Tenant, lang: Lang) -> String {
+    let mut text = i18n::message_with(lang, "tenant_title", &[("tenant", &tenant.id), ("source", &tenant.source)]);
+    if tenant.members.is_empty() {
+        text.push_str(&format!(" {}", i18n::message(lang, "tenant_no_chats")));
+    }
+    for member in &tenant.members {
+        text.push_str(&format!("\n  {}", member));
+    }
+    text.push_str(&format!("\n\n{}", i18n::message(lang, "tenant_rooms")));
+    for room in rooms().rooms_in(Some(&tenant.id)) {
+        text.push_str(&format!("\n  {} – {}", room.name, room.device));
+    }
+    text
+}
+
+// Tiefst- und Höchstwerte je Raum und Typ, insgesamt und im laufenden Jahr
+pub fn format_records(records: &Records, tenant: Option<&str>, device_id: Option<&str>, lang: Lang) -> String {
+    let year = local_year(Utc::now().timestamp());
+    let extreme = |e: Option<records::Extreme>, unit: &str| match e {
+        Some(e) => format!("{:.1} {} ({})", e.value, unit, format_timestamp(e.timestamp, "%d.%m.%Y")),
+        None => "–".to_string(),
+    };
+    let mut text = format!("{}\n", i18n::message(lang, "records_title"));
+    let mut any = false;
+    let shown = |device: &str| rooms().visible(tenant, device) && device_id.is_none_or(|id| id == device);
+    for (device, sensor_type) in records.keys().into_iter().filter(|(d, _)| shown(d)) {
+        let Some(series) = records.get(device, sensor_type) else { continue };
+        let (typ, unit) = type_label_in(lang, sensor_type);
+        any = true;
+        text.push_str(&format!("📍 {} – {}:\n", markdown_bold(&room_name(device)), escape_markdown(typ)));
+        let all_time = i18n::message(lang, "records_all_time");
+        text.push_str(&format!("   {}: 🔻 {}, 🔺 {}\n", all_time, extreme(series.all_time.min, unit), extreme(series.all_time.max, unit)));
+        if let Some(current) = series.years.get(&year) {
+            text.push_str(&format!("   {}: 🔻 {}, 🔺 {}\n", year, extreme(current.min, unit), extreme(current.max, unit)));
+        }
+    }
+    if !any {
+        return i18n::message(lang, "records_none").to_string();
+    }
+    text
+}
+
+// Neue Rekorde im Statusbericht (Legacy-Markdown)
+pub fn format_new_records(new_records: &[NewRecord], lang: Lang) -> String {
+    let mut text = String::new();
+    for record in new_records {
+        let (typ, unit) = type_label_in(lang, &record.sensor_type);
+        let symbol = match (SensorKind::from(record.sensor_type.as_str()), record.low) {
+            (SensorKind::Temperature, true) => "🥶",
+            (SensorKind::Temperature, false) => "🥵",
+            (_, true) => "📉",
+            (_, false) => "📈",
+        };
+        let line = i18n::message_with(
+            lang,
+            if record.low { "record_new_low" } else { "record_new_high" },
+            &[
+                ("room", &escape_markdown(&room_name(&record.device_id))),
+                ("type", typ),
+                ("value", &format!("{:.1}", record.extreme.value)),
+                ("unit", unit),
+                ("time", &format_timestamp(record.extreme.timestamp, "%H:%M")),
+            ],
+        );
+        text.push_str(&format!("\n{} {}", symbol, line));
+    }
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text
+}
+
+// Notizen als kursive Zeilen unter /status (Legacy-Markdown)
+pub fn format_notes(notes: &BTreeMap<String, String>, sensor_data: &[SensorData]) -> String {
+    notes_for(notes, sensor_data)
+        .map(|(room, note)| {
+            let line = format!("📝 {}: {}", room, note);
+            // In Legacy-Markdown lässt sich "_" innerhalb von Kursivschrift nicht
+            // maskieren, und ein \\ am Ende maskierte das schließende "_"
+            if line.contains(['_', '\\']) { format!("{}\n", escape_markdown(&line)) } else { format!("_{}_\n", escape_markdown(&line)) }
+        })
+        .collect()
+}
+
+pub fn format_status(sensor_data: &[SensorData], trends: &Trends, lang: Lang, units: TempUnit, tz: Option<Tz>, times: TimeFormat) -> String {
+    let mut text = format!("{}\n", i18n::message(lang, "status_title"));
+    let registry = rooms();
+
+    for entry in sensor_data {
+        let raum = room_name(&entry.device_id);
+        let typ = type_label_in(lang, entry.sensor_type.as_str()).0;
+        let einheit = unit_in(units, entry.sensor_type.as_str());
+        let value = units.show(&entry.sensor_type, entry.value);
+        let quantity = quantities::lookup(entry.sensor_type.as_str());
+        let icon = quantity.and_then(|q| q.icon).or_else(|| registry.emoji(&entry.device_id)).unwrap_or("📍");
+        let decimals = quantity.map_or(1, |q| q.decimals);
+
+        let formatted = escape_markdown(&format_reading_time(entry.timestamp, lang.datetime_format(), tz, times, lang));
+
+        let trend = trends.get(&(entry.device_id.clone(), entry.sensor_type.as_str().to_string())).map(|trend| format!(" {}", trend)).unwrap_or_default();
+
+        text.push_str(&format!(
+            "{} {} – {}: *{:.*} {}*{} ({})\n",
+            icon,
+            markdown_bold(&raum),
+            escape_markdown(typ),
+            decimals,
+            value,
+            einheit,
+            trend,
+            formatted
+        ));
+    }
+    text
+}
+
+// /history: Minimum, Maximum, Mittel sowie erster und letzter Rohwert der
+// letzten `hours` Stunden
+#[allow(clippy::too_many_arguments)]
+pub fn format_history(samples: &[(i64, f64)], device_id: &str, sensor_type: &str, hours: i64, now: i64, lang: Lang, units: TempUnit, tz: Option<Tz>) -> String {
+    let typ = type_label_in(lang, sensor_type).0;
+    let unit = unit_in(units, sensor_type);
+    let kind = SensorKind::from(sensor_type);
+    let samples: Vec<(i64, f64)> = samples.iter().map(|&(ts, value)| (ts, units.show(&kind, value))).collect();
+    let since = now - hours * 60 * 60;
+    let (Some(&(first_ts, first)), Some(&(last_ts, last))) = (samples.first(), samples.last()) else {
+        return i18n::message_with(lang, "history_empty", &[("type", typ), ("room", &room_name(device_id)), ("hours", &hours.to_string())]);
+    };
+    let min = samples.iter().map(|(_, v)| *v).fold(f64::INFINITY, f64::min);
+    let max = samples.iter().map(|(_, v)| *v).fold(f64::NEG_INFINITY, f64::max);
+    let mean = samples.iter().map(|(_, v)| v).sum::<f64>() / samples.len() as f64;
+    let mut text = i18n::message_with(
+        lang,
+        "history",
+        &[
+            ("type", typ),
+            ("room", &room_name(device_id)),
+            ("hours", &hours.to_string()),
+            ("count", &samples.len().to_string()),
+            ("min", &format!("{:.1}", min)),
+            ("max", &format!("{:.1}", max)),
+            ("mean", &format!("{:.1}", mean)),
+            ("first", &format!("{:.1}", first)),
+            ("first_at", &format_timestamp_in(first_ts, "%d.%m. %H:%M", tz)),
+            ("last", &format!("{:.1}", last)),
+            ("last_at", &format_timestamp_in(last_ts, "%d.%m. %H:%M", tz)),
+            ("unit", unit),
+        ],
+    );
+    // Weniger Verlauf als angefragt, z.B. kurz nach dem ersten Start
+    if timeutil::seconds_between(since, first_ts) > HISTORY_GAP_SECONDS {
+        let since = format_timestamp_in(first_ts, "%d.%m. %H:%M", tz);
+        text.push_str(&format!("\n{}", i18n::message_with(lang, "history_since", &[("since", &since)])));
+    }
+    text
+}
+
+// Eine Zeile von /alarms
+pub fn format_alarm_entry(entry: &alarm_log::Entry, lang: Lang, units: TempUnit, tz: Option<Tz>) -> String {
+    let typ = type_label_in(lang, entry.sensor_type.as_str()).0;
+    let unit = unit_in(units, entry.sensor_type.as_str());
+    let show = |value: f64| units.show(&entry.sensor_type, value);
+    let comparison = match entry.threshold {
+        Some(threshold) if !entry.recovered => format!(" {} {:.1} {}", if entry.direction.is_min() { "<" } else { ">" }, show(threshold), unit),
+        _ => String::new(),
+    };
+    format!(
+        "{} {} {} {} {}: {:.1} {}{}{}",
+        if entry.recovered { "✅" } else { "🚨" },
+        format_timestamp_in(entry.timestamp, "%d.%m. %H:%M", tz),
+        room_name(&entry.device_id),
+        typ,
+        entry.direction,
+        show(entry.value),
+        unit,
+        comparison,
+        entry.suppressed.map(|s| format!(" ({})", s.label(lang))).unwrap_or_default()
+    )
+}
+
+// /stats: je Zeitraum Min und Max mit Zeitpunkt sowie Mittel
+pub fn format_stats(samples: &[(i64, f64)], device_id: &str, sensor_type: &str, now: i64, lang: Lang, units: TempUnit, tz: Option<Tz>) -> String {
+    let typ = type_label_in(lang, sensor_type).0;
+    let unit = unit_in(units, sensor_type);
+    let kind = SensorKind::from(sensor_type);
+    let decimals = quantities::lookup(sensor_type).map_or(1, |q| q.decimals);
+    let show = |value: f64| format!("{:.*} {}", decimals, units.show(&kind, value), unit);
+    let mut text = format!("📊 {} {}", typ, room_name(device_id));
+    for (label, seconds, fmt) in [("stats_day", 24 * 60 * 60, "%H:%M"), ("stats_week", 7 * 24 * 60 * 60, "%d.%m. %H:%M")] {
+        let label = i18n::message(lang, label);
+        let since = now - seconds;
+        let window: Vec<(i64, f64)> = samples.iter().filter(|(ts, _)| *ts >= since).copied().collect();
+        let Some(aggregate) = stats::aggregate(&window) else {
+            text.push_str(&format!("\n\n{}", i18n::message_with(lang, "stats_empty", &[("period", label)])));
+            continue;
+        };
+        let block = i18n::message_with(
+            lang,
+            "stats_period",
+            &[
+                ("period", label),
+                ("count", &aggregate.count.to_string()),
+                ("min", &show(aggregate.min.1)),
+                ("min_at", &format_timestamp_in(aggregate.min.0, fmt, tz)),
+                ("max", &show(aggregate.max.1)),
+                ("max_at", &format_timestamp_in(aggregate.max.0, fmt, tz)),
+                ("mean", &show(aggregate.mean)),
+            ],
+        );
+        text.push_str(&format!("\n\n{}", block));
+        if timeutil::seconds_between(since, aggregate.first) > HISTORY_GAP_SECONDS {
+            let first = format_timestamp_in(aggregate.first, "%d.%m. %H:%M", tz);
+            text.push_str(&format!("\n{}", i18n::message_with(lang, "stats_short", &[("since", &first)])));
+        }
+    }
+    text
+}
+
+// /sensors: je Gerät Raum, gemeldete Typen und Alter des neuesten Werts,
+// in der Reihenfolge der Raumdatei, sonst nach Geräte-ID und Typ sortiert,
+// damit sich Aufrufe vergleichen lassen
+pub fn format_sensors(sensor_data: &[SensorData], now: i64, lang: Lang) -> String {
+    let mut devices: BTreeMap<&str, (Vec<&str>, i64)> = BTreeMap::new();
+    for entry in sensor_data {
+        let device = devices.entry(&entry.device_id).or_insert((Vec::new(), i64::MIN));
+        device.0.push(entry.sensor_type.as_str());
+        device.1 = device.1.max(entry.timestamp);
+    }
+    if devices.is_empty() {
+        return i18n::message(lang, "sensors_none").to_string();
+    }
+    let mut text = format!("{}\n", i18n::message_with(lang, "sensors_title", &[("count", &devices.len().to_string())]));
+    let mut devices: Vec<_> = devices.into_iter().collect();
+    devices.sort_by_key(|(device_id, _)| rooms().order(device_id));
+    for (device_id, (mut types, newest)) in devices {
+        types.sort_unstable();
+        types.dedup();
+        let room = match room_name(device_id) {
+            name if name == device_id => i18n::message(lang, "sensors_no_room").to_string(),
+            name => name.to_string(),
+        };
+        let age = format_duration(timeutil::seconds_between(newest, now).max(0));
+        let line = i18n::message_with(lang, "sensors_line", &[("device", device_id), ("room", &room), ("types", &types.join(", ")), ("age", &age)]);
+        text.push_str(&format!("{}\n", line));
+    }
+    text.push_str(i18n::message(lang, "sensors_hint"));
+    text
+}
+
+// Zeichen, die in Legacy-Markdown eine Bedeutung haben
+pub fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '_' | '*' | '`' | '[') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// Fettdruck für Namen aus Raumdatei und Geräte-IDs. Innerhalb von *…* lässt
+// sich in Legacy-Markdown nichts maskieren; Namen wie "outdoor_balcony"
+// erscheinen deshalb maskiert und ohne Fettdruck, ebenso Namen mit \\, der
+// am Ende das schließende * maskieren würde.
+pub fn markdown_bold(text: &str) -> String {
+    if text.contains(['_', '*', '`', '[', '\\']) { escape_markdown(text) } else { format!("*{}*", text) }
+}
+
+// Verpasste Warnungen aus der Ruhezeit, optional zusammen mit dem Statusbericht
+pub fn format_digest(missed: &[(DateTime<Utc>, String)], status: Option<&str>, tz: Option<Tz>) -> String {
+    let mut text = String::new();
+    if !missed.is_empty() {
+        text.push_str(&format!("🔕 *Verpasste Warnungen ({}):*\n", missed.len()));
+        for (at, alert) in missed {
+            text.push_str(&format!("{} – {}\n", format_local_in(*at, "%H:%M", tz), escape_markdown(alert)));
+        }
+    }
+    if let Some(status) = status {
+        if !text.is_empty() {
+            text.push_str("\n📅 *Tageszusammenfassung*\n");
+        }
+        text.push_str(status);
+    }
+    text
+}
+
+// Sammelmeldung im Modus /alert-mode digest (Legacy-Markdown)
+pub fn format_alert_digest(entries: &[(DateTime<Utc>, String)], tz: Option<Tz>) -> String {
+    let mut text = format!("🗂 *Sammelmeldung ({}):*\n", entries.len());
+    for (at, line) in entries {
+        text.push_str(&format!("{} – {}\n", format_local_in(*at, "%H:%M", tz), escape_markdown(line)));
+    }
+    text
+}
+
+// Zeile einer Warnung für die Sammelmeldung, ohne Aufzählungszeichen
+pub fn digest_line(lines: &[String]) -> String {
+    lines.iter().map(|line| line.trim_start_matches("• ")).collect::<Vec<_>>().join("\n")
+}
+
+// Was der Modus von /notifications lautlos zustellt
+pub fn notification_summary(mode: NotificationMode, lang: Lang) -> &'static str {
+    match mode {
+        NotificationMode::Loud => i18n::message(lang, "notifications_loud"),
+        NotificationMode::Normal => i18n::message(lang, "notifications_normal"),
+        NotificationMode::Quiet => i18n::message(lang, "notifications_quiet"),
+    }
+}
+
+pub fn format_alert_mode(mode: AlertMode, lang: Lang) -> String {
+    match mode {
+        AlertMode::Instant => i18n::message(lang, "alert_mode_is_instant").to_string(),
+        AlertMode::Digest { minutes } => i18n::message_with(lang, "alert_mode_is_digest", &[("interval", &format_duration(minutes * 60))]),
+    }
+}
+
+// Änderungen je Raum seit dem letzten Ansehen
+pub fn format_diff(previous: &Snapshot, current: &Snapshot, lang: Lang, units: TempUnit) -> String {
+    let elapsed = format_duration((current.at - previous.at).num_seconds());
+    let since = format_local(previous.at, "%d.%m. %H:%M");
+    let mut text = format!("{}\n", i18n::message_with(lang, "diff_title", &[("since", &since), ("elapsed", &elapsed)]));
+    for ((device, sensor_type), change) in snapshot::diff(previous, current) {
+        let typ = type_label_in(lang, sensor_type.as_str()).0;
+        let einheit = unit_in(units, sensor_type.as_str());
+        let kind = SensorKind::from(sensor_type.as_str());
+        let show = |value: f64| units.show(&kind, value);
+        let zeile = match change {
+            Change::Changed { before, after } => {
+                let delta = after - before;
+                let pfeil = if delta >= 0.05 {
+                    "↑"
+                } else if delta <= -0.05 {
+                    "↓"
+                } else {
+                    "→"
+                };
+                let delta = format!("{:+.1}", units.show_delta(&kind, delta));
+                let now = format!("{:.1}", show(after));
+                let change = i18n::message_with(lang, "diff_changed", &[("delta", &delta), ("unit", einheit), ("elapsed", &elapsed), ("now", &now)]);
+                format!("{} {}", pfeil, change)
+            }
+            Change::Appeared { value } => i18n::message_with(lang, "diff_appeared", &[("value", &format!("{:.1}", show(value))), ("unit", einheit)]),
+            Change::Disappeared { value } => i18n::message_with(lang, "diff_disappeared", &[("value", &format!("{:.1}", show(value))), ("unit", einheit)]),
+        };
+        text.push_str(&format!("📍 {}: {} {}\n", markdown_bold(&room_name(&device)), escape_markdown(typ), zeile));
+    }
+    text
+}
+
+pub fn format_health(uptime: &UptimeLog, escalation: &Escalation, tenant: Option<&str>, latencies: &[i64], now: i64, lang: Lang) -> String {
+    let line = |key: &str, values: &[(&str, &str)]| format!("{}\n", i18n::message_with(lang, key, values));
+    let mut text = line("health_title", &[]);
+    if let Some(started) = uptime.started_at() {
+        text.push_str(&line("health_running", &[("since", &format_timestamp(started, "%d.%m.%Y %H:%M")), ("duration", &format_duration(now - started))]));
+    }
+    if let Some(heartbeat) = uptime.last_heartbeat() {
+        text.push_str(&line("health_heartbeat", &[("time", &format_timestamp(heartbeat, "%H:%M"))]));
+    }
+    let (last_fetch, failures) = {
+        let status = bot_status();
+        (status.last_fetch.clone(), status.fetch_failures)
+    };
+    match last_fetch {
+        Some((at, None)) => {
+            let devices: std::collections::BTreeSet<String> = latest()
+                .as_ref()
+                .map(|snapshot| snapshot.readings.iter().filter(|r| rooms().visible(tenant, &r.device_id)).map(|r| r.device_id.clone()).collect())
+                .unwrap_or_default();
+            text.push_str(&line("health_fetch", &[("time", &format_timestamp(at.timestamp(), "%H:%M")), ("devices", &devices.len().to_string())]));
+        }
+        Some((at, Some(error))) => text.push_str(&line(
+            "health_fetch_failed",
+            &[("time", &format_timestamp(at.timestamp(), "%H:%M")), ("error", &escape_markdown(&error.in_lang(lang))), ("count", &failures.to_string())],
+        )),
+        None => text.push_str(&line("health_fetch_none", &[])),
+    }
+    text.push_str(&line("health_interval", &[("interval", &format_poll_interval(poll_interval()))]));
+    let own: Vec<String> = all_sources().iter().filter_map(|(_, source)| source.poll_interval()).map(|d| format_poll_interval(d.as_secs())).collect();
+    if !own.is_empty() {
+        text.push_str(&line("health_source_intervals", &[("intervals", &own.join(", "))]));
+    }
+
+    let since = now - 7 * 24 * 60 * 60;
+    let recent: Vec<_> = uptime.downtime().iter().filter(|d| d.end > since).collect();
+    let total = uptime::overlap(since, now, &recent.iter().map(|d| (d.start, d.end)).collect::<Vec<_>>());
+    let unclean = recent.iter().filter(|d| !d.clean).count();
+    let unexpected = if unclean > 0 { i18n::message_with(lang, "health_unexpected", &[("count", &unclean.to_string())]) } else { String::new() };
+    text.push_str(&line("health_downtime", &[("count", &recent.len().to_string()), ("total", &format_duration(total)), ("unexpected", &unexpected)]));
+    for (device_id, remaining) in escalation.active(tokio::time::Instant::now()).into_iter().filter(|(d, _)| rooms().visible(tenant, d)) {
+        text.push_str(&line(
+            "health_escalated",
+            &[
+                ("room", &room_name(&device_id)),
+                ("interval", &format_duration(escalation.interval().as_secs() as i64)),
+                ("remaining", &format_duration(remaining.as_secs() as i64)),
+            ],
+        ));
+    }
+    if !latencies.is_empty() {
+        let mut sorted = latencies.to_vec();
+        sorted.sort_unstable();
+        text.push_str(&line(
+            "health_latency",
+            &[
+                ("count", &sorted.len().to_string()),
+                ("median", &format_duration(sorted[sorted.len() / 2])),
+                ("max", &format_duration(sorted[sorted.len() - 1])),
+            ],
+        ));
+    }
+    text
+}
+
+fn format_mute(config: &UserConfig) -> String {
+    match config.muted_until {
+        Some(until) => {
+            let line = i18n::message_with(
+                config.lang,
+                "settings_muted_until",
+                &[("until", &format_local(until, "%d.%m. %H:%M")), ("missed", &config.muted_missed.to_string())],
+            );
+            format!("{}\n", line)
+        }
+        None => String::new(),
+    }
+}
+
+// Unterhalb dieses Temperaturunterschieds ist es draußen "etwa gleich warm"
+const SAME_TEMPERATURE: f64 = 0.5;
+// Zeilen für /status: je Innenraum mit Temperatur und Feuchte, ob Lüften
+// bei diesem Wetter hilft. Ohne Wetter (kein Standort, Dienst nicht
+// erreichbar) keine.
+pub fn outdoor_comparison(readings: &[SensorData], weather: Option<weather::Weather>, lang: Lang, units: TempUnit) -> Vec<String> {
+    let Some(weather) = weather else { return Vec::new() };
+    let mut devices: Vec<&str> = Vec::new();
+    for reading in readings {
+        if !devices.contains(&reading.device_id.as_str()) && !rooms().is_outdoor(&reading.device_id) {
+            devices.push(&reading.device_id);
+        }
+    }
+    let value = |device_id: &str, kind: SensorKind| readings.iter().find(|r| r.device_id == device_id && r.sensor_type == kind).map(|r| r.value);
+    let unit = units.symbol();
+    devices
+        .into_iter()
+        .filter_map(|device_id| {
+            let (temperature, humidity) = (value(device_id, SensorKind::Temperature)?, value(device_id, SensorKind::Humidity)?);
+            let airing = metrics::airing(temperature, humidity, weather.temperature, weather.humidity)?;
+            let difference = units.show_delta(&SensorKind::Temperature, airing.outdoor_warmer_by.abs());
+            let outside = format!("{:.1} {}, {:.0} %", units.show(&SensorKind::Temperature, weather.temperature), unit, weather.humidity);
+            let comparison = match (lang, airing.outdoor_warmer_by) {
+                (Lang::De, d) if d.abs() < SAME_TEMPERATURE => format!("draußen etwa gleich warm ({})", outside),
+                (Lang::De, d) => format!("draußen {:.1} {} {} ({})", difference, unit, if d < 0.0 { "kühler" } else { "wärmer" }, outside),
+                (Lang::En, d) if d.abs() < SAME_TEMPERATURE => format!("about as warm outside ({})", outside),
+                (Lang::En, d) => format!("{:.1} {} {} outside ({})", difference, unit, if d < 0.0 { "cooler" } else { "warmer" }, outside),
+            };
+            let hint = match (lang, airing.effect) {
+                (Lang::De, AiringEffect::Drier) => "Lüften senkt die Luftfeuchtigkeit",
+                (Lang::De, AiringEffect::Wetter) => "Lüften bringt feuchtere Luft herein",
+                (Lang::De, AiringEffect::Neutral) => "an der Luftfeuchtigkeit ändert Lüften wenig",
+                (Lang::En, AiringEffect::Drier) => "airing lowers the humidity",
+                (Lang::En, AiringEffect::Wetter) => "airing lets in more humid air",
+                (Lang::En, AiringEffect::Neutral) => "airing hardly changes the humidity",
+            };
+            Some(format!("🌤 {}: {} – {}", room_name(device_id), comparison, hint))
+        })
+        .collect()
+}
+
+// Fußzeilen von /status: wann als Nächstes abgefragt wird, der nächste
+// eigene Bericht sowie laufende Stummschaltungen. Was nicht zutrifft, fehlt.
+pub fn status_footer(config: Option<&UserConfig>, chat_id: i64, now: DateTime<Utc>) -> Vec<String> {
+    let lang = config.map(|c| c.lang).unwrap_or_default();
+    let mut lines = Vec::new();
+    let (next_poll, next_report) = {
+        let status = bot_status();
+        (status.next_poll, status.next_reports.get(&chat_id).copied())
+    };
+    if let Some(at) = next_poll.filter(|at| *at > now) {
+        let seconds = (at - now).num_seconds();
+        let wann = if seconds < 60 {
+            i18n::message(lang, "footer_poll_soon").to_string()
+        } else {
+            i18n::message_with(lang, "footer_poll_in", &[("duration", &format_duration(seconds))])
+        };
+        lines.push(i18n::message_with(lang, "footer_poll", &[("when", &wann), ("time", &format_local(at, "%H:%M"))]));
+    }
+    if let Some(at) = next_report {
+        lines.push(i18n::message_with(lang, "footer_report", &[("time", &format_local(at, "%d.%m. %H:%M"))]));
+    }
+    let skipped = bot_status().skipped.get(&tenant_of(chat_id)).copied().unwrap_or(0);
+    if skipped > 0 {
+        lines.push(i18n::message_with(lang, "footer_skipped", &[("count", &skipped.to_string())]));
+    }
+    let Some(config) = config else { return lines };
+    if config.is_muted(now) {
+        lines.push(format_mute(config).trim_end().to_string());
+    }
+    let snoozed = config.snoozed.values().filter(|snooze| snooze.until > now).count();
+    match snoozed {
+        0 => {}
+        1 => lines.push(i18n::message(lang, "footer_snoozed_one").to_string()),
+        n => lines.push(i18n::message_with(lang, "footer_snoozed", &[("count", &n.to_string())])),
+    }
+    lines
+}
+
+// Hinweis auf Stummschaltungen, die wegen der Obergrenze enden
+pub fn format_snoozes_ended(ended: &[(String, ThresholdKey)], lang: Lang) -> String {
+    if ended.is_empty() {
+        return String::new();
+    }
+    let names: Vec<String> = ended
+        .iter()
+        .map(|(device_id, key)| format!("{} {} {}", room_name(device_id), type_label_in(lang, key.kind.as_str()).0, key.direction.as_str().to_uppercase()))
+        .collect();
+    let text = i18n::message_with(lang, "snoozes_ended", &[("max", &snooze::MAX_ACTIVE.to_string()), ("names", &names.join(", "))]);
+    format!("\n{}", text)
+}
+
+// /snoozes: aktive Stummschaltungen, die zuerst endende zuerst
+pub fn format_snoozes(config: &UserConfig, now: DateTime<Utc>) -> String {
+    let mut active: Vec<_> = config.snoozed.iter().filter(|(_, snooze)| snooze.until > now).collect();
+    let lang = config.lang;
+    if active.is_empty() {
+        return i18n::message(lang, "snoozes_none").to_string();
+    }
+    active.sort_by_key(|(_, snooze)| snooze.until);
+    let mut text = i18n::message(lang, "snoozes_title").to_string();
+    for ((device_id, key), snooze) in active {
+        let line = i18n::message_with(
+            lang,
+            "snoozes_line",
+            &[
+                ("room", &room_name(device_id)),
+                ("type", type_label_in(lang, key.kind.as_str()).0),
+                ("direction", &key.direction.as_str().to_uppercase()),
+                ("left", &format_duration((snooze.until - now).num_seconds())),
+                ("until", &format_local(snooze.until, "%H:%M")),
+                ("origin", snooze.origin.label(lang)),
+            ],
+        );
+        text.push_str(&format!("\n{}", line));
+    }
+    text.push_str(&format!("\n{}", i18n::message(lang, "snoozes_clear_hint")));
+    text
+}
+
+pub fn format_settings(config: &UserConfig) -> String {
+    let lang = config.lang;
+    let at = |time: String| i18n::message_with(lang, "clock_time", &[("time", &time)]);
+    // "keiner" für Bericht, "keine" für Zusammenfassung und Ruhezeit
+    let none = |key: &str| i18n::message(lang, key).to_string();
+    let bericht = config.report_schedule.as_ref().map(|s| s.to_string()).unwrap_or_else(|| none("settings_none_report"));
+    let zusammenfassung = config.daily_summary.as_ref().map(|s| at(s.to_string())).unwrap_or_else(|| none("settings_none"));
+    let wochenbericht = config.weekly_report.as_ref().map(|s| at(s.to_string())).unwrap_or_else(|| none("settings_none_report"));
+    let ruhezeit = config.quiet_hours.map(|w| at(w.to_string())).unwrap_or_else(|| none("settings_none"));
+    let layout = if config.layout == Layout::Table { "settings_layout_table" } else { "settings_layout_classic" };
+    let zeitangaben = match config.time_format {
+        TimeFormat::Absolute => "settings_time_absolute",
+        TimeFormat::Relative => "settings_time_relative",
+        TimeFormat::Both => "settings_time_both",
+    };
+    let raumbilder = if config.room_images { "settings_on" } else { "off" };
+    let mut text = i18n::message_with(
+        lang,
+        "settings",
+        &[
+            ("thresholds", &config.thresholds.len().to_string()),
+            ("report", &bericht),
+            ("daily", &zusammenfassung),
+            ("weekly", &wochenbericht),
+            ("quiet", &ruhezeit),
+            ("alert_mode", &format_alert_mode(config.alert_mode, lang)),
+            ("notifications", &config.notifications.to_string()),
+            ("layout", i18n::message(lang, layout)),
+            ("times", i18n::message(lang, zeitangaben)),
+            ("units", config.units.symbol()),
+            ("zone", &timezone_name(chat_timezone(Some(config)))),
+            ("notes", &config.notes.len().to_string()),
+            ("images", i18n::message(lang, raumbilder)),
+            ("interval", &format_poll_interval(poll_interval())),
+        ],
+    );
+    text.push('\n');
+    if let Some(handover) = &config.escalate_to {
+        text.push_str(&format!("{}\n", i18n::message_with(lang, "settings_handover", &[("handover", &format_handover(handover, lang))])));
+    }
+    if let Some(watering) = config.watering {
+        let line = i18n::message_with(lang, "settings_watering", &[("above", &format!("{:.1}", watering.above)), ("days", &watering.days.to_string())]);
+        text.push_str(&format!("{}\n", line));
+    }
+    if config.is_muted(Utc::now()) {
+        text.push_str(&format_mute(config));
+    } else {
+        text.push_str(&format!("{}\n", i18n::message(lang, "settings_not_muted")));
+    }
+    text
+}
+
+pub fn format_profiles(profiles: &Profiles, lang: Lang) -> String {
+    if profiles.profiles.is_empty() {
+        return format!("{}\n{}", i18n::message(lang, "profiles_none"), profiles::usage(lang));
+    }
+    let mut text = i18n::message(lang, "profiles_title").to_string();
+    for (name, profile) in &profiles.profiles {
+        let count = profile.thresholds.len().to_string();
+        let line = match profile.window {
+            Some(window) => i18n::message_with(lang, "profile_line_window", &[("name", name), ("count", &count), ("window", &window.to_string())]),
+            None => i18n::message_with(lang, "profile_line", &[("name", name), ("count", &count)]),
+        };
+        let active = if profiles.active.as_deref() == Some(name) { i18n::message(lang, "profile_active_mark") } else { "" };
+        text.push_str(&format!("\n{}{}", line, active));
+    }
+    if profiles.manual.is_some() {
+        text.push_str(&format!("\n{}", i18n::message(lang, "profiles_manual")));
+    }
+    text
+}
+
+pub fn format_handover(handover: &Handover, lang: Lang) -> String {
+    let key = if handover.from == Severity::Critical { "handover_critical" } else { "handover_all" };
+    i18n::message_with(lang, key, &[("duration", &format_duration(handover.after_minutes * 60)), ("chat", &handover.chat_id.to_string())])
+}
+
+pub fn format_schedule(schedule: &WeeklySchedule, lang: Lang) -> String {
+    let mut text = format!("{}\n", i18n::message(lang, "schedule_title"));
+    for (day, times) in schedule.weekly_plan() {
+        let zeiten = if times.is_empty() { "–".to_string() } else { times.iter().map(|t| t.format("%H:%M").to_string()).collect::<Vec<_>>().join(", ") };
+        text.push_str(&format!("{}: {}\n", weekday_name(day, lang), zeiten));
+    }
+    if let Some(at) = next_fire(schedule, Utc::now()) {
+        text.push_str(&format!("{}\n", i18n::message_with(lang, "schedule_next", &[("time", &format_local(at, "%d.%m.%Y %H:%M"))])));
+    }
+    text
+}
+
+// Geräte mit ihren Messwerttypen, eine Zeile je Gerät
+pub fn known_devices(readings: &[SensorData]) -> String {
+    let mut devices: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for reading in readings {
+        let types = devices.entry(reading.device_id.as_str()).or_default();
+        if !types.contains(&reading.sensor_type.as_str()) {
+            types.push(reading.sensor_type.as_str());
+        }
+    }
+    devices.into_iter().map(|(device, types)| format!("{} ({}): {}", room_name(device), device, types.join(", "))).collect::<Vec<_>>().join("\n")
+}
+
+// Schwellen eines Geräts nach /copy-thresholds und /apply-default, ersetzte markiert
+pub fn format_device_thresholds(config: &UserConfig, device: &str, overwritten: &[ThresholdKey]) -> String {
+    let mut keys: Vec<&ThresholdKey> = config.thresholds.keys().filter(|(d, _)| d == device).map(|(_, key)| key).collect();
+    keys.sort();
+    let lang = config.lang;
+    let mut text = format!("{}\n", i18n::message_with(lang, "device_thresholds_title", &[("room", &room_name(device))]));
+    for key in keys {
+        let id = (device.to_string(), key.clone());
+        let einheit = unit_in(config.units, key.kind.as_str());
+        let values: Vec<String> = config.thresholds[&id]
+            .entries()
+            .iter()
+            .map(|entry| {
+                let value = config.units.show(&key.kind, entry.value);
+                match entry.window {
+                    Some(w) => format!("{:.1} {} {}", value, einheit, i18n::message_with(lang, "threshold_window", &[("window", &w.to_string())])),
+                    None => format!("{:.1} {}", value, einheit),
+                }
+            })
+            .collect();
+        let symbol = if key.direction.is_min() { "🔻" } else { "🔺" };
+        text.push_str(&format!("{} {} {}: {}", symbol, key.direction.as_str().to_uppercase(), type_label_in(lang, key.kind.as_str()).0, values.join(", ")));
+        if let Some(severity) = config.alert_styles.get(&id).and_then(|style| style.severity) {
+            text.push_str(&format!(" · {}", i18n::message_with(lang, "threshold_severity", &[("severity", severity.label(lang))])));
+        }
+        if let Some(minutes) = config.repeat.get(&id) {
+            text.push_str(&format!(" · {}", i18n::message_with(lang, "device_thresholds_repeat", &[("interval", &format_duration(minutes * 60))])));
+        }
+        if let Some(hysteresis) = config.hysteresis.get(&id) {
+            text.push_str(&format!(" · ↔️ {:.1} {}", config.units.show_delta(&key.kind, *hysteresis), einheit));
+        }
+        if overwritten.contains(key) {
+            text.push_str(&format!(" {}", i18n::message(lang, "device_thresholds_replaced")));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    // Legacy-Markdown: *…* paarweise, _, ` und [ nur maskiert. Ein \\
+    // maskiert nur diese vier, vor anderen Zeichen bleibt er stehen.
+    fn assert_valid_markdown(text: &str) {
+        let mut stars = 0;
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    chars.next_if(|next| matches!(next, '_' | '*' | '`' | '['));
+                }
+                '*' => stars += 1,
+                '_' | '`' | '[' => panic!("unmaskiertes {} in {:?}", c, text),
+                _ => {}
+            }
+        }
+        assert_eq!(stars % 2, 0, "ungepaarte * in {:?}", text);
+    }
+
+    #[test]
+    fn escape_masks_every_markdown_character() {
+        assert_eq!(escape_markdown("outdoor_balcony *neu* [1] `x`."), "outdoor\\_balcony \\*neu\\* \\[1] \\`x\\`.");
+        assert_eq!(escape_markdown("Wohnzimmer 21.5"), "Wohnzimmer 21.5");
+    }
+
+    #[test]
+    fn bold_only_without_markdown_characters() {
+        assert_eq!(markdown_bold("Wohnzimmer"), "*Wohnzimmer*");
+        assert_eq!(markdown_bold("outdoor_balcony"), "outdoor\\_balcony");
+        assert_valid_markdown(&markdown_bold("a*b[c"));
+        assert_valid_markdown(&format!("{} – x", markdown_bold("keller\\")));
+    }
+
+    #[test]
+    fn status_with_odd_names_is_valid_markdown() {
+        let readings: Vec<SensorData> = ["outdoor_balcony", "keller*2", "[dach].v2"]
+            .iter()
+            .map(|device| SensorData { device_id: device.to_string(), sensor_type: SensorKind::from("co2_ppm*"), value: 412.0, timestamp: 1_700_000_000 })
+            .collect();
+        let text = format_status(&readings, &Trends::new(), Lang::De, TempUnit::default(), None, TimeFormat::Absolute);
+        assert!(text.contains("outdoor\\_balcony"));
+        assert_valid_markdown(&text);
+    }
+
+    // Telegram zählt die Länge in UTF-16-Einheiten
+    fn assert_sendable(text: &str) {
+        assert!(text.encode_utf16().count() <= 4096, "{} Zeichen", text.encode_utf16().count());
+    }
+
+    proptest! {
+        // Auch ein Raum mit allen Messgrößen bei f64::MAX bleibt eine Nachricht
+        #[test]
+        fn status_is_sendable(readings in vec(any::<SensorData>(), 0..8), lang in any::<Lang>(), units in any::<TempUnit>(), times in any::<TimeFormat>()) {
+            let text = format_status(&readings, &Trends::new(), lang, units, None, times);
+            assert_valid_markdown(&text);
+            assert_sendable(&text);
+        }
+
+        #[test]
+        fn alert_digest_is_valid_markdown(entries in crate::arbitrary::stamped()) {
+            assert_valid_markdown(&format_alert_digest(&entries, None));
+            assert_valid_markdown(&format_digest(&entries, None, None));
+        }
+    }
+
+    #[test]
+    fn missed_alerts_are_marked_with_their_time() {
+        let at = |h, m| chrono::NaiveDate::from_ymd_opt(2024, 1, 16).unwrap().and_hms_opt(h, m, 0).unwrap().and_utc();
+        let missed = [(at(1, 5), "🔺 Wohnzimmer 26.0 °C".to_string()), (at(2, 40), "🔻 sensor_2 17.0 °C".to_string())];
+        let text = format_digest(&missed, None, Some(chrono_tz::UTC));
+        assert_eq!(text, "🔕 *Verpasste Warnungen (2):*\n01:05 – 🔺 Wohnzimmer 26.0 °C\n02:40 – 🔻 sensor\\_2 17.0 °C\n");
+        assert_valid_markdown(&text);
+
+        let with_report = format_digest(&missed, Some("📊 Bericht"), Some(chrono_tz::UTC));
+        assert!(with_report.ends_with("\n📅 *Tageszusammenfassung*\n📊 Bericht"), "{}", with_report);
+        assert_eq!(format_digest(&[], Some("📊 Bericht"), None), "📊 Bericht");
+    }
+
+    // Eine Notiz je Gerät in der Reihenfolge von /status, nur für gemeldete Geräte
+    #[test]
+    fn notes_follow_the_status_order() {
+        let reading = |device: &str, kind: SensorKind| SensorData { device_id: device.into(), sensor_type: kind, value: 21.0, timestamp: 1_700_000_000 };
+        let readings =
+            [reading("outdoor_balcony", SensorKind::Temperature), reading("sensor1", SensorKind::Temperature), reading("sensor1", SensorKind::Humidity)];
+        let notes: BTreeMap<String, String> = [("sensor1", "Heizung *neu*"), ("outdoor_balcony", "Fühler im Schatten"), ("keller", "nicht gemeldet")]
+            .into_iter()
+            .map(|(device, note)| (device.to_string(), note.to_string()))
+            .collect();
+
+        let text = format_notes(&notes, &readings);
+        assert_eq!(text, "📝 outdoor\\_balcony: Fühler im Schatten\n_📝 Wohnzimmer: Heizung \\*neu\\*_\n");
+        assert_eq!(format_notes(&BTreeMap::new(), &readings), "");
+    }
+
+    // Unterschied drinnen/draußen je Raum; ohne Wetter oder ohne Feuchte im
+    // Raum entfällt die Zeile
+    #[test]
+    fn outdoor_comparison_shows_the_difference() {
+        let reading = |device: &str, kind: SensorKind, value: f64| SensorData { device_id: device.into(), sensor_type: kind, value, timestamp: 1_700_000_000 };
+        let readings = [
+            reading("sensor1", SensorKind::Temperature, 22.0),
+            reading("sensor1", SensorKind::Humidity, 60.0),
+            reading("keller", SensorKind::Temperature, 15.0),
+        ];
+        let winter = weather::Weather { temperature: 5.0, humidity: 80.0 };
+
+        assert_eq!(
+            outdoor_comparison(&readings, Some(winter), Lang::De, TempUnit::Celsius),
+            ["🌤 Wohnzimmer: draußen 17.0 °C kühler (5.0 °C, 80 %) – Lüften senkt die Luftfeuchtigkeit"]
+        );
+        assert_eq!(
+            outdoor_comparison(&readings, Some(winter), Lang::En, TempUnit::Fahrenheit),
+            ["🌤 Wohnzimmer: 30.6 °F cooler outside (41.0 °F, 80 %) – airing lowers the humidity"]
+        );
+        let mild = weather::Weather { temperature: 22.3, humidity: 60.0 };
+        assert_eq!(
+            outdoor_comparison(&readings, Some(mild), Lang::De, TempUnit::Celsius),
+            ["🌤 Wohnzimmer: draußen etwa gleich warm (22.3 °C, 60 %) – an der Luftfeuchtigkeit ändert Lüften wenig"]
+        );
+    }
+
+    #[test]
+    fn outdoor_comparison_without_weather_is_empty() {
+        let reading = |kind: SensorKind, value: f64| SensorData { device_id: "sensor1".into(), sensor_type: kind, value, timestamp: 1_700_000_000 };
+        let readings = [reading(SensorKind::Temperature, 22.0), reading(SensorKind::Humidity, 60.0)];
+        assert!(outdoor_comparison(&readings, None, Lang::De, TempUnit::Celsius).is_empty());
+    }
+}
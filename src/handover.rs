@@ -0,0 +1,93 @@
+use crate::i18n::{self, Lang};
+use crate::routing::Severity;
+use serde::{Deserialize, Serialize};
+
+// Weitergabe unbestätigter Warnungen an einen zweiten Chat (/escalate-to).
+// Der Ziel-Chat muss sie mit /accept-escalations-from freigegeben haben.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+pub struct Handover {
+    pub chat_id: i64,
+    pub after_minutes: i64,
+    #[serde(default = "critical")]
+    pub from: Severity, // Warnungen ab dieser Stufe
+}
+
+fn critical() -> Severity {
+    Severity::Critical
+}
+
+impl Handover {
+    // Weitergeben, wenn eine Warnung dieser Stufe seit `started` unbestätigt besteht
+    pub fn due(&self, severity: Severity, started: i64, now: i64) -> bool {
+        severity >= self.from && now - started >= self.after_minutes * 60
+    }
+}
+
+pub fn usage(lang: Lang) -> &'static str {
+    i18n::message(lang, "handover_usage")
+}
+
+// "<chat-id> after 30m [stufe]"; `minutes` wandelt die Dauer um
+pub fn parse(spec: &str, lang: Lang, minutes: impl Fn(&str) -> Option<i64>) -> Result<Handover, String> {
+    let usage = usage(lang);
+    let parts: Vec<&str> = spec.split_whitespace().collect();
+    let [chat_id, after, duration, rest @ ..] = parts.as_slice() else { return Err(usage.to_string()) };
+    if !["after", "nach"].contains(&after.to_lowercase().as_str()) || rest.len() > 1 {
+        return Err(usage.to_string());
+    }
+    let chat_id = chat_id.parse::<i64>().map_err(|_| i18n::message_with(lang, "handover_chat_id", &[("value", chat_id), ("usage", usage)]))?;
+    let after_minutes = minutes(duration).filter(|m| *m > 0).ok_or_else(|| i18n::message_with(lang, "handover_duration", &[("usage", usage)]))?;
+    let from = match rest.first() {
+        Some(severity) => severity.parse::<Severity>().map_err(|_| i18n::message_with(lang, "handover_severity", &[("value", severity)]))?,
+        None => Severity::Critical,
+    };
+    if from == Severity::Info {
+        return Err(i18n::message(lang, "handover_info").to_string());
+    }
+    Ok(Handover { chat_id, after_minutes, from })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(text: &str) -> Option<i64> {
+        let (number, unit) = text.split_at(text.len() - 1);
+        let number: i64 = number.parse().ok()?;
+        match unit {
+            "m" => Some(number),
+            "h" => Some(number * 60),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn parses_chat_duration_and_level() {
+        let handover = parse("123456 after 30m", Lang::De, minutes).unwrap();
+        assert_eq!(handover, Handover { chat_id: 123456, after_minutes: 30, from: Severity::Critical });
+        let handover = parse("-1001 nach 2h warn", Lang::De, minutes).unwrap();
+        assert_eq!(handover, Handover { chat_id: -1001, after_minutes: 120, from: Severity::Warning });
+    }
+
+    #[test]
+    fn rejects_malformed_specs() {
+        assert_eq!(parse("123456", Lang::De, minutes).unwrap_err(), usage(Lang::De));
+        assert_eq!(parse("123456 before 30m", Lang::De, minutes).unwrap_err(), usage(Lang::De));
+        assert_eq!(parse("123456 after 30m warn extra", Lang::De, minutes).unwrap_err(), usage(Lang::De));
+        assert!(parse("anna after 30m", Lang::De, minutes).unwrap_err().contains("keine Chat-ID"));
+        assert!(parse("123456 after 0m", Lang::De, minutes).unwrap_err().contains("Dauer"));
+        assert!(parse("123456 after 30m info", Lang::De, minutes).unwrap_err().contains("Info"));
+        assert!(parse("123456 after 30m laut", Lang::En, minutes).unwrap_err().contains("Unknown level"));
+    }
+
+    #[test]
+    fn due_after_the_delay_from_the_level_on() {
+        let handover = Handover { chat_id: 1, after_minutes: 30, from: Severity::Warning };
+        let started = 1_700_000_000;
+        assert!(!handover.due(Severity::Critical, started, started + 29 * 60));
+        assert!(handover.due(Severity::Critical, started, started + 30 * 60));
+        assert!(handover.due(Severity::Warning, started, started + 30 * 60));
+        assert!(!handover.due(Severity::Info, started, started + 60 * 60));
+    }
+}
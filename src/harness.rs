@@ -0,0 +1,1174 @@
+// Prüfstand für Befehle und Überwachung: ein Sensor-Webserver auf localhost
+// (axum), ein Messenger, der mitschreibt, und eine vorgegebene Uhr. Befehle
+// laufen durch `commands::answer`, Messwerte durch `Monitor::run_once` und die
+// echte Ausgangswarteschlange. Einstellungen, letzter Stand und Quellen sind
+// prozessweit; die Tests hier laufen deshalb nacheinander.
+
+use crate::background;
+use crate::commands::{Command, answer};
+use crate::messenger::{Messenger, OutgoingMessage, SendError};
+use crate::monitor::{Clock, Monitor};
+use crate::outbox::Outbox;
+use crate::rooms::RoomRegistry;
+use crate::sensor::{SensorKind, ThresholdDirection, ThresholdKey};
+use crate::source::{BoxFuture, FetchError, HttpSource};
+use crate::state::{QuietQueue, Shared};
+use crate::storage::{self, JsonStore};
+use crate::{SOURCES, SensorData, SensorSource, bot_status, latest, restore_state};
+use axum::http::{StatusCode, header};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use teloxide::prelude::*;
+use teloxide::utils::command::BotCommands;
+use tokio::sync::{Mutex, MutexGuard, mpsc};
+
+const CHAT: i64 = 4711;
+const OTHER: i64 = 4712;
+pub(crate) const ADMIN: i64 = 4700;
+
+static SERIAL: Mutex<()> = Mutex::const_new(());
+// Antwort des Sensor-Webservers: Statuscode und Rumpf
+static RESPONSE: std::sync::Mutex<(u16, String)> = std::sync::Mutex::new((200, String::new()));
+static ADDR: OnceLock<SocketAddr> = OnceLock::new();
+
+// Läuft auf einem eigenen Thread, damit er alle Tests (je mit eigener
+// Laufzeit) überdauert
+fn endpoint() -> String {
+    let addr = ADDR.get_or_init(|| {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+            runtime.block_on(async move {
+                let app = axum::Router::new().route(
+                    "/sensors",
+                    axum::routing::get(|| async {
+                        let (status, body) = RESPONSE.lock().unwrap().clone();
+                        (StatusCode::from_u16(status).unwrap(), [(header::CONTENT_TYPE, "application/json")], body)
+                    }),
+                );
+                axum::serve(tokio::net::TcpListener::from_std(listener).unwrap(), app).await.unwrap();
+            });
+        });
+        addr
+    });
+    format!("http://{}/sensors", addr)
+}
+
+fn respond(status: u16, body: impl Into<String>) {
+    *RESPONSE.lock().unwrap() = (status, body.into());
+}
+
+// Temperaturen je Gerät zum angegebenen Zeitpunkt
+fn serve(at: DateTime<Utc>, readings: &[(&str, f64)]) {
+    let readings: Vec<serde_json::Value> = readings
+        .iter()
+        .map(|(device, value)| serde_json::json!({"device_id": device, "sensor_type": "temperature", "value": value, "timestamp": at.timestamp()}))
+        .collect();
+    respond(200, serde_json::Value::from(readings).to_string());
+}
+
+// Quelle ohne Webserver: liefert, was der Test zuletzt eingestellt hat
+struct MockSource(std::sync::Mutex<Result<Vec<SensorData>, FetchError>>);
+
+impl MockSource {
+    fn set(&self, at: DateTime<Utc>, readings: &[(&str, f64)]) {
+        let readings = readings
+            .iter()
+            .map(|(device, value)| SensorData { device_id: device.to_string(), sensor_type: SensorKind::Temperature, value: *value, timestamp: at.timestamp() })
+            .collect();
+        *self.0.lock().unwrap() = Ok(readings);
+    }
+}
+
+impl SensorSource for MockSource {
+    fn fetch(&self) -> BoxFuture<'_, Result<Vec<SensorData>, FetchError>> {
+        let result = self.0.lock().unwrap().clone();
+        Box::pin(async move { result })
+    }
+
+    fn poll_interval(&self) -> Option<Duration> {
+        Some(Duration::ZERO)
+    }
+}
+
+// Schreibt alles mit; Warnungen bekommen fortlaufende Nachrichten-IDs
+#[derive(Default)]
+struct Recorder {
+    sent: std::sync::Mutex<Vec<OutgoingMessage>>,
+    tx: OnceLock<mpsc::UnboundedSender<OutgoingMessage>>,
+    next_id: AtomicI32,
+}
+
+impl Recorder {
+    fn take(&self) -> Vec<OutgoingMessage> {
+        std::mem::take(&mut *self.sent.lock().unwrap())
+    }
+}
+
+impl Messenger for Recorder {
+    fn send<'a>(&'a self, message: &'a OutgoingMessage) -> BoxFuture<'a, Result<(), SendError>> {
+        Box::pin(async move { self.send_returning_id(message).await.map(|_| ()) })
+    }
+
+    fn send_returning_id<'a>(&'a self, message: &'a OutgoingMessage) -> BoxFuture<'a, Result<Option<i32>, SendError>> {
+        self.sent.lock().unwrap().push(message.clone());
+        if let Some(tx) = self.tx.get() {
+            tx.send(message.clone()).ok();
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        Box::pin(async move { Ok(Some(id)) })
+    }
+}
+
+// Texte, die an diesen Chat gingen
+fn texts_to(sent: &[OutgoingMessage], chat: i64) -> Vec<&str> {
+    sent.iter().filter(|message| message.chat_id == chat).map(|message| message.text.as_str()).collect()
+}
+
+struct TestClock(std::sync::Mutex<DateTime<Utc>>);
+
+impl TestClock {
+    fn set(&self, at: DateTime<Utc>) {
+        *self.0.lock().unwrap() = at;
+    }
+
+    fn advance(&self, minutes: i64) -> DateTime<Utc> {
+        let mut now = self.0.lock().unwrap();
+        *now += chrono::Duration::minutes(minutes);
+        *now
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.0.lock().unwrap()
+    }
+}
+
+struct Rig {
+    shared: Shared,
+    replies: Arc<Recorder>,
+    alerts: mpsc::UnboundedReceiver<OutgoingMessage>,
+    outbox: Outbox,
+    monitor: Monitor,
+    clock: Arc<TestClock>,
+    // In der Ruhezeit zurückgehaltene Warnungen
+    quiet: QuietQueue,
+    // Ohne: die Überwachung fragt den Sensor-Webserver ab
+    mock: Option<Arc<MockSource>>,
+    dir: PathBuf,
+    _serial: MutexGuard<'static, ()>,
+}
+
+impl Rig {
+    async fn new() -> Rig {
+        Rig::with_source(None, None, None).await
+    }
+
+    // Mit Admin-Chat, der die Meldungen der Überwachung bekommt
+    async fn with_admin() -> Rig {
+        Rig::with_source(None, Some(ChatId(ADMIN)), None).await
+    }
+
+    async fn mocked() -> Rig {
+        Rig::with_source(Some(Arc::new(MockSource(std::sync::Mutex::new(Ok(Vec::new()))))), None, None).await
+    }
+
+    // Mit dieser Raumdatei; ihre Haushalte fragen ebenfalls den Sensor-Webserver
+    // des Prüfstands ab ({source} in der Datei)
+    async fn with_rooms(rooms: &str) -> Rig {
+        Rig::with_source(None, None, Some(rooms)).await
+    }
+
+    async fn with_source(mock: Option<Arc<MockSource>>, admin: Option<ChatId>, rooms: Option<&str>) -> Rig {
+        Rig::start(fresh_dir(), mock, admin, rooms).await
+    }
+
+    // Neustart des Bots: gespeicherter Zustand, Uhr und Raumdatei bleiben,
+    // alles im Speicher (Alarm-Zustände, Warteschlange) beginnt neu. Der
+    // Admin-Chat entfällt.
+    async fn restart(self) -> Rig {
+        self.shared.storage.flush();
+        let dir = fresh_dir();
+        for entry in std::fs::read_dir(&self.dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_file() {
+                std::fs::copy(&path, dir.join(path.file_name().unwrap())).unwrap();
+            }
+        }
+        let rooms = std::fs::read_to_string(dir.join("rooms.toml")).ok();
+        let (mock, now) = (self.mock.clone(), self.clock.now());
+        drop(self);
+        let rig = Rig::start(dir, mock, None, rooms.as_deref()).await;
+        *rig.clock.0.lock().unwrap() = now;
+        rig
+    }
+
+    async fn start(dir: PathBuf, mock: Option<Arc<MockSource>>, admin: Option<ChatId>, rooms: Option<&str>) -> Rig {
+        let serial = SERIAL.lock().await;
+        let url = endpoint();
+        // /status und /setmax fragen die prozessweiten Quellen ab
+        SOURCES.get_or_init(|| vec![Arc::new(HttpSource::new(url.clone())) as Arc<dyn SensorSource>]);
+        *latest() = None;
+        respond(200, "[]");
+
+        if let Some(rooms) = rooms {
+            let path = dir.join("rooms.toml");
+            std::fs::write(&path, rooms.replace("{source}", &url)).unwrap();
+            crate::set_rooms(RoomRegistry::load(&path).unwrap());
+        }
+        let replies = Arc::new(Recorder::default());
+        let shared = restore_state(Arc::new(JsonStore::in_dir(&dir)), replies.clone()).await;
+
+        // Warnungen laufen über die echte Warteschlange an einen eigenen Recorder
+        let delivered = Arc::new(Recorder::default());
+        let (alerts_tx, alerts) = mpsc::unbounded_channel();
+        delivered.tx.set(alerts_tx).unwrap();
+        let (delivery_tx, delivery_rx) = mpsc::unbounded_channel();
+        let outbox = Outbox::spawn(delivered, delivery_tx, 1);
+        tokio::spawn(background::track_deliveries(shared.clone(), delivery_rx));
+
+        let clock = Arc::new(TestClock(std::sync::Mutex::new(Utc::now())));
+        let source: Arc<dyn SensorSource> = match &mock {
+            Some(mock) => mock.clone(),
+            None => Arc::new(HttpSource::new(url).with_interval(Duration::ZERO)),
+        };
+        let mut sources = vec![(None, source)];
+        for tenant in crate::rooms().tenants() {
+            sources.push((Some(tenant.id.clone()), Arc::new(HttpSource::new(tenant.source.clone()).with_interval(Duration::ZERO)) as Arc<dyn SensorSource>));
+        }
+        let quiet: QuietQueue = Arc::new(Mutex::new(HashMap::new()));
+        let monitor = Monitor::new(
+            admin,
+            outbox.clone(),
+            shared.configs.clone(),
+            shared.flags.clone(),
+            shared.history.clone(),
+            shared.records.clone(),
+            quiet.clone(),
+            shared.storage.clone(),
+            shared.uptime.clone(),
+            shared.escalation.clone(),
+            sources,
+            clock.clone(),
+        )
+        .await;
+        Rig { shared, replies, alerts, outbox, monitor, clock, quiet, mock, dir, _serial: serial }
+    }
+
+    async fn command(&self, text: &str) -> Vec<OutgoingMessage> {
+        self.command_in(CHAT, text).await
+    }
+
+    // Befehl wie aus einem privaten Chat; liefert die Antworten
+    async fn command_in(&self, chat: i64, text: &str) -> Vec<OutgoingMessage> {
+        let command = Command::parse(text, "sensorbot").unwrap();
+        answer(Bot::new("0:harness"), message(chat, text), command, self.shared.clone()).await.unwrap();
+        self.replies.take()
+    }
+
+    // Ein Durchlauf der Überwachung mit diesen Messwerten, fünf Minuten nach dem letzten
+    async fn poll(&mut self, readings: &[(&str, f64)]) -> Vec<OutgoingMessage> {
+        let at = self.clock.advance(5);
+        match &self.mock {
+            Some(mock) => mock.set(at, readings),
+            None => serve(at, readings),
+        }
+        self.run().await
+    }
+
+    // Ein Durchlauf mit der eingestellten Antwort; liefert, was zugestellt wurde
+    async fn run(&mut self) -> Vec<OutgoingMessage> {
+        self.monitor.run_once().await;
+        assert_eq!(self.outbox.drain(Duration::from_secs(5)).await, 0);
+        let mut sent = Vec::new();
+        while let Ok(message) = self.alerts.try_recv() {
+            sent.push(message);
+        }
+        sent
+    }
+
+    // Bis die Zustellung der Warnung bei der laufenden Verletzung vermerkt ist
+    async fn delivered(&self, chat: i64, device: &str) -> i32 {
+        for _ in 0..50 {
+            let configs = self.shared.configs.lock().await;
+            let message_id = configs.get(&chat).and_then(|c| c.episodes.iter().find(|((d, _), _)| d == device).and_then(|(_, e)| e.message_id));
+            if let Some(message_id) = message_id {
+                return message_id;
+            }
+            drop(configs);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("Zustellung der Warnung für {} nicht vermerkt", device);
+    }
+}
+
+fn fresh_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sensorbot-harness-{}-{}", std::process::id(), rand::random::<u32>()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn message(chat: i64, text: &str) -> Message {
+    serde_json::from_value(serde_json::json!({
+        "message_id": 1,
+        "date": Utc::now().timestamp(),
+        "chat": {"id": chat, "type": "private", "first_name": "Ada"},
+        "from": {"id": chat, "is_bot": false, "first_name": "Ada"},
+        "text": text,
+    }))
+    .unwrap()
+}
+
+impl Drop for Rig {
+    fn drop(&mut self) {
+        // Raumdatei des Tests verwerfen
+        crate::set_rooms(RoomRegistry::default());
+        self.shared.storage.flush();
+        std::fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+#[tokio::test]
+async fn start_greets_by_name() {
+    let rig = Rig::new().await;
+    let replies = rig.command("/start").await;
+    assert_eq!(replies.len(), 1);
+    assert_eq!(replies[0].chat_id, CHAT);
+    assert!(replies[0].text.contains("Willkommen, Ada!"), "{}", replies[0].text);
+}
+
+#[tokio::test]
+async fn status_lists_the_sensor_endpoint() {
+    let rig = Rig::new().await;
+    serve(Utc::now(), &[("sensor1", 21.5), ("sensor2", 19.0)]);
+    let replies = rig.command("/status").await;
+    assert_eq!(replies.len(), 1);
+    let text = &replies[0].text;
+    assert!(replies[0].markdown);
+    // sensor1 ist im Raumverzeichnis als Wohnzimmer hinterlegt
+    assert!(text.contains("*Wohnzimmer* – Temperatur: *21.5 °C*"), "{}", text);
+    assert!(text.contains("*sensor2* – Temperatur: *19.0 °C*"), "{}", text);
+}
+
+#[tokio::test]
+async fn status_without_data() {
+    let rig = Rig::new().await;
+    // Quelle antwortet, kennt aber noch keine Messwerte: nur die Überschrift
+    let replies = rig.command("/status").await;
+    assert_eq!(replies.len(), 1);
+    assert!(!replies[0].text.contains("sensor"), "{}", replies[0].text);
+    assert!(!replies[0].text.starts_with('❌'), "{}", replies[0].text);
+
+    // Quelle gestört, kein früherer Stand
+    respond(500, "kaputt");
+    let replies = rig.command("/status").await;
+    assert_eq!(replies.len(), 1);
+    assert!(replies[0].text.starts_with("❌ Sensor-Webserver nicht erreichbar"), "{}", replies[0].text);
+    rig.command("/language en").await;
+    let replies = rig.command("/status").await;
+    assert!(replies[0].text.starts_with("❌ Sensor web server unreachable"), "{}", replies[0].text);
+}
+
+#[tokio::test]
+async fn threshold_breach_and_recovery_end_to_end() {
+    let mut rig = Rig::new().await;
+    serve(Utc::now(), &[("sensor1", 22.0)]);
+    let replies = rig.command("/setmax sensor1 temperature 25").await;
+    assert!(replies[0].text.starts_with("🔺 MAX-Schwellwert"), "{}", replies[0].text);
+
+    assert!(rig.poll(&[("sensor1", 24.0)]).await.is_empty());
+    let alarm = rig.poll(&[("sensor1", 25.8)]).await;
+    assert_eq!(alarm.len(), 1, "{:?}", alarm);
+    assert_eq!(alarm[0].chat_id, CHAT);
+    assert!(alarm[0].text.contains("25.8"), "{}", alarm[0].text);
+    let message_id = rig.delivered(CHAT, "sensor1").await;
+
+    // Noch innerhalb der Hysterese: weder Entwarnung noch zweite Warnung
+    assert!(rig.poll(&[("sensor1", 24.8)]).await.is_empty());
+    let recovery = rig.poll(&[("sensor1", 23.5)]).await;
+    assert_eq!(recovery.len(), 1, "{:?}", recovery);
+    assert!(recovery[0].text.starts_with('✅'), "{}", recovery[0].text);
+    assert_eq!(recovery[0].reply_to, Some(message_id));
+    assert!(rig.shared.configs.lock().await[&CHAT].episodes.is_empty());
+}
+
+// Nach einem Neustart mitten in einer Verletzung kommt keine zweite Warnung,
+// die Entwarnung aber schon, als Antwort auf die Warnung von vor dem Neustart
+#[tokio::test]
+async fn restart_while_breached_keeps_the_alert_state() {
+    let mut rig = Rig::new().await;
+    serve(Utc::now(), &[("sensor1", 22.0)]);
+    rig.command("/setmax sensor1 temperature 25").await;
+    assert_eq!(rig.poll(&[("sensor1", 25.8)]).await.len(), 1);
+    let message_id = rig.delivered(CHAT, "sensor1").await;
+
+    let mut rig = rig.restart().await;
+    assert_eq!(rig.shared.flags.lock().await.len(), 1);
+    assert!(rig.poll(&[("sensor1", 26.4)]).await.is_empty());
+    let recovery = rig.poll(&[("sensor1", 23.5)]).await;
+    assert_eq!(recovery.len(), 1, "{:?}", recovery);
+    assert!(recovery[0].text.starts_with('✅'), "{}", recovery[0].text);
+    assert_eq!(recovery[0].reply_to, Some(message_id));
+    assert!(rig.shared.configs.lock().await[&CHAT].episodes.is_empty());
+}
+
+// Ein Testwert aus /inject läuft durch dieselbe Auswertung wie ein Messwert
+// und löst die Warnung aus; außer dem Admin darf niemand einspeisen
+#[tokio::test]
+async fn injected_reading_triggers_the_alert() {
+    let mut rig = Rig::mocked().await;
+    serve(Utc::now(), &[("sensor1", 22.0)]);
+    rig.command("/setmax sensor1 temperature 25").await;
+
+    let refused = rig.command("/inject sensor1 temperature 26.5").await;
+    assert_eq!(refused[0].text, "Dieser Befehl ist dem Admin vorbehalten.");
+    assert!(rig.run().await.is_empty());
+
+    let injected = rig.command_in(ADMIN, "/inject sensor1 temperature 26.5 --no-store").await;
+    assert!(injected[0].text.starts_with("🧪") && injected[0].text.contains("nicht gespeichert"), "{}", injected[0].text);
+    let alarm = rig.run().await;
+    assert_eq!(alarm.len(), 1, "{:?}", alarm);
+    assert_eq!(alarm[0].chat_id, CHAT);
+    assert!(alarm[0].text.contains("26.5"), "{}", alarm[0].text);
+}
+
+// Eine Warnung trägt den Zeitpunkt des Messwerts und den der Zustellung;
+// /alarms zeigt den des Messwerts, die Verzögerung ist der Abstand
+#[tokio::test]
+async fn alert_keeps_reading_time_and_delivery_time_apart() {
+    let mut rig = Rig::mocked().await;
+    serve(Utc::now(), &[("sensor1", 22.0)]);
+    rig.command("/setmax sensor1 temperature 25").await;
+
+    let before = Utc::now().timestamp();
+    rig.command_in(ADMIN, "/inject sensor1 temperature 26.5 2h --no-store").await;
+    assert_eq!(rig.run().await.len(), 1);
+    rig.delivered(CHAT, "sensor1").await;
+    let after = Utc::now().timestamp();
+
+    let configs = rig.shared.configs.lock().await;
+    let config = &configs[&CHAT];
+    let record = config.alerts.back().unwrap();
+    let observed = record.observed_at.unwrap();
+    assert!((before - 2 * 60 * 60..=after - 2 * 60 * 60).contains(&observed), "{}", observed);
+    assert!((before..=after).contains(&record.delivered_at.unwrap()));
+    assert_eq!(config.alarm_log.latest(1).next().unwrap().timestamp, observed);
+    let latency = crate::delivery_latencies(config);
+    assert!(latency.len() == 1 && (2 * 60 * 60..=2 * 60 * 60 + after - before).contains(&latency[0]), "{:?}", latency);
+}
+
+#[tokio::test]
+async fn malformed_json_from_the_sensor_endpoint() {
+    let mut rig = Rig::new().await;
+    serve(Utc::now(), &[("sensor1", 22.0)]);
+    rig.command("/setmax sensor1 temperature 25").await;
+
+    respond(200, r#"[{"device_id": "sensor1", "sensor_type": "temperature", "value": 26"#);
+    let failures = bot_status().fetch_failures;
+    assert!(rig.run().await.is_empty());
+    assert_eq!(bot_status().fetch_failures, failures + 1);
+    let replies = rig.command("/status").await;
+    assert!(replies[0].text.starts_with('❌'), "{}", replies[0].text);
+
+    // Unlesbare Einträge fallen einzeln heraus, der Rest wird ausgewertet
+    let at = rig.clock.advance(5).timestamp();
+    respond(200, format!(r#"[{{"device_id": "sensor1", "sensor_type": "temperature", "value": 25.9, "timestamp": {}}}, "kaputt"]"#, at));
+    let alarm = rig.run().await;
+    assert_eq!(alarm.len(), 1, "{:?}", alarm);
+    assert_eq!(bot_status().fetch_failures, 0);
+}
+
+// Eine Liste nur aus unlesbaren Einträgen ist ein Formatfehler: Sie setzt den
+// Zähler nicht zurück, der Admin erfährt es nach SCHEMA_ALARM_AFTER Abrufen
+#[tokio::test]
+async fn all_broken_entries_reach_the_schema_alarm() {
+    let mut rig = Rig::with_admin().await;
+    let broken = r#"[{"device": "sensor1", "temp": 21.5}, {"device": "sensor2", "temp": 20.0}]"#;
+    let alarm_after = crate::settings().schema_alarm_after.max(1);
+    for _ in 1..alarm_after {
+        respond(200, broken);
+        assert!(rig.run().await.is_empty());
+    }
+    respond(200, broken);
+    let sent = rig.run().await;
+    let reports = texts_to(&sent, ADMIN);
+    assert_eq!(reports.len(), 1, "{:?}", sent);
+    assert!(reports[0].contains("device_id"), "{}", reports[0]);
+    assert!(reports[0].contains("\"temp\""), "{}", reports[0]);
+
+    // Ein lesbarer Eintrag dazwischen beginnt die Zählung von vorn
+    respond(200, broken);
+    assert!(rig.run().await.is_empty());
+    // (meldet dem Admin höchstens den neuen Sensor)
+    serve(rig.clock.advance(5), &[("sensor1", 21.0)]);
+    rig.run().await;
+    for _ in 1..alarm_after {
+        respond(200, broken);
+        assert!(rig.run().await.is_empty());
+    }
+    respond(200, broken);
+    assert_eq!(texts_to(&rig.run().await, ADMIN).len(), 1);
+}
+
+async fn broken_handler() -> ResponseResult<()> {
+    panic!("Absicht: Test einer Panik im Befehl")
+}
+
+// Eine Panik trifft nur ihre Anfrage: der Chat bekommt eine Antwort, die
+// Panik wird gezählt, und der nächste Befehl läuft normal
+#[tokio::test]
+async fn panicking_command_still_answers_and_the_next_one_runs() {
+    let rig = Rig::new().await;
+    let panics = crate::HANDLER_PANICS.load(Ordering::Relaxed);
+    crate::guarded(ChatId(CHAT), "/status", &rig.shared.configs, rig.replies.as_ref(), broken_handler()).await.unwrap();
+    assert_eq!(texts_to(&rig.replies.take(), CHAT), [crate::INTERNAL_ERROR]);
+    assert_eq!(crate::HANDLER_PANICS.load(Ordering::Relaxed), panics + 1);
+    assert!(crate::telemetry::render(std::time::Instant::now()).contains("\n# TYPE telegrambot_handler_panics_total counter\n"));
+
+    let handler = answer(Bot::new("0:harness"), message(CHAT, "/start"), Command::parse("/start", "sensorbot").unwrap(), rig.shared.clone());
+    crate::guarded(ChatId(CHAT), "/start", &rig.shared.configs, rig.replies.as_ref(), handler).await.unwrap();
+    let replies = rig.replies.take();
+    assert_eq!(replies.len(), 1, "{:?}", replies);
+    assert_ne!(replies[0].text, crate::INTERNAL_ERROR);
+    assert_eq!(crate::HANDLER_PANICS.load(Ordering::Relaxed), panics + 1);
+}
+
+const TWO_HOUSEHOLDS: &str = r#"
+[[room]]
+device = "sensor1"
+name = "Wohnzimmer"
+
+[[tenant]]
+id = "nachbar"
+members = [4712]
+source = "{source}"
+
+[[tenant.room]]
+device = "sensor1"
+name = "Küche"
+"#;
+
+// Zwei Haushalte mit je einem Raum am selben Gerät: Jeder Chat sieht, ändert
+// und bekommt Warnungen nur für die Räume seines Haushalts
+#[tokio::test]
+async fn tenants_only_see_edit_and_hear_their_own_rooms() {
+    let mut rig = Rig::with_rooms(TWO_HOUSEHOLDS).await;
+    serve(rig.clock.advance(5), &[("sensor1", 22.0)]);
+    rig.run().await;
+
+    let own = rig.command_in(CHAT, "/status").await;
+    assert!(own[0].text.contains("Wohnzimmer") && !own[0].text.contains("Küche"), "{}", own[0].text);
+    let neighbour = rig.command_in(OTHER, "/status").await;
+    assert!(neighbour[0].text.contains("Küche") && !neighbour[0].text.contains("Wohnzimmer"), "{}", neighbour[0].text);
+    assert!(!rig.command_in(OTHER, "/status Wohnzimmer").await[0].text.contains("22"));
+
+    // Fremde Räume und Geräte lassen sich nicht einrichten
+    rig.command_in(OTHER, "/setmax Wohnzimmer temperature 25").await;
+    rig.command_in(OTHER, "/setmax sensor1 temperature 25").await;
+    rig.command_in(CHAT, "/setmax nachbar/sensor1 temperature 25").await;
+    rig.command_in(CHAT, "/setmax Küche temperature 25").await;
+    {
+        let configs = rig.shared.configs.lock().await;
+        let devices = |chat: i64| configs.get(&chat).map(|c| c.thresholds.keys().map(|(device, _)| device.clone()).collect::<Vec<_>>()).unwrap_or_default();
+        assert!(devices(OTHER).iter().all(|device| device.starts_with("nachbar/")), "{:?}", devices(OTHER));
+        assert!(devices(CHAT).iter().all(|device| !device.contains('/')), "{:?}", devices(CHAT));
+    }
+
+    rig.command_in(OTHER, "/setmax Küche temperature 25").await;
+    rig.command_in(CHAT, "/setmax Wohnzimmer temperature 30").await;
+    serve(rig.clock.advance(5), &[("sensor1", 26.0)]);
+    let sent = rig.run().await;
+    assert_eq!(texts_to(&sent, OTHER).len(), 1, "{:?}", sent);
+    assert!(texts_to(&sent, OTHER)[0].contains("Küche"), "{:?}", sent);
+    assert!(texts_to(&sent, CHAT).is_empty(), "{:?}", sent);
+
+    serve(rig.clock.advance(5), &[("sensor1", 31.0)]);
+    let sent = rig.run().await;
+    assert_eq!(texts_to(&sent, CHAT).len(), 1, "{:?}", sent);
+    assert!(texts_to(&sent, CHAT)[0].contains("Wohnzimmer") && !texts_to(&sent, CHAT)[0].contains("Küche"), "{:?}", sent);
+    assert!(texts_to(&sent, OTHER).is_empty(), "{:?}", sent);
+}
+
+// Die Zustände des Bots gibt es einmal je Prozess: ein zweiter Start wird
+// abgelehnt, bevor er etwas davon anfasst
+#[tokio::test]
+async fn second_run_is_refused() {
+    // Der erste Start; in diesem Testprozess läuft sonst keiner
+    crate::claim_start().ok();
+    let second = crate::SensorBot::builder().messenger(Recorder::default()).run().await;
+    assert!(matches!(second, Err(crate::StartError::AlreadyStarted)));
+    assert!(crate::OUTBOX.get().is_none());
+    assert!(crate::ALERT_SINKS.get().is_none());
+}
+
+// Ein Präfix mehrerer Räume wird nicht geraten: die Antwort nennt die Kandidaten
+#[tokio::test]
+async fn ambiguous_room_prefix_lists_the_candidates() {
+    let rooms = "[[room]]\ndevice = \"sensor1\"\nname = \"Schlafzimmer\"\n\n[[room]]\ndevice = \"sensor2\"\nname = \"Schlafzimmer Gast\"\n";
+    let mut rig = Rig::with_rooms(rooms).await;
+    serve(rig.clock.advance(5), &[("sensor1", 19.5), ("sensor2", 18.0)]);
+    rig.run().await;
+
+    let replies = rig.command("/status schlaf").await;
+    assert_eq!(texts_to(&replies, CHAT), ["Meinst du Schlafzimmer oder Schlafzimmer Gast?"]);
+    let replies = rig.command("/status schlafzimmer").await;
+    assert!(replies[0].text.contains("19,5") || replies[0].text.contains("19.5"), "{}", replies[0].text);
+    assert!(!replies[0].text.contains("Gast"), "{}", replies[0].text);
+}
+
+#[tokio::test]
+async fn mock_source_min_and_max_breaches() {
+    let mut rig = Rig::mocked().await;
+    serve(Utc::now(), &[("sensor1", 22.0)]);
+    rig.command("/setmin sensor1 temperature 18").await;
+    rig.command("/setmax sensor1 temperature 25").await;
+
+    assert!(rig.poll(&[("sensor1", 22.0)]).await.is_empty());
+    let high = rig.poll(&[("sensor1", 26.0)]).await;
+    assert_eq!(texts_to(&high, CHAT).len(), 1, "{:?}", high);
+    assert!(high[0].text.contains("26.0"), "{}", high[0].text);
+    let recovered = rig.poll(&[("sensor1", 22.0)]).await;
+    assert!(texts_to(&recovered, CHAT)[0].starts_with('✅'), "{:?}", recovered);
+
+    let low = rig.poll(&[("sensor1", 17.0)]).await;
+    assert_eq!(texts_to(&low, CHAT).len(), 1, "{:?}", low);
+    assert!(low[0].text.contains("17.0"), "{}", low[0].text);
+    let recovered = rig.poll(&[("sensor1", 20.0)]).await;
+    assert!(texts_to(&recovered, CHAT)[0].starts_with('✅'), "{:?}", recovered);
+
+    // Tiefst- und Höchstwert der Folge sind als Rekord vermerkt
+    let records = rig.shared.records.lock().await;
+    let series = records.get("sensor1", "temperature").unwrap();
+    assert_eq!(series.all_time.min.map(|e| e.value), Some(17.0));
+    assert_eq!(series.all_time.max.map(|e| e.value), Some(26.0));
+}
+
+#[tokio::test]
+async fn mock_source_flag_transitions() {
+    let mut rig = Rig::mocked().await;
+    serve(Utc::now(), &[("sensor1", 22.0)]);
+    rig.command("/setmax sensor1 temperature 25").await;
+    let key = (CHAT, "sensor1".to_string(), ThresholdKey::new(SensorKind::Temperature, ThresholdDirection::Max));
+    let flag = |rig: &Rig| rig.shared.flags.try_lock().unwrap().get(&key).copied().unwrap_or(false);
+
+    assert!(rig.poll(&[("sensor1", 24.0)]).await.is_empty());
+    assert!(!flag(&rig));
+    assert_eq!(rig.poll(&[("sensor1", 26.0)]).await.len(), 1);
+    assert!(flag(&rig));
+    // Bleibt über der Schwelle: keine zweite Warnung, Zustand bleibt
+    assert!(rig.poll(&[("sensor1", 27.0)]).await.is_empty());
+    assert!(flag(&rig));
+    // Ein gescheiterter Abruf ändert nichts am Zustand
+    *rig.mock.as_ref().unwrap().0.lock().unwrap() = Err(FetchError::Request("zeitüberschreitung".to_string()));
+    assert!(rig.run().await.is_empty());
+    assert!(flag(&rig));
+    assert_eq!(rig.poll(&[("sensor1", 23.0)]).await.len(), 1);
+    assert!(!flag(&rig));
+    // Nach der Entwarnung warnt ein neuer Anstieg wieder
+    assert_eq!(rig.poll(&[("sensor1", 26.5)]).await.len(), 1);
+    assert!(flag(&rig));
+}
+
+#[tokio::test]
+async fn mock_source_several_users_on_one_sensor() {
+    let mut rig = Rig::mocked().await;
+    serve(Utc::now(), &[("sensor1", 22.0)]);
+    rig.command_in(CHAT, "/setmax sensor1 temperature 25").await;
+    rig.command_in(OTHER, "/setmax sensor1 temperature 27").await;
+
+    let sent = rig.poll(&[("sensor1", 26.0)]).await;
+    assert_eq!(texts_to(&sent, CHAT).len(), 1, "{:?}", sent);
+    assert!(texts_to(&sent, OTHER).is_empty(), "{:?}", sent);
+    let sent = rig.poll(&[("sensor1", 28.0)]).await;
+    assert!(texts_to(&sent, CHAT).is_empty(), "{:?}", sent);
+    assert_eq!(texts_to(&sent, OTHER).len(), 1, "{:?}", sent);
+    let sent = rig.poll(&[("sensor1", 23.0)]).await;
+    assert!(texts_to(&sent, CHAT)[0].starts_with('✅'), "{:?}", sent);
+    assert!(texts_to(&sent, OTHER)[0].starts_with('✅'), "{:?}", sent);
+}
+
+#[tokio::test]
+async fn two_users_cross_in_either_order() {
+    let mut rig = Rig::mocked().await;
+    serve(Utc::now(), &[("sensor1", 22.0)]);
+    rig.command_in(CHAT, "/setmax sensor1 temperature 25").await;
+    rig.command_in(OTHER, "/setmin sensor1 temperature 20").await;
+
+    // Erst unterschreitet der eine, dann überschreitet der andere
+    let sent = rig.poll(&[("sensor1", 19.0)]).await;
+    assert_eq!((texts_to(&sent, CHAT).len(), texts_to(&sent, OTHER).len()), (0, 1), "{:?}", sent);
+    let sent = rig.poll(&[("sensor1", 26.0)]).await;
+    assert_eq!(texts_to(&sent, CHAT).len(), 1, "{:?}", sent);
+    assert!(texts_to(&sent, OTHER)[0].starts_with('✅'), "{:?}", sent);
+    let sent = rig.poll(&[("sensor1", 19.0)]).await;
+    assert!(texts_to(&sent, CHAT)[0].starts_with('✅'), "{:?}", sent);
+    assert!(!texts_to(&sent, OTHER)[0].starts_with('✅'), "{:?}", sent);
+}
+
+#[tokio::test]
+async fn clearing_one_threshold_keeps_the_other_alarm() {
+    let mut rig = Rig::mocked().await;
+    serve(Utc::now(), &[("sensor1", 22.0)]);
+    rig.command_in(CHAT, "/setmax sensor1 temperature 25").await;
+    rig.command_in(OTHER, "/setmax sensor1 temperature 25").await;
+
+    let sent = rig.poll(&[("sensor1", 26.0)]).await;
+    assert_eq!((texts_to(&sent, CHAT).len(), texts_to(&sent, OTHER).len()), (1, 1), "{:?}", sent);
+    let message_id = rig.delivered(OTHER, "sensor1").await;
+
+    let replies = rig.command_in(CHAT, "/clear-threshold sensor1 temperature max").await;
+    assert!(replies[0].text.starts_with("🗑"), "{}", replies[0].text);
+    assert!(rig.poll(&[("sensor1", 26.5)]).await.is_empty());
+    let configs = rig.shared.configs.lock().await;
+    assert!(configs[&CHAT].episodes.is_empty());
+    assert_eq!(configs[&OTHER].episodes.len(), 1);
+    drop(configs);
+
+    // Entwarnung nur für den, dessen Schwelle noch steht, als Antwort auf seine Warnung
+    let sent = rig.poll(&[("sensor1", 23.0)]).await;
+    assert!(texts_to(&sent, CHAT).is_empty(), "{:?}", sent);
+    assert_eq!(sent.len(), 1, "{:?}", sent);
+    assert!(sent[0].text.starts_with('✅'), "{}", sent[0].text);
+    assert_eq!(sent[0].reply_to, Some(message_id));
+
+    // Neu gesetzt, warnt die Schwelle wieder unabhängig vom anderen
+    rig.command_in(CHAT, "/setmax sensor1 temperature 25").await;
+    let sent = rig.poll(&[("sensor1", 26.0)]).await;
+    assert_eq!((texts_to(&sent, CHAT).len(), texts_to(&sent, OTHER).len()), (1, 1), "{:?}", sent);
+}
+
+#[tokio::test]
+async fn snoozes_are_listed_counted_and_cleared() {
+    let mut rig = Rig::mocked().await;
+    serve(Utc::now(), &[("sensor1", 22.0)]);
+    rig.command("/setmin sensor1 temperature 18").await;
+    rig.command("/setmax sensor1 temperature 25").await;
+    assert_eq!(rig.command("/snoozes").await[0].text, "Keine Schwelle ist stummgeschaltet.");
+
+    let replies = rig.command("/snooze Wohnzimmer 2h").await;
+    assert!(replies[0].text.starts_with("🔇 Keine Warnungen zu Wohnzimmer"), "{}", replies[0].text);
+    let listed = rig.command("/snoozes").await;
+    let lines: Vec<&str> = listed[0].text.lines().collect();
+    assert_eq!(lines.len(), 4, "{}", listed[0].text);
+    assert!(lines[1..3].iter().all(|line| line.starts_with("Wohnzimmer Temperatur") && line.contains("– noch ")), "{}", listed[0].text);
+    assert!(lines[1..3].iter().all(|line| line.ends_with("per /snooze)")), "{}", listed[0].text);
+    storage::flush(&rig.shared.storage).await;
+    let stored = rig.shared.storage.load_users();
+    let expiry = Utc::now() + chrono::Duration::hours(2);
+    assert!(stored[&CHAT].snoozed.values().all(|snooze| (snooze.until - expiry).num_seconds().abs() < 60));
+    let status = rig.command("/status").await;
+    assert!(status[0].text.contains("🔇 2 Schwellen stummgeschaltet (/snoozes)"), "{}", status[0].text);
+
+    // Stumm: kein Alarm, der Zustand läuft aber weiter
+    assert!(rig.poll(&[("sensor1", 26.0)]).await.is_empty());
+
+    assert_eq!(rig.command("/snooze clear").await[0].text, "🔔 2 Stummschaltungen aufgehoben.");
+    assert_eq!(rig.command("/snoozes").await[0].text, "Keine Schwelle ist stummgeschaltet.");
+    let status = rig.command("/status").await;
+    assert!(!status[0].text.contains("stummgeschaltet"), "{}", status[0].text);
+    storage::flush(&rig.shared.storage).await;
+    assert!(rig.shared.storage.load_users()[&CHAT].snoozed.is_empty());
+}
+
+// Fuß von /status mit nächster Abfrage, eigenem Bericht, Stummschaltung und
+// stummgeschalteten Schwellen; abgelaufene Stummschaltungen fehlen
+#[tokio::test]
+async fn status_footer_lists_next_poll_report_and_mutes() {
+    let _rig = Rig::new().await;
+    let now = local(16, 9, 0);
+    {
+        let mut status = bot_status();
+        status.next_poll = Some(now + chrono::Duration::seconds(5 * 60 + 30));
+        status.next_reports.insert(CHAT, local(17, 7, 30));
+    }
+    let snooze = |hours: i64| crate::snooze::Snooze { until: now + chrono::Duration::hours(hours), since: now, origin: crate::snooze::Origin::Command };
+    let config = crate::UserConfig {
+        muted_until: Some(local(16, 12, 0)),
+        muted_missed: 3,
+        snoozed: [
+            (("sensor1".to_string(), ThresholdKey::new(SensorKind::Temperature, ThresholdDirection::Max)), snooze(2)),
+            (("sensor1".to_string(), ThresholdKey::new(SensorKind::Humidity, ThresholdDirection::Max)), snooze(4)),
+            (("sensor2".to_string(), ThresholdKey::new(SensorKind::Temperature, ThresholdDirection::Min)), snooze(-1)),
+        ]
+        .into_iter()
+        .collect(),
+        ..crate::UserConfig::default()
+    };
+
+    let footer = crate::status_footer(Some(&config), CHAT, now);
+    let soon = crate::status_footer(None, OTHER, now + chrono::Duration::seconds(5 * 60));
+    {
+        let mut status = bot_status();
+        status.next_poll = None;
+        status.next_reports.remove(&CHAT);
+    }
+    assert_eq!(
+        footer,
+        [
+            "⏱ Nächste Abfrage in 5 min (09:05 Uhr)",
+            "🗓 Nächster Bericht: 17.01. 07:30 Uhr",
+            "🔇 Stumm bis 16.01. 12:00 Uhr (3 Warnungen verpasst)",
+            "🔇 2 Schwellen stummgeschaltet (/snoozes)",
+        ]
+    );
+    assert_eq!(soon, ["⏱ Nächste Abfrage gleich (09:05 Uhr)"]);
+}
+
+#[tokio::test]
+async fn status_filtered_by_room_or_device() {
+    let mut rig = Rig::new().await;
+    // Über die Überwachung abgerufen, damit /status den Stand kennt
+    rig.poll(&[("sensor1", 21.5), ("sensor2", 19.0)]).await;
+
+    let all = rig.command("/status").await;
+    assert!(all[0].text.contains("Wohnzimmer") && all[0].text.contains("sensor2"), "{}", all[0].text);
+    for room in ["wohnzimmer", "WOHNZIMMER", "sensor1"] {
+        let text = &rig.command(&format!("/status {}", room)).await[0].text;
+        assert!(text.contains("*Wohnzimmer* – Temperatur: *21.5 °C*") && !text.contains("sensor2"), "{}: {}", room, text);
+    }
+    // Ohne Eintrag im Raumverzeichnis über die Geräte-ID
+    let text = &rig.command("/status Sensor2").await[0].text;
+    assert!(text.contains("*sensor2* – Temperatur: *19.0 °C*") && !text.contains("Wohnzimmer"), "{}", text);
+
+    let text = &rig.command("/status Keller").await[0].text;
+    assert_eq!(text, "Unbekannter Raum 'Keller'. Verfügbar: Wohnzimmer, sensor2");
+}
+
+// Ortszeit wie die Ruhezeit (ohne DEFAULT_TZ die des Systems)
+fn local(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Local.with_ymd_and_hms(2024, 1, day, hour, minute, 0).single().unwrap().with_timezone(&Utc)
+}
+
+#[tokio::test]
+async fn quiet_hours_hold_alerts_and_collapse_their_recovery() {
+    let mut rig = Rig::mocked().await;
+    serve(Utc::now(), &[("sensor1", 22.0)]);
+    rig.command("/setmax sensor1 temperature 25").await;
+    let replies = rig.command("/quiet-hours 22:00 07:00").await;
+    assert!(replies[0].text.starts_with("🔕 Ruhezeit 22:00-07:00"), "{}", replies[0].text);
+
+    rig.clock.set(local(15, 23, 0));
+    assert!(rig.poll(&[("sensor1", 22.0)]).await.is_empty());
+    assert!(rig.poll(&[("sensor1", 26.0)]).await.is_empty());
+    let queued = rig.quiet.lock().await.get(&CHAT).cloned().unwrap_or_default();
+    assert_eq!(queued.len(), 1, "{:?}", queued);
+    assert!(queued[0].1.contains("26.0"), "{}", queued[0].1);
+
+    // Nach Mitternacht erholt, danach Ruhezeit vorbei: keine Entwarnung
+    // zu einer Warnung, die nie zugestellt wurde, und nichts Neues in der Warteschlange
+    rig.clock.set(local(16, 2, 0));
+    assert!(rig.poll(&[("sensor1", 23.0)]).await.is_empty());
+    rig.clock.set(local(16, 7, 30));
+    assert!(rig.poll(&[("sensor1", 23.0)]).await.is_empty());
+    assert_eq!(rig.quiet.lock().await[&CHAT].len(), 1);
+    assert!(rig.shared.configs.lock().await[&CHAT].episodes.is_empty());
+}
+
+#[tokio::test]
+async fn quiet_hours_off_delivers_again() {
+    let mut rig = Rig::mocked().await;
+    serve(Utc::now(), &[("sensor1", 22.0)]);
+    rig.command("/setmax sensor1 temperature 25").await;
+    rig.command("/quiet-hours 22:00-07:00").await;
+    rig.clock.set(local(15, 23, 0));
+    assert!(rig.poll(&[("sensor1", 26.0)]).await.is_empty());
+    assert!(rig.poll(&[("sensor1", 23.0)]).await.is_empty());
+
+    assert!(rig.command("/quiet-hours off").await[0].text.starts_with("🔔 Ruhezeit deaktiviert."));
+    assert!(rig.shared.configs.lock().await[&CHAT].quiet_hours.is_none());
+    assert_eq!(rig.poll(&[("sensor1", 26.0)]).await.len(), 1);
+}
+
+#[tokio::test]
+async fn durations_with_a_multibyte_unit_or_too_many_days_are_rejected() {
+    let rig = Rig::new().await;
+    for spec in ["4ü", "99999999999999d"] {
+        assert_eq!(crate::parse_duration(spec), None, "{}", spec);
+        let text = &rig.command(&format!("/mute-all {}", spec)).await[0].text;
+        assert_eq!(text, "Verwendung: /mute-all 4h (auch 30m oder 2d)", "{}", spec);
+        let text = &rig.command(&format!("/snooze Wohnzimmer {}", spec)).await[0].text;
+        assert!(text.starts_with("Verwendung: /snooze"), "{}: {}", spec, text);
+    }
+    assert_eq!(crate::parse_duration(" 2H "), Some(chrono::Duration::hours(2)));
+}
+
+#[tokio::test]
+async fn daily_summary_follows_a_weekly_plan() {
+    let rig = Rig::new().await;
+    let text = &rig.command("/subscribe-daily mo-fr 06:30; sa,so 09:00").await[0].text;
+    assert!(text.starts_with("🌙 Tageszusammenfassung mo-fr 06:30; sa-so 09:00 Uhr"), "{}", text);
+    let text = &rig.command("/subscribe-daily 07:00 19:00").await[0].text;
+    assert!(text.starts_with("Bitte höchstens eine Uhrzeit je Wochentag angeben."), "{}", text);
+
+    let schedule = rig.shared.configs.lock().await[&CHAT].daily_summary.clone().unwrap();
+    // 13.01.2024 ist ein Samstag
+    assert_eq!(crate::last_fire(&schedule, local(13, 10, 0)), Some(local(13, 9, 0)));
+    assert_eq!(crate::last_fire(&schedule, local(15, 6, 0)), Some(local(14, 9, 0)));
+    assert_eq!(crate::last_fire(&schedule, local(15, 7, 0)), Some(local(15, 6, 30)));
+}
+
+#[test]
+fn daily_summary_and_weekly_report_speak_the_chat_language() {
+    let config = crate::UserConfig { lang: crate::Lang::En, first_name: Some("Ada".into()), ..Default::default() };
+    let history = crate::history::History::default();
+    let now = local(13, 21, 0);
+    let daily = crate::daily_summary(&config, CHAT, &history, now - chrono::Duration::days(1), now);
+    assert!(daily.starts_with("🌙 Good evening Ada, your *daily summary* since "), "{}", daily);
+    assert!(daily.ends_with("\nNo readings in this period.\n"), "{}", daily);
+    let weekly = crate::weekly_report(&config, CHAT, &history, now);
+    assert!(weekly.starts_with("📅 Hello Ada, your *weekly report* from "), "{}", weekly);
+}
+
+#[test]
+fn daily_summary_saved_as_a_single_time_is_read_as_daily() {
+    let config: crate::UserConfig = serde_json::from_str(r#"{"daily_summary": "21:00:00"}"#).unwrap();
+    assert_eq!(config.daily_summary.as_ref().map(ToString::to_string).as_deref(), Some("21:00"));
+    let saved = serde_json::to_string(&config).unwrap();
+    let config: crate::UserConfig = serde_json::from_str(&saved).unwrap();
+    assert_eq!(config.daily_summary.as_ref().map(ToString::to_string).as_deref(), Some("21:00"));
+    let config: crate::UserConfig = serde_json::from_str(r#"{"daily_summary": null}"#).unwrap();
+    assert!(config.daily_summary.is_none());
+}
+
+#[tokio::test]
+async fn relative_threshold_speaks_the_chat_language_and_uses_its_hysteresis() {
+    let mut rig = Rig::mocked().await;
+    serve(Utc::now(), &[("sensor1", 22.0), ("sensor2", 20.0)]);
+    rig.command("/language en").await;
+    rig.command("/set sensor1 temperature max-rel sensor2 temperature +2").await;
+    let key = ("sensor1".to_string(), ThresholdKey::new(SensorKind::from("temperature"), ThresholdDirection::Max));
+    rig.shared.configs.lock().await.get_mut(&CHAT).unwrap().hysteresis.insert(key, 3.0);
+
+    let sent = rig.poll(&[("sensor1", 25.0), ("sensor2", 20.0)]).await;
+    assert_eq!(texts_to(&sent, CHAT), ["↕️ Temperature Wohnzimmer: 25.0 °C above the limit 22.0 °C (sensor2 20.0 °C +2.0 °C)."]);
+    // Unter der Grenze, aber innerhalb der eigenen Hysterese von 3 °C
+    assert!(rig.poll(&[("sensor1", 21.0), ("sensor2", 20.0)]).await.is_empty());
+    let sent = rig.poll(&[("sensor1", 18.5), ("sensor2", 20.0)]).await;
+    assert_eq!(texts_to(&sent, CHAT), ["✅ Temperature Wohnzimmer: 18.5 °C back below the limit 22.0 °C (sensor2 20.0 °C +2.0 °C)."]);
+}
+
+#[tokio::test]
+async fn vanished_sensor_is_announced_in_the_chat_language() {
+    let mut rig = Rig::mocked().await;
+    serve(Utc::now(), &[("sensor1", 22.0), ("sensor2", 19.0)]);
+    rig.command("/language en").await;
+    rig.command("/setmax sensor1 temperature 25").await;
+    rig.poll(&[("sensor1", 22.0), ("sensor2", 19.0)]).await;
+
+    let mut gone = Vec::new();
+    for _ in 0..3 {
+        let sent = rig.poll(&[("sensor2", 19.0)]).await;
+        gone.extend(texts_to(&sent, CHAT).into_iter().filter(|text| text.starts_with('❌')).map(str::to_string));
+    }
+    assert_eq!(gone, ["❌ Sensor Wohnzimmer has stopped reporting."]);
+    let sent = rig.poll(&[("sensor1", 22.0), ("sensor2", 19.0)]).await;
+    assert!(texts_to(&sent, CHAT).contains(&"✅ Wohnzimmer is sending Temperature again."), "{:?}", sent);
+}
+
+#[tokio::test]
+async fn threshold_without_readings_is_reported_in_the_chat_language() {
+    let mut rig = Rig::mocked().await;
+    rig.command("/language en").await;
+    rig.command("/setmin sensor1 humidity 30").await;
+    rig.poll(&[("sensor1", 22.0)]).await;
+    rig.clock.advance(48 * 60);
+    let sent = rig.poll(&[("sensor1", 22.0)]).await;
+    let notices: Vec<&str> = texts_to(&sent, CHAT).into_iter().filter(|text| text.starts_with('⚠')).collect();
+    assert_eq!(
+        notices,
+        [
+            "⚠ This threshold cannot be monitored: Wohnzimmer – Humidity min.\nNo reading for device 'sensor1' / type 'humidity' in 48 h. Please check /thresholds."
+        ]
+    );
+}
+
+#[tokio::test]
+async fn unknown_room_is_answered_in_the_chat_language() {
+    let rig = Rig::mocked().await;
+    rig.command("/language en").await;
+    let sent = rig.command("/records Keller").await;
+    assert_eq!(sent[0].text, "Unknown room 'Keller'. Available: Wohnzimmer");
+}
+
+#[tokio::test]
+async fn settings_thresholds_and_snoozes_speak_the_chat_language() {
+    let rig = Rig::mocked().await;
+    rig.command("/language en").await;
+    rig.command("/setmax sensor1 temperature 25").await;
+    let sent = rig.command("/snooze Wohnzimmer 2h").await;
+    let listed = rig.command("/thresholds").await;
+    assert!(listed[0].text.starts_with("📏 *Your thresholds:*\n"), "{}", listed[0].text);
+    assert!(listed[0].text.contains("🔇 until ") && listed[0].text.contains("25.0 °C (always)"), "{}", listed[0].text);
+    assert!(sent[0].text.starts_with("🔇 No alerts for Wohnzimmer until "), "{}", sent[0].text);
+    let sent = rig.command("/snoozes").await;
+    assert!(sent[0].text.starts_with("🔇 Muted thresholds:\nWohnzimmer Temperature MAX – "), "{}", sent[0].text);
+    assert!(sent[0].text.ends_with("by /snooze)\nLift all with /snooze clear."), "{}", sent[0].text);
+    let sent = rig.command("/settings").await;
+    assert!(sent[0].text.contains("Alerts: immediately (/alert-mode)"), "{}", sent[0].text);
+    assert!(sent[0].text.ends_with("Muting: off\n"), "{}", sent[0].text);
+}
+
+#[tokio::test]
+async fn clear_preview_speaks_the_chat_language() {
+    let rig = Rig::mocked().await;
+    rig.command("/language en").await;
+    rig.command("/setmax sensor1 temperature 25").await;
+    let sent = rig.command("/clear all").await;
+    assert!(sent[0].text.contains("\n• 1 thresholds\n"), "{}", sent[0].text);
+    assert!(sent[0].text.ends_with("\n\nConfirm within 2 min."), "{}", sent[0].text);
+}
+
+#[tokio::test]
+async fn history_and_sensors_speak_the_chat_language() {
+    let mut rig = Rig::mocked().await;
+    rig.command("/language en").await;
+    rig.poll(&[("sensor1", 21.0)]).await;
+    let sent = rig.command("/history sensor1 temperature").await;
+    assert!(sent[0].text.starts_with("📈 Temperature Wohnzimmer, last 6 h (1 values):\n"), "{}", sent[0].text);
+    let sent = rig.command("/sensors").await;
+    assert!(sent[0].text.starts_with("📡 Reported devices (1):\nsensor1 (Wohnzimmer): temperature – "), "{}", sent[0].text);
+    assert!(sent[0].text.contains(" ago\nUse device ID and type"), "{}", sent[0].text);
+}
+
+#[tokio::test]
+async fn reminder_speaks_the_chat_language() {
+    let mut rig = Rig::mocked().await;
+    rig.command("/language en").await;
+    rig.command("/setmax sensor1 temperature 25").await;
+    rig.command("/repeat sensor1 temperature max 10m").await;
+    rig.poll(&[("sensor1", 27.0)]).await;
+    let mut reminders = Vec::new();
+    for _ in 0..2 {
+        let sent = rig.poll(&[("sensor1", 27.5)]).await;
+        reminders.extend(texts_to(&sent, CHAT).into_iter().filter(|text| text.starts_with('🔁')).map(str::to_string));
+    }
+    assert_eq!(reminders, ["🔁 Still: Temperature Wohnzimmer above the threshold (25.0 °C) for 10 min, now 27.5 °C."]);
+}
+
+#[test]
+fn every_command_is_described_in_every_language() {
+    for command in Command::bot_commands() {
+        let name = command.command.trim_start_matches('/');
+        for lang in crate::Lang::ALL {
+            assert!(crate::i18n::command_description(lang, name).is_some(), "/{} ohne Beschreibung in {}", name, lang.code());
+        }
+    }
+}
+
+// Befehle mit Bindestrich stehen mit Unterstrich im Menü und werden in dieser
+// Schreibweise auch verstanden
+#[test]
+fn menu_lists_kebab_case_commands_with_underscores() {
+    let everyone = crate::menu_commands(crate::Lang::En, false);
+    let admin = crate::menu_commands(crate::Lang::En, true);
+    assert_eq!(admin.len(), Command::bot_commands().len());
+    assert!(admin.len() <= 100, "Telegram nimmt höchstens 100 Befehle ins Menü");
+    assert_eq!(everyone.len(), admin.len() - crate::ADMIN_COMMANDS.len());
+    for entry in &admin {
+        assert!(entry.command.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'), "{}", entry.command);
+    }
+    let quiet = everyone.iter().find(|entry| entry.command == "quiet_hours").expect("quiet_hours im Menü");
+    assert_eq!(Some(quiet.description.as_str()), crate::i18n::command_description(crate::Lang::En, "quiet-hours"));
+
+    assert_eq!(crate::expand_command("/quiet_hours 22:00 07:00"), "/quiet-hours 22:00 07:00");
+    assert_eq!(crate::expand_command("/test_alarm@sensorbot"), "/test-alarm@sensorbot");
+    assert!(matches!(Command::parse(&crate::expand_command("/quiet_hours off"), "sensorbot"), Ok(Command::QuietHours(spec)) if spec == "off"));
+    // Echte Befehle und unbekannte bleiben, wie sie sind
+    assert_eq!(crate::expand_command("/status_x"), "/status_x");
+    assert_eq!(crate::expand_command("/quiet-hours off"), "/quiet-hours off");
+}
+
+#[test]
+fn usage_hints_speak_the_chat_language() {
+    let message: Message = serde_json::from_value(serde_json::json!({
+        "message_id": 1,
+        "date": Utc::now().timestamp(),
+        "chat": {"id": CHAT, "type": "private", "first_name": "Ada"},
+        "from": {"id": CHAT, "is_bot": false, "first_name": "Ada", "language_code": "en-GB"},
+        "text": "/wohnzimmer-tmin warm",
+    }))
+    .unwrap();
+    let me: teloxide::types::Me = serde_json::from_value(serde_json::json!({
+        "id": 1,
+        "is_bot": true,
+        "first_name": "Sensorbot",
+        "username": "sensorbot",
+        "can_join_groups": false,
+        "can_read_all_group_messages": false,
+        "supports_inline_queries": false,
+    }))
+    .unwrap();
+    let hint = crate::command_usage_hint(&message, &me).unwrap();
+    assert!(crate::format_usage_hint(&hint, crate::Lang::De).starts_with("❌ 'warm' ist keine Zahl"));
+    assert!(crate::format_usage_hint(&hint, crate::Lang::En).starts_with("❌ 'warm' is not a number"));
+    assert_eq!(crate::profile_lang(message.from()), crate::Lang::En);
+
+    let message: Message = serde_json::from_value(serde_json::json!({
+        "message_id": 2,
+        "date": Utc::now().timestamp(),
+        "chat": {"id": CHAT, "type": "private", "first_name": "Ada"},
+        "text": "/setmax sensor1 temperature warm",
+    }))
+    .unwrap();
+    let hint = crate::command_usage_hint(&message, &me).unwrap();
+    assert_eq!(
+        crate::format_usage_hint(&hint, crate::Lang::En),
+        "❌ 'warm' is not a number. Usage: <device> <type> <value> [HH:MM-HH:MM] [info|warn|critical] [\"text\"]\nℹ️ /setmax: Set a maximum threshold."
+    );
+}
+
+#[tokio::test]
+async fn alert_buttons_speak_the_chat_language() {
+    let mut rig = Rig::mocked().await;
+    rig.command("/language en").await;
+    rig.command("/setmax sensor1 temperature 25").await;
+    let sent = rig.poll(&[("sensor1", 27.0)]).await;
+    let buttons = sent.iter().find_map(|message| message.buttons.as_ref()).expect("Warnung mit Buttons");
+    let labels: Vec<&str> = buttons.inline_keyboard.iter().flatten().map(|button| button.text.as_str()).collect();
+    assert_eq!(labels, ["−1", "+1", "Adjust threshold…", "✅ OK", "😴 1h", "😴 6h", "😴 until tomorrow"]);
+}
+
+#[tokio::test]
+async fn parse_errors_speak_the_chat_language() {
+    let rig = Rig::mocked().await;
+    rig.command("/language en").await;
+    let sent = rig.command("/quiet-hours 25:00 07:00").await;
+    assert!(sent[0].text.starts_with("❌ Invalid time '25:00' (expected HH:MM)\n"), "{}", sent[0].text);
+    let sent = rig.command("/schedule xy 07:00").await;
+    assert_eq!(sent[0].text, "❌ Unknown weekday 'xy' (mo, di, mi, do, fr, sa, so)");
+    let sent = rig.command("/layout wide").await;
+    assert_eq!(sent[0].text, "Usage: /format compact|table");
+}
+
+#[tokio::test]
+async fn schedules_speak_the_chat_language() {
+    let rig = Rig::new().await;
+    rig.command("/language en").await;
+    rig.command("/schedule mo 08:00").await;
+    let text = &rig.command("/schedules").await[0].text;
+    assert!(text.starts_with("📅 *Status report – weekly plan:*"), "{}", text);
+    assert!(text.contains("Mon"), "{}", text);
+    assert!(text.contains("Next report: "), "{}", text);
+}
+
+#[tokio::test]
+async fn admin_reports_speak_the_admin_language() {
+    let report = crate::fleet::format_report(&[], 0, 0, crate::Lang::En);
+    assert!(report[0].starts_with("🛰 *Weekly fleet report* "), "{}", report[0]);
+    assert!(report[0].ends_with("\n\nNo readings this week."), "{}", report[0]);
+
+    let mut test = crate::SelfTest::default();
+    test.register(crate::Text::new("selftest_storage", &[]), true, || async { Ok(crate::Text::new("selftest_writable", &[])) });
+    test.register(crate::Text::verbatim("Wetter"), false, || async { Err(crate::Text::verbatim("HTTP 503")) });
+    let summary = crate::selftest::summary(&test.run().await, crate::Lang::En);
+    assert!(summary.starts_with("🩺 Self-test: 1 of 2 checks failed, the bot runs with limitations\n✅ Storage ("), "{}", summary);
+    assert!(summary.ends_with("): HTTP 503"), "{}", summary);
+}
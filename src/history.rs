@@ -0,0 +1,440 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{HashMap, VecDeque};
+
+const DAY: i64 = 24 * 60 * 60;
+const FIVE_MINUTES: i64 = 5 * 60;
+const HOUR: i64 = 60 * 60;
+
+// Stufen der Aufbewahrung, damit die Datei auf einer SD-Karte klein bleibt:
+// Rohwerte, dann 5-Minuten-Mittel, dann Stundenmittel. `compact` rechnet
+// ältere Werte in die nächste Stufe um und entfernt, was zu alt ist.
+const RAW_MAX_AGE_SECONDS: i64 = 7 * DAY;
+const FIVE_MINUTE_MAX_AGE_SECONDS: i64 = 90 * DAY;
+const HOURLY_MAX_AGE_SECONDS: i64 = 2 * 365 * DAY;
+
+/// Zusammengefasste Messwerte eines Zeitraums ab `start`; ein Rohwert ist
+/// ein Intervall mit `count` 1.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(from = "(i64, f64, f64, f64, u32)", into = "(i64, f64, f64, f64, u32)")]
+pub struct Bucket {
+    pub start: i64,
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+    pub count: u32,
+}
+
+impl Bucket {
+    fn sample(timestamp: i64, value: f64) -> Bucket {
+        Bucket { start: timestamp, mean: value, min: value, max: value, count: 1 }
+    }
+
+    fn merge(&mut self, other: &Bucket) {
+        let count = self.count + other.count;
+        self.mean = (self.mean * self.count as f64 + other.mean * other.count as f64) / count as f64;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.count = count;
+    }
+}
+
+// Im JSON als Tupel, das spart auf Dauer einiges an Platz
+impl From<(i64, f64, f64, f64, u32)> for Bucket {
+    fn from((start, mean, min, max, count): (i64, f64, f64, f64, u32)) -> Bucket {
+        Bucket { start, mean, min, max, count }
+    }
+}
+
+impl From<Bucket> for (i64, f64, f64, f64, u32) {
+    fn from(b: Bucket) -> Self {
+        (b.start, b.mean, b.min, b.max, b.count)
+    }
+}
+
+// Beginn des Intervalls der Länge `step`, in dem `timestamp` liegt
+fn align(timestamp: i64, step: i64) -> i64 {
+    timestamp.div_euclid(step) * step
+}
+
+// In eine nach Beginn sortierte Stufe einfügen oder mit dem Intervall zusammenführen
+fn add(tier: &mut VecDeque<Bucket>, bucket: Bucket) {
+    match tier.binary_search_by_key(&bucket.start, |b| b.start) {
+        Ok(index) => tier[index].merge(&bucket),
+        Err(index) => tier.insert(index, bucket),
+    }
+}
+
+#[derive(Default)]
+struct Series {
+    raw: VecDeque<(i64, f64)>,
+    five_minutes: VecDeque<Bucket>,
+    hourly: VecDeque<Bucket>,
+}
+
+impl Series {
+    // Ab hier gibt es Rohwerte bzw. 5-Minuten-Mittel
+    fn raw_start(&self) -> i64 {
+        self.raw.front().map_or(i64::MAX, |&(ts, _)| ts)
+    }
+
+    fn five_minute_start(&self) -> i64 {
+        self.five_minutes.front().map_or(i64::MAX, |b| b.start).min(self.raw_start())
+    }
+
+    // Ende der verdichteten Daten; ältere Rohwerte sind schon enthalten
+    fn compacted_until(&self) -> i64 {
+        match self.five_minutes.back() {
+            Some(last) => last.start + FIVE_MINUTES,
+            None => self.hourly.back().map_or(i64::MIN, |last| last.start + HOUR),
+        }
+    }
+
+    // Je Zeitraum die feinste Stufe: Stundenmittel bis zum ersten
+    // 5-Minuten-Mittel, diese bis zum ersten Rohwert, dann Rohwerte
+    fn points_since(&self, since: i64) -> impl Iterator<Item = Bucket> + '_ {
+        let (five_start, raw_start) = (self.five_minute_start(), self.raw_start());
+        let hourly = self.hourly.iter().filter(move |b| b.start >= since && b.start < five_start);
+        let five = self.five_minutes.iter().filter(move |b| b.start >= since && b.start < raw_start);
+        let raw = self.raw.iter().filter(move |(ts, _)| *ts >= since).map(|&(ts, value)| Bucket::sample(ts, value));
+        hourly.chain(five).copied().chain(raw)
+    }
+
+    // Liefert die Zahl umgerechneter oder entfernter Einträge
+    fn compact(&mut self, now: i64) -> usize {
+        let mut changed = 0;
+        let raw_cutoff = align(now - RAW_MAX_AGE_SECONDS, FIVE_MINUTES);
+        while let Some(&(ts, value)) = self.raw.front().filter(|(ts, _)| *ts < raw_cutoff) {
+            self.raw.pop_front();
+            add(&mut self.five_minutes, Bucket { start: align(ts, FIVE_MINUTES), ..Bucket::sample(ts, value) });
+            changed += 1;
+        }
+        let five_cutoff = align(now - FIVE_MINUTE_MAX_AGE_SECONDS, HOUR);
+        while let Some(bucket) = self.five_minutes.front().copied().filter(|b| b.start < five_cutoff) {
+            self.five_minutes.pop_front();
+            add(&mut self.hourly, Bucket { start: align(bucket.start, HOUR), ..bucket });
+            changed += 1;
+        }
+        while self.hourly.front().is_some_and(|b| b.start < now - HOURLY_MAX_AGE_SECONDS) {
+            self.hourly.pop_front();
+            changed += 1;
+        }
+        changed
+    }
+
+    fn is_empty(&self) -> bool {
+        self.raw.is_empty() && self.five_minutes.is_empty() && self.hourly.is_empty()
+    }
+}
+
+/// Verteilung der Werte einer Messreihe über einen Zeitraum; `low` und
+/// `high` sind das 10- und 90-%-Quantil.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Distribution {
+    pub min: f64,
+    pub low: f64,
+    pub median: f64,
+    pub high: f64,
+    pub max: f64,
+}
+
+// Verlauf je (Gerät, Typ): Rohwerte als (Zeitstempel, Wert) und ältere
+// Werte verdichtet
+#[derive(Default)]
+pub struct History {
+    series: HashMap<(String, String), Series>,
+}
+
+// Gespeicherte Form einer Messreihe
+#[derive(Serialize, Deserialize)]
+struct SeriesRecord {
+    device_id: String,
+    sensor_type: String,
+    samples: VecDeque<(i64, f64)>,
+    #[serde(default, skip_serializing_if = "VecDeque::is_empty")]
+    five_minutes: VecDeque<Bucket>,
+    #[serde(default, skip_serializing_if = "VecDeque::is_empty")]
+    hourly: VecDeque<Bucket>,
+}
+
+impl History {
+    // Neuen Messwert anhängen. Liefert der Server denselben Wert erneut
+    // (gleicher Zeitstempel), wird er nicht doppelt gespeichert. Ältere
+    // Werte verdichtet erst `compact`.
+    pub fn record(&mut self, device_id: &str, sensor_type: &str, timestamp: i64, value: f64) {
+        let series = self.series.entry((device_id.to_string(), sensor_type.to_string())).or_default();
+
+        if series.raw.back().is_some_and(|&(last, _)| timestamp <= last) {
+            return;
+        }
+        series.raw.push_back((timestamp, value));
+    }
+
+    // Ältere Messwerte nachtragen (Import): einsortiert statt angehängt,
+    // vorhandene Zeitstempel bleiben unverändert. Was älter als die
+    // Aufbewahrung oder schon verdichtet ist, entfällt (sonst zählte es
+    // doppelt). Liefert die Zahl eingefügter Werte; danach `compact` aufrufen.
+    pub fn import(&mut self, samples: Vec<(String, String, i64, f64)>) -> usize {
+        let mut stored = 0;
+        for (device_id, sensor_type, timestamp, value) in samples {
+            let series = self.series.entry((device_id, sensor_type)).or_default();
+            let newest = series.raw.back().map_or(timestamp, |&(last, _)| last.max(timestamp));
+            if timestamp < newest - HOURLY_MAX_AGE_SECONDS || timestamp < series.compacted_until() {
+                continue;
+            }
+            if let Err(index) = series.raw.binary_search_by_key(&timestamp, |&(ts, _)| ts) {
+                series.raw.insert(index, (timestamp, value));
+                stored += 1;
+            }
+        }
+        stored
+    }
+
+    // Rohwerte von 7 Tagen, 5-Minuten-Mittel von 90 Tagen, Stundenmittel
+    // von 2 Jahren behalten. Liefert die Zahl umgerechneter oder entfernter Einträge.
+    pub fn compact(&mut self, now: i64) -> usize {
+        let changed = self.series.values_mut().map(|series| series.compact(now)).sum();
+        self.series.retain(|_, series| !series.is_empty());
+        changed
+    }
+
+    // Gemeldete Typen eines Geräts, sortiert
+    pub fn sensor_types(&self, device_id: &str) -> Vec<&str> {
+        let mut types: Vec<&str> = self.series.keys().filter(|(device, _)| device == device_id).map(|(_, typ)| typ.as_str()).collect();
+        types.sort_unstable();
+        types
+    }
+
+    // Gespeicherte Einträge (Rohwerte und Mittel) je Gerät, für /debug
+    pub fn entries_per_device(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for ((device, _), series) in &self.series {
+            *counts.entry(device).or_default() += series.raw.len() + series.five_minutes.len() + series.hourly.len();
+        }
+        let mut counts: Vec<(String, usize)> = counts.into_iter().map(|(device, n)| (device.to_string(), n)).collect();
+        counts.sort();
+        counts
+    }
+
+    // Rohwerte einer Messreihe
+    pub fn series(&self, device_id: &str, sensor_type: &str) -> Option<&VecDeque<(i64, f64)>> {
+        self.series.get(&(device_id.to_string(), sensor_type.to_string())).map(|series| &series.raw)
+    }
+
+    // Alle Rohwerte ab `since` als (Gerät, Typ, Zeitstempel, Wert), zeitlich sortiert
+    pub fn samples_since(&self, since: i64) -> Vec<(&str, &str, i64, f64)> {
+        let mut samples: Vec<_> = self
+            .series
+            .iter()
+            .flat_map(|((device, typ), series)| {
+                series.raw.iter().filter(move |(ts, _)| *ts >= since).map(move |&(ts, value)| (device.as_str(), typ.as_str(), ts, value))
+            })
+            .collect();
+        samples.sort_by_key(|&(device, typ, ts, _)| (ts, device, typ));
+        samples
+    }
+
+    // Verlauf ab `since` über alle Stufen, je Zeitraum in der feinsten
+    // vorhandenen Auflösung, als (Gerät, Typ, Intervall), zeitlich sortiert
+    pub fn points_since(&self, since: i64) -> Vec<(&str, &str, Bucket)> {
+        let mut points: Vec<_> = self
+            .series
+            .iter()
+            .flat_map(|((device, typ), series)| series.points_since(since).map(move |bucket| (device.as_str(), typ.as_str(), bucket)))
+            .collect();
+        points.sort_by_key(|&(device, typ, bucket)| (bucket.start, device, typ));
+        points
+    }
+
+    // Verteilung ab `since` über alle Stufen. Die Quantile kommen aus den
+    // Mittelwerten der Intervalle, gewichtet mit ihrer Zahl an Messwerten;
+    // Minimum und Maximum sind exakt. None ohne Messwerte.
+    pub fn distribution(&self, device_id: &str, sensor_type: &str, since: i64) -> Option<Distribution> {
+        let series = self.series.get(&(device_id.to_string(), sensor_type.to_string()))?;
+        let mut buckets: Vec<Bucket> = series.points_since(since).collect();
+        if buckets.is_empty() {
+            return None;
+        }
+        let min = buckets.iter().map(|b| b.min).fold(f64::INFINITY, f64::min);
+        let max = buckets.iter().map(|b| b.max).fold(f64::NEG_INFINITY, f64::max);
+        buckets.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+        let total: u64 = buckets.iter().map(|b| b.count as u64).sum();
+        let quantile = |q: f64| {
+            let target = ((total as f64 * q).ceil() as u64).max(1);
+            let mut seen = 0;
+            for bucket in &buckets {
+                seen += bucket.count as u64;
+                if seen >= target {
+                    return bucket.mean;
+                }
+            }
+            buckets[buckets.len() - 1].mean
+        };
+        Some(Distribution { min, low: quantile(0.1), median: quantile(0.5), high: quantile(0.9), max })
+    }
+
+    // Beginn der aktuellen gleichgerichteten Bewegung bis zum neuesten Wert:
+    // steigend (`rising`) oder fallend. Gleichbleibende Werte unterbrechen
+    // den Trend nicht. Kein Trend, wenn sich der Wert nicht verändert hat.
+    pub fn trend_start(&self, device_id: &str, sensor_type: &str, rising: bool) -> Option<(i64, f64)> {
+        let series = self.series(device_id, sensor_type)?;
+        let &(_, latest) = series.back()?;
+
+        let mut start = *series.back()?;
+        for &(ts, value) in series.iter().rev().skip(1) {
+            let continues = if rising { value <= start.1 } else { value >= start.1 };
+            if !continues {
+                break;
+            }
+            start = (ts, value);
+        }
+
+        if start.1 == latest { None } else { Some(start) }
+    }
+}
+
+impl Serialize for History {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut records: Vec<SeriesRecord> = self
+            .series
+            .iter()
+            .map(|((device_id, sensor_type), series)| SeriesRecord {
+                device_id: device_id.clone(),
+                sensor_type: sensor_type.clone(),
+                samples: series.raw.clone(),
+                five_minutes: series.five_minutes.clone(),
+                hourly: series.hourly.clone(),
+            })
+            .collect();
+        records.sort_by(|a, b| (&a.device_id, &a.sensor_type).cmp(&(&b.device_id, &b.sensor_type)));
+        records.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for History {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let records = Vec::<SeriesRecord>::deserialize(deserializer)?;
+        let series = records
+            .into_iter()
+            .map(|r| {
+                let series = Series { raw: r.samples, five_minutes: r.five_minutes, hourly: r.hourly };
+                ((r.device_id, r.sensor_type), series)
+            })
+            .collect();
+        Ok(History { series })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: i64 = 1_699_999_200; // volle Stunde
+
+    // Alle `step` Sekunden ein Messwert bis `end`; liefert deren Zahl
+    fn filled(history: &mut History, step: i64, end: i64) -> u32 {
+        let mut count = 0;
+        for ts in (START..=end).step_by(step as usize) {
+            history.record("sensor1", "temperature", ts, (ts % 7) as f64);
+            count += 1;
+        }
+        count
+    }
+
+    // Jeder Messwert steckt in genau einem Punkt, die Punkte folgen lückenlos aufeinander
+    fn assert_stitched(points: &[Bucket], recorded: u32, max_gap: i64) {
+        assert_eq!(points.iter().map(|b| b.count).sum::<u32>(), recorded);
+        for pair in points.windows(2) {
+            assert!(pair[0].start < pair[1].start, "doppelt: {:?}", pair);
+            assert!(pair[1].start - pair[0].start <= max_gap, "Lücke: {:?}", pair);
+        }
+    }
+
+    fn stitched(history: &History, since: i64) -> Vec<Bucket> {
+        history.points_since(since).into_iter().map(|(_, _, bucket)| bucket).collect()
+    }
+
+    #[test]
+    fn five_minute_means_end_where_raw_values_begin() {
+        let mut history = History::default();
+        let now = START + 8 * DAY;
+        let recorded = filled(&mut history, 60, now);
+        assert!(history.compact(now) > 0);
+
+        let points = stitched(&history, START);
+        let first_raw = points.iter().position(|b| b.count == 1).unwrap();
+        assert_eq!(points[first_raw].start, align(now - RAW_MAX_AGE_SECONDS, FIVE_MINUTES));
+        assert_eq!(points[first_raw - 1].start + FIVE_MINUTES, points[first_raw].start);
+        assert!(points[..first_raw].iter().all(|b| b.count == 5));
+        assert_stitched(&points, recorded, FIVE_MINUTES);
+    }
+
+    #[test]
+    fn all_three_tiers_are_stitched_without_overlap() {
+        let mut history = History::default();
+        let now = START + 92 * DAY;
+        let recorded = filled(&mut history, FIVE_MINUTES, now);
+        history.compact(now);
+
+        let points = stitched(&history, START);
+        let hourly = points.iter().take_while(|b| b.count == 12).count();
+        assert!(hourly > 0 && points[hourly].start - points[hourly - 1].start == HOUR);
+        assert_eq!(points[hourly].start, align(now - FIVE_MINUTE_MAX_AGE_SECONDS, HOUR));
+        assert_stitched(&points, recorded, HOUR);
+
+        // Ab einem späteren Zeitpunkt nur die Punkte danach
+        let later = stitched(&history, now - DAY);
+        assert_eq!(later.first().map(|b| b.start), Some(now - DAY));
+        assert_stitched(&later, DAY as u32 / FIVE_MINUTES as u32 + 1, FIVE_MINUTES);
+    }
+
+    #[test]
+    fn compacted_values_are_neither_imported_nor_recorded_twice() {
+        let mut history = History::default();
+        let now = START + 8 * DAY;
+        let recorded = filled(&mut history, 60, now);
+        history.compact(now);
+
+        let again = (START..now).step_by(600).map(|ts| ("sensor1".to_string(), "temperature".to_string(), ts, 99.0)).collect();
+        history.import(again);
+        history.record("sensor1", "temperature", now, 99.0);
+        history.compact(now);
+        assert_stitched(&stitched(&history, START), recorded, FIVE_MINUTES);
+    }
+
+    #[test]
+    fn distribution_without_values_is_none() {
+        let mut history = History::default();
+        assert_eq!(history.distribution("sensor1", "temperature", START), None);
+        history.record("sensor1", "temperature", START, 21.0);
+        assert_eq!(history.distribution("sensor1", "temperature", START + 1), None);
+        assert_eq!(history.distribution("sensor1", "humidity", START), None);
+    }
+
+    #[test]
+    fn quantiles_hit_the_bucket_at_the_edge() {
+        let mut history = History::default();
+        for n in 1..=10 {
+            history.record("sensor1", "temperature", START + n * 60, n as f64);
+        }
+        let all = history.distribution("sensor1", "temperature", START).unwrap();
+        assert_eq!(all, Distribution { min: 1.0, low: 1.0, median: 5.0, high: 9.0, max: 10.0 });
+        // Ab genau dem Zeitstempel eines Werts zählt er mit
+        let since = history.distribution("sensor1", "temperature", START + 6 * 60).unwrap();
+        assert_eq!((since.min, since.low, since.max), (6.0, 6.0, 10.0));
+    }
+
+    #[test]
+    fn means_are_weighted_with_their_count_and_extremes_stay_exact() {
+        let mut history = History::default();
+        for (n, value) in [18.0, 19.0, 20.0, 21.0, 22.0].into_iter().enumerate() {
+            history.record("sensor1", "temperature", START + n as i64 * 60, value);
+        }
+        let now = START + 8 * DAY;
+        history.record("sensor1", "temperature", now, 30.0);
+        history.compact(now);
+
+        // Ein 5-Minuten-Mittel 20.0 aus fünf Werten und ein Rohwert 30.0
+        let dist = history.distribution("sensor1", "temperature", START).unwrap();
+        assert_eq!(dist, Distribution { min: 18.0, low: 20.0, median: 20.0, high: 30.0, max: 30.0 });
+    }
+}
@@ -0,0 +1,26 @@
+use crate::SensorData;
+
+/// Dauerhafter Speicher für Rohwerte neben dem JSON-Verlauf, eingebaut
+/// SQLite (Feature sqlite, DATABASE_PATH). /history und /chart reichen
+/// damit bis zu einem Jahr zurück.
+///
+/// Stabilität: Teil der öffentlichen Schnittstelle, Änderungen nur mit
+/// neuer Hauptversion.
+pub trait HistoryStore: Send + Sync {
+    /// Messwerte eintragen; schon vorhandene Zeitstempel werden übergangen.
+    /// Liefert die Zahl neuer Einträge.
+    fn insert(&self, readings: &[&SensorData]) -> Result<usize, String>;
+
+    /// Rohwerte einer Messreihe ab `since` als (Zeitstempel, Wert), zeitlich sortiert
+    fn range(&self, device_id: &str, sensor_type: &str, since: i64) -> Result<Vec<(i64, f64)>, String>;
+
+    /// Für den Selbsttest; ohne eigene Implementierung ungeprüft (None)
+    fn check_writable(&self) -> Option<Result<(), String>> {
+        None
+    }
+
+    /// Für /debug: Größe in Bytes, falls bekannt
+    fn size(&self) -> Option<u64> {
+        None
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{SharedCharts, UserConfigs, ical, push, settings, source, telemetry};
+use axum::Router;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use chrono::Utc;
+use log::{error, info, warn};
+use std::collections::HashMap;
+
+#[derive(Clone)]
+struct ApiState {
+    configs: UserConfigs,
+    charts: SharedCharts,
+}
+
+// Eingebauter HTTP-Server (HTTP_ADDR, z.B. "0.0.0.0:8090")
+pub async fn serve(addr: String, configs: UserConfigs, charts: SharedCharts) {
+    let app = Router::new()
+        .route("/api/calendar.ics", get(calendar))
+        .route("/charts/:file", get(chart))
+        .route("/readings", post(readings))
+        .with_state(ApiState { configs, charts });
+    listen("HTTP-Server", &addr, app).await;
+}
+
+// Eigener Port für Prometheus (METRICS_PORT), ohne die übrigen Endpunkte
+pub async fn serve_metrics(port: u16) {
+    let app = Router::new().route("/metrics", get(metrics));
+    listen("Metrik-Endpunkt", &format!("0.0.0.0:{}", port), app).await;
+}
+
+async fn listen(name: &str, addr: &str, app: Router) {
+    match tokio::net::TcpListener::bind(addr).await {
+        Ok(listener) => {
+            info!("{} lauscht auf {}", name, addr);
+            if let Err(err) = axum::serve(listener, app).await {
+                error!("{} beendet: {}", name, err);
+            }
+        }
+        Err(err) => error!("{} kann nicht an {} gebunden werden: {}", name, addr, err),
+    }
+}
+
+async fn metrics() -> Response {
+    let body = telemetry::render(std::time::Instant::now());
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")], body).into_response()
+}
+
+// Token aus "Authorization: Bearer …" oder ?token=… (Kalender-Apps können keine Header setzen)
+fn token<'a>(headers: &'a HeaderMap, query: &'a HashMap<String, String>) -> Option<&'a str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| query.get("token").map(String::as_str))
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+// Geplante Berichte, Ruhezeit und Stummschaltung des Token-Inhabers als iCal
+async fn calendar(State(state): State<ApiState>, headers: HeaderMap, Query(query): Query<HashMap<String, String>>) -> Response {
+    let Some(token) = token(&headers, &query) else {
+        return (StatusCode::UNAUTHORIZED, "Token fehlt (/api-token im Bot)").into_response();
+    };
+    let configs = state.configs.lock().await;
+    let Some((chat_id, config)) = configs.iter().find(|(_, c)| c.api_token.as_deref() == Some(token)) else {
+        return (StatusCode::UNAUTHORIZED, "Unbekanntes Token").into_response();
+    };
+
+    let body = ical::render(*chat_id, config, settings().timezone, Utc::now());
+    ([(header::CONTENT_TYPE, "text/calendar; charset=utf-8")], body).into_response()
+}
+
+// Messwerte direkt von Geräten: ein Objekt oder eine Liste im Format der
+// Sensorliste, mit PUSH_TOKEN als Bearer-Token
+async fn readings(headers: HeaderMap, Query(query): Query<HashMap<String, String>>, body: String) -> Response {
+    let Some(expected) = settings().push_token.as_deref() else {
+        return (StatusCode::NOT_FOUND, "POST /readings ist aus (PUSH_TOKEN)").into_response();
+    };
+    if token(&headers, &query) != Some(expected) {
+        return (StatusCode::UNAUTHORIZED, "Token fehlt oder falsch").into_response();
+    }
+    match source::parse_readings(&body) {
+        Ok((readings, skipped)) if !readings.is_empty() => {
+            if skipped > 0 {
+                warn!("POST /readings: {} von {} Einträgen übersprungen", skipped, skipped + readings.len());
+            }
+            let accepted = readings.len();
+            push::push(readings);
+            (StatusCode::ACCEPTED, format!("{} Messwerte angenommen, {} übersprungen", accepted, skipped)).into_response()
+        }
+        Ok((_, skipped)) => (StatusCode::BAD_REQUEST, format!("Keine lesbaren Messwerte ({} übersprungen)", skipped)).into_response(),
+        Err(err) => (StatusCode::BAD_REQUEST, format!("Kein JSON-Messwert: {}", err)).into_response(),
+    }
+}
+
+// Diagramm in voller Auflösung; das Token im Namen ist der Zugang
+async fn chart(State(state): State<ApiState>, Path(file): Path<String>) -> Response {
+    let Some(token) = file.strip_suffix(".png") else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    match state.charts.lock().await.get(token, tokio::time::Instant::now()) {
+        Some(png) => ([(header::CONTENT_TYPE, "image/png")], png.to_vec()).into_response(),
+        None => (StatusCode::NOT_FOUND, "Link abgelaufen").into_response(),
+    }
+}
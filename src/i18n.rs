@@ -0,0 +1,1440 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Sprache der Antworten eines Chats (/language), Standard Deutsch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    #[default]
+    De,
+    En,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::De, Lang::En];
+
+    // ISO-639-1 Code, wie ihn Telegram als language_code verwendet
+    pub fn code(self) -> &'static str {
+        match self {
+            Lang::De => "de",
+            Lang::En => "en",
+        }
+    }
+
+    // Zeitstempel in /status und Listen
+    pub fn datetime_format(self) -> &'static str {
+        match self {
+            Lang::De => "%d.%m.%Y %H:%M:%S",
+            Lang::En => "%Y-%m-%d %H:%M:%S",
+        }
+    }
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "de" | "deutsch" => Ok(Lang::De),
+            "en" | "english" => Ok(Lang::En),
+            other => Err(format!("Sprache '{}' unbekannt (de oder en) / unknown language (de or en)", other)),
+        }
+    }
+}
+
+// Antworttexte: (Schlüssel, de, en). Platzhalter in geschweiften Klammern
+// ersetzt `message_with`.
+const MESSAGES: &[(&str, &str, &str)] = &[
+    ("admin_only", "Dieser Befehl ist dem Admin vorbehalten.", "This command is reserved for the admin."),
+    ("help_title", "📖 *Hilfe:*", "📖 *Help:*"),
+    ("status_title", "📊 *Aktuelle Sensordaten:*", "📊 *Current sensor readings:*"),
+    ("unknown_room", "Unbekannter Raum '{room}'. Verfügbar: {rooms}", "Unknown room '{room}'. Available: {rooms}"),
+    ("language_set", "🌐 Sprache: Deutsch", "🌐 Language: English"),
+    ("language_usage", "Verwendung: /language de oder /language en", "Usage: /language de or /language en"),
+    ("welcome", "👋 Willkommen, {name}! Nutze /help für alle Befehle.", "👋 Welcome, {name}! Use /help for all commands."),
+    ("report_greeting", "👋 Hallo {name}, hier dein Bericht.", "👋 Hello {name}, here is your report."),
+    ("fetch_failed", "❌ Fehler beim Abrufen der Sensordaten.", "❌ Could not fetch the sensor data."),
+    ("temperature", "Temperatur", "Temperature"),
+    ("humidity", "Luftfeuchtigkeit", "Humidity"),
+    ("pressure", "Luftdruck", "Pressure"),
+    ("dewpoint", "Taupunkt", "Dew point"),
+    ("heatindex", "Hitzeindex", "Heat index"),
+    ("abshumidity", "Absolute Feuchte", "Absolute humidity"),
+    ("battery", "Batterie", "Battery"),
+    ("voltage", "Spannung", "Voltage"),
+    // Entwarnung
+    ("recovery", "✅ {type} im {room} wieder im Normalbereich: {value} {unit}", "✅ {type} in {room} back to normal: {value} {unit}"),
+    ("recovery_lowest", "⏱ Dauer: {duration} · Tiefstwert: {worst} {unit} um {at}", "⏱ Duration: {duration} · lowest: {worst} {unit} at {at}"),
+    ("recovery_highest", "⏱ Dauer: {duration} · Höchstwert: {worst} {unit} um {at}", "⏱ Duration: {duration} · highest: {worst} {unit} at {at}"),
+    ("recovery_reminder", "🔁 1 Erinnerung verschickt", "🔁 1 reminder sent"),
+    ("recovery_reminders", "🔁 {count} Erinnerungen verschickt", "🔁 {count} reminders sent"),
+    ("recovery_escalated", "📡 Währenddessen häufiger abgefragt", "📡 Polled more often meanwhile"),
+    // Ausbleibende Daten und Meldeabstände
+    ("stale", "📡 {room} liefert seit {since} Uhr keine neuen Daten.", "📡 {room} has sent no new data since {since}."),
+    ("fresh", "📡 {room} liefert wieder neue Daten (Lücke: {gap}).", "📡 {room} is sending data again (gap: {gap})."),
+    (
+        "cadence_slowed",
+        "📶 {room} meldet seltener: seit {gap} kein neuer Messwert (üblich alle {median}).",
+        "📶 {room} reports less often: no new reading for {gap} (usually every {median}).",
+    ),
+    ("cadence_resumed", "📶 {room} meldet wieder (Lücke: {gap}).", "📶 {room} is reporting again (gap: {gap})."),
+    // Neue und verschwundene Sensoren
+    ("sensor_new", "🆕 Neuer Sensor erkannt: {device}/{type}", "🆕 New sensor found: {device}/{type}"),
+    ("sensor_gone", "❌ Sensor {room} meldet sich nicht mehr.", "❌ Sensor {room} has stopped reporting."),
+    ("sensor_gone_types", "❌ {room} liefert keine Werte mehr für {types}.", "❌ {room} no longer sends values for {types}."),
+    ("sensor_returned", "✅ {room} liefert wieder {type}.", "✅ {room} is sending {type} again."),
+    // Schwellen ohne Messwerte (coverage)
+    (
+        "unmonitored",
+        "⚠ Diese Schwelle kann nicht überwacht werden: {room} – {type} {direction}.\nSeit {duration} kam kein Messwert für Gerät '{device}' / Typ '{kind}'. Bitte mit /thresholds prüfen.",
+        "⚠ This threshold cannot be monitored: {room} – {type} {direction}.\nNo reading for device '{device}' / type '{kind}' in {duration}. Please check /thresholds.",
+    ),
+    // Batterie und Erinnerungen (/repeat)
+    ("battery_low", "🔋 Batterie von {room} schwach: {value} %. Bitte bald wechseln.", "🔋 Battery of {room} low: {value} %. Please replace it soon."),
+    ("battery_replaced", "🔋 Batterie von {room} gewechselt, jetzt {value} %.", "🔋 Battery of {room} replaced, now {value} %."),
+    (
+        "reminder_below",
+        "🔁 Weiterhin: {type} {room} seit {duration} unter der Schwelle ({threshold} {unit}), aktuell {value} {unit}.",
+        "🔁 Still: {type} {room} below the threshold ({threshold} {unit}) for {duration}, now {value} {unit}.",
+    ),
+    (
+        "reminder_above",
+        "🔁 Weiterhin: {type} {room} seit {duration} über der Schwelle ({threshold} {unit}), aktuell {value} {unit}.",
+        "🔁 Still: {type} {room} above the threshold ({threshold} {unit}) for {duration}, now {value} {unit}.",
+    ),
+    // Schnelle Änderung (/rate)
+    (
+        "rate_falling",
+        "📉 {type} {room} fällt schnell: {change} {unit} in {window} (jetzt {value} {unit}).",
+        "📉 {type} in {room} falling fast: {change} {unit} in {window} (now {value} {unit}).",
+    ),
+    (
+        "rate_rising",
+        "📈 {type} {room} steigt schnell: {change} {unit} in {window} (jetzt {value} {unit}).",
+        "📈 {type} in {room} rising fast: {change} {unit} in {window} (now {value} {unit}).",
+    ),
+    // Vorschlag nach langer Verletzung
+    (
+        "long_violation_min",
+        "🧐 Deine MIN-Schwelle {type} im {room} ({threshold} {unit}, aktuell {value} {unit}) ist seit {duration} ununterbrochen unterschritten.\nWerte seitdem: min {min}, Median {median}, max {max} {unit}.\nVorschlag: {suggested} {unit}, dann warnt der Bot nur noch bei den niedrigsten 10 % dieser Werte.",
+        "🧐 Your MIN threshold for {type} in {room} ({threshold} {unit}, now {value} {unit}) has been undercut for {duration} without a break.\nValues since then: min {min}, median {median}, max {max} {unit}.\nSuggestion: {suggested} {unit}, then the bot only warns for the lowest 10 % of these values.",
+    ),
+    (
+        "long_violation_max",
+        "🧐 Deine MAX-Schwelle {type} im {room} ({threshold} {unit}, aktuell {value} {unit}) ist seit {duration} ununterbrochen überschritten.\nWerte seitdem: min {min}, Median {median}, max {max} {unit}.\nVorschlag: {suggested} {unit}, dann warnt der Bot nur noch bei den höchsten 10 % dieser Werte.",
+        "🧐 Your MAX threshold for {type} in {room} ({threshold} {unit}, now {value} {unit}) has been exceeded for {duration} without a break.\nValues since then: min {min}, median {median}, max {max} {unit}.\nSuggestion: {suggested} {unit}, then the bot only warns for the highest 10 % of these values.",
+    ),
+    ("review_set", "Auf {value} setzen", "Set to {value}"),
+    ("review_delete", "Schwelle löschen", "Delete threshold"),
+    // Schwelle relativ zu einem anderen Sensor (/set … max-rel)
+    (
+        "relation_above",
+        "↕️ {type} {room}: {value} {unit} über der Grenze {limit} {unit} ({other_room} {other_value} {other_unit} {offset} {unit}).",
+        "↕️ {type} {room}: {value} {unit} above the limit {limit} {unit} ({other_room} {other_value} {other_unit} {offset} {unit}).",
+    ),
+    (
+        "relation_below",
+        "↕️ {type} {room}: {value} {unit} unter der Grenze {limit} {unit} ({other_room} {other_value} {other_unit} {offset} {unit}).",
+        "↕️ {type} {room}: {value} {unit} below the limit {limit} {unit} ({other_room} {other_value} {other_unit} {offset} {unit}).",
+    ),
+    (
+        "relation_back_above",
+        "✅ {type} {room}: {value} {unit} wieder über der Grenze {limit} {unit} ({other_room} {other_value} {other_unit} {offset} {unit}).",
+        "✅ {type} {room}: {value} {unit} back above the limit {limit} {unit} ({other_room} {other_value} {other_unit} {offset} {unit}).",
+    ),
+    (
+        "relation_back_below",
+        "✅ {type} {room}: {value} {unit} wieder unter der Grenze {limit} {unit} ({other_room} {other_value} {other_unit} {offset} {unit}).",
+        "✅ {type} {room}: {value} {unit} back below the limit {limit} {unit} ({other_room} {other_value} {other_unit} {offset} {unit}).",
+    ),
+    // Prüfung neuer Schwellen
+    ("threshold_invalid", "'{value}' ist kein gültiger Schwellwert.", "'{value}' is not a valid threshold."),
+    (
+        "threshold_implausible",
+        "{value} {unit} ist als Schwelle für {type} nicht plausibel. Erlaubt sind {low} bis {high} {unit}.",
+        "{value} {unit} is not a plausible threshold for {type}. Allowed are {low} to {high} {unit}.",
+    ),
+    (
+        "threshold_below_max",
+        "MIN {value} muss unter der MAX-Schwelle {other} {unit} bleiben (siehe /thresholds).",
+        "MIN {value} must stay below the MAX threshold {other} {unit} (see /thresholds).",
+    ),
+    (
+        "threshold_above_min",
+        "MAX {value} muss über der MIN-Schwelle {other} {unit} bleiben (siehe /thresholds).",
+        "MAX {value} must stay above the MIN threshold {other} {unit} (see /thresholds).",
+    ),
+    // Weitergabe (/escalate-to)
+    (
+        "handover_usage",
+        "Verwendung: /escalate-to <chat-id> after <dauer> [warn|critical], z.B. /escalate-to 123456 after 30m. Ohne Stufe nur kritische Warnungen.",
+        "Usage: /escalate-to <chat-id> after <duration> [warn|critical], e.g. /escalate-to 123456 after 30m. Without a level only critical alerts.",
+    ),
+    ("handover_chat_id", "❌ '{value}' ist keine Chat-ID. {usage}", "❌ '{value}' is not a chat ID. {usage}"),
+    ("handover_duration", "❌ Dauer wie 30m oder 2h angeben. {usage}", "❌ Give a duration like 30m or 2h. {usage}"),
+    ("handover_severity", "❌ Unbekannte Stufe '{value}', erlaubt sind warn und critical.", "❌ Unknown level '{value}', allowed are warn and critical."),
+    (
+        "handover_info",
+        "❌ Info-Meldungen werden nicht weitergegeben, erlaubt sind warn und critical.",
+        "❌ Info messages are not handed over, allowed are warn and critical.",
+    ),
+    // Antworten auf Befehle
+    ("refresh_none", "🔄 Abgefragt, keine neuen Messwerte.", "🔄 Fetched, no new readings."),
+    ("refresh_one", "🔄 Abgefragt: 1 neuer Messwert, ausgewertet.", "🔄 Fetched: 1 new reading, evaluated."),
+    ("refresh_many", "🔄 Abgefragt: {count} neue Messwerte, ausgewertet.", "🔄 Fetched: {count} new readings, evaluated."),
+    ("refresh_failed", "❌ Abfrage fehlgeschlagen: {error}", "❌ Fetch failed: {error}"),
+    ("hours_range", "❌ Stunden bitte als ganze Zahl von 1 bis {max} angeben.", "❌ Please give the hours as a whole number from 1 to {max}."),
+    ("days_range", "❌ Tage bitte als ganze Zahl von 1 bis {max} angeben.", "❌ Please give the days as a whole number from 1 to {max}."),
+    (
+        "history_usage",
+        "Verwendung: /history <gerät> <typ> [stunden], z.B. /history Wohnzimmer temperature 12",
+        "Usage: /history <device> <type> [hours], e.g. /history Wohnzimmer temperature 12",
+    ),
+    ("alarms_usage", "Verwendung: /alarms [anzahl], 1 bis 50", "Usage: /alarms [count], 1 to 50"),
+    ("alarms_none", "Noch keine Alarme aufgezeichnet.", "No alerts recorded yet."),
+    ("alarms_title", "🗂 Letzte Alarme:", "🗂 Latest alerts:"),
+    (
+        "stats_usage",
+        "Verwendung: /stats <gerät> <typ>, z.B. /stats Wohnzimmer temperature",
+        "Usage: /stats <device> <type>, e.g. /stats Wohnzimmer temperature",
+    ),
+    (
+        "export_usage",
+        "Verwendung: /export <gerät> <typ> [tage], z.B. /export Wohnzimmer temperature 30",
+        "Usage: /export <device> <type> [days], e.g. /export Wohnzimmer temperature 30",
+    ),
+    ("export_empty", "Keine Messwerte für {type} {room} in den letzten {days} Tagen.", "No readings for {type} {room} in the last {days} days."),
+    (
+        "export_too_large",
+        "❌ Die Datei wäre {size} MB groß (höchstens {max} MB). Bitte einen kürzeren Zeitraum wählen.",
+        "❌ The file would be {size} MB (at most {max} MB). Please choose a shorter period.",
+    ),
+    (
+        "export_caption",
+        "📄 {type} {room}, letzte {days} Tage ({count} Werte, Zeitzone {zone})",
+        "📄 {type} {room}, last {days} days ({count} values, time zone {zone})",
+    ),
+    (
+        "chart_period",
+        "❌ Ungültiger Zeitraum '{value}', z.B. 12h oder 3d (höchstens {max} Tage).",
+        "❌ Invalid period '{value}', e.g. 12h or 3d (at most {max} days).",
+    ),
+    ("copy_same_room", "❌ Quelle und Ziel sind derselbe Raum.", "❌ Source and target are the same room."),
+    ("copy_done", "📋 Schwellen von {room} übernommen.", "📋 Thresholds copied from {room}."),
+    (
+        "copy_usage",
+        "Verwendung: /copy-thresholds <von> <nach>, z.B. /copy-thresholds wohnzimmer schlafzimmer",
+        "Usage: /copy-thresholds <from> <to>, e.g. /copy-thresholds wohnzimmer schlafzimmer",
+    ),
+    ("default_applied", "📏 Vorlage angewendet.", "📏 Template applied."),
+    ("apply_default_usage", "Verwendung: /apply-default <raum>", "Usage: /apply-default <room>"),
+    ("thresholds_none", "Du hast noch keine Schwellwerte gesetzt.", "You have not set any thresholds yet."),
+    ("schedule_usage", "Verwendung: /schedule mo-fr 06:30; sa,so 09:00 (oder /schedule off)", "Usage: /schedule mo-fr 06:30; sa,so 09:00 (or /schedule off)"),
+    ("schedule_off", "📅 Geplanter Statusbericht deaktiviert.", "📅 Scheduled status report turned off."),
+    ("schedule_set", "📅 Statusbericht geplant: {schedule}\nNächster Bericht: {next}", "📅 Status report scheduled: {schedule}\nNext report: {next}"),
+    ("quiet_off", "🔔 Ruhezeit deaktiviert. Gesammelte Warnungen folgen in Kürze.", "🔔 Quiet hours turned off. Collected alerts follow shortly."),
+    (
+        "quiet_set",
+        "🔕 Ruhezeit {window} Uhr. Warnungen werden gesammelt und danach zusammen gesendet.",
+        "🔕 Quiet hours {window}. Alerts are collected and sent together afterwards.",
+    ),
+    ("quiet_usage", "Verwendung: /quiet-hours 22:00 07:00 (oder /quiet-hours off)", "Usage: /quiet-hours 22:00 07:00 (or /quiet-hours off)"),
+    ("alert_mode_instant", "🔔 Warnungen kommen wieder sofort.", "🔔 Alerts are sent immediately again."),
+    (
+        "alert_mode_digest",
+        "🗂 Warnungen kommen jetzt gesammelt alle {interval}, nur wenn es etwas zu melden gibt. Kritische kommen weiter sofort.",
+        "🗂 Alerts now come collected every {interval}, only when there is something to report. Critical ones still come immediately.",
+    ),
+    (
+        "alert_mode_usage",
+        "Verwendung: /alert-mode instant oder /alert-mode digest 1h (mindestens {min} min). Aktuell: {current}",
+        "Usage: /alert-mode instant or /alert-mode digest 1h (at least {min} min). Current: {current}",
+    ),
+    ("notifications_set", "🔔 Benachrichtigungen: {mode}.", "🔔 Notifications: {mode}."),
+    ("notifications_current", "Aktuell: {mode}.", "Current: {mode}."),
+    ("mute_all_usage", "Verwendung: /mute-all 4h (auch 30m oder 2d)", "Usage: /mute-all 4h (also 30m or 2d)"),
+    (
+        "mute_usage",
+        "Verwendung: /mute <dauer> oder /mute <raum> <dauer>, z.B. /mute Wohnzimmer 2h",
+        "Usage: /mute <duration> or /mute <room> <duration>, e.g. /mute Wohnzimmer 2h",
+    ),
+    ("unmute_rooms", "{count} Raum-Stummschaltungen aufgehoben.", "{count} room mutes lifted."),
+    ("unmute_snoozes", "🔔 {count} Stummschaltungen aufgehoben.", "🔔 {count} mutes lifted."),
+    ("unmute_none", "Es ist keine Stummschaltung aktiv.", "Nothing is muted."),
+    (
+        "diff_first",
+        "🔍 Keine Vergleichsdaten. Ab jetzt zeigt /diff, was sich seit diesem Aufruf geändert hat.",
+        "🔍 Nothing to compare yet. From now on /diff shows what changed since this call.",
+    ),
+    ("undo_done", "↩️ Zurückgenommen: {direction}-Schwelle {type} {room}", "↩️ Undone: {direction} threshold {type} {room}"),
+    ("undo_none", "Es gibt keine Änderung, die sich zurücknehmen lässt.", "There is no change to undo."),
+    ("http_not_built", "Der HTTP-Server ist in diesem Build deaktiviert.", "The HTTP server is not part of this build."),
+    ("http_disabled", "Der HTTP-Server ist nicht aktiviert (HTTP_ADDR).", "The HTTP server is not enabled (HTTP_ADDR)."),
+    (
+        "api_token",
+        "🔑 Neues Token erzeugt, alte Links gelten nicht mehr.\nKalender: {base}/api/calendar.ics?token={token}",
+        "🔑 New token created, old links no longer work.\nCalendar: {base}/api/calendar.ics?token={token}",
+    ),
+    ("notes_title", "📝 Deine Notizen:", "📝 Your notes:"),
+    (
+        "note_usage",
+        "Verwendung: /note wohnzimmer \"Sensor hängt hinter dem Vorhang\" (oder /note wohnzimmer clear)",
+        "Usage: /note wohnzimmer \"sensor hangs behind the curtain\" (or /note wohnzimmer clear)",
+    ),
+    ("note_removed", "🗑 Notiz für {room} entfernt.", "🗑 Note for {room} removed."),
+    ("note_missing", "Für {room} gibt es keine Notiz.", "There is no note for {room}."),
+    ("note_empty", "Die Notiz ist leer.", "The note is empty."),
+    ("note_too_long", "❌ Notiz zu lang (höchstens {max} Zeichen).", "❌ Note too long (at most {max} characters)."),
+    ("note_saved", "📝 Notiz für {room} gespeichert.", "📝 Note for {room} saved."),
+    ("backup_disabled", "Sicherungen sind nicht aktiviert (BACKUP_DIR).", "Backups are not enabled (BACKUP_DIR)."),
+    ("backup_done", "💾 Sicherung angelegt: {path}", "💾 Backup created: {path}"),
+    ("backup_failed", "❌ Sicherung fehlgeschlagen: {error}", "❌ Backup failed: {error}"),
+    ("debug_title", "🛠 Zustand", "🛠 State"),
+    ("unknown_tenant", "Unbekannter Haushalt '{tenant}'. Vorhanden: {tenants}", "Unknown household '{tenant}'. Available: {tenants}"),
+    ("image_removed", "🖼 Raumbild von {room} entfernt.", "🖼 Room picture of {room} removed."),
+    ("image_missing", "{room} hat kein Raumbild.", "{room} has no room picture."),
+    ("image_send", "🖼 Sende jetzt ein Foto für {room}.", "🖼 Now send a photo for {room}."),
+    ("injected", "🧪 Testwert eingespeist: {room} {type} {value} von {time}", "🧪 Test value injected: {room} {type} {value} from {time}"),
+    (
+        "injected_unsaved",
+        "🧪 Testwert eingespeist: {room} {type} {value} von {time} (nicht gespeichert)",
+        "🧪 Test value injected: {room} {type} {value} from {time} (not stored)",
+    ),
+    (
+        "test_alarm_usage",
+        "Verwendung: /test-alarm [gerät] [typ], z.B. /test-alarm Keller temperature",
+        "Usage: /test-alarm [device] [type], e.g. /test-alarm Keller temperature",
+    ),
+    (
+        "test_alarm_none",
+        "❌ Keine passende Schwelle gesetzt (oder gerade kein Zeitfenster aktiv). Mit /setmin oder /setmax festlegen, /thresholds zeigt deine Schwellen.",
+        "❌ No matching threshold set (or no time window active right now). Set one with /setmin or /setmax, /thresholds shows your thresholds.",
+    ),
+    (
+        "test_alarm_silent",
+        "❌ Der Testwert hat keinen Alarm ausgelöst; bitte die Schwelle mit /thresholds prüfen.",
+        "❌ The test value did not trigger an alert; please check the threshold with /thresholds.",
+    ),
+    (
+        "clear_usage",
+        "Verwendung: /clear all – löscht alle deine Einstellungen ({days} Tage lang mit /undo-clear umkehrbar).",
+        "Usage: /clear all – deletes all your settings (reversible with /undo-clear for {days} days).",
+    ),
+    (
+        "clear_preview",
+        "🗑 Das löscht alle deine Einstellungen:\n{summary}\nDanach lassen sie sich {days} Tage lang mit /undo-clear wiederherstellen.",
+        "🗑 This deletes all your settings:\n{summary}\nAfterwards they can be restored with /undo-clear for {days} days.",
+    ),
+    ("clear_nothing", "Es gibt keine Einstellungen zum Löschen.", "There are no settings to delete."),
+    (
+        "undo_clear_done",
+        "♻️ Deine Einstellungen sind wiederhergestellt. Seitdem neu Eingerichtetes bleibt erhalten.",
+        "♻️ Your settings are restored. Anything set up since then is kept.",
+    ),
+    (
+        "undo_clear_expired",
+        "Die Frist ist abgelaufen, die gelöschten Einstellungen sind endgültig entfernt.",
+        "The deadline has passed, the deleted settings are gone for good.",
+    ),
+    ("undo_clear_missing", "Es gibt keine gelöschten Einstellungen zum Wiederherstellen.", "There are no deleted settings to restore."),
+    (
+        "backup_caption",
+        "💾 Deine Einstellungen:\n{summary}\n\nZum Wiederherstellen die Datei mit der Bildunterschrift /restore zurückschicken.",
+        "💾 Your settings:\n{summary}\n\nTo restore them, send the file back with the caption /restore.",
+    ),
+    ("backup_not_created", "❌ Die Sicherung konnte nicht erstellt werden.", "❌ The backup could not be created."),
+    (
+        "restore_usage",
+        "Schick die Datei von /backup mit der Bildunterschrift /restore oder antworte mit /restore auf sie.",
+        "Send the file from /backup with the caption /restore or reply to it with /restore.",
+    ),
+    ("forget_preview", "⚠️ Das löscht endgültig alle deine Daten, ohne /undo-clear:", "⚠️ This deletes all your data for good, without /undo-clear:"),
+    ("forget_archive", "• gelöschte Einstellungen im Archiv", "• deleted settings in the archive"),
+    (
+        "room_images_on",
+        "🖼 Raumbilder an: kritische Warnungen und /status <raum> kommen mit Bild, sofern eines hinterlegt ist.",
+        "🖼 Room pictures on: critical alerts and /status <room> come with a picture if one is stored.",
+    ),
+    ("room_images_off", "🖼 Raumbilder aus.", "🖼 Room pictures off."),
+    ("room_images_usage", "Verwendung: /room-images on oder off", "Usage: /room-images on or off"),
+    // Profile (/profile)
+    (
+        "profile_usage",
+        "Verwendung:\n/profile – Profile anzeigen\n/profile create <name> bzw. delete <name>\n/profile set <name> <gerät> <typ> min|max <wert>|off\n/profile activate <name>|auto\n/profile schedule <name> 22:00-06:00|off",
+        "Usage:\n/profile – show profiles\n/profile create <name> or delete <name>\n/profile set <name> <device> <type> min|max <value>|off\n/profile activate <name>|auto\n/profile schedule <name> 22:00-06:00|off",
+    ),
+    (
+        "profile_name_invalid",
+        "❌ Ungültiger Profilname '{name}': Buchstaben, Ziffern, - und _, höchstens {max} Zeichen.",
+        "❌ Invalid profile name '{name}': letters, digits, - and _, at most {max} characters.",
+    ),
+    ("profile_exists", "Profil {name} gibt es schon.", "Profile {name} already exists."),
+    (
+        "profile_limit",
+        "❌ Höchstens {max} Profile, bitte erst eines mit /profile delete entfernen.",
+        "❌ At most {max} profiles, please remove one with /profile delete first.",
+    ),
+    (
+        "profile_created",
+        "🗂 Profil {name} angelegt. Schwellen mit /profile set {name} <gerät> <typ> min|max <wert> festlegen.",
+        "🗂 Profile {name} created. Set thresholds with /profile set {name} <device> <type> min|max <value>.",
+    ),
+    ("profile_deleted", "🗂 Profil {name} gelöscht.", "🗂 Profile {name} deleted."),
+    ("profile_missing", "Profil {name} gibt es nicht.", "Profile {name} does not exist."),
+    (
+        "profile_create_first",
+        "Profil {name} gibt es nicht, erst /profile create {name} senden.",
+        "Profile {name} does not exist, send /profile create {name} first.",
+    ),
+    ("profile_value_off", "🗂 {threshold}: im Profil {name} aus.", "🗂 {threshold}: off in profile {name}."),
+    ("profile_value_invalid", "❌ Wert als Zahl oder off angeben.", "❌ Give the value as a number or off."),
+    ("profile_value_implausible", "❌ {value} ist für {type} nicht plausibel.", "❌ {value} is not plausible for {type}."),
+    ("profile_value_set", "🗂 {threshold}: im Profil {name} {value} {unit}.", "🗂 {threshold}: {value} {unit} in profile {name}."),
+    ("profile_auto", "🗂 Profile wieder nach Zeitplan.", "🗂 Profiles follow their schedule again."),
+    ("profile_activated", "🗂 Profil {name} aktiv, bis /profile activate auto.", "🗂 Profile {name} active until /profile activate auto."),
+    ("profile_overlap", "❌ Das Zeitfenster überschneidet sich mit Profil {name}.", "❌ The time window overlaps with profile {name}."),
+    ("profile_unscheduled", "🗂 Profil {name} ohne Zeitplan.", "🗂 Profile {name} without a schedule."),
+    ("profile_scheduled", "🗂 Profil {name} gilt täglich {window} Uhr.", "🗂 Profile {name} applies daily {window}."),
+    ("profiles_none", "Keine Profile angelegt.", "No profiles created."),
+    ("profiles_title", "🗂 Profile:", "🗂 Profiles:"),
+    ("profile_line", "{name} ({count} Schwellen)", "{name} ({count} thresholds)"),
+    ("profile_line_window", "{name} ({count} Schwellen, {window} Uhr)", "{name} ({count} thresholds, {window})"),
+    ("profile_active_mark", " ◀ aktiv", " ◀ active"),
+    (
+        "profiles_manual",
+        "Manuell gewählt; /profile activate auto schaltet wieder nach Zeitplan.",
+        "Chosen manually; /profile activate auto switches back to the schedule.",
+    ),
+    // Weitergabe (/escalate-to, /accept-escalations-from)
+    ("handover_self", "❌ Weitergabe an den eigenen Chat ist nicht möglich.", "❌ Handing over to your own chat is not possible."),
+    (
+        "handover_not_accepted",
+        "❌ Chat {chat} nimmt keine Warnungen von dir an. Dort zuerst /accept-escalations-from {own} senden.",
+        "❌ Chat {chat} does not accept alerts from you. Send /accept-escalations-from {own} there first.",
+    ),
+    ("handover_active", "🆘 {handover}. /escalation off beendet das.", "🆘 {handover}. /escalation off ends this."),
+    (
+        "handover_critical",
+        "Kritische Warnungen gehen nach {duration} ohne Bestätigung an Chat {chat}",
+        "Critical alerts go to chat {chat} after {duration} without acknowledgement",
+    ),
+    ("handover_all", "Warnungen gehen nach {duration} ohne Bestätigung an Chat {chat}", "Alerts go to chat {chat} after {duration} without acknowledgement"),
+    ("handover_ended", "🆘 Weitergabe beendet.", "🆘 Handover ended."),
+    (
+        "handover_none",
+        "Keine Weitergabe eingerichtet. Mit /escalate-to <chat-id> after <dauer> einrichten.",
+        "No handover set up. Set one up with /escalate-to <chat-id> after <duration>.",
+    ),
+    ("escalation_usage", "Verwendung: /escalation oder /escalation off", "Usage: /escalation or /escalation off"),
+    (
+        "accept_usage",
+        "Verwendung: /accept-escalations-from <chat-id>, /accept-escalations-from <chat-id> off beendet das.",
+        "Usage: /accept-escalations-from <chat-id>, /accept-escalations-from <chat-id> off ends this.",
+    ),
+    (
+        "handover_revoked",
+        "🆘 Chat {chat} nimmt keine Warnungen mehr an, die Weitergabe ist beendet.",
+        "🆘 Chat {chat} no longer accepts alerts, the handover has ended.",
+    ),
+    ("accept_off", "🆘 Warnungen von Chat {chat} kommen nicht mehr hierher.", "🆘 Alerts from chat {chat} no longer come here."),
+    ("accept_missing", "Chat {chat} war nicht freigegeben.", "Chat {chat} was not accepted."),
+    (
+        "accept_on",
+        "🆘 Chat {chat} darf unbestätigte Warnungen hierher weitergeben. Dort jetzt z.B. /escalate-to {own} after 30m einrichten.",
+        "🆘 Chat {chat} may hand over unacknowledged alerts to here. Now set it up there, e.g. /escalate-to {own} after 30m.",
+    ),
+    // Admin-Befehle
+    ("fleet_report_on", "🛰 Geräte-Wochenbericht an: sonntags um 18:00.", "🛰 Weekly fleet report on: Sundays at 18:00."),
+    ("fleet_report_off", "🛰 Geräte-Wochenbericht aus.", "🛰 Weekly fleet report off."),
+    ("fleet_report_now", "🛰 Geräte-Wochenbericht kommt gleich.", "🛰 The fleet report is on its way."),
+    ("fleet_report_usage", "Verwendung: /fleet-report on, off oder now", "Usage: /fleet-report on, off or now"),
+    (
+        "interval_usage",
+        "Verwendung: /set-interval <sekunden> oder z.B. 15m. Aktuell: {current}",
+        "Usage: /set-interval <seconds> or e.g. 15m. Current: {current}",
+    ),
+    (
+        "interval_too_short",
+        "❌ Mindestens {min} Sekunden, sonst wird der Sensor-Webserver zu oft abgefragt.",
+        "❌ At least {min} seconds, otherwise the sensor web server is polled too often.",
+    ),
+    ("interval_too_long", "❌ Höchstens 1 Tag.", "❌ At most 1 day."),
+    (
+        "interval_set",
+        "⏱ Abfrageintervall: {interval} (vorher {previous}). Gilt bis zum Neustart, dauerhaft über POLL_INTERVAL_SECONDS.",
+        "⏱ Poll interval: {interval} (before {previous}). Applies until restart, permanently via POLL_INTERVAL_SECONDS.",
+    ),
+    ("off", "aus", "off"),
+    (
+        "weather_usage",
+        "Verwendung: /weather-location <breite>,<länge> oder off. Aktuell: {current}",
+        "Usage: /weather-location <latitude>,<longitude> or off. Current: {current}",
+    ),
+    (
+        "weather_off",
+        "🌤 Kein Wettervergleich mehr in /status. Gilt bis zum Neustart, dauerhaft über WEATHER_LOCATION.",
+        "🌤 No more weather comparison in /status. Applies until restart, permanently via WEATHER_LOCATION.",
+    ),
+    ("weather_now", "Draußen gerade {temperature} °C, {humidity} %.", "Outside right now {temperature} °C, {humidity} %."),
+    (
+        "weather_unavailable",
+        "Open-Meteo antwortet gerade nicht, /status zeigt das Wetter, sobald es wieder geht.",
+        "Open-Meteo is not answering right now, /status shows the weather as soon as it works again.",
+    ),
+    (
+        "weather_set",
+        "🌤 Standort {location}. {check} Gilt bis zum Neustart, dauerhaft über WEATHER_LOCATION.",
+        "🌤 Location {location}. {check} Applies until restart, permanently via WEATHER_LOCATION.",
+    ),
+    ("purge_usage", "Verwendung: /purge-user <chat_id>", "Usage: /purge-user <chat_id>"),
+    ("purge_self", "❌ Den eigenen Chat löscht /forgetme.", "❌ Your own chat is deleted with /forgetme."),
+    ("purge_done", "🗑 Chat {chat} gelöscht ({count} Schwellen).", "🗑 Chat {chat} deleted ({count} thresholds)."),
+    ("purge_missing", "Von Chat {chat} ist keine Konfiguration gespeichert.", "No configuration is stored for chat {chat}."),
+    ("ignore_usage", "Verwendung: /ignore <gerät>", "Usage: /ignore <device>"),
+    ("ignore_already", "{room} wird schon ignoriert.", "{room} is already ignored."),
+    (
+        "ignore_preview",
+        "🚫 {room} wird ignoriert: keine Messwerte, Warnungen oder Statuszeilen mehr.\n{count} Schwellen in {chats} anderen Chats lösen dann nichts mehr aus; diese Chats werden informiert.",
+        "🚫 {room} will be ignored: no more readings, alerts or status lines.\n{count} thresholds in {chats} other chats will no longer fire; these chats are informed.",
+    ),
+    ("unignore_done", "✅ {room} wird wieder überwacht.", "✅ {room} is monitored again."),
+    ("unignore_missing", "{room} wird nicht ignoriert. Siehe /ignored.", "{room} is not ignored. See /ignored."),
+    ("ignored_none", "Es werden keine Geräte ignoriert.", "No devices are ignored."),
+    ("ignored_title", "🚫 Ignorierte Geräte:", "🚫 Ignored devices:"),
+    ("since_by", "{what} – seit {since}, von {by}", "{what} – since {since}, by {by}"),
+    ("access_title", "🔐 Zugriff:", "🔐 Access:"),
+    ("access_open", "Keine Freigaben, der Bot ist für alle offen.", "No grants, the bot is open to everyone."),
+    ("allow_usage", "Verwendung: /allow <chat_id> bzw. /deny <chat_id>", "Usage: /allow <chat_id> or /deny <chat_id>"),
+    ("allow_admin", "Der Admin-Chat ist immer freigegeben.", "The admin chat is always allowed."),
+    ("allow_done", "✅ Chat {chat} ist freigeschaltet.", "✅ Chat {chat} is allowed."),
+    ("allow_already", "Chat {chat} ist schon freigeschaltet.", "Chat {chat} is already allowed."),
+    (
+        "deny_done",
+        "⛔ Chat {chat} ist gesperrt und bekommt keine Nachrichten mehr. Seine Einstellungen bleiben erhalten.",
+        "⛔ Chat {chat} is blocked and gets no more messages. Its settings are kept.",
+    ),
+    ("deny_already", "Chat {chat} ist schon gesperrt.", "Chat {chat} is already blocked."),
+    ("broadcast_usage", "Verwendung: /broadcast <text>", "Usage: /broadcast <text>"),
+    ("broadcast_started", "📣 Sende an {count} Chats, der Bericht folgt.", "📣 Sending to {count} chats, the report follows."),
+    // Stummschalten (/snooze)
+    ("snooze_none", "Es war keine Schwelle stummgeschaltet.", "No threshold was muted."),
+    (
+        "snooze_usage",
+        "Verwendung: /snooze <raum> <dauer> (z.B. 2h, 30m, 1d) oder /snooze clear",
+        "Usage: /snooze <room> <duration> (e.g. 2h, 30m, 1d) or /snooze clear",
+    ),
+    // Schwellen entfernen und feinjustieren
+    (
+        "threshold_not_set",
+        "Für {type} {room} ist kein {direction}-Schwellwert gesetzt. Deine Schwellen: /thresholds",
+        "No {direction} threshold is set for {type} {room}. Your thresholds: /thresholds",
+    ),
+    (
+        "threshold_removed",
+        "🗑 {direction}-Schwellwert {type} {room} entfernt. /undo stellt ihn wieder her.",
+        "🗑 {direction} threshold {type} {room} removed. /undo restores it.",
+    ),
+    (
+        "clear_threshold_usage",
+        "Verwendung: /clear-threshold <gerät> <typ> <min|max>, z.B. /clear-threshold Wohnzimmer temperature max",
+        "Usage: /clear-threshold <device> <type> <min|max>, e.g. /clear-threshold Wohnzimmer temperature max",
+    ),
+    ("clear_all_nothing", "Du hast keine Schwellwerte gesetzt, es gibt nichts zu entfernen.", "You have not set any thresholds, there is nothing to remove."),
+    (
+        "clear_all_one",
+        "🗑 {count} Schwellwert entfernt. /undo stellt einzeln wieder her, bis zu {max}.",
+        "🗑 {count} threshold removed. /undo restores one at a time, up to {max}.",
+    ),
+    (
+        "clear_all_many",
+        "🗑 {count} Schwellwerte entfernt. /undo stellt einzeln wieder her, bis zu {max}.",
+        "🗑 {count} thresholds removed. /undo restores one at a time, up to {max}.",
+    ),
+    ("configure_no_devices", "Keine Geräte mit aktuellen Messwerten gefunden.", "No devices with current readings found."),
+    ("configure_device", "⚙️ Schwelle einrichten – welches Gerät?", "⚙️ Set up a threshold – which device?"),
+    (
+        "hysteresis_default",
+        "↔️ Hysterese {type} {room}: wieder Standard ({value} {unit}).",
+        "↔️ Hysteresis {type} {room}: back to the default ({value} {unit}).",
+    ),
+    (
+        "hysteresis_set",
+        "↔️ Hysterese {type} {room}: {value} {unit}. Ein Alarm endet erst, wenn der Wert so weit zurück im Bereich ist.",
+        "↔️ Hysteresis {type} {room}: {value} {unit}. An alert only ends once the value is back in range by this much.",
+    ),
+    ("hysteresis_invalid", "❌ Die Hysterese muss eine Zahl ab 0 sein.", "❌ The hysteresis must be a number from 0."),
+    (
+        "hysteresis_usage",
+        "Verwendung: /hysteresis <gerät> <typ> <min|max> <wert>, z.B. /hysteresis Wohnzimmer temperature max 0.5, oder default",
+        "Usage: /hysteresis <device> <type> <min|max> <value>, e.g. /hysteresis Wohnzimmer temperature max 0.5, or default",
+    ),
+    ("repeat_off", "🔁 Erinnerung {type} {room} aus, es bleibt bei einer Warnung je Alarm.", "🔁 Reminder {type} {room} off, one alert per alarm again."),
+    (
+        "repeat_set",
+        "🔁 {type} {room}: solange der Alarm besteht, alle {interval} erneut warnen.",
+        "🔁 {type} {room}: alert again every {interval} while the alarm lasts.",
+    ),
+    ("repeat_interval", "❌ Abstand von {min} bis 1 Tag angeben, z.B. 60m oder 2h.", "❌ Give an interval from {min} to 1 day, e.g. 60m or 2h."),
+    (
+        "repeat_usage",
+        "Verwendung: /repeat <gerät> <typ> <min|max> <dauer>, z.B. /repeat Gewächshaus temperature min 60m, oder off",
+        "Usage: /repeat <device> <type> <min|max> <duration>, e.g. /repeat Gewächshaus temperature min 60m, or off",
+    ),
+    (
+        "rate_usage",
+        "Verwendung: /rate <gerät> <typ> <änderung> <dauer>, z.B. /rate Wohnzimmer temperature -3 15m, oder /rate <gerät> <typ> off",
+        "Usage: /rate <device> <type> <change> <duration>, e.g. /rate Wohnzimmer temperature -3 15m, or /rate <device> <type> off",
+    ),
+    ("rate_off", "📉 Änderungsalarm {type} {room} aus.", "📉 Change alert {type} {room} off."),
+    ("rate_missing", "Für {type} {room} ist kein Änderungsalarm gesetzt.", "No change alert is set for {type} {room}."),
+    (
+        "rate_delta_invalid",
+        "❌ Änderung als Zahl ungleich 0 angeben, negativ für einen Abfall, z.B. -3.",
+        "❌ Give the change as a number other than 0, negative for a drop, e.g. -3.",
+    ),
+    ("rate_window_range", "❌ Zeitraum von 1m bis 24h angeben.", "❌ Give a period from 1m to 24h."),
+    ("rate_window_invalid", "❌ Zeitraum z.B. als 15m oder 1h angeben.", "❌ Give the period e.g. as 15m or 1h."),
+    (
+        "rate_set_falling",
+        "📉 {type} {room}: Alarm, wenn der Wert innerhalb von {window} um {change} {unit} fällt.",
+        "📉 {type} {room}: alert when the value falls by {change} {unit} within {window}.",
+    ),
+    (
+        "rate_set_rising",
+        "📈 {type} {room}: Alarm, wenn der Wert innerhalb von {window} um {change} {unit} steigt.",
+        "📈 {type} {room}: alert when the value rises by {change} {unit} within {window}.",
+    ),
+    (
+        "set_relative_usage",
+        "Verwendung: /set <gerät> <typ> max-rel|min-rel <gerät> <typ> <abstand>, z.B. /set Keller humidity max-rel Aussen humidity +10, oder /set <gerät> <typ> max-rel|min-rel off",
+        "Usage: /set <device> <type> max-rel|min-rel <device> <type> <offset>, e.g. /set Keller humidity max-rel Aussen humidity +10, or /set <device> <type> max-rel|min-rel off",
+    ),
+    ("relation_off", "↕️ Vergleichsschwelle {type} {room} {direction} aus.", "↕️ Relative threshold {type} {room} {direction} off."),
+    ("relation_missing", "Für {type} {room} ist keine Vergleichsschwelle {direction} gesetzt.", "No relative threshold {direction} is set for {type} {room}."),
+    ("relation_offset_invalid", "❌ Abstand als Zahl angeben, z.B. +10 oder -3.", "❌ Give the offset as a number, e.g. +10 or -3."),
+    ("relation_self", "❌ Ein Sensor lässt sich nicht mit sich selbst vergleichen.", "❌ A sensor cannot be compared with itself."),
+    (
+        "relation_set_above",
+        "↕️ {type} {room}: Alarm, wenn der Wert über {other_type} {other_room} {offset} {unit} liegt.",
+        "↕️ {type} {room}: alert when the value is above {other_type} {other_room} {offset} {unit}.",
+    ),
+    (
+        "relation_set_below",
+        "↕️ {type} {room}: Alarm, wenn der Wert unter {other_type} {other_room} {offset} {unit} liegt.",
+        "↕️ {type} {room}: alert when the value is below {other_type} {other_room} {offset} {unit}.",
+    ),
+    // Gießerinnerung, Batterie, Live-Übersicht und Anzeige
+    ("watering_off", "🌱 Gießerinnerung aus.", "🌱 Watering reminder off."),
+    (
+        "watering_on",
+        "🌱 Gießerinnerung an: abends um {time}, wenn es {days} Tage in Folge über {above} °C warm war.",
+        "🌱 Watering reminder on: in the evening at {time} after {days} days in a row above {above} °C.",
+    ),
+    ("watering_no_outdoor", "Hinweis: Noch kein Raum ist als outdoor eingetragen.", "Note: no room is marked as outdoor yet."),
+    (
+        "watering_usage",
+        "Verwendung: /watering <°C> [tage 1–{max}], z.B. /watering 28 3, oder /watering off",
+        "Usage: /watering <°C> [days 1–{max}], e.g. /watering 28 3, or /watering off",
+    ),
+    ("snoozes_none", "Keine Schwelle ist stummgeschaltet.", "No threshold is muted."),
+    ("health_panics", "Abgefangene Abstürze in Befehlen und Buttons: {count}", "Caught crashes in commands and buttons: {count}"),
+    ("health_skipped", "Unlesbare Einträge beim letzten Abruf ({tenant}): {count}", "Unreadable entries in the last fetch ({tenant}): {count}"),
+    ("main_tenant", "Haupthaushalt", "main household"),
+    (
+        "units_set",
+        "🌡 Temperaturen ab jetzt in {unit}. Schwellen bleiben unverändert, sie werden nur umgerechnet angezeigt.",
+        "🌡 Temperatures from now on in {unit}. Thresholds stay the same, they are only shown converted.",
+    ),
+    ("units_usage", "Verwendung: /units celsius oder /units fahrenheit. Aktuell: {unit}", "Usage: /units celsius or /units fahrenheit. Current: {unit}"),
+    ("battery_threshold", "🔋 Warnung unter {value} %.", "🔋 Alert below {value} %."),
+    ("battery_disabled", "🔋 Keine Warnungen bei schwacher Batterie.", "🔋 No alerts for low batteries."),
+    ("battery_no_devices", "Kein Gerät meldet derzeit einen Ladestand.", "No device reports a battery level right now."),
+    ("battery_off", "🔋 Keine Warnungen mehr bei schwacher Batterie.", "🔋 No more alerts for low batteries."),
+    ("battery_default", "🔋 Warnung wieder unter {value} % (Standard).", "🔋 Alert below {value} % again (default)."),
+    ("battery_set", "🔋 Warnung ab jetzt unter {value} %.", "🔋 Alert from now on below {value} %."),
+    (
+        "battery_usage",
+        "Verwendung: /battery <prozent>, z.B. /battery 15, oder /battery off bzw. /battery default",
+        "Usage: /battery <percent>, e.g. /battery 15, or /battery off or /battery default",
+    ),
+    ("live_running", "📌 Die Live-Übersicht läuft schon, /live off beendet sie.", "📌 The live overview is already running, /live off ends it."),
+    (
+        "live_not_pinned",
+        "📌 Anheften nicht möglich, dafür braucht der Bot in Gruppen das Recht dazu. Bearbeitet wird die Übersicht trotzdem.",
+        "📌 Pinning is not possible, in groups the bot needs the right to do so. The overview is still updated.",
+    ),
+    (
+        "live_off",
+        "📌 Live-Übersicht beendet, die Nachricht bleibt mit dem letzten Stand stehen.",
+        "📌 Live overview ended, the message stays with the last values.",
+    ),
+    ("live_none", "Es läuft keine Live-Übersicht.", "No live overview is running."),
+    ("live_usage", "Verwendung: /live on oder /live off", "Usage: /live on or /live off"),
+    (
+        "timezone_current",
+        "🕰 Zeitzone: {zone}. Ändern z.B. mit /timezone Europe/Berlin, zurück mit /timezone default.",
+        "🕰 Time zone: {zone}. Change it e.g. with /timezone Europe/Berlin, back with /timezone default.",
+    ),
+    ("timezone_default", "🕰 Zeitzone wieder Standard: {zone}.", "🕰 Time zone back to the default: {zone}."),
+    ("timezone_set", "🕰 Zeitzone {zone}, dort ist es jetzt {time} Uhr.", "🕰 Time zone {zone}, it is {time} there now."),
+    (
+        "timezone_unknown",
+        "❌ Unbekannte Zeitzone '{zone}'. Beispiele: Europe/Berlin, Europe/Vienna, Europe/Zurich, Europe/London, America/New_York, UTC",
+        "❌ Unknown time zone '{zone}'. Examples: Europe/Berlin, Europe/Vienna, Europe/Zurich, Europe/London, America/New_York, UTC",
+    ),
+    ("timeformat_set", "🕒 Zeitangaben jetzt z.B. so: {example}", "🕒 Times now look like this: {example}"),
+    ("layout_table", "🖥 /status-Darstellung: Tabelle", "🖥 /status layout: table"),
+    ("layout_classic", "🖥 /status-Darstellung: klassisch", "🖥 /status layout: classic"),
+    // Berichte
+    ("schedules_none", "Du hast keine geplanten Berichte. Nutze /schedule.", "You have no scheduled reports. Use /schedule."),
+    (
+        "daily_on",
+        "🌙 Tageszusammenfassung {schedule} Uhr: Min, Max, Mittel und Warnungen je Raum.",
+        "🌙 Daily summary {schedule}: min, max, mean and alerts per room.",
+    ),
+    (
+        "daily_usage",
+        "Verwendung: /subscribe-daily <HH:MM> oder ein Wochenplan, z.B. /subscribe-daily mo-fr 06:30; sa,so 09:00",
+        "Usage: /subscribe-daily <HH:MM> or a weekly plan, e.g. /subscribe-daily mo-fr 06:30; sa,so 09:00",
+    ),
+    ("daily_off", "🌙 Tageszusammenfassung abbestellt.", "🌙 Daily summary cancelled."),
+    ("daily_none", "Du hast keine Tageszusammenfassung bestellt.", "You have not subscribed to a daily summary."),
+    (
+        "weekly_on",
+        "📅 Wochenbericht jeden {schedule} Uhr: Min, Max, Mittel, Alarme und Zeit außerhalb der Schwellen je Raum.",
+        "📅 Weekly report every {schedule}: min, max, mean, alerts and time outside the thresholds per room.",
+    ),
+    (
+        "weekly_usage",
+        "Verwendung: /subscribe-weekly <wochentag> <HH:MM>, z.B. /subscribe-weekly so 18:00",
+        "Usage: /subscribe-weekly <weekday> <HH:MM>, e.g. /subscribe-weekly so 18:00",
+    ),
+    ("weekly_off", "📅 Wochenbericht abbestellt.", "📅 Weekly report cancelled."),
+    ("weekly_none", "Du hast keinen Wochenbericht bestellt.", "You have not subscribed to a weekly report."),
+    // Schwellen setzen (/setmin, /setmax)
+    (
+        "threshold_no_readings",
+        "❌ {room} liefert keine Messwerte vom Typ '{kind}'.\nBekannte Geräte:\n{devices}",
+        "❌ {room} sends no readings of type '{kind}'.\nKnown devices:\n{devices}",
+    ),
+    ("threshold_set", "{direction}-Schwellwert {type} {room}: {value} {unit}", "{direction} threshold {type} {room}: {value} {unit}"),
+    ("threshold_window", "({window} Uhr)", "({window})"),
+    ("threshold_severity", "Stufe: {severity}", "Level: {severity}"),
+    (
+        "test_alarm_sent",
+        "🧪 Probealarm für {type} {room} ({direction}) verschickt, Stufe {severity}: Warnung und Entwarnung folgen.",
+        "🧪 Test alert for {type} {room} ({direction}) sent, level {severity}: alert and all-clear follow.",
+    ),
+    // Stummschaltung und /settings
+    ("mute_too_long", "❌ Höchstens {max} am Stück.", "❌ At most {max} at a time."),
+    ("mute_all_set", "🔇 Alle Benachrichtigungen stumm bis {until} Uhr. Beenden mit /unmute.", "🔇 All notifications muted until {until}. End with /unmute."),
+    ("snooze_no_thresholds", "Du hast keine Schwellen für {room}.", "You have no thresholds for {room}."),
+    ("snooze_set", "🔇 Keine Warnungen zu {room} bis {until} Uhr. Beenden mit /unmute.", "🔇 No alerts for {room} until {until}. End with /unmute."),
+    ("snooze_reaction", "🔇 Keine Warnungen zu {room} für {duration}.", "🔇 No alerts for {room} for {duration}."),
+    (
+        "snoozes_ended",
+        "Höchstens {max} Schwellen sind gleichzeitig stumm, deshalb wieder aktiv: {names}",
+        "At most {max} thresholds can be muted at once, so these are active again: {names}",
+    ),
+    ("snoozes_title", "🔇 Stummgeschaltete Schwellen:", "🔇 Muted thresholds:"),
+    (
+        "snoozes_line",
+        "{room} {type} {direction} – noch {left} (bis {until} Uhr, {origin})",
+        "{room} {type} {direction} – {left} left (until {until}, {origin})",
+    ),
+    ("snoozes_clear_hint", "Alle aufheben mit /snooze clear.", "Lift all with /snooze clear."),
+    ("severity_info", "Info", "info"),
+    ("severity_warning", "Warnung", "warning"),
+    ("severity_critical", "kritisch", "critical"),
+    ("snooze_origin_reaction", "per Reaktion", "by reaction"),
+    ("snooze_origin_command", "per /snooze", "by /snooze"),
+    ("snooze_origin_button", "per Button", "by button"),
+    ("unmute_missed_none", "🔔 Stummschaltung beendet, keine Warnungen verpasst.", "🔔 Muting ended, no alerts missed."),
+    ("unmute_missed_one", "🔔 Stummschaltung beendet, 1 Warnung verpasst.", "🔔 Muting ended, 1 alert missed."),
+    ("unmute_missed", "🔔 Stummschaltung beendet, {count} Warnungen verpasst.", "🔔 Muting ended, {count} alerts missed."),
+    ("notifications_loud", "Alles kommt mit Ton.", "Everything comes with sound."),
+    (
+        "notifications_normal",
+        "Antworten, Berichte und Info-Warnungen kommen lautlos, Warnungen mit Ton.",
+        "Replies, reports and info alerts arrive silently, alerts with sound.",
+    ),
+    ("notifications_quiet", "Nur kritische Warnungen kommen mit Ton.", "Only critical alerts come with sound."),
+    ("alert_mode_is_instant", "sofort", "immediately"),
+    ("alert_mode_is_digest", "gesammelt alle {interval}", "collected every {interval}"),
+    (
+        "settings",
+        "⚙️ *Deine Einstellungen:*\nSchwellwerte: {thresholds} (/thresholds)\nStatusbericht: {report}\nTageszusammenfassung: {daily} (/subscribe-daily)\nWochenbericht: {weekly} (/subscribe-weekly)\nRuhezeit: {quiet}\nWarnungen: {alert_mode} (/alert-mode)\nBenachrichtigungen: {notifications} (/notifications)\nDarstellung: {layout} (/format)\nZeitangaben: {times} (/timeformat)\nTemperatureinheit: {units} (/units)\nZeitzone: {zone} (/timezone)\nNotizen: {notes} (/note)\nRaumbilder: {images} (/room-images)\nAbfrageintervall: {interval}",
+        "⚙️ *Your settings:*\nThresholds: {thresholds} (/thresholds)\nStatus report: {report}\nDaily summary: {daily} (/subscribe-daily)\nWeekly report: {weekly} (/subscribe-weekly)\nQuiet hours: {quiet}\nAlerts: {alert_mode} (/alert-mode)\nNotifications: {notifications} (/notifications)\nLayout: {layout} (/format)\nTimes: {times} (/timeformat)\nTemperature unit: {units} (/units)\nTime zone: {zone} (/timezone)\nNotes: {notes} (/note)\nRoom images: {images} (/room-images)\nPoll interval: {interval}",
+    ),
+    ("settings_none", "keine", "none"),
+    ("settings_none_report", "keiner", "none"),
+    ("settings_on", "an", "on"),
+    ("settings_layout_table", "Tabelle", "table"),
+    ("settings_layout_classic", "klassisch", "classic"),
+    ("settings_time_absolute", "absolut", "absolute"),
+    ("settings_time_relative", "relativ", "relative"),
+    ("settings_time_both", "beides", "both"),
+    ("settings_handover", "Weitergabe: {handover} (/escalation)", "Hand-over: {handover} (/escalation)"),
+    (
+        "settings_watering",
+        "Gießerinnerung: ab {above} °C an {days} Tagen in Folge (/watering)",
+        "Watering reminder: from {above} °C on {days} days in a row (/watering)",
+    ),
+    ("settings_muted_until", "🔇 Stumm bis {until} Uhr ({missed} Warnungen verpasst)", "🔇 Muted until {until} ({missed} alerts missed)"),
+    ("settings_not_muted", "Stummschaltung: aus", "Muting: off"),
+    ("clock_time", "{time} Uhr", "{time}"),
+    // Schwellen auflisten (/thresholds, /copy-thresholds)
+    ("thresholds_title", "📏 *Deine Schwellwerte:*", "📏 *Your thresholds:*"),
+    ("thresholds_profile", "🗂 Profil {name} aktiv ({how})", "🗂 Profile {name} active ({how})"),
+    ("thresholds_profile_manual", "gewählt, /profile activate auto beendet das", "chosen, /profile activate auto ends that"),
+    ("thresholds_profile_chosen", "gewählt", "chosen"),
+    ("thresholds_flag_no_readings", "⚠ keine Messwerte", "⚠ no readings"),
+    ("thresholds_flag_alarm", "🚨 Alarm seit {since}", "🚨 alert since {since}"),
+    ("thresholds_flag_acknowledged", "✅ bestätigt", "✅ acknowledged"),
+    ("thresholds_flag_snoozed", "🔇 bis {until}", "🔇 until {until}"),
+    ("thresholds_flag_undelivered", "📵 Zustellung gestört", "📵 delivery failing"),
+    ("thresholds_flag_ignored", "🚫 Gerät ignoriert", "🚫 device ignored"),
+    ("thresholds_hysteresis", "↔️ Hysterese {value} {unit}", "↔️ Hysteresis {value} {unit}"),
+    ("thresholds_repeat", "🔁 Erinnerung alle {interval}", "🔁 Reminder every {interval}"),
+    ("thresholds_otherwise", "sonst", "otherwise"),
+    ("thresholds_always", "immer", "always"),
+    ("thresholds_active_mark", "◀ aktiv", "◀ active"),
+    ("thresholds_profile_value", "{value} (Profil {name})", "{value} (profile {name})"),
+    ("thresholds_rate", "{icon} {room} – {type}: Änderung {change} {unit} in {window}", "{icon} {room} – {type}: change {change} {unit} within {window}"),
+    (
+        "thresholds_relation_above",
+        "↕️ {room} – {type} {direction}: Alarm über {other_type} {other_room} {offset} {unit}",
+        "↕️ {room} – {type} {direction}: alert above {other_type} {other_room} {offset} {unit}",
+    ),
+    (
+        "thresholds_relation_below",
+        "↕️ {room} – {type} {direction}: Alarm unter {other_type} {other_room} {offset} {unit}",
+        "↕️ {room} – {type} {direction}: alert below {other_type} {other_room} {offset} {unit}",
+    ),
+    (
+        "thresholds_no_readings_note",
+        "⚠ = seit über {duration} kein Messwert für Gerät und Typ dieser Schwelle.",
+        "⚠ = no reading for this threshold's device and type for over {duration}.",
+    ),
+    ("thresholds_this_chat", "diesem Chat", "this chat"),
+    ("thresholds_other_chat", "Chat {chat}", "chat {chat}"),
+    ("thresholds_undelivered", "📵 Warnungen kommen bei {chat} seit {since} nicht an: {reason}.", "📵 Alerts have not reached {chat} since {since}: {reason}."),
+    ("device_thresholds_title", "Schwellen für {room}:", "Thresholds for {room}:"),
+    ("device_thresholds_repeat", "🔁 alle {interval}", "🔁 every {interval}"),
+    ("device_thresholds_replaced", "(ersetzt)", "(replaced)"),
+    // Bestätigungen (/clear all, /forgetme, /restore, /ignore)
+    ("confirm_apply", "✅ Anwenden", "✅ Apply"),
+    ("confirm_cancel", "❌ Abbrechen", "❌ Cancel"),
+    ("confirm_within", "Bestätigen innerhalb von {duration}.", "Confirm within {duration}."),
+    ("confirm_cancelled", "❌ Abgebrochen, nichts wurde geändert.", "❌ Cancelled, nothing was changed."),
+    (
+        "confirm_expired",
+        "⌛ Die Vorschau ist abgelaufen, nichts wurde geändert. Sende den Befehl bitte erneut.",
+        "⌛ The preview has expired, nothing was changed. Please send the command again.",
+    ),
+    ("confirm_missing", "Schon erledigt oder abgebrochen.", "Already done or cancelled."),
+    ("confirm_foreign", "Nur wer den Befehl gesendet hat, kann bestätigen.", "Only the sender of the command can confirm."),
+    ("summary_thresholds", "{count} Schwellen", "{count} thresholds"),
+    ("summary_rates", "{count} Änderungsalarme", "{count} rate-of-change alerts"),
+    ("summary_relations", "{count} Vergleichsschwellen", "{count} relative thresholds"),
+    ("summary_notes", "{count} Notizen", "{count} notes"),
+    ("summary_report", "geplanter Statusbericht", "scheduled status report"),
+    ("summary_daily", "Tageszusammenfassung {schedule} Uhr", "daily summary {schedule}"),
+    ("summary_weekly", "Wochenbericht {schedule} Uhr", "weekly report {schedule}"),
+    ("summary_api_token", "API-Token", "API token"),
+    ("summary_quiet", "Ruhezeit", "quiet hours"),
+    ("summary_watering", "Gießerinnerung", "watering reminder"),
+    ("summary_locale", "Sprache {lang}, Temperaturen in {units}, Zeitzone {zone}", "language {lang}, temperatures in {units}, time zone {zone}"),
+    (
+        "clear_done",
+        "🗑 Alle deine Einstellungen wurden gelöscht. Bis {until} lassen sie sich mit /undo-clear wiederherstellen.",
+        "🗑 All your settings were deleted. They can be restored with /undo-clear until {until}.",
+    ),
+    (
+        "forget_done",
+        "🗑 Alle deine Daten wurden endgültig gelöscht. Das lässt sich nicht rückgängig machen.",
+        "🗑 All your data was deleted for good. This cannot be undone.",
+    ),
+    ("forget_nothing", "🗑 Es sind keine Daten mehr von dir gespeichert.", "🗑 No data of yours is stored any more."),
+    ("restore_too_large", "❌ Die Datei ist zu groß für eine Sicherung von /backup.", "❌ The file is too large to be a /backup file."),
+    (
+        "restore_download_failed",
+        "❌ Die Datei konnte nicht geladen werden. Bitte noch einmal senden.",
+        "❌ The file could not be downloaded. Please send it again.",
+    ),
+    (
+        "restore_version",
+        "Die Sicherung hat Version {version}, dieser Bot kennt nur bis {known}. Bitte den Bot aktualisieren.",
+        "The backup has version {version}, this bot only knows up to {known}. Please update the bot.",
+    ),
+    ("restore_damaged", "Die Sicherung ist beschädigt: {error}", "The backup is damaged: {error}"),
+    ("restore_not_backup", "Das ist keine Datei von /backup.", "This is not a /backup file."),
+    ("restore_preview", "♻️ Sicherung vom {created} wiederherstellen?", "♻️ Restore the backup from {created}?"),
+    ("restore_other_chat", "ℹ️ Die Sicherung stammt aus einem anderen Chat.", "ℹ️ The backup comes from another chat."),
+    ("restore_dropped", "⚠️ {count} Schwellen auf hier unbekannten Geräten werden übersprungen.", "⚠️ {count} thresholds on devices unknown here are skipped."),
+    (
+        "restore_replaces",
+        "Deine jetzigen Einstellungen werden ersetzt; laufende Alarme und dein API-Token bleiben.",
+        "Your current settings are replaced; active alerts and your API token stay.",
+    ),
+    ("restore_done", "♻️ Deine Einstellungen sind aus der Sicherung übernommen.", "♻️ Your settings were restored from the backup."),
+    (
+        "ignore_notice",
+        "🚫 {room} wird nicht mehr überwacht (vom Admin ignoriert). Deine Schwellen dafür bleiben gespeichert, lösen aber keine Warnungen aus.",
+        "🚫 {room} is no longer monitored (ignored by the admin). Your thresholds for it are kept but trigger no alerts.",
+    ),
+    (
+        "ignore_done",
+        "🚫 {room} wird ab jetzt ignoriert. {count} Chats mit Schwellen darauf wurden informiert.",
+        "🚫 {room} is ignored from now on. {count} chats with thresholds on it were informed.",
+    ),
+    // Verlauf, Statistik, Rekorde, Geräte und Diagramme
+    ("suppressed_muted", "stumm", "muted"),
+    ("suppressed_quiet", "Ruhezeit", "quiet hours"),
+    ("suppressed_snoozed", "schlummert", "snoozed"),
+    ("records_title", "🏆 *Rekorde:*", "🏆 *Records:*"),
+    ("records_all_time", "Insgesamt", "All time"),
+    ("records_none", "Noch keine Rekordwerte erfasst.", "No records yet."),
+    ("record_new_low", "{room} {type}: neuer Tiefstwert! {value} {unit} um {time}", "{room} {type}: new low! {value} {unit} at {time}"),
+    ("record_new_high", "{room} {type}: neuer Höchstwert! {value} {unit} um {time}", "{room} {type}: new high! {value} {unit} at {time}"),
+    ("history_empty", "Keine Messwerte für {type} {room} in den letzten {hours} h.", "No readings for {type} {room} in the last {hours} h."),
+    (
+        "history",
+        "📈 {type} {room}, letzte {hours} h ({count} Werte):\nMin {min} {unit} · Max {max} {unit} · Mittel {mean} {unit}\nErster Wert {first} {unit} ({first_at}), letzter {last} {unit} ({last_at})",
+        "📈 {type} {room}, last {hours} h ({count} values):\nMin {min} {unit} · Max {max} {unit} · Mean {mean} {unit}\nFirst value {first} {unit} ({first_at}), last {last} {unit} ({last_at})",
+    ),
+    ("history_since", "ℹ️ Messwerte erst seit {since} vorhanden.", "ℹ️ Readings only available since {since}."),
+    ("stats_day", "24 Stunden", "24 hours"),
+    ("stats_week", "7 Tage", "7 days"),
+    ("stats_empty", "Letzte {period}: keine Messwerte.", "Last {period}: no readings."),
+    (
+        "stats_period",
+        "Letzte {period} ({count} Werte):\nMin {min} ({min_at})\nMax {max} ({max_at})\nMittel {mean}",
+        "Last {period} ({count} values):\nMin {min} ({min_at})\nMax {max} ({max_at})\nMean {mean}",
+    ),
+    ("stats_short", "ℹ️ Verlauf kürzer als der Zeitraum: Messwerte erst seit {since}.", "ℹ️ History shorter than the period: readings only since {since}."),
+    ("sensors_none", "📡 Der Sensor-Webserver meldet derzeit keine Geräte.", "📡 The sensor web server currently reports no devices."),
+    ("sensors_title", "📡 Gemeldete Geräte ({count}):", "📡 Reported devices ({count}):"),
+    ("sensors_no_room", "ohne Raum", "no room"),
+    ("sensors_line", "{device} ({room}): {types} – vor {age}", "{device} ({room}): {types} – {age} ago"),
+    ("sensors_hint", "Geräte-ID und Typ so für /setmin und /setmax verwenden.", "Use device ID and type like this for /setmin and /setmax."),
+    ("diff_title", "🔍 *Änderungen seit {since} (vor {elapsed}):*", "🔍 *Changes since {since} ({elapsed} ago):*"),
+    ("diff_changed", "{delta} {unit} in {elapsed} (jetzt {now} {unit})", "{delta} {unit} in {elapsed} (now {now} {unit})"),
+    ("diff_appeared", "🆕 neu: {value} {unit}", "🆕 new: {value} {unit}"),
+    ("diff_disappeared", "❌ nicht mehr gemeldet (zuletzt {value} {unit})", "❌ no longer reported (last {value} {unit})"),
+    ("chart_no_rooms", "Es sind keine Räume konfiguriert.", "No rooms are configured."),
+    (
+        "chart_usage",
+        "📈 Diagramme verfügbar für: {rooms}\nVerwendung: /chart <raum> [typ] [dauer, z.B. 12h oder 3d]",
+        "📈 Charts available for: {rooms}\nUsage: /chart <room> [type] [period, e.g. 12h or 3d]",
+    ),
+    ("chart_none", "Für {room} gibt es kein passendes Diagramm.", "There is no matching chart for {room}."),
+    ("chart_feed_source", "Quelle: ThingSpeak-Kanal {channel}", "Source: ThingSpeak channel {channel}"),
+    ("chart_feed_empty", "ThingSpeak-Kanal {channel} hat für diesen Zeitraum keine Werte.", "ThingSpeak channel {channel} has no values for this period."),
+    ("chart_feed_failed", "ThingSpeak-Kanal {channel} nicht abrufbar: {error}", "ThingSpeak channel {channel} not reachable: {error}"),
+    ("chart_heading", "{room} – {type}, letzte {duration}", "{room} – {type}, last {duration}"),
+    ("chart_too_few", "Für ein Diagramm sind es zu wenige Messwerte.", "Too few readings for a chart."),
+    ("chart_full_size", "Volle Auflösung ({ttl}): {url}", "Full resolution ({ttl}): {url}"),
+    ("chart_disabled", "Diagramme sind in diesem Build deaktiviert.", "Charts are disabled in this build."),
+    // Tageszusammenfassung, Wochenbericht und Hinweise aus dem Hintergrund
+    ("daily_greeting", "🌙 Guten Abend {name}, ", "🌙 Good evening {name}, "),
+    ("daily_title", "deine *Tageszusammenfassung* seit {since}:", "your *daily summary* since {since}:"),
+    ("daily_warning", "1 Warnung", "1 alert"),
+    ("daily_warnings", "{count} Warnungen", "{count} alerts"),
+    ("daily_one_time", "Bitte höchstens eine Uhrzeit je Wochentag angeben.", "Please give at most one time per weekday."),
+    ("weekly_greeting", "📅 Hallo {name}, ", "📅 Hello {name}, "),
+    ("weekly_title", "dein *Wochenbericht* vom {from} bis {to}:", "your *weekly report* from {from} to {to}:"),
+    ("weekly_alarm", "1 Alarm", "1 alert"),
+    ("weekly_alarms", "{count} Alarme", "{count} alerts"),
+    ("weekly_never_outside", "nie außerhalb der Schwellen", "never outside the thresholds"),
+    ("weekly_outside", "{duration} außerhalb der Schwellen", "{duration} outside the thresholds"),
+    ("weekly_coverage", "(Messwerte für {percent} % der Woche)", "(readings for {percent} % of the week)"),
+    ("weekly_one_time", "Bitte genau einen Wochentag und eine Uhrzeit angeben.", "Please give exactly one weekday and one time."),
+    ("weekly_chart_title", "Temperaturen der Woche", "Temperatures this week"),
+    ("period_no_readings", "Keine Messwerte in diesem Zeitraum.", "No readings in this period."),
+    ("period_line", "📍 {room} – {type}: Min {min} · Max {max} · Mittel {mean} {unit}", "📍 {room} – {type}: Min {min} · Max {max} · Mean {mean} {unit}"),
+    (
+        "frost_warning",
+        "❄️ *Heute Nacht Frostgefahr* – {room}\nJetzt {now} °C, bis {until} Uhr etwa {min} °C erwartet. Pflanzen reinholen oder abdecken.",
+        "❄️ *Frost risk tonight* – {room}\nNow {now} °C, about {min} °C expected by {until}. Bring plants in or cover them.",
+    ),
+    (
+        "watering_reminder",
+        "🌱 *Gießen nicht vergessen* – {room}\n{days} Tage in Folge über {above} °C, heute bis {max} °C.",
+        "🌱 *Don't forget to water* – {room}\n{days} days in a row above {above} °C, up to {max} °C today.",
+    ),
+    (
+        "mold_warning",
+        "🦠 Schimmelgefahr im {room}: Luftfeuchtigkeit seit {duration} über {limit} %. Bitte lüften oder heizen.",
+        "🦠 Mould risk in {room}: humidity above {limit} % for {duration}. Please air or heat the room.",
+    ),
+    ("handover_forwarded", "🆘 Weitergegeben von {owner}, seit {pending} unbestätigt:", "🆘 Handed over by {owner}, unacknowledged for {pending}:"),
+    (
+        "handover_sent",
+        "🆘 Warnung zu {room} nach {pending} ohne Bestätigung an Chat {chat} weitergegeben.",
+        "🆘 Alert for {room} handed over to chat {chat} after {pending} without acknowledgement.",
+    ),
+    // Zugang, Drosselung und Hinweise zu verunglückten Befehlen
+    ("throttled", "⏳ Bitte langsamer, weitere Befehle beantworte ich gleich wieder.", "⏳ Please slow down, I will answer further commands again shortly."),
+    (
+        "not_admitted",
+        "🔒 Dieser Bot ist privat. Der Admin kann deinen Chat mit /allow freischalten.",
+        "🔒 This bot is private. The admin can admit your chat with /allow.",
+    ),
+    ("or", " oder ", " or "),
+    ("did_you_mean", "🤔 Meintest du {commands}?", "🤔 Did you mean {commands}?"),
+    ("argument_missing", "Es fehlt ein Wert.", "A value is missing."),
+    (
+        "argument_not_a_number",
+        "'{argument}' ist keine Zahl, Nachkommastellen mit Punkt (z.B. 18.5).",
+        "'{argument}' is not a number, use a point for decimals (e.g. 18.5).",
+    ),
+    ("arguments_too_few", "Es fehlen Angaben.", "Some details are missing."),
+    ("arguments_too_many", "Zu viele Angaben.", "Too many details."),
+    ("room_ambiguous", "Meinst du {rooms}?", "Do you mean {rooms}?"),
+    ("room_no_readings", "Für {room} liegen keine aktuellen Messwerte vor.", "There are no current readings for {room}."),
+    ("delivery_test", "🔔 Test: Dieser Chat erhält Warnungen ab Stufe \"{severity}\".", "🔔 Test: this chat receives alerts from level \"{severity}\"."),
+    (
+        "delivery_failed",
+        "⚠ Warnungen ab Stufe \"{severity}\" gehen zusätzlich an Chat {chat}, kommen dort aber nicht an: {reason}.\nIst das dein Chat, bitte starte zuerst einen privaten Chat mit mir; eine Gruppe muss mich wieder aufnehmen. Bis zur nächsten erfolgreichen Zustellung ist das bei /thresholds vermerkt.",
+        "⚠ Alerts from level \"{severity}\" also go to chat {chat} but do not arrive there: {reason}.\nIf this is your chat, please start a private chat with me first; a group has to add me again. Until the next successful delivery this is noted in /thresholds.",
+    ),
+    // Buttons an Warnungen, Anpassen per Nachricht und /configure
+    ("snooze_until_tomorrow", "😴 bis morgen", "😴 until tomorrow"),
+    ("adjust_button", "Schwelle anpassen…", "Adjust threshold…"),
+    ("adjust_ask", "Neuer {direction}-Wert für {type} {room}? Schick einfach die Zahl.", "New {direction} value for {type} {room}? Just send the number."),
+    ("adjust_new_threshold", "✏️ Neue Schwelle: {value} {unit}", "✏️ New threshold: {value} {unit}"),
+    ("adjust_recovered", "✅ Wert liegt damit wieder im Bereich.", "✅ The value is back within range."),
+    ("adjust_not_a_number", "Kein Zahlenwert – Anpassung abgebrochen.", "Not a number – adjustment cancelled."),
+    ("acknowledged", "Bestätigt", "Acknowledged"),
+    ("acknowledged_by", "✅ Bestätigt von {name} um {time} Uhr", "✅ Acknowledged by {name} at {time}"),
+    ("snoozed_until", "🔇 Stumm bis {time} Uhr", "🔇 Muted until {time}"),
+    ("threshold_deleted", "Schwelle gelöscht", "Threshold deleted"),
+    ("threshold_gone", "Schwelle gibt es nicht mehr", "The threshold no longer exists"),
+    ("threshold_deleted_undo", "🗑 Schwelle gelöscht, /undo stellt sie wieder her.", "🗑 Threshold deleted, /undo restores it."),
+    ("threshold_not_active", "Diese Schwelle ist gerade nicht aktiv.", "This threshold is not active right now."),
+    ("configure_cancel", "✖ Abbrechen", "✖ Cancel"),
+    ("configure_cancelled", "✖ Einrichtung abgebrochen.", "✖ Setup cancelled."),
+    ("configure_expired_short", "Abgelaufen – bitte /configure neu starten.", "Expired – please start /configure again."),
+    ("configure_expired", "⌛ Einrichtung abgelaufen. /configure startet neu.", "⌛ Setup expired. /configure starts over."),
+    ("configure_kind", "⚙️ {room} – welcher Messwert?", "⚙️ {room} – which reading?"),
+    ("configure_direction", "⚙️ {type} {room} – Warnung unter MIN oder über MAX?", "⚙️ {type} {room} – alert below MIN or above MAX?"),
+    (
+        "configure_value",
+        "⚙️ {direction}-Wert für {type} {room} in {unit}? Schick einfach die Zahl.",
+        "⚙️ {direction} value for {type} {room} in {unit}? Just send the number.",
+    ),
+    ("room_image_saved", "🖼 Raumbild für {room} gespeichert.", "🖼 Room picture for {room} saved."),
+    ("room_image_failed", "❌ Raumbild nicht gespeichert: {error}", "❌ Room picture not saved: {error}"),
+    ("device_no_readings", "❌ {room} liefert keine Messwerte.\nBekannte Geräte:\n{devices}", "❌ {room} reports no readings.\nKnown devices:\n{devices}"),
+    ("copy_nothing", "Für {room} sind keine Schwellen gesetzt.", "No thresholds are set for {room}."),
+    ("default_not_applicable", "{room} liefert weder Temperatur noch Luftfeuchtigkeit.", "{room} reports neither temperature nor humidity."),
+    ("fallback", "Ich habe dich nicht verstanden. Nutze /help für Befehle.", "I did not understand you. Use /help for commands."),
+    // Fehler beim Lesen von Zeitplänen, Zeitfenstern und Schwellen
+    ("unknown_weekday", "Unbekannter Wochentag '{day}' (mo, di, mi, do, fr, sa, so)", "Unknown weekday '{day}' (mo, di, mi, do, fr, sa, so)"),
+    ("invalid_time", "Ungültige Uhrzeit '{time}' (erwartet HH:MM)", "Invalid time '{time}' (expected HH:MM)"),
+    ("schedule_no_time", "Keine Uhrzeit in '{group}' angegeben", "No time given in '{group}'"),
+    ("schedule_empty", "Zeitplan ist leer. Beispiel: mo-fr 06:30; sa,so 09:00", "The schedule is empty. Example: mo-fr 06:30; sa,so 09:00"),
+    ("window_format", "Zeitfenster '{window}' muss die Form HH:MM-HH:MM haben", "Time window '{window}' must have the form HH:MM-HH:MM"),
+    ("window_empty", "Start und Ende des Zeitfensters dürfen nicht gleich sein", "Start and end of the time window must differ"),
+    ("window_overlap", "Zeitfenster {window} überschneidet sich mit {other}", "Time window {window} overlaps {other}"),
+    (
+        "threshold_args_usage",
+        "Verwendung: <gerät> <typ> <wert> [HH:MM-HH:MM] [info|warn|critical] [\"Text\"]",
+        "Usage: <device> <type> <value> [HH:MM-HH:MM] [info|warn|critical] [\"text\"]",
+    ),
+    ("threshold_args_text_empty", "Der Text in Anführungszeichen ist leer.", "The quoted text is empty."),
+    ("threshold_args_not_a_number", "'{value}' ist keine Zahl.", "'{value}' is not a number."),
+    ("layout_usage", "Verwendung: /format compact|table", "Usage: /format compact|table"),
+    ("notifications_usage", "Verwendung: /notifications loud|normal|quiet", "Usage: /notifications loud|normal|quiet"),
+    ("timeformat_usage", "Verwendung: /timeformat relative|absolute|both", "Usage: /timeformat relative|absolute|both"),
+    ("unknown_direction", "Richtung '{direction}' unbekannt (min oder max)", "Unknown direction '{direction}' (min or max)"),
+    // Fußzeilen von /status, /schedules, /health und Kalender
+    ("footer_poll_soon", "gleich", "shortly"),
+    ("footer_poll_in", "in {duration}", "in {duration}"),
+    ("footer_poll", "⏱ Nächste Abfrage {when} ({time} Uhr)", "⏱ Next fetch {when} ({time})"),
+    ("footer_report", "🗓 Nächster Bericht: {time} Uhr", "🗓 Next report: {time}"),
+    ("footer_skipped", "⚠️ {count} unlesbare Einträge der Sensordaten fehlen hier", "⚠️ {count} unreadable sensor entries are missing here"),
+    ("footer_snoozed_one", "🔇 1 Schwelle stummgeschaltet (/snoozes)", "🔇 1 threshold muted (/snoozes)"),
+    ("footer_snoozed", "🔇 {count} Schwellen stummgeschaltet (/snoozes)", "🔇 {count} thresholds muted (/snoozes)"),
+    ("alert_synthetic", "🧪 Testwert (/inject), kein echter Messwert", "🧪 Test value (/inject), not a real reading"),
+    ("snooze_ended", "🔔 Stummschaltung für {room} beendet.", "🔔 Mute for {room} ended."),
+    ("schedule_title", "📅 *Statusbericht – Wochenplan:*", "📅 *Status report – weekly plan:*"),
+    ("schedule_next", "Nächster Bericht: {time}", "Next report: {time}"),
+    ("health_title", "🩺 *Bot-Zustand:*", "🩺 *Bot health:*"),
+    ("health_running", "Läuft seit {since} ({duration})", "Running since {since} ({duration})"),
+    ("health_heartbeat", "Letzter Überwachungsdurchlauf: {time}", "Last monitoring pass: {time}"),
+    ("health_fetch", "Letzter Abruf: {time}, {devices} Geräte", "Last fetch: {time}, {devices} devices"),
+    (
+        "health_fetch_failed",
+        "⚠️ Letzter Abruf: {time} fehlgeschlagen ({error}), {count}× in Folge",
+        "⚠️ Last fetch: {time} failed ({error}), {count}× in a row",
+    ),
+    ("health_fetch_none", "Letzter Abruf: noch keiner", "Last fetch: none yet"),
+    ("health_interval", "Abfrageintervall: {interval}", "Fetch interval: {interval}"),
+    ("health_source_intervals", "Eigene Abfrageintervalle einzelner Quellen: {intervals}", "Own fetch intervals of single sources: {intervals}"),
+    ("health_downtime", "Ausfälle (7 Tage): {count}, gesamt {total}{unexpected}", "Outages (7 days): {count}, total {total}{unexpected}"),
+    ("health_unexpected", ", davon {count} unerwartet", ", {count} of them unexpected"),
+    (
+        "health_escalated",
+        "⏱ {room} nach Alarm alle {interval} abgefragt (noch {remaining})",
+        "⏱ {room} fetched every {interval} after an alert ({remaining} left)",
+    ),
+    (
+        "health_latency",
+        "Messwert bis Warnung (letzte {count}): Median {median}, höchstens {max}",
+        "Reading to alert (last {count}): median {median}, at most {max}",
+    ),
+    ("ical_report", "📅 Statusbericht", "📅 Status report"),
+    ("ical_report_description", "Geplanter Statusbericht ({days})", "Scheduled status report ({days})"),
+    ("ical_quiet", "🔕 Ruhezeit", "🔕 Quiet hours"),
+    ("ical_quiet_description", "Warnungen werden gesammelt und danach zusammen gesendet", "Alerts are collected and sent together afterwards"),
+    ("ical_muted", "🔇 Stummgeschaltet", "🔇 Muted"),
+    ("ical_muted_description", "Alle Benachrichtigungen sind stumm, /unmute beendet das", "All notifications are muted, /unmute ends this"),
+    // Abruf der Sensoren und Zustellung
+    ("fetch_unreachable", "Sensor-Webserver nicht erreichbar ({error})", "Sensor web server unreachable ({error})"),
+    ("fetch_unreadable", "Sensor-Webserver liefert unlesbare Daten ({error})", "Sensor web server returns unreadable data ({error})"),
+    (
+        "status_cached_unreachable",
+        "⚠️ Stand: vor {age}, Sensor-Webserver derzeit nicht erreichbar",
+        "⚠️ As of {age} ago, the sensor web server is currently unreachable",
+    ),
+    (
+        "status_cached_unreadable",
+        "⚠️ Stand: vor {age}, Sensor-Webserver liefert derzeit unlesbare Daten",
+        "⚠️ As of {age} ago, the sensor web server currently returns unreadable data",
+    ),
+    ("refresh_not_running", "Überwachung läuft nicht", "monitoring is not running"),
+    ("refresh_no_source", "keine Quelle zum Abfragen", "no source to fetch from"),
+    (
+        "location_invalid",
+        "'{value}' ist kein Standort, erwartet <breite>,<länge>, z.B. 49.79,9.95",
+        "'{value}' is not a location, expected <latitude>,<longitude>, e.g. 49.79,9.95",
+    ),
+    ("unreachable_blocked", "Bot wurde blockiert", "the bot was blocked"),
+    ("unreachable_kicked", "Bot wurde aus dem Chat entfernt", "the bot was removed from the chat"),
+    ("unreachable_gone", "Chat gibt es nicht mehr", "the chat no longer exists"),
+    ("unreachable_deleted", "Konto wurde gelöscht", "the account was deleted"),
+    ("unreachable_not_started", "privater Chat mit dem Bot wurde nie gestartet", "a private chat with the bot was never started"),
+    ("unreachable_no_rights", "Bot darf dort nicht schreiben", "the bot may not write there"),
+    ("unreachable_migrated", "Gruppe wurde in eine Supergruppe umgewandelt", "the group was converted into a supergroup"),
+    // Hinweise an den Admin, /debug, /users, Geräte-Wochenbericht und Selbsttest
+    ("send_throttled", "gedrosselt ({seconds} s)", "throttled ({seconds} s)"),
+    ("send_reply_missing", "Bezugsnachricht fehlt", "the message replied to is missing"),
+    ("routing_unreachable", "⚠ Warnungen können nicht an Chat {chat} gesendet werden: {reason}", "⚠ Alerts cannot be sent to chat {chat}: {reason}"),
+    ("routing_title", "🛠 Weiterleitung von Warnungen", "🛠 Alert routing"),
+    ("routing_level", "Stufe \"{severity}\":", "Level \"{severity}\":"),
+    ("routing_owners_only", "nur an die Besitzer der Schwelle", "only to the owners of the threshold"),
+    ("routing_reachable", "✅ erreichbar", "✅ reachable"),
+    ("routing_unchecked", "⏳ noch nicht geprüft", "⏳ not checked yet"),
+    ("routing_margin", "Kritisch ab Abstand zur Schwelle:", "Critical from distance to the threshold:"),
+    (
+        "routing_tenants",
+        "Weitere Haushalte (ohne Weiterleitung): {tenants}\nDetails: /debug <haushalt>",
+        "Further households (without routing): {tenants}\nDetails: /debug <household>",
+    ),
+    ("tenant_title", "🏠 Haushalt {tenant}\nQuelle: {source}\n\nChats:", "🏠 Household {tenant}\nSource: {source}\n\nChats:"),
+    ("tenant_no_chats", "keine", "none"),
+    ("tenant_rooms", "Räume:", "Rooms:"),
+    ("users_title", "👥 {count} bekannte Chats:", "👥 {count} known chats:"),
+    ("users_line", "{chat}{name} – {thresholds} Schwellen, {last}", "{chat}{name} – {thresholds} thresholds, {last}"),
+    ("users_last", "zuletzt {time}", "last {time}"),
+    ("users_no_command", "noch kein Befehl erfasst", "no command recorded yet"),
+    ("users_blocked", ", hat den Bot blockiert", ", has blocked the bot"),
+    ("fleet_title", "🛰 *Geräte-Wochenbericht* {from} – {to}", "🛰 *Weekly fleet report* {from} – {to}"),
+    ("fleet_empty", "Keine Messwerte in dieser Woche.", "No readings this week."),
+    ("fleet_header", "Raum|Verf.|Ausf.|Takt|Lücke", "Room|Avail.|Outages|Interval|Gap"),
+    (
+        "fleet_legend",
+        "Verf. = Zeit ohne Ausfall, Ausf. = Lücken deutlich über dem üblichen Takt",
+        "Avail. = time without outage, Outages = gaps well above the usual interval",
+    ),
+    ("fleet_new", "🆕 Neu: {rooms}", "🆕 New: {rooms}"),
+    ("fleet_silent", "💤 Verstummt: {rooms}", "💤 Gone silent: {rooms}"),
+    ("selftest_passed", "🩺 Selbsttest: alle {count} Prüfungen bestanden", "🩺 Self-test: all {count} checks passed"),
+    (
+        "selftest_broken",
+        "🩺 Selbsttest: {failed} von {count} Prüfungen fehlgeschlagen, der Bot arbeitet so nicht richtig",
+        "🩺 Self-test: {failed} of {count} checks failed, the bot does not work properly like this",
+    ),
+    (
+        "selftest_degraded",
+        "🩺 Selbsttest: {failed} von {count} Prüfungen fehlgeschlagen, der Bot läuft eingeschränkt",
+        "🩺 Self-test: {failed} of {count} checks failed, the bot runs with limitations",
+    ),
+    ("selftest_timeout", "keine Antwort nach {seconds} s", "no answer after {seconds} s"),
+    ("selftest_tenant_sensors", "Sensoren ({tenant})", "Sensors ({tenant})"),
+    ("selftest_source", "Sensorquelle {number}", "Sensor source {number}"),
+    ("selftest_no_readings", "keine Messwerte", "no readings"),
+    ("selftest_readings", "{count} Messwerte", "{count} readings"),
+    ("selftest_storage", "Speicher", "Storage"),
+    ("selftest_database", "Datenbank", "Database"),
+    ("selftest_writable", "beschreibbar", "writable"),
+    ("selftest_unchecked", "nicht prüfbar", "cannot be checked"),
+    ("selftest_not_open", "nicht geöffnet", "not open"),
+    ("selftest_rooms_file", "Raumdatei", "Rooms file"),
+    ("selftest_rooms", "{count} Räume", "{count} rooms"),
+    ("selftest_builtin_rooms", "eingebaute Zuordnung", "built-in mapping"),
+    ("selftest_replies_file", "Antworten", "Replies"),
+    ("selftest_replies", "{count} Antworten", "{count} replies"),
+    ("selftest_builtin_replies", "eingebaute Antworten", "built-in replies"),
+    ("nightly_backup_failed", "💾 Nächtliche Sicherung fehlgeschlagen: {error}", "💾 Nightly backup failed: {error}"),
+    (
+        "implausible_confirmed",
+        "🩺 {room} meldet {type} {count}-mal in Folge unplausibel ({doubt}), zuletzt {value} {unit}. Der Wert gilt jetzt als echt; vielleicht ist der Sensor defekt.",
+        "🩺 {room} reported {type} implausibly {count} times in a row ({doubt}), last {value} {unit}. The value now counts as real; the sensor may be faulty.",
+    ),
+    ("doubt_invalid", "kein Zahlenwert", "not a number"),
+    ("doubt_out_of_range", "außerhalb {low} bis {high}", "outside {low} to {high}"),
+    ("doubt_spike", "Sprung vom letzten Wert {previous}", "jump from the last value {previous}"),
+    (
+        "schema_unreadable",
+        "🧩 Sensordaten {count} Mal in Folge nicht lesbar – Format geändert?\nFehler: {error}",
+        "🧩 Sensor data unreadable {count} times in a row – format changed?\nError: {error}",
+    ),
+    ("schema_found", "Gefundene Felder: {fields}", "Found fields: {fields}"),
+    ("schema_expected", "Erwartete Felder: {fields}", "Expected fields: {fields}"),
+    ("schema_missing", "Fehlend: {fields}", "Missing: {fields}"),
+    ("schema_unknown", "Unbekannt: {fields}", "Unknown: {fields}"),
+    ("schema_payload", "Antwort:", "Response:"),
+    ("recovered_files", "♻️ Beim Start waren Zustandsdateien defekt:", "♻️ State files were damaged at startup:"),
+    (
+        "second_instance",
+        "⚠ Zweite Bot-Instanz (PID {pid}) nicht gestartet: PID {running} läuft bereits.",
+        "⚠ Second bot instance (PID {pid}) not started: PID {running} is already running.",
+    ),
+    (
+        "reload_rooms_none",
+        "Keine Raumdatei konfiguriert (ROOMS_FILE), es gilt die eingebaute Zuordnung.",
+        "No rooms file configured (ROOMS_FILE), the built-in mapping applies.",
+    ),
+    ("reload_rooms_invalid", "❌ Raumdatei fehlerhaft, es bleibt beim bisherigen Stand:", "❌ Rooms file is invalid, the previous state stays:"),
+    (
+        "reload_rooms_restart",
+        "❌ Haushalte oder ihre Quellen haben sich geändert; das braucht einen Neustart.",
+        "❌ Households or their sources have changed; that needs a restart.",
+    ),
+    ("reload_rooms_done", "🔄 {count} Räume aus {path} geladen.", "🔄 {count} rooms loaded from {path}."),
+    ("broadcast_report", "📣 Rundnachricht: {delivered} zugestellt, {failed} fehlgeschlagen.", "📣 Broadcast: {delivered} delivered, {failed} failed."),
+    ("broadcast_more", "… und {count} weitere", "… and {count} more"),
+    (
+        "inject_usage",
+        "Verwendung: /inject <gerät> <typ> <wert> [alter, z.B. 2h] [--no-store]",
+        "Usage: /inject <device> <type> <value> [age, e.g. 2h] [--no-store]",
+    ),
+    ("inject_invalid_age", "Alter '{age}' ungültig, z.B. 30m, 2h oder 1d", "Invalid age '{age}', e.g. 30m, 2h or 1d"),
+];
+
+pub fn message(lang: Lang, key: &str) -> &'static str {
+    match MESSAGES.iter().find(|(name, _, _)| *name == key) {
+        Some((_, de, en)) => match lang {
+            Lang::De => de,
+            Lang::En => en,
+        },
+        None => {
+            log::warn!("Kein Text für '{}'", key);
+            ""
+        }
+    }
+}
+
+// Text mit ersetzten Platzhaltern, z.B. [("room", "Bad")] für {room}
+pub fn message_with(lang: Lang, key: &str, values: &[(&str, &str)]) -> String {
+    values.iter().fold(message(lang, key).to_string(), |text, (name, value)| text.replace(&format!("{{{}}}", name), value))
+}
+
+/// Meldung aus dem Katalog, deren Sprache erst beim Antworten feststeht,
+/// z.B. Fehler aus FromStr. Display liefert Deutsch (Logs, Konfiguration).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Text {
+    // Sätze in Reihenfolge
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    // Schlüssel und Werte der Platzhalter
+    Key(&'static str, Vec<(&'static str, String)>),
+    // Ohne Übersetzung, z.B. die Fehlermeldung von Telegram
+    Verbatim(String),
+}
+
+impl Text {
+    pub fn new(key: &'static str, values: &[(&'static str, &str)]) -> Text {
+        Text { parts: vec![Part::Key(key, values.iter().map(|(name, value)| (*name, value.to_string())).collect())] }
+    }
+
+    pub fn verbatim(text: impl Into<String>) -> Text {
+        Text { parts: vec![Part::Verbatim(text.into())] }
+    }
+
+    // Weiterer Satz dahinter, z.B. die Verwendung nach dem Fehler
+    pub fn and(mut self, next: Text) -> Text {
+        self.parts.extend(next.parts);
+        self
+    }
+
+    pub fn in_lang(&self, lang: Lang) -> String {
+        let parts: Vec<String> = self
+            .parts
+            .iter()
+            .map(|part| match part {
+                Part::Key(key, values) => {
+                    let values: Vec<(&str, &str)> = values.iter().map(|(name, value)| (*name, value.as_str())).collect();
+                    message_with(lang, key, &values)
+                }
+                Part::Verbatim(text) => text.clone(),
+            })
+            .collect();
+        parts.join(" ")
+    }
+}
+
+impl fmt::Display for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.in_lang(Lang::default()))
+    }
+}
+
+impl std::error::Error for Text {}
+
+// Beschreibungen der Befehle für das Telegram-Menü: (Befehl, de, en)
+const COMMAND_DESCRIPTIONS: &[(&str, &str, &str)] = &[
+    ("start", "Startet den Bot.", "Starts the bot."),
+    ("help", "Zeigt diese Hilfe an.", "Shows this help."),
+    ("status", "Zeigt alle aktuellen Sensordaten, optional nur für einen Raum.", "Shows all current sensor readings, optionally for one room."),
+    ("refresh", "Sofort neue Messwerte abrufen.", "Fetch new readings now."),
+    ("history", "Verlauf als Zahlen.", "History as numbers."),
+    ("sensors", "Gemeldete Geräte und Messgrößen.", "Reported devices and sensor types."),
+    ("chart", "Diagramm anzeigen.", "Show a chart."),
+    ("export", "Messwerte als CSV-Datei.", "Readings as a CSV file."),
+    ("wohnzimmer-tdia", "Temperaturverlauf Wohnzimmer.", "Living room temperature chart."),
+    ("wohnzimmer-hdia", "Luftfeuchtigkeitsverlauf Wohnzimmer.", "Living room humidity chart."),
+    ("wohnzimmer-tmin", "Alarm, wenn Temperatur unter Wert fällt.", "Alert when temperature drops below value."),
+    ("wohnzimmer-tmax", "Alarm, wenn Temperatur über Wert steigt.", "Alert when temperature rises above value."),
+    ("wohnzimmer-hmin", "Alarm, wenn Luftfeuchtigkeit unter Wert fällt.", "Alert when humidity drops below value."),
+    ("wohnzimmer-hmax", "Alarm, wenn Luftfeuchtigkeit über Wert steigt.", "Alert when humidity rises above value."),
+    ("setmin", "MIN-Schwelle setzen.", "Set a minimum threshold."),
+    ("setmax", "MAX-Schwelle setzen.", "Set a maximum threshold."),
+    ("thresholds", "Zeigt deine Schwellwerte.", "Shows your thresholds."),
+    ("copy-thresholds", "Schwellen auf einen anderen Raum übertragen.", "Copy thresholds to another room."),
+    ("apply-default", "Übliche Schwellen für einen Raum.", "Sensible default thresholds for a room."),
+    ("schedule", "Statusbericht planen.", "Schedule a status report."),
+    ("schedules", "Zeigt deine geplanten Berichte.", "Shows your scheduled reports."),
+    ("subscribe-daily", "Tageszusammenfassung bestellen.", "Subscribe to a daily summary."),
+    ("unsubscribe-daily", "Tageszusammenfassung abbestellen.", "Unsubscribe from the daily summary."),
+    ("subscribe-weekly", "Wochenbericht mit Diagramm bestellen.", "Subscribe to a weekly report with chart."),
+    ("unsubscribe-weekly", "Wochenbericht abbestellen.", "Unsubscribe from the weekly report."),
+    ("quiet-hours", "Ruhezeit für Warnungen.", "Quiet hours for alerts."),
+    ("alert-mode", "Warnungen sofort oder gesammelt.", "Alerts instantly or as a digest."),
+    ("notifications", "Benachrichtigungston einstellen.", "Choose which messages make a sound."),
+    ("mute-all", "Alle Benachrichtigungen stummschalten.", "Mute all notifications."),
+    ("mute", "Alles oder einen Raum stummschalten.", "Mute everything or one room."),
+    ("unmute", "Stummschaltung beenden.", "End muting."),
+    ("settings", "Zeigt deine Einstellungen.", "Shows your settings."),
+    ("layout", "Darstellung von /status.", "Layout of /status."),
+    ("format", "Darstellung von /status: compact oder table.", "Layout of /status: compact or table."),
+    ("timeformat", "Zeitangaben: relativ, absolut oder beides.", "Timestamps: relative, absolute or both."),
+    ("live", "Angeheftete Live-Übersicht.", "Pinned live status."),
+    ("alarms", "Letzte Alarme und Entwarnungen.", "Recent alarms and recoveries."),
+    ("stats", "Min, Max und Mittel für 24 h und 7 Tage.", "Min, max and mean for 24 h and 7 days."),
+    ("diff", "Änderungen seit dem letzten Ansehen.", "Changes since you last looked."),
+    ("health", "Laufzeit und Ausfälle des Bots.", "Bot uptime and downtime."),
+    ("undo", "Letzte Schwellwert-Änderung zurücknehmen.", "Undo the last threshold change."),
+    ("api-token", "Token für den Kalender-Link.", "Token for the calendar link."),
+    ("note", "Notiz zu einem Gerät.", "Note for a device."),
+    ("backup-now", "Sofort eine Sicherung anlegen.", "Create a backup now."),
+    ("records", "Tiefst- und Höchstwerte, optional nur für einen Raum.", "Lowest and highest values, optionally for one room."),
+    ("debug", "Interner Zustand und Zustellung, optional zu einem Haushalt.", "Internal state and delivery details, optionally for one household."),
+    ("setimage", "Raumbild festlegen.", "Set a room picture."),
+    ("room-images", "Raumbilder bei Warnungen und /status.", "Room pictures with alerts and /status."),
+    ("profile", "Schwellen-Profile verwalten.", "Manage threshold profiles."),
+    ("escalate-to", "Unbestätigte Warnungen weitergeben.", "Hand unacknowledged alerts to another chat."),
+    ("accept-escalations-from", "Weitergegebene Warnungen annehmen.", "Accept alerts handed over from a chat."),
+    ("escalation", "Weitergabe anzeigen oder beenden.", "Show or stop alert hand-over."),
+    ("inject", "Testwert einspeisen.", "Inject a test reading."),
+    ("test-alarm", "Probealarm mit Entwarnung.", "Send a test alarm and recovery."),
+    ("clear", "Alle Einstellungen löschen.", "Delete all settings."),
+    ("undo-clear", "Gelöschte Einstellungen wiederherstellen.", "Restore deleted settings."),
+    ("forgetme", "Alle Daten endgültig löschen.", "Permanently delete all data."),
+    ("backup", "Einstellungen als Datei sichern.", "Save settings as a file."),
+    ("restore", "Einstellungen aus einer Sicherungsdatei übernehmen.", "Restore settings from a backup file."),
+    ("fleet-report", "Wöchentlicher Geräte-Bericht.", "Weekly sensor fleet report."),
+    ("set-interval", "Abfrageintervall ändern.", "Change the polling interval."),
+    ("purge-user", "Daten eines Chats löschen.", "Delete a chat's data."),
+    ("reload-rooms", "Raumdatei neu einlesen.", "Reload the rooms file."),
+    ("selftest", "Selbsttest ausführen.", "Run a self-test."),
+    ("weather-location", "Standort für das Wetter.", "Location for outdoor weather."),
+    ("ignore", "Gerät ignorieren.", "Ignore a device."),
+    ("unignore", "Gerät wieder überwachen.", "Monitor a device again."),
+    ("ignored", "Ignorierte Geräte.", "Ignored devices."),
+    ("allow", "Chat freischalten.", "Allow a chat."),
+    ("deny", "Chat sperren.", "Deny a chat."),
+    ("broadcast", "Nachricht an alle Chats.", "Message all chats."),
+    ("users", "Bekannte Chats anzeigen.", "List known chats."),
+    ("snooze", "Raum stummschalten.", "Snooze a room."),
+    ("snoozes", "Aktive Stummschaltungen.", "Active snoozes."),
+    ("clear-threshold", "Eine Schwelle entfernen.", "Remove one threshold."),
+    ("clear-all", "Alle Schwellwerte entfernen.", "Remove all thresholds."),
+    ("configure", "Schwelle per Tastatur einrichten.", "Set up a threshold step by step."),
+    ("hysteresis", "Hysterese einer Schwelle.", "Hysteresis of a threshold."),
+    ("repeat", "Warnung wiederholen.", "Repeat a warning."),
+    ("rate", "Alarm bei schneller Änderung.", "Alert on rapid change."),
+    ("set", "Schwelle relativ zu einem anderen Sensor.", "Threshold relative to another sensor."),
+    ("language", "Sprache der Antworten: de oder en.", "Reply language: de or en."),
+    ("battery", "Warnung bei schwacher Batterie.", "Low-battery warning."),
+    ("units", "Temperatureinheit: celsius oder fahrenheit.", "Temperature unit: celsius or fahrenheit."),
+    ("timezone", "Zeitzone für Uhrzeiten, z.B. Europe/Berlin.", "Time zone for displayed times, e.g. Europe/Berlin."),
+    ("watering", "Gießerinnerung für Räume im Freien.", "Watering reminder for outdoor rooms."),
+];
+
+pub fn command_description(lang: Lang, command: &str) -> Option<&'static str> {
+    COMMAND_DESCRIPTIONS.iter().find(|(name, _, _)| *name == command).map(|(_, de, en)| match lang {
+        Lang::De => *de,
+        Lang::En => *en,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(text: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = text.split('{').skip(1).filter_map(|rest| rest.split_once('}')).map(|(name, _)| name).collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    #[test]
+    fn both_languages_have_the_same_placeholders() {
+        for (key, de, en) in MESSAGES {
+            assert_eq!(placeholders(de), placeholders(en), "{}", key);
+        }
+    }
+
+    #[test]
+    fn keys_are_unique() {
+        for (index, (key, _, _)) in MESSAGES.iter().enumerate() {
+            assert!(!MESSAGES[..index].iter().any(|(other, _, _)| other == key), "{} twice", key);
+        }
+    }
+
+    #[test]
+    fn placeholders_are_replaced() {
+        let text = message_with(Lang::En, "fresh", &[("room", "Bad"), ("gap", "2 h")]);
+        assert_eq!(text, "📡 Bad is sending data again (gap: 2 h).");
+        assert_eq!(message(Lang::De, "missing"), "");
+    }
+
+    #[test]
+    fn text_gets_its_language_when_shown() {
+        let text = Text::new("threshold_args_not_a_number", &[("value", "warm")]).and(Text::new("layout_usage", &[]));
+        assert_eq!(text.in_lang(Lang::En), "'warm' is not a number. Usage: /format compact|table");
+        assert_eq!(text.to_string(), "'warm' ist keine Zahl. Verwendung: /format compact|table");
+        let text = Text::new("refresh_not_running", &[]).and(Text::verbatim("(Forbidden)"));
+        assert_eq!(text.in_lang(Lang::En), "monitoring is not running (Forbidden)");
+    }
+}
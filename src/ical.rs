@@ -0,0 +1,314 @@
+use crate::UserConfig;
+use crate::i18n;
+use crate::schedule::weekday_name;
+use crate::timeutil;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc, Weekday};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+use std::collections::BTreeMap;
+
+// Dauer, mit der ein geplanter Bericht im Kalender erscheint
+const REPORT_MINUTES: i64 = 5;
+
+// So viele Jahre ab heute stehen die Zeitumstellungen im VTIMEZONE
+const TIMEZONE_YEARS: i64 = 5;
+
+fn byday(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+// Text nach RFC 5545 maskieren
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+// Zeilen über 75 Byte werden umbrochen, Fortsetzung beginnt mit Leerzeichen
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
+
+fn utc(dt: DateTime<Utc>) -> String {
+    dt.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// Abstand zu UTC wie in TZOFFSETFROM/TZOFFSETTO (+0100, -0930, +053328)
+fn utc_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.unsigned_abs();
+    let hhmm = format!("{}{:02}{:02}", sign, seconds / 3600, seconds / 60 % 60);
+    if seconds.is_multiple_of(60) { hhmm } else { format!("{}{:02}", hhmm, seconds % 60) }
+}
+
+// Observance ab `at` (UTC), DTSTART in der bis dahin geltenden Ortszeit
+fn observance(tz: Tz, at: DateTime<Utc>, from: i32) -> Vec<String> {
+    let offset = tz.offset_from_utc_datetime(&at.naive_utc());
+    let kind = if offset.dst_offset().is_zero() { "STANDARD" } else { "DAYLIGHT" };
+    let mut lines = vec![
+        format!("BEGIN:{}", kind),
+        format!("DTSTART:{}", (at.naive_utc() + Duration::seconds(from.into())).format("%Y%m%dT%H%M%S")),
+        format!("TZOFFSETFROM:{}", utc_offset(from)),
+        format!("TZOFFSETTO:{}", utc_offset(offset.fix().local_minus_utc())),
+    ];
+    lines.extend(offset.abbreviation().map(|name| format!("TZNAME:{}", escape(name))));
+    lines.push(format!("END:{}", kind));
+    lines
+}
+
+// VTIMEZONE zur TZID der Termine aus den Daten von chrono-tz: der Stand
+// von vor einem Jahr und danach jede Zeitumstellung der nächsten Jahre mit
+// festem DTSTART. Gesucht wird stundenweise, die Umstellung selbst dann
+// auf die Minute genau.
+fn vtimezone(tz: Tz, now: DateTime<Utc>) -> Vec<String> {
+    let offset = |at: DateTime<Utc>| {
+        let offset = tz.offset_from_utc_datetime(&at.naive_utc());
+        (offset.fix().local_minus_utc(), offset.dst_offset().is_zero())
+    };
+    let start = now - Duration::days(366);
+    let start = start - Duration::seconds(start.timestamp().rem_euclid(3600));
+    let mut lines = vec!["BEGIN:VTIMEZONE".to_string(), format!("TZID:{}", tz.name())];
+    lines.extend(observance(tz, start, offset(start).0));
+
+    let mut at = start;
+    while at < now + Duration::days(365 * TIMEZONE_YEARS) {
+        let before = offset(at);
+        let next = at + Duration::hours(1);
+        if offset(next) != before {
+            let change = (1..=60).map(|minute| at + Duration::minutes(minute)).find(|t| offset(*t) != before).unwrap_or(next);
+            lines.extend(observance(tz, change, before.0));
+        }
+        at = next;
+    }
+    lines.push("END:VTIMEZONE".to_string());
+    lines
+}
+
+// Kalender eines Chats: geplante Berichte und Ruhezeit als Serien,
+// eine laufende Stummschaltung als einzelner Termin.
+// Wiederholungen stehen in Ortszeit (TZID mit passendem VTIMEZONE), damit
+// sie über die Zeitumstellung hinweg zur gleichen Uhrzeit bleiben. Ohne
+// Zeitzone entstehen "schwebende" Zeiten in der Zeitzone des Kalenders.
+pub fn render(chat_id: i64, config: &UserConfig, tz: Option<Tz>, now: DateTime<Utc>) -> String {
+    let today = timeutil::local_date_of(now, tz);
+    let dtstamp = utc(now);
+    let local = |name: &str, at: NaiveDateTime| match tz {
+        Some(tz) => format!("{};TZID={}:{}", name, tz.name(), at.format("%Y%m%dT%H%M%S")),
+        None => format!("{}:{}", name, at.format("%Y%m%dT%H%M%S")),
+    };
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//TelegramBot//Sensor-Kalender//DE".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape("Sensor-Bot")),
+    ];
+    if let Some(tz) = tz {
+        lines.push(format!("X-WR-TIMEZONE:{}", tz.name()));
+        lines.extend(vtimezone(tz, now));
+    }
+
+    // Je Uhrzeit ein Termin mit allen Wochentagen, an denen sie vorkommt
+    if let Some(schedule) = &config.report_schedule {
+        let mut by_time: BTreeMap<NaiveTime, Vec<Weekday>> = BTreeMap::new();
+        for (day, times) in schedule.weekly_plan() {
+            for time in times {
+                by_time.entry(*time).or_default().push(day);
+            }
+        }
+        for (time, days) in by_time {
+            let first = next_date(today, &days);
+            let rule = if days.len() == 7 {
+                "RRULE:FREQ=DAILY".to_string()
+            } else {
+                format!("RRULE:FREQ=WEEKLY;BYDAY={}", days.iter().map(|d| byday(*d)).collect::<Vec<_>>().join(","))
+            };
+            let tage = days.iter().map(|d| weekday_name(*d, config.lang)).collect::<Vec<_>>().join(", ");
+            lines.extend([
+                "BEGIN:VEVENT".to_string(),
+                format!("UID:report-{}-{}@telegrambot", time.format("%H%M"), chat_id),
+                format!("DTSTAMP:{}", dtstamp),
+                local("DTSTART", first.and_time(time)),
+                format!("DURATION:PT{}M", REPORT_MINUTES),
+                rule,
+                format!("SUMMARY:{}", escape(i18n::message(config.lang, "ical_report"))),
+                format!("DESCRIPTION:{}", escape(&i18n::message_with(config.lang, "ical_report_description", &[("days", &tage)]))),
+                "END:VEVENT".to_string(),
+            ]);
+        }
+    }
+
+    if let Some(window) = config.quiet_hours {
+        let mut minutes = (window.end - window.start).num_minutes();
+        if minutes <= 0 {
+            minutes += 24 * 60; // über Mitternacht
+        }
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:quiet-hours-{}@telegrambot", chat_id),
+            format!("DTSTAMP:{}", dtstamp),
+            local("DTSTART", today.and_time(window.start)),
+            format!("DURATION:PT{}M", minutes),
+            "RRULE:FREQ=DAILY".to_string(),
+            format!("SUMMARY:{}", escape(i18n::message(config.lang, "ical_quiet"))),
+            format!("DESCRIPTION:{}", escape(i18n::message(config.lang, "ical_quiet_description"))),
+            "TRANSP:TRANSPARENT".to_string(),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+
+    if let Some(until) = config.muted_until.filter(|until| *until > now) {
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:mute-{}-{}@telegrambot", until.timestamp(), chat_id),
+            format!("DTSTAMP:{}", dtstamp),
+            format!("DTSTART:{}", dtstamp),
+            format!("DTEND:{}", utc(until)),
+            format!("SUMMARY:{}", escape(i18n::message(config.lang, "ical_muted"))),
+            format!("DESCRIPTION:{}", escape(i18n::message(config.lang, "ical_muted_description"))),
+            "TRANSP:TRANSPARENT".to_string(),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line) + "\r\n").collect()
+}
+
+// Erster Tag ab heute, der zu einem der Wochentage passt
+fn next_date(today: NaiveDate, days: &[Weekday]) -> NaiveDate {
+    (0..7).map(|offset| today + Duration::days(offset)).find(|date| days.contains(&date.weekday())).unwrap_or(today)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thresholds::TimeWindow;
+    use icalendar::parser::{Component, read_calendar, unfold};
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)
+    }
+
+    fn config() -> UserConfig {
+        UserConfig {
+            report_schedule: Some("mo-fr 07:30; sa,so 09:00".parse().unwrap()),
+            quiet_hours: Some(TimeWindow { start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(), end: NaiveTime::from_hms_opt(7, 0, 0).unwrap() }),
+            muted_until: Some(at("2026-10-14T18:00:00Z")),
+            ..UserConfig::default()
+        }
+    }
+
+    fn value<'a>(component: &'a Component, name: &str) -> Option<&'a str> {
+        component.properties.iter().find(|p| p.name == name).map(|p| p.val.as_str())
+    }
+
+    fn seconds(offset: &str) -> i32 {
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let digits: Vec<i32> = offset.as_bytes()[1..].chunks(2).map(|pair| std::str::from_utf8(pair).unwrap().parse().unwrap()).collect();
+        sign * (digits[0] * 3600 + digits[1] * 60 + digits.get(2).unwrap_or(&0))
+    }
+
+    // Abstand zu UTC für eine Ortszeit laut VTIMEZONE: TZOFFSETTO der letzten
+    // Observance, die davor beginnt
+    fn offset_at(timezone: &Component, local: NaiveDateTime) -> i32 {
+        let starts = timezone.components.iter().map(|observance| {
+            let start = NaiveDateTime::parse_from_str(value(observance, "DTSTART").unwrap(), "%Y%m%dT%H%M%S").unwrap();
+            (start, seconds(value(observance, "TZOFFSETTO").unwrap()))
+        });
+        starts.filter(|(start, _)| *start <= local).max_by_key(|(start, _)| *start).expect("keine Observance vor dem Termin").1
+    }
+
+    #[test]
+    fn calendar_with_timezone_parses_and_defines_every_tzid() {
+        let ics = render(42, &config(), Some(chrono_tz::Europe::Berlin), at("2026-10-14T10:00:00Z"));
+        assert!(ics.lines().all(|line| line.len() <= 76), "{}", ics);
+        let unfolded = unfold(&ics);
+        let calendar = read_calendar(&unfolded).unwrap();
+
+        let timezones: Vec<&Component> = calendar.components.iter().filter(|c| c.name == "VTIMEZONE").collect();
+        assert_eq!(timezones.len(), 1);
+        assert_eq!(value(timezones[0], "TZID"), Some("Europe/Berlin"));
+        let events: Vec<&Component> = calendar.components.iter().filter(|c| c.name == "VEVENT").collect();
+        assert_eq!(events.len(), 4);
+        for event in events {
+            let start = event.properties.iter().find(|p| p.name == "DTSTART").unwrap();
+            match start.params.iter().find(|param| param.key == "TZID") {
+                Some(tzid) => assert_eq!(tzid.val.as_ref().map(|v| v.as_str()), Some("Europe/Berlin")),
+                None => assert!(start.val.as_str().ends_with('Z'), "{}", start.val.as_str()),
+            }
+        }
+    }
+
+    #[test]
+    fn timezone_offsets_match_chrono_tz() {
+        let now = at("2026-10-14T10:00:00Z");
+        for tz in [chrono_tz::Europe::Berlin, chrono_tz::America::New_York, chrono_tz::Australia::Lord_Howe, chrono_tz::Asia::Kolkata] {
+            let ics = render(42, &config(), Some(tz), now);
+            let unfolded = unfold(&ics);
+            let calendar = read_calendar(&unfolded).unwrap();
+            let timezone = calendar.components.iter().find(|c| c.name == "VTIMEZONE").unwrap();
+            assert_eq!(value(timezone, "TZID"), Some(tz.name()));
+
+            // Jeden Tag der nächsten Jahre um 07:30 und 22:00 Ortszeit
+            for day in 0..365 * TIMEZONE_YEARS {
+                for time in [NaiveTime::from_hms_opt(7, 30, 0).unwrap(), NaiveTime::from_hms_opt(22, 0, 0).unwrap()] {
+                    let local = (now.date_naive() + Duration::days(day)).and_time(time);
+                    let expected = tz.from_local_datetime(&local).earliest().unwrap().offset().fix().local_minus_utc();
+                    assert_eq!(offset_at(timezone, local), expected, "{} {}", tz.name(), local);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn berlin_changes_on_the_last_sundays_of_march_and_october() {
+        let ics = unfold(&render(42, &config(), Some(chrono_tz::Europe::Berlin), at("2026-10-14T10:00:00Z")));
+        assert!(ics.contains("BEGIN:DAYLIGHT\r\nDTSTART:20270328T020000\r\nTZOFFSETFROM:+0100\r\nTZOFFSETTO:+0200\r\nTZNAME:CEST\r\n"), "{}", ics);
+        assert!(ics.contains("BEGIN:STANDARD\r\nDTSTART:20261025T030000\r\nTZOFFSETFROM:+0200\r\nTZOFFSETTO:+0100\r\nTZNAME:CET\r\n"), "{}", ics);
+    }
+
+    #[test]
+    fn zone_without_dst_has_a_single_observance() {
+        let ics = render(42, &config(), Some(chrono_tz::Asia::Tokyo), at("2026-10-14T10:00:00Z"));
+        let unfolded = unfold(&ics);
+        let calendar = read_calendar(&unfolded).unwrap();
+        let timezone = calendar.components.iter().find(|c| c.name == "VTIMEZONE").unwrap();
+        assert_eq!(timezone.components.len(), 1);
+        assert_eq!(value(&timezone.components[0], "TZOFFSETTO"), Some("+0900"));
+    }
+
+    #[test]
+    fn without_timezone_times_float_and_nothing_refers_to_a_tzid() {
+        let ics = render(42, &config(), None, at("2026-10-14T10:00:00Z"));
+        let unfolded = unfold(&ics);
+        let calendar = read_calendar(&unfolded).unwrap();
+        assert!(calendar.components.iter().all(|c| c.name != "VTIMEZONE"));
+        assert!(!ics.contains("TZID"));
+        let mute = calendar.components.iter().find(|c| value(c, "UID").is_some_and(|uid| uid.starts_with("mute-"))).unwrap();
+        assert_eq!(value(mute, "DTEND"), Some("20261014T180000Z"));
+    }
+
+    #[test]
+    fn offsets_with_seconds_keep_them() {
+        assert_eq!(utc_offset(3600), "+0100");
+        assert_eq!(utc_offset(-9 * 3600 - 30 * 60), "-0930");
+        assert_eq!(utc_offset(5 * 3600 + 53 * 60 + 28), "+055328");
+    }
+}
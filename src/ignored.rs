@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Wann und von wem ein Gerät ignoriert wurde
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IgnoredDevice {
+    pub since: DateTime<Utc>,
+    pub by: String, // Name oder Chat-ID des Admins
+}
+
+// Geräte, deren Messwerte der Bot gleich nach dem Abruf verwirft
+// (/ignore): kein Status, kein Verlauf, keine Warnungen.
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct IgnoreList {
+    devices: BTreeMap<String, IgnoredDevice>,
+}
+
+impl IgnoreList {
+    pub fn contains(&self, device_id: &str) -> bool {
+        self.devices.contains_key(device_id)
+    }
+
+    // false, wenn das Gerät schon ignoriert wird
+    pub fn insert(&mut self, device_id: &str, by: String, now: DateTime<Utc>) -> bool {
+        if self.contains(device_id) {
+            return false;
+        }
+        self.devices.insert(device_id.to_string(), IgnoredDevice { since: now, by });
+        true
+    }
+
+    pub fn remove(&mut self, device_id: &str) -> bool {
+        self.devices.remove(device_id).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &IgnoredDevice)> {
+        self.devices.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+}
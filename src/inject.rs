@@ -0,0 +1,85 @@
+use crate::SensorData;
+use crate::i18n::Text;
+use crate::sensor::SensorKind;
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+// Testwert für die Überwachung: läuft durch dieselbe Auswertung wie echte
+// Messwerte, mit `store = false` ohne Verlauf und Rekorde
+#[derive(Debug, Clone)]
+pub struct Injection {
+    pub reading: SensorData,
+    pub store: bool,
+}
+
+static QUEUE: Mutex<Vec<Injection>> = Mutex::new(Vec::new());
+static WAKE: Notify = Notify::const_new();
+
+// `age` liest Angaben wie "2h"; ohne Alter gilt `now`
+pub fn parse(args: &str, now: i64, age: impl Fn(&str) -> Option<chrono::Duration>) -> Result<Injection, Text> {
+    let mut store = true;
+    let mut parts = Vec::new();
+    for part in args.split_whitespace() {
+        match part {
+            "--no-store" => store = false,
+            _ => parts.push(part),
+        }
+    }
+    let (device_id, sensor_type, value, timestamp) = match parts.as_slice() {
+        [device_id, sensor_type, value] => (device_id, sensor_type, value, now),
+        [device_id, sensor_type, value, ago] => {
+            let ago = age(ago).ok_or_else(|| Text::new("inject_invalid_age", &[("age", ago)]))?;
+            (device_id, sensor_type, value, now - ago.num_seconds())
+        }
+        _ => return Err(Text::new("inject_usage", &[])),
+    };
+    let value: f64 =
+        value.replace(',', ".").parse().ok().filter(|v: &f64| v.is_finite()).ok_or_else(|| Text::new("threshold_args_not_a_number", &[("value", value)]))?;
+    let reading = SensorData { device_id: device_id.to_string(), sensor_type: SensorKind::from(*sensor_type), value, timestamp };
+    Ok(Injection { reading, store })
+}
+
+// Für den nächsten Durchlauf vormerken und die Überwachung wecken
+pub fn push(injection: Injection) {
+    QUEUE.lock().unwrap_or_else(|e| e.into_inner()).push(injection);
+    WAKE.notify_one();
+}
+
+pub fn take() -> Vec<Injection> {
+    std::mem::take(&mut *QUEUE.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+// Wartet, bis ein Testwert vorgemerkt wird
+pub async fn notified() {
+    WAKE.notified().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hours(text: &str) -> Option<chrono::Duration> {
+        text.strip_suffix('h')?.parse().ok().map(chrono::Duration::hours)
+    }
+
+    #[test]
+    fn reading_with_age_and_without_storing() {
+        let injection = parse("sensor1 temperature 26,5 2h --no-store", 10_000, hours).unwrap();
+        assert!(!injection.store);
+        assert_eq!(injection.reading.value, 26.5);
+        assert_eq!(injection.reading.timestamp, 10_000 - 7_200);
+        assert_eq!(injection.reading.sensor_type, SensorKind::Temperature);
+
+        let now = parse("sensor1 humidity 80", 10_000, hours).unwrap();
+        assert!(now.store);
+        assert_eq!(now.reading.timestamp, 10_000);
+    }
+
+    #[test]
+    fn wrong_arguments_are_explained() {
+        assert_eq!(parse("sensor1 temperature", 0, hours).unwrap_err(), Text::new("inject_usage", &[]));
+        assert_eq!(parse("sensor1 temperature warm", 0, hours).unwrap_err(), Text::new("threshold_args_not_a_number", &[("value", "warm")]));
+        assert_eq!(parse("sensor1 temperature inf", 0, hours).unwrap_err(), Text::new("threshold_args_not_a_number", &[("value", "inf")]));
+        assert_eq!(parse("sensor1 temperature 20 gestern", 0, hours).unwrap_err(), Text::new("inject_invalid_age", &[("age", "gestern")]));
+    }
+}
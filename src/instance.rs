@@ -0,0 +1,47 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+// Exklusive Sperre gegen eine zweite laufende Bot-Instanz.
+// Die Sperre ist ein Advisory-Lock (flock) auf der Lock-Datei: das Betriebssystem
+// gibt sie beim Prozessende frei, ein Absturz hinterlässt also keine Leiche.
+// Die PID in der Datei dient nur der Diagnose.
+pub struct InstanceLock {
+    file: File,
+}
+
+pub enum LockError {
+    // Eine andere Instanz hält die Sperre (PID, soweit lesbar)
+    Held(Option<u32>),
+    Io(io::Error),
+}
+
+impl InstanceLock {
+    pub fn acquire(path: &Path) -> Result<InstanceLock, LockError> {
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path).map_err(LockError::Io)?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut content = String::new();
+                let pid = file.read_to_string(&mut content).ok().and_then(|_| content.trim().parse().ok());
+                return Err(LockError::Held(pid));
+            }
+            Err(TryLockError::Error(err)) => return Err(LockError::Io(err)),
+        }
+
+        file.set_len(0).map_err(LockError::Io)?;
+        file.seek(SeekFrom::Start(0)).map_err(LockError::Io)?;
+        write!(file, "{}", std::process::id()).map_err(LockError::Io)?;
+        file.flush().map_err(LockError::Io)?;
+
+        Ok(InstanceLock { file })
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let _ = self.file.set_len(0);
+        let _ = self.file.unlock();
+    }
+}
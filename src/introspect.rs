@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+// Innerer Zustand des laufenden Bots für /debug. Was ein Teilsystem nicht
+// liefern kann (keine Datenbank, kein Linux), bleibt None und wird als
+// "–" angezeigt.
+#[derive(Debug, Default)]
+pub struct DebugSnapshot {
+    pub user_configs: usize,
+    pub threshold_flags: usize,
+    pub history: Vec<(String, usize)>, // Einträge je Gerät, sortiert
+    pub queued: Option<usize>,         // Nachrichten in der Warteschlange
+    pub files: Vec<(String, Option<u64>)>,
+    pub rss_kib: Option<u64>,
+    pub last_iteration: Option<Duration>,
+}
+
+impl DebugSnapshot {
+    // Als Block in Festbreitenschrift (Legacy-Markdown)
+    pub fn format(&self) -> String {
+        let or_dash = |value: Option<String>| value.unwrap_or_else(|| "–".to_string());
+        let mut lines = vec![
+            ("Konfigurationen".to_string(), self.user_configs.to_string()),
+            ("Alarm-Zustände".to_string(), self.threshold_flags.to_string()),
+            ("Warteschlange".to_string(), or_dash(self.queued.map(|n| n.to_string()))),
+            ("Speicher (RSS)".to_string(), or_dash(self.rss_kib.map(|kib| size(kib * 1024)))),
+            ("Letzter Durchlauf".to_string(), or_dash(self.last_iteration.map(|d| format!("{} ms", d.as_millis())))),
+        ];
+        lines.push(("Verlauf".to_string(), format!("{} Einträge", self.history.iter().map(|(_, n)| n).sum::<usize>())));
+        lines.extend(self.history.iter().map(|(device, n)| (format!("  {}", device), n.to_string())));
+        if !self.files.is_empty() {
+            lines.push(("Dateien".to_string(), String::new()));
+            lines.extend(self.files.iter().map(|(name, bytes)| (format!("  {}", name), or_dash(bytes.map(size)))));
+        }
+        let width = lines.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+        let body: Vec<String> =
+            lines.iter().map(|(label, value)| format!("{}{}  {}", label, " ".repeat(width - label.chars().count()), value).trim_end().to_string()).collect();
+        format!("```\n{}\n```", body.join("\n").replace('`', "'"))
+    }
+}
+
+fn size(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MiB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.1} KiB", b as f64 / 1024.0),
+        b => format!("{} B", b),
+    }
+}
+
+// Belegter Arbeitsspeicher des Prozesses (VmRSS) in KiB
+#[cfg(target_os = "linux")]
+pub fn rss_kib() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn rss_kib() -> Option<u64> {
+    None
+}
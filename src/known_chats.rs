@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnownChat {
+    pub since: DateTime<Utc>, // erster Befehl, bei älteren Chats der Zeitpunkt der Übernahme
+    #[serde(default)]
+    pub last_command: Option<DateTime<Utc>>, // None bei Chats von vor dieser Liste, bis sie wieder einen Befehl schicken
+}
+
+// Alle Chats, die dem Bot je einen Befehl geschickt haben (/broadcast,
+// /users). /forgetme und /purge-user entfernen den Eintrag.
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct KnownChats {
+    chats: BTreeMap<i64, KnownChat>,
+}
+
+impl KnownChats {
+    pub fn command(&mut self, chat_id: i64, now: DateTime<Utc>) {
+        self.chats.entry(chat_id).or_insert(KnownChat { since: now, last_command: None }).last_command = Some(now);
+    }
+
+    // Chat mit gespeicherter Konfiguration übernehmen; true, wenn er neu ist
+    pub fn adopt(&mut self, chat_id: i64, now: DateTime<Utc>) -> bool {
+        if self.chats.contains_key(&chat_id) {
+            return false;
+        }
+        self.chats.insert(chat_id, KnownChat { since: now, last_command: None });
+        true
+    }
+
+    pub fn forget(&mut self, chat_id: i64) -> bool {
+        self.chats.remove(&chat_id).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&i64, &KnownChat)> {
+        self.chats.iter()
+    }
+}
@@ -0,0 +1,208 @@
+use crate::i18n::{Lang, Text};
+use crate::quantities;
+use crate::timeformat::TimeFormat;
+use crate::units::TempUnit;
+use crate::{SensorData, format_reading_time, room_name, type_label, unit_in};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use unicode_width::UnicodeWidthStr;
+
+// Breiter wird die Tabelle nicht, sonst bricht sie auf dem Handy um
+// und /status fällt auf die klassische Darstellung zurück
+const MAX_TABLE_WIDTH: usize = 40;
+const COLUMN_GAP: &str = "  ";
+
+// Darstellung von /status je Benutzer (/layout bzw. /format)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+pub enum Layout {
+    #[default]
+    Classic,
+    Table,
+}
+
+impl FromStr for Layout {
+    type Err = Text;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "classic" | "compact" | "kompakt" => Ok(Layout::Classic),
+            "table" | "tabelle" => Ok(Layout::Table),
+            _ => Err(Text::new("layout_usage", &[])),
+        }
+    }
+}
+
+// Zeichen, die im HTML-Modus von Telegram maskiert werden müssen
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// Kurze Spaltenköpfe, damit die Tabelle aufs Handy passt
+fn column_label(sensor_type: &str, units: TempUnit) -> String {
+    let label = type_label(sensor_type).0;
+    let unit = unit_in(units, sensor_type);
+    let label = quantities::lookup(sensor_type).and_then(|quantity| quantity.short).unwrap_or(label);
+    if unit.is_empty() { label.to_string() } else { format!("{} {}", label, unit) }
+}
+
+// Alter des neuesten Messwerts eines Raums, so kurz wie möglich
+fn short_age(seconds: i64) -> String {
+    match seconds.max(0) {
+        s if s < 60 * 60 => format!("{}m", s / 60),
+        s if s < 24 * 60 * 60 => format!("{}h", s / (60 * 60)),
+        s => format!("{}d", s / (24 * 60 * 60)),
+    }
+}
+
+// Auf Anzeigebreite auffüllen: Emoji zählen doppelt, Umlaute einfach
+fn pad(text: &str, width: usize, right: bool) -> String {
+    let fill = " ".repeat(width.saturating_sub(text.width()));
+    if right { format!("{}{}", fill, text) } else { format!("{}{}", text, fill) }
+}
+
+// Räume × Messgrößen als ausgerichtetes Raster (HTML, <pre>), dazu das Alter
+// des neuesten Werts je Raum. None, wenn die Tabelle zu breit würde.
+pub fn format_status_table(sensor_data: &[SensorData], units: TempUnit, tz: Option<Tz>, times: TimeFormat, now: i64) -> Option<String> {
+    // Reihenfolge wie von der Sensorliste geliefert
+    let mut devices: Vec<&str> = Vec::new();
+    let mut types: Vec<&str> = Vec::new();
+    for entry in sensor_data {
+        if !devices.contains(&entry.device_id.as_str()) {
+            devices.push(&entry.device_id);
+        }
+        if !types.contains(&entry.sensor_type.as_str()) {
+            types.push(entry.sensor_type.as_str());
+        }
+    }
+
+    let mut header = vec!["Raum".to_string()];
+    header.extend(types.iter().map(|typ| column_label(typ, units)));
+    header.push("Alter".to_string());
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for device in &devices {
+        let mut row = vec![room_name(device).to_string()];
+        for typ in &types {
+            let value = sensor_data.iter().find(|e| e.device_id == *device && e.sensor_type.as_str() == *typ);
+            let decimals = quantities::lookup(typ).map_or(1, |quantity| quantity.decimals);
+            row.push(value.map(|e| format!("{:.*}", decimals, units.show(&e.sensor_type, e.value))).unwrap_or_else(|| "–".into()));
+        }
+        let newest = sensor_data.iter().filter(|e| e.device_id == *device).map(|e| e.timestamp).max().unwrap_or(now);
+        row.push(short_age(now - newest));
+        rows.push(row);
+    }
+
+    let widths: Vec<usize> = (0..header.len()).map(|col| rows.iter().chain([&header]).map(|row| row[col].width()).max().unwrap_or(0)).collect();
+    let total = widths.iter().sum::<usize>() + COLUMN_GAP.len() * widths.len().saturating_sub(1);
+    if total > MAX_TABLE_WIDTH {
+        return None;
+    }
+
+    let line = |row: &[String]| {
+        row.iter().zip(&widths).enumerate().map(|(col, (cell, width))| pad(cell, *width, col > 0)).collect::<Vec<_>>().join(COLUMN_GAP).trim_end().to_string()
+    };
+
+    let mut table = line(&header);
+    table.push('\n');
+    table.push_str(&"─".repeat(total));
+    for row in &rows {
+        table.push('\n');
+        table.push_str(&line(row));
+    }
+
+    let newest = sensor_data.iter().map(|e| e.timestamp).max().unwrap_or_default();
+    Some(format!(
+        "📊 <b>Aktuelle Sensordaten</b> ({})\n<pre>{}</pre>",
+        escape_html(&format_reading_time(newest, "%d.%m.%Y %H:%M", tz, times, Lang::De)),
+        escape_html(&table)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::SensorKind;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn reading(device: &str, kind: SensorKind, value: f64, age: i64) -> SensorData {
+        SensorData { device_id: device.into(), sensor_type: kind, value, timestamp: NOW - age }
+    }
+
+    // Nur die Tabelle im <pre>-Block
+    fn grid(sensor_data: &[SensorData]) -> String {
+        let html = format_status_table(sensor_data, TempUnit::Celsius, Some(chrono_tz::UTC), TimeFormat::Absolute, NOW).unwrap();
+        let (_, pre) = html.split_once("<pre>").unwrap();
+        pre.trim_end_matches("</pre>").to_string()
+    }
+
+    #[test]
+    fn two_rooms() {
+        let readings = [
+            reading("sensor1", SensorKind::Temperature, 21.44, 60),
+            reading("sensor1", SensorKind::Humidity, 48.0, 60),
+            reading("bad", SensorKind::Temperature, 23.0, 3 * 60 * 60),
+        ];
+        let html = format_status_table(&readings, TempUnit::Celsius, Some(chrono_tz::UTC), TimeFormat::Absolute, NOW).unwrap();
+        assert!(html.starts_with("📊 <b>Aktuelle Sensordaten</b> (14.11.2023 22:12)\n<pre>"), "{}", html);
+        assert_eq!(
+            grid(&readings),
+            "Raum        Temp. °C  Feuchte %  Alter\n\
+             ──────────────────────────────────────\n\
+             Wohnzimmer      21.4       48.0     1m\n\
+             bad             23.0          –     3h"
+        );
+    }
+
+    #[test]
+    fn five_rooms() {
+        let readings: Vec<SensorData> = [("sensor1", 21.0), ("bad", 23.5), ("kueche", 19.25), ("flur", 18.0), ("keller", 12.5)]
+            .into_iter()
+            .enumerate()
+            .map(|(n, (device, value))| reading(device, SensorKind::Temperature, value, n as i64 * 20 * 60))
+            .collect();
+        assert_eq!(
+            grid(&readings),
+            "Raum        Temp. °C  Alter\n\
+             ───────────────────────────\n\
+             Wohnzimmer      21.0     0m\n\
+             bad             23.5    20m\n\
+             kueche          19.2    40m\n\
+             flur            18.0     1h\n\
+             keller          12.5     1h"
+        );
+    }
+
+    #[test]
+    fn too_wide_table_falls_back() {
+        let readings =
+            [reading("sensor1", SensorKind::Temperature, 21.0, 0), reading("ein_sehr_langer_geraetename_im_keller", SensorKind::Temperature, 12.0, 0)];
+        assert_eq!(format_status_table(&readings, TempUnit::Celsius, None, TimeFormat::Absolute, NOW), None);
+    }
+
+    // Umlaute zählen einfach, Emoji doppelt; Werte und Alter rechtsbündig
+    #[test]
+    fn columns_align_with_mixed_width_names_and_units() {
+        let readings = [
+            reading("küche", SensorKind::Temperature, 21.0, 5 * 60),
+            reading("küche", SensorKind::Co2, 1250.0, 5 * 60),
+            reading("🛁 bad", SensorKind::Temperature, 24.5, 12 * 60 * 60),
+            reading("sensor1", SensorKind::Co2, 480.0, 3 * 24 * 60 * 60),
+        ];
+        let html = format_status_table(&readings, TempUnit::Fahrenheit, None, TimeFormat::Absolute, NOW).unwrap();
+        let (_, pre) = html.split_once("<pre>").unwrap();
+        let table = pre.trim_end_matches("</pre>");
+        let widths: Vec<usize> = table.lines().map(|line| line.width()).collect();
+        assert!(widths.iter().all(|width| *width == widths[0]), "{:?}\n{}", widths, table);
+        assert_eq!(
+            table,
+            "Raum        Temp. °F  CO₂ ppm  Alter\n\
+             ────────────────────────────────────\n\
+             küche           69.8   1250.0     5m\n\
+             🛁 bad          76.1        –    12h\n\
+             Wohnzimmer         –    480.0     3d"
+        );
+    }
+}
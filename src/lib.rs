@@ -0,0 +1,4117 @@
+//! Sensor-Bot für Telegram: Überwachung von Schwellwerten, geplante Berichte
+//! und Warnungen. Läuft eigenständig (`TelegramBot`) oder eingebettet über
+//! [`SensorBot::builder`]; siehe `examples/embedded.rs`.
+//!
+//! Stabilität: öffentlich und stabil sind `SensorBot`, `SensorBotBuilder`,
+//! `BotHandle`, `Health`, `StartError`, `Settings`, `SensorData`, `Alert`,
+//! `UserConfig` (nur als serialisierbarer Datensatz) sowie die Traits
+//! `SensorSource`, `Messenger`, `AlertSink`, `Store` und `HistoryStore`
+//! samt `SinkAlert` und `Severity`. Die übrigen Datensätze, die ein
+//! eigener `Store` ablegt (`History`, `UptimeLog`, `Records`, `AccessList`,
+//! `Archive`, `IgnoreList`, `KnownChats`, `Reachability`), sind wie
+//! `UserConfig` nur über ihr serde-Format stabil. Alles andere ist intern.
+
+use chrono::{DateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use log::{error, info, warn};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::future::IntoFuture;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use teloxide::RequestError;
+use teloxide::dispatching::{ShutdownToken, UpdateHandler};
+use teloxide::net::Download;
+use teloxide::prelude::*;
+use teloxide::types::{
+    BotCommand, BotCommandScope, CallbackQuery, InlineKeyboardMarkup, InlineQuery, InlineQueryResult, InlineQueryResultArticle, InputMessageContent,
+    InputMessageContentText, Me, MessageId, ParseMode, Recipient,
+};
+use teloxide::utils::command::BotCommands;
+use teloxide::utils::command::ParseError;
+use tokio::sync::{Mutex, watch};
+use tokio::task::JoinHandle;
+
+mod access;
+mod adjust;
+mod alarm_log;
+mod alerts;
+#[cfg(test)]
+mod arbitrary;
+mod archive;
+mod background;
+mod backup;
+mod cadence;
+mod charts;
+mod commands;
+mod configure;
+mod confirm;
+mod correlation;
+mod coverage;
+#[cfg(feature = "storage-sqlite")]
+mod database;
+mod digest;
+mod discovery;
+mod episode;
+mod escalation;
+mod export;
+mod fetch;
+mod fleet;
+mod format;
+mod handover;
+#[cfg(test)]
+mod harness;
+mod history;
+mod history_store;
+#[cfg(feature = "http-api")]
+mod http;
+mod i18n;
+#[cfg(feature = "http-api")]
+mod ical;
+mod ignored;
+mod inject;
+mod instance;
+mod introspect;
+mod known_chats;
+mod layout;
+mod logfile;
+mod messenger;
+mod metrics;
+mod mold;
+mod monitor;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+mod notify;
+mod outbox;
+mod outdoor;
+mod plausibility;
+#[cfg(feature = "charts")]
+mod plot;
+mod polling;
+mod profiles;
+mod push;
+mod quantities;
+mod rate;
+mod ratelimit;
+mod reachability;
+mod reactions;
+mod records;
+mod redact;
+mod refresh;
+mod relative;
+mod replies;
+mod room_images;
+mod rooms;
+mod routing;
+mod schedule;
+mod schema_watch;
+mod selftest;
+mod sensor;
+mod settings;
+mod shortcuts;
+mod simulate;
+mod snapshot;
+mod snooze;
+mod source;
+mod sparkline;
+mod staleness;
+mod state;
+mod stats;
+mod storage;
+mod telemetry;
+mod thingspeak;
+mod thresholds;
+mod timeformat;
+mod timeutil;
+mod units;
+mod uptime;
+mod weather;
+mod weekly;
+pub use access::AccessList;
+use adjust::{Adjust, SnoozeFor};
+pub use alerts::{Alert, format_alert, format_alert_in};
+pub use archive::{Archive, Archived};
+pub use backup::USAGE as RESTORE_USAGE;
+use charts::ChartCache;
+use commands::{Command, answer};
+use confirm::{Confirmations, Taken};
+use digest::AlertMode;
+use escalation::Escalation;
+use fetch::{all_sources, fetch_sensor_data, fetch_sensor_data_for, status_readings, tenant_sources};
+use format::{
+    alert_message, backup_summary, config_summary, digest_line, escape_markdown, format_alarm_entry, format_alert_digest, format_alert_mode,
+    format_device_thresholds, format_diff, format_digest, format_handover, format_health, format_history, format_new_records, format_notes, format_profiles,
+    format_records, format_recovery, format_routing, format_schedule, format_sensors, format_settings, format_snoozes, format_snoozes_ended, format_stats,
+    format_status, format_tenant, known_chat_list, known_devices, live_text, live_values, markdown_bold, notes_for, notification_summary, outdoor_comparison,
+    status_footer,
+};
+pub use history::{Bucket, History};
+pub use history_store::HistoryStore;
+pub use i18n::Lang;
+use i18n::{Text, command_description};
+pub use ignored::IgnoreList;
+use instance::{InstanceLock, LockError};
+pub use known_chats::KnownChats;
+pub use logfile::RotatingLog;
+pub use messenger::{AlertSink, Messenger, OutgoingMessage, SendError, SinkAlert, TelegramMessenger};
+use monitor::EventKind;
+#[cfg(feature = "mqtt")]
+pub use mqtt::{MqttConfig, MqttSource};
+use notify::{MessageKind, NotificationMode};
+use outbox::{Delivery, Outbox};
+pub use plausibility::Limits as PlausibleLimits;
+use profiles::Profiles;
+use ratelimit::{RateLimiter, Verdict};
+pub use reachability::Reachability;
+use reactions::{Reaction, ReactionUpdate};
+pub use records::Records;
+pub use records::USAGE as RECORDS_USAGE;
+use replies::Replies;
+use rooms::{Room, RoomMatch, RoomRegistry};
+pub use routing::Severity;
+use schedule::WeeklySchedule;
+use selftest::SelfTest;
+pub use sensor::{SensorKind, ThresholdDirection, ThresholdKey};
+pub use settings::{Settings, token_from_env};
+pub use simulate::USAGE as SIMULATE_USAGE;
+use snooze::Snooze;
+pub use source::{BoxFuture, Endpoint, FetchError, HttpSource, SensorSource, parse_endpoints};
+pub use state::UserConfig;
+use state::{
+    AlertRecord, LiveStatus, QuietQueue, Setter, Shared, SharedCharts, SharedEscalation, SharedHistory, SharedRecords, SharedUptime, ThresholdFlags, UndoEntry,
+    UserConfigs,
+};
+pub use storage::{JsonStore, Store};
+pub use thingspeak::USAGE as THINGSPEAK_USAGE;
+use thresholds::{DEFAULT_TEMPLATE, ThresholdEntry, ThresholdSchedule, TimeWindow};
+use timeformat::TimeFormat;
+use units::TempUnit;
+pub use uptime::{Downtime, UptimeLog};
+pub use weather::Location as WeatherLocation;
+
+// Iteration in der neue Sensordaten abgerufen werden, aus
+// POLL_INTERVAL_SECONDS und per /set-interval änderbar.
+// Wird das /status Kommando benutzt, wird nochmal extra abgefragt.
+// Die ITERATION ist nur für Grenzwerte interessant und da reichen
+// 10 Minuten.
+static POLL_INTERVAL: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(settings::DEFAULT_POLL_INTERVAL_SECONDS);
+// Weckt die Überwachung, damit ein neues Intervall sofort gilt
+static POLL_INTERVAL_CHANGED: tokio::sync::Notify = tokio::sync::Notify::const_new();
+
+fn poll_interval() -> u64 {
+    POLL_INTERVAL.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+// Takt, in dem geplante Berichte geprüft werden
+const SCHEDULER_TICK_IN_SECONDS: u64 = 30;
+// So viele Schwellwert-Änderungen lassen sich mit /undo zurücknehmen
+const MAX_UNDO: usize = 10;
+// So lange wartet "Schwelle anpassen…" auf den neuen Wert
+const PENDING_INPUT_SECONDS: u64 = 5 * 60;
+// Höchstlänge einer Notiz zu einem Gerät
+const MAX_NOTE_CHARS: usize = 200;
+// So viele zugestellte Warnungen je Chat bleiben für Reaktionen zuordenbar
+const MAX_ALERT_RECORDS: usize = 100;
+// Beim Beenden: so lange darf der laufende Überwachungsdurchlauf noch
+// laufen, danach so lange wird die Ausgangswarteschlange abgearbeitet
+const SHUTDOWN_MONITOR_WAIT: std::time::Duration = std::time::Duration::from_secs(5);
+const SHUTDOWN_OUTBOX_WAIT: std::time::Duration = std::time::Duration::from_secs(3);
+// Ortszeit des Geräte-Wochenberichts an den Admin (/fleet-report)
+const FLEET_REPORT_AT: &str = "so 18:00";
+// Geräte-Wochenbericht außer der Reihe (/fleet-report now)
+static FLEET_REPORT_NOW: tokio::sync::Notify = tokio::sync::Notify::const_new();
+// Ortszeit der nächtlichen Verdichtung des Verlaufs, nach der Sicherung
+const COMPACT_AT: NaiveTime = match NaiveTime::from_hms_opt(4, 0, 0) {
+    Some(at) => at,
+    None => NaiveTime::MIN,
+};
+// Ortszeit der abendlichen Frost- und Gießhinweise für Räume im Freien
+const OUTDOOR_CHECK_AT: NaiveTime = match NaiveTime::from_hms_opt(20, 0, 0) {
+    Some(at) => at,
+    None => NaiveTime::MIN,
+};
+// Bis zu dieser Ortszeit am Morgen reicht die Frostprognose
+const FROST_UNTIL: NaiveTime = match NaiveTime::from_hms_opt(6, 0, 0) {
+    Some(at) => at,
+    None => NaiveTime::MIN,
+};
+// "😴 bis morgen" unter Warnungen endet zu dieser Ortszeit des Chats
+const SNOOZE_UNTIL_MORNING: NaiveTime = match NaiveTime::from_hms_opt(7, 0, 0) {
+    Some(at) => at,
+    None => NaiveTime::MIN,
+};
+// /history ohne Angabe und höchstens; länger hält der Verlauf keine
+// Rohwerte, die Datenbank (DATABASE_PATH) bis zu einem Jahr
+const HISTORY_DEFAULT_HOURS: i64 = 6;
+const HISTORY_MAX_HOURS: i64 = 7 * 24;
+const DATABASE_MAX_HOURS: i64 = 365 * 24;
+// /chart ohne Zeitraum; darunter wird statt einer Linie der Text gesendet
+const CHART_DEFAULT_HOURS: i64 = 24;
+#[cfg(feature = "charts")]
+const MIN_CHART_POINTS: usize = 3;
+// Beginnt der Verlauf später als so lange nach dem Fensteranfang, gibt es einen Hinweis
+const HISTORY_GAP_SECONDS: i64 = 15 * 60;
+
+// Höchstens so viele heiße Tage in Folge lassen sich für /watering angeben
+const MAX_WATERING_DAYS: u32 = 14;
+
+// Erinnerung für Schwellen mit Stufe "critical", solange kein /repeat gesetzt ist
+const CRITICAL_REPEAT_MINUTES: i64 = 60;
+
+// /alert-mode digest ohne Angabe und kürzestes erlaubtes Intervall
+const DEFAULT_DIGEST_MINUTES: i64 = 60;
+const MIN_DIGEST_MINUTES: i64 = 5;
+
+// Wie `UserConfig::active_threshold`, wenn andere Felder gerade geändert werden
+fn threshold_in(
+    thresholds: &HashMap<(String, ThresholdKey), ThresholdSchedule>,
+    profiles: &Profiles,
+    key: &(String, ThresholdKey),
+    local: NaiveTime,
+) -> Option<ThresholdEntry> {
+    match profiles.override_for(key) {
+        Some((name, value)) => value.map(|value| ThresholdEntry { value, window: None, source: format!("/profile {}", name) }),
+        None => thresholds.get(key)?.active_entry(local).cloned(),
+    }
+}
+
+// Einstellungen des laufenden Bots, einmal je Prozess gesetzt
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+// Raumverzeichnis (ROOMS_FILE, sonst eingebaute Zuordnung). /reload-rooms
+// tauscht es aus; wer gerade den vorigen Stand hält, behält ihn bis zum
+// Ende seines Aufrufs.
+static ROOM_REGISTRY: std::sync::LazyLock<std::sync::RwLock<Arc<RoomRegistry>>> =
+    std::sync::LazyLock::new(|| std::sync::RwLock::new(Arc::new(RoomRegistry::default())));
+
+// Rohwerte über den Verlauf hinaus (DATABASE_PATH oder eingebettet ein
+// eigener Speicher), beim Start gesetzt
+static DATABASE: OnceLock<Arc<dyn HistoryStore>> = OnceLock::new();
+
+// Zusätzliche Ziele für Warnungen, beim Start gesetzt
+static ALERT_SINKS: OnceLock<Vec<Arc<dyn AlertSink>>> = OnceLock::new();
+
+// Antworten auf Freitext (REPLIES_FILE, sonst eingebaute Antworten)
+static REPLIES: OnceLock<Replies> = OnceLock::new();
+
+// Warteschlange für Hintergrund-Nachrichten, beim Start gesetzt (/test-alarm)
+static OUTBOX: OnceLock<Outbox> = OnceLock::new();
+
+// Gesetzt, sobald `run` den Bot startet; die Zustände oben gibt es nur einmal
+// je Prozess, ein zweiter Start würde sie mit dem ersten teilen
+static STARTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+fn claim_start() -> Result<(), StartError> {
+    match STARTED.swap(true, std::sync::atomic::Ordering::SeqCst) {
+        false => Ok(()),
+        true => Err(StartError::AlreadyStarted),
+    }
+}
+
+// Messwert-Quellen (ohne Angabe der Sensor-Webserver auf localhost)
+static SOURCES: OnceLock<Vec<Arc<dyn SensorSource>>> = OnceLock::new();
+
+// Ergebnis der Testnachricht je zusätzlichem Ziel aus [routing], für /debug
+static ROUTE_CHECKS: std::sync::Mutex<BTreeMap<i64, Result<(), SendError>>> = std::sync::Mutex::new(BTreeMap::new());
+
+// Erreichbarkeit der Chats aus den tatsächlichen Zustellungen, beim Start
+// aus dem Speicher geladen
+static REACHABILITY: std::sync::LazyLock<std::sync::Mutex<Reachability>> = std::sync::LazyLock::new(Default::default);
+
+// Freigegebene und gesperrte Chats (/allow, /deny), beim Start aus dem Speicher geladen
+static ACCESS: std::sync::LazyLock<std::sync::Mutex<AccessList>> = std::sync::LazyLock::new(Default::default);
+
+// Befehle je Chat für COMMAND_RATE_PER_MINUTE
+static RATE_LIMITER: std::sync::LazyLock<std::sync::Mutex<RateLimiter>> =
+    std::sync::LazyLock::new(|| std::sync::Mutex::new(RateLimiter::new(settings().command_rate_per_minute)));
+
+// Chats, die den Bot benutzt haben (/broadcast, /users), beim Start aus dem Speicher geladen
+static KNOWN_CHATS: std::sync::LazyLock<std::sync::Mutex<KnownChats>> = std::sync::LazyLock::new(Default::default);
+
+// Ignorierte Geräte (/ignore), beim Start aus dem Speicher geladen
+static IGNORED: std::sync::LazyLock<std::sync::Mutex<IgnoreList>> = std::sync::LazyLock::new(Default::default);
+
+// Änderung, die erst nach "✅ Anwenden" unter der Vorschau ausgeführt wird
+enum PendingChange {
+    ClearAll,
+    Forget,
+    Ignore { device: String, by: String },
+    Restore(Box<UserConfig>),
+}
+
+// Offene Vorschauen mit Bestätigungs-Buttons, je Token
+static CONFIRMATIONS: std::sync::Mutex<Confirmations<PendingChange>> = std::sync::Mutex::new(Confirmations::new());
+
+// Abgefangene Paniken in Befehlen und Buttons seit dem Start (/health)
+static HANDLER_PANICS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+// Offenes /setimage je Chat: Gerät und Beginn; das nächste Foto wird das Raumbild
+static PENDING_IMAGES: std::sync::Mutex<BTreeMap<i64, (String, std::time::Instant)>> = std::sync::Mutex::new(BTreeMap::new());
+
+// Nächste Termine für den Fuß von /status, veröffentlicht von der Überwachung
+// (nächste Abfrage) und vom Zeitplaner (nächster Bericht je Chat), dazu die
+// beim letzten Abruf verworfenen Einträge je Haushalt (None = Haupthaushalt)
+struct BotStatus {
+    next_poll: Option<DateTime<Utc>>,
+    next_reports: BTreeMap<i64, DateTime<Utc>>,
+    skipped: BTreeMap<Option<String>, usize>,
+    // Letzter Abruf der Überwachung und Fehler, falls er scheiterte (/health)
+    last_fetch: Option<(DateTime<Utc>, Option<Text>)>,
+    fetch_failures: u32,                         // fehlgeschlagene Abrufe in Folge
+    last_iteration: Option<std::time::Duration>, // Dauer des letzten Durchlaufs (/debug)
+}
+
+static BOT_STATUS: std::sync::Mutex<BotStatus> = std::sync::Mutex::new(BotStatus {
+    next_poll: None,
+    next_reports: BTreeMap::new(),
+    skipped: BTreeMap::new(),
+    last_fetch: None,
+    fetch_failures: 0,
+    last_iteration: None,
+});
+
+fn bot_status() -> std::sync::MutexGuard<'static, BotStatus> {
+    BOT_STATUS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Weckt die Live-Nachrichten (/live) nach einem erfolgreichen Abruf
+static LATEST_FETCHED: tokio::sync::Notify = tokio::sync::Notify::const_new();
+
+// Messwerte des letzten erfolgreichen Abrufs der Überwachung (alle Haushalte)
+struct SensorSnapshot {
+    readings: Vec<SensorData>,
+    fetched_at: DateTime<Utc>,
+}
+
+static LATEST: std::sync::Mutex<Option<SensorSnapshot>> = std::sync::Mutex::new(None);
+
+fn latest() -> std::sync::MutexGuard<'static, Option<SensorSnapshot>> {
+    LATEST.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Geschickte Messwerte in den letzten Stand übernehmen, wo sie neuer sind
+fn merge_latest<'a>(pushed: impl IntoIterator<Item = &'a SensorData>) {
+    let mut latest = latest();
+    let snapshot = latest.get_or_insert_with(|| SensorSnapshot { readings: Vec::new(), fetched_at: Utc::now() });
+    for reading in pushed {
+        let same = |r: &&mut SensorData| r.device_id == reading.device_id && r.sensor_type == reading.sensor_type;
+        match snapshot.readings.iter_mut().find(same) {
+            Some(existing) if existing.timestamp < reading.timestamp => *existing = reading.clone(),
+            Some(_) => {}
+            None => snapshot.readings.push(reading.clone()),
+        }
+    }
+}
+
+/// Ein Messwert, wie ihn eine `SensorSource` liefert.
+///
+/// Stabilität: Felder und JSON-Format sind Teil der öffentlichen Schnittstelle.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+pub struct SensorData {
+    pub device_id: String,       // Unique identifier for each sensor
+    pub sensor_type: SensorKind, // Example: "temperature" or "humidity"
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::extreme()"))]
+    pub value: f64, // The measured value
+    pub timestamp: i64,          // (Optional) If time tracking is wanted
+}
+
+// Inhalt der Datei von /backup. `version` steigt bei inkompatiblen
+// Änderungen; neuere Dateien weist /restore ab, statt Teile zu verlieren.
+#[derive(Serialize, Deserialize)]
+struct ConfigBackup {
+    version: u32,
+    chat_id: i64,
+    created_at: DateTime<Utc>,
+    config: UserConfig,
+}
+
+const BACKUP_VERSION: u32 = 1;
+
+// Datei von /backup lesen; neuere Versionen als die bekannte werden abgelehnt
+fn parse_config_backup(content: &[u8], lang: Lang) -> Result<ConfigBackup, String> {
+    match serde_json::from_slice::<serde_json::Value>(content) {
+        Ok(value) => match value.get("version").and_then(|v| v.as_u64()) {
+            Some(version) if version > BACKUP_VERSION as u64 => {
+                Err(i18n::message_with(lang, "restore_version", &[("version", &version.to_string()), ("known", &BACKUP_VERSION.to_string())]))
+            }
+            Some(_) => serde_json::from_value::<ConfigBackup>(value).map_err(|err| i18n::message_with(lang, "restore_damaged", &[("error", &err.to_string())])),
+            None => Err(i18n::message(lang, "restore_not_backup").to_string()),
+        },
+        Err(_) => Err(i18n::message(lang, "restore_not_backup").to_string()),
+    }
+}
+// Größere Dateien sind keine Sicherung von /backup
+const MAX_BACKUP_BYTES: u32 = 1024 * 1024;
+
+// Mini-Verlauf je (Gerät, Typ) für /status, siehe status_trends
+type Trends = HashMap<(String, String), String>;
+// Offener Dialog "Schwelle anpassen…" je Chat
+struct PendingAdjust {
+    device_id: String,
+    key: ThresholdKey,
+    alert: Option<(MessageId, String)>, // Warnung, die danach aktualisiert wird
+    since: std::time::Instant,
+    create: bool, // aus /configure: Schwelle anlegen statt die aktive anpassen
+}
+
+type PendingInput = Arc<Mutex<HashMap<i64, PendingAdjust>>>;
+// Alarme eines Durchlaufs je (Chat, Gerät): (Schwelle, Zeitstempel, Text, Zeile für Sammelwarnungen, Stufe)
+type AlarmGroups = BTreeMap<(i64, String), Vec<(ThresholdKey, i64, String, String, Severity)>>;
+
+// Warnung eines Geräts, die in diesem Durchlauf an den Chat geht. Mehrere
+// je Chat werden zu einer Sammelwarnung mit einer Zeile je Schwelle.
+struct BatchedAlert {
+    device_id: String,
+    text: String,
+    lines: Vec<String>,
+    keys: Vec<ThresholdKey>,
+    severity: Severity,
+    observed_at: i64,
+}
+
+// Kopien für [routing]: (Ziel, Gerät, Schwellen) -> (Text, Stufe, Besitzer)
+type RoutedCopies = BTreeMap<(i64, String, Vec<ThresholdKey>), (String, Severity, Vec<i64>, i64)>;
+
+#[cfg(not(test))]
+fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::default)
+}
+
+// In Tests ist der Admin des Prüfstands gesetzt, damit Admin-Befehle erreichbar sind
+#[cfg(test)]
+fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(|| Settings { admin_chat: Some(harness::ADMIN), ..Settings::default() })
+}
+
+// Zeitfunktionen mit der konfigurierten Zeitzone, Regeln siehe timeutil
+
+// Uhrzeit eines Zeitpunkts in der konfigurierten Zeitzone
+fn local_time_of(dt: DateTime<Utc>) -> NaiveTime {
+    timeutil::local_time_of(dt, settings().timezone)
+}
+
+// Aktuelle Uhrzeit in der konfigurierten Zeitzone
+fn local_time() -> NaiveTime {
+    local_time_of(Utc::now())
+}
+
+// Liegt der Zeitpunkt im Zeitfenster (Ortszeit)?
+fn in_local_window(window: &TimeWindow, at: DateTime<Utc>) -> bool {
+    timeutil::within_local_window(window, at, settings().timezone)
+}
+
+// Nächster Termin eines Zeitplans in der konfigurierten Zeitzone
+fn next_fire(schedule: &WeeklySchedule, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    timeutil::next_fire(schedule, after, settings().timezone)
+}
+
+// Letzter Termin eines Zeitplans bis einschließlich `now`
+fn last_fire(schedule: &WeeklySchedule, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let mut last = None;
+    let mut after = now - chrono::Duration::days(8);
+    while let Some(fire) = next_fire(schedule, after).filter(|fire| *fire <= now) {
+        last = Some(fire);
+        after = fire;
+    }
+    last
+}
+
+fn format_local(dt: DateTime<Utc>, fmt: &str) -> String {
+    timeutil::format_local(dt, settings().timezone, fmt)
+}
+
+fn replies() -> &'static Replies {
+    REPLIES.get_or_init(Replies::default)
+}
+
+fn rooms() -> Arc<RoomRegistry> {
+    ROOM_REGISTRY.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn set_rooms(registry: RoomRegistry) {
+    *ROOM_REGISTRY.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(registry);
+}
+
+// Sensor-Zeitstempel (Sekunden) in der konfigurierten Zeitzone
+fn format_timestamp(timestamp: i64, fmt: &str) -> String {
+    format_timestamp_in(timestamp, fmt, settings().timezone)
+}
+
+// Zeitzone eines Chats: eigene (/timezone), sonst DEFAULT_TZ, sonst Systemzeit.
+// Gilt nur für die Anzeige; Zeitpläne und Zeitfenster bleiben bei DEFAULT_TZ.
+fn chat_timezone(config: Option<&UserConfig>) -> Option<Tz> {
+    config.and_then(|c| c.timezone).or(settings().timezone)
+}
+
+fn format_local_in(dt: DateTime<Utc>, fmt: &str, tz: Option<Tz>) -> String {
+    timeutil::format_local(dt, tz, fmt)
+}
+
+fn format_timestamp_in(timestamp: i64, fmt: &str, tz: Option<Tz>) -> String {
+    if !timeutil::is_known(timestamp) {
+        return "Zeit unbekannt".to_string();
+    }
+    format_local_in(timeutil::from_timestamp(timestamp), fmt, tz)
+}
+
+fn timezone_name(tz: Option<Tz>) -> String {
+    tz.map(|tz| tz.name().to_string()).unwrap_or_else(|| "Systemzeit".to_string())
+}
+
+// Kalenderjahr eines Zeitstempels in der konfigurierten Zeitzone
+fn local_year(timestamp: i64) -> i32 {
+    timeutil::local_year_of(timeutil::from_timestamp(timestamp), settings().timezone)
+}
+
+// "4h", "30m", "2d" -> Dauer
+fn parse_duration(s: &str) -> Option<chrono::Duration> {
+    let s = s.trim().to_lowercase();
+    let (split, unit) = s.char_indices().last()?;
+    let number: i64 = s[..split].trim().parse().ok().filter(|n| *n > 0)?;
+    // Zu große Zahlen wie unlesbare Angaben behandeln
+    match unit {
+        'd' => chrono::TimeDelta::try_days(number),
+        'h' => chrono::TimeDelta::try_hours(number),
+        'm' => chrono::TimeDelta::try_minutes(number),
+        _ => None,
+    }
+}
+
+// Abfrageintervall, unter einer Minute in Sekunden
+fn format_poll_interval(seconds: u64) -> String {
+    if seconds < 60 || !seconds.is_multiple_of(60) { format!("{} s", seconds) } else { format_duration(seconds as i64) }
+}
+
+// /help in der Sprache des Chats; Befehle ohne Übersetzung wie im Menü
+fn format_help(lang: Lang) -> String {
+    Command::bot_commands()
+        .into_iter()
+        .map(|cmd| {
+            let name = cmd.command.trim_start_matches('/');
+            let description = command_description(lang, name).map(str::to_string).unwrap_or(cmd.description);
+            format!("/{} — {}", name, description)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Zeitpunkt eines Messwerts so, wie der Chat es gewählt hat (/timeformat)
+fn format_reading_time(timestamp: i64, fmt: &str, tz: Option<Tz>, times: TimeFormat, lang: Lang) -> String {
+    if !timeutil::is_known(timestamp) {
+        return format_timestamp_in(timestamp, fmt, tz);
+    }
+    let relative = || timeformat::relative(timeutil::from_timestamp(timestamp), Utc::now(), tz, lang);
+    match times {
+        TimeFormat::Absolute => format_timestamp_in(timestamp, fmt, tz),
+        TimeFormat::Relative => relative(),
+        TimeFormat::Both => format!("{}, {}", relative(), format_timestamp_in(timestamp, fmt, tz)),
+    }
+}
+
+// Sekunden als "2 h 10 min"
+fn format_duration(seconds: i64) -> String {
+    let minutes = seconds / 60;
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{} min", m),
+        (h, 0) => format!("{} h", h),
+        (h, m) => format!("{} h {} min", h, m),
+    }
+}
+
+// Sprache für Hinweise an den Admin-Chat
+fn admin_lang(configs: &HashMap<i64, UserConfig>) -> Lang {
+    settings().admin_chat.and_then(|admin| configs.get(&admin)).map(|config| config.lang).unwrap_or_default()
+}
+
+fn room_name(device_id: &str) -> String {
+    rooms().room_name(device_id).to_string()
+}
+
+// Raumname (ohne Groß-/Kleinschreibung) oder Geräte-ID -> Geräte-ID,
+// über alle Haushalte (Admin, Kommandozeile)
+fn resolve_device(name: &str) -> String {
+    rooms().find(name).map(|room| room.device.clone()).unwrap_or_else(|| name.to_string())
+}
+
+// Haushalt eines Chats; None ist der Haupthaushalt
+fn tenant_of(chat_id: i64) -> Option<String> {
+    rooms().tenant_of(chat_id).map(|tenant| tenant.id.clone())
+}
+
+// Wie `resolve_device`, aber nur innerhalb des Haushalts des Chats. Nicht
+// eingetragene Geräte-IDs gelten als Geräte des eigenen Haushalts; IDs eines
+// fremden Haushalts ergeben None.
+fn resolve_device_in(chat_id: i64, name: &str) -> Option<String> {
+    let registry = rooms();
+    let tenant = registry.tenant_of(chat_id).map(|tenant| tenant.id.as_str());
+    if let Some(room) = registry.find_in(tenant, name) {
+        return Some(room.device.clone());
+    }
+    match (registry.device_tenant(name), tenant) {
+        (Some(owner), _) => Some(name.to_string()).filter(|_| Some(owner) == tenant),
+        (None, Some(tenant)) => Some(rooms::qualify(tenant, name)),
+        (None, None) => Some(name.to_string()),
+    }
+}
+
+// Nur die Messwerte, die ein Chat sehen darf
+fn visible_readings(chat_id: i64, readings: &[SensorData]) -> Vec<SensorData> {
+    let tenant = tenant_of(chat_id);
+    readings.iter().filter(|r| rooms().visible(tenant.as_deref(), &r.device_id)).cloned().collect()
+}
+
+// Für /status und Berichte: Batteriestände nur, wenn sie unter der Warngrenze liegen
+fn status_list(readings: Vec<SensorData>, battery_low: f64) -> Vec<SensorData> {
+    let mut readings: Vec<SensorData> = readings.into_iter().filter(|r| r.sensor_type != SensorKind::Battery || r.value < battery_low).collect();
+    // Räume in der Reihenfolge der Raumdatei, Messgrößen eines Raums wie geliefert
+    readings.sort_by_key(|r| rooms().order(&r.device_id));
+    readings
+}
+
+// Raumnamen des Haushalts für "Verfügbar: …"
+fn room_names(chat_id: i64) -> String {
+    rooms().rooms_in(tenant_of(chat_id).as_deref()).map(|r| r.name.as_str()).collect::<Vec<_>>().join(", ")
+}
+
+// Antwort auf einen Raum, den der Chat nicht kennt
+fn unknown_room(lang: Lang, chat_id: i64, room: &str) -> String {
+    i18n::message_with(lang, "unknown_room", &[("room", room), ("rooms", &room_names(chat_id))])
+}
+
+// Wie room_names, dazu Geräte des letzten Abrufs ohne Eintrag im Raumverzeichnis
+fn status_targets(chat_id: i64) -> String {
+    let mut names: Vec<String> = rooms().rooms_in(tenant_of(chat_id).as_deref()).map(|r| r.name.clone()).collect();
+    if let Some(snapshot) = latest().as_ref() {
+        for reading in visible_readings(chat_id, &snapshot.readings) {
+            if !rooms().rooms().iter().any(|r| r.device == reading.device_id) && !names.contains(&reading.device_id) {
+                names.push(reading.device_id);
+            }
+        }
+    }
+    names.join(", ")
+}
+
+// Schwelle der alten Wohnzimmer-Befehle. Früher unter dem Raumnamen statt
+// der Geräte-ID abgelegt und daher nie ausgelöst, siehe migrate_configs.
+fn wohnzimmer_key(chat_id: i64, kind: SensorKind, direction: ThresholdDirection) -> (String, ThresholdKey) {
+    // Ein Raumname ohne Präfix wird immer aufgelöst
+    let device = resolve_device_in(chat_id, "Wohnzimmer").unwrap_or_default();
+    (device, ThresholdKey::new(kind, direction))
+}
+
+fn type_label(sensor_type: &str) -> (&str, &str) {
+    type_label_in(Lang::De, sensor_type)
+}
+
+// Einheit in der Anzeige des Chats: Temperaturen nach /units
+fn unit_in(units: TempUnit, sensor_type: &str) -> &str {
+    if units::is_temperature(&SensorKind::from(sensor_type)) { units.symbol() } else { type_label(sensor_type).1 }
+}
+
+// Bezeichnung und Einheit aus quantities; unbekannte Typen wie geliefert, ohne Einheit
+fn type_label_in(lang: Lang, sensor_type: &str) -> (&str, &str) {
+    match quantities::lookup(sensor_type) {
+        Some(quantity) => (quantity.label.unwrap_or_else(|| i18n::message(lang, quantity.sensor_type)), quantity.unit),
+        None => (sensor_type, ""),
+    }
+}
+
+// /reload-rooms: Namen, Reihenfolge, Symbole, Tipps, Regeln und [routing]
+// gelten sofort. Haushalte bringen eigene Quellen mit, die beim Start
+// eingerichtet werden; ändern sie sich, bleibt es beim alten Stand.
+fn reload_rooms(lang: Lang) -> String {
+    let Some(path) = &settings().rooms_file else {
+        return i18n::message(lang, "reload_rooms_none").to_string();
+    };
+    let registry = match RoomRegistry::load(path) {
+        Ok(registry) => registry,
+        Err(errors) => return format!("{}\n{}", i18n::message(lang, "reload_rooms_invalid"), errors.join("\n")),
+    };
+    let households = |registry: &RoomRegistry| -> Vec<(String, String)> { registry.tenants().iter().map(|t| (t.id.clone(), t.source.clone())).collect() };
+    if households(&registry) != households(&rooms()) {
+        return i18n::message(lang, "reload_rooms_restart").to_string();
+    }
+    let count = registry.rooms().len();
+    set_rooms(registry);
+    info!("Raumdatei {} neu geladen, {} Räume", path.display(), count);
+    i18n::message_with(lang, "reload_rooms_done", &[("count", &count.to_string()), ("path", &path.display().to_string())])
+}
+
+// Prüfungen für den Selbsttest beim Start und /selftest. Nur Telegram und
+// der Speicher sind unverzichtbar; ohne Sensoren oder mit fehlerhaften
+// Dateien läuft der Bot eingeschränkt weiter.
+fn self_test(bot: Option<Bot>, storage: Arc<dyn Store>) -> SelfTest {
+    let mut test = SelfTest::default();
+    if let Some(bot) = bot {
+        test.register(Text::verbatim("Telegram"), true, move || {
+            let bot = bot.clone();
+            async move {
+                let me = bot.get_me().await.map_err(|err| Text::verbatim(err.to_string()))?;
+                Ok(Text::verbatim(format!("@{}", me.username())))
+            }
+        });
+    }
+    let mut all = tenant_sources(None);
+    let registry = rooms();
+    for tenant in registry.tenants() {
+        all.extend(tenant_sources(Some(tenant)));
+    }
+    for (index, (tenant, source)) in all.into_iter().enumerate() {
+        let name = match tenant {
+            Some(tenant) => Text::new("selftest_tenant_sensors", &[("tenant", &tenant)]),
+            None => Text::new("selftest_source", &[("number", &(index + 1).to_string())]),
+        };
+        test.register(name, false, move || {
+            let source = source.clone();
+            async move {
+                match source.fetch().await {
+                    Ok(readings) if readings.is_empty() => Err(Text::new("selftest_no_readings", &[])),
+                    Ok(readings) => Ok(Text::new("selftest_readings", &[("count", &readings.len().to_string())])),
+                    Err(err) => Err(err.text()),
+                }
+            }
+        });
+    }
+    test.register(Text::new("selftest_storage", &[]), true, move || {
+        let storage = storage.clone();
+        async move {
+            match storage.check_writable() {
+                Some(result) => result.map(|()| Text::new("selftest_writable", &[])).map_err(Text::verbatim),
+                None => Ok(Text::new("selftest_unchecked", &[])),
+            }
+        }
+    });
+    if settings().database_path.is_some() || DATABASE.get().is_some() {
+        test.register(Text::new("selftest_database", &[]), false, || async {
+            match DATABASE.get().map(|db| db.check_writable()) {
+                Some(Some(result)) => result.map(|()| Text::new("selftest_writable", &[])).map_err(Text::verbatim),
+                Some(None) => Ok(Text::new("selftest_unchecked", &[])),
+                None => Err(Text::new("selftest_not_open", &[])),
+            }
+        });
+    }
+    test.register(Text::new("selftest_rooms_file", &[]), false, || async {
+        match &settings().rooms_file {
+            Some(path) => RoomRegistry::load(path)
+                .map(|registry| Text::new("selftest_rooms", &[("count", &registry.rooms().len().to_string())]))
+                .map_err(|errors| Text::verbatim(errors.join("; "))),
+            None => Ok(Text::new("selftest_builtin_rooms", &[])),
+        }
+    });
+    test.register(Text::new("selftest_replies_file", &[]), false, || async {
+        match &settings().replies_file {
+            Some(path) => Replies::load(path).map(|replies| Text::new("selftest_replies", &[("count", &replies.len().to_string())])).map_err(Text::verbatim),
+            None => Ok(Text::new("selftest_builtin_replies", &[])),
+        }
+    });
+    test
+}
+
+// Einstellungen übernehmen und Raumverzeichnis laden; einmal je Prozess
+fn load_settings(settings: Settings) -> Result<(), String> {
+    if let Some(path) = &settings.rooms_file {
+        let registry = RoomRegistry::load(path).map_err(|errors| format!("Raumdatei {} fehlerhaft:\n  {}", path.display(), errors.join("\n  ")))?;
+        info!("{} Räume aus {} geladen", registry.rooms().len(), path.display());
+        set_rooms(registry);
+    }
+    if let Some(path) = &settings.replies_file {
+        // Anders als die Raumdatei kein Grund, den Start abzubrechen
+        match Replies::load(path) {
+            Ok(replies) => {
+                info!("{} Antworten aus {} geladen", replies.len(), path.display());
+                REPLIES.set(replies).ok();
+            }
+            Err(err) => warn!("{}, eingebaute Antworten werden verwendet", err),
+        }
+    }
+    room_images::init(settings.room_images_dir.clone());
+    POLL_INTERVAL.store(settings.poll_interval_seconds, std::sync::atomic::Ordering::Relaxed);
+    SETTINGS.set(settings).map_err(|_| "Einstellungen wurden in diesem Prozess bereits gesetzt".to_string())
+}
+
+/// Offline-Auswertung gespeicherter Messwerte (`TelegramBot simulate …`),
+/// Argumente siehe [`SIMULATE_USAGE`]. Einstellungen kommen aus der Umgebung.
+pub fn simulate(args: &[String]) -> Result<(), String> {
+    load_settings(Settings::from_env())?;
+    simulate::run(args)
+}
+
+/// Sicherung zurückspielen (`TelegramBot restore <verzeichnis>`), siehe
+/// [`RESTORE_USAGE`]. Bricht ab, solange der Bot läuft.
+pub fn restore(args: &[String]) -> Result<(), String> {
+    let [backup] = args else {
+        return Err("Genau ein Sicherungsverzeichnis angeben".to_string());
+    };
+    // Sperre halten, damit der Bot nicht während des Zurückspielens startet
+    let _lock = offline_lock(&Settings::from_env())?;
+    for path in JsonStore::from_env().restore(Path::new(backup))? {
+        println!("{} wiederhergestellt", path.display());
+    }
+    Ok(())
+}
+
+/// Rekordwerte aus dem gespeicherten Verlauf neu berechnen
+/// (`TelegramBot rebuild-records`), z.B. nach einem Messfehler. Ältere
+/// Rekorde als der Verlauf gehen dabei verloren.
+pub fn rebuild_records(args: &[String]) -> Result<(), String> {
+    if !args.is_empty() {
+        return Err("Keine Argumente erwartet".to_string());
+    }
+    load_settings(Settings::from_env())?;
+    let _lock = offline_lock(settings())?;
+    let store = JsonStore::from_env();
+    let records = Records::from_history(&store.load_history(), local_year);
+    store.save_records(&records);
+    for (device_id, sensor_type) in records.keys() {
+        let Some(series) = records.get(device_id, sensor_type) else { continue };
+        let (min, max) = (series.all_time.min, series.all_time.max);
+        println!(
+            "{} {}: {} / {}",
+            room_name(device_id),
+            type_label(sensor_type).0,
+            min.map(|e| format!("{:.1}", e.value)).unwrap_or_default(),
+            max.map(|e| format!("{:.1}", e.value)).unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+/// Messwerte eines ThingSpeak-Kanals nachtragen (`TelegramBot import-thingspeak …`),
+/// Argumente siehe [`THINGSPEAK_USAGE`]. Alle Werte fließen in die Rekorde,
+/// in den Verlauf die der letzten 2 Jahre (ältere als 7 Tage verdichtet).
+/// Ein erneuter Aufruf macht
+/// beim zuletzt übernommenen Eintrag weiter.
+pub async fn import_thingspeak(args: &[String]) -> Result<(), String> {
+    load_settings(Settings::from_env())?;
+    let _lock = offline_lock(settings())?;
+    let store = JsonStore::from_env();
+    let mut history = store.load_history();
+    let mut records = store.load_records();
+    let save = |history: &History, records: &Records| {
+        store.save_history(history);
+        store.save_records(records);
+    };
+    let imported = thingspeak::run(args, &mut history, &mut records, local_year, save).await?;
+    println!("{} Einträge mit {} Messwerten übernommen, {} davon im Verlauf", imported.entries, imported.samples, imported.stored);
+    Ok(())
+}
+
+// Sperre für Offline-Befehle, damit der Bot währenddessen nicht startet
+fn offline_lock(settings: &Settings) -> Result<Option<InstanceLock>, String> {
+    let Some(path) = &settings.lock_file else { return Ok(None) };
+    InstanceLock::acquire(path).map(Some).map_err(|err| match err {
+        LockError::Held(pid) => format!("Der Bot läuft noch (PID {}), bitte erst beenden", pid.map(|p| p.to_string()).unwrap_or_else(|| "?".into())),
+        LockError::Io(err) => format!("Sperrdatei {} kann nicht angelegt werden: {}", path.display(), err),
+    })
+}
+
+/// Einstieg zum Einbetten des Bots in eine andere Anwendung.
+pub struct SensorBot;
+
+impl SensorBot {
+    pub fn builder() -> SensorBotBuilder {
+        SensorBotBuilder::default()
+    }
+}
+
+/// Konfiguration vor dem Start, siehe [`SensorBot::builder`].
+///
+/// Ohne Quelle wird der Sensor-Webserver auf localhost abgefragt, ohne
+/// Speicher die JSON-Dateien aus der Umgebung (STATE_FILE, …) benutzt.
+#[derive(Default)]
+pub struct SensorBotBuilder {
+    token: Option<String>,
+    messenger: Option<Arc<dyn Messenger>>,
+    sources: Vec<Arc<dyn SensorSource>>,
+    store: Option<Arc<dyn Store>>,
+    history_store: Option<Arc<dyn HistoryStore>>,
+    alert_sinks: Vec<Arc<dyn AlertSink>>,
+    settings: Settings,
+}
+
+impl SensorBotBuilder {
+    /// Telegram-Token: nimmt Befehle entgegen und stellt ohne eigenen
+    /// Messenger auch die Hintergrund-Nachrichten zu
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Eigene Zustellung für Warnungen und Berichte
+    pub fn messenger(mut self, messenger: impl Messenger + 'static) -> Self {
+        self.messenger = Some(Arc::new(messenger));
+        self
+    }
+
+    /// Weitere Messwert-Quelle; die Werte aller Quellen werden zusammengeführt
+    pub fn source(mut self, source: impl SensorSource + 'static) -> Self {
+        self.sources.push(Arc::new(source));
+        self
+    }
+
+    pub fn store(mut self, store: impl Store + 'static) -> Self {
+        self.store = Some(Arc::new(store));
+        self
+    }
+
+    /// Eigener Speicher für Rohwerte; ersetzt die Datenbank aus DATABASE_PATH
+    pub fn history_store(mut self, store: impl HistoryStore + 'static) -> Self {
+        self.history_store = Some(Arc::new(store));
+        self
+    }
+
+    /// Weiteres Ziel, das jede Warnung zusätzlich zum Chat bekommt
+    pub fn alert_sink(mut self, sink: impl AlertSink + 'static) -> Self {
+        self.alert_sinks.push(Arc::new(sink));
+        self
+    }
+
+    pub fn settings(mut self, settings: Settings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Startet Überwachung, Zeitpläne und (mit Token) den Telegram-Dispatcher.
+    /// Einmal je Prozess möglich, da Einstellungen prozessweit gelten; jeder
+    /// weitere Aufruf endet mit [`StartError::AlreadyStarted`].
+    pub async fn run(self) -> Result<BotHandle, StartError> {
+        let SensorBotBuilder { token, messenger, sources, store, history_store, alert_sinks, settings: config } = self;
+        let bot = token.map(Bot::new);
+        let messenger: Arc<dyn Messenger> = match (messenger, &bot) {
+            (Some(messenger), _) => messenger,
+            (None, Some(bot)) => Arc::new(TelegramMessenger::new(bot.clone())),
+            (None, None) => return Err(StartError::NoMessenger),
+        };
+        claim_start()?;
+
+        // Chat-IDs und Namen nicht im Klartext protokollieren
+        redact::init(config.log_redact);
+        let admin_chat = config.admin_chat.map(ChatId);
+        let lock_path = config.lock_file.clone();
+        load_settings(config).map_err(StartError::Settings)?;
+        if !sources.is_empty() {
+            SOURCES.set(sources).map_err(|_| StartError::Settings("Quellen bereits gesetzt".into()))?;
+        }
+        if let Some(history_store) = history_store {
+            DATABASE.set(history_store).map_err(|_| StartError::Settings("Speicher für Rohwerte bereits gesetzt".into()))?;
+        }
+        ALERT_SINKS.set(alert_sinks).map_err(|_| StartError::Settings("Ziele für Warnungen bereits gesetzt".into()))?;
+
+        // Nur eine Instanz darf laufen, sonst kommen Alarme doppelt.
+        // Die Sperre wird beim Beenden (auch nach Absturz) vom System freigegeben.
+        let instance_lock = match lock_path {
+            None => None,
+            Some(lock_path) => match InstanceLock::acquire(&lock_path) {
+                Ok(lock) => Some(lock),
+                Err(LockError::Held(pid)) => {
+                    if let Some(admin) = admin_chat {
+                        let pid = pid.map(|p| p.to_string()).unwrap_or_else(|| "?".into());
+                        let _ = messenger
+                            .send(&OutgoingMessage {
+                                chat_id: admin.0,
+                                // Einstellungen der Chats sind noch nicht geladen
+                                text: i18n::message_with(Lang::default(), "second_instance", &[("pid", &std::process::id().to_string()), ("running", &pid)]),
+                                markdown: false,
+                                html: false,
+                                buttons: None,
+                                silent: false,
+                                reply_to: None,
+                                room_image: None,
+                            })
+                            .await;
+                    }
+                    return Err(StartError::AlreadyRunning { pid, lock_file: lock_path });
+                }
+                Err(LockError::Io(error)) => return Err(StartError::Lock { lock_file: lock_path, error }),
+            },
+        };
+
+        // Konfiguration und Verlauf überstehen Neustarts
+        let storage: Arc<dyn Store> = store.unwrap_or_else(|| Arc::new(JsonStore::from_env()));
+        open_database();
+        let shared = restore_state(storage.clone(), messenger.clone()).await;
+        let quiet_queue: QuietQueue = Arc::new(Mutex::new(HashMap::new()));
+        // Hintergrund-Nachrichten laufen gedrosselt über die Warteschlange,
+        // Antworten auf Befehle gehen direkt an Telegram
+        let (delivery_tx, delivery_rx) = tokio::sync::mpsc::unbounded_channel::<Delivery>();
+        let outbox = Outbox::spawn(messenger.clone(), delivery_tx, settings().outbox_max_attempts);
+        OUTBOX.set(outbox.clone()).map_err(|_| StartError::AlreadyStarted)?;
+        let mut tasks = Vec::new();
+
+        let recoveries = storage::take_recoveries();
+        if let (Some(admin), false) = (settings().admin_chat, recoveries.is_empty()) {
+            let lang = admin_lang(&*shared.configs.lock().await);
+            outbox.send(ChatId(admin), format!("{}\n{}", i18n::message(lang, "recovered_files"), recoveries.join("\n")));
+        }
+
+        tasks.push(tokio::spawn(background::report_self_test(
+            self_test(bot.clone(), storage.clone()),
+            outbox.clone(),
+            admin_lang(&*shared.configs.lock().await),
+        )));
+        let targets = rooms().routing().all_targets();
+        if !targets.is_empty() {
+            let lang = admin_lang(&*shared.configs.lock().await);
+            tasks.push(tokio::spawn(background::check_routing(targets, messenger, storage.clone(), outbox.clone(), lang)));
+        }
+
+        // Sensor-Überwachung starten
+        let monitor = monitor::Monitor::new(
+            admin_chat,
+            outbox.clone(),
+            shared.configs.clone(),
+            shared.flags.clone(),
+            shared.history.clone(),
+            shared.records.clone(),
+            quiet_queue.clone(),
+            storage.clone(),
+            shared.uptime.clone(),
+            shared.escalation.clone(),
+            all_sources(),
+            Arc::new(monitor::SystemClock),
+        )
+        .await;
+        let (monitor, stop) = monitor::spawn(monitor);
+
+        tasks.push(tokio::spawn(background::scheduled_reports(shared.clone(), outbox.clone(), quiet_queue)));
+        tasks.push(tokio::spawn(background::alert_digests(shared.clone(), outbox.clone())));
+        purge_archive(storage.as_ref());
+        purge_blocked(&shared.configs, &shared.flags, storage.as_ref()).await;
+        if let Some(admin) = admin_chat {
+            tasks.push(tokio::spawn(background::fleet_reports(shared.clone(), outbox.clone(), admin)));
+        }
+        if rooms().rooms().iter().any(|r| r.outdoor) {
+            tasks.push(tokio::spawn(background::outdoor_hints(shared.clone(), outbox.clone())));
+        }
+        tasks.push(tokio::spawn(background::daily_summaries(shared.clone(), outbox.clone())));
+        tasks.push(tokio::spawn(background::scheduled_profiles(shared.clone(), outbox.clone())));
+        tasks.push(tokio::spawn(background::hand_over(shared.clone(), outbox.clone())));
+        tasks.push(tokio::spawn(background::weekly_reports(shared.clone(), outbox.clone(), bot.clone())));
+        tasks.push(tokio::spawn(background::nightly_maintenance(shared.clone())));
+        if let Some(dir) = settings().backup_dir.clone() {
+            tasks.push(tokio::spawn(background::nightly_backup(shared.clone(), outbox.clone(), dir)));
+        }
+        tasks.push(tokio::spawn(background::track_deliveries(shared.clone(), delivery_rx)));
+
+        #[cfg(not(feature = "http-api"))]
+        if settings().http_addr.is_some() {
+            warn!("HTTP_ADDR ist gesetzt, aber der HTTP-Server ist in diesem Build deaktiviert (Feature http-api)");
+        }
+        #[cfg(not(feature = "http-api"))]
+        if settings().metrics_port.is_some() {
+            warn!("METRICS_PORT ist gesetzt, aber der HTTP-Server ist in diesem Build deaktiviert (Feature http-api)");
+        }
+        #[cfg(feature = "http-api")]
+        if let Some(port) = settings().metrics_port {
+            tasks.push(tokio::spawn(http::serve_metrics(port)));
+        }
+        #[cfg(feature = "http-api")]
+        if let Some(addr) = settings().http_addr.clone() {
+            tasks.push(tokio::spawn(http::serve(addr, shared.configs.clone(), shared.charts.clone())));
+            tasks.push(tokio::spawn(background::purge_charts(shared.charts.clone())));
+        }
+
+        // Dispatcher nur mit Token; eingebettet ohne Token gibt es keine Befehle
+        let dispatcher = match bot {
+            Some(bot) => {
+                register_commands(&bot).await;
+                tasks.push(tokio::spawn(live_updates(bot.clone(), shared.configs.clone(), shared.history.clone(), storage.clone())));
+
+                let pending_input: PendingInput = Arc::new(Mutex::new(HashMap::new()));
+                // Der Befehls-Handler bekommt alle Handles gebündelt; dptree
+                // injiziert ohnehin höchstens neun Parameter. Antworten gehen
+                // an Telegram, auch neben einem eigenen Messenger.
+                let commands = Shared { replies: Arc::new(TelegramMessenger::new(bot.clone())), ..shared.clone() };
+                let handler = update_handler(commands);
+
+                let bot_clone = bot.clone();
+                let mut dispatcher = Dispatcher::builder(bot, handler)
+                    .dependencies(dptree::deps![
+                        shared.configs.clone(),
+                        shared.flags.clone(),
+                        storage.clone(),
+                        shared.uptime.clone(),
+                        shared.escalation.clone(),
+                        shared.charts.clone(),
+                        shared.records.clone(),
+                        pending_input
+                    ])
+                    .error_handler(LoggingErrorHandler::with_custom_text("Fehler beim Bearbeiten eines Updates"))
+                    .build();
+                let shutdown = dispatcher.shutdown_token();
+                Some((
+                    shutdown,
+                    tokio::spawn(async move {
+                        let listener = reactions::listener(bot_clone).await;
+                        let error_handler = LoggingErrorHandler::with_custom_text("Fehler beim Abruf der Updates");
+                        dispatcher.dispatch_with_listener(listener, error_handler).await
+                    }),
+                ))
+            }
+            None => None,
+        };
+
+        Ok(BotHandle {
+            tasks,
+            dispatcher,
+            monitor,
+            stop,
+            outbox,
+            storage,
+            configs: shared.configs,
+            history: shared.history,
+            records: shared.records,
+            uptime: shared.uptime,
+            escalation: shared.escalation,
+            _instance_lock: instance_lock,
+        })
+    }
+}
+
+// Verteilung der Updates: gesperrte Chats, Drosselung, Befehle, offene
+// Eingaben und Freitext, dazu Buttons, Inline-Anfragen und Reaktionen
+fn update_handler(commands: Shared) -> UpdateHandler<RequestError> {
+    let answer = move |bot, msg, cmd| guarded_answer(bot, msg, cmd, commands.clone());
+    dptree::entry()
+        .branch(
+            Update::filter_message()
+                .branch(dptree::filter(|msg: Message| !admitted(msg.chat.id.0)).endpoint(reject_message))
+                .branch(
+                    dptree::filter_map(|msg: Message, me: Me| parse_command(&msg, &me))
+                        .filter_map(|msg: Message| Some(throttle(msg.chat.id.0)).filter(|v| *v != Verdict::Allowed))
+                        .endpoint(reply_throttled),
+                )
+                .branch(dptree::filter_map(|msg: Message, me: Me| parse_command(&msg, &me)).endpoint(answer.clone()))
+                .branch(dptree::filter(|msg: Message| is_restore_document(&msg)).endpoint(handle_restore_document))
+                .branch(dptree::filter_map(|msg: Message, me: Me| command_usage_hint(&msg, &me)).endpoint(reply_usage_hint))
+                .branch(dptree::filter_async(awaiting_input).endpoint(handle_pending_input))
+                .branch(dptree::filter_map(|msg: Message, me: Me| intent_command(&msg, &me)).endpoint(answer))
+                .branch(dptree::filter(|msg: Message| msg.text().is_some()).endpoint(handle_message)),
+        )
+        .branch(
+            Update::filter_callback_query()
+                .branch(dptree::filter(|q: CallbackQuery| q.message.as_ref().is_some_and(|m| !admitted(m.chat.id.0))).endpoint(reject_callback))
+                .branch(dptree::endpoint(guarded_callback)),
+        )
+        .branch(Update::filter_inline_query().endpoint(handle_inline_query))
+        .branch(
+            dptree::filter_map(|update: Update| reactions::parse(&update))
+                .filter(|reaction: ReactionUpdate| admitted(reaction.chat.id))
+                .endpoint(handle_reaction),
+        )
+}
+
+// Gespeicherten Zustand laden: Konfigurationen (mit Umstellungen älterer
+// Stände), Alarm-Zustände, Verlauf, Rekorde und Betriebszeiten
+async fn restore_state(storage: Arc<dyn Store>, replies: Arc<dyn Messenger>) -> Shared {
+    let mut loaded_configs = storage.load_users();
+    let migrated = migrate_configs(&mut loaded_configs);
+    if migrated > 0 {
+        info!("{} Schwellen auf Geräte-IDs umgestellt", migrated);
+        storage.save_users(&loaded_configs);
+    }
+    // Vor dem Neustart gemeldete Verletzungen nicht gleich noch einmal melden
+    let restored_flags = if settings().announce_on_start { HashMap::new() } else { monitor::restored_flags(&loaded_configs) };
+    let user_configs: UserConfigs = Arc::new(Mutex::new(loaded_configs));
+    if !restored_flags.is_empty() {
+        info!("{} laufende Verletzungen aus der Zeit vor dem Neustart übernommen", restored_flags.len());
+    }
+    let threshold_flags: ThresholdFlags = Arc::new(Mutex::new(restored_flags));
+    let mut loaded_history = storage.load_history();
+    if loaded_history.compact(Utc::now().timestamp()) > 0 {
+        storage.save_history(&loaded_history);
+    }
+    let history: SharedHistory = Arc::new(Mutex::new(loaded_history));
+    let records: SharedRecords = Arc::new(Mutex::new(storage.load_records()));
+    *reachability() = storage.load_reachability();
+    *ignored() = storage.load_ignored();
+    *access() = storage.load_access();
+    // Chats von vor der Liste bekannter Chats anhand ihrer Konfiguration übernehmen
+    *known_chats() = storage.load_known_chats();
+    let adopted = user_configs.lock().await.keys().filter(|&&chat_id| known_chats().adopt(chat_id, Utc::now())).count();
+    if adopted > 0 {
+        storage.save_known_chats(&known_chats());
+    }
+    let escalation: SharedEscalation = Arc::new(Mutex::new(Escalation::new(
+        std::time::Duration::from_secs(settings().escalate_interval_seconds),
+        std::time::Duration::from_secs(settings().escalate_minutes * 60),
+    )));
+    let charts: SharedCharts =
+        Arc::new(Mutex::new(ChartCache::new(std::time::Duration::from_secs(settings().chart_ttl_minutes * 60), settings().chart_cache_mb * 1024 * 1024)));
+
+    // Ausfall seit dem letzten Lauf festhalten
+    let mut uptime_log = storage.load_uptime();
+    if let Some(downtime) = uptime_log.start(Utc::now().timestamp()) {
+        info!(
+            "Bot war offline von {} bis {} ({}, {})",
+            format_timestamp(downtime.start, "%d.%m.%Y %H:%M"),
+            format_timestamp(downtime.end, "%d.%m.%Y %H:%M"),
+            format_duration(downtime.end - downtime.start),
+            if downtime.clean { "sauber beendet" } else { "unerwartet beendet" }
+        );
+    }
+    storage.save_uptime(&uptime_log);
+    let uptime: SharedUptime = Arc::new(Mutex::new(uptime_log));
+    Shared { configs: user_configs, flags: threshold_flags, history, records, storage, uptime, escalation, charts, replies }
+}
+
+/// Gründe, aus denen `run` den Bot nicht startet
+#[derive(Debug)]
+pub enum StartError {
+    /// Weder Token noch Messenger angegeben
+    NoMessenger,
+    /// Einstellungen ungültig (z.B. Raumdatei) oder bereits gesetzt
+    Settings(String),
+    /// `run` wurde in diesem Prozess schon einmal aufgerufen
+    AlreadyStarted,
+    /// Eine andere Instanz hält die Sperrdatei
+    AlreadyRunning {
+        pid: Option<u32>,
+        lock_file: PathBuf,
+    },
+    Lock {
+        lock_file: PathBuf,
+        error: std::io::Error,
+    },
+}
+
+impl fmt::Display for StartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartError::NoMessenger => write!(f, "Weder Telegram-Token noch Messenger angegeben"),
+            StartError::Settings(err) => write!(f, "{}", err),
+            StartError::AlreadyStarted => write!(f, "Der Bot wurde in diesem Prozess bereits gestartet"),
+            StartError::AlreadyRunning { pid, lock_file } => write!(
+                f,
+                "Es läuft bereits eine Bot-Instanz (PID {}, Sperre {}). Start abgebrochen.",
+                pid.map(|p| p.to_string()).unwrap_or_else(|| "?".into()),
+                lock_file.display()
+            ),
+            StartError::Lock { lock_file, error } => {
+                write!(f, "Sperrdatei {} kann nicht angelegt werden: {}", lock_file.display(), error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StartError {}
+
+/// Laufzeit und Ausfälle, wie bei /health
+#[derive(Debug, Clone)]
+pub struct Health {
+    pub started_at: Option<i64>,
+    /// Letzter abgeschlossener Überwachungsdurchlauf
+    pub last_heartbeat: Option<i64>,
+    pub downtime: Vec<Downtime>,
+    /// Geräte, die nach einem Alarm gerade häufiger abgefragt werden
+    pub escalated: Vec<String>,
+}
+
+/// Laufender Bot, von [`SensorBotBuilder::run`]
+pub struct BotHandle {
+    tasks: Vec<JoinHandle<()>>,
+    dispatcher: Option<(ShutdownToken, JoinHandle<()>)>,
+    monitor: JoinHandle<()>,
+    stop: watch::Sender<bool>,
+    outbox: Outbox,
+    storage: Arc<dyn Store>,
+    configs: UserConfigs,
+    history: SharedHistory,
+    records: SharedRecords,
+    uptime: SharedUptime,
+    escalation: SharedEscalation,
+    _instance_lock: Option<InstanceLock>,
+}
+
+impl BotHandle {
+    /// Messwerte des letzten erfolgreichen Überwachungsdurchlaufs
+    pub async fn snapshot(&self) -> Vec<SensorData> {
+        latest().as_ref().map(|snapshot| snapshot.readings.clone()).unwrap_or_default()
+    }
+
+    pub async fn health(&self) -> Health {
+        let uptime = self.uptime.lock().await;
+        Health {
+            started_at: uptime.started_at(),
+            last_heartbeat: uptime.last_heartbeat(),
+            downtime: uptime.downtime().to_vec(),
+            escalated: self.escalation.lock().await.active(tokio::time::Instant::now()).into_iter().map(|(device_id, _)| device_id).collect(),
+        }
+    }
+
+    /// Dispatcher beenden, den laufenden Überwachungsdurchlauf abwarten, die
+    /// Warteschlange leeren, alles speichern und das saubere Ende vermerken.
+    /// Zusammen höchstens etwa 10 Sekunden, so lange wartet `docker stop`.
+    pub async fn shutdown(mut self) {
+        info!("Bot wird beendet: keine neuen Befehle mehr");
+        if let Some((shutdown, dispatcher)) = self.dispatcher {
+            match shutdown.shutdown() {
+                Ok(done) => {
+                    done.await;
+                    dispatcher.await.ok();
+                }
+                // noch nicht gestartet
+                Err(_) => dispatcher.abort(),
+            }
+        }
+
+        info!("Warte auf den laufenden Überwachungsdurchlauf");
+        self.stop.send(true).ok();
+        if tokio::time::timeout(SHUTDOWN_MONITOR_WAIT, &mut self.monitor).await.is_err() {
+            warn!("Überwachung nach {:?} abgebrochen", SHUTDOWN_MONITOR_WAIT);
+            self.monitor.abort();
+        }
+
+        match self.outbox.drain(SHUTDOWN_OUTBOX_WAIT).await {
+            0 => info!("Ausgangswarteschlange geleert"),
+            left => warn!("{} Nachrichten nicht mehr zugestellt", left),
+        }
+        // Zeitpläne und Wartungsaufgaben speichern selbst bei jeder Änderung
+        for task in &self.tasks {
+            task.abort();
+        }
+
+        // Gespeichert wird bei jeder Änderung; hier nur zur Sicherheit, falls
+        // eine abgebrochene Aufgabe ihre Änderung nicht mehr schreiben konnte
+        info!("Speichere Zustand");
+        self.storage.save_users(&*self.configs.lock().await);
+        self.storage.save_history(&*self.history.lock().await);
+        self.storage.save_records(&*self.records.lock().await);
+        self.storage.save_known_chats(&known_chats());
+
+        let mut uptime = self.uptime.lock().await;
+        uptime.shutdown(Utc::now().timestamp());
+        self.storage.save_uptime(&uptime);
+        storage::flush(&self.storage).await;
+        info!("Bot sauber beendet");
+    }
+}
+
+// Befehlsmenü in allen Sprachen bei Telegram registrieren.
+// Deutsch ist der Standard, weitere Sprachen gelten per language_code.
+// Nur für den Admin-Chat im Menü; die Befehle prüfen das selbst noch einmal
+const ADMIN_COMMANDS: &[&str] = &[
+    "allow",
+    "backup-now",
+    "broadcast",
+    "debug",
+    "deny",
+    "fleet-report",
+    "ignore",
+    "ignored",
+    "inject",
+    "purge-user",
+    "reload-rooms",
+    "selftest",
+    "set-interval",
+    "weather-location",
+    "setimage",
+    "unignore",
+    "users",
+];
+
+// Telegram erlaubt im Menü nur a-z, 0-9 und _: /quiet-hours steht dort als
+// /quiet_hours, expand_command macht daraus wieder den Befehl
+fn menu_name(command: &str) -> String {
+    command.replace('-', "_")
+}
+
+fn menu_commands(lang: Lang, admin: bool) -> Vec<BotCommand> {
+    Command::bot_commands()
+        .into_iter()
+        .filter_map(|cmd| {
+            let name = cmd.command.trim_start_matches('/').to_string();
+            if !admin && ADMIN_COMMANDS.contains(&name.as_str()) {
+                return None;
+            }
+            let description = match command_description(lang, &name) {
+                Some(text) => text.to_string(),
+                None => {
+                    warn!("Keine Beschreibung für /{} in Sprache {}", name, lang.code());
+                    cmd.description
+                }
+            };
+            Some(BotCommand::new(menu_name(&name), description))
+        })
+        .collect()
+}
+
+// Befehlsmenü je Sprache, im Admin-Chat zusätzlich die Admin-Befehle.
+// Fehler werden nur protokolliert, der Bot startet trotzdem.
+async fn register_commands(bot: &Bot) {
+    let admin = settings().admin_chat;
+    for lang in Lang::ALL {
+        let mut request = bot.set_my_commands(menu_commands(lang, false));
+        if lang != Lang::default() {
+            request = request.language_code(lang.code());
+        }
+        match request.await {
+            Ok(_) => info!("Befehle für Sprache {} registriert", lang.code()),
+            Err(err) => warn!("Registrieren der Befehle für Sprache {} fehlgeschlagen: {}", lang.code(), err),
+        }
+
+        let Some(admin) = admin else { continue };
+        let mut request = bot.set_my_commands(menu_commands(lang, true)).scope(BotCommandScope::Chat { chat_id: Recipient::Id(ChatId(admin)) });
+        if lang != Lang::default() {
+            request = request.language_code(lang.code());
+        }
+        match request.await {
+            Ok(_) => info!("Admin-Befehle für Sprache {} registriert", lang.code()),
+            Err(err) => warn!("Registrieren der Admin-Befehle für Sprache {} fehlgeschlagen: {}", lang.code(), err),
+        }
+    }
+}
+
+// Handler in eigener Aufgabe ausführen, damit eine Panik nur diese eine
+// Anfrage trifft; Err mit dem Text der Panik
+async fn isolated(handler: impl Future<Output = ResponseResult<()>> + Send + 'static) -> Result<ResponseResult<()>, String> {
+    match tokio::spawn(handler).await {
+        Ok(result) => Ok(result),
+        Err(err) if err.is_panic() => {
+            HANDLER_PANICS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            telemetry::handler_panic();
+            let payload = err.into_panic();
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unbekannt".to_string());
+            Err(message)
+        }
+        // Abgebrochen, nur beim Beenden
+        Err(_) => Ok(Ok(())),
+    }
+}
+
+const INTERNAL_ERROR: &str = "⚠️ Interner Fehler, wurde protokolliert.";
+
+async fn guarded_answer(bot: Bot, msg: Message, cmd: Command, shared: Shared) -> ResponseResult<()> {
+    let chat = msg.chat.id;
+    let command = msg.text().and_then(|text| text.split_whitespace().next()).unwrap_or_default().to_string();
+    let (configs, replies) = (shared.configs.clone(), shared.replies.clone());
+    guarded(chat, &command, &configs, replies.as_ref(), answer(bot, msg, cmd, shared)).await
+}
+
+// Befehl isoliert ausführen; nach einer Panik bekommt der Chat trotzdem eine Antwort
+async fn guarded(
+    chat: ChatId,
+    command: &str,
+    configs: &UserConfigs,
+    replies: &dyn Messenger,
+    handler: impl Future<Output = ResponseResult<()>> + Send + 'static,
+) -> ResponseResult<()> {
+    match isolated(handler).await {
+        Ok(result) => result,
+        Err(panic) => {
+            error!("Panik in {} von Chat {}: {}", command, redact::chat(chat.0), panic);
+            let mode = configs.lock().await.get(&chat.0).map(|c| c.notifications).unwrap_or_default();
+            reply(replies, chat, mode, INTERNAL_ERROR).await?;
+            Ok(())
+        }
+    }
+}
+
+async fn guarded_callback(
+    bot: Bot,
+    q: CallbackQuery,
+    configs: UserConfigs,
+    flags: ThresholdFlags,
+    storage: Arc<dyn Store>,
+    pending: PendingInput,
+) -> ResponseResult<()> {
+    let id = q.id.clone();
+    let data = q.data.clone().unwrap_or_default();
+    let chat = q.message.as_ref().map(|message| message.chat.id.0);
+    match isolated(handle_callback(bot.clone(), q, configs, flags, storage, pending)).await {
+        Ok(result) => result,
+        Err(panic) => {
+            error!("Panik bei Button {} in Chat {}: {}", data, chat.map(redact::chat).unwrap_or_default(), panic);
+            bot.answer_callback_query(id).text(INTERNAL_ERROR).show_alert(true).await?;
+            Ok(())
+        }
+    }
+}
+
+// Tageszusammenfassung seit `since`: je Gerät und Typ Min, Max und Mittel
+// aus dem Verlauf sowie die Zahl zugestellter Warnungen (Legacy-Markdown)
+fn daily_summary(config: &UserConfig, chat_id: i64, history: &History, since: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let tenant = tenant_of(chat_id);
+    let mut series: BTreeMap<(&str, &str), Vec<f64>> = BTreeMap::new();
+    for (device, typ, _, value) in history.samples_since(since.timestamp()) {
+        if rooms().visible(tenant.as_deref(), device) && !ignored().contains(device) {
+            series.entry((device, typ)).or_default().push(value);
+        }
+    }
+    let lang = config.lang;
+    let mut text = fill_name(i18n::message(lang, "daily_greeting"), config.first_name.as_deref());
+    text = escape_markdown(&text);
+    let since_text = format_local_in(since, "%d.%m. %H:%M", chat_timezone(Some(config)));
+    text.push_str(&format!("{}\n", i18n::message_with(lang, "daily_title", &[("since", &since_text)])));
+    if series.is_empty() {
+        text.push_str(&format!("{}\n", i18n::message(lang, "period_no_readings")));
+    }
+    for ((device, typ), values) in series {
+        let label = type_label_in(lang, typ).0;
+        let unit = unit_in(config.units, typ);
+        let kind = SensorKind::from(typ);
+        let min = config.units.show(&kind, values.iter().copied().fold(f64::INFINITY, f64::min));
+        let max = config.units.show(&kind, values.iter().copied().fold(f64::NEG_INFINITY, f64::max));
+        let mean = config.units.show(&kind, values.iter().sum::<f64>() / values.len() as f64);
+        let warnings = config
+            .alerts
+            .iter()
+            .filter(|alert| alert.device_id == device && alert.keys.iter().any(|key| key.kind == kind))
+            .filter(|alert| alert.delivered_at.or(alert.observed_at).is_some_and(|at| at >= since.timestamp() && at <= now.timestamp()))
+            .count();
+        text.push_str(&i18n::message_with(
+            lang,
+            "period_line",
+            &[
+                ("room", &markdown_bold(&room_name(device))),
+                ("type", &escape_markdown(label)),
+                ("min", &format!("{:.1}", min)),
+                ("max", &format!("{:.1}", max)),
+                ("mean", &format!("{:.1}", mean)),
+                ("unit", unit),
+            ],
+        ));
+        match warnings {
+            0 => text.push('\n'),
+            1 => text.push_str(&format!(", {}\n", i18n::message(lang, "daily_warning"))),
+            n => text.push_str(&format!(", {}\n", i18n::message_with(lang, "daily_warnings", &[("count", &n.to_string())]))),
+        }
+    }
+    text
+}
+
+// "so 18:00": genau ein Wochentag mit einer Uhrzeit
+fn parse_weekly(spec: &str, lang: Lang) -> Result<WeeklySchedule, String> {
+    let schedule: WeeklySchedule = spec.parse().map_err(|err: Text| err.in_lang(lang))?;
+    let times: Vec<usize> = schedule.weekly_plan().iter().map(|(_, times)| times.len()).filter(|n| *n > 0).collect();
+    if times != [1] {
+        return Err(i18n::message(lang, "weekly_one_time").to_string());
+    }
+    Ok(schedule)
+}
+
+// "21:00" oder "mo-fr 06:30; sa,so 09:00": höchstens eine Uhrzeit je Tag,
+// die Tageszusammenfassung kommt einmal am Tag
+fn parse_daily(spec: &str, lang: Lang) -> Result<WeeklySchedule, String> {
+    let schedule: WeeklySchedule = spec.parse().map_err(|err: Text| err.in_lang(lang))?;
+    if schedule.weekly_plan().iter().any(|(_, times)| times.len() > 1) {
+        return Err(i18n::message(lang, "daily_one_time").to_string());
+    }
+    Ok(schedule)
+}
+
+// Letzter Termin des Wochenberichts bis einschließlich `now`
+fn last_weekly_fire(schedule: &WeeklySchedule, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    next_fire(schedule, now - chrono::Duration::days(7)).filter(|fire| *fire <= now).or_else(|| next_fire(schedule, now - chrono::Duration::days(8)))
+}
+
+// Verlauf der Woche bis `now` je sichtbarem Gerät und Typ, über alle Stufen
+fn week_points(history: &History, chat_id: i64, now: DateTime<Utc>) -> BTreeMap<(&str, &str), Vec<Bucket>> {
+    let tenant = tenant_of(chat_id);
+    let mut series: BTreeMap<(&str, &str), Vec<Bucket>> = BTreeMap::new();
+    for (device, typ, bucket) in history.points_since(now.timestamp() - weekly::WEEK_SECONDS) {
+        if bucket.start <= now.timestamp() && rooms().visible(tenant.as_deref(), device) && !ignored().contains(device) {
+            series.entry((device, typ)).or_default().push(bucket);
+        }
+    }
+    series
+}
+
+// Wochenbericht: je Gerät und Typ Min, Max und Mittel, Alarme und die Zeit
+// außerhalb der Schwellen, alles aus dem gespeicherten Verlauf und Alarmlog,
+// damit ein Neustart nichts zurücksetzt. Lücken zählen nicht mit
+// (Legacy-Markdown).
+fn weekly_report(config: &UserConfig, chat_id: i64, history: &History, now: DateTime<Utc>) -> String {
+    let since = now.timestamp() - weekly::WEEK_SECONDS;
+    let tz = chat_timezone(Some(config));
+    let lang = config.lang;
+    let mut text = escape_markdown(&fill_name(i18n::message(lang, "weekly_greeting"), config.first_name.as_deref()));
+    let (from, to) = (format_timestamp_in(since, "%d.%m.", tz), format_local_in(now, "%d.%m. %H:%M", tz));
+    text.push_str(&format!("{}\n", i18n::message_with(lang, "weekly_title", &[("from", &from), ("to", &to)])));
+    let series = week_points(history, chat_id, now);
+    if series.is_empty() {
+        text.push_str(&format!("{}\n", i18n::message(lang, "period_no_readings")));
+    }
+    for ((device, typ), points) in series {
+        let kind = SensorKind::from(typ);
+        let limits: Vec<(ThresholdDirection, &ThresholdSchedule)> = [ThresholdDirection::Min, ThresholdDirection::Max]
+            .into_iter()
+            .filter_map(|direction| Some((direction, config.thresholds.get(&(device.to_string(), ThresholdKey::new(kind.clone(), direction)))?)))
+            .collect();
+        let outside = |ts: i64, value: f64| {
+            let at = local_time_of(timeutil::from_timestamp(ts));
+            limits
+                .iter()
+                .any(|(direction, schedule)| schedule.active_entry(at).is_some_and(|entry| monitor::in_alarm(value, entry.value, *direction, 0.0, false)))
+        };
+        let Some(stats) = weekly::stats(&points, now.timestamp(), outside) else { continue };
+        text.push_str(&i18n::message_with(
+            lang,
+            "period_line",
+            &[
+                ("room", &markdown_bold(&room_name(device))),
+                ("type", &escape_markdown(type_label_in(lang, typ).0)),
+                ("min", &format!("{:.1}", config.units.show(&kind, stats.min))),
+                ("max", &format!("{:.1}", config.units.show(&kind, stats.max))),
+                ("mean", &format!("{:.1}", config.units.show(&kind, stats.mean))),
+                ("unit", unit_in(config.units, typ)),
+            ],
+        ));
+        let alarms = config.alarm_log.since(since).filter(|entry| !entry.recovered && entry.device_id == device && entry.sensor_type == kind).count();
+        match alarms {
+            0 => {}
+            1 => text.push_str(&format!(", {}", i18n::message(lang, "weekly_alarm"))),
+            n => text.push_str(&format!(", {}", i18n::message_with(lang, "weekly_alarms", &[("count", &n.to_string())]))),
+        }
+        if !limits.is_empty() {
+            match stats.outside {
+                0 => text.push_str(&format!(", {}", i18n::message(lang, "weekly_never_outside"))),
+                seconds => text.push_str(&format!(", {}", i18n::message_with(lang, "weekly_outside", &[("duration", &format_duration(seconds))]))),
+            }
+        }
+        // Größere Lücken erwähnen, sonst wirkt die Zeit außerhalb zu kurz
+        let coverage = stats.covered as f64 / weekly::WEEK_SECONDS as f64;
+        if coverage < 0.95 {
+            text.push_str(&format!(" {}", i18n::message_with(lang, "weekly_coverage", &[("percent", &format!("{:.0}", coverage * 100.0))])));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+// Temperaturen aller Räume der Woche übereinander; None ohne Verlauf oder
+// wenn das Zeichnen scheitert
+#[cfg(feature = "charts")]
+fn weekly_chart(config: &UserConfig, chat_id: i64, history: &History, now: DateTime<Utc>) -> Option<Vec<u8>> {
+    let kind = SensorKind::Temperature;
+    let series: Vec<(String, Vec<(i64, f64)>)> = week_points(history, chat_id, now)
+        .into_iter()
+        .filter(|((_, typ), points)| SensorKind::from(*typ) == kind && points.len() >= MIN_CHART_POINTS)
+        .map(|((device, _), points)| {
+            let shown = points.iter().map(|b| (b.start, config.units.show(&kind, b.mean))).collect();
+            (room_name(device).to_string(), shown)
+        })
+        .collect();
+    if series.is_empty() {
+        return None;
+    }
+    let tz = chat_timezone(Some(config));
+    let label = |ts: i64| format_timestamp_in(ts, "%d.%m.", tz);
+    match plot::render_overlay(i18n::message(config.lang, "weekly_chart_title"), unit_in(config.units, kind.as_str()), &series, &label) {
+        Ok(png) => Some(png),
+        Err(err) => {
+            warn!("Wochendiagramm nicht gezeichnet: {}", err);
+            None
+        }
+    }
+}
+
+// Datei von /backup laden und prüfen, dann mit Vorschau zur Bestätigung
+// anbieten. Schwellen auf Geräten, die der Chat nicht sieht, fallen weg.
+async fn offer_restore(
+    bot: &Bot,
+    msg: &Message,
+    document: &teloxide::types::Document,
+    tz: Option<Tz>,
+    mode: NotificationMode,
+    lang: Lang,
+) -> ResponseResult<()> {
+    let chat = msg.chat.id;
+    if document.file.size > MAX_BACKUP_BYTES {
+        reply(bot, chat, mode, i18n::message(lang, "restore_too_large")).await?;
+        return Ok(());
+    }
+    let mut content = Vec::new();
+    let downloaded = match bot.get_file(document.file.id.clone()).await {
+        Ok(file) => bot.download_file(&file.path, &mut content).await.map_err(|err| err.to_string()),
+        Err(err) => Err(err.to_string()),
+    };
+    if let Err(err) = downloaded {
+        warn!("Sicherungsdatei von {} nicht geladen: {}", redact::chat(chat.0), err);
+        reply(bot, chat, mode, i18n::message(lang, "restore_download_failed")).await?;
+        return Ok(());
+    }
+    let mut backup = match parse_config_backup(&content, lang) {
+        Ok(backup) => backup,
+        Err(err) => {
+            reply(bot, chat, mode, format!("❌ {}", err)).await?;
+            return Ok(());
+        }
+    };
+
+    let tenant = tenant_of(chat.0);
+    let config = &mut backup.config;
+    let before = config.thresholds.len();
+    config.thresholds.retain(|(device, _), _| rooms().visible(tenant.as_deref(), device));
+    let dropped = before - config.thresholds.len();
+    config.rates.retain(|(device, _), _| rooms().visible(tenant.as_deref(), device));
+    config.relations.retain(|(device, _), rule| rooms().visible(tenant.as_deref(), device) && rooms().visible(tenant.as_deref(), &rule.other_device));
+    config.configured_by.retain(|key, _| config.thresholds.contains_key(key));
+    config.hysteresis.retain(|key, _| config.thresholds.contains_key(key));
+    config.repeat.retain(|key, _| config.thresholds.contains_key(key));
+    config.alert_styles.retain(|key, _| config.thresholds.contains_key(key));
+
+    let created = format_local_in(backup.created_at, "%d.%m.%Y %H:%M", tz);
+    let mut preview = format!("{}\n{}", i18n::message_with(lang, "restore_preview", &[("created", &created)]), backup_summary(config, lang));
+    if backup.chat_id != chat.0 {
+        preview.push_str(&format!("\n{}", i18n::message(lang, "restore_other_chat")));
+    }
+    if dropped > 0 {
+        preview.push_str(&format!("\n{}", i18n::message_with(lang, "restore_dropped", &[("count", &dropped.to_string())])));
+    }
+    preview.push_str(&format!("\n\n{}", i18n::message(lang, "restore_replaces")));
+    ask_confirmation(bot, msg, mode, lang, PendingChange::Restore(Box::new(backup.config)), preview).await
+}
+
+// Dokument mit der Bildunterschrift /restore (auch /restore@bot)
+fn is_restore_document(msg: &Message) -> bool {
+    msg.document().is_some()
+        && msg.caption().and_then(|caption| caption.split_whitespace().next()).is_some_and(|command| command == "/restore" || command.starts_with("/restore@"))
+}
+
+async fn handle_restore_document(bot: Bot, msg: Message, configs: UserConfigs) -> ResponseResult<()> {
+    let (tz, mode, lang) = {
+        let configs = configs.lock().await;
+        let config = configs.get(&msg.chat.id.0);
+        (chat_timezone(config), config.map(|c| c.notifications).unwrap_or_default(), config.map(|c| c.lang).unwrap_or_default())
+    };
+    match msg.document() {
+        Some(document) => offer_restore(&bot, &msg, document, tz, mode, lang).await,
+        None => Ok(()),
+    }
+}
+
+// Andere Chats mit Schwellen auf dem Gerät und deren Anzahl
+fn threshold_holders(user_configs: &HashMap<i64, UserConfig>, device_id: &str, except: i64) -> Vec<(i64, usize)> {
+    user_configs
+        .iter()
+        .filter(|(chat, _)| **chat != except)
+        .map(|(chat, config)| (*chat, config.thresholds.keys().filter(|(d, _)| d == device_id).count()))
+        .filter(|(_, count)| *count > 0)
+        .collect()
+}
+
+// Vorschau mit "✅ Anwenden" und "❌ Abbrechen"; angewendet wird erst in handle_callback
+async fn ask_confirmation(
+    replies: &dyn Messenger,
+    msg: &Message,
+    mode: NotificationMode,
+    lang: Lang,
+    change: PendingChange,
+    preview: String,
+) -> ResponseResult<()> {
+    let owner = msg.from().map(|user| user.id.0 as i64).unwrap_or(msg.chat.id.0);
+    let token = random_token();
+    let buttons = confirm::buttons(&token, lang);
+    CONFIRMATIONS.lock().unwrap_or_else(|e| e.into_inner()).insert(token, owner, change, std::time::Instant::now());
+    let within = i18n::message_with(lang, "confirm_within", &[("duration", &format_duration(confirm::CONFIRM_SECONDS as i64))]);
+    let text = format!("{}\n\n{}", preview, within);
+    reply(replies, msg.chat.id, mode, text).reply_markup(buttons).await?;
+    Ok(())
+}
+
+// Bestätigte Änderung ausführen; liefert den Text, der die Vorschau ersetzt
+async fn apply_change(bot: &Bot, chat: ChatId, change: PendingChange, configs: &UserConfigs, storage: &dyn Store) -> String {
+    let mut user_configs = configs.lock().await;
+    let lang = user_configs.get(&chat.0).map(|c| c.lang).unwrap_or_default();
+    let text = match change {
+        PendingChange::ClearAll => match user_configs.remove(&chat.0) {
+            Some(config) => {
+                let mut archive = storage.load_archive();
+                let until = archive.insert(chat.0, config, Utc::now()).restorable_until();
+                storage.save_archive(&archive);
+                info!("Konfiguration von {} archiviert", redact::chat(chat.0));
+                i18n::message_with(lang, "clear_done", &[("until", &format_local(until, "%d.%m.%Y %H:%M"))])
+            }
+            None => i18n::message(lang, "clear_nothing").to_string(),
+        },
+        PendingChange::Forget => {
+            // Ohne Archiv und ohne Frist; nur nächtliche Sicherungen enthalten
+            // den alten Stand noch, bis sie wegrotiert sind
+            let live = forget_chat(chat.0, &mut user_configs, storage).is_some();
+            info!("Daten von {} endgültig gelöscht", redact::chat(chat.0));
+            i18n::message(lang, if live { "forget_done" } else { "forget_nothing" }).to_string()
+        }
+        PendingChange::Restore(backup) => {
+            let current = user_configs.remove(&chat.0).unwrap_or_default();
+            user_configs.insert(chat.0, current.replaced_by(*backup));
+            info!("Konfiguration von {} aus Sicherungsdatei übernommen", redact::chat(chat.0));
+            i18n::message(lang, "restore_done").to_string()
+        }
+        PendingChange::Ignore { device, by } => {
+            if !ignored().insert(&device, by, Utc::now()) {
+                return i18n::message_with(lang, "ignore_already", &[("room", &room_name(&device))]);
+            }
+            storage.save_ignored(&ignored());
+            info!("Gerät {} wird ignoriert", device);
+            // Einmalig alle informieren, die dort Schwellen haben
+            let affected: Vec<(i64, Lang)> = threshold_holders(&user_configs, &device, chat.0)
+                .into_iter()
+                .map(|(holder, _)| (holder, user_configs.get(&holder).map(|c| c.lang).unwrap_or_default()))
+                .collect();
+            drop(user_configs);
+            for (holder, holder_lang) in &affected {
+                let notice = i18n::message_with(*holder_lang, "ignore_notice", &[("room", &room_name(&device))]);
+                if let Err(err) = bot.send_message(ChatId(*holder), notice).await {
+                    warn!("Hinweis zu ignoriertem Gerät an {} fehlgeschlagen: {}", redact::chat(*holder), err);
+                }
+            }
+            return i18n::message_with(lang, "ignore_done", &[("room", &room_name(&device)), ("count", &affected.len().to_string())]);
+        }
+    };
+    storage.save_users(&user_configs);
+    text
+}
+
+// "✅ Anwenden" oder "❌ Abbrechen" unter einer Vorschau
+async fn handle_confirmation(bot: Bot, q: CallbackQuery, apply: bool, token: &str, configs: UserConfigs, storage: Arc<dyn Store>) -> ResponseResult<()> {
+    let user = q.from.id.0 as i64;
+    let chat = q.message.as_ref().map(|message| message.chat.id.0).unwrap_or(user);
+    let lang = configs.lock().await.get(&chat).map(|c| c.lang).unwrap_or_default();
+    let taken = CONFIRMATIONS.lock().unwrap_or_else(|e| e.into_inner()).take(token, user, apply, std::time::Instant::now());
+    let text = match taken {
+        Taken::Apply(change) => {
+            let Some(message) = q.message.as_ref() else {
+                bot.answer_callback_query(q.id).await?;
+                return Ok(());
+            };
+            apply_change(&bot, message.chat.id, change, &configs, storage.as_ref()).await
+        }
+        Taken::Cancelled => i18n::message(lang, "confirm_cancelled").to_string(),
+        Taken::Expired => i18n::message(lang, "confirm_expired").to_string(),
+        Taken::Missing => {
+            bot.answer_callback_query(q.id).text(i18n::message(lang, "confirm_missing")).await?;
+            return Ok(());
+        }
+        Taken::Foreign => {
+            bot.answer_callback_query(q.id).text(i18n::message(lang, "confirm_foreign")).show_alert(true).await?;
+            return Ok(());
+        }
+    };
+    bot.answer_callback_query(q.id.clone()).await?;
+    if let Some(message) = q.message.as_ref() {
+        // Ohne reply_markup verschwinden die Buttons
+        bot.edit_message_text(message.chat.id, message.id, text).await?;
+    }
+    Ok(())
+}
+
+// Nicht erratbares Token aus 32 alphanumerischen Zeichen (CSPRNG)
+fn random_token() -> String {
+    rand::thread_rng().sample_iter(&rand::distributions::Alphanumeric).take(32).map(char::from).collect()
+}
+
+// Liefert die URL ein PNG, kommt es als Foto. Mit HTTP-Server steht in der
+// Beschriftung ein befristeter Link auf die volle Auflösung, die Telegram
+// beim Foto wegkomprimiert.
+#[cfg(feature = "charts")]
+async fn fetch_png(url: &reqwest::Url) -> Option<Vec<u8>> {
+    let response = reqwest::get(url.clone()).await.ok()?.error_for_status().ok()?;
+    let is_png =
+        response.headers().get(reqwest::header::CONTENT_TYPE).and_then(|value| value.to_str().ok()).is_some_and(|value| value.starts_with("image/png"));
+    if !is_png {
+        return None;
+    }
+    response.bytes().await.ok().map(|bytes| bytes.to_vec())
+}
+
+// Diagramme eines Raums senden: aus dem Verlauf gezeichnet, sonst aus den
+// Werten des ThingSpeak-Felds bzw. dem Bild oder Link aus dem
+// Raumverzeichnis, sonst die Werte als Text.
+// Ohne Typ werden alle Messgrößen des Raums gesendet, ohne Treffer die
+// verfügbaren Räume gelistet.
+#[cfg(feature = "charts")]
+#[allow(deprecated, clippy::too_many_arguments)]
+async fn send_chart(
+    bot: &Bot,
+    chart_cache: &SharedCharts,
+    history: &SharedHistory,
+    config: Option<&UserConfig>,
+    user_id: ChatId,
+    room: &str,
+    sensor_type: Option<&str>,
+    hours: i64,
+) -> ResponseResult<()> {
+    let tenant = tenant_of(user_id.0);
+    let mode = config.map(|c| c.notifications).unwrap_or_default();
+    let lang = config.map(|c| c.lang).unwrap_or_default();
+    let registry = rooms();
+    let Some(found) = registry.find_in(tenant.as_deref(), room).filter(|_| !room.is_empty()) else {
+        let text = if registry.rooms_in(tenant.as_deref()).next().is_none() {
+            i18n::message(lang, "chart_no_rooms").to_string()
+        } else {
+            i18n::message_with(lang, "chart_usage", &[("rooms", &room_names(user_id.0))])
+        };
+        reply(bot, user_id, mode, text).await?;
+        return Ok(());
+    };
+
+    let now = Utc::now().timestamp();
+    let since = now - hours * 60 * 60;
+    // Messgrößen aus dem Verlauf und dem Raumverzeichnis, ohne die Sperre über das Senden zu halten
+    let series: Vec<(String, Vec<(i64, f64)>)> = {
+        let history = history.lock().await;
+        let mut types: Vec<String> = history.sensor_types(&found.device).into_iter().map(str::to_string).collect();
+        types.extend(found.charts.keys().cloned());
+        types.sort();
+        types.dedup();
+        types
+            .into_iter()
+            .filter(|typ| sensor_type.is_none_or(|t| t == typ.as_str()))
+            .map(|typ| {
+                let samples = recorded_samples(&history, &found.device, &typ, since);
+                (typ, samples)
+            })
+            .collect()
+    };
+    if series.is_empty() {
+        reply(bot, user_id, mode, i18n::message_with(lang, "chart_none", &[("room", &found.name)])).await?;
+        return Ok(());
+    }
+
+    let tz = chat_timezone(config);
+    let label = |ts: i64| format_timestamp_in(ts, if hours > 24 { "%d.%m." } else { "%H:%M" }, tz);
+    let units = config.map(|c| c.units).unwrap_or_default();
+    for (typ, mut samples) in series {
+        let kind = SensorKind::from(typ.as_str());
+        let symbol = if kind == SensorKind::Humidity { "💧" } else { "📈" };
+        let typ_label = type_label_in(lang, &typ).0;
+        let unit = unit_in(units, &typ);
+        let mut title = format!("{} {}", symbol, markdown_bold(&format!("{} – {}:", found.name, typ_label)));
+        // Zu wenig eigener Verlauf: Werte direkt beim ThingSpeak-Kanal abfragen
+        let feed = found.feeds.get(&typ).filter(|_| samples.len() < MIN_CHART_POINTS);
+        let mut feed_note = None;
+        if let Some(feed) = feed {
+            let key = feed.read_key.as_deref().or(settings().thingspeak_read_key.as_deref());
+            match thingspeak::field_feed(feed.channel, feed.field, key, since).await {
+                Ok(points) if points.len() > samples.len() => {
+                    samples = points;
+                    title.push_str(&format!("\n{}", i18n::message_with(lang, "chart_feed_source", &[("channel", &feed.channel.to_string())])));
+                }
+                Ok(_) => feed_note = Some(i18n::message_with(lang, "chart_feed_empty", &[("channel", &feed.channel.to_string())])),
+                Err(err) => {
+                    warn!("ThingSpeak-Kanal {} Feld {}: {}", feed.channel, feed.field, err);
+                    feed_note = Some(i18n::message_with(lang, "chart_feed_failed", &[("channel", &feed.channel.to_string()), ("error", &err.to_string())]));
+                }
+            }
+        }
+        let mut png = None;
+        if samples.len() >= MIN_CHART_POINTS {
+            let mut lines = chart_lines(config, &found.device, &typ);
+            for line in &mut lines {
+                line.value = units.show(&kind, line.value);
+            }
+            let shown: Vec<(i64, f64)> = samples.iter().map(|&(ts, value)| (ts, units.show(&kind, value))).collect();
+            let heading =
+                i18n::message_with(lang, "chart_heading", &[("room", &found.name), ("type", typ_label), ("duration", &format_duration(hours * 60 * 60))]);
+            match plot::render(&heading, unit, &shown, &lines, &label) {
+                Ok(rendered) => png = Some(rendered),
+                Err(err) => warn!("Diagramm für {} {} nicht gezeichnet: {}", found.device, typ, err),
+            }
+        }
+        // Feste Bilder bzw. Links nur ohne abrufbares Feld, die Linkvorschau ist unzuverlässig
+        if png.is_none()
+            && feed.is_none()
+            && let Some(url) = found.charts.get(&typ)
+        {
+            png = fetch_png(url).await;
+            if png.is_none() {
+                reply(bot, user_id, mode, title).parse_mode(ParseMode::Markdown).await?;
+                // Ohne Formatierung zeigt Telegram die Linkvorschau
+                reply(bot, user_id, mode, url.as_str()).await?;
+                continue;
+            }
+        }
+        let Some(png) = png else {
+            // Zu wenige Werte für eine Linie, dann wenigstens die Zahlen
+            let mut text = format_history(&samples, &found.device, &typ, hours, now, lang, units, tz);
+            if let Some(note) = &feed_note {
+                text.push_str(&format!("\nℹ️ {}", note));
+            }
+            reply(bot, user_id, mode, format!("{}\n{}", text, i18n::message(lang, "chart_too_few"))).await?;
+            continue;
+        };
+        let mut caption = title;
+        if cfg!(feature = "http-api") && settings().http_addr.is_some() {
+            let mut cache = chart_cache.lock().await;
+            let ttl = cache.ttl().as_secs() as i64;
+            if let Some(token) = cache.insert(png.clone(), tokio::time::Instant::now()) {
+                let url = format!("{}/charts/{}.png", settings().http_public_url.trim_end_matches('/'), token);
+                caption.push_str(&format!("\n{}", i18n::message_with(lang, "chart_full_size", &[("ttl", &format_duration(ttl)), ("url", &url)])));
+            }
+        }
+        bot.send_photo(user_id, teloxide::types::InputFile::memory(png))
+            .caption(caption)
+            .disable_notification(mode.silent(MessageKind::Reply))
+            .parse_mode(ParseMode::Markdown)
+            .await?;
+    }
+    Ok(())
+}
+
+// Jetzt gültige Min- und Max-Schwellen des Chats für das Diagramm
+#[cfg(feature = "charts")]
+fn chart_lines(config: Option<&UserConfig>, device_id: &str, sensor_type: &str) -> Vec<plot::Line<'static>> {
+    let now = local_time();
+    [(ThresholdDirection::Min, "Min"), (ThresholdDirection::Max, "Max")]
+        .into_iter()
+        .filter_map(|(direction, label)| {
+            let key = (device_id.to_string(), ThresholdKey::new(SensorKind::from(sensor_type), direction));
+            let entry = config?.active_threshold(&key, now)?;
+            Some(plot::Line { value: entry.value, label, max: direction == ThresholdDirection::Max })
+        })
+        .collect()
+}
+
+// Zeitraum für /chart: "12h", "3d" oder Stunden als Zahl
+fn parse_chart_hours(text: &str) -> Option<i64> {
+    let text = text.trim().to_lowercase();
+    let hours = match text.strip_suffix('d') {
+        Some(days) => days.parse::<i64>().ok()?.checked_mul(24)?,
+        None => text.strip_suffix('h').unwrap_or(&text).parse().ok()?,
+    };
+    Some(hours).filter(|h| (1..=max_history_hours()).contains(h))
+}
+
+#[cfg(not(feature = "charts"))]
+#[allow(clippy::too_many_arguments)]
+async fn send_chart(
+    bot: &Bot,
+    _chart_cache: &SharedCharts,
+    _history: &SharedHistory,
+    config: Option<&UserConfig>,
+    user_id: ChatId,
+    _room: &str,
+    _sensor_type: Option<&str>,
+    _hours: i64,
+) -> ResponseResult<()> {
+    let mode = config.map(|c| c.notifications).unwrap_or_default();
+    reply(bot, user_id, mode, i18n::message(config.map(|c| c.lang).unwrap_or_default(), "chart_disabled")).await?;
+    Ok(())
+}
+
+// Konfiguration, Archiv, wartende Bilder, Erreichbarkeit und Eintrag in den
+// bekannten Chats entfernen (/forgetme, /purge-user); gibt die bisherige
+// Konfiguration zurück
+fn forget_chat(chat_id: i64, user_configs: &mut HashMap<i64, UserConfig>, storage: &dyn Store) -> Option<UserConfig> {
+    let config = user_configs.remove(&chat_id);
+    let mut archive = storage.load_archive();
+    if archive.forget(chat_id) {
+        storage.save_archive(&archive);
+    }
+    PENDING_IMAGES.lock().unwrap_or_else(|e| e.into_inner()).remove(&chat_id);
+    if reachability().forget(chat_id) {
+        storage.save_reachability(&reachability());
+    }
+    if known_chats().forget(chat_id) {
+        storage.save_known_chats(&known_chats());
+    }
+    config
+}
+
+// Wie forget_chat, zusätzlich ohne Alarmzustände, damit eine später neu
+// angelegte gleiche Schwelle wieder von vorn beginnt. Gespeichert wird die
+// Konfiguration hier; gibt die Zahl der entfernten Schwellen zurück.
+fn purge_chat(chat_id: i64, user_configs: &mut HashMap<i64, UserConfig>, flags: &mut monitor::Flags, storage: &dyn Store) -> Option<usize> {
+    flags.retain(|(chat, _, _), _| *chat != chat_id);
+    let config = forget_chat(chat_id, user_configs, storage)?;
+    storage.save_users(user_configs);
+    Some(config.thresholds.len())
+}
+
+// Chats, die den Bot seit mehr als BLOCKED_PURGE_DAYS blockieren, vergessen
+async fn purge_blocked(configs: &UserConfigs, flags: &ThresholdFlags, storage: &dyn Store) {
+    let days = settings().blocked_purge_days;
+    if days == 0 {
+        return;
+    }
+    let chats = reachability().failing_since(messenger::BLOCKED, Utc::now() - chrono::Duration::days(days));
+    if chats.is_empty() {
+        return;
+    }
+    let mut user_configs = configs.lock().await;
+    let mut flags = flags.lock().await;
+    for chat_id in chats {
+        let thresholds = purge_chat(chat_id, &mut user_configs, &mut flags, storage);
+        info!("{} blockiert den Bot seit über {} Tagen, Daten gelöscht ({} Schwellen)", redact::chat(chat_id), days, thresholds.unwrap_or(0));
+    }
+}
+
+// Mit /clear all gelöschte Konfigurationen nach Ablauf der Frist endgültig entfernen
+fn purge_archive(storage: &dyn Store) {
+    let mut archive = storage.load_archive();
+    let purged = archive.purge(Utc::now());
+    if purged > 0 {
+        storage.save_archive(&archive);
+        info!("{} archivierte Konfigurationen endgültig gelöscht", purged);
+    }
+}
+
+// Geräte-Wochenbericht über die letzten sieben Tage
+fn fleet_report(history: &History, lang: Lang) -> Vec<String> {
+    let end = Utc::now().timestamp();
+    let start = end - 7 * 24 * 60 * 60;
+    let mut weeks = fleet::summarize(history, start, end, settings().cadence_factor, poll_interval() as i64);
+    weeks.retain(|week| !ignored().contains(&week.device_id));
+    fleet::format_report(&weeks, start, end, lang)
+}
+
+// Abendliche Hinweise für Räume im Freien als (Chat, Text): Frostgefahr im
+// Frühjahr an alle, die dort Schwellen oder eine Gießerinnerung haben, und
+// die Gießerinnerung nach mehreren heißen Tagen in Folge
+fn outdoor_evening(configs: &HashMap<i64, UserConfig>, history: &History, now: DateTime<Utc>) -> Vec<(i64, String)> {
+    let tz = settings().timezone;
+    let today = timeutil::local_date_of(now, tz);
+    let frost_season = outdoor::FROST_MONTHS.contains(&chrono::Datelike::month(&today));
+    let until = next_fire(&WeeklySchedule::daily(FROST_UNTIL), now).map(|at| at.timestamp());
+    let since = now.timestamp() - (MAX_WATERING_DAYS as i64 + 1) * 24 * 60 * 60;
+    let points = history.points_since(since);
+
+    let mut messages = Vec::new();
+    for room in rooms().rooms().iter().filter(|r| r.outdoor && !ignored().contains(&r.device)) {
+        let name = escape_markdown(&room.name);
+        let frost = match (frost_season, until, history.series(&room.device, "temperature")) {
+            (true, Some(until), Some(series)) => {
+                let samples: Vec<(i64, f64)> = series.iter().copied().collect();
+                outdoor::projected_min(&samples, now.timestamp(), until).filter(|min| *min < outdoor::FROST_BELOW).zip(samples.last().map(|(_, value)| *value))
+            }
+            _ => None,
+        };
+        let maxima = outdoor::daily_max(
+            points.iter().filter(|(device, typ, _)| *device == room.device && *typ == "temperature").map(|(_, _, bucket)| (bucket.start, bucket.max)),
+            |ts| timeutil::local_date_of(timeutil::from_timestamp(ts), tz),
+        );
+
+        for (&chat_id, config) in configs {
+            if !rooms().visible(tenant_of(chat_id).as_deref(), &room.device) || config.is_muted(now) {
+                continue;
+            }
+            let watches = config.watering.is_some() || config.thresholds.keys().any(|(device, _)| *device == room.device);
+            if let Some((min, latest)) = frost
+                && watches
+            {
+                let text = i18n::message_with(
+                    config.lang,
+                    "frost_warning",
+                    &[
+                        ("room", &name),
+                        ("now", &format!("{:.1}", latest)),
+                        ("until", &FROST_UNTIL.format("%H:%M").to_string()),
+                        ("min", &format!("{:.1}", min)),
+                    ],
+                );
+                messages.push((chat_id, text));
+            }
+            if let Some(watering) = config.watering
+                && outdoor::hot_streak(&maxima, today, watering)
+            {
+                let max = maxima.get(&today).copied().unwrap_or_default();
+                let text = i18n::message_with(
+                    config.lang,
+                    "watering_reminder",
+                    &[("room", &name), ("days", &watering.days.to_string()), ("above", &format!("{:.1}", watering.above)), ("max", &format!("{:.1}", max))],
+                );
+                messages.push((chat_id, text));
+            }
+        }
+    }
+    messages
+}
+
+fn access() -> std::sync::MutexGuard<'static, AccessList> {
+    ACCESS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Darf der Chat den Bot benutzen? Der Admin immer, sonst zuerst /allow bzw.
+// /deny, dann Mitglieder weiterer Haushalte, Ziele aus [routing] und
+// ALLOWED_CHAT_IDS. Gibt es weder Freigaben noch ALLOWED_CHAT_IDS, ist der
+// Bot für alle offen.
+fn admitted(chat_id: i64) -> bool {
+    if settings().admin_chat == Some(chat_id) {
+        return true;
+    }
+    let access = access();
+    if let Some(allowed) = access.decision(chat_id) {
+        return allowed;
+    }
+    let listed = &settings().allowed_chats;
+    (listed.is_empty() && !access.restricts())
+        || listed.contains(&chat_id)
+        || rooms().tenant_of(chat_id).is_some()
+        || rooms().routing().all_targets().contains_key(&chat_id)
+}
+
+// Zu viele Befehle in kurzer Zeit? Jeder Befehl löst womöglich eine Abfrage
+// der Sensoren aus. Der Admin und COMMAND_RATE_PER_MINUTE=0 sind frei.
+fn throttle(chat_id: i64) -> Verdict {
+    if settings().command_rate_per_minute == 0 || settings().admin_chat == Some(chat_id) {
+        return Verdict::Allowed;
+    }
+    RATE_LIMITER.lock().unwrap_or_else(|e| e.into_inner()).check(chat_id, std::time::Instant::now())
+}
+
+async fn reply_throttled(bot: Bot, msg: Message, verdict: Verdict, configs: UserConfigs) -> ResponseResult<()> {
+    if verdict == Verdict::Warn {
+        info!("Chat {} gebremst: zu viele Befehle", redact::chat(msg.chat.id.0));
+        let (mode, lang) = configs.lock().await.get(&msg.chat.id.0).map(|c| (c.notifications, c.lang)).unwrap_or_default();
+        reply(&bot, msg.chat.id, mode, i18n::message(lang, "throttled")).await?;
+    }
+    Ok(())
+}
+
+// Nicht freigeschaltete Chats haben keine Einstellungen; es gilt die
+// Sprache aus dem Telegram-Profil, sonst Deutsch
+fn profile_lang(user: Option<&teloxide::types::User>) -> Lang {
+    user.and_then(|user| user.language_code.as_deref()).and_then(|code| code.split('-').next()?.parse().ok()).unwrap_or_default()
+}
+
+async fn reject_message(bot: Bot, msg: Message) -> ResponseResult<()> {
+    warn!("Zugriff verweigert: Chat {} ({})", redact::chat(msg.chat.id.0), msg.text().unwrap_or_default().split_whitespace().next().unwrap_or("-"));
+    let lang = profile_lang(msg.from());
+    bot.send_message(msg.chat.id, format!("{}\nChat-ID: {}", i18n::message(lang, "not_admitted"), msg.chat.id.0)).await?;
+    Ok(())
+}
+
+// Verunglückter Befehl, erst in der Antwort in der Sprache des Chats
+#[derive(Debug, Clone)]
+enum UsageHint {
+    Suggest(Vec<String>),
+    Invalid { command: String, error: ArgumentError },
+}
+
+#[derive(Debug, Clone)]
+enum ArgumentError {
+    MissingValue,
+    NotANumber(String),
+    TooFew,
+    TooMany,
+    Invalid(Text),
+    Other(String),
+}
+
+// Bekannter Befehl mit unlesbaren Argumenten: statt der englischen
+// Fehlermeldung von teloxide ein Hinweis mit der Verwendung.
+// None für alles andere, das geht an die übrigen Zweige.
+fn command_usage_hint(msg: &Message, me: &Me) -> Option<UsageHint> {
+    let text = expand_command(msg.text()?);
+    let text = text.as_str();
+    let head = text.strip_prefix('/')?.split_whitespace().next()?;
+    let (name, addressed) = head.split_once('@').map_or((head, false), |(name, _)| (name, true));
+    let name = name.to_lowercase();
+    let error = match Command::parse(text, me.username()) {
+        // Vorschläge nur, wo der Bot sicher gemeint ist, nicht für Befehle
+        // anderer Bots in Gruppen
+        Err(ParseError::UnknownCommand(_)) if msg.chat.is_private() || addressed => {
+            let commands: Vec<String> = Command::bot_commands().into_iter().map(|c| c.command.trim_start_matches('/').to_string()).collect();
+            let found = shortcuts::suggest(&name, commands.iter().map(String::as_str).chain(replies().aliases()));
+            if found.is_empty() {
+                return None;
+            }
+            return Some(UsageHint::Suggest(found.iter().map(|command| format!("/{}", command)).collect()));
+        }
+        Ok(_) | Err(ParseError::UnknownCommand(_) | ParseError::WrongBotName(_)) => return None,
+        Err(ParseError::IncorrectFormat(err)) if err.is::<std::num::ParseFloatError>() => match text.split_whitespace().nth(1) {
+            None => ArgumentError::MissingValue,
+            Some(argument) => ArgumentError::NotANumber(argument.to_string()),
+        },
+        Err(ParseError::TooFewArguments { .. }) => ArgumentError::TooFew,
+        Err(ParseError::TooManyArguments { .. }) => ArgumentError::TooMany,
+        Err(ParseError::IncorrectFormat(err) | ParseError::Custom(err)) => match err.downcast::<Text>() {
+            Ok(text) => ArgumentError::Invalid(*text),
+            Err(err) => ArgumentError::Other(err.to_string()),
+        },
+    };
+    Command::bot_commands().into_iter().any(|c| c.command.trim_start_matches('/') == name).then_some(UsageHint::Invalid { command: name, error })
+}
+
+fn format_usage_hint(hint: &UsageHint, lang: Lang) -> String {
+    let (command, error) = match hint {
+        UsageHint::Suggest(found) => {
+            return i18n::message_with(lang, "did_you_mean", &[("commands", &found.join(i18n::message(lang, "or")))]);
+        }
+        UsageHint::Invalid { command, error } => (command, error),
+    };
+    let error = match error {
+        ArgumentError::MissingValue => i18n::message(lang, "argument_missing").to_string(),
+        ArgumentError::NotANumber(argument) => i18n::message_with(lang, "argument_not_a_number", &[("argument", argument)]),
+        ArgumentError::TooFew => i18n::message(lang, "arguments_too_few").to_string(),
+        ArgumentError::TooMany => i18n::message(lang, "arguments_too_many").to_string(),
+        ArgumentError::Invalid(text) => text.in_lang(lang),
+        ArgumentError::Other(err) => err.clone(),
+    };
+    let description = command_description(lang, command)
+        .map(str::to_string)
+        .or_else(|| Command::bot_commands().into_iter().find(|c| c.command.trim_start_matches('/') == command).map(|c| c.description))
+        .unwrap_or_default();
+    format!("❌ {}\nℹ️ /{}: {}", error, command, description)
+}
+
+// Höchstens so viele Wörter nach dem Befehl werden als Abkürzung aufgelöst;
+// der Rest (Notizen, Texte) bleibt, wie er ist
+const ABBREVIATED_ARGUMENTS: usize = 2;
+
+// Kurzformen aus REPLIES_FILE auflösen, bevor teloxide den Befehl parst:
+// "/t" -> "/status", "/status wz" -> "/status wohnzimmer". Echte Befehle
+// gehen gleichnamigen Kurzformen vor; Text ohne Schrägstrich bleibt. Die
+// Schreibweise aus dem Menü (/quiet_hours) wird zum Befehl (/quiet-hours).
+fn expand_command(text: &str) -> String {
+    let Some(rest) = text.strip_prefix('/') else { return text.to_string() };
+    let (head, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let (name, bot_name) = head.split_once('@').map_or((head, None), |(name, bot_name)| (name, Some(bot_name)));
+    let command =
+        |name: &str| Command::bot_commands().into_iter().map(|c| c.command.trim_start_matches('/').to_string()).find(|c| c.eq_ignore_ascii_case(name));
+    let known = command(name).is_some();
+    let menu = command(&name.replace('_', "-")).filter(|_| !known && name.contains('_'));
+    let alias = menu.as_deref().or_else(|| replies().alias(name).filter(|_| !known));
+    let abbreviated = args.split_whitespace().take(ABBREVIATED_ARGUMENTS).any(|word| replies().abbreviation(word).is_some());
+    // Unverändert zurück, damit Zeilenumbrüche und Leerzeichen bleiben
+    if alias.is_none() && !abbreviated {
+        return text.to_string();
+    }
+    let (name, alias_args) = match alias {
+        Some(alias) => alias.split_once(' ').map_or((alias, ""), |(name, args)| (name, args.trim())),
+        None => (name, ""),
+    };
+    let args = match (alias_args, args) {
+        ("", args) => args.to_string(),
+        (alias_args, "") => alias_args.to_string(),
+        (alias_args, args) => format!("{} {}", alias_args, args),
+    };
+    let mut expanded = format!("/{}", name);
+    if let Some(bot_name) = bot_name {
+        expanded.push_str(&format!("@{}", bot_name));
+    }
+    if !args.is_empty() {
+        expanded.push(' ');
+    }
+    let mut words = 0;
+    for piece in args.split_inclusive(char::is_whitespace) {
+        let word = piece.trim_end();
+        if !word.is_empty() && words < ABBREVIATED_ARGUMENTS {
+            words += 1;
+            expanded.push_str(replies().abbreviation(word).unwrap_or(word));
+        } else {
+            expanded.push_str(word);
+        }
+        expanded.push_str(&piece[word.len()..]);
+    }
+    expanded
+}
+
+// Statt filter_command: erst Kurzformen auflösen, dann parsen.
+// /help zeigt weiter nur die echten Befehle.
+fn parse_command(msg: &Message, me: &Me) -> Option<Command> {
+    let text = msg.text()?;
+    if !text.starts_with('/') {
+        return None;
+    }
+    Command::parse(&expand_command(text), me.username()).ok()
+}
+
+// Text einer Nachricht, die an den Bot gerichtet ist: im Einzelchat alles, in
+// Gruppen nur mit Erwähnung (ohne sie) oder als Antwort auf den Bot
+fn addressed_text(msg: &Message, me: &Me) -> Option<String> {
+    let text = msg.text()?;
+    let mention = format!("@{}", me.username());
+    if msg.chat.is_private() {
+        Some(text.to_string())
+    } else if text.contains(&mention) {
+        Some(text.replace(&mention, " ").trim().to_string())
+    } else if msg.reply_to_message().and_then(|reply| reply.from()).is_some_and(|from| from.id == me.id) {
+        Some(text.to_string())
+    } else {
+        None
+    }
+}
+
+// Frage in Freitext als Befehl ("wie warm ist es im wohnzimmer?" ->
+// /status wohnzimmer). Der Raum ist ein Wort oder zwei aufeinanderfolgende,
+// auch als Abkürzung; ohne Raum gilt der Befehl für alle Räume.
+fn intent_command(msg: &Message, me: &Me) -> Option<Command> {
+    let text = addressed_text(msg, me)?;
+    if text.starts_with('/') {
+        return None;
+    }
+    let command = shortcuts::intent(&text)?;
+    let mut command_text = format!("/{}", command);
+    if command == shortcuts::ROOM_INTENT {
+        // Nur genaue Treffer: mit Präfixen passte "wie" schon auf "Wiese"
+        let words: Vec<&str> =
+            text.split(|c: char| c.is_whitespace() || c == ',').map(|word| word.trim_end_matches(['?', '!', '.'])).filter(|word| !word.is_empty()).collect();
+        let tenant = tenant_of(msg.chat.id.0);
+        let candidates = words.windows(2).map(|pair| pair.join(" ")).chain(words.iter().map(|word| replies().abbreviation(word).unwrap_or(word).to_string()));
+        let room = candidates.into_iter().find_map(|candidate| rooms().find_in(tenant.as_deref(), &candidate).map(|room| room.name.clone()));
+        if let Some(room) = room {
+            command_text.push_str(&format!(" {}", room));
+        }
+    }
+    Command::parse(&command_text, me.username()).ok()
+}
+
+async fn reply_usage_hint(bot: Bot, msg: Message, hint: UsageHint, configs: UserConfigs) -> ResponseResult<()> {
+    let (mode, lang) = configs.lock().await.get(&msg.chat.id.0).map(|c| (c.notifications, c.lang)).unwrap_or_default();
+    reply(&bot, msg.chat.id, mode, format_usage_hint(&hint, lang)).await?;
+    Ok(())
+}
+
+async fn reject_callback(bot: Bot, q: CallbackQuery) -> ResponseResult<()> {
+    warn!("Zugriff verweigert: Button in Chat {}", q.message.as_ref().map(|m| redact::chat(m.chat.id.0)).unwrap_or_default());
+    bot.answer_callback_query(q.id).text(i18n::message(profile_lang(Some(&q.from)), "not_admitted")).show_alert(true).await?;
+    Ok(())
+}
+
+// So lange darf Telegram Antworten auf Inline-Anfragen zwischenspeichern
+const INLINE_CACHE_SECONDS: u32 = 30;
+
+// "@bot wohnzimmer" in einem beliebigen Chat: je passendem Raum ein Eintrag
+// mit den Werten aus dem letzten Abruf zum Teilen. Ohne Text alle Räume.
+// Es zählt die Person, die fragt, nicht der Chat, in dem sie tippt; wer
+// nicht zugelassen ist, bekommt nur eine leere Liste.
+#[allow(deprecated)]
+async fn handle_inline_query(bot: Bot, q: InlineQuery, configs: UserConfigs) -> ResponseResult<()> {
+    let chat_id = q.from.id.0 as i64;
+    let answer = |results: Vec<InlineQueryResult>| bot.answer_inline_query(q.id.clone(), results).cache_time(INLINE_CACHE_SECONDS).is_personal(true);
+    if !admitted(chat_id) {
+        warn!("Zugriff verweigert: Inline-Anfrage von {}", redact::chat(chat_id));
+        answer(Vec::new()).await?;
+        return Ok(());
+    }
+    let tenant = tenant_of(chat_id);
+    let registry = rooms();
+    let found: Vec<&Room> = if q.query.trim().is_empty() {
+        registry.rooms_in(tenant.as_deref()).collect()
+    } else {
+        match registry.match_text(tenant.as_deref(), &q.query) {
+            RoomMatch::None => Vec::new(),
+            RoomMatch::One(room) => vec![room],
+            RoomMatch::Ambiguous(found) => found,
+        }
+    };
+    let readings = latest().as_ref().map(|snapshot| visible_readings(chat_id, &snapshot.readings)).unwrap_or_default();
+    let (lang, units, time_format, tz, battery_low) = {
+        let user_configs = configs.lock().await;
+        let config = user_configs.get(&chat_id);
+        let (lang, units, time_format) = config.map(|c| (c.lang, c.units, c.time_format)).unwrap_or_default();
+        (lang, units, time_format, chat_timezone(config), config.map_or(settings().battery_low, UserConfig::battery_low))
+    };
+
+    let results: Vec<InlineQueryResult> = found
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, room)| {
+            let readings = status_list(readings.iter().filter(|r| r.device_id == room.device).cloned().collect(), battery_low);
+            if readings.is_empty() {
+                return None;
+            }
+            let summary: Vec<String> = readings
+                .iter()
+                .map(|r| {
+                    let decimals = quantities::lookup(r.sensor_type.as_str()).map_or(1, |q| q.decimals);
+                    format!("{:.*} {}", decimals, units.show(&r.sensor_type, r.value), unit_in(units, r.sensor_type.as_str()))
+                })
+                .collect();
+            let text = format_status(&readings, &Trends::new(), lang, units, tz, time_format);
+            let content = InputMessageContent::Text(InputMessageContentText::new(text).parse_mode(ParseMode::Markdown));
+            let article = InlineQueryResultArticle::new(format!("room-{}", index), room.name.clone(), content).description(summary.join(" · "));
+            Some(InlineQueryResult::Article(article))
+        })
+        .collect();
+    if results.is_empty() {
+        answer(results)
+            .switch_pm_text(match lang {
+                Lang::De => "Kein Raum gefunden – Bot öffnen",
+                Lang::En => "No room found – open the bot",
+            })
+            .switch_pm_parameter("inline")
+            .await?;
+    } else {
+        answer(results).await?;
+    }
+    Ok(())
+}
+
+fn ignored() -> std::sync::MutexGuard<'static, IgnoreList> {
+    IGNORED.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn known_chats() -> std::sync::MutexGuard<'static, KnownChats> {
+    KNOWN_CHATS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn reachability() -> std::sync::MutexGuard<'static, Reachability> {
+    REACHABILITY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Hat der Chat den Bot blockiert? Dann gehen keine Hintergrund-Nachrichten hin
+fn blocked(chat_id: i64) -> bool {
+    reachability().failure(chat_id).is_some_and(|failure| failure.reason == messenger::BLOCKED)
+}
+
+// Erreichbarkeit nach einer Zustellung festhalten; gespeichert wird nur,
+// wenn sich der Zustand ändert
+fn note_reached(storage: &dyn Store, chat_id: i64) {
+    let mut reach = reachability();
+    if reach.reached(chat_id, Utc::now()) {
+        storage.save_reachability(&reach);
+    }
+}
+
+fn note_unreachable(storage: &dyn Store, chat_id: i64, reason: &str) {
+    let mut reach = reachability();
+    if reach.failed(chat_id, reason, Utc::now()) {
+        info!("{} nicht erreichbar: {}", redact::chat(chat_id), reason);
+        storage.save_reachability(&reach);
+    }
+}
+
+// Nach dem Anlegen einer Schwelle die zusätzlichen Ziele ihrer Warnungen
+// still prüfen, die der Bot noch nie erreicht hat oder zuletzt nicht
+// erreichen konnte. Der eigene Chat hat eben die Bestätigung erhalten.
+async fn check_delivery(replies: &dyn Messenger, user_id: ChatId, configs: &UserConfigs, storage: &dyn Store) -> ResponseResult<()> {
+    note_reached(storage, user_id.0);
+    // Warnungen weiterer Haushalte gehen nicht an die Ziele aus [routing]
+    if tenant_of(user_id.0).is_some() {
+        return Ok(());
+    }
+    let targets: Vec<(i64, Severity)> =
+        rooms().routing().all_targets().into_iter().filter(|(target, _)| *target != user_id.0 && reachability().needs_check(*target)).collect();
+    let (mode, lang, targets) = {
+        let configs = configs.lock().await;
+        let lang_of = |chat: i64| configs.get(&chat).map(|c| c.lang).unwrap_or_default();
+        let (mode, lang) = configs.get(&user_id.0).map(|c| (c.notifications, c.lang)).unwrap_or_default();
+        (mode, lang, targets.into_iter().map(|(target, severity)| (target, severity, lang_of(target))).collect::<Vec<_>>())
+    };
+    for (target, severity, target_lang) in targets {
+        let message = OutgoingMessage {
+            chat_id: target,
+            text: i18n::message_with(target_lang, "delivery_test", &[("severity", severity.label(target_lang))]),
+            markdown: false,
+            html: false,
+            buttons: None,
+            silent: true,
+            reply_to: None,
+            room_image: None,
+        };
+        let reason = match replies.send(&message).await {
+            Ok(()) => {
+                note_reached(storage, target);
+                continue;
+            }
+            Err(SendError::Unreachable(reason)) => reason,
+            Err(err) => {
+                warn!("Testnachricht an {} fehlgeschlagen: {:?}", redact::chat(target), err);
+                continue;
+            }
+        };
+        note_unreachable(storage, target, &reason);
+        reply(
+            replies,
+            user_id,
+            mode,
+            i18n::message_with(
+                lang,
+                "delivery_failed",
+                &[("severity", severity.label(lang)), ("chat", &redact::chat(target)), ("reason", &messenger::reason_in(&reason, lang))],
+            ),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+// Zwischen zwei Zustellungen, weit unter der Grenze von Telegram (30 je Sekunde)
+const BROADCAST_PAUSE: std::time::Duration = std::time::Duration::from_millis(50);
+
+// /broadcast: Text nacheinander an alle Ziele, nach einer Drosselung ein
+// zweiter Versuch. Ergebnis als Bericht für den Admin.
+async fn broadcast(bot: &Bot, targets: &[i64], text: &str, storage: &dyn Store, lang: Lang) -> String {
+    let mut delivered = 0;
+    let mut problems: Vec<String> = Vec::new();
+    for &target in targets {
+        let mut result = bot.send_message(ChatId(target), text).await;
+        if let Err(teloxide::RequestError::RetryAfter(wait)) = &result {
+            tokio::time::sleep(*wait).await;
+            result = bot.send_message(ChatId(target), text).await;
+        }
+        match result {
+            Ok(_) => {
+                delivered += 1;
+                note_reached(storage, target);
+            }
+            Err(err) => {
+                let reason = match messenger::unreachable_reason(&err) {
+                    Some(reason) => {
+                        note_unreachable(storage, target, reason);
+                        messenger::reason_in(reason, lang)
+                    }
+                    None => err.to_string(),
+                };
+                warn!("Rundnachricht an {} fehlgeschlagen: {}", redact::chat(target), reason);
+                problems.push(format!("{}: {}", target, reason));
+            }
+        }
+        tokio::time::sleep(BROADCAST_PAUSE).await;
+    }
+    let mut report = i18n::message_with(lang, "broadcast_report", &[("delivered", &delivered.to_string()), ("failed", &problems.len().to_string())]);
+    // Bei sehr vielen Fehlern nur die ersten, damit der Bericht in eine Nachricht passt
+    for problem in problems.iter().take(30) {
+        report.push_str(&format!("\n• {}", problem));
+    }
+    if problems.len() > 30 {
+        report.push_str(&format!("\n{}", i18n::message_with(lang, "broadcast_more", &[("count", &(problems.len() - 30).to_string())])));
+    }
+    report
+}
+
+// Status eines Raums zu Freitext oder /status <raum> (Legacy-Markdown).
+// None, wenn der Text zu keinem Raum passt.
+// Antwort und, wenn Messwerte angezeigt werden, das Gerät (für das Raumbild)
+#[allow(clippy::too_many_arguments)]
+async fn room_status(
+    chat_id: i64,
+    text: &str,
+    history: &SharedHistory,
+    notes: &BTreeMap<String, String>,
+    lang: Lang,
+    units: TempUnit,
+    tz: Option<Tz>,
+    times: TimeFormat,
+) -> Option<(String, Option<String>)> {
+    let registry = rooms();
+    let room = match registry.match_text(tenant_of(chat_id).as_deref(), text) {
+        RoomMatch::None => None,
+        RoomMatch::Ambiguous(found) => {
+            let names: Vec<String> = found.iter().map(|r| escape_markdown(&r.name)).collect();
+            return Some((i18n::message_with(lang, "room_ambiguous", &[("rooms", &names.join(i18n::message(lang, "or")))]), None));
+        }
+        RoomMatch::One(room) => Some(room),
+    };
+    let (sensor_data, stale) = match status_readings(chat_id).await {
+        Ok(fetched) => fetched,
+        Err(_) if room.is_none() => return None,
+        Err(err) => return Some((format!("❌ {}", escape_markdown(&err.text().in_lang(lang))), None)),
+    };
+    // Geräte ohne Eintrag im Raumverzeichnis über ihre ID
+    let (device, name) = match room {
+        Some(room) => (room.device.clone(), room.name.clone()),
+        None => {
+            let device = sensor_data.iter().find(|e| e.device_id.eq_ignore_ascii_case(text.trim()))?.device_id.clone();
+            (device.clone(), device)
+        }
+    };
+    let readings: Vec<SensorData> = sensor_data.into_iter().filter(|e| e.device_id == device).collect();
+    if readings.is_empty() {
+        return Some((i18n::message_with(lang, "room_no_readings", &[("room", &escape_markdown(&name))]), None));
+    }
+    let trends = status_trends(&*history.lock().await, &readings);
+    let mut text = format!("{}{}", format_status(&readings, &trends, lang, units, tz, times), format_notes(notes, &readings));
+    if let Some(stale) = stale {
+        text.push_str(&format!("\n{}", escape_markdown(&stale.in_lang(lang))));
+    }
+    Some((text, room.map(|room| room.device.clone())))
+}
+
+// Messwerte der Live-Übersicht eines Chats, wie bei /status, aber sortiert,
+// damit eine andere Reihenfolge der Quellen nicht als Änderung zählt
+fn live_readings(chat_id: i64, config: &UserConfig, readings: Vec<SensorData>) -> Vec<SensorData> {
+    let mut readings = status_list(visible_readings(chat_id, &readings), config.battery_low());
+    readings.sort_by(|a, b| (&a.device_id, &a.sensor_type).cmp(&(&b.device_id, &b.sensor_type)));
+    readings
+}
+
+// Live-Übersicht senden und still anheften; true, wenn das Anheften geklappt hat
+#[allow(deprecated)]
+async fn post_live(bot: &Bot, chat: ChatId, text: &str) -> ResponseResult<(MessageId, bool)> {
+    let sent = bot.send_message(chat, text).parse_mode(ParseMode::Markdown).await?;
+    let pinned = match bot.pin_chat_message(chat, sent.id).disable_notification(true).await {
+        Ok(_) => true,
+        Err(err) => {
+            warn!("Live-Übersicht in {} nicht angeheftet: {}", redact::chat(chat.0), err);
+            false
+        }
+    };
+    Ok((sent.id, pinned))
+}
+
+// Nach jedem erfolgreichen Abruf die Live-Übersichten bearbeiten, deren Werte
+// sich geändert haben. Wurde die Nachricht gelöscht, kommt eine neue.
+#[allow(deprecated)]
+async fn live_updates(bot: Bot, configs: UserConfigs, history: SharedHistory, storage: Arc<dyn Store>) {
+    loop {
+        LATEST_FETCHED.notified().await;
+        let Some(readings) = latest().as_ref().map(|snapshot| snapshot.readings.clone()) else { continue };
+        let trends = status_trends(&*history.lock().await, &readings);
+        let due: Vec<(i64, i32, String, String)> = configs
+            .lock()
+            .await
+            .iter()
+            .filter(|&(&chat_id, _)| !blocked(chat_id))
+            .filter_map(|(&chat_id, config)| {
+                let live = config.live.as_ref()?;
+                let readings = live_readings(chat_id, config, readings.clone());
+                let values = live_values(&readings);
+                (!readings.is_empty() && values != live.values).then(|| (chat_id, live.message_id, values, live_text(&readings, &trends, config)))
+            })
+            .collect();
+        for (chat_id, message_id, values, text) in due {
+            let chat = ChatId(chat_id);
+            let result = bot.edit_message_text(chat, MessageId(message_id), &text).parse_mode(ParseMode::Markdown).await;
+            let message_id = match result {
+                Ok(_) | Err(teloxide::RequestError::Api(teloxide::ApiError::MessageNotModified)) => message_id,
+                Err(teloxide::RequestError::Api(teloxide::ApiError::MessageToEditNotFound | teloxide::ApiError::MessageIdInvalid)) => {
+                    match post_live(&bot, chat, &text).await {
+                        Ok((id, _)) => {
+                            info!("Live-Übersicht in {} war gelöscht, neu gesendet", redact::chat(chat_id));
+                            id.0
+                        }
+                        Err(err) => {
+                            warn!("Live-Übersicht in {} nicht neu gesendet: {}", redact::chat(chat_id), err);
+                            continue;
+                        }
+                    }
+                }
+                Err(err) => {
+                    warn!("Live-Übersicht in {} nicht bearbeitet: {}", redact::chat(chat_id), err);
+                    continue;
+                }
+            };
+            let mut configs = configs.lock().await;
+            // Inzwischen mit /live off beendet? Dann bleibt es dabei
+            if let Some(live) = configs.get_mut(&chat_id).and_then(|c| c.live.as_mut()) {
+                live.message_id = message_id;
+                live.values = values;
+                storage.save_users(&configs);
+            }
+        }
+    }
+}
+
+// Status eines Raums, mit Raumbild als Foto, wenn `device` gesetzt und ein
+// Bild hinterlegt ist und der Text in die Bildunterschrift passt
+#[allow(deprecated)]
+async fn send_room_status(messenger: &dyn Messenger, chat: ChatId, mode: NotificationMode, text: String, device: Option<&str>) -> ResponseResult<()> {
+    reply(messenger, chat, mode, text).parse_mode(ParseMode::Markdown).room_image(device).await
+}
+
+// Mini-Verlauf der angezeigten Messwerte; ohne genug Verlauf kein Eintrag
+fn status_trends(history: &History, sensor_data: &[SensorData]) -> Trends {
+    let now = Utc::now().timestamp();
+    sensor_data
+        .iter()
+        .filter_map(|entry| {
+            let decimals = quantities::lookup(entry.sensor_type.as_str()).map_or(1, |q| q.decimals);
+            let series = history.series(&entry.device_id, entry.sensor_type.as_str())?;
+            let trend = sparkline::trend(series, now, 10f64.powi(-(decimals as i32)))?;
+            Some(((entry.device_id.clone(), entry.sensor_type.as_str().to_string()), trend))
+        })
+        .collect()
+}
+
+// Größe der Messwert-Datenbank für /debug, wenn sie geöffnet ist
+fn database_file() -> Option<(String, Option<u64>)> {
+    DATABASE.get().map(|db| ("Datenbank".to_string(), db.size()))
+}
+
+// Datenbank öffnen, wenn DATABASE_PATH gesetzt ist und keine eigene
+// übergeben wurde; ohne sie bleibt es beim JSON-Verlauf, der Bot startet
+// trotzdem
+fn open_database() {
+    let Some(path) = &settings().database_path else { return };
+    if DATABASE.get().is_some() {
+        warn!("DATABASE_PATH={} wird ignoriert: eigener Speicher für Rohwerte übergeben", path.display());
+        return;
+    }
+    #[cfg(feature = "storage-sqlite")]
+    match database::Database::open(path) {
+        Ok(db) => {
+            info!("Messwerte werden zusätzlich in {} gespeichert", path.display());
+            DATABASE.set(Arc::new(db)).ok();
+        }
+        Err(err) => warn!("Datenbank nicht verfügbar, nur JSON-Verlauf: {}", err),
+    }
+    #[cfg(not(feature = "storage-sqlite"))]
+    warn!("DATABASE_PATH={} wird ignoriert: die Datenbank ist in diesem Build deaktiviert (Feature storage-sqlite)", path.display());
+}
+
+// Warnung an die zusätzlichen Ziele, jedes in eigener Aufgabe, damit ein
+// langsames die Überwachung nicht aufhält
+fn notify_sinks(chat_id: i64, alert: &BatchedAlert) {
+    for sink in ALERT_SINKS.get().into_iter().flatten() {
+        let sink = sink.clone();
+        let alert =
+            SinkAlert { chat_id, device_id: alert.device_id.clone(), text: alert.text.clone(), severity: alert.severity, observed_at: alert.observed_at };
+        tokio::spawn(async move {
+            if let Err(err) = sink.alert(&alert).await {
+                warn!("Warnung für {} nicht an zusätzliches Ziel zugestellt: {}", redact::chat(alert.chat_id), err);
+            }
+        });
+    }
+}
+
+// Messwerte eines Abrufs in die Datenbank, falls vorhanden
+fn record_readings<'a>(readings: impl Iterator<Item = &'a SensorData>) {
+    if let Some(db) = DATABASE.get()
+        && let Err(err) = db.insert(&readings.collect::<Vec<_>>())
+    {
+        warn!("Messwerte nicht in die Datenbank geschrieben: {}", err);
+    }
+}
+
+// Rohwerte ab `since` aus der Datenbank, sonst aus dem Verlauf
+fn recorded_samples(history: &History, device_id: &str, sensor_type: &str, since: i64) -> Vec<(i64, f64)> {
+    if let Some(db) = DATABASE.get() {
+        match db.range(device_id, sensor_type, since) {
+            Ok(samples) => return samples,
+            Err(err) => warn!("Datenbankabfrage fehlgeschlagen, nutze den Verlauf: {}", err),
+        }
+    }
+    history.series(device_id, sensor_type).map(|series| series.iter().filter(|(ts, _)| *ts >= since).copied().collect()).unwrap_or_default()
+}
+
+// Längster Zeitraum für /history und /chart
+fn max_history_hours() -> i64 {
+    if DATABASE.get().is_some() {
+        return DATABASE_MAX_HOURS;
+    }
+    HISTORY_MAX_HOURS
+}
+
+// Antwort an den Chat, der gerade etwas geschickt hat, mit dem Ton nach
+// /notifications. Formatierung und Buttons setzt der Aufrufer wie bei
+// bot.send_message. Sie geht direkt über den Messenger, nicht über die
+// Warteschlange, damit sie nicht hinter Warnungen wartet.
+fn reply(messenger: &dyn Messenger, chat: ChatId, mode: NotificationMode, text: impl Into<String>) -> Reply<'_> {
+    let message = OutgoingMessage {
+        chat_id: chat.0,
+        text: text.into(),
+        markdown: false,
+        html: false,
+        buttons: None,
+        silent: mode.silent(MessageKind::Reply),
+        reply_to: None,
+        room_image: None,
+    };
+    Reply { messenger, message }
+}
+
+struct Reply<'a> {
+    messenger: &'a dyn Messenger,
+    message: OutgoingMessage,
+}
+
+impl Reply<'_> {
+    // Der Messenger kennt HTML und Legacy-Markdown
+    fn parse_mode(mut self, mode: ParseMode) -> Self {
+        match mode {
+            ParseMode::Html => self.message.html = true,
+            _ => self.message.markdown = true,
+        }
+        self
+    }
+
+    fn reply_markup(mut self, buttons: InlineKeyboardMarkup) -> Self {
+        self.message.buttons = Some(buttons);
+        self
+    }
+
+    fn reply_to_message_id(mut self, message_id: MessageId) -> Self {
+        self.message.reply_to = Some(message_id.0);
+        self
+    }
+
+    // Mit Raumbild, falls für das Gerät eines hinterlegt ist
+    fn room_image(mut self, device: Option<&str>) -> Self {
+        self.message.room_image = device.map(str::to_string);
+        self
+    }
+}
+
+impl<'a> IntoFuture for Reply<'a> {
+    type Output = ResponseResult<()>;
+    type IntoFuture = BoxFuture<'a, ResponseResult<()>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move { Ok(self.messenger.send(&self.message).await?) })
+    }
+}
+
+// Laufzeit und Ausfälle der letzten 7 Tage
+// Sekunden vom Messwert bis zur Zustellung je gemerkter Warnung des Chats
+fn delivery_latencies(config: &UserConfig) -> Vec<i64> {
+    config.alerts.iter().filter_map(|record| Some(record.delivered_at? - record.observed_at?)).map(|latency| latency.max(0)).collect()
+}
+
+// Stummschaltung beenden; liefert die Abschlussmeldung
+// /mute-all <dauer>; None bei unlesbarer Dauer
+fn mute_chat(config: &mut UserConfig, spec: &str) -> Option<String> {
+    let max = chrono::Duration::hours(settings().mute_max_hours);
+    let duration = parse_duration(spec)?;
+    if duration > max {
+        return Some(i18n::message_with(config.lang, "mute_too_long", &[("max", &format_duration(max.num_seconds()))]));
+    }
+    config.muted_until = Some(Utc::now() + duration);
+    Some(i18n::message_with(config.lang, "mute_all_set", &[("until", &format_local(Utc::now() + duration, "%d.%m. %H:%M"))]))
+}
+
+// /snooze <raum> <dauer>: alle Schwellen des Raums stumm, die Alarmzustände
+// laufen weiter; None bei unlesbarer Angabe
+fn snooze_room(config: &mut UserConfig, chat_id: i64, spec: &str) -> Option<String> {
+    let now = Utc::now();
+    let max = chrono::Duration::hours(settings().mute_max_hours);
+    let (room, duration) = spec.trim().rsplit_once(char::is_whitespace)?;
+    let (room, duration) = (room.trim(), parse_duration(duration)?);
+    if duration > max {
+        return Some(i18n::message_with(config.lang, "mute_too_long", &[("max", &format_duration(max.num_seconds()))]));
+    }
+    let Some(device) = resolve_device_in(chat_id, room) else {
+        return Some(unknown_room(config.lang, chat_id, room));
+    };
+    let keys: Vec<_> = config.thresholds.keys().filter(|(d, _)| *d == device).cloned().collect();
+    if keys.is_empty() {
+        return Some(i18n::message_with(config.lang, "snooze_no_thresholds", &[("room", &room_name(&device))]));
+    }
+    let mut ended = Vec::new();
+    for key in &keys {
+        let snooze = Snooze { until: now + duration, since: now, origin: snooze::Origin::Command };
+        ended.extend(snooze::insert(&mut config.snoozed, key.clone(), snooze));
+    }
+    ended.retain(|key| !keys.contains(key));
+    let text = i18n::message_with(config.lang, "snooze_set", &[("room", &room_name(&device)), ("until", &format_local(now + duration, "%d.%m. %H:%M"))]);
+    Some(format!("{}{}", text, format_snoozes_ended(&ended, config.lang)))
+}
+
+fn unmute_summary(config: &mut UserConfig) -> String {
+    config.muted_until = None;
+    match std::mem::take(&mut config.muted_missed) {
+        0 => i18n::message(config.lang, "unmute_missed_none").to_string(),
+        1 => i18n::message(config.lang, "unmute_missed_one").to_string(),
+        n => i18n::message_with(config.lang, "unmute_missed", &[("count", &n.to_string())]),
+    }
+}
+
+// Neue Schwelle prüfen, bevor sie gespeichert wird: im plausiblen Bereich
+// der Messgröße und nicht auf der falschen Seite der Gegenschwelle. Verglichen
+// wird mit dem Eintrag für dasselbe Zeitfenster, sonst mit dem Standardwert.
+fn validate_threshold(config: &UserConfig, key: &(String, ThresholdKey), value: f64, window: Option<TimeWindow>) -> Result<(), String> {
+    let lang = config.lang;
+    let typ = type_label_in(lang, key.1.kind.as_str()).0;
+    let einheit = unit_in(config.units, key.1.kind.as_str());
+    let show = |value: f64| config.units.show(&key.1.kind, value);
+    if !value.is_finite() {
+        return Err(i18n::message_with(lang, "threshold_invalid", &[("value", &value.to_string())]));
+    }
+    if let Some((low, high)) = thresholds::plausible_range(&key.1.kind)
+        && !(low..=high).contains(&value)
+    {
+        return Err(i18n::message_with(
+            lang,
+            "threshold_implausible",
+            &[
+                ("value", &format!("{:.1}", show(value))),
+                ("unit", einheit),
+                ("type", typ),
+                ("low", &format!("{:.0}", show(low))),
+                ("high", &format!("{:.0}", show(high))),
+            ],
+        ));
+    }
+    let opposite = config
+        .thresholds
+        .get(&(key.0.clone(), key.1.opposite()))
+        .and_then(|s| s.entries().iter().find(|e| e.window == window).or_else(|| s.entries().iter().find(|e| e.window.is_none())));
+    // Die Umrechnung erhält die Reihenfolge, verglichen werden also gleich die angezeigten Werte
+    adjust::check_opposite(key.1.direction, show(value), opposite.map(|e| show(e.value)), einheit, lang)
+}
+
+// Alte Stände: Schwellen unter einem Raumnamen (die Wohnzimmer-Befehle)
+// gehören zur Geräte-ID des Raums. Typ und Richtung der Schlüssel sind
+// schon beim Laden vereinheitlicht. Liefert die Zahl umgestellter Schwellen.
+fn migrate_configs(configs: &mut HashMap<i64, UserConfig>) -> usize {
+    let mut migrated = 0;
+    for config in configs.values_mut() {
+        let stale: Vec<(String, ThresholdKey)> =
+            config.thresholds.keys().filter(|(device, _)| rooms().find(device).is_some_and(|room| room.device != *device)).cloned().collect();
+        for key in stale {
+            let Some(schedule) = config.thresholds.remove(&key) else { continue };
+            let device = resolve_device(&key.0);
+            // Eine unter der Geräte-ID gesetzte Schwelle ist neuer und bleibt
+            config.thresholds.entry((device, key.1)).or_insert(schedule);
+            migrated += 1;
+        }
+    }
+    migrated
+}
+
+// Messgrößen eines Geräts für /copy-thresholds und /apply-default. None, wenn
+// die Quelle nicht antwortet und sich das nicht prüfen lässt.
+async fn device_kinds(chat_id: i64, device: &str, lang: Lang) -> Result<Option<Vec<SensorKind>>, String> {
+    let Ok(readings) = fetch_sensor_data_for(chat_id).await else { return Ok(None) };
+    if readings.is_empty() {
+        return Ok(None);
+    }
+    let kinds: Vec<SensorKind> = readings.iter().filter(|r| r.device_id == device).map(|r| r.sensor_type.clone()).collect();
+    if kinds.is_empty() {
+        return Err(i18n::message_with(lang, "device_no_readings", &[("room", &room_name(device)), ("devices", &known_devices(&readings))]));
+    }
+    Ok(Some(kinds))
+}
+
+// Alle Schwellen eines Geräts samt Hysterese, Erinnerung und Stufe auf ein
+// anderes übertragen. Gleiche Schwellen des Ziels werden ersetzt, andere
+// bleiben. Zurück kommen die ersetzten; bei einem Fehler bleibt alles, wie es war.
+fn copy_thresholds(config: &mut UserConfig, from: &str, to: &str, setter: Option<Setter>) -> Result<Vec<ThresholdKey>, String> {
+    let mut copied: Vec<(ThresholdKey, ThresholdSchedule)> =
+        config.thresholds.iter().filter(|((device, _), _)| device == from).map(|((_, key), schedule)| (key.clone(), schedule.clone())).collect();
+    if copied.is_empty() {
+        return Err(i18n::message_with(config.lang, "copy_nothing", &[("room", &room_name(from))]));
+    }
+    copied.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut updated = config.clone();
+    let mut overwritten = Vec::new();
+    for (key, schedule) in copied {
+        let source = (from.to_string(), key.clone());
+        let target = (to.to_string(), key.clone());
+        if updated.thresholds.contains_key(&target) {
+            overwritten.push(key);
+        }
+        change_threshold(&mut updated, target.clone(), |s| {
+            *s = schedule;
+            Ok(())
+        })?;
+        copy_extra(&mut updated.hysteresis, &source, &target);
+        copy_extra(&mut updated.repeat, &source, &target);
+        copy_extra(&mut updated.alert_styles, &source, &target);
+        record_setter(&mut updated, target, setter.clone());
+    }
+    validate_device_thresholds(&updated, to)?;
+    *config = updated;
+    Ok(overwritten)
+}
+
+// Zusatz einer Schwelle wie bei der Quelle: übernehmen oder entfernen
+fn copy_extra<T: Clone>(map: &mut HashMap<(String, ThresholdKey), T>, from: &(String, ThresholdKey), to: &(String, ThresholdKey)) {
+    match map.get(from).cloned() {
+        Some(extra) => map.insert(to.clone(), extra),
+        None => map.remove(to),
+    };
+}
+
+// DEFAULT_TEMPLATE für ein Gerät, nur für Messgrößen, die es liefert (ohne
+// Antwort der Quelle alle). Ersetzt die Werte samt Zeitfenstern; Stufe,
+// Erinnerung und Hysterese bleiben.
+fn apply_default(
+    config: &mut UserConfig,
+    device: &str,
+    kinds: Option<&[SensorKind]>,
+    source: String,
+    setter: Option<Setter>,
+) -> Result<Vec<ThresholdKey>, String> {
+    let template: Vec<_> = DEFAULT_TEMPLATE.iter().filter(|(kind, _, _)| kinds.is_none_or(|kinds| kinds.contains(kind))).collect();
+    if template.is_empty() {
+        return Err(i18n::message_with(config.lang, "default_not_applicable", &[("room", &room_name(device))]));
+    }
+    let mut updated = config.clone();
+    let mut overwritten = Vec::new();
+    for (kind, direction, value) in template {
+        let key = ThresholdKey::new(kind.clone(), *direction);
+        let target = (device.to_string(), key.clone());
+        if updated.thresholds.contains_key(&target) {
+            overwritten.push(key);
+        }
+        change_threshold(&mut updated, target.clone(), |s| {
+            *s = ThresholdSchedule::default();
+            s.set(*value, None, source.clone())
+        })?;
+        record_setter(&mut updated, target, setter.clone());
+    }
+    validate_device_thresholds(&updated, device)?;
+    *config = updated;
+    Ok(overwritten)
+}
+
+// Alle Einträge der Schwellen eines Geräts wie bei /setmin prüfen
+fn validate_device_thresholds(config: &UserConfig, device: &str) -> Result<(), String> {
+    for (key, schedule) in config.thresholds.iter().filter(|((d, _), _)| d == device) {
+        for entry in schedule.entries() {
+            validate_threshold(config, key, entry.value, entry.window)?;
+        }
+    }
+    Ok(())
+}
+
+// Nur in Gruppen gesetzt; in privaten Chats ist ohnehin der Chat gemeint
+fn record_setter(config: &mut UserConfig, key: (String, ThresholdKey), setter: Option<Setter>) {
+    match setter {
+        Some(setter) => config.configured_by.insert(key, setter),
+        None => config.configured_by.remove(&key),
+    };
+}
+
+// Schwelle samt laufender Verletzung entfernen, mit /undo umkehrbar;
+// false, wenn es sie nicht gab
+fn remove_threshold(config: &mut UserConfig, key: (String, ThresholdKey)) -> bool {
+    let Some(previous) = config.thresholds.remove(&key) else { return false };
+    config.episodes.remove(&key);
+    config.acknowledged.remove(&key);
+    config.configured_by.remove(&key);
+    config.undo.push(UndoEntry { device_id: key.0, key: key.1, previous: Some(previous) });
+    if config.undo.len() > MAX_UNDO {
+        config.undo.remove(0);
+    }
+    true
+}
+
+// Einziger Weg, Schwellen zu ändern: merkt sich den vorherigen Stand für /undo.
+// Schlägt die Änderung fehl, bleibt alles unverändert.
+fn change_threshold(
+    config: &mut UserConfig,
+    key: (String, ThresholdKey),
+    change: impl FnOnce(&mut ThresholdSchedule) -> Result<(), Text>,
+) -> Result<(), String> {
+    let previous = config.thresholds.get(&key).cloned();
+    let mut schedule = previous.clone().unwrap_or_default();
+    change(&mut schedule).map_err(|err| err.in_lang(config.lang))?;
+    config.thresholds.insert(key.clone(), schedule);
+    config.undo.push(UndoEntry { device_id: key.0, key: key.1, previous });
+    if config.undo.len() > MAX_UNDO {
+        config.undo.remove(0);
+    }
+    Ok(())
+}
+
+// Gerade aktive Schwelle neu setzen (Buttons und Dialog an Warnungen).
+// Die Gegenschwelle (MIN < MAX) wird dabei eingehalten.
+fn adjust_threshold(config: &mut UserConfig, device_id: &str, key: &ThresholdKey, value: impl FnOnce(f64) -> f64, source: String) -> Result<f64, String> {
+    let now = local_time();
+    let threshold_key = (device_id.to_string(), key.clone());
+    let entry = config.thresholds.get(&threshold_key).and_then(|s| s.active_entry(now)).cloned().ok_or(i18n::message(config.lang, "threshold_not_active"))?;
+    let new_value = value(entry.value);
+    validate_threshold(config, &threshold_key, new_value, entry.window)?;
+
+    change_threshold(config, threshold_key, |s| s.set(new_value, entry.window, source))?;
+    Ok(new_value)
+}
+
+// Einmal je Verletzung, die seit LONG_VIOLATION_DAYS ununterbrochen besteht:
+// Verteilung der Werte seit Beginn zeigen und eine Schwelle vorschlagen, die
+// nur noch bei den extremsten 10 % anschlägt. true, wenn Verletzungen als
+// geprüft markiert wurden.
+// Nach einem Wechsel liegt die Batterie deutlich über der Warngrenze; kleine
+// Schwankungen um die Grenze beenden die Warnung nicht
+const BATTERY_REPLACED_MARGIN: f64 = 10.0;
+
+// Einmalige Warnung bei schwacher Batterie und Entwarnung nach dem Wechsel,
+// an den Admin und alle mit Schwellen auf dem Gerät. true, wenn sich der
+// gespeicherte Stand geändert hat.
+fn check_batteries(configs: &mut HashMap<i64, UserConfig>, readings: &[SensorData], outbox: &Outbox, now: DateTime<Utc>) -> bool {
+    let mut changed = false;
+    for reading in readings.iter().filter(|r| r.sensor_type == SensorKind::Battery) {
+        let device_id = &reading.device_id;
+        if ignored().contains(device_id) {
+            continue;
+        }
+        for (&chat_id, config) in configs.iter_mut() {
+            let concerned = settings().admin_chat == Some(chat_id) || config.thresholds.keys().any(|(device, _)| device == device_id);
+            if !concerned || !rooms().visible(tenant_of(chat_id).as_deref(), device_id) {
+                continue;
+            }
+            let low = config.battery_low();
+            let notice = if low > 0.0 && reading.value < low && config.battery_warned.insert(device_id.clone()) {
+                "battery_low"
+            } else if reading.value >= low + BATTERY_REPLACED_MARGIN && config.battery_warned.remove(device_id) {
+                "battery_replaced"
+            } else {
+                continue;
+            };
+            let text = i18n::message_with(config.lang, notice, &[("room", &room_name(device_id)), ("value", &format!("{:.0}", reading.value))]);
+            changed = true;
+            if config.is_muted(now) {
+                config.muted_missed += 1;
+            } else {
+                outbox.send(ChatId(chat_id), text);
+            }
+        }
+    }
+    changed
+}
+
+fn review_long_violations(configs: &mut HashMap<i64, UserConfig>, history: &History, outbox: &Outbox, now: i64) -> bool {
+    let days = settings().long_violation_days;
+    if days <= 0 {
+        return false;
+    }
+    let mut changed = false;
+    for (chat_id, config) in configs.iter_mut() {
+        for ((device_id, key), episode) in config.episodes.iter_mut() {
+            if episode.reviewed || episode.last_seen - episode.started < days * 24 * 60 * 60 {
+                continue;
+            }
+            episode.reviewed = true;
+            changed = true;
+            let Some(entry) = threshold_in(&config.thresholds, &config.profiles, &(device_id.clone(), key.clone()), local_time()) else {
+                continue;
+            };
+            let Some(dist) = history.distribution(device_id, key.kind.as_str(), episode.started) else { continue };
+            let Some(&(_, current)) = history.series(device_id, key.kind.as_str()).and_then(|s| s.back()) else { continue };
+            let show = |value: f64| config.units.show(&key.kind, value);
+            let one = |value: f64| format!("{:.1}", show(value));
+            // Auf halbe Einheiten der Anzeige, nach außen gerundet
+            let (shown, notice) = if key.direction.is_min() {
+                ((show(dist.low) * 2.0).floor() / 2.0, "long_violation_min")
+            } else {
+                ((show(dist.high) * 2.0).ceil() / 2.0, "long_violation_max")
+            };
+            let suggested = config.units.parse(&key.kind, shown);
+            let label = type_label_in(config.lang, key.kind.as_str()).0;
+            let unit = unit_in(config.units, key.kind.as_str());
+            let text = i18n::message_with(
+                config.lang,
+                notice,
+                &[
+                    ("type", label),
+                    ("room", &room_name(device_id)),
+                    ("threshold", &one(entry.value)),
+                    ("value", &one(current)),
+                    ("unit", unit),
+                    ("duration", &format_duration(now - episode.started)),
+                    ("min", &one(dist.min)),
+                    ("median", &one(dist.median)),
+                    ("max", &one(dist.max)),
+                    ("suggested", &format!("{:.1}", shown)),
+                ],
+            );
+            match adjust::review_buttons(device_id, key, suggested, shown, config.lang) {
+                Some(buttons) => outbox.send_with_buttons(ChatId(*chat_id), text, buttons),
+                None => outbox.send(ChatId(*chat_id), text),
+            }
+        }
+    }
+    changed
+}
+
+// Erinnerungen nach /repeat: solange ein Alarm besteht, im eingestellten
+// Abstand erneut warnen, als Antwort auf die erste Warnung. Schwellen mit
+// eigener Stufe "critical" erinnern ohne /repeat alle
+// CRITICAL_REPEAT_MINUTES, Infos nie. Stumm, bestätigt oder in der
+// Ruhezeit wird nicht erinnert. true, wenn sich eine Episode geändert hat.
+fn remind_persistent_violations(configs: &mut HashMap<i64, UserConfig>, flags: &monitor::Flags, history: &History, outbox: &Outbox, now: i64) -> bool {
+    let mut changed = false;
+    let at = timeutil::from_timestamp(now);
+    for (&chat_id, config) in configs.iter_mut() {
+        if config.is_muted(at) || config.quiet_hours.is_some_and(|w| in_local_window(&w, at)) {
+            continue;
+        }
+        for (id, episode) in config.episodes.iter_mut() {
+            let severity = config.alert_styles.get(id).and_then(|style| style.severity);
+            let minutes = match (config.repeat.get(id), severity) {
+                (_, Some(Severity::Info)) => continue,
+                (Some(minutes), _) => *minutes,
+                (None, Some(Severity::Critical)) => CRITICAL_REPEAT_MINUTES,
+                (None, _) => continue,
+            };
+            let (device_id, key) = id;
+            let alarm = flags.get(&(chat_id, device_id.clone(), key.clone())) == Some(&true);
+            let since = episode.reminded_at.unwrap_or(episode.started);
+            if !alarm
+                || now - since < minutes * 60
+                || config.acknowledged.contains_key(id)
+                || config.snoozed.get(id).is_some_and(|snooze| snooze.until > at)
+                || ignored().contains(device_id)
+            {
+                continue;
+            }
+            let Some(current) = threshold_in(&config.thresholds, &config.profiles, id, local_time()) else { continue };
+            let Some(&(_, value)) = history.series(device_id, key.kind.as_str()).and_then(|s| s.back()) else { continue };
+            let unit = unit_in(config.units, key.kind.as_str());
+            let text = i18n::message_with(
+                config.lang,
+                if key.direction.is_min() { "reminder_below" } else { "reminder_above" },
+                &[
+                    ("type", type_label_in(config.lang, key.kind.as_str()).0),
+                    ("room", &room_name(device_id)),
+                    ("duration", &format_duration(now - episode.started)),
+                    ("threshold", &format!("{:.1}", config.units.show(&key.kind, current.value))),
+                    ("value", &format!("{:.1}", config.units.show(&key.kind, value))),
+                    ("unit", unit),
+                ],
+            );
+            if config.alert_mode != AlertMode::Instant && severity != Some(Severity::Critical) {
+                config.digest.entries.push((at, text));
+            } else {
+                outbox.send_reply(ChatId(chat_id), text, episode.message_id);
+            }
+            episode.reminded_at = Some(now);
+            episode.reminders += 1;
+            changed = true;
+        }
+    }
+    changed
+}
+
+// Warnungen, die nach der Frist aus /escalate-to noch bestehen und nicht
+// mit "✅ OK" bestätigt sind, einmal an den freigegebenen Chat weitergeben.
+// Dessen Ruhezeit gilt nur für nicht kritische Warnungen. true, wenn sich
+// Episoden geändert haben.
+fn hand_over_unacknowledged(configs: &mut HashMap<i64, UserConfig>, flags: &monitor::Flags, outbox: &Outbox, now: DateTime<Utc>) -> bool {
+    let mut due = Vec::new();
+    for (&chat_id, config) in configs.iter() {
+        let Some(handover) = config.escalate_to else { continue };
+        for (id, episode) in &config.episodes {
+            let Some((text, severity)) = &episode.alert else { continue };
+            if episode.handed_over
+                || config.acknowledged.contains_key(id)
+                || flags.get(&(chat_id, id.0.clone(), id.1.clone())) != Some(&true)
+                || !handover.due(*severity, episode.started, now.timestamp())
+            {
+                continue;
+            }
+            let Some(target) = configs.get(&handover.chat_id).filter(|t| t.escalations_from.contains(&chat_id)) else { continue };
+            if *severity != Severity::Critical && target.quiet_hours.is_some_and(|w| in_local_window(&w, now)) {
+                continue;
+            }
+            let owner = config.first_name.clone().unwrap_or_else(|| format!("Chat {}", chat_id));
+            let pending = format_duration(now.timestamp() - episode.started);
+            let text = format!("{}\n\n{}", i18n::message_with(target.lang, "handover_forwarded", &[("owner", &owner), ("pending", &pending)]), text);
+            let silent = target.notifications.silent(MessageKind::Alert(*severity));
+            due.push((chat_id, config.lang, id.clone(), handover.chat_id, text, silent, pending));
+        }
+    }
+    let changed = !due.is_empty();
+    for (chat_id, lang, id, target, text, silent, pending) in due {
+        outbox.send_plain(ChatId(target), text, silent);
+        let notice = i18n::message_with(lang, "handover_sent", &[("room", &room_name(&id.0)), ("pending", &pending), ("chat", &redact::chat(target))]);
+        outbox.send(ChatId(chat_id), notice);
+        if let Some(episode) = configs.get_mut(&chat_id).and_then(|c| c.episodes.get_mut(&id)) {
+            episode.handed_over = true;
+        }
+    }
+    changed
+}
+
+// Schimmelgefahr je Chat für die Geräte, an denen er Schwellen hat (außer
+// im Freien): einmal je Strecke, mit ihrer Dauer. Eigener Zustand neben den
+// Alarm-Zuständen, denn es zählt die Dauer, nicht der einzelne Messwert.
+// true, wenn sich der Zustand geändert hat.
+fn warn_mold_risk(configs: &mut HashMap<i64, UserConfig>, history: &History, outbox: &Outbox, now: i64) -> bool {
+    let at = timeutil::from_timestamp(now);
+    let mut changed = false;
+    for (&chat_id, config) in configs.iter_mut() {
+        let devices: BTreeSet<&String> = config.thresholds.keys().map(|(device, _)| device).collect();
+        let risks: BTreeMap<String, i64> = devices
+            .into_iter()
+            .filter(|device| !rooms().is_outdoor(device) && !ignored().contains(device))
+            .filter_map(|device| {
+                let samples = history.series(device, SensorKind::Humidity.as_str())?;
+                Some((device.clone(), mold::at_risk(samples.iter().copied(), now)?))
+            })
+            .collect();
+        let before = config.mold_warned.len();
+        config.mold_warned.retain(|device, _| risks.contains_key(device));
+        changed |= config.mold_warned.len() != before;
+        // Stumm oder in der Ruhezeit: später, solange die Gefahr anhält
+        if config.is_muted(at) || config.quiet_hours.is_some_and(|w| in_local_window(&w, at)) {
+            continue;
+        }
+        for (device, start) in risks {
+            if config.mold_warned.contains_key(&device) {
+                continue;
+            }
+            let text = i18n::message_with(
+                config.lang,
+                "mold_warning",
+                &[("room", &room_name(&device)), ("duration", &format_duration(now - start)), ("limit", &format!("{:.0}", mold::HUMIDITY_LIMIT))],
+            );
+            outbox.send(ChatId(chat_id), text);
+            config.mold_warned.insert(device, start);
+            changed = true;
+        }
+    }
+    changed
+}
+
+// Nach einer Anpassung sofort neu prüfen; true, wenn der Alarm damit endet
+async fn reevaluate(config: &UserConfig, flags: &ThresholdFlags, chat_id: i64, device_id: &str, key: &ThresholdKey) -> bool {
+    let Ok(sensor_data) = fetch_sensor_data().await else { return false };
+    let Some(reading) = sensor_data.iter().find(|e| e.device_id == device_id && e.sensor_type == key.kind) else {
+        return false;
+    };
+    let mut flags = flags.lock().await;
+    monitor::evaluate_threshold(chat_id, config, reading, key.direction, &mut flags, local_time()).is_some_and(|event| event.kind == EventKind::Recovered)
+}
+
+// Warnungstext nach einer Anpassung: ursprüngliche Warnung plus neue Schwelle
+fn adjusted_alert(original: &str, sensor_type: &SensorKind, value: f64, recovered: bool, units: TempUnit, lang: Lang) -> String {
+    let original = original.split("\n\n✏️").next().unwrap_or(original);
+    let mut text = format!("{}\n\n{}", original, new_threshold(sensor_type, value, units, lang));
+    if recovered {
+        text.push_str(&format!("\n{}", i18n::message(lang, "adjust_recovered")));
+    }
+    text
+}
+
+fn new_threshold(sensor_type: &SensorKind, value: f64, units: TempUnit, lang: Lang) -> String {
+    i18n::message_with(
+        lang,
+        "adjust_new_threshold",
+        &[("value", &format!("{:.1}", units.show(sensor_type, value))), ("unit", unit_in(units, sensor_type.as_str()))],
+    )
+}
+
+// Warnungen hinter einer Nachricht. Eine Kopie im Alarm-Kanal gilt für jeden
+// Chat, dem die Schwelle gehört.
+fn alerts_posted_at(user_configs: &mut HashMap<i64, UserConfig>, chat_id: i64, message_id: i32) -> Vec<(&mut UserConfig, AlertRecord)> {
+    let mut owned = Vec::new();
+    for (owner, config) in user_configs.iter_mut() {
+        let posted_here = |r: &&AlertRecord| r.message_id == message_id && r.chat_id.unwrap_or(*owner) == chat_id;
+        if let Some(record) = config.alerts.iter().find(posted_here).cloned() {
+            owned.push((config, record));
+        }
+    }
+    owned
+}
+
+// 👍/✅ bestätigt eine Warnung, 🔇 schaltet ihre Schwellen eine Stunde stumm.
+// In Gruppen zählen nur Reaktionen von Admins. Kommen keine Reaktionen an
+// (ältere Bot-API, Bot in der Gruppe kein Admin), bleibt es bei Befehlen.
+async fn handle_reaction(bot: Bot, reaction: ReactionUpdate, configs: UserConfigs, storage: Arc<dyn Store>) -> ResponseResult<()> {
+    let actions = reaction.added();
+    if actions.is_empty() {
+        return Ok(());
+    }
+    let chat = ChatId(reaction.chat.id);
+    let allowed = if reaction.is_private() || reaction.actor_chat.as_ref().is_some_and(|actor| actor.id == chat.0) {
+        true
+    } else if let Some(user) = &reaction.user {
+        bot.get_chat_member(chat, UserId(user.id)).await?.is_privileged()
+    } else {
+        false
+    };
+    if !allowed {
+        return Ok(());
+    }
+
+    let mut user_configs = configs.lock().await;
+    let lang = user_configs.get(&chat.0).map(|c| c.lang).unwrap_or_default();
+    let mut owned = alerts_posted_at(&mut user_configs, chat.0, reaction.message_id);
+    let Some(record) = owned.first().map(|(_, record)| record.clone()) else {
+        return Ok(());
+    };
+    let now = Utc::now();
+    let mut response = None;
+    let mut ended = Vec::new();
+    for action in actions {
+        for (config, owned_record) in owned.iter_mut() {
+            for key in &owned_record.keys {
+                let threshold = (owned_record.device_id.clone(), key.clone());
+                match action {
+                    Reaction::Acknowledge => {
+                        config.acknowledged.insert(threshold, now);
+                    }
+                    Reaction::Snooze => {
+                        let until = now + chrono::Duration::seconds(reactions::SNOOZE_SECONDS);
+                        let snooze = Snooze { until, since: now, origin: snooze::Origin::Reaction };
+                        ended.extend(snooze::insert(&mut config.snoozed, threshold, snooze));
+                    }
+                }
+            }
+        }
+        match action {
+            Reaction::Acknowledge => info!("Warnung zu {} in Chat {} per Reaktion bestätigt", room_name(&record.device_id), redact::chat(chat.0)),
+            Reaction::Snooze => {
+                response = Some(format!(
+                    "{}{}",
+                    i18n::message_with(
+                        lang,
+                        "snooze_reaction",
+                        &[("room", &room_name(&record.device_id)), ("duration", &format_duration(reactions::SNOOZE_SECONDS))]
+                    ),
+                    format_snoozes_ended(&ended, lang)
+                ))
+            }
+        }
+    }
+    let mode = user_configs.get(&chat.0).map(|c| c.notifications).unwrap_or_default();
+    storage.save_users(&user_configs);
+    drop(user_configs);
+    if let Some(text) = response {
+        reply(&bot, chat, mode, text).reply_to_message_id(MessageId(reaction.message_id)).await?;
+    }
+    Ok(())
+}
+
+// Anfang des Hinweises in einer Warnung, nachdem sie per Button
+// stummgeschaltet wurde (snoozed_until in jeder Sprache)
+const SNOOZED_MARK: &str = "🔇";
+
+// Buttons unter Warnungen: "−1", "+1", "Schwelle anpassen…", "✅ OK" und "😴", unter
+// Vorschlägen nach langer Verletzung "Auf … setzen" und "Schwelle löschen", außerdem
+// die Bestätigung von Vorschauen
+async fn handle_callback(
+    bot: Bot,
+    q: CallbackQuery,
+    configs: UserConfigs,
+    flags: ThresholdFlags,
+    storage: Arc<dyn Store>,
+    pending: PendingInput,
+) -> ResponseResult<()> {
+    if let Some((apply, token)) = q.data.as_deref().and_then(confirm::parse) {
+        let token = token.to_string();
+        return handle_confirmation(bot, q, apply, &token, configs, storage).await;
+    }
+    if let (Some(click), Some(message)) = (q.data.as_deref().and_then(configure::parse), q.message.clone()) {
+        return handle_configure(bot, q, message, click, configs, pending).await;
+    }
+    let (Some(request), Some(message)) = (q.data.as_deref().and_then(adjust::parse), q.message.as_ref()) else {
+        bot.answer_callback_query(q.id).await?;
+        return Ok(());
+    };
+    let chat = message.chat.id;
+    let sensor_type = request.key.kind.clone();
+    let lang = configs.lock().await.get(&chat.0).map(|c| c.lang).unwrap_or_default();
+
+    match request.adjust {
+        Adjust::Ask => {
+            let frage = i18n::message_with(
+                lang,
+                "adjust_ask",
+                &[
+                    ("direction", &request.key.direction.as_str().to_uppercase()),
+                    ("type", type_label_in(lang, sensor_type.as_str()).0),
+                    ("room", &room_name(&request.device_id)),
+                ],
+            );
+            pending.lock().await.insert(
+                chat.0,
+                PendingAdjust {
+                    alert: message.text().map(|text| (message.id, text.to_string())),
+                    device_id: request.device_id,
+                    key: request.key,
+                    since: std::time::Instant::now(),
+                    create: false,
+                },
+            );
+            bot.answer_callback_query(q.id).await?;
+            let mode = configs.lock().await.get(&chat.0).map(|c| c.notifications).unwrap_or_default();
+            reply(&bot, chat, mode, frage).await?;
+        }
+        Adjust::Shift(delta) => {
+            let mut user_configs = configs.lock().await;
+            let Some(config) = user_configs.get_mut(&chat.0) else {
+                drop(user_configs);
+                bot.answer_callback_query(q.id).await?;
+                return Ok(());
+            };
+            let source = format!("Button {:+} an Warnung", delta);
+            // Die Buttons verschieben um eine Einheit der Anzeige
+            let units = config.units;
+            let shift = units.parse_delta(&sensor_type, delta);
+            match adjust_threshold(config, &request.device_id, &request.key, |v| v + shift, source) {
+                Ok(value) => {
+                    // Abfrage und Antworten ohne die Sperre, ausgewertet wird eine Kopie
+                    let config = config.clone();
+                    storage.save_users(&user_configs);
+                    drop(user_configs);
+                    let recovered = reevaluate(&config, &flags, chat.0, &request.device_id, &request.key).await;
+                    bot.answer_callback_query(q.id).text(new_threshold(&sensor_type, value, units, lang)).await?;
+                    if let Some(text) = message.text() {
+                        let markup = message.reply_markup().cloned();
+                        let mut edit = bot.edit_message_text(chat, message.id, adjusted_alert(text, &sensor_type, value, recovered, units, lang));
+                        if let Some(markup) = markup {
+                            edit = edit.reply_markup(markup);
+                        }
+                        edit.await?;
+                    }
+                }
+                Err(err) => {
+                    drop(user_configs);
+                    bot.answer_callback_query(q.id).text(err).show_alert(true).await?;
+                }
+            }
+        }
+        Adjust::Set(value) => {
+            let mut user_configs = configs.lock().await;
+            let Some(config) = user_configs.get_mut(&chat.0) else {
+                drop(user_configs);
+                bot.answer_callback_query(q.id).await?;
+                return Ok(());
+            };
+            let source = "Vorschlag nach langer Verletzung".to_string();
+            let units = config.units;
+            match adjust_threshold(config, &request.device_id, &request.key, |_| value, source) {
+                Ok(value) => {
+                    let config = config.clone();
+                    storage.save_users(&user_configs);
+                    drop(user_configs);
+                    let recovered = reevaluate(&config, &flags, chat.0, &request.device_id, &request.key).await;
+                    bot.answer_callback_query(q.id).text(new_threshold(&sensor_type, value, units, lang)).await?;
+                    if let Some(text) = message.text() {
+                        bot.edit_message_text(chat, message.id, adjusted_alert(text, &sensor_type, value, recovered, units, lang)).await?;
+                    }
+                }
+                Err(err) => {
+                    drop(user_configs);
+                    bot.answer_callback_query(q.id).text(err).show_alert(true).await?;
+                }
+            }
+        }
+        Adjust::Acknowledge => {
+            let now = Utc::now();
+            let mut user_configs = configs.lock().await;
+            let owned = alerts_posted_at(&mut user_configs, chat.0, message.id.0);
+            let found = !owned.is_empty();
+            for (config, record) in owned {
+                for key in &record.keys {
+                    config.acknowledged.insert((record.device_id.clone(), key.clone()), now);
+                }
+            }
+            // Ältere Warnungen ohne Eintrag: nur die Schwelle aus dem Button
+            if !found && let Some(config) = user_configs.get_mut(&chat.0) {
+                config.acknowledged.insert((request.device_id.clone(), request.key.clone()), now);
+            }
+            storage.save_users(&user_configs);
+            let tz = chat_timezone(user_configs.get(&chat.0));
+            drop(user_configs);
+            info!("Warnung zu {} in Chat {} per Button bestätigt", room_name(&request.device_id), redact::chat(chat.0));
+            bot.answer_callback_query(q.id).text(i18n::message(lang, "acknowledged")).await?;
+            if let Some(text) = message.text() {
+                let by = format!(
+                    "\n\n{}",
+                    i18n::message_with(lang, "acknowledged_by", &[("name", &q.from.full_name()), ("time", &format_local_in(now, "%H:%M", tz))])
+                );
+                let mut edit = bot.edit_message_text(chat, message.id, format!("{}{}", text, by));
+                if let Some(markup) = message.reply_markup() {
+                    edit = edit.reply_markup(adjust::without_acknowledge(markup));
+                }
+                edit.await?;
+            }
+        }
+        Adjust::Snooze(span) => {
+            let now = Utc::now();
+            let mut user_configs = configs.lock().await;
+            let tz = chat_timezone(user_configs.get(&chat.0));
+            let until = match span {
+                SnoozeFor::Hours(hours) => now + chrono::Duration::hours(hours),
+                SnoozeFor::Tomorrow => timeutil::next_fire(&WeeklySchedule::daily(SNOOZE_UNTIL_MORNING), now, tz).unwrap_or(now + chrono::Duration::hours(12)),
+            };
+            // Erneutes Drücken ersetzt die laufende Stummschaltung
+            let snooze = Snooze { until, since: now, origin: snooze::Origin::Button };
+            let mut owned = alerts_posted_at(&mut user_configs, chat.0, message.id.0);
+            let mut keys = Vec::new();
+            let mut ended = Vec::new();
+            for (config, record) in owned.iter_mut() {
+                for key in &record.keys {
+                    let threshold = (record.device_id.clone(), key.clone());
+                    ended.extend(snooze::insert(&mut config.snoozed, threshold.clone(), snooze.clone()));
+                    keys.push(threshold);
+                }
+            }
+            // Ältere Warnungen ohne Eintrag: nur die Schwelle aus dem Button
+            if owned.is_empty()
+                && let Some(config) = user_configs.get_mut(&chat.0)
+            {
+                let threshold = (request.device_id.clone(), request.key.clone());
+                ended.extend(snooze::insert(&mut config.snoozed, threshold.clone(), snooze));
+                keys.push(threshold);
+            }
+            ended.retain(|key| !keys.contains(key));
+            storage.save_users(&user_configs);
+            drop(user_configs);
+            info!("Warnung zu {} in Chat {} per Button stummgeschaltet", room_name(&request.device_id), redact::chat(chat.0));
+            let bis = format_local_in(until, "%d.%m. %H:%M", tz);
+            let muted = i18n::message_with(lang, "snoozed_until", &[("time", &bis)]);
+            bot.answer_callback_query(q.id).text(muted.clone()).await?;
+            if let Some(text) = message.text() {
+                // Nur der neueste Hinweis bleibt stehen
+                let text: Vec<&str> = text.lines().filter(|line| !line.starts_with(SNOOZED_MARK)).collect();
+                let text = format!("{}\n\n{} ({}){}", text.join("\n").trim_end(), muted, q.from.full_name(), format_snoozes_ended(&ended, lang));
+                let mut edit = bot.edit_message_text(chat, message.id, text);
+                if let Some(markup) = message.reply_markup() {
+                    edit = edit.reply_markup(markup.clone());
+                }
+                edit.await?;
+            }
+        }
+        Adjust::Disable => {
+            let mut user_configs = configs.lock().await;
+            let removed = user_configs.get_mut(&chat.0).is_some_and(|config| remove_threshold(config, (request.device_id.clone(), request.key.clone())));
+            storage.save_users(&user_configs);
+            drop(user_configs);
+            bot.answer_callback_query(q.id).text(i18n::message(lang, if removed { "threshold_deleted" } else { "threshold_gone" })).await?;
+            if removed && let Some(text) = message.text() {
+                bot.edit_message_text(chat, message.id, format!("{}\n\n{}", text, i18n::message(lang, "threshold_deleted_undo"))).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Tastaturschritte von /configure: Gerät, Messgröße, Richtung, dann wird der
+// Wert als normale Nachricht erfragt (offener Dialog wie bei "Schwelle anpassen…")
+async fn handle_configure(
+    bot: Bot,
+    q: CallbackQuery,
+    message: Message,
+    click: configure::Click,
+    configs: UserConfigs,
+    pending: PendingInput,
+) -> ResponseResult<()> {
+    use configure::Step;
+    let chat = message.chat.id;
+    let (lang, units) = configs.lock().await.get(&chat.0).map(|c| (c.lang, c.units)).unwrap_or_default();
+    if Utc::now().timestamp() - click.started > configure::TIMEOUT_SECONDS {
+        pending.lock().await.remove(&chat.0);
+        bot.answer_callback_query(q.id).text(i18n::message(lang, "configure_expired_short")).await?;
+        bot.edit_message_text(chat, message.id, i18n::message(lang, "configure_expired")).await?;
+        return Ok(());
+    }
+    bot.answer_callback_query(q.id).await?;
+    let readings = status_readings(chat.0).await.map(|(readings, _)| readings).unwrap_or_default();
+    let (text, markup) = match click.step {
+        Step::Cancel => {
+            pending.lock().await.remove(&chat.0);
+            (i18n::message(lang, "configure_cancelled").to_string(), None)
+        }
+        Step::Device { device_id } => {
+            let mut kinds: Vec<SensorKind> = Vec::new();
+            for reading in readings.iter().filter(|r| r.device_id == device_id) {
+                if !kinds.contains(&reading.sensor_type) {
+                    kinds.push(reading.sensor_type.clone());
+                }
+            }
+            let choices =
+                kinds.into_iter().map(|kind| (type_label_in(lang, kind.as_str()).0.to_string(), Step::Kind { device_id: device_id.clone(), kind })).collect();
+            (i18n::message_with(lang, "configure_kind", &[("room", &room_name(&device_id))]), Some(configure::keyboard(click.started, choices, lang)))
+        }
+        Step::Kind { device_id, kind } => {
+            let user_configs = configs.lock().await;
+            let current = |direction: ThresholdDirection| {
+                user_configs
+                    .get(&chat.0)
+                    .and_then(|c| c.active_threshold(&(device_id.clone(), ThresholdKey::new(kind.clone(), direction)), local_time()))
+                    .map(|entry| format!(" ({:.1})", units.show(&kind, entry.value)))
+                    .unwrap_or_default()
+            };
+            let choices = [ThresholdDirection::Min, ThresholdDirection::Max]
+                .into_iter()
+                .map(|direction| {
+                    let symbol = if direction.is_min() { "🔻" } else { "🔺" };
+                    let label = format!("{} {}{}", symbol, direction.as_str().to_uppercase(), current(direction));
+                    (label, Step::Direction { device_id: device_id.clone(), kind: kind.clone(), direction })
+                })
+                .collect();
+            (
+                i18n::message_with(lang, "configure_direction", &[("type", type_label_in(lang, kind.as_str()).0), ("room", &room_name(&device_id))]),
+                Some(configure::keyboard(click.started, choices, lang)),
+            )
+        }
+        Step::Direction { device_id, kind, direction } => {
+            let text = i18n::message_with(
+                lang,
+                "configure_value",
+                &[
+                    ("direction", &direction.as_str().to_uppercase()),
+                    ("type", type_label_in(lang, kind.as_str()).0),
+                    ("room", &room_name(&device_id)),
+                    ("unit", unit_in(units, kind.as_str())),
+                ],
+            );
+            pending.lock().await.insert(
+                chat.0,
+                PendingAdjust { alert: None, device_id, key: ThresholdKey::new(kind, direction), since: std::time::Instant::now(), create: true },
+            );
+            (text, Some(configure::cancel_keyboard(click.started, lang)))
+        }
+    };
+    let mut edit = bot.edit_message_text(chat, message.id, text);
+    if let Some(markup) = markup {
+        edit = edit.reply_markup(markup);
+    }
+    edit.await?;
+    Ok(())
+}
+
+// Antwort auf "Schwelle anpassen…"; ohne offenen Dialog wird nur auf Raumnamen geantwortet
+#[allow(deprecated)]
+async fn handle_pending_input(
+    bot: Bot,
+    msg: Message,
+    configs: UserConfigs,
+    flags: ThresholdFlags,
+    storage: Arc<dyn Store>,
+    pending: PendingInput,
+) -> ResponseResult<()> {
+    let chat = msg.chat.id;
+    let (mode, lang) = configs.lock().await.get(&chat.0).map(|c| (c.notifications, c.lang)).unwrap_or_default();
+    // Foto nach /setimage
+    if let Some(photo) = msg.photo().and_then(|sizes| sizes.last()) {
+        let image = PENDING_IMAGES.lock().unwrap_or_else(|e| e.into_inner()).remove(&chat.0);
+        if let Some((device, since)) = image {
+            if since.elapsed().as_secs() <= PENDING_INPUT_SECONDS {
+                let text = match room_images::store(&bot, &device, &photo.file.id).await {
+                    Ok(()) => i18n::message_with(lang, "room_image_saved", &[("room", &room_name(&device))]),
+                    Err(err) => i18n::message_with(lang, "room_image_failed", &[("error", &err)]),
+                };
+                reply(&bot, chat, mode, text).await?;
+            }
+            return Ok(());
+        }
+    }
+    let request = pending.lock().await.remove(&chat.0);
+    let Some(request) = request else {
+        return Ok(());
+    };
+    if request.since.elapsed().as_secs() > PENDING_INPUT_SECONDS {
+        return Ok(());
+    }
+    let Some(value) = msg.text().and_then(|t| t.trim().replace(',', ".").parse::<f64>().ok()) else {
+        reply(&bot, chat, mode, i18n::message(lang, "adjust_not_a_number")).await?;
+        return Ok(());
+    };
+
+    let mut user_configs = configs.lock().await;
+    let config = user_configs.entry(chat.0).or_default();
+    let source = msg.text().unwrap_or_default().to_string();
+    let units = config.units;
+    let sensor_type = &request.key.kind;
+    let value = units.parse(sensor_type, value);
+    if request.create {
+        let key = (request.device_id.clone(), request.key.clone());
+        let text = match validate_threshold(config, &key, value, None)
+            .and_then(|()| change_threshold(config, key.clone(), |s| s.set(value, None, format!("/configure: {}", source))))
+        {
+            Ok(()) => {
+                record_setter(config, key, msg.from().filter(|_| !msg.chat.is_private()).map(Setter::of));
+                storage.save_users(&user_configs);
+                format!(
+                    "{} {}",
+                    if request.key.direction.is_min() { "🔻" } else { "🔺" },
+                    i18n::message_with(
+                        lang,
+                        "threshold_set",
+                        &[
+                            ("direction", &request.key.direction.as_str().to_uppercase()),
+                            ("type", type_label_in(lang, sensor_type.as_str()).0),
+                            ("room", &room_name(&request.device_id)),
+                            ("value", &format!("{:.1}", units.show(sensor_type, value))),
+                            ("unit", unit_in(units, sensor_type.as_str())),
+                        ],
+                    )
+                )
+            }
+            Err(err) => format!("❌ {}", err),
+        };
+        drop(user_configs);
+        reply(&bot, chat, mode, text).await?;
+        return Ok(());
+    }
+    match adjust_threshold(config, &request.device_id, &request.key, |_| value, source) {
+        Ok(value) => {
+            let config = config.clone();
+            storage.save_users(&user_configs);
+            drop(user_configs);
+            let recovered = reevaluate(&config, &flags, chat.0, &request.device_id, &request.key).await;
+            reply(&bot, chat, mode, new_threshold(sensor_type, value, units, lang)).await?;
+            if let Some((message_id, text)) = request.alert {
+                bot.edit_message_text(chat, message_id, adjusted_alert(&text, sensor_type, value, recovered, units, lang)).await?;
+            }
+        }
+        Err(err) => {
+            drop(user_configs);
+            reply(&bot, chat, mode, format!("❌ {}", err)).await?;
+        }
+    }
+    Ok(())
+}
+
+// Liste der Schwellwerte inkl. Zeitplan, aktueller Eintrag ist markiert
+fn format_thresholds(config: &UserConfig, chat_id: i64) -> String {
+    let now = local_time();
+    let tz = chat_timezone(Some(config));
+    let profile = config.profiles.active();
+    let mut keys: Vec<_> = config.thresholds.keys().chain(profile.into_iter().flat_map(|(_, p)| p.thresholds.keys())).collect();
+    keys.sort();
+    keys.dedup();
+    let unset = ThresholdSchedule::default();
+
+    // Warnungen jeder Schwelle gehen an diesen Chat und die Ziele aus [routing]
+    let impaired: Vec<(i64, reachability::Failure)> = {
+        let reach = reachability();
+        std::iter::once(chat_id)
+            .chain(rooms().routing().all_targets().into_keys().filter(|target| *target != chat_id && tenant_of(chat_id).is_none()))
+            .filter_map(|target| reach.failure(target).map(|failure| (target, failure.clone())))
+            .collect()
+    };
+
+    let lang = config.lang;
+    let mut text = format!("{}\n", i18n::message(lang, "thresholds_title"));
+    if let Some((name, active)) = profile {
+        let how = match (&config.profiles.manual, active.window) {
+            (Some(_), _) => i18n::message(lang, "thresholds_profile_manual").to_string(),
+            (None, Some(window)) => i18n::message_with(lang, "clock_time", &[("time", &window.to_string())]),
+            (None, None) => i18n::message(lang, "thresholds_profile_chosen").to_string(),
+        };
+        let line = i18n::message_with(lang, "thresholds_profile", &[("name", &markdown_bold(name)), ("how", &escape_markdown(&how))]);
+        text.push_str(&format!("{}\n", line));
+    }
+    for key in keys {
+        let schedule = config.thresholds.get(key).unwrap_or(&unset);
+        let typ = type_label_in(lang, key.1.kind.as_str()).0;
+        let einheit = unit_in(config.units, key.1.kind.as_str());
+        let overridden = config.profiles.override_for(key);
+        let active = if overridden.is_some() { None } else { schedule.active_entry(now) };
+
+        let mut flags = Vec::new();
+        if config.unmonitored.contains_key(key) {
+            flags.push(i18n::message(lang, "thresholds_flag_no_readings").to_string());
+        }
+        if let Some(episode) = config.episodes.get(key) {
+            flags.push(i18n::message_with(lang, "thresholds_flag_alarm", &[("since", &format_timestamp_in(episode.started, "%d.%m. %H:%M", tz))]));
+        }
+        if config.acknowledged.contains_key(key) {
+            flags.push(i18n::message(lang, "thresholds_flag_acknowledged").to_string());
+        }
+        if let Some(snooze) = config.snoozed.get(key).filter(|snooze| snooze.until > Utc::now()) {
+            flags.push(i18n::message_with(lang, "thresholds_flag_snoozed", &[("until", &format_timestamp_in(snooze.until.timestamp(), "%H:%M", tz))]));
+        }
+        if !impaired.is_empty() {
+            flags.push(i18n::message(lang, "thresholds_flag_undelivered").to_string());
+        }
+        if ignored().contains(&key.0) {
+            flags.push(i18n::message(lang, "thresholds_flag_ignored").to_string());
+        }
+        let warning: String = flags.iter().map(|flag| format!(" {}", flag)).collect();
+        text.push_str(&format!("📍 {} – {} {}:{}\n", markdown_bold(&room_name(&key.0)), escape_markdown(typ), key.1.direction, warning));
+        if let Some(hysteresis) = config.hysteresis.get(key) {
+            let value = format!("{:.1}", config.units.show_delta(&key.1.kind, *hysteresis));
+            text.push_str(&format!("   {}\n", i18n::message_with(lang, "thresholds_hysteresis", &[("value", &value), ("unit", einheit)])));
+        }
+        if let Some(minutes) = config.repeat.get(key) {
+            text.push_str(&format!("   {}\n", i18n::message_with(lang, "thresholds_repeat", &[("interval", &format_duration(minutes * 60))])));
+        }
+        if let Some(style) = config.alert_styles.get(key) {
+            if let Some(severity) = style.severity {
+                text.push_str(&format!("   🏷 {}\n", i18n::message_with(lang, "threshold_severity", &[("severity", severity.label(lang))])));
+            }
+            if let Some(custom) = &style.text {
+                text.push_str(&format!("   💬 {}\n", escape_markdown(custom)));
+            }
+        }
+        for entry in schedule.entries() {
+            let zeitraum = match entry.window {
+                Some(w) => i18n::message_with(lang, "clock_time", &[("time", &w.to_string())]),
+                None if schedule.entries().len() > 1 => i18n::message(lang, "thresholds_otherwise").to_string(),
+                None => i18n::message(lang, "thresholds_always").to_string(),
+            };
+            let marker = if active == Some(entry) { format!(" {}", i18n::message(lang, "thresholds_active_mark")) } else { String::new() };
+            text.push_str(&format!("   {:.1} {} ({}){}\n", config.units.show(&key.1.kind, entry.value), einheit, zeitraum, marker));
+        }
+        let profile_line = |value: String, name: &str| {
+            let line = i18n::message_with(lang, "thresholds_profile_value", &[("value", &value), ("name", &escape_markdown(name))]);
+            format!("   {} {}\n", line, i18n::message(lang, "thresholds_active_mark"))
+        };
+        match overridden {
+            Some((name, Some(value))) => text.push_str(&profile_line(format!("{:.1} {}", config.units.show(&key.1.kind, value), einheit), name)),
+            Some((name, None)) => text.push_str(&profile_line(i18n::message(lang, "off").to_string(), name)),
+            None => {}
+        }
+    }
+    let mut rates: Vec<_> = config.rates.iter().collect();
+    rates.sort_by(|a, b| a.0.cmp(b.0));
+    for ((device_id, kind), rule) in rates {
+        let line = i18n::message_with(
+            lang,
+            "thresholds_rate",
+            &[
+                ("icon", if rule.delta < 0.0 { "📉" } else { "📈" }),
+                ("room", &markdown_bold(&room_name(device_id))),
+                ("type", &escape_markdown(type_label_in(lang, kind.as_str()).0)),
+                ("change", &format!("{:+.1}", config.units.show_delta(kind, rule.delta))),
+                ("unit", unit_in(config.units, kind.as_str())),
+                ("window", &format_duration(rule.minutes * 60)),
+            ],
+        );
+        text.push_str(&format!("{}\n", line));
+    }
+    let mut relations: Vec<_> = config.relations.iter().collect();
+    relations.sort_by(|a, b| a.0.cmp(b.0));
+    for ((device_id, key), rule) in relations {
+        let line = i18n::message_with(
+            lang,
+            if key.direction == ThresholdDirection::Max { "thresholds_relation_above" } else { "thresholds_relation_below" },
+            &[
+                ("room", &markdown_bold(&room_name(device_id))),
+                ("type", &escape_markdown(type_label_in(lang, key.kind.as_str()).0)),
+                ("direction", &key.direction.to_string()),
+                ("other_type", &escape_markdown(type_label_in(lang, rule.other_kind.as_str()).0)),
+                ("other_room", &escape_markdown(&room_name(&rule.other_device))),
+                ("offset", &format!("{:+.1}", config.units.show_delta(&key.kind, rule.offset))),
+                ("unit", unit_in(config.units, key.kind.as_str())),
+            ],
+        );
+        text.push_str(&format!("{}\n", line));
+    }
+    if !config.unmonitored.is_empty() {
+        let note = i18n::message_with(lang, "thresholds_no_readings_note", &[("duration", &format_duration(coverage::SEEN_WITHIN_SECONDS))]);
+        text.push_str(&format!("\n{}\n", note));
+    }
+    for (target, failure) in &impaired {
+        let chat = if *target == chat_id {
+            i18n::message(lang, "thresholds_this_chat").to_string()
+        } else {
+            i18n::message_with(lang, "thresholds_other_chat", &[("chat", &redact::chat(*target))])
+        };
+        let line = i18n::message_with(
+            lang,
+            "thresholds_undelivered",
+            &[("chat", &chat), ("since", &format_local_in(failure.since, "%d.%m. %H:%M", tz)), ("reason", &messenger::reason_in(&failure.reason, lang))],
+        );
+        text.push_str(&format!("\n{}", line));
+    }
+    text
+}
+
+// "{name}" durch den Vornamen ersetzen; ohne Namen fällt der Platzhalter
+// samt davorstehendem Leerzeichen bzw. Komma weg ("Hallo!")
+fn fill_name(template: &str, name: Option<&str>) -> String {
+    match name {
+        Some(name) => template.replace("{name}", name),
+        None => template.replace(", {name}", "").replace(" {name}", "").replace("{name}", ""),
+    }
+}
+
+// Wartet der Chat auf eine Eingabe, z.B. einen Zahlenwert nach "✏️ Anpassen"
+// oder ein Foto nach /setimage?
+async fn awaiting_input(msg: Message, pending: PendingInput) -> bool {
+    let chat = msg.chat.id.0;
+    (msg.photo().is_some() && PENDING_IMAGES.lock().unwrap_or_else(|e| e.into_inner()).contains_key(&chat)) || pending.lock().await.contains_key(&chat)
+}
+
+// Freitext ohne Befehl und ohne offenen Dialog: Raumname als Frage, z.B.
+// "Schlafzimmer?", sonst Antwort aus REPLIES_FILE
+async fn handle_message(bot: Bot, msg: Message, me: Me, configs: UserConfigs, history: SharedHistory) -> ResponseResult<()> {
+    // In Gruppen nur, wenn der Bot erwähnt oder ihm geantwortet wird
+    let Some(text) = addressed_text(&msg, &me) else {
+        return Ok(());
+    };
+    let text = text.as_str();
+    let chat = msg.chat.id;
+    let (notes, with_image, name, lang, units, tz, times, mode) = configs
+        .lock()
+        .await
+        .get(&chat.0)
+        .map(|c| (c.notes.clone(), c.room_images, c.first_name.clone(), c.lang, c.units, c.timezone, c.time_format, c.notifications))
+        .unwrap_or_default();
+    let tz = tz.or(settings().timezone);
+    if let Some((status, device)) = room_status(chat.0, text, &history, &notes, lang, units, tz, times).await {
+        return send_room_status(&bot, chat, mode, status, device.as_deref().filter(|_| with_image)).await;
+    }
+    reply(&bot, chat, mode, fill_name(replies().reply(text, lang), name.as_deref())).await?;
+    Ok(())
+}
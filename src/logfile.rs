@@ -0,0 +1,91 @@
+use chrono::Local;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const DEFAULT_MAX_MB: u64 = 10;
+const DEFAULT_KEEP: usize = 5;
+
+/// Log-Datei, die beim Start angehängt statt geleert wird und ab einer
+/// Größe zur Seite gelegt wird (`bot.log.20240501-031500`). Es bleiben die
+/// neuesten `keep` alten Dateien. Umbenennen und Löschen sind kurze
+/// Dateisystem-Aufrufe im Schreibpfad des Loggers, ohne Kopieren.
+pub struct RotatingLog {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_bytes: u64, // 0 = nie rotieren
+    keep: usize,
+}
+
+impl RotatingLog {
+    pub fn open(path: impl Into<PathBuf>, max_bytes: u64, keep: usize) -> io::Result<RotatingLog> {
+        let path = path.into();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(RotatingLog { path, file, written, max_bytes, keep })
+    }
+
+    /// LOG_FILE (Standard bot.log), LOG_MAX_MB (Standard 10, 0 = nie
+    /// rotieren) und LOG_KEEP (Standard 5 alte Dateien)
+    pub fn from_env() -> io::Result<RotatingLog> {
+        let path = env::var("LOG_FILE").unwrap_or_else(|_| "bot.log".to_string());
+        let max_mb = env::var("LOG_MAX_MB").ok().and_then(|v| v.parse().ok()).unwrap_or(DEFAULT_MAX_MB);
+        let keep = env::var("LOG_KEEP").ok().and_then(|v| v.parse().ok()).unwrap_or(DEFAULT_KEEP);
+        RotatingLog::open(path, max_mb * 1024 * 1024, keep)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let name = self.path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let mut rotated = self.path.with_file_name(format!("{}.{}", name, Local::now().format("%Y%m%d-%H%M%S")));
+        // Zwei Rotationen in derselben Sekunde
+        let mut n = 1;
+        while rotated.exists() {
+            rotated = self.path.with_file_name(format!("{}.{}-{}", name, Local::now().format("%Y%m%d-%H%M%S"), n));
+            n += 1;
+        }
+        fs::rename(&self.path, &rotated)?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.written = 0;
+        prune(&self.path, &name, self.keep);
+        Ok(())
+    }
+}
+
+// Älteste rotierte Dateien über `keep` hinaus löschen; der Zeitstempel im
+// Namen sortiert chronologisch
+fn prune(path: &Path, name: &str, keep: usize) {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    let prefix = format!("{}.", name);
+    let mut rotated: Vec<PathBuf> =
+        entries.filter_map(|entry| entry.ok()).filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix)).map(|entry| entry.path()).collect();
+    rotated.sort();
+    let excess = rotated.len().saturating_sub(keep);
+    for old in &rotated[..excess] {
+        fs::remove_file(old).ok();
+    }
+}
+
+impl Write for RotatingLog {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.max_bytes > 0 && self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            // Schlägt das Umbenennen fehl, wird in die alte Datei weitergeschrieben
+            if self.rotate().is_err() {
+                self.written = 0;
+            }
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -1,294 +1,129 @@
-use teloxide::prelude::*;
-use teloxide::utils::command::BotCommands;
-use tokio::sync::Mutex;
-use std::collections::HashMap;
-use std::sync::{Arc, OnceLock};
-use simplelog::*;
-use std::fs::File;
 use dotenv::dotenv;
+use log::error;
+use simplelog::*;
 use std::env;
-use log::info;
-use reqwest;
-use serde::{Serialize, Deserialize};
-use teloxide::types::ParseMode;
-use chrono::{NaiveDateTime, Local, TimeZone};
-
-// Iteration in der neue Sensordaten abgerufen werden:
-const ITERATION_IN_SECONDS: u64 = 10 * 60; // 10 minutes
-// Wird das /status Kommando benutzt, wird nochmal extra abgefragt.
-// Die ITERATION ist nur für Grenzwerte interessant und da reichen
-// 10 Minuten.
-
-
-
-// Struktur für JSON Daten
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct SensorData {
-    device_id: String,   // Unique identifier for each sensor
-    sensor_type: String, // Example: "temperature" or "humidity"
-    value: f64,          // The measured value
-    timestamp: i64,   // (Optional) If time tracking is wanted
-}
-
-// Benutzerkonfiguration
-#[derive(Default)]
-struct UserConfig {
-    thresholds: HashMap<(String, String), f64>, // (sensor_id, sensor_type) -> threshold
-}
-
-type UserConfigs = Arc<Mutex<HashMap<i64, UserConfig>>>;
-
-
-// Telegram-Befehle
-#[derive(BotCommands, Clone)]
-#[command(rename_rule = "kebab-case", description = "Verfügbare Befehle:")]
-enum Command {
-    #[command(description = "Startet den Bot.")]
-    Start,
-    #[command(description = "Zeigt diese Hilfe an.")]
-    Help,
-    #[command(description = "Zeigt alle aktuellen Sensordaten.")]
-    Status,
-    #[command(description = "Temperaturverlauf Wohnzimmer.")]
-    WohnzimmerTdia,
-    #[command(description = "Luftfeuchtigkeitsverlauf Wohnzimmer.")]
-    WohnzimmerHdia,
-    #[command(description = "Alarm, wenn Temperatur unter Wert fällt.")]
-    WohnzimmerTmin(f64),
-    #[command(description = "Alarm, wenn Temperatur über Wert steigt.")]
-    WohnzimmerTmax(f64),
-    #[command(description = "Alarm, wenn Luftfeuchtigkeit unter Wert fällt.")]
-    WohnzimmerHmin(f64),
-    #[command(description = "Alarm, wenn Luftfeuchtigkeit über Wert steigt.")]
-    WohnzimmerHmax(f64),
-}
-
-// Sensordaten von Webserver abrufen
-async fn fetch_sensor_data() -> Option<Vec<SensorData>> {
-    println!("DEBUG: Starte HTTP-Anfrage an localhost:8080/sensors");
-
-    let response = reqwest::get("http://localhost:8080/sensors").await;
-
-    match response {
-        Ok(resp) => match resp.text().await {
-            Ok(text) => {
-                match serde_json::from_str::<Vec<SensorData>>(&text) {
-                    Ok(data) => {
-                        //println!("📊 Erhaltene Sensordaten: {:?}", data);
-                        Some(data) // Return multiple sensors
-                    }
-                    Err(err) => {
-                        println!("Fehler beim JSON-Parsing: {:?}\nJSON-Text: {}", err, text);
-                        None
-                    }
-                }
-            }
-            Err(err) => {
-                println!("Fehler beim Abrufen der Antwort als Text: {:?}", err);
-                None
-            }
-        },
-        Err(err) => {
-            println!("Fehler bei der HTTP-Anfrage: {:?}", err);
-            None
-        }
-    }
-}
+use telegrambot::{HttpSource, JsonStore, RotatingLog, SensorBot, Settings, parse_endpoints, token_from_env};
+#[cfg(feature = "mqtt")]
+use telegrambot::{MqttConfig, MqttSource};
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
-    let token = env::var("TELEGRAMBOT_TOKEN").expect("TELEGRAMBOT_TOKEN nicht gesetzt!");
-
-    CombinedLogger::init(vec![
-        TermLogger::new(LevelFilter::Info, Config::default(), TerminalMode::Mixed, ColorChoice::Auto),
-        WriteLogger::new(LevelFilter::Info, Config::default(), File::create("bot.log").unwrap()),
-    ]).unwrap();
-
-    let bot = Bot::new(token);
-    let user_configs: UserConfigs = Arc::new(Mutex::new(HashMap::new()));
-    let threshold_flags: Arc<Mutex<HashMap<(i64, String, String), bool>>> = Arc::new(Mutex::new(HashMap::new()));
-
-    // Sensor-Überwachung starten
-    let bot_clone = bot.clone();
-    let configs_clone = user_configs.clone();
-    let flags_clone = threshold_flags.clone();
-
-    tokio::spawn(async move {
-        loop {
-            if let Some(sensor_data_list) = fetch_sensor_data().await {
-                let mut configs = configs_clone.lock().await;
-                let mut flags = flags_clone.lock().await;
-
-                for sensor in sensor_data_list {
-                    for (&user_id, config) in configs.iter() {
-                        let key_min = (sensor.device_id.clone(), format!("{}_min", sensor.sensor_type));
-                        let key_max = (sensor.device_id.clone(), format!("{}_max", sensor.sensor_type));
-
-                        let user_key_min = (user_id, sensor.device_id.clone(), key_min.1.clone());
-                        let user_key_max = (user_id, sensor.device_id.clone(), key_max.1.clone());
-
-                        if let Some(&min_val) = config.thresholds.get(&key_min) {
-                            if sensor.value < min_val {
-                                if flags.get(&user_key_min) != Some(&true) {
-                                    let _ = bot_clone.send_message(ChatId(user_id), format!(
-                                        "⚠ {} im {} ist unter die Schwelle gefallen: {:.1} (Schwelle: {:.1})",
-                                        sensor.sensor_type, sensor.device_id, sensor.value, min_val
-                                    )).await;
-                                    flags.insert(user_key_min, true);
-                                }
-                            } else {
-                                flags.insert(user_key_min, false);
-                            }
-                        }
-
-                        if let Some(&max_val) = config.thresholds.get(&key_max) {
-                            if sensor.value > max_val {
-                                if flags.get(&user_key_max) != Some(&true) {
-                                    let _ = bot_clone.send_message(ChatId(user_id), format!(
-                                        "⚠ {} im {} ist über die Schwelle gestiegen: {:.1} (Schwelle: {:.1})",
-                                        sensor.sensor_type, sensor.device_id, sensor.value, max_val
-                                    )).await;
-                                    flags.insert(user_key_max, true);
-                                }
-                            } else {
-                                flags.insert(user_key_max, false);
-                            }
-                        }
-                    }
-                }
-            }
-
-            tokio::time::sleep(tokio::time::Duration::from_secs(ITERATION_IN_SECONDS)).await;
-        }
-    });
 
-    // Dispatcher starten
-    let handler = Update::filter_message()
-        .branch(dptree::entry().filter_command::<Command>().endpoint(answer));
-
-    Dispatcher::builder(bot, handler)
-        .dependencies(dptree::deps![user_configs, threshold_flags])
-        .enable_ctrlc_handler()
-        .build()
-        .dispatch()
-        .await;
-}
-
-async fn answer(
-    bot: Bot,
-    msg: Message,
-    cmd: Command,
-    configs: UserConfigs,
-    // threshold_flags ist hier nicht nötig
-) -> ResponseResult<()> {
-    let user_id = msg.chat.id;
-    let mut user_configs = configs.lock().await;
-
-    match cmd {
-        Command::Start => {
-            bot.send_message(user_id, "👋 Willkommen! Nutze /help für alle Befehle.").await?;
-        }
-
-        Command::Help => {
-            let text = Command::descriptions();
-            bot.send_message(user_id, format!("📖 *Hilfe:*\n{}", text))
-                .parse_mode(ParseMode::Markdown)
-                .await?;
+    // Offline-Auswertung: kein Bot, keine Log-Datei, keine Sperre
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().is_some_and(|a| a == "simulate") {
+        TermLogger::init(LevelFilter::Warn, Config::default(), TerminalMode::Stderr, ColorChoice::Auto).unwrap();
+        if let Err(err) = telegrambot::simulate(&args[1..]) {
+            eprintln!("{}\n{}", err, telegrambot::SIMULATE_USAGE);
+            std::process::exit(2);
         }
-
-        Command::Status => {
-            if let Some(sensor_data) = fetch_sensor_data().await {
-                let mut text = String::from("📊 *Aktuelle Sensordaten:*\n");
-
-                for entry in sensor_data {
-                    let raum = match entry.device_id.as_str() {
-                        "sensor1" => "Wohnzimmer",
-                        _ => &entry.device_id,
-                    };
-
-                    let (typ, einheit) = match entry.sensor_type.as_str() {
-                        "temperature" => ("Temperatur", "°C"),
-                        "humidity" => ("Luftfeuchtigkeit", "%"),
-                        _ => (&entry.sensor_type[..], ""),
-                    };
-
-                    let dt = NaiveDateTime::from_timestamp_opt(entry.timestamp as i64, 0)
-                    .unwrap_or_else(|| NaiveDateTime::from_timestamp(0, 0));
-                    let zeit = Local.from_utc_datetime(&dt);
-                
-                    let formatted = zeit.format("%d.%m.%Y %H:%M:%S");
-
-                    text.push_str(&format!("📍 *{}* – {}: *{:.1} {}* ({})\n", raum, typ, entry.value, einheit, formatted));
-                }
-
-                bot.send_message(user_id, text)
-                    .parse_mode(ParseMode::Markdown)
-                    .await?;
-            } else {
-                bot.send_message(user_id, "❌ Fehler beim Abrufen der Sensordaten.").await?;
-            }
-        }
-
-        Command::WohnzimmerTdia => {
-            let url = "https://thingspeak.mathworks.com/channels/1115568/charts/1?...";
-            bot.send_message(user_id, "📈 *Temperaturverlauf Wohnzimmer:*")
-                .parse_mode(ParseMode::Markdown)
-                .await?;
-            bot.send_message(user_id, url).disable_web_page_preview(false).await?;
+        return;
+    }
+    if args.first().is_some_and(|a| a == "rebuild-records") {
+        if let Err(err) = telegrambot::rebuild_records(&args[1..]) {
+            eprintln!("{}\n{}", err, telegrambot::RECORDS_USAGE);
+            std::process::exit(2);
         }
-
-        Command::WohnzimmerHdia => {
-            let url = "https://thingspeak.mathworks.com/channels/1115568/charts/2?...";
-            bot.send_message(user_id, "💧 *Luftfeuchtigkeit Wohnzimmer:*")
-                .parse_mode(ParseMode::Markdown)
-                .await?;
-            bot.send_message(user_id, url).disable_web_page_preview(false).await?;
+        return;
+    }
+    if args.first().is_some_and(|a| a == "import-thingspeak") {
+        TermLogger::init(LevelFilter::Warn, Config::default(), TerminalMode::Stderr, ColorChoice::Auto).unwrap();
+        if let Err(err) = telegrambot::import_thingspeak(&args[1..]).await {
+            eprintln!("{}\n{}", err, telegrambot::THINGSPEAK_USAGE);
+            std::process::exit(2);
         }
-
-        Command::WohnzimmerTmin(value) => {
-            user_configs.entry(user_id.0).or_default()
-                .thresholds.insert(("Wohnzimmer".into(), "temperature_min".into()), value);
-
-            bot.send_message(user_id, format!("🔻 MIN-Schwellwert Temperatur Wohnzimmer: {:.1} °C", value)).await?;
+        return;
+    }
+    if args.first().is_some_and(|a| a == "restore") {
+        if let Err(err) = telegrambot::restore(&args[1..]) {
+            eprintln!("{}\n{}", err, telegrambot::RESTORE_USAGE);
+            std::process::exit(2);
         }
+        return;
+    }
 
-        Command::WohnzimmerTmax(value) => {
-            user_configs.entry(user_id.0).or_default()
-                .thresholds.insert(("Wohnzimmer".into(), "temperature_max".into()), value);
-
-            bot.send_message(user_id, format!("🔺 MAX-Schwellwert Temperatur Wohnzimmer: {:.1} °C", value)).await?;
+    // Laufende Verletzungen nach dem Start erneut melden statt sie fortzusetzen
+    let announce_on_start = args.iter().any(|a| a == "--announce-on-start");
+
+    // Alle Fehler der Konfiguration auf einmal melden, nicht nur den ersten
+    let mut problems = Vec::new();
+    let token = token_from_env().map_err(|err| problems.push(err)).ok();
+    #[cfg(feature = "mqtt")]
+    let mqtt = MqttConfig::from_env().transpose().map_err(|err| problems.push(err)).ok().flatten();
+    #[cfg(not(feature = "mqtt"))]
+    let mqtt: Option<()> = {
+        if env::var("MQTT_URL").is_ok_and(|url| !url.trim().is_empty()) {
+            problems.push("MQTT_URL ist gesetzt, aber MQTT ist in diesem Build deaktiviert (Feature mqtt)".to_string());
         }
-
-        Command::WohnzimmerHmin(value) => {
-            user_configs.entry(user_id.0).or_default()
-                .thresholds.insert(("Wohnzimmer".into(), "humidity_min".into()), value);
-
-            bot.send_message(user_id, format!("🔻 MIN-Schwellwert Luftfeuchtigkeit Wohnzimmer: {:.1} %", value)).await?;
+        None
+    };
+    // Mit MQTT_URL ist SENSOR_ENDPOINTS optional; beides zusammen geht auch
+    let endpoints = match env::var("SENSOR_ENDPOINTS") {
+        Ok(list) => parse_endpoints(&list),
+        Err(_) if mqtt.is_some() => Ok(Vec::new()),
+        Err(_) => Err("nicht gesetzt".to_string()),
+    };
+    let endpoints = endpoints
+        .map_err(|err| problems.push(format!("SENSOR_ENDPOINTS: {}, z.B. SENSOR_ENDPOINTS=http://localhost:8080/sensors,http://gateway2:8080/sensors 1h", err)))
+        .unwrap_or_default();
+    let settings = Settings::from_env_checked().map_err(|errors| problems.extend(errors)).ok();
+    let log_file = RotatingLog::from_env().map_err(|err| problems.push(format!("Log-Datei kann nicht geöffnet werden: {}", err))).ok();
+    let (token, settings, log_file) = match (token, settings, log_file) {
+        (Some(token), Some(mut settings), Some(log_file)) if problems.is_empty() => {
+            settings.announce_on_start |= announce_on_start;
+            (token, settings, log_file)
         }
-
-        Command::WohnzimmerHmax(value) => {
-            user_configs.entry(user_id.0).or_default()
-                .thresholds.insert(("Wohnzimmer".into(), "humidity_max".into()), value);
-
-            bot.send_message(user_id, format!("🔺 MAX-Schwellwert Luftfeuchtigkeit Wohnzimmer: {:.1} %", value)).await?;
+        _ => {
+            eprintln!("Konfiguration fehlerhaft:\n  {}", problems.join("\n  "));
+            std::process::exit(2);
         }
+    };
+    CombinedLogger::init(vec![
+        TermLogger::new(LevelFilter::Info, Config::default(), TerminalMode::Mixed, ColorChoice::Auto),
+        WriteLogger::new(LevelFilter::Info, Config::default(), log_file),
+    ])
+    .unwrap();
+
+    let mut builder = SensorBot::builder().token(token).settings(settings);
+    for endpoint in endpoints {
+        builder = builder.source(match endpoint.interval {
+            Some(interval) => HttpSource::new(endpoint.url).with_interval(interval),
+            None => HttpSource::new(endpoint.url),
+        });
+    }
+    #[cfg(feature = "mqtt")]
+    if let Some(config) = mqtt {
+        builder = builder.source(MqttSource::connect(config));
     }
+    let started = builder.store(JsonStore::from_env()).run().await;
+    let bot = match started {
+        Ok(bot) => bot,
+        Err(err) => {
+            error!("{}", err);
+            std::process::exit(1);
+        }
+    };
 
-    Ok(())
+    stop_signal().await;
+    bot.shutdown().await;
 }
 
-
-async fn handle_message(bot: Bot, msg: Message) -> ResponseResult<()> {
-    if let Some(text) = msg.text() {
-        let user_id = msg.chat.id;
-        let response = match text.to_lowercase().as_str() {
-            "hallo" => "👋 Hallo Julia! Wie kann ich helfen?",
-            "wie geht's?" => "Mir geht es super! 🤖",
-            "ich liebe dich" => "Ich liebe dich auch",
-            _ => "Ich habe dich nicht verstanden. Nutze /help für Befehle.",
-        }; // alles in Kleinschreibung angeben!!
-        bot.send_message(user_id, response).await?;
+// Strg+C oder SIGTERM (docker stop, systemd)
+async fn stop_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = term.recv() => {}
+                }
+                return;
+            }
+            Err(err) => error!("SIGTERM kann nicht abgefangen werden: {}", err),
+        }
     }
-    Ok(())
+    tokio::signal::ctrl_c().await.ok();
 }
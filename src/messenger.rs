@@ -0,0 +1,216 @@
+use crate::i18n::{self, Lang};
+use crate::room_images;
+use crate::routing::Severity;
+use crate::source::BoxFuture;
+use std::time::Duration;
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardMarkup, MessageId, ParseMode};
+use teloxide::{ApiError, RequestError};
+
+/// Hintergrund-Nachricht aus Überwachung und Zeitplänen
+#[derive(Debug, Clone)]
+pub struct OutgoingMessage {
+    pub chat_id: i64,
+    pub text: String,
+    /// Legacy-Markdown wie bei /status, sonst Klartext
+    pub markdown: bool,
+    /// HTML statt Markdown, z.B. für die Tabelle von /status
+    pub html: bool,
+    /// Inline-Buttons unter der Nachricht (Schwelle anpassen)
+    pub buttons: Option<InlineKeyboardMarkup>,
+    /// Ohne Benachrichtigungston zustellen
+    pub silent: bool,
+    /// Als Antwort auf diese Nachricht zustellen (Folgemeldung zu einer Warnung)
+    pub reply_to: Option<i32>,
+    /// Gerät, dessen Raumbild (falls hinterlegt) mit dem Text als
+    /// Bildunterschrift gesendet wird. Kanäle ohne Bilder senden nur den Text.
+    pub room_image: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum SendError {
+    /// Gedrosselt; die Warteschlange versucht es nach der Wartezeit erneut
+    RetryAfter(Duration),
+    /// Die Nachricht aus `reply_to` gibt es nicht mehr (gelöscht)
+    ReplyTargetMissing,
+    /// Der Chat nimmt keine Nachrichten des Bots an (blockiert, entfernt,
+    /// keine Schreibrechte); der Text beschreibt den Grund
+    Unreachable(String),
+    Failed(String),
+}
+
+/// Stellt Hintergrund-Nachrichten zu. Standard ist Telegram, eingebettet
+/// kann z.B. ein anderer Kanal oder ein Log dienen.
+///
+/// Stabilität: Teil der öffentlichen Schnittstelle, Änderungen nur mit
+/// neuer Hauptversion.
+pub trait Messenger: Send + Sync {
+    fn send<'a>(&'a self, message: &'a OutgoingMessage) -> BoxFuture<'a, Result<(), SendError>>;
+
+    /// Wie `send`, liefert aber die ID der zugestellten Nachricht, falls der
+    /// Kanal eine kennt. Damit lassen sich Reaktionen einer Warnung zuordnen.
+    fn send_returning_id<'a>(&'a self, message: &'a OutgoingMessage) -> BoxFuture<'a, Result<Option<i32>, SendError>> {
+        Box::pin(async move { self.send(message).await.map(|()| None) })
+    }
+}
+
+/// Warnung für ein zusätzliches Ziel, siehe [`AlertSink`]
+#[derive(Debug, Clone)]
+pub struct SinkAlert {
+    /// Chat, dessen Schwelle verletzt ist
+    pub chat_id: i64,
+    pub device_id: String,
+    /// Klartext wie im Chat
+    pub text: String,
+    pub severity: Severity,
+    /// Zeitpunkt des auslösenden Messwerts
+    pub observed_at: i64,
+}
+
+/// Zusätzliches Ziel für Schwellwert-Warnungen neben dem Chat, z.B. E-Mail
+/// oder ein Webhook. Bekommt jede Warnung sofort, unabhängig von Ruhezeit
+/// und Sammelmodus des Chats; Fehler werden nur protokolliert.
+///
+/// Stabilität: Teil der öffentlichen Schnittstelle, Änderungen nur mit
+/// neuer Hauptversion.
+pub trait AlertSink: Send + Sync {
+    fn alert<'a>(&'a self, alert: &'a SinkAlert) -> BoxFuture<'a, Result<(), String>>;
+}
+
+/// Zustellung über die Telegram-Bot-API
+pub struct TelegramMessenger {
+    bot: Bot,
+}
+
+impl TelegramMessenger {
+    pub fn new(bot: Bot) -> TelegramMessenger {
+        TelegramMessenger { bot }
+    }
+}
+
+impl Messenger for TelegramMessenger {
+    fn send<'a>(&'a self, message: &'a OutgoingMessage) -> BoxFuture<'a, Result<(), SendError>> {
+        self.bot.send(message)
+    }
+
+    fn send_returning_id<'a>(&'a self, message: &'a OutgoingMessage) -> BoxFuture<'a, Result<Option<i32>, SendError>> {
+        self.bot.send_returning_id(message)
+    }
+}
+
+// Auch der Bot selbst, damit Antworten auf Befehle denselben Weg nehmen
+impl Messenger for Bot {
+    fn send<'a>(&'a self, message: &'a OutgoingMessage) -> BoxFuture<'a, Result<(), SendError>> {
+        Box::pin(async move { self.send_returning_id(message).await.map(|_| ()) })
+    }
+
+    // Legacy-Markdown bis zur Umstellung auf MarkdownV2
+    #[allow(deprecated)]
+    fn send_returning_id<'a>(&'a self, message: &'a OutgoingMessage) -> BoxFuture<'a, Result<Option<i32>, SendError>> {
+        Box::pin(async move {
+            if let Some(device) = &message.room_image
+                && let Some(result) = room_images::send_photo(self, message, device).await
+            {
+                return result.map(|sent| Some(sent.id.0)).map_err(send_error);
+            }
+            let mut request = self.send_message(ChatId(message.chat_id), &message.text);
+            if message.html {
+                request = request.parse_mode(ParseMode::Html);
+            } else if message.markdown {
+                request = request.parse_mode(ParseMode::Markdown);
+            }
+            if let Some(buttons) = &message.buttons {
+                request = request.reply_markup(buttons.clone());
+            }
+            if message.silent {
+                request = request.disable_notification(true);
+            }
+            if let Some(reply_to) = message.reply_to {
+                request = request.reply_to_message_id(MessageId(reply_to));
+            }
+            request.await.map(|sent| Some(sent.id.0)).map_err(send_error)
+        })
+    }
+}
+
+// Für Befehls-Handler, die Telegram-Fehler weiterreichen
+impl From<SendError> for RequestError {
+    fn from(err: SendError) -> RequestError {
+        match err {
+            SendError::RetryAfter(wait) => RequestError::RetryAfter(wait),
+            SendError::ReplyTargetMissing => RequestError::Api(ApiError::MessageToReplyNotFound),
+            SendError::Unreachable(reason) | SendError::Failed(reason) => RequestError::Io(std::io::Error::other(reason)),
+        }
+    }
+}
+
+fn send_error(err: RequestError) -> SendError {
+    match err {
+        RequestError::RetryAfter(wait) => SendError::RetryAfter(wait),
+        RequestError::Api(err) if reply_target_missing(&err) => SendError::ReplyTargetMissing,
+        err => match unreachable_reason(&err) {
+            Some(reason) => SendError::Unreachable(reason.to_string()),
+            None => SendError::Failed(err.to_string()),
+        },
+    }
+}
+
+// Grund für einen Chat, der den Bot blockiert hat; an ihn gehen keine
+// Warnungen mehr, bis er wieder einen Befehl schickt
+pub(crate) const BLOCKED: &str = "Bot wurde blockiert";
+
+// Schlüssel im Katalog und gespeicherter Wortlaut der Gründe. Gespeichert
+// bleibt Deutsch, damit frühere Einträge und BLOCKED weiter passen.
+const REASONS: &[(&str, &str)] = &[
+    ("unreachable_blocked", BLOCKED),
+    ("unreachable_kicked", "Bot wurde aus dem Chat entfernt"),
+    ("unreachable_gone", "Chat gibt es nicht mehr"),
+    ("unreachable_deleted", "Konto wurde gelöscht"),
+    ("unreachable_not_started", "privater Chat mit dem Bot wurde nie gestartet"),
+    ("unreachable_no_rights", "Bot darf dort nicht schreiben"),
+    ("unreachable_migrated", "Gruppe wurde in eine Supergruppe umgewandelt"),
+];
+
+// Fehler, die sich nicht durch erneutes Senden beheben, sondern nur im Chat
+// selbst (Bot entsperren, wieder hinzufügen, privaten Chat starten)
+pub(crate) fn unreachable_reason(err: &RequestError) -> Option<&'static str> {
+    let key = match err {
+        RequestError::Api(ApiError::BotBlocked) => "unreachable_blocked",
+        RequestError::Api(ApiError::BotKicked | ApiError::BotKickedFromSupergroup) => "unreachable_kicked",
+        RequestError::Api(ApiError::ChatNotFound | ApiError::GroupDeactivated) => "unreachable_gone",
+        RequestError::Api(ApiError::UserDeactivated) => "unreachable_deleted",
+        RequestError::Api(ApiError::CantInitiateConversation) => "unreachable_not_started",
+        RequestError::Api(ApiError::NotEnoughRightsToPostMessages) => "unreachable_no_rights",
+        RequestError::MigrateToChatId(_) => "unreachable_migrated",
+        _ => return None,
+    };
+    REASONS.iter().find(|(name, _)| *name == key).map(|(_, reason)| *reason)
+}
+
+// Gespeicherter Grund in der Sprache des Chats; anderer Wortlaut, z.B. von
+// einem eingebetteten Kanal, bleibt, wie er ist
+pub(crate) fn reason_in(reason: &str, lang: Lang) -> String {
+    match REASONS.iter().find(|(_, stored)| *stored == reason) {
+        Some((key, _)) => i18n::message(lang, key).to_string(),
+        None => reason.to_string(),
+    }
+}
+
+// Ergebnis einer Testnachricht für /debug und den Admin
+pub(crate) fn send_error_in(err: &SendError, lang: Lang) -> String {
+    match err {
+        SendError::RetryAfter(wait) => i18n::message_with(lang, "send_throttled", &[("seconds", &wait.as_secs().to_string())]),
+        SendError::ReplyTargetMissing => i18n::message(lang, "send_reply_missing").to_string(),
+        SendError::Unreachable(reason) => reason_in(reason, lang),
+        SendError::Failed(err) => err.clone(),
+    }
+}
+
+// Telegram meldet das je nach Version mit verschiedenem Wortlaut
+fn reply_target_missing(err: &ApiError) -> bool {
+    match err {
+        ApiError::MessageToReplyNotFound => true,
+        ApiError::Unknown(text) => text.contains("replied not found") || text.contains("reply message not found"),
+        _ => false,
+    }
+}
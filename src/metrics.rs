@@ -0,0 +1,188 @@
+use crate::SensorData;
+use crate::sensor::SensorKind;
+use std::collections::BTreeMap;
+
+// Magnus-Formel nach Sonntag (1990), gut von -45 bis 60 °C
+const MAGNUS_A: f64 = 17.62;
+const MAGNUS_B: f64 = 243.12;
+
+// Taupunkt in °C aus Temperatur (°C) und relativer Luftfeuchtigkeit (%).
+// None bei Feuchte außerhalb von (0, 100].
+pub fn dew_point(temperature: f64, humidity: f64) -> Option<f64> {
+    if !(humidity > 0.0 && humidity <= 100.0 && temperature.is_finite()) {
+        return None;
+    }
+    let gamma = (humidity / 100.0).ln() + MAGNUS_A * temperature / (MAGNUS_B + temperature);
+    Some(MAGNUS_B * gamma / (MAGNUS_A - gamma))
+}
+
+// Absolute Luftfeuchtigkeit in g/m³ aus Temperatur (°C) und relativer
+// Luftfeuchtigkeit (%), über den Sättigungsdampfdruck nach Magnus.
+// None bei Feuchte außerhalb von (0, 100].
+pub fn absolute_humidity(temperature: f64, humidity: f64) -> Option<f64> {
+    if !(humidity > 0.0 && humidity <= 100.0 && temperature.is_finite()) {
+        return None;
+    }
+    let saturation = 6.112 * (MAGNUS_A * temperature / (MAGNUS_B + temperature)).exp(); // hPa
+    Some(216.7 * humidity / 100.0 * saturation / (273.15 + temperature))
+}
+
+// Unterschied der absoluten Feuchte, ab dem Lüften spürbar etwas ändert
+const AIRING_MIN_G_PER_M3: f64 = 1.0;
+
+// Was Öffnen der Fenster bewirkt, aus Sicht des Raums
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Airing {
+    pub outdoor_warmer_by: f64, // °C, negativ: draußen kühler
+    pub effect: AiringEffect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AiringEffect {
+    Drier,  // draußen weniger Wasser in der Luft: Lüften senkt die Feuchte
+    Wetter, // draußen mehr: Lüften bringt Feuchte herein
+    Neutral,
+}
+
+// Vergleich drinnen/draußen über die absolute Feuchte; die relative ändert
+// sich mit der Temperatur und taugt dafür nicht
+pub fn airing(indoor_temperature: f64, indoor_humidity: f64, outdoor_temperature: f64, outdoor_humidity: f64) -> Option<Airing> {
+    let indoor = absolute_humidity(indoor_temperature, indoor_humidity)?;
+    let outdoor = absolute_humidity(outdoor_temperature, outdoor_humidity)?;
+    let effect = if outdoor <= indoor - AIRING_MIN_G_PER_M3 {
+        AiringEffect::Drier
+    } else if outdoor >= indoor + AIRING_MIN_G_PER_M3 {
+        AiringEffect::Wetter
+    } else {
+        AiringEffect::Neutral
+    };
+    Some(Airing { outdoor_warmer_by: outdoor_temperature - indoor_temperature, effect })
+}
+
+// Gefühlte Temperatur bei Hitze in °C (Hitzeindex des US-Wetterdienstes).
+// Unter etwa 27 °C gilt die einfache Steadman-Näherung, die nahe an der
+// Lufttemperatur bleibt, darüber die Regression nach Rothfusz samt Korrekturen.
+pub fn heat_index(temperature: f64, humidity: f64) -> Option<f64> {
+    if !(0.0..=100.0).contains(&humidity) || !temperature.is_finite() {
+        return None;
+    }
+    let t = temperature * 9.0 / 5.0 + 32.0;
+    let rh = humidity;
+    let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+    let fahrenheit = if (simple + t) / 2.0 < 80.0 {
+        simple
+    } else {
+        let mut hi = -42.379 + 2.04901523 * t + 10.14333127 * rh - 0.22475541 * t * rh - 0.00683783 * t * t - 0.05481717 * rh * rh
+            + 0.00122874 * t * t * rh
+            + 0.00085282 * t * rh * rh
+            - 0.00000199 * t * t * rh * rh;
+        if rh < 13.0 && (80.0..=112.0).contains(&t) {
+            hi -= (13.0 - rh) / 4.0 * ((17.0 - (t - 95.0).abs()) / 17.0).sqrt();
+        } else if rh > 85.0 && (80.0..=87.0).contains(&t) {
+            hi += (rh - 85.0) / 10.0 * (87.0 - t) / 5.0;
+        }
+        hi
+    };
+    Some((fahrenheit - 32.0) * 5.0 / 9.0)
+}
+
+// Abgeleitete Messwerte (Taupunkt, Hitzeindex, absolute Feuchte) für Geräte, die Temperatur
+// und Luftfeuchtigkeit liefern. Sie laufen als gewöhnliche Messwerte durch
+// Schwellen, Verlauf und /status. Liefert ein Gerät den Typ schon selbst,
+// wird nichts berechnet. Zeitstempel ist der ältere der beiden Werte.
+pub fn derive(readings: &[SensorData]) -> Vec<SensorData> {
+    let mut inputs: BTreeMap<&str, (Option<&SensorData>, Option<&SensorData>)> = BTreeMap::new();
+    for reading in readings {
+        let entry = inputs.entry(reading.device_id.as_str()).or_default();
+        match reading.sensor_type {
+            SensorKind::Temperature => entry.0 = Some(reading),
+            SensorKind::Humidity => entry.1 = Some(reading),
+            _ => {}
+        }
+    }
+    let reported = |device_id: &str, kind: &SensorKind| readings.iter().any(|r| r.device_id == device_id && r.sensor_type == *kind);
+    let mut derived = Vec::new();
+    for (device_id, inputs) in inputs {
+        let (Some(temperature), Some(humidity)) = inputs else { continue };
+        let timestamp = temperature.timestamp.min(humidity.timestamp);
+        let values = [
+            (SensorKind::DewPoint, dew_point(temperature.value, humidity.value)),
+            (SensorKind::HeatIndex, heat_index(temperature.value, humidity.value)),
+            (SensorKind::AbsoluteHumidity, absolute_humidity(temperature.value, humidity.value)),
+        ];
+        for (kind, value) in values {
+            let Some(value) = value else { continue };
+            if reported(device_id, &kind) {
+                continue;
+            }
+            derived.push(SensorData { device_id: device_id.to_string(), sensor_type: kind, value, timestamp });
+        }
+    }
+    derived
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fahrenheit_to_celsius(f: f64) -> f64 {
+        (f - 32.0) * 5.0 / 9.0
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64, tolerance: f64) {
+        let actual = actual.unwrap();
+        assert!((actual - expected).abs() <= tolerance, "{} statt {} ± {}", actual, expected, tolerance);
+    }
+
+    // Taupunkttabellen nach Magnus (über Wasser), auf 0.1 °C gerundet
+    #[test]
+    fn dew_point_matches_magnus_tables() {
+        for (temperature, humidity, expected) in [(20.0, 50.0, 9.3), (25.0, 80.0, 21.3), (30.0, 30.0, 10.5), (-10.0, 70.0, -14.4)] {
+            assert_close(dew_point(temperature, humidity), expected, 0.1);
+        }
+        // Gesättigt liegt der Taupunkt auf der Lufttemperatur
+        assert_close(dew_point(18.0, 100.0), 18.0, 1e-9);
+    }
+
+    // Tabelle des US-Wetterdienstes (°F, ganzzahlig); Toleranz 1 °F
+    #[test]
+    fn heat_index_matches_the_nws_table() {
+        for (temperature, humidity, expected) in [(80.0, 40.0, 80.0), (90.0, 70.0, 106.0), (100.0, 40.0, 109.0), (96.0, 65.0, 121.0), (86.0, 90.0, 105.0)] {
+            assert_close(heat_index(fahrenheit_to_celsius(temperature), humidity), fahrenheit_to_celsius(expected), 5.0 / 9.0);
+        }
+        // Unterhalb der Schwüle bleibt er nahe an der Lufttemperatur
+        assert_close(heat_index(21.0, 50.0), 21.0, 1.0);
+    }
+
+    #[test]
+    fn humidity_outside_the_range_gives_nothing() {
+        assert_eq!(dew_point(20.0, 0.0), None);
+        assert_eq!(dew_point(20.0, 100.5), None);
+        assert_eq!(heat_index(30.0, -1.0), None);
+        assert_eq!(dew_point(f64::NAN, 50.0), None);
+        assert_eq!(heat_index(f64::INFINITY, 50.0), None);
+    }
+
+    // Kalte, trockene Winterluft trocknet, schwüle Sommerluft befeuchtet,
+    // bei fast gleicher absoluter Feuchte ändert Lüften nichts
+    #[test]
+    fn airing_compares_absolute_humidity() {
+        let winter = airing(22.0, 60.0, 5.0, 80.0).unwrap();
+        assert_eq!(winter.effect, AiringEffect::Drier);
+        assert!((winter.outdoor_warmer_by + 17.0).abs() < 1e-9);
+
+        let summer = airing(20.0, 40.0, 28.0, 70.0).unwrap();
+        assert_eq!(summer.effect, AiringEffect::Wetter);
+        assert!((summer.outdoor_warmer_by - 8.0).abs() < 1e-9);
+
+        assert_eq!(airing(20.0, 50.0, 20.0, 52.0).unwrap().effect, AiringEffect::Neutral);
+        // Trotz 10 Prozentpunkten Unterschied: draußen wärmer, also gleich viel Wasser
+        assert_eq!(airing(20.0, 60.0, 23.0, 50.0).unwrap().effect, AiringEffect::Neutral);
+    }
+
+    #[test]
+    fn airing_without_valid_humidity_gives_nothing() {
+        assert_eq!(airing(20.0, 0.0, 5.0, 80.0), None);
+        assert_eq!(airing(20.0, 50.0, 5.0, f64::NAN), None);
+    }
+}
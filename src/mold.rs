@@ -0,0 +1,96 @@
+// Schimmelgefahr: relative Feuchte dauerhaft über HUMIDITY_LIMIT. Anders als
+// eine Schwelle zählt nicht der einzelne Messwert, sondern wie lange es
+// ohne Unterbrechung so bleibt.
+pub const HUMIDITY_LIMIT: f64 = 70.0;
+pub const SUSTAINED_SECONDS: i64 = 8 * 60 * 60;
+// Längere Lücken zwischen zwei Messwerten unterbrechen die Strecke; ohne
+// Messwerte weiß niemand, ob die Feuchte zwischendurch gefallen ist
+pub const MAX_GAP_SECONDS: i64 = 30 * 60;
+
+// Beginn der Strecke über `limit`, die mit dem neuesten Messwert endet
+// (Messwerte zeitlich sortiert). Ein Wert darauf oder darunter sowie eine
+// Lücke über MAX_GAP_SECONDS unterbrechen sie; auch der neueste Messwert
+// darf nicht älter sein. None, wenn gerade keine Strecke läuft.
+pub fn streak_start(samples: impl DoubleEndedIterator<Item = (i64, f64)>, limit: f64, now: i64) -> Option<i64> {
+    let mut samples = samples.rev();
+    let (newest, value) = samples.next()?;
+    if value <= limit || now - newest > MAX_GAP_SECONDS {
+        return None;
+    }
+    let mut start = newest;
+    for (timestamp, value) in samples {
+        if value <= limit || start - timestamp > MAX_GAP_SECONDS {
+            break;
+        }
+        start = timestamp;
+    }
+    Some(start)
+}
+
+// Beginn der Gefahr, wenn die Strecke lange genug anhält
+pub fn at_risk(samples: impl DoubleEndedIterator<Item = (i64, f64)>, now: i64) -> Option<i64> {
+    streak_start(samples, HUMIDITY_LIMIT, now).filter(|start| now - start >= SUSTAINED_SECONDS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: i64 = 15 * 60;
+    const START: i64 = 1_700_000_000;
+
+    // Alle 15 Minuten ein Messwert ab START
+    fn every_quarter_hour(values: &[f64]) -> Vec<(i64, f64)> {
+        values.iter().enumerate().map(|(i, &value)| (START + i as i64 * STEP, value)).collect()
+    }
+
+    fn newest(samples: &[(i64, f64)]) -> i64 {
+        samples.last().unwrap().0
+    }
+
+    #[test]
+    fn streak_grows_with_every_humid_reading() {
+        let mut values = vec![65.0, 72.0];
+        for _ in 0..4 {
+            values.push(75.0);
+            let samples = every_quarter_hour(&values);
+            assert_eq!(streak_start(samples.iter().copied(), HUMIDITY_LIMIT, newest(&samples)), Some(START + STEP));
+        }
+        assert_eq!(streak_start(every_quarter_hour(&[]).into_iter(), HUMIDITY_LIMIT, START), None);
+    }
+
+    // Ein einziger Wert auf oder unter der Grenze beginnt die Strecke neu,
+    // ebenso eine zu lange Lücke; ein alter letzter Messwert beendet sie
+    #[test]
+    fn one_dry_reading_or_a_gap_breaks_the_streak() {
+        let samples = every_quarter_hour(&[75.0, 75.0, 68.0, 75.0, 75.0]);
+        assert_eq!(streak_start(samples.iter().copied(), HUMIDITY_LIMIT, newest(&samples)), Some(START + 3 * STEP));
+        let samples = every_quarter_hour(&[75.0, 70.0, 75.0]);
+        assert_eq!(streak_start(samples.iter().copied(), HUMIDITY_LIMIT, newest(&samples)), Some(START + 2 * STEP));
+        let samples = every_quarter_hour(&[75.0, 75.0, 69.9]);
+        assert_eq!(streak_start(samples.iter().copied(), HUMIDITY_LIMIT, newest(&samples)), None);
+
+        let gap = [(START, 75.0), (START + MAX_GAP_SECONDS + 1, 75.0)];
+        assert_eq!(streak_start(gap.into_iter(), HUMIDITY_LIMIT, START + MAX_GAP_SECONDS + 1), Some(START + MAX_GAP_SECONDS + 1));
+        let no_gap = [(START, 75.0), (START + MAX_GAP_SECONDS, 75.0)];
+        assert_eq!(streak_start(no_gap.into_iter(), HUMIDITY_LIMIT, START + MAX_GAP_SECONDS), Some(START));
+        assert_eq!(streak_start(no_gap.into_iter(), HUMIDITY_LIMIT, START + 2 * MAX_GAP_SECONDS + 1), None);
+    }
+
+    // Gefahr genau ab SUSTAINED_SECONDS, gerechnet vom ersten feuchten Messwert
+    #[test]
+    fn risk_starts_once_the_streak_lasts_long_enough() {
+        let humid = (SUSTAINED_SECONDS / STEP) as usize;
+        let samples = every_quarter_hour(&vec![75.0; humid]);
+        assert_eq!(at_risk(samples.iter().copied(), newest(&samples)), None);
+        assert_eq!(at_risk(samples.iter().copied(), START + SUSTAINED_SECONDS - 1), None);
+        assert_eq!(at_risk(samples.iter().copied(), START + SUSTAINED_SECONDS), Some(START));
+
+        let samples = every_quarter_hour(&vec![75.0; humid + 1]);
+        assert_eq!(at_risk(samples.iter().copied(), newest(&samples)), Some(START));
+        let mut broken = vec![75.0; humid + 1];
+        broken[1] = 60.0;
+        let samples = every_quarter_hour(&broken);
+        assert_eq!(at_risk(samples.iter().copied(), newest(&samples)), None);
+    }
+}
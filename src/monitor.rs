@@ -0,0 +1,1233 @@
+use crate::alarm_log::Suppressed;
+use crate::cadence::{CadenceEvent, CadenceTracker};
+use crate::digest::AlertMode;
+use crate::discovery::{SensorEvent, SensorWatch};
+use crate::episode::Episode;
+use crate::escalation::Escalation;
+use crate::fetch::{TenantSources, fetch_each, merge_newest};
+use crate::history::History;
+use crate::i18n::{Lang, Text};
+use crate::inject::Injection;
+use crate::notify::MessageKind;
+use crate::outbox::{AlertMeta, Outbox};
+use crate::plausibility::{PlausibilityFilter, Screening};
+use crate::polling::PollSchedule;
+use crate::routing::Severity;
+use crate::schema_watch::SchemaWatch;
+use crate::sensor::{SensorKind, ThresholdDirection, ThresholdKey};
+use crate::source::FetchError;
+use crate::staleness::{StaleEvent, StaleWatch};
+use crate::storage::Store;
+use crate::{
+    AlarmGroups, BatchedAlert, LATEST_FETCHED, POLL_INTERVAL_CHANGED, QuietQueue, RoutedCopies, SensorData, SensorSnapshot, SharedEscalation, SharedHistory,
+    SharedRecords, SharedUptime, ThresholdFlags, UserConfig, UserConfigs, adjust, admin_lang, alarm_log, alert_message, bot_status, chat_timezone,
+    check_batteries, correlation, coverage, digest_line, format_duration, format_recovery, format_timestamp, i18n, ignored, in_local_window, inject, latest,
+    local_time_of, local_year, merge_latest, metrics, notify_sinks, poll_interval, push, rate, record_readings, refresh, relative,
+    remind_persistent_violations, review_long_violations, room_name, rooms, settings, telemetry, tenant_of, type_label, type_label_in, unit_in, warn_mold_risk,
+};
+use chrono::{DateTime, NaiveTime, Utc};
+use log::{info, warn};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use teloxide::types::ChatId;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant};
+
+// Zustand je (Chat, Gerät, Schwelle): true = Alarm besteht
+pub type Flags = HashMap<(i64, String, ThresholdKey), bool>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    // Schwelle neu verletzt, hier wird gewarnt
+    Alarm,
+    // Wert wieder im erlaubten Bereich
+    Recovered,
+    // Alarm bestand, aber keine Schwelle ist mehr aktiv (außerhalb aller Zeitfenster)
+    Deactivated,
+}
+
+#[derive(Debug, Clone)]
+pub struct ThresholdEvent {
+    pub kind: EventKind,
+    pub chat_id: i64,
+    pub device_id: String,
+    pub sensor_type: SensorKind,
+    pub direction: ThresholdDirection,
+    pub value: f64,
+    pub threshold: Option<f64>,
+    pub source: String,
+    pub timestamp: i64,
+}
+
+// Alarm-Zustände nach einem Neustart: jede gespeicherte laufende Verletzung
+// (Episode) einer noch vorhandenen Schwelle gilt als gemeldet. Ist der Wert
+// beim ersten Messwert noch außerhalb, kommt keine zweite Warnung; ist er
+// zurück, kommt die Entwarnung.
+pub fn restored_flags(configs: &HashMap<i64, UserConfig>) -> Flags {
+    configs
+        .iter()
+        .flat_map(|(&chat_id, config)| {
+            config
+                .episodes
+                .keys()
+                .filter(|key| config.thresholds.contains_key(*key) || config.profiles.override_for(key).is_some())
+                .map(move |(device_id, key)| ((chat_id, device_id.clone(), key.clone()), true))
+        })
+        .collect()
+}
+
+// Prüft Messwerte gegen alle Schwellwerte und aktualisiert die Alarm-Zustände.
+// Wird von der Überwachung und von `simulate` gleichermaßen benutzt.
+pub fn evaluate(configs: &HashMap<i64, UserConfig>, readings: &[SensorData], flags: &mut Flags, local: NaiveTime) -> Vec<ThresholdEvent> {
+    let mut events = Vec::new();
+    for sensor in readings {
+        for (&chat_id, config) in configs {
+            for direction in ThresholdDirection::ALL {
+                events.extend(evaluate_threshold(chat_id, config, sensor, direction, flags, local));
+            }
+        }
+    }
+    events
+}
+
+// Eine Schwelle (Richtung) eines Chats gegen einen Messwert prüfen
+pub fn evaluate_threshold(
+    chat_id: i64,
+    config: &UserConfig,
+    sensor: &SensorData,
+    direction: ThresholdDirection,
+    flags: &mut Flags,
+    local: NaiveTime,
+) -> Option<ThresholdEvent> {
+    let key = (sensor.device_id.clone(), ThresholdKey::new(sensor.sensor_type.clone(), direction));
+    let user_key = (chat_id, key.0.clone(), key.1.clone());
+    let event = |kind, threshold, source: &str| ThresholdEvent {
+        kind,
+        chat_id,
+        device_id: sensor.device_id.clone(),
+        sensor_type: sensor.sensor_type.clone(),
+        direction,
+        value: sensor.value,
+        threshold,
+        source: source.to_string(),
+        timestamp: sensor.timestamp,
+    };
+
+    // Beim Wechsel des Zeitfensters wird gegen die neue Schwelle geprüft.
+    // Ein bestehender Alarm bleibt bestehen, bis der Wert auch gegenüber der
+    // neuen Schwelle um die Hysterese zurück im Bereich ist.
+    let Some(entry) = config.active_threshold(&key, local) else {
+        return (flags.remove(&user_key) == Some(true)).then(|| event(EventKind::Deactivated, None, ""));
+    };
+
+    let was_alarm = flags.get(&user_key) == Some(&true);
+    let alarm = in_alarm(sensor.value, entry.value, direction, config.hysteresis_for(&key), was_alarm);
+    flags.insert(user_key, alarm);
+    if alarm && !was_alarm {
+        Some(event(EventKind::Alarm, Some(entry.value), &entry.source))
+    } else if !alarm && was_alarm {
+        Some(event(EventKind::Recovered, Some(entry.value), &entry.source))
+    } else {
+        None
+    }
+}
+
+// Besteht nach diesem Messwert ein Alarm? Ausgelöst wird, sobald der Wert
+// die Schwelle verletzt; enden muss er erst, wenn der Wert mindestens
+// `hysteresis` zurück im erlaubten Bereich liegt. So wechselt ein Wert knapp
+// an der Schwelle nicht bei jeder Abfrage zwischen Warnung und Entwarnung.
+pub fn in_alarm(value: f64, threshold: f64, direction: ThresholdDirection, hysteresis: f64, was_alarm: bool) -> bool {
+    let hysteresis = if was_alarm { hysteresis.max(0.0) } else { 0.0 };
+    match direction {
+        ThresholdDirection::Min => value < threshold + hysteresis,
+        ThresholdDirection::Max => value > threshold - hysteresis,
+    }
+}
+
+// Uhr der Überwachung: im Betrieb die Systemzeit, in Tests vorgegeben
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+// Überwachung: fragt die Quellen nach ihrem Plan ab, wertet die Messwerte
+// aus und verschickt Warnungen. Hält alles, was von einem Durchlauf zum
+// nächsten bleibt; `spawn` ruft `run_once` in einer Schleife auf.
+pub struct Monitor {
+    admin_chat: Option<ChatId>,
+    outbox: Outbox,
+    user_configs: UserConfigs,
+    threshold_flags: ThresholdFlags,
+    history: SharedHistory,
+    records: SharedRecords,
+    quiet_queue: QuietQueue,
+    storage: Arc<dyn Store>,
+    uptime: SharedUptime,
+    escalation: SharedEscalation,
+    // Quellen stehen mit dem Start fest, jede mit eigenem Abfrageplan
+    sources: TenantSources,
+    clock: Arc<dyn Clock>,
+    schedule: PollSchedule,
+    cadence: CadenceTracker,
+    schema_watch: SchemaWatch,
+    stale_watch: StaleWatch,
+    // Letzte Antwort je Quelle; zusammen ergeben sie den Stand für /status
+    source_readings: Vec<Vec<SensorData>>,
+    // Welche Sensoren es gibt, je Quelle bei ihren regulären Abfragen
+    sensor_watches: Vec<SensorWatch>,
+    plausibility: PlausibilityFilter,
+    // Änderungsalarme (/rate) je Chat, Gerät und Typ: true = gemeldet
+    rate_flags: HashMap<(i64, String, SensorKind), bool>,
+    // Vergleichsschwellen (/set … max-rel) je Chat, Gerät und Schwelle: true = Alarm
+    relative_flags: HashMap<(i64, String, ThresholdKey), bool>,
+    // Neuester per POST /readings erhaltene Messwert je Gerät und Typ
+    pushed_newest: HashMap<(String, SensorKind), SensorData>,
+}
+
+// Was ein Durchlauf auswertet: abgefragte und geschickte Messwerte, dahinter
+// die Testwerte aus /inject
+struct Pass<'a> {
+    readings: Vec<SensorData>,
+    // Anzahl echter Messwerte vorne in `readings`
+    real: usize,
+    injected: &'a [Injection],
+    polled: bool,
+    regular: bool,
+    now: DateTime<Utc>,
+}
+
+impl Pass<'_> {
+    fn real(&self) -> &[SensorData] {
+        &self.readings[..self.real]
+    }
+
+    // Was in Verlauf und Rekorde geht: alles außer Testwerten mit --no-store
+    fn stored(&self) -> impl Iterator<Item = &SensorData> {
+        self.readings.iter().enumerate().filter(|(index, _)| *index < self.real || self.injected[index - self.real].store).map(|(_, sensor)| sensor)
+    }
+
+    fn synthetic(&self, event: &ThresholdEvent) -> bool {
+        self.injected.iter().any(|injection| {
+            let reading = &injection.reading;
+            reading.device_id == event.device_id && reading.sensor_type == event.sensor_type && reading.timestamp == event.timestamp
+        })
+    }
+}
+
+fn interval(sources: &TenantSources, index: usize) -> Duration {
+    sources[index].1.poll_interval().unwrap_or(Duration::from_secs(poll_interval()))
+}
+
+impl Monitor {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn new(
+        admin_chat: Option<ChatId>,
+        outbox: Outbox,
+        user_configs: UserConfigs,
+        threshold_flags: ThresholdFlags,
+        history: SharedHistory,
+        records: SharedRecords,
+        quiet_queue: QuietQueue,
+        storage: Arc<dyn Store>,
+        uptime: SharedUptime,
+        escalation: SharedEscalation,
+        sources: TenantSources,
+        clock: Arc<dyn Clock>,
+    ) -> Monitor {
+        let mut cadence = CadenceTracker::new(settings().cadence_factor, poll_interval() as i64);
+        let mut plausibility = PlausibilityFilter::new(&settings().plausible_ranges, settings().plausibility_confirm_after);
+        let sensor_watches = {
+            let history = history.lock().await;
+            cadence.seed(&history);
+            plausibility.seed(&history);
+            // Was schon im Verlauf steht, gilt nach einem Neustart nicht als neu
+            let known_sensors: Vec<(String, SensorKind)> =
+                history.points_since(i64::MIN).into_iter().map(|(device, kind, _)| (device.to_string(), SensorKind::from(kind))).collect();
+            sources.iter().map(|_| SensorWatch::new(settings().sensor_missing_after, known_sensors.iter().cloned())).collect()
+        };
+        Monitor {
+            admin_chat,
+            outbox,
+            user_configs,
+            threshold_flags,
+            history,
+            records,
+            quiet_queue,
+            storage,
+            uptime,
+            escalation,
+            schedule: PollSchedule::new(sources.len(), Instant::now()),
+            source_readings: vec![Vec::new(); sources.len()],
+            sources,
+            clock,
+            cadence,
+            schema_watch: SchemaWatch::new(settings().schema_alarm_after),
+            stale_watch: StaleWatch::new(settings().stale_after_minutes * 60),
+            sensor_watches,
+            plausibility,
+            rate_flags: HashMap::new(),
+            relative_flags: HashMap::new(),
+            pushed_newest: HashMap::new(),
+        }
+    }
+
+    // Ein Durchlauf. Fällige Quellen werden regulär abgefragt und alle ihre
+    // Geräte ausgewertet; von den übrigen nur die Geräte, die nach einem
+    // Alarm häufiger abgefragt werden. /refresh fragt alle Quellen außer der
+    // Reihe ab, ohne ihren Plan zu verschieben. Liefert, wann der nächste
+    // Durchlauf fällig ist.
+    pub async fn run_once(&mut self) -> Instant {
+        let now_instant = Instant::now();
+        let waiting = refresh::take();
+        let mut regular_sources = self.schedule.take_due(now_instant, |index| interval(&self.sources, index));
+        if !waiting.is_empty() {
+            regular_sources = (0..self.sources.len()).collect();
+        }
+        let regular = !regular_sources.is_empty();
+        let due = {
+            let mut escalation = self.escalation.lock().await;
+            for device_id in escalation.expire(now_instant) {
+                info!("Häufigere Abfrage von {} beendet (Zeit abgelaufen)", room_name(&device_id));
+            }
+            escalation.take_due(now_instant)
+        };
+        let escalated_sources: Vec<usize> =
+            if due.is_empty() { Vec::new() } else { (0..self.sources.len()).filter(|index| !regular_sources.contains(index)).collect() };
+        let mut refreshed: refresh::Outcome = Err(Text::new("refresh_no_source", &[]));
+
+        // Testwerte aus /inject laufen sofort durch, ohne Abfrage der Quellen
+        let injected = inject::take();
+        let mut pushed = push::take();
+        pushed.retain(|reading| !ignored().contains(&reading.device_id));
+        let admin_lang = admin_lang(&*self.user_configs.lock().await);
+        screen_readings(&mut self.plausibility, &mut pushed, &self.outbox, admin_lang);
+        pushed.extend(metrics::derive(&pushed));
+        let polled = regular || !due.is_empty();
+        let iteration_started = std::time::Instant::now();
+
+        if polled || !injected.is_empty() || !pushed.is_empty() {
+            let (fetched, sensor_events) =
+                if polled { self.poll(&regular_sources, &escalated_sources, &due, admin_lang).await } else { (Ok(Vec::new()), Vec::new()) };
+            self.note_fetch(&fetched, polled, admin_lang);
+            if let Err(err) = &fetched {
+                refreshed = Err(err.text());
+            }
+            let fetched = fetched.or_else(|err| if injected.is_empty() && pushed.is_empty() { Err(err) } else { Ok(Vec::new()) });
+            if let Ok(readings) = fetched {
+                if polled {
+                    refreshed = Ok(self.update_latest(&readings));
+                }
+                let pass = self.combine(readings, pushed, &injected, polled, regular);
+                self.evaluate_pass(pass, sensor_events).await;
+            }
+
+            // Lebenszeichen: bis hierhin lief der Bot sicher
+            {
+                let mut uptime = self.uptime.lock().await;
+                uptime.heartbeat(self.clock.now().timestamp());
+                self.storage.save_uptime(&uptime);
+            }
+            bot_status().last_iteration = Some(iteration_started.elapsed());
+        }
+        // Erst nach der Auswertung, damit Warnungen vor der Antwort rausgehen
+        if !waiting.is_empty() {
+            refresh::answer(waiting, refreshed);
+        }
+
+        let next = self.schedule.next_due().unwrap_or(now_instant + Duration::from_secs(poll_interval()));
+        let wake = self.escalation.lock().await.next_due().map_or(next, |at| at.min(next));
+        let until_wake = wake.saturating_duration_since(Instant::now());
+        bot_status().next_poll = Some(self.clock.now() + chrono::Duration::from_std(until_wake).unwrap_or_default());
+        wake
+    }
+
+    // Nach /set-interval alle Termine neu setzen
+    pub fn reschedule(&mut self) {
+        self.schedule.reschedule(Instant::now(), |index| interval(&self.sources, index));
+    }
+
+    // Quellen abfragen; ein Fehler zählt nur, wenn keine der Quellen antwortet
+    async fn poll(
+        &mut self,
+        regular_sources: &[usize],
+        escalated_sources: &[usize],
+        due: &[String],
+        admin_lang: Lang,
+    ) -> (Result<Vec<SensorData>, FetchError>, Vec<SensorEvent>) {
+        let polled_sources: Vec<usize> = regular_sources.iter().chain(escalated_sources).copied().collect();
+        let mut readings = Vec::new();
+        let mut sensor_events = Vec::new();
+        let mut first_error = None;
+        let mut answered = false;
+        for (index, result) in fetch_each(&self.sources, &polled_sources).await {
+            match result {
+                Ok(mut data) => {
+                    answered = true;
+                    // Ausreißer fallen vor allem anderen heraus, auch aus /status
+                    screen_readings(&mut self.plausibility, &mut data, &self.outbox, admin_lang);
+                    self.source_readings[index] = data.clone();
+                    if regular_sources.contains(&index) {
+                        sensor_events.extend(self.sensor_watches[index].observe(data.iter().map(|s| (s.device_id.clone(), s.sensor_type.clone()))));
+                    } else {
+                        data.retain(|sensor| due.contains(&sensor.device_id));
+                    }
+                    merge_newest(&mut readings, data);
+                }
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+        let fetched = match first_error {
+            Some(err) if !answered => Err(err),
+            _ => Ok(readings),
+        };
+        (fetched, sensor_events)
+    }
+
+    fn note_fetch(&mut self, fetched: &Result<Vec<SensorData>, FetchError>, polled: bool, admin_lang: Lang) {
+        if polled {
+            let mut status = bot_status();
+            status.last_fetch = Some((self.clock.now(), fetched.as_ref().err().map(FetchError::text)));
+            status.fetch_failures = if fetched.is_ok() { 0 } else { status.fetch_failures + 1 };
+        }
+        match fetched {
+            Ok(_) => self.schema_watch.success(),
+            Err(FetchError::Parse { error, payload }) => {
+                // Weitere gleiche Fehler bleiben still, bis sich der Fehler ändert
+                if let Some(report) = self.schema_watch.failure(error, payload, admin_lang) {
+                    match self.admin_chat {
+                        Some(admin) => self.outbox.send(admin, report),
+                        None => warn!("{}", report),
+                    }
+                }
+            }
+            Err(FetchError::Request(_)) => {}
+        }
+    }
+
+    // Stand für /status aus den letzten Antworten aller Quellen. Liefert für
+    // /refresh die Geräte, deren Messwerte neuer sind als der bisherige Stand.
+    fn update_latest(&self, fetched: &[SensorData]) -> Vec<String> {
+        let mut latest = latest();
+        let previous = latest.as_ref().map(|snapshot| snapshot.readings.as_slice()).unwrap_or_default();
+        let refreshed = fetched
+            .iter()
+            .filter(|r| !previous.iter().any(|p| p.device_id == r.device_id && p.sensor_type == r.sensor_type && p.timestamp >= r.timestamp))
+            .map(|r| r.device_id.clone())
+            .collect();
+        let mut readings = Vec::new();
+        for data in &self.source_readings {
+            merge_newest(&mut readings, data.iter().cloned());
+        }
+        *latest = Some(SensorSnapshot { readings, fetched_at: self.clock.now() });
+        if !fetched.is_empty() {
+            LATEST_FETCHED.notify_one();
+        }
+        refreshed
+    }
+
+    // Geschickte und abgefragte Werte: jeder Messwert wird nur einmal
+    // ausgewertet, ältere oder gleich alte fallen weg
+    fn combine<'a>(&mut self, mut readings: Vec<SensorData>, mut pushed: Vec<SensorData>, injected: &'a [Injection], polled: bool, regular: bool) -> Pass<'a> {
+        let reading_key = |sensor: &SensorData| (sensor.device_id.clone(), sensor.sensor_type.clone());
+        let newer = |sensor: &SensorData, newest: &HashMap<(String, SensorKind), SensorData>| {
+            newest.get(&reading_key(sensor)).is_none_or(|seen| sensor.timestamp > seen.timestamp)
+        };
+        readings.retain(|sensor| newer(sensor, &self.pushed_newest));
+        pushed.retain(|sensor| newer(sensor, &self.pushed_newest));
+        for sensor in &pushed {
+            if newer(sensor, &self.pushed_newest) {
+                self.pushed_newest.insert(reading_key(sensor), sensor.clone());
+            }
+        }
+        if !pushed.is_empty() {
+            info!("{} Messwerte per POST /readings", pushed.len());
+        }
+        // /status zeigt den jeweils neueren Stand aus beiden Wegen
+        if polled || !pushed.is_empty() {
+            merge_latest(self.pushed_newest.values());
+        }
+        readings.extend(pushed);
+        // Testwerte hinten anhängen; sie zählen nicht für die Meldeabstände
+        let real = readings.len();
+        for injection in injected {
+            let reading = &injection.reading;
+            info!(
+                "Testwert (synthetisch) für {} {}: {:.1} von {}{}",
+                reading.device_id,
+                reading.sensor_type,
+                reading.value,
+                format_timestamp(reading.timestamp, "%d.%m.%Y %H:%M"),
+                if injection.store { "" } else { ", ohne Speichern" }
+            );
+        }
+        readings.extend(injected.iter().map(|injection| injection.reading.clone()));
+        Pass { readings, real, injected, polled, regular, now: self.clock.now() }
+    }
+
+    async fn evaluate_pass(&mut self, pass: Pass<'_>, sensor_events: Vec<SensorEvent>) {
+        let (user_configs, threshold_flags, history, escalation) =
+            (self.user_configs.clone(), self.threshold_flags.clone(), self.history.clone(), self.escalation.clone());
+        let mut configs = user_configs.lock().await;
+        let mut flags = threshold_flags.lock().await;
+        let mut history = history.lock().await;
+        let mut escalation = escalation.lock().await;
+        if pass.polled {
+            let registry = rooms();
+            let households = || std::iter::once(None).chain(registry.tenants().iter().map(|tenant| Some(tenant.id.clone())));
+            let mut sensors: BTreeMap<Option<String>, usize> = households().map(|tenant| (tenant, 0)).collect();
+            for reading in latest().as_ref().map_or(&[][..], |snapshot| &snapshot.readings) {
+                *sensors.entry(registry.device_tenant(&reading.device_id).map(str::to_string)).or_default() += 1;
+            }
+            let mut thresholds: BTreeMap<Option<String>, usize> = households().map(|tenant| (tenant, 0)).collect();
+            for (chat_id, config) in configs.iter() {
+                *thresholds.entry(tenant_of(*chat_id)).or_default() += config.thresholds.len();
+            }
+            telemetry::gauges(sensors, thresholds);
+        }
+        self.record(&pass, &mut history).await;
+
+        // Meldeabstände je Gerät anhand des neuesten Zeitstempels
+        let mut newest: HashMap<&str, i64> = HashMap::new();
+        for sensor in pass.real() {
+            let ts = newest.entry(&sensor.device_id).or_insert(sensor.timestamp);
+            *ts = (*ts).max(sensor.timestamp);
+        }
+        let mut changed = self.report_stale(&mut configs, &newest, pass.now);
+        changed |= self.report_sensor_events(&mut configs, sensor_events, pass.now);
+        changed |= self.report_cadence(&mut configs, newest, pass.now);
+        changed |= check_batteries(&mut configs, pass.real(), &self.outbox, pass.now);
+        changed |= self.check_rates(&mut configs, &history, &pass);
+        changed |= self.check_relations(&mut configs, &pass);
+        // Laufende Verletzungen: schlimmsten Wert mitführen
+        for config in configs.values_mut() {
+            for ((device_id, key), episode) in config.episodes.iter_mut() {
+                for sensor in pass.readings.iter().filter(|s| s.device_id == *device_id && s.sensor_type == key.kind) {
+                    changed |= episode.observe(key.direction, sensor.timestamp, sensor.value);
+                }
+            }
+        }
+        let (alarms, alarms_changed) = collect_alarms(&mut configs, &mut flags, &mut escalation, &history, &self.outbox, &pass);
+        changed |= alarms_changed;
+        // Verletzungen, die während einer Pause des Bots endeten: nach
+        // dem ersten regulären Durchlauf ohne Alarm stillschweigend beenden
+        if pass.regular {
+            for (chat_id, config) in configs.iter_mut() {
+                let before = config.episodes.len();
+                let thresholds = &config.thresholds;
+                config.episodes.retain(|id, _| thresholds.contains_key(id) && flags.get(&(*chat_id, id.0.clone(), id.1.clone())) != Some(&false));
+                changed |= config.episodes.len() != before;
+            }
+        }
+        changed |= self.dispatch(&mut configs, alarms, pass.now).await;
+        if pass.regular {
+            // Schwellen, zu denen nie Messwerte kommen (z.B. falsches Gerät)
+            let now_ts = pass.now.timestamp();
+            let grace = settings().unmonitored_grace_hours * 60 * 60;
+            for (chat_id, config) in configs.iter_mut() {
+                let (unmonitored, notify) = coverage::check(config.thresholds.keys(), &mut config.unmonitored, &history, now_ts, grace);
+                changed |= unmonitored;
+                for (device_id, key) in notify {
+                    let text = i18n::message_with(
+                        config.lang,
+                        "unmonitored",
+                        &[
+                            ("room", &room_name(&device_id)),
+                            ("type", type_label_in(config.lang, key.kind.as_str()).0),
+                            ("direction", key.direction.as_str()),
+                            ("duration", &format_duration(coverage::SEEN_WITHIN_SECONDS + grace)),
+                            ("device", &device_id),
+                            ("kind", key.kind.as_str()),
+                        ],
+                    );
+                    self.outbox.send(ChatId(*chat_id), text);
+                }
+            }
+            changed |= review_long_violations(&mut configs, &history, &self.outbox, now_ts);
+            changed |= remind_persistent_violations(&mut configs, &flags, &history, &self.outbox, now_ts);
+            changed |= warn_mold_risk(&mut configs, &history, &self.outbox, now_ts);
+        }
+        if changed {
+            self.storage.save_users(&configs);
+        }
+    }
+
+    // Verlauf und Rekorde
+    async fn record(&self, pass: &Pass<'_>, history: &mut History) {
+        for sensor in pass.stored() {
+            history.record(&sensor.device_id, sensor.sensor_type.as_str(), sensor.timestamp, sensor.value);
+        }
+        self.storage.save_history(history);
+        record_readings(pass.stored());
+
+        let mut records = self.records.lock().await;
+        for sensor in pass.stored() {
+            let year = local_year(sensor.timestamp);
+            for record in records.observe(&sensor.device_id, sensor.sensor_type.as_str(), sensor.timestamp, sensor.value, year) {
+                info!(
+                    "Neuer {} {} in {}: {:.1}",
+                    if record.low { "Tiefstwert" } else { "Höchstwert" },
+                    type_label(&record.sensor_type).0,
+                    room_name(&record.device_id),
+                    record.extreme.value
+                );
+            }
+        }
+        self.storage.save_records(&records);
+    }
+
+    // Veraltete Messwerte an alle mit Schwellen auf dem Gerät, Text je Chat
+    // in dessen Sprache
+    fn report_stale(&mut self, configs: &mut HashMap<i64, UserConfig>, newest: &HashMap<&str, i64>, now: DateTime<Utc>) -> bool {
+        let mut changed = false;
+        for event in self.stale_watch.observe(newest, now.timestamp()) {
+            let (device_id, key, placeholder) = match event {
+                StaleEvent::Stale { device_id, since } => {
+                    let fmt = if now.timestamp() - since >= 24 * 60 * 60 { "%d.%m. %H:%M" } else { "%H:%M" };
+                    (device_id, "stale", ("since", format_timestamp(since, fmt)))
+                }
+                StaleEvent::Fresh { device_id, gap } => (device_id, "fresh", ("gap", format_duration(gap))),
+            };
+            if ignored().contains(&device_id) {
+                continue;
+            }
+            for (&chat_id, config) in configs.iter_mut() {
+                if !config.thresholds.keys().any(|(device, _)| *device == device_id) || !rooms().visible(tenant_of(chat_id).as_deref(), &device_id) {
+                    continue;
+                }
+                if config.is_muted(now) {
+                    config.muted_missed += 1;
+                    changed = true;
+                } else {
+                    let text = i18n::message_with(config.lang, key, &[("room", &room_name(&device_id)), (placeholder.0, &placeholder.1)]);
+                    self.outbox.send(ChatId(chat_id), text);
+                }
+            }
+        }
+        changed
+    }
+
+    // Neue Sensoren an den Admin, verschwundene zusätzlich an alle mit
+    // Schwellen auf dem Gerät; Text je Chat in dessen Sprache
+    fn report_sensor_events(&self, configs: &mut HashMap<i64, UserConfig>, sensor_events: Vec<SensorEvent>, now: DateTime<Utc>) -> bool {
+        let mut changed = false;
+        for event in sensor_events {
+            let (device_id, everyone) = match &event {
+                SensorEvent::New { device_id, .. } => (device_id.clone(), false),
+                SensorEvent::Gone { device_id, .. } | SensorEvent::Returned { device_id, .. } => (device_id.clone(), true),
+            };
+            if ignored().contains(&device_id) {
+                continue;
+            }
+            let room = room_name(&device_id);
+            let text = |lang: Lang| match &event {
+                SensorEvent::New { kind, .. } => i18n::message_with(lang, "sensor_new", &[("device", &device_id), ("type", kind.as_str())]),
+                SensorEvent::Gone { all: true, .. } => i18n::message_with(lang, "sensor_gone", &[("room", &room)]),
+                SensorEvent::Gone { kinds, .. } => {
+                    let labels: Vec<&str> = kinds.iter().map(|kind| type_label_in(lang, kind.as_str()).0).collect();
+                    i18n::message_with(lang, "sensor_gone_types", &[("room", &room), ("types", &labels.join(", "))])
+                }
+                SensorEvent::Returned { kind, .. } => {
+                    i18n::message_with(lang, "sensor_returned", &[("room", &room), ("type", type_label_in(lang, kind.as_str()).0)])
+                }
+            };
+            info!("{}", text(Lang::De));
+            let mut recipients: BTreeSet<i64> = self.admin_chat.map(|admin| admin.0).into_iter().collect();
+            if everyone {
+                recipients.extend(
+                    configs
+                        .iter()
+                        .filter(|(chat_id, config)| {
+                            config.thresholds.keys().any(|(device, _)| *device == device_id) && rooms().visible(tenant_of(**chat_id).as_deref(), &device_id)
+                        })
+                        .map(|(&chat_id, _)| chat_id),
+                );
+            }
+            for chat_id in recipients {
+                let lang = configs.get(&chat_id).map(|c| c.lang).unwrap_or_default();
+                match configs.get_mut(&chat_id).filter(|c| c.is_muted(now)) {
+                    Some(config) => {
+                        config.muted_missed += 1;
+                        changed = true;
+                    }
+                    None => self.outbox.send(ChatId(chat_id), text(lang)),
+                }
+            }
+        }
+        changed
+    }
+
+    // Auffällig seltene Meldungen eines Geräts an den Admin
+    fn report_cadence(&mut self, configs: &mut HashMap<i64, UserConfig>, newest: HashMap<&str, i64>, now: DateTime<Utc>) -> bool {
+        let mut events: Vec<CadenceEvent> = newest.into_iter().filter_map(|(device_id, ts)| self.cadence.observe(device_id, ts)).collect();
+        events.extend(self.cadence.check(now.timestamp()));
+        // Ein ignoriertes Gerät liefert nichts mehr und wäre sonst dauerhaft "verstummt"
+        events.retain(|event| match event {
+            CadenceEvent::Slowed { device_id, .. } | CadenceEvent::Resumed { device_id, .. } => !ignored().contains(device_id),
+        });
+        let admin_lang = self.admin_chat.and_then(|admin| configs.get(&admin.0)).map(|c| c.lang).unwrap_or_default();
+        let mut changed = false;
+        for event in events {
+            let text = match event {
+                CadenceEvent::Slowed { device_id, gap, median } => i18n::message_with(
+                    admin_lang,
+                    "cadence_slowed",
+                    &[("room", &room_name(&device_id)), ("gap", &format_duration(gap)), ("median", &format_duration(median))],
+                ),
+                CadenceEvent::Resumed { device_id, gap } => {
+                    i18n::message_with(admin_lang, "cadence_resumed", &[("room", &room_name(&device_id)), ("gap", &format_duration(gap))])
+                }
+            };
+            match self.admin_chat {
+                Some(admin) => match configs.get_mut(&admin.0).filter(|c| c.is_muted(now)) {
+                    Some(config) => {
+                        config.muted_missed += 1;
+                        changed = true;
+                    }
+                    None => self.outbox.send(admin, text),
+                },
+                None => warn!("{}", text),
+            }
+        }
+        changed
+    }
+
+    // Schnelle Änderungen: einmal melden, bis die Änderung im Fenster wieder
+    // unter der Regel liegt
+    fn check_rates(&mut self, configs: &mut HashMap<i64, UserConfig>, history: &History, pass: &Pass<'_>) -> bool {
+        let mut changed = false;
+        for (&chat_id, config) in configs.iter_mut() {
+            let rules: Vec<_> = config.rates.iter().map(|(key, rule)| (key.clone(), *rule)).collect();
+            for ((device_id, kind), rule) in rules {
+                if !pass.readings.iter().any(|s| s.device_id == device_id && s.sensor_type == kind)
+                    || !rooms().visible(tenant_of(chat_id).as_deref(), &device_id)
+                {
+                    continue;
+                }
+                let Some(series) = history.series(&device_id, kind.as_str()) else { continue };
+                let Some(change) = rate::change(series, rule.minutes) else { continue };
+                let violated = rule.violated(change);
+                let was = self.rate_flags.insert((chat_id, device_id.clone(), kind.clone()), violated) == Some(true);
+                if !violated || was {
+                    continue;
+                }
+                let label = type_label_in(config.lang, kind.as_str()).0;
+                let unit = unit_in(config.units, kind.as_str());
+                let current = series.back().map_or(0.0, |&(_, value)| value);
+                let text = i18n::message_with(
+                    config.lang,
+                    if change < 0.0 { "rate_falling" } else { "rate_rising" },
+                    &[
+                        ("type", label),
+                        ("room", &room_name(&device_id)),
+                        ("change", &format!("{:+.1}", config.units.show_delta(&kind, change))),
+                        ("unit", unit),
+                        ("window", &format_duration(rule.minutes * 60)),
+                        ("value", &format!("{:.1}", config.units.show(&kind, current))),
+                    ],
+                );
+                if config.is_muted(pass.now) {
+                    config.muted_missed += 1;
+                    changed = true;
+                } else {
+                    self.outbox.send(ChatId(chat_id), text);
+                }
+            }
+        }
+        changed
+    }
+
+    // Vergleichsschwellen: nur mit frischen Werten beider Sensoren aus diesem
+    // Abruf; Alarm und Entwarnung je einmal, mit Hysterese
+    fn check_relations(&mut self, configs: &mut HashMap<i64, UserConfig>, pass: &Pass<'_>) -> bool {
+        let checked_at = pass.now.timestamp();
+        let reading = |device_id: &str, kind: &SensorKind| {
+            pass.readings.iter().filter(|s| s.device_id == device_id && s.sensor_type == *kind).max_by_key(|s| s.timestamp).map(|s| (s.timestamp, s.value))
+        };
+        let mut changed = false;
+        for (&chat_id, config) in configs.iter_mut() {
+            let rules: Vec<_> = config.relations.iter().map(|(key, rule)| (key.clone(), rule.clone())).collect();
+            for ((device_id, key), rule) in rules {
+                let tenant = tenant_of(chat_id);
+                if !rooms().visible(tenant.as_deref(), &device_id) || !rooms().visible(tenant.as_deref(), &rule.other_device) {
+                    continue;
+                }
+                let Some((own, other)) = relative::comparable(
+                    reading(&device_id, &key.kind),
+                    reading(&rule.other_device, &rule.other_kind),
+                    checked_at,
+                    settings().stale_after_minutes * 60,
+                ) else {
+                    continue;
+                };
+                let flag = (chat_id, device_id.clone(), key.clone());
+                let was = self.relative_flags.get(&flag) == Some(&true);
+                let hysteresis = config.hysteresis_for(&(device_id.clone(), key.clone()));
+                let alarm = in_alarm(own, rule.limit(other), key.direction, hysteresis, was);
+                self.relative_flags.insert(flag, alarm);
+                if alarm == was {
+                    continue;
+                }
+                let message = match (alarm, key.direction) {
+                    (true, ThresholdDirection::Max) => "relation_above",
+                    (true, ThresholdDirection::Min) => "relation_below",
+                    (false, ThresholdDirection::Min) => "relation_back_above",
+                    (false, ThresholdDirection::Max) => "relation_back_below",
+                };
+                let text = i18n::message_with(
+                    config.lang,
+                    message,
+                    &[
+                        ("type", type_label_in(config.lang, key.kind.as_str()).0),
+                        ("room", &room_name(&device_id)),
+                        ("value", &format!("{:.1}", config.units.show(&key.kind, own))),
+                        ("unit", unit_in(config.units, key.kind.as_str())),
+                        ("limit", &format!("{:.1}", config.units.show(&key.kind, rule.limit(other)))),
+                        ("other_room", &room_name(&rule.other_device)),
+                        ("other_value", &format!("{:.1}", config.units.show(&rule.other_kind, other))),
+                        ("other_unit", unit_in(config.units, rule.other_kind.as_str())),
+                        ("offset", &format!("{:+.1}", config.units.show_delta(&key.kind, rule.offset))),
+                    ],
+                );
+                if config.is_muted(pass.now) {
+                    config.muted_missed += 1;
+                    changed = true;
+                } else {
+                    self.outbox.send(ChatId(chat_id), text);
+                }
+            }
+        }
+        changed
+    }
+
+    // Warnungen zustellen: je Chat eine Nachricht, in der Ruhezeit in die
+    // Warteschlange, im Sammelmodus in die Sammelmeldung, dazu die Kopien für
+    // die Ziele aus [routing]
+    async fn dispatch(&self, configs: &mut HashMap<i64, UserConfig>, alarms: AlarmGroups, now: DateTime<Utc>) -> bool {
+        let mut digested = false;
+        // Kopien für zusätzliche Ziele: je (Ziel, Gerät, Schwellen) nur
+        // einmal, auch wenn mehrere Chats dieselbe Schwelle haben
+        let mut routed = RoutedCopies::new();
+        let mut batches: BTreeMap<i64, Vec<BatchedAlert>> = BTreeMap::new();
+        for ((chat_id, device_id), group) in alarms {
+            let keys: Vec<(ThresholdKey, i64)> = group.iter().map(|(key, ts, _, _, _)| (key.clone(), *ts)).collect();
+            let registry = rooms();
+            let hints: Vec<&str> = correlation::matching(registry.rules_for(&device_id), &keys).into_iter().map(|rule| rule.hint.as_str()).collect();
+            // Zeitstempel ist der des Messwerts; versendet wird ggf. deutlich später
+            let observed_at = group.iter().map(|(_, ts, _, _, _)| *ts).max().unwrap_or_default();
+            let messages: Vec<BatchedAlert> = if hints.is_empty() {
+                group
+                    .into_iter()
+                    .map(|(key, ts, text, line, severity)| BatchedAlert {
+                        device_id: device_id.clone(),
+                        text,
+                        lines: vec![line],
+                        keys: vec![key],
+                        severity,
+                        observed_at: ts,
+                    })
+                    .collect()
+            } else {
+                let mut text = group.iter().map(|(_, _, text, _, _)| text.as_str()).collect::<Vec<_>>().join("\n\n");
+                let mut lines: Vec<String> = group.iter().map(|(_, _, _, line, _)| line.clone()).collect();
+                for hint in hints {
+                    text.push_str(&format!("\n\n🔗 {}", hint));
+                    lines.push(format!("  🔗 {}", hint));
+                }
+                let severity = group.iter().map(|(_, _, _, _, severity)| *severity).max().unwrap_or(Severity::Warning);
+                let keys = group.into_iter().map(|(key, _, _, _, _)| key).collect();
+                vec![BatchedAlert { device_id: device_id.clone(), text, lines, keys, severity, observed_at }]
+            };
+            let quiet = configs.get(&chat_id).and_then(|c| c.quiet_hours);
+            // [routing] gilt nur für den Haupthaushalt
+            let targets = |severity| if rooms().device_tenant(&device_id).is_none() { rooms().routing().targets(severity) } else { Vec::new() };
+            for alert in messages {
+                notify_sinks(chat_id, &alert);
+                for target in targets(alert.severity) {
+                    routed
+                        .entry((target, device_id.clone(), alert.keys.clone()))
+                        .or_insert_with(|| (alert.text.clone(), alert.severity, Vec::new(), alert.observed_at))
+                        .2
+                        .push(chat_id);
+                }
+                if quiet.is_some_and(|w| in_local_window(&w, now)) {
+                    let at = DateTime::from_timestamp(alert.observed_at, 0).unwrap_or(now);
+                    self.quiet_queue.lock().await.entry(chat_id).or_default().push((at, alert.text));
+                    continue;
+                }
+                // Sammelmodus: Nicht Kritisches wartet auf die nächste Sammelmeldung
+                if let Some(config) = configs.get_mut(&chat_id).filter(|c| c.alert_mode != AlertMode::Instant && alert.severity != Severity::Critical) {
+                    let at = DateTime::from_timestamp(alert.observed_at, 0).unwrap_or(now);
+                    config.digest.entries.push((at, digest_line(&alert.lines)));
+                    digested = true;
+                    continue;
+                }
+                batches.entry(chat_id).or_default().push(alert);
+            }
+        }
+        // Eine Nachricht je Chat und Durchlauf, Geräte sortiert. Stumm,
+        // zurückgestellt oder in der Ruhezeit Gesammeltes ist hier schon
+        // heraus; bleibt nichts übrig, geht nichts raus.
+        for (chat_id, batch) in batches {
+            self.send_batch(configs, chat_id, batch);
+        }
+        // Ohne Buttons: im Kanal soll niemand Schwellen anderer ändern.
+        // Wer die Warnung schon selbst bekommt, erhält keine Kopie.
+        for ((target, device_id, keys), (text, severity, owners, observed_at)) in routed {
+            if owners.contains(&target) {
+                continue;
+            }
+            let room_image = severity == Severity::Critical && configs.get(&target).is_some_and(|c| c.room_images);
+            let silent = configs.get(&target).map(|c| c.notifications).unwrap_or_default().silent(MessageKind::Alert(severity));
+            self.outbox.send_alert(ChatId(target), text, None, AlertMeta { device_id, keys, owners, observed_at }, room_image, silent);
+        }
+        digested
+    }
+
+    fn send_batch(&self, configs: &HashMap<i64, UserConfig>, chat_id: i64, mut batch: Vec<BatchedAlert>) {
+        let with_images = configs.get(&chat_id).is_some_and(|c| c.room_images);
+        let notifications = configs.get(&chat_id).map(|c| c.notifications).unwrap_or_default();
+        let lang = configs.get(&chat_id).map(|c| c.lang).unwrap_or_default();
+        if batch.len() == 1 {
+            let BatchedAlert { device_id, text, keys, severity, observed_at, .. } = batch.remove(0);
+            let buttons = match keys.as_slice() {
+                [key] => adjust::buttons(&device_id, key, lang),
+                _ => {
+                    let labelled: Vec<(ThresholdKey, &str)> = keys.iter().map(|key| (key.clone(), type_label_in(lang, key.kind.as_str()).0)).collect();
+                    adjust::group_buttons(&device_id, &labelled, lang)
+                }
+            };
+            let meta = AlertMeta { device_id, keys, owners: vec![chat_id], observed_at };
+            self.outbox.send_alert(
+                ChatId(chat_id),
+                text,
+                buttons,
+                meta,
+                severity == Severity::Critical && with_images,
+                notifications.silent(MessageKind::Alert(severity)),
+            );
+            return;
+        }
+        let count: usize = batch.iter().map(|alert| alert.keys.len()).sum();
+        let mut text = match lang {
+            Lang::De => format!("⚠ {} Schwellen gleichzeitig verletzt:", count),
+            Lang::En => format!("⚠ {} thresholds breached at once:", count),
+        };
+        for alert in &batch {
+            for line in &alert.lines {
+                text.push('\n');
+                text.push_str(line);
+            }
+        }
+        // "✅ OK" bestätigt alles; Anpassen geht über /configure oder /setmin
+        let buttons = batch.first().and_then(|alert| adjust::acknowledge_button(&alert.device_id, alert.keys.first()?, lang));
+        let room_image = batch.iter().find(|alert| alert.severity == Severity::Critical).filter(|_| with_images).map(|alert| alert.device_id.clone());
+        // Der Ton richtet sich nach der höchsten Stufe darin
+        let highest = batch.iter().map(|alert| alert.severity).max().unwrap_or(Severity::Info);
+        let silent = notifications.silent(MessageKind::Alert(highest));
+        let metas = batch
+            .into_iter()
+            .map(|alert| AlertMeta { device_id: alert.device_id, keys: alert.keys, owners: vec![chat_id], observed_at: alert.observed_at })
+            .collect();
+        self.outbox.send_alerts(ChatId(chat_id), text, buttons, metas, room_image, silent);
+    }
+}
+
+// Schwellen prüfen und die Ereignisse verbuchen: Alarmprotokoll, laufende
+// Verletzungen, häufigere Abfrage und Entwarnungen. Die Warnungen selbst
+// kommen je Chat und Gerät gesammelt zurück, damit zusammengehörige
+// (Korrelationsregeln) in einer Nachricht kommen.
+fn collect_alarms(
+    configs: &mut HashMap<i64, UserConfig>,
+    flags: &mut Flags,
+    escalation: &mut Escalation,
+    history: &History,
+    outbox: &Outbox,
+    pass: &Pass<'_>,
+) -> (AlarmGroups, bool) {
+    let now = pass.now;
+    let mut changed = false;
+    let mut alarms = AlarmGroups::new();
+    for event in evaluate(configs, &pass.readings, flags, local_time_of(now)) {
+        // Schwellen auf Geräte eines fremden Haushalts (z.B. von vor
+        // der Zuordnung des Chats) lösen nichts aus
+        if !rooms().visible(tenant_of(event.chat_id).as_deref(), &event.device_id) {
+            continue;
+        }
+        // Mehrere Alarme eines Geräts teilen sich ein Fenster; es endet,
+        // wenn keiner mehr besteht
+        if event.kind == EventKind::Alarm {
+            if escalation.trigger(&event.device_id, Instant::now()) {
+                info!(
+                    "{} wird nach Alarm {} lang alle {} abgefragt",
+                    room_name(&event.device_id),
+                    format_duration(settings().escalate_minutes as i64 * 60),
+                    format_duration(escalation.interval().as_secs() as i64)
+                );
+            }
+        } else if !flags.iter().any(|((_, device_id, _), alarm)| *alarm && *device_id == event.device_id) && escalation.recover(&event.device_id) {
+            info!("Häufigere Abfrage von {} beendet (Wert erholt)", room_name(&event.device_id));
+        }
+        let key = ThresholdKey::new(event.sensor_type.clone(), event.direction);
+        let Some(config) = configs.get_mut(&event.chat_id) else { continue };
+        let id = (event.device_id.clone(), key.clone());
+        if event.kind != EventKind::Deactivated {
+            let suppressed = if config.is_snoozed(&event.device_id, &key, now) {
+                Some(Suppressed::Snoozed)
+            } else if config.is_muted(now) {
+                Some(Suppressed::Muted)
+            } else if config.quiet_hours.is_some_and(|w| in_local_window(&w, now)) {
+                Some(Suppressed::Quiet)
+            } else {
+                None
+            };
+            config.alarm_log.record(alarm_log::Entry::of(&event, suppressed), now.timestamp());
+            changed = true;
+        }
+        if event.kind == EventKind::Alarm {
+            let mut episode = Episode::resume_or_start(config.episodes.remove(&id), event.direction, event.timestamp, event.value);
+            episode.escalated |= escalation.active(Instant::now()).iter().any(|(d, _)| *d == event.device_id);
+            config.episodes.insert(id.clone(), episode);
+            changed = true;
+        } else {
+            // Bestätigung gilt bis zur Erholung
+            changed |= config.acknowledged.remove(&id).is_some();
+            let Some(episode) = config.episodes.remove(&id) else { continue };
+            changed = true;
+            // Entwarnung als Antwort auf die Warnung, damit beides
+            // im Chat zusammensteht. Nur wenn die Warnung zugestellt
+            // wurde und der Chat gerade Nachrichten bekommt.
+            let quiet = config.quiet_hours.is_some_and(|w| in_local_window(&w, now));
+            let delivers = event.kind == EventKind::Recovered && !quiet && !config.is_muted(now) && !config.is_snoozed(&event.device_id, &key, now);
+            let recovery = format_recovery(&event, &episode, config.lang, config.units, chat_timezone(Some(config)));
+            if let (true, Some(handover)) = (episode.handed_over, config.escalate_to) {
+                outbox.send(ChatId(handover.chat_id), format!("✅ Zur weitergegebenen Warnung:\n{}", recovery));
+            }
+            let Some(alarm_message) = episode.message_id else {
+                // Warnung kam nur in der Sammelmeldung, die Entwarnung auch
+                if delivers && config.alert_mode != AlertMode::Instant {
+                    let line = recovery.lines().next().unwrap_or_default().to_string();
+                    config.digest.entries.push((now, line));
+                }
+                continue;
+            };
+            if delivers {
+                outbox.send_reply(ChatId(event.chat_id), recovery, Some(alarm_message));
+            }
+            continue;
+        }
+        if config.is_snoozed(&event.device_id, &key, now) {
+            continue;
+        }
+        if config.is_muted(now) {
+            config.muted_missed += 1;
+            changed = true;
+            continue;
+        }
+        let (text, line, severity) = alert_message(Some(&*config), &event, history, pass.synthetic(&event));
+        // Für die Weitergabe (/escalate-to), falls niemand bestätigt
+        if config.escalate_to.is_some()
+            && let Some(episode) = config.episodes.get_mut(&id)
+        {
+            episode.alert = Some((text.clone(), severity));
+            changed = true;
+        }
+        alarms.entry((event.chat_id, event.device_id)).or_default().push((key, event.timestamp, text, line, severity));
+    }
+    (alarms, changed)
+}
+
+// Überwachung als eigene Aufgabe. Über den Sender wird sie nach dem
+// laufenden Durchlauf beendet.
+pub fn spawn(mut monitor: Monitor) -> (JoinHandle<()>, watch::Sender<bool>) {
+    // Beim Beenden läuft der Durchlauf zu Ende, statt abgebrochen zu werden
+    let (stop, mut stopped) = watch::channel(false);
+    let task = tokio::spawn(async move {
+        loop {
+            let wake = monitor.run_once().await;
+            tokio::select! {
+                _ = tokio::time::sleep_until(wake) => {}
+                _ = inject::notified() => {}
+                _ = push::notified() => {}
+                _ = refresh::notified() => {}
+                _ = POLL_INTERVAL_CHANGED.notified() => monitor.reschedule(),
+                // Ohne Handle (Err) läuft die Überwachung weiter
+                Ok(()) = stopped.changed() => break,
+            }
+        }
+    });
+    (task, stop)
+}
+
+// Prüfstufe für eingehende Messwerte: einzelne Ausreißer fallen heraus, mit
+// ihnen die daraus berechneten Werte (Taupunkt, Hitzeindex, absolute Feuchte). Halten sie lange genug
+// an, gelten sie als echt und der Admin erfährt davon.
+fn screen_readings(filter: &mut PlausibilityFilter, readings: &mut Vec<SensorData>, outbox: &Outbox, lang: Lang) {
+    let derived = |kind: &SensorKind| matches!(kind, SensorKind::DewPoint | SensorKind::HeatIndex | SensorKind::AbsoluteHumidity);
+    let mut rejected: BTreeSet<String> = BTreeSet::new();
+    readings.retain(|reading| {
+        if derived(&reading.sensor_type) {
+            return true;
+        }
+        let (label, unit) = type_label(reading.sensor_type.as_str());
+        let verdict = filter.check(&reading.device_id, &reading.sensor_type, reading.timestamp, reading.value);
+        match verdict {
+            Screening::Reject(doubt) => info!("Ausreißer verworfen: {} {} {:.1} {} ({})", room_name(&reading.device_id), label, reading.value, unit, doubt),
+            Screening::Confirmed { doubt, samples } => {
+                info!(
+                    "{} {} {:.1} {} nach {} zweifelhaften Werten in Folge angenommen ({})",
+                    room_name(&reading.device_id),
+                    label,
+                    reading.value,
+                    unit,
+                    samples,
+                    doubt
+                );
+                if let Some(admin) = settings().admin_chat.filter(|_| settings().plausibility_notify) {
+                    let text = i18n::message_with(
+                        lang,
+                        "implausible_confirmed",
+                        &[
+                            ("room", &room_name(&reading.device_id)),
+                            ("type", type_label_in(lang, reading.sensor_type.as_str()).0),
+                            ("count", &samples.to_string()),
+                            ("doubt", &doubt.text().in_lang(lang)),
+                            ("value", &format!("{:.1}", reading.value)),
+                            ("unit", unit),
+                        ],
+                    );
+                    outbox.send(ChatId(admin), text);
+                }
+            }
+            Screening::Accept | Screening::Again { .. } => {}
+        }
+        if !verdict.keep() {
+            rejected.insert(reading.device_id.clone());
+        }
+        verdict.keep()
+    });
+    readings.retain(|reading| !(derived(&reading.sensor_type) && rejected.contains(&reading.device_id)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOON: NaiveTime = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+
+    fn reading(device_id: &str, value: f64) -> SensorData {
+        SensorData { device_id: device_id.to_string(), sensor_type: SensorKind::Temperature, value, timestamp: 1_700_000_000 }
+    }
+
+    fn with_threshold(mut config: UserConfig, device_id: &str, direction: ThresholdDirection, value: f64, hysteresis: f64) -> UserConfig {
+        let key = (device_id.to_string(), ThresholdKey::new(SensorKind::Temperature, direction));
+        config.thresholds.entry(key.clone()).or_default().set_default(value, "test".to_string());
+        config.hysteresis.insert(key, hysteresis);
+        config
+    }
+
+    // Ereignisse einer Folge von Messwerten für einen Chat
+    fn kinds(config: &UserConfig, values: &[f64]) -> Vec<Option<EventKind>> {
+        let mut flags = Flags::new();
+        values
+            .iter()
+            .map(|&value| evaluate_threshold(1, config, &reading("sensor1", value), ThresholdDirection::Max, &mut flags, NOON).map(|event| event.kind))
+            .collect()
+    }
+
+    #[test]
+    fn alarm_starts_only_past_the_threshold() {
+        assert!(!in_alarm(25.0, 25.0, ThresholdDirection::Max, 0.5, false));
+        assert!(in_alarm(25.1, 25.0, ThresholdDirection::Max, 0.5, false));
+        assert!(!in_alarm(18.0, 18.0, ThresholdDirection::Min, 0.5, false));
+        assert!(in_alarm(17.9, 18.0, ThresholdDirection::Min, 0.5, false));
+    }
+
+    #[test]
+    fn alarm_ends_only_past_the_hysteresis() {
+        assert!(in_alarm(24.6, 25.0, ThresholdDirection::Max, 0.5, true));
+        assert!(!in_alarm(24.5, 25.0, ThresholdDirection::Max, 0.5, true));
+        assert!(in_alarm(18.4, 18.0, ThresholdDirection::Min, 0.5, true));
+        assert!(!in_alarm(18.5, 18.0, ThresholdDirection::Min, 0.5, true));
+        // Eine negative Hysterese wirkt wie keine
+        assert!(!in_alarm(25.0, 25.0, ThresholdDirection::Max, -1.0, true));
+    }
+
+    #[test]
+    fn value_hovering_at_the_threshold_alarms_once() {
+        let config = with_threshold(UserConfig::default(), "sensor1", ThresholdDirection::Max, 25.0, 0.5);
+        assert_eq!(
+            kinds(&config, &[25.2, 24.9, 25.1, 24.7, 24.4, 25.3]),
+            [Some(EventKind::Alarm), None, None, None, Some(EventKind::Recovered), Some(EventKind::Alarm)]
+        );
+    }
+
+    #[test]
+    fn removed_threshold_deactivates_a_running_alarm() {
+        let config = with_threshold(UserConfig::default(), "sensor1", ThresholdDirection::Max, 25.0, 0.5);
+        let mut flags = Flags::new();
+        let event = evaluate_threshold(1, &config, &reading("sensor1", 26.0), ThresholdDirection::Max, &mut flags, NOON);
+        assert_eq!(event.map(|e| e.kind), Some(EventKind::Alarm));
+        let event = evaluate_threshold(1, &UserConfig::default(), &reading("sensor1", 26.0), ThresholdDirection::Max, &mut flags, NOON);
+        assert_eq!(event.map(|e| e.kind), Some(EventKind::Deactivated));
+        assert!(flags.is_empty());
+    }
+
+    #[test]
+    fn recovery_only_on_the_transition_with_value_and_threshold() {
+        // unter → über → unter der MIN-Schwelle, jeweils zweimal hintereinander
+        let config = with_threshold(UserConfig::default(), "sensor1", ThresholdDirection::Min, 18.0, 0.5);
+        let mut flags = Flags::new();
+        let events: Vec<Option<ThresholdEvent>> = [17.0, 17.2, 19.0, 19.5, 17.5, 17.0]
+            .iter()
+            .map(|&value| evaluate_threshold(1, &config, &reading("sensor1", value), ThresholdDirection::Min, &mut flags, NOON))
+            .collect();
+        let kinds: Vec<Option<EventKind>> = events.iter().map(|event| event.as_ref().map(|e| e.kind)).collect();
+        assert_eq!(kinds, [Some(EventKind::Alarm), None, Some(EventKind::Recovered), None, Some(EventKind::Alarm), None]);
+        let recovered = events[2].as_ref().unwrap();
+        assert_eq!((recovered.value, recovered.threshold), (19.0, Some(18.0)));
+    }
+
+    #[test]
+    fn two_chats_on_the_same_sensor_keep_their_own_state() {
+        let strict = with_threshold(UserConfig::default(), "sensor1", ThresholdDirection::Max, 24.0, 0.0);
+        let relaxed = with_threshold(UserConfig::default(), "sensor1", ThresholdDirection::Max, 26.0, 0.0);
+        let mut configs = HashMap::from([(1, strict), (2, relaxed)]);
+        let mut flags = Flags::new();
+        let mut step = |configs: &HashMap<i64, UserConfig>, value: f64| {
+            let mut events: Vec<(i64, EventKind)> =
+                evaluate(configs, &[reading("sensor1", value)], &mut flags, NOON).into_iter().map(|e| (e.chat_id, e.kind)).collect();
+            events.sort_by_key(|(chat_id, _)| *chat_id);
+            events
+        };
+        assert_eq!(step(&configs, 25.0), [(1, EventKind::Alarm)]);
+        assert_eq!(step(&configs, 27.0), [(2, EventKind::Alarm)]);
+        // Chat 1 löscht seine Schwelle, Chat 2 behält seinen Alarm
+        configs.get_mut(&1).unwrap().thresholds.clear();
+        assert_eq!(step(&configs, 27.0), [(1, EventKind::Deactivated)]);
+        assert_eq!(step(&configs, 25.0), [(2, EventKind::Recovered)]);
+        assert_eq!(step(&configs, 27.0), [(2, EventKind::Alarm)]);
+    }
+
+    #[test]
+    fn wohnzimmer_threshold_alerts_on_sensor1() {
+        // Früher unter dem Raumnamen abgelegt und daher nie ausgelöst
+        let (device_id, key) = crate::wohnzimmer_key(1, SensorKind::Temperature, ThresholdDirection::Max);
+        assert_eq!(device_id, "sensor1");
+        let mut config = UserConfig::default();
+        config.thresholds.entry((device_id, key)).or_default().set_default(25.0, "/wohnzimmer-tmax 25".to_string());
+        let configs = HashMap::from([(1, config)]);
+        let events = evaluate(&configs, &[reading("sensor1", 26.0)], &mut Flags::new(), NOON);
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].kind, events[0].device_id.as_str(), events[0].threshold), (EventKind::Alarm, "sensor1", Some(25.0)));
+    }
+}
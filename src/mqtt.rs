@@ -0,0 +1,213 @@
+// Messwerte von einem MQTT-Broker (über rumqttc, nur Abonnieren mit QoS 0,
+// ohne TLS). Gerät und Messgröße stehen im Topic, die Nachricht ist der
+// Wert. Neue Werte lösen sofort eine Auswertung aus (wie POST /readings);
+// als `SensorSource` liefert die Quelle den jeweils letzten Wert je Gerät
+// und Messgröße für die regulären Durchläufe und /status.
+use crate::sensor::SensorKind;
+use crate::source::{BoxFuture, FetchError, SensorSource};
+use crate::{SensorData, push};
+use chrono::Utc;
+use log::{debug, info, warn};
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const DEFAULT_PORT: u16 = 1883;
+const DEFAULT_TOPIC: &str = "home/+/+";
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+// Wartezeit vor dem nächsten Verbindungsversuch, verdoppelt bis zum Maximum
+const FIRST_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+// Größere Pakete sind keine Messwerte; die Verbindung wird neu aufgebaut
+const MAX_PACKET_BYTES: usize = 64 * 1024;
+// Ausstehende Anfragen an den Client (nur das Abonnement)
+const REQUEST_CAPACITY: usize = 10;
+
+/// Zugang zum Broker: MQTT_URL (mqtt://host:port), MQTT_USERNAME,
+/// MQTT_PASSWORD, MQTT_TOPIC (Standard "home/+/+": erstes + Gerät, zweites
+/// + Messgröße) und MQTT_CLIENT_ID
+#[derive(Debug, Clone)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub topic: String,
+    pub client_id: String,
+}
+
+impl MqttConfig {
+    // None ohne MQTT_URL
+    pub fn from_env() -> Option<Result<MqttConfig, String>> {
+        let url = env::var("MQTT_URL").ok().filter(|url| !url.trim().is_empty())?;
+        let topic = env::var("MQTT_TOPIC").unwrap_or_else(|_| DEFAULT_TOPIC.to_string());
+        Some(parse_url(&url).and_then(|(host, port)| {
+            if topic.split('/').filter(|segment| *segment == "+").count() != 2 || topic.contains('#') {
+                return Err(format!("MQTT_TOPIC '{}' braucht genau zwei + (Gerät und Messgröße) und kein #", topic));
+            }
+            Ok(MqttConfig {
+                host,
+                port,
+                username: env::var("MQTT_USERNAME").ok().filter(|u| !u.is_empty()),
+                password: env::var("MQTT_PASSWORD").ok(),
+                topic,
+                client_id: env::var("MQTT_CLIENT_ID").unwrap_or_else(|_| format!("telegrambot-{}", std::process::id())),
+            })
+        }))
+    }
+}
+
+fn parse_url(url: &str) -> Result<(String, u16), String> {
+    let rest = url.trim().strip_prefix("mqtt://").ok_or_else(|| format!("MQTT_URL '{}' muss mit mqtt:// beginnen", url))?;
+    let rest = rest.trim_end_matches('/');
+    match rest.rsplit_once(':') {
+        Some((host, port)) => {
+            let port = port.parse().map_err(|_| format!("Port in MQTT_URL '{}' ungültig", url))?;
+            Ok((host.to_string(), port))
+        }
+        None if !rest.is_empty() => Ok((rest.to_string(), DEFAULT_PORT)),
+        None => Err(format!("MQTT_URL '{}' ohne Host", url)),
+    }
+}
+
+// Gerät und Messgröße aus den +-Stellen des Topics
+fn topic_reading(pattern: &str, topic: &str) -> Option<(String, SensorKind)> {
+    let mut wildcards = Vec::new();
+    let mut parts = topic.split('/');
+    for segment in pattern.split('/') {
+        let part = parts.next()?;
+        match segment {
+            "+" => wildcards.push(part),
+            literal if literal != part => return None,
+            _ => {}
+        }
+    }
+    if parts.next().is_some() {
+        return None;
+    }
+    match wildcards.as_slice() {
+        [device_id, kind] if !device_id.is_empty() && !kind.is_empty() => Some((device_id.to_string(), SensorKind::from(*kind))),
+        _ => None,
+    }
+}
+
+fn parse_value(payload: &[u8]) -> Option<f64> {
+    std::str::from_utf8(payload).ok()?.trim().replace(',', ".").parse().ok().filter(|v: &f64| v.is_finite())
+}
+
+type Latest = Arc<Mutex<BTreeMap<(String, SensorKind), SensorData>>>;
+
+/// Quelle, die einen MQTT-Broker abonniert. Die Verbindung läuft im
+/// Hintergrund und wird bei Verlust mit wachsender Pause neu aufgebaut.
+pub struct MqttSource {
+    latest: Latest,
+    broker: String,
+}
+
+impl MqttSource {
+    // Muss innerhalb der Tokio-Laufzeit aufgerufen werden
+    pub fn connect(config: MqttConfig) -> MqttSource {
+        let latest: Latest = Arc::new(Mutex::new(BTreeMap::new()));
+        let broker = format!("{}:{}", config.host, config.port);
+        tokio::spawn(run(config, latest.clone()));
+        MqttSource { latest, broker }
+    }
+}
+
+impl SensorSource for MqttSource {
+    fn fetch(&self) -> BoxFuture<'_, Result<Vec<SensorData>, FetchError>> {
+        Box::pin(async move {
+            // Vor der ersten Nachricht einfach noch keine Messwerte, kein Fehler
+            let readings: Vec<SensorData> = self.latest.lock().unwrap_or_else(|e| e.into_inner()).values().cloned().collect();
+            if readings.is_empty() {
+                debug!("Noch keine Nachricht von MQTT-Broker {}", self.broker);
+            }
+            Ok(readings)
+        })
+    }
+}
+
+// Verbindung halten: rumqttc baut sie beim nächsten `poll` nach einem
+// Fehler selbst neu auf, dazwischen wird mit wachsender Pause gewartet.
+// Mit Clean Session muss nach jeder Anmeldung neu abonniert werden.
+async fn run(config: MqttConfig, latest: Latest) {
+    let mut options = MqttOptions::new(config.client_id.clone(), config.host.clone(), config.port);
+    options.set_keep_alive(KEEP_ALIVE).set_clean_session(true).set_max_packet_size(MAX_PACKET_BYTES, MAX_PACKET_BYTES);
+    if let Some(username) = &config.username {
+        options.set_credentials(username.clone(), config.password.clone().unwrap_or_default());
+    }
+    let (client, mut events) = AsyncClient::new(options, REQUEST_CAPACITY);
+    let mut backoff = FIRST_BACKOFF;
+    loop {
+        match events.poll().await {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                backoff = FIRST_BACKOFF;
+                match client.try_subscribe(config.topic.clone(), QoS::AtMostOnce) {
+                    Ok(()) => info!("MQTT-Broker {}:{} verbunden, abonniert: {}", config.host, config.port, config.topic),
+                    Err(err) => warn!("MQTT-Abonnement {} nicht möglich: {}", config.topic, err),
+                }
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => receive(&config, &latest, &publish.topic, &publish.payload),
+            Ok(_) => {}
+            Err(err) => {
+                warn!("MQTT-Broker {}:{}: {}", config.host, config.port, err);
+                info!("Neuer Verbindungsversuch zum MQTT-Broker in {} s", backoff.as_secs());
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+}
+
+fn receive(config: &MqttConfig, latest: &Latest, topic: &str, payload: &[u8]) {
+    let Some((device_id, sensor_type)) = topic_reading(&config.topic, topic) else { return };
+    let Some(value) = parse_value(payload) else {
+        debug!("MQTT-Nachricht auf {} ist kein Zahlenwert, übersprungen", topic);
+        return;
+    };
+    let reading = SensorData { device_id, sensor_type, value, timestamp: Utc::now().timestamp() };
+    latest.lock().unwrap_or_else(|e| e.into_inner()).insert((reading.device_id.clone(), reading.sensor_type.clone()), reading.clone());
+    push::push(vec![reading]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn topic_segments_name_device_and_kind() {
+        assert_eq!(topic_reading("home/+/+", "home/kueche/temperature"), Some(("kueche".to_string(), SensorKind::Temperature)));
+        assert_eq!(topic_reading("home/+/+", "garden/kueche/temperature"), None);
+        assert_eq!(topic_reading("home/+/+", "home/kueche/temperature/raw"), None);
+        assert_eq!(topic_reading("home/+/+", "home//temperature"), None);
+    }
+
+    #[test]
+    fn payload_is_a_number() {
+        assert_eq!(parse_value(b" 21,5\n"), Some(21.5));
+        assert_eq!(parse_value(b"NaN"), None);
+        assert_eq!(parse_value(b"{\"value\": 1}"), None);
+    }
+
+    #[test]
+    fn broker_url() {
+        assert_eq!(parse_url("mqtt://broker:1884/"), Ok(("broker".to_string(), 1884)));
+        assert_eq!(parse_url("mqtt://broker"), Ok(("broker".to_string(), DEFAULT_PORT)));
+        assert!(parse_url("http://broker").is_err());
+    }
+
+    #[tokio::test]
+    async fn no_readings_before_the_first_message() {
+        let config =
+            MqttConfig { host: "localhost".into(), port: DEFAULT_PORT, username: None, password: None, topic: DEFAULT_TOPIC.into(), client_id: "test".into() };
+        let source = MqttSource { latest: Latest::default(), broker: "localhost:1883".into() };
+        assert!(source.fetch().await.unwrap().is_empty());
+
+        receive(&config, &source.latest, "home/kueche/temperature", b"21.5");
+        let readings = source.fetch().await.unwrap();
+        assert_eq!(readings.len(), 1);
+        assert_eq!(readings[0].value, 21.5);
+    }
+}
@@ -0,0 +1,60 @@
+use crate::i18n::Text;
+use crate::routing::Severity;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// Benachrichtigungston je Chat (/notifications). Kritische Warnungen kommen
+// in jedem Modus mit Ton.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationMode {
+    Loud, // alles mit Ton, auch Antworten auf Befehle
+    #[default]
+    Normal, // Antworten und Sammelmeldungen lautlos, Warnungen ab Stufe warn mit Ton
+    Quiet, // nur kritische Warnungen mit Ton
+}
+
+// Wozu eine Nachricht gehört; zusammen mit dem Modus entscheidet das den Ton
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    Reply,  // Antwort auf einen Befehl oder eine Nachricht des Chats
+    Digest, // Berichte, Zusammenfassungen, gesammelte Warnungen
+    Alert(Severity),
+}
+
+impl NotificationMode {
+    pub fn silent(self, kind: MessageKind) -> bool {
+        match (self, kind) {
+            (_, MessageKind::Alert(Severity::Critical)) => false,
+            (NotificationMode::Loud, _) => false,
+            (NotificationMode::Quiet, _) => true,
+            (NotificationMode::Normal, MessageKind::Alert(severity)) => severity == Severity::Info,
+            (NotificationMode::Normal, MessageKind::Reply | MessageKind::Digest) => true,
+        }
+    }
+}
+
+impl FromStr for NotificationMode {
+    type Err = Text;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "loud" | "laut" => Ok(NotificationMode::Loud),
+            "normal" => Ok(NotificationMode::Normal),
+            "quiet" | "leise" => Ok(NotificationMode::Quiet),
+            _ => Err(Text::new("notifications_usage", &[])),
+        }
+    }
+}
+
+impl fmt::Display for NotificationMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotificationMode::Loud => write!(f, "laut"),
+            NotificationMode::Normal => write!(f, "normal"),
+            NotificationMode::Quiet => write!(f, "leise"),
+        }
+    }
+}
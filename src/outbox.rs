@@ -0,0 +1,552 @@
+use crate::messenger::{Messenger, OutgoingMessage, SendError};
+use crate::redact;
+use crate::sensor::ThresholdKey;
+use crate::{telemetry, tenant_of};
+use log::{debug, error, info, warn};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::time::{Duration, Instant};
+use teloxide::prelude::*;
+use teloxide::types::InlineKeyboardMarkup;
+use tokio::sync::mpsc;
+
+// Budget für Hintergrund-Nachrichten (Warnungen, Berichte). Telegram erlaubt
+// ca. 30 Nachrichten/s insgesamt und 1/s je Chat; der Rest bleibt für direkte
+// Antworten auf Befehle frei, damit diese nie hinter einem Stau warten.
+const BULK_MESSAGES_PER_SECOND: u32 = 20;
+const PER_CHAT_INTERVAL: Duration = Duration::from_secs(1);
+// Wartezeit nach einem Fehlschlag (Netzwerk, Serverfehler), verdoppelt bis zum Maximum
+const FIRST_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+// Ab dieser Wartezeit wird der Stau protokolliert
+const SLOW_WAIT: Duration = Duration::from_secs(10);
+
+struct Queued {
+    message: OutgoingMessage,
+    queued_at: Instant,
+    alerts: Vec<AlertMeta>,    // mehrere bei einer Sammelwarnung
+    interactive: bool,         // Antwort auf einen Befehl, siehe `send_interactive`
+    reply_target: Option<i32>, // `reply_to` beim Einreihen, falls es unterwegs entfällt
+    attempts: u32,
+    backoff: Duration, // Pause nach dem nächsten Fehlschlag
+}
+
+impl Queued {
+    fn new(message: OutgoingMessage, alerts: Vec<AlertMeta>, interactive: bool) -> Queued {
+        let reply_target = message.reply_to;
+        Queued { message, queued_at: Instant::now(), alerts, interactive, reply_target, attempts: 0, backoff: FIRST_BACKOFF }
+    }
+}
+
+// Wozu eine Warnung gehört: Gerät, Schwellen und die Chats, deren Schwellen
+// es sind (bei Kopien in zusätzliche Chats ggf. mehrere)
+#[derive(Clone)]
+pub struct AlertMeta {
+    pub device_id: String,
+    pub keys: Vec<ThresholdKey>,
+    pub owners: Vec<i64>,
+    pub observed_at: i64, // Zeitpunkt des auslösenden Messwerts, nicht des Versands
+}
+
+// Zugestellte Warnung mit Nachrichten-ID, für Reaktionen und Folgemeldungen
+pub struct SentAlert {
+    pub chat_id: i64,
+    pub message_id: i32,
+    pub alert: AlertMeta,
+    pub delivered_at: i64, // tatsächliche Zustellung, nach Warteschlange und Wiederholungen
+}
+
+// Rückmeldungen der Warteschlange an den Bot
+pub enum Delivery {
+    Alert(SentAlert),
+    // Folgemeldung ging ohne Bezug raus, weil die Warnung gelöscht wurde
+    ReplyTargetMissing { chat_id: i64, message_id: i32 },
+    // Ergebnis jeder Zustellung für die Erreichbarkeit des Chats; Drosselung
+    // und Netzwerkfehler sagen darüber nichts aus und werden nicht gemeldet
+    Reached { chat_id: i64 },
+    Unreachable { chat_id: i64, reason: String },
+}
+
+// Warteschlange für Nachrichten aus Überwachung und Zeitplänen.
+// Antworten auf Befehle gehen direkt über den Bot und umgehen sie.
+// Zugestellt wird über den Messenger (Telegram oder eingebettet ein eigener),
+// je Chat der Reihe nach. Ein gedrosselter Chat hält die anderen nicht auf,
+// auch nicht, während er auf einen neuen Versuch wartet.
+#[derive(Clone)]
+pub struct Outbox {
+    tx: mpsc::UnboundedSender<Queued>,
+    // Kann kurz unter 0 fallen: gezählt wird erst nach dem Einreihen, die
+    // Zustellung kann schneller sein
+    queued: Arc<AtomicIsize>,
+}
+
+impl Outbox {
+    // Zugestellte Warnungen, verwaiste Antworten und die Erreichbarkeit der
+    // Chats werden über `deliveries` gemeldet. Nach `max_attempts`
+    // Fehlschlägen wird eine Nachricht verworfen.
+    pub fn spawn(messenger: Arc<dyn Messenger>, deliveries: mpsc::UnboundedSender<Delivery>, max_attempts: u32) -> Outbox {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let queued = Arc::new(AtomicIsize::new(0));
+        tokio::spawn(run(messenger, rx, queued.clone(), deliveries, max_attempts.max(1)));
+        Outbox { tx, queued }
+    }
+
+    pub fn send(&self, chat: ChatId, text: impl Into<String>) {
+        self.enqueue(chat, text.into(), false, false);
+    }
+
+    // Wie `send`, `silent` nach der Einstellung des Chats (/notifications)
+    pub fn send_plain(&self, chat: ChatId, text: impl Into<String>, silent: bool) {
+        self.enqueue(chat, text.into(), false, silent);
+    }
+
+    // Antwort auf einen Befehl, die über die Warteschlange geht (/test-alarm):
+    // kommt vor allen Hintergrund-Nachrichten dran und zählt nicht zu deren
+    // Budget, nur die Drosselung je Chat gilt
+    pub fn send_interactive(&self, chat: ChatId, text: impl Into<String>, silent: bool) {
+        let message =
+            OutgoingMessage { chat_id: chat.0, text: text.into(), markdown: false, html: false, buttons: None, silent, reply_to: None, room_image: None };
+        self.queue(message, Vec::new(), true);
+    }
+
+    // Legacy-Markdown, wie bei /status
+    pub fn send_markdown(&self, chat: ChatId, text: impl Into<String>) {
+        self.enqueue(chat, text.into(), true, false);
+    }
+
+    // Bericht oder Zusammenfassung in Legacy-Markdown; `silent` nach der
+    // Einstellung des Chats (/notifications)
+    pub fn send_digest(&self, chat: ChatId, text: impl Into<String>, silent: bool) {
+        self.enqueue(chat, text.into(), true, silent);
+    }
+
+    // Warnung zu Schwellen eines Geräts, optional mit Inline-Buttons und
+    // dem Raumbild des Geräts; `silent` für Infos ohne Benachrichtigungston
+    pub fn send_alert(&self, chat: ChatId, text: String, buttons: Option<InlineKeyboardMarkup>, alert: AlertMeta, room_image: bool, silent: bool) {
+        let room_image = room_image.then(|| alert.device_id.clone());
+        self.push(OutgoingMessage { chat_id: chat.0, text, markdown: false, html: false, buttons, silent, reply_to: None, room_image }, vec![alert]);
+    }
+
+    // Sammelwarnung zu mehreren Geräten in einer Nachricht; jede Warnung
+    // wird einzeln als zugestellt gemeldet, mit derselben Nachrichten-ID
+    pub fn send_alerts(
+        &self,
+        chat: ChatId,
+        text: String,
+        buttons: Option<InlineKeyboardMarkup>,
+        alerts: Vec<AlertMeta>,
+        room_image: Option<String>,
+        silent: bool,
+    ) {
+        self.push(OutgoingMessage { chat_id: chat.0, text, markdown: false, html: false, buttons, silent, reply_to: None, room_image }, alerts);
+    }
+
+    // Hinweis mit Inline-Buttons, ohne Bezug zu einer Warnung
+    pub fn send_with_buttons(&self, chat: ChatId, text: String, buttons: InlineKeyboardMarkup) {
+        self.push(
+            OutgoingMessage { chat_id: chat.0, text, markdown: false, html: false, buttons: Some(buttons), silent: false, reply_to: None, room_image: None },
+            Vec::new(),
+        );
+    }
+
+    // Folgemeldung als Antwort auf eine frühere Warnung; gibt es die nicht
+    // mehr, geht sie ohne Bezug raus
+    pub fn send_reply(&self, chat: ChatId, text: String, reply_to: Option<i32>) {
+        self.push(
+            OutgoingMessage { chat_id: chat.0, text, markdown: false, html: false, buttons: None, silent: false, reply_to, room_image: None },
+            Vec::new(),
+        );
+    }
+
+    // Nachrichten, die noch nicht zugestellt sind (/debug)
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed).max(0) as usize
+    }
+
+    // Beim Beenden: warten, bis alles zugestellt ist, höchstens `timeout`.
+    // Liefert die Zahl der Nachrichten, die noch ausstehen.
+    pub async fn drain(&self, timeout: Duration) -> usize {
+        let deadline = Instant::now() + timeout;
+        while self.queued() > 0 && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        self.queued()
+    }
+
+    fn enqueue(&self, chat: ChatId, text: String, markdown: bool, silent: bool) {
+        self.push(OutgoingMessage { chat_id: chat.0, text, markdown, html: false, buttons: None, silent, reply_to: None, room_image: None }, Vec::new());
+    }
+
+    fn push(&self, message: OutgoingMessage, alerts: Vec<AlertMeta>) {
+        self.queue(message, alerts, false);
+    }
+
+    fn queue(&self, message: OutgoingMessage, alerts: Vec<AlertMeta>, interactive: bool) {
+        let chat_id = message.chat_id;
+        // Gesperrte Chats bekommen auch keine Warnungen und Berichte mehr
+        if !crate::admitted(chat_id) {
+            info!("Nachricht an nicht freigegebenen Chat {} verworfen", redact::chat(chat_id));
+            return;
+        }
+        if crate::blocked(chat_id) {
+            debug!("Chat {} hat den Bot blockiert, Nachricht verworfen", redact::chat(chat_id));
+            return;
+        }
+        if self.tx.send(Queued::new(message, alerts, interactive)).is_err() {
+            warn!("Ausgangswarteschlange geschlossen, Nachricht an {} verworfen", redact::chat(chat_id));
+            return;
+        }
+        self.queued.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// Nächste Nachricht, die jetzt raus darf: zuerst Antworten auf Befehle,
+// dann Hintergrund-Nachrichten im Budget, jeweils die älteste eines Chats,
+// der gerade nicht gedrosselt ist oder auf einen neuen Versuch wartet. Sonst
+// der Zeitpunkt, ab dem eine darf.
+fn next_ready(waiting: &mut VecDeque<Queued>, not_before: &HashMap<i64, Instant>, bulk_ready: Instant, now: Instant) -> Result<Queued, Option<Instant>> {
+    let chat_ready = |chat_id: i64| not_before.get(&chat_id).copied().unwrap_or(now);
+    let mut earliest: Option<Instant> = None;
+    for interactive in [true, false] {
+        let mut seen = Vec::new();
+        for (index, queued) in waiting.iter().enumerate().filter(|(_, q)| q.interactive == interactive) {
+            let chat_id = queued.message.chat_id;
+            if seen.contains(&chat_id) {
+                continue; // nur die älteste je Chat, damit die Reihenfolge bleibt
+            }
+            seen.push(chat_id);
+            let ready = if interactive { chat_ready(chat_id) } else { chat_ready(chat_id).max(bulk_ready) };
+            if ready <= now {
+                return Ok(waiting.remove(index).expect("Index aus der Schleife"));
+            }
+            earliest = Some(earliest.map_or(ready, |e| e.min(ready)));
+        }
+    }
+    Err(earliest)
+}
+
+async fn run(
+    messenger: Arc<dyn Messenger>,
+    mut rx: mpsc::UnboundedReceiver<Queued>,
+    queued: Arc<AtomicIsize>,
+    deliveries: mpsc::UnboundedSender<Delivery>,
+    max_attempts: u32,
+) {
+    let global_interval = Duration::from_secs(1) / BULK_MESSAGES_PER_SECOND;
+    let mut last_global = Instant::now() - global_interval;
+    // Je Chat frühester nächster Versand: Abstand nach einer Zustellung,
+    // Pause vor einem neuen Versuch
+    let mut not_before: HashMap<i64, Instant> = HashMap::new();
+    let mut waiting: VecDeque<Queued> = VecDeque::new();
+    let mut open = true;
+
+    loop {
+        while let Ok(next) = rx.try_recv() {
+            waiting.push_back(next);
+        }
+        // Budget einhalten: global für Hintergrund-Nachrichten und je Chat
+        let mut next = match next_ready(&mut waiting, &not_before, last_global + global_interval, Instant::now()) {
+            Ok(next) => next,
+            Err(wake) => {
+                if !open && waiting.is_empty() {
+                    break;
+                }
+                tokio::select! {
+                    received = rx.recv(), if open => match received {
+                        Some(next) => waiting.push_back(next),
+                        None => open = false,
+                    },
+                    _ = tokio::time::sleep_until(wake.unwrap_or_else(Instant::now).into()), if wake.is_some() => {}
+                }
+                continue;
+            }
+        };
+
+        let wait = next.queued_at.elapsed();
+        let pending = (queued.load(Ordering::Relaxed) - 1).max(0);
+        if wait > SLOW_WAIT && next.attempts == 0 {
+            info!("Ausgangswarteschlange: {:?} Wartezeit, {} Nachrichten ausstehend", wait, pending);
+        }
+
+        let attempt = deliver(messenger.as_ref(), &mut next, max_attempts).await;
+        let now = Instant::now();
+        if !next.interactive {
+            last_global = now;
+        }
+        not_before.retain(|_, at| *at > now);
+        let result = match attempt {
+            Attempt::Done(result) => result,
+            // Bleibt die älteste seines Chats; die anderen Chats laufen weiter
+            Attempt::Retry(pause) => {
+                not_before.insert(next.message.chat_id, now + pause);
+                waiting.push_front(next);
+                continue;
+            }
+        };
+        telemetry::outbox_wait(next.queued_at.elapsed());
+        let Queued { message, alerts, reply_target, .. } = next;
+        match &result {
+            Ok(_) => {
+                deliveries.send(Delivery::Reached { chat_id: message.chat_id }).ok();
+            }
+            Err(SendError::Unreachable(reason)) => {
+                deliveries.send(Delivery::Unreachable { chat_id: message.chat_id, reason: reason.clone() }).ok();
+            }
+            Err(_) => {}
+        }
+        match &result {
+            Ok(_) => telemetry::alerts_sent(tenant_of(message.chat_id).as_deref(), alerts.len()),
+            Err(_) => telemetry::send_error(tenant_of(message.chat_id).as_deref()),
+        }
+        let message_id = result.ok().flatten();
+        if let (Some(message_id), None) = (reply_target, message.reply_to) {
+            deliveries.send(Delivery::ReplyTargetMissing { chat_id: message.chat_id, message_id }).ok();
+        }
+        if let Some(message_id) = message_id {
+            let delivered_at = chrono::Utc::now().timestamp();
+            for alert in alerts {
+                deliveries.send(Delivery::Alert(SentAlert { chat_id: message.chat_id, message_id, alert, delivered_at })).ok();
+            }
+        }
+        not_before.insert(message.chat_id, now + PER_CHAT_INTERVAL);
+        // Erst nach der Zustellung, damit `drain` auch auf diese wartet
+        queued.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// Ergebnis eines Zustellversuchs
+enum Attempt {
+    // Nachrichten-ID, wenn zugestellt und vom Messenger bekannt, sonst der
+    // letzte Fehler (bereits protokolliert)
+    Done(Result<Option<i32>, SendError>),
+    // Neuer Versuch frühestens nach dieser Pause
+    Retry(Duration),
+}
+
+// Gedrosselt wird so lange pausiert, wie Telegram vorgibt; andere
+// Fehlschläge (Netzwerk, Serverfehler) mit wachsender Pause wiederholt,
+// jeweils bis `max_attempts`. Fehlt die Nachricht, auf die geantwortet
+// werden soll, wird `reply_to` entfernt und gleich ohne Bezug erneut
+// gesendet (zählt nicht als Versuch).
+async fn deliver(messenger: &dyn Messenger, queued: &mut Queued, max_attempts: u32) -> Attempt {
+    loop {
+        queued.attempts += 1;
+        let attempt = queued.attempts;
+        let message = &mut queued.message;
+        let error = match messenger.send_returning_id(message).await {
+            Ok(id) => return Attempt::Done(Ok(id)),
+            Err(err) => err,
+        };
+        let result = match error {
+            SendError::RetryAfter(wait) if attempt < max_attempts => {
+                warn!("Telegram drosselt, warte {:?} vor Nachricht an {}", wait, redact::chat(message.chat_id));
+                return Attempt::Retry(wait);
+            }
+            SendError::Failed(err) if attempt < max_attempts => {
+                let backoff = queued.backoff;
+                warn!("Nachricht an {} fehlgeschlagen ({}), neuer Versuch in {:?}", redact::chat(message.chat_id), err, backoff);
+                queued.backoff = (backoff * 2).min(MAX_BACKOFF);
+                return Attempt::Retry(backoff);
+            }
+            SendError::ReplyTargetMissing if message.reply_to.is_some() => {
+                info!("Bezugsnachricht in {} fehlt, sende ohne Antwortbezug", redact::chat(message.chat_id));
+                message.reply_to = None;
+                queued.attempts -= 1;
+                continue;
+            }
+            err @ SendError::RetryAfter(wait) => {
+                error!("Nachricht an {} nach {} Versuchen verworfen: weiter gedrosselt ({:?})", redact::chat(message.chat_id), attempt, wait);
+                Err(err)
+            }
+            SendError::Unreachable(reason) => {
+                warn!("Nachricht an {} fehlgeschlagen: {}", redact::chat(message.chat_id), reason);
+                Err(SendError::Unreachable(reason))
+            }
+            SendError::Failed(err) => {
+                error!("Nachricht an {} nach {} Versuchen verworfen: {}", redact::chat(message.chat_id), attempt, err);
+                Err(SendError::Failed(err))
+            }
+            err @ SendError::ReplyTargetMissing => {
+                warn!("Nachricht an {} fehlgeschlagen: Bezugsnachricht fehlt", redact::chat(message.chat_id));
+                Err(err)
+            }
+        };
+        return Attempt::Done(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::BoxFuture;
+
+    // Meldet jede Zustellung mit Chat und Text
+    struct Recorder(mpsc::UnboundedSender<(i64, String)>);
+
+    impl Messenger for Recorder {
+        fn send<'a>(&'a self, message: &'a OutgoingMessage) -> BoxFuture<'a, Result<(), SendError>> {
+            self.0.send((message.chat_id, message.text.clone())).ok();
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    fn outbox() -> (Outbox, mpsc::UnboundedReceiver<(i64, String)>) {
+        let (sent_tx, sent_rx) = mpsc::unbounded_channel();
+        let (delivery_tx, _) = mpsc::unbounded_channel();
+        (Outbox::spawn(Arc::new(Recorder(sent_tx)), delivery_tx, 1), sent_rx)
+    }
+
+    async fn wait_for(sent: &mut mpsc::UnboundedReceiver<(i64, String)>, text: &str, within: Duration) -> bool {
+        tokio::time::timeout(within, async {
+            while let Some((_, sent)) = sent.recv().await {
+                if sent == text {
+                    return;
+                }
+            }
+        })
+        .await
+        .is_ok()
+    }
+
+    #[tokio::test]
+    async fn interactive_reply_overtakes_200_queued_alerts() {
+        let (outbox, mut sent) = outbox();
+        for n in 0..200 {
+            outbox.send(ChatId(n % 50), format!("Warnung {}", n));
+        }
+        outbox.send_interactive(ChatId(7), "Antwort", false);
+        assert!(wait_for(&mut sent, "Antwort", PER_CHAT_INTERVAL + Duration::from_millis(200)).await);
+        assert!(outbox.queued() > 150, "Warnungen sollten noch warten");
+    }
+
+    #[tokio::test]
+    async fn throttled_chat_does_not_hold_up_others() {
+        let (outbox, mut sent) = outbox();
+        for n in 0..10 {
+            outbox.send(ChatId(1), format!("Chat 1 Nr. {}", n));
+        }
+        outbox.send(ChatId(2), "Chat 2");
+        assert!(wait_for(&mut sent, "Chat 2", Duration::from_millis(500)).await);
+    }
+
+    #[tokio::test]
+    async fn order_within_a_chat_is_kept() {
+        let (outbox, mut sent) = outbox();
+        outbox.send(ChatId(1), "erste");
+        outbox.send(ChatId(2), "andere");
+        outbox.send(ChatId(1), "zweite");
+        let mut chat_1 = Vec::new();
+        while chat_1.len() < 2 {
+            let (chat, text) = tokio::time::timeout(Duration::from_secs(3), sent.recv()).await.unwrap().unwrap();
+            if chat == 1 {
+                chat_1.push(text);
+            }
+        }
+        assert_eq!(chat_1, ["erste", "zweite"]);
+    }
+
+    // Wie Recorder, scheitert aber je Chat erst mit den vorgegebenen Fehlern;
+    // zugestellte Nachrichten haben die ID 1
+    struct Flaky {
+        failures: std::sync::Mutex<HashMap<i64, VecDeque<SendError>>>,
+        sent: mpsc::UnboundedSender<(i64, String)>,
+    }
+
+    impl Messenger for Flaky {
+        fn send<'a>(&'a self, message: &'a OutgoingMessage) -> BoxFuture<'a, Result<(), SendError>> {
+            Box::pin(async move { self.send_returning_id(message).await.map(|_| ()) })
+        }
+
+        fn send_returning_id<'a>(&'a self, message: &'a OutgoingMessage) -> BoxFuture<'a, Result<Option<i32>, SendError>> {
+            let failure = self.failures.lock().unwrap().get_mut(&message.chat_id).and_then(VecDeque::pop_front);
+            if failure.is_none() {
+                self.sent.send((message.chat_id, message.text.clone())).ok();
+            }
+            Box::pin(async move { failure.map_or(Ok(Some(1)), Err) })
+        }
+    }
+
+    fn flaky(failures: Vec<(i64, Vec<SendError>)>, max_attempts: u32) -> (Outbox, mpsc::UnboundedReceiver<(i64, String)>) {
+        let (sent, sent_rx) = mpsc::unbounded_channel();
+        let (delivery_tx, _) = mpsc::unbounded_channel();
+        let failures = failures.into_iter().map(|(chat, errors)| (chat, errors.into())).collect();
+        (Outbox::spawn(Arc::new(Flaky { failures: std::sync::Mutex::new(failures), sent }), delivery_tx, max_attempts), sent_rx)
+    }
+
+    #[tokio::test]
+    async fn retry_after_does_not_hold_up_other_chats() {
+        let (outbox, mut sent) = flaky(vec![(1, vec![SendError::RetryAfter(Duration::from_secs(2))])], 3);
+        let start = Instant::now();
+        outbox.send(ChatId(1), "gedrosselt");
+        outbox.send(ChatId(2), "andere");
+        assert!(wait_for(&mut sent, "andere", Duration::from_millis(500)).await);
+        assert_eq!(outbox.queued(), 1);
+        assert!(wait_for(&mut sent, "gedrosselt", Duration::from_secs(3)).await);
+        assert!(start.elapsed() >= Duration::from_secs(2));
+        assert_eq!(outbox.drain(Duration::from_secs(1)).await, 0);
+    }
+
+    #[tokio::test]
+    async fn failed_sends_back_off_and_keep_their_order() {
+        let failures = vec![SendError::Failed("502".to_string()), SendError::Failed("502".to_string())];
+        let (outbox, mut sent) = flaky(vec![(1, failures)], 3);
+        let start = Instant::now();
+        outbox.send(ChatId(1), "erste");
+        outbox.send(ChatId(1), "zweite");
+        outbox.send(ChatId(2), "andere");
+        let mut order = Vec::new();
+        while order.len() < 3 {
+            let (chat, text) = tokio::time::timeout(Duration::from_secs(6), sent.recv()).await.unwrap().unwrap();
+            order.push((chat, text, start.elapsed()));
+        }
+        // Der andere Chat wartet nicht auf die Pausen von 1 s und 2 s
+        assert_eq!((order[0].0, order[0].1.as_str()), (2, "andere"));
+        assert!(order[0].2 < Duration::from_millis(500));
+        assert_eq!(order[1..].iter().map(|(_, text, _)| text.as_str()).collect::<Vec<_>>(), ["erste", "zweite"]);
+        assert!(order[1].2 >= FIRST_BACKOFF * 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let failures = vec![(1, vec![SendError::Failed("502".to_string()), SendError::Failed("502".to_string())])];
+        let (outbox, mut sent) = flaky(failures, 2);
+        outbox.send(ChatId(1), "verloren");
+        outbox.send(ChatId(1), "danach");
+        let (_, first) = tokio::time::timeout(Duration::from_secs(3), sent.recv()).await.unwrap().unwrap();
+        assert_eq!(first, "danach");
+        assert_eq!(outbox.drain(Duration::from_secs(1)).await, 0);
+    }
+
+    // Die Warnung behält den Zeitpunkt des Messwerts; zugestellt ist sie
+    // erst nach der Wiederholung
+    #[tokio::test]
+    async fn retried_alert_is_stamped_at_delivery() {
+        let (sent, _sent_rx) = mpsc::unbounded_channel();
+        let (delivery_tx, mut deliveries) = mpsc::unbounded_channel();
+        let failures = std::sync::Mutex::new([(1, VecDeque::from([SendError::Failed("502".to_string())]))].into_iter().collect());
+        let outbox = Outbox::spawn(Arc::new(Flaky { failures, sent }), delivery_tx, 3);
+        let before = chrono::Utc::now().timestamp();
+        let meta = AlertMeta { device_id: "sensor1".into(), keys: Vec::new(), owners: vec![1], observed_at: before - 600 };
+        outbox.send_alert(ChatId(1), "Warnung".into(), None, meta, false, false);
+
+        let alert = loop {
+            match tokio::time::timeout(Duration::from_secs(3), deliveries.recv()).await.unwrap().unwrap() {
+                Delivery::Alert(alert) => break alert,
+                _ => continue,
+            }
+        };
+        assert_eq!(alert.alert.observed_at, before - 600);
+        assert!(alert.delivered_at >= before + FIRST_BACKOFF.as_secs() as i64, "{} vs {}", alert.delivered_at, before);
+    }
+
+    #[tokio::test]
+    async fn closed_queue_counts_nothing() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        drop(rx);
+        let outbox = Outbox { tx, queued: Arc::new(AtomicIsize::new(0)) };
+        outbox.send(ChatId(1), "ins Leere");
+        assert_eq!(outbox.queued(), 0);
+        assert_eq!(outbox.drain(Duration::from_secs(5)).await, 0);
+    }
+}
@@ -0,0 +1,67 @@
+use chrono::{Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Frostwarnungen nur im Frühjahr, wenn schon Pflanzen draußen stehen
+pub const FROST_MONTHS: std::ops::RangeInclusive<u32> = 3..=5;
+// Ab dieser erwarteten Tiefsttemperatur droht Bodenfrost
+pub const FROST_BELOW: f64 = 2.0;
+// Abendlicher Verlauf, aus dem die Abkühlung geschätzt wird
+const TREND_WINDOW_SECONDS: i64 = 3 * 60 * 60;
+const MIN_SAMPLES: usize = 4;
+// Nachts flacht die Abkühlung ab; nur dieser Anteil des abendlichen
+// Gefälles wird bis zum Morgen fortgeschrieben
+const NIGHT_DAMPING: f64 = 0.5;
+
+// /watering: Erinnerung, wenn das Tagesmaximum an `days` Tagen in Folge
+// über `above` lag
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+pub struct Watering {
+    pub above: f64,
+    pub days: u32,
+}
+
+// Erwartete Tiefsttemperatur bis `until` aus den Rohwerten (Zeitstempel,
+// Wert): Gefälle der letzten Stunden per Ausgleichsgerade, gedämpft
+// fortgeschrieben. None bei zu wenigen Werten oder wenn es nicht abkühlt.
+pub fn projected_min(samples: &[(i64, f64)], now: i64, until: i64) -> Option<f64> {
+    let recent: Vec<(f64, f64)> =
+        samples.iter().filter(|(ts, _)| *ts > now - TREND_WINDOW_SECONDS && *ts <= now).map(|&(ts, value)| ((ts - now) as f64 / 3600.0, value)).collect();
+    if recent.len() < MIN_SAMPLES {
+        return None;
+    }
+    let n = recent.len() as f64;
+    let mean_t = recent.iter().map(|(t, _)| t).sum::<f64>() / n;
+    let mean_v = recent.iter().map(|(_, v)| v).sum::<f64>() / n;
+    let spread: f64 = recent.iter().map(|(t, _)| (t - mean_t).powi(2)).sum();
+    if spread == 0.0 {
+        return None;
+    }
+    let slope = recent.iter().map(|(t, v)| (t - mean_t) * (v - mean_v)).sum::<f64>() / spread; // °C je Stunde
+    if slope >= 0.0 {
+        return None;
+    }
+    let latest = recent.last()?.1;
+    let hours = (until - now).max(0) as f64 / 3600.0;
+    Some(latest + slope * hours * NIGHT_DAMPING)
+}
+
+// Höchstwert je Tag aus (Zeitstempel, Maximum) eines Zeitraums
+pub fn daily_max(points: impl IntoIterator<Item = (i64, f64)>, date_of: impl Fn(i64) -> NaiveDate) -> BTreeMap<NaiveDate, f64> {
+    let mut days: BTreeMap<NaiveDate, f64> = BTreeMap::new();
+    for (ts, max) in points {
+        let day = days.entry(date_of(ts)).or_insert(f64::NEG_INFINITY);
+        *day = day.max(max);
+    }
+    days
+}
+
+// Lag das Maximum an den letzten `days` Tagen bis einschließlich `today`
+// lückenlos über `above`? Ein Tag ohne Messwerte unterbricht die Folge.
+pub fn hot_streak(maxima: &BTreeMap<NaiveDate, f64>, today: NaiveDate, watering: Watering) -> bool {
+    watering.days > 0
+        && (0..watering.days as u64)
+            .filter_map(|back| today.checked_sub_days(Days::new(back)))
+            .all(|day| maxima.get(&day).is_some_and(|max| *max > watering.above))
+}
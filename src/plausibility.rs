@@ -0,0 +1,246 @@
+use crate::history::History;
+use crate::i18n::Text;
+use crate::sensor::SensorKind;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+// Grenzen einer Messgröße: außerhalb von low..=high oder mit einem Sprung von
+// mehr als `spike` zum letzten angenommenen Wert gilt ein Messwert als zweifelhaft
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    pub low: f64,
+    pub high: f64,
+    pub spike: Option<f64>,
+}
+
+// Eingebaut; PLAUSIBLE_RANGES ersetzt einzelne Typen
+const BUILTIN: &[(SensorKind, Limits)] = &[
+    (SensorKind::Temperature, Limits { low: -40.0, high: 80.0, spike: Some(10.0) }),
+    (SensorKind::Humidity, Limits { low: 0.0, high: 100.0, spike: Some(30.0) }),
+    (SensorKind::Pressure, Limits { low: 300.0, high: 1100.0, spike: Some(20.0) }),
+    (SensorKind::Co2, Limits { low: 0.0, high: 10000.0, spike: None }),
+    (SensorKind::Battery, Limits { low: 0.0, high: 100.0, spike: None }),
+];
+
+// "-40..80" oder mit Sprunggrenze "-40..80:10"
+impl FromStr for Limits {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (range, spike) = match s.split_once(':') {
+            Some((range, spike)) => (range, Some(spike)),
+            None => (s, None),
+        };
+        let number = |n: &str| n.trim().parse::<f64>().ok().filter(|n| n.is_finite());
+        let (low, high) = range
+            .split_once("..")
+            .and_then(|(low, high)| Some((number(low)?, number(high)?)))
+            .filter(|(low, high)| low < high)
+            .ok_or_else(|| format!("'{}' ist kein Bereich wie -40..80", range.trim()))?;
+        let spike = match spike {
+            Some(spike) => Some(number(spike).filter(|n| *n > 0.0).ok_or_else(|| format!("'{}' ist keine Sprunggrenze über 0", spike.trim()))?),
+            None => None,
+        };
+        Ok(Limits { low, high, spike })
+    }
+}
+
+// Warum ein Messwert zweifelhaft ist
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Doubt {
+    Invalid, // NaN oder unendlich, wird nie angenommen
+    OutOfRange { low: f64, high: f64 },
+    Spike { previous: f64 },
+}
+
+impl Doubt {
+    pub fn text(&self) -> Text {
+        match self {
+            Doubt::Invalid => Text::new("doubt_invalid", &[]),
+            Doubt::OutOfRange { low, high } => Text::new("doubt_out_of_range", &[("low", &low.to_string()), ("high", &high.to_string())]),
+            Doubt::Spike { previous } => Text::new("doubt_spike", &[("previous", &format!("{:.1}", previous))]),
+        }
+    }
+}
+
+impl fmt::Display for Doubt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.text().fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Screening {
+    Accept,
+    // Einzelner Ausreißer: weder auswerten noch speichern
+    Reject(Doubt),
+    // So oft in Folge zweifelhaft, dass der Wert wohl echt ist
+    Confirmed { doubt: Doubt, samples: u32 },
+    // Derselbe Messwert (Zeitstempel) wie beim letzten Mal, gleiches Urteil
+    Again { keep: bool },
+}
+
+impl Screening {
+    pub fn keep(self) -> bool {
+        match self {
+            Screening::Accept | Screening::Confirmed { .. } => true,
+            Screening::Reject(_) => false,
+            Screening::Again { keep } => keep,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Series {
+    accepted: Option<f64>,
+    doubted: u32,              // zweifelhafte Messwerte in Folge
+    last: Option<(i64, bool)>, // Zeitstempel und Urteil des letzten Messwerts
+}
+
+// Prüfstufe für eingehende Messwerte je Gerät und Messgröße. Ein Wert, der
+// `confirm_after` Mal in Folge zweifelhaft ist, wird angenommen; ab dann
+// vergleicht die Sprungprüfung mit ihm.
+pub struct PlausibilityFilter {
+    limits: HashMap<SensorKind, Limits>,
+    confirm_after: u32,
+    series: HashMap<(String, SensorKind), Series>,
+}
+
+impl PlausibilityFilter {
+    // confirm_after 0 schaltet die Prüfung ab
+    pub fn new(overrides: &[(SensorKind, Limits)], confirm_after: u32) -> PlausibilityFilter {
+        let limits = BUILTIN.iter().chain(overrides).cloned().collect();
+        PlausibilityFilter { limits, confirm_after, series: HashMap::new() }
+    }
+
+    // Letzte Werte aus dem Verlauf, damit die Sprungprüfung nach einem
+    // Neustart gleich greift
+    pub fn seed(&mut self, history: &History) {
+        for (device_id, sensor_type, _, value) in history.samples_since(i64::MIN) {
+            self.series.entry((device_id.to_string(), SensorKind::from(sensor_type))).or_default().accepted = Some(value);
+        }
+    }
+
+    pub fn check(&mut self, device_id: &str, kind: &SensorKind, timestamp: i64, value: f64) -> Screening {
+        if self.confirm_after == 0 {
+            return Screening::Accept;
+        }
+        let limits = self.limits.get(kind).copied();
+        let series = self.series.entry((device_id.to_string(), kind.clone())).or_default();
+        if let Some((ts, keep)) = series.last
+            && ts == timestamp
+        {
+            return Screening::Again { keep };
+        }
+        if !value.is_finite() {
+            series.last = Some((timestamp, false));
+            return Screening::Reject(Doubt::Invalid);
+        }
+        let doubt = match limits {
+            Some(Limits { low, high, .. }) if !(low..=high).contains(&value) => Some(Doubt::OutOfRange { low, high }),
+            Some(Limits { spike: Some(spike), .. }) => {
+                series.accepted.filter(|previous| (value - previous).abs() > spike).map(|previous| Doubt::Spike { previous })
+            }
+            _ => None,
+        };
+        let verdict = match doubt {
+            None => Screening::Accept,
+            Some(doubt) => {
+                series.doubted += 1;
+                if series.doubted >= self.confirm_after { Screening::Confirmed { doubt, samples: series.doubted } } else { Screening::Reject(doubt) }
+            }
+        };
+        if verdict.keep() {
+            series.accepted = Some(value);
+            series.doubted = 0;
+        }
+        series.last = Some((timestamp, verdict.keep()));
+        verdict
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPERATURE: SensorKind = SensorKind::Temperature;
+
+    // Prüft nacheinander, je Messwert eine Minute später
+    fn screen(filter: &mut PlausibilityFilter, device_id: &str, kind: &SensorKind, values: &[f64]) -> Vec<Screening> {
+        values.iter().enumerate().map(|(i, &value)| filter.check(device_id, kind, i as i64 * 60, value)).collect()
+    }
+
+    #[test]
+    fn out_of_range_is_rejected_until_it_repeats() {
+        let mut filter = PlausibilityFilter::new(&[], 3);
+        let doubt = Doubt::OutOfRange { low: -40.0, high: 80.0 };
+        assert_eq!(
+            screen(&mut filter, "sensor1", &TEMPERATURE, &[85.0, 85.0, 85.0, 21.0]),
+            [
+                Screening::Reject(doubt),
+                Screening::Reject(doubt),
+                Screening::Confirmed { doubt, samples: 3 },
+                Screening::Reject(Doubt::Spike { previous: 85.0 })
+            ]
+        );
+        assert_eq!(filter.check("sensor1", &TEMPERATURE, 600, f64::NAN), Screening::Reject(Doubt::Invalid));
+    }
+
+    // Die Grenzen selbst gehören zum Bereich, ein Sprung genau um die
+    // Sprunggrenze ist noch keiner
+    #[test]
+    fn edge_values_are_accepted() {
+        let mut filter = PlausibilityFilter::new(&[], 3);
+        assert_eq!(screen(&mut filter, "kalt", &TEMPERATURE, &[-40.0, -30.0]), [Screening::Accept, Screening::Accept]);
+        assert_eq!(screen(&mut filter, "heiß", &TEMPERATURE, &[80.0, 70.0]), [Screening::Accept, Screening::Accept]);
+        assert_eq!(screen(&mut filter, "trocken", &SensorKind::Humidity, &[0.0, 30.0]), [Screening::Accept, Screening::Accept]);
+        assert_eq!(screen(&mut filter, "nass", &SensorKind::Humidity, &[100.0]), [Screening::Accept]);
+        assert_eq!(screen(&mut filter, "luft", &SensorKind::Co2, &[400.0, 9000.0]), [Screening::Accept, Screening::Accept]);
+    }
+
+    // Ein einzelner Sprung fällt heraus, verglichen wird weiter mit dem
+    // letzten angenommenen Wert; hält der neue Wert an, gilt er
+    #[test]
+    fn jumps_are_measured_against_the_last_accepted_value() {
+        let mut filter = PlausibilityFilter::new(&[], 2);
+        let spike = Doubt::Spike { previous: 20.0 };
+        assert_eq!(
+            screen(&mut filter, "sensor1", &TEMPERATURE, &[20.0, 31.0, 29.0, 41.0, 41.0, 42.0]),
+            [
+                Screening::Accept,
+                Screening::Reject(spike),
+                Screening::Accept,
+                Screening::Reject(Doubt::Spike { previous: 29.0 }),
+                Screening::Confirmed { doubt: Doubt::Spike { previous: 29.0 }, samples: 2 },
+                Screening::Accept,
+            ]
+        );
+        // Derselbe Messwert noch einmal bekommt dasselbe Urteil
+        assert_eq!(filter.check("sensor1", &TEMPERATURE, 300, 42.0), Screening::Again { keep: true });
+        assert_eq!(filter.check("sensor1", &TEMPERATURE, 360, 60.0), Screening::Reject(Doubt::Spike { previous: 42.0 }));
+        assert_eq!(filter.check("sensor1", &TEMPERATURE, 360, 60.0), Screening::Again { keep: false });
+    }
+
+    #[test]
+    fn overrides_and_seeded_history_apply() {
+        let strict: Limits = "-10..40:2".parse().unwrap();
+        let mut filter = PlausibilityFilter::new(&[(TEMPERATURE, strict)], 3);
+        let mut history = History::default();
+        history.record("sensor1", "temperature", 0, 21.0);
+        filter.seed(&history);
+
+        assert_eq!(filter.check("sensor1", &TEMPERATURE, 60, 24.0), Screening::Reject(Doubt::Spike { previous: 21.0 }));
+        assert_eq!(filter.check("keller", &TEMPERATURE, 60, 45.0), Screening::Reject(Doubt::OutOfRange { low: -10.0, high: 40.0 }));
+        assert_eq!(PlausibilityFilter::new(&[], 0).check("keller", &TEMPERATURE, 60, f64::NAN), Screening::Accept);
+    }
+
+    #[test]
+    fn limits_parse() {
+        assert_eq!("-40..80".parse(), Ok(Limits { low: -40.0, high: 80.0, spike: None }));
+        assert_eq!(" 0 .. 100 : 30 ".parse(), Ok(Limits { low: 0.0, high: 100.0, spike: Some(30.0) }));
+        for invalid in ["80..-40", "5..5", "0..inf", "0..100:0", "0..100:x", "0-100"] {
+            assert!(invalid.parse::<Limits>().is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use plotters::prelude::*;
+use std::io::Cursor;
+
+const WIDTH: u32 = 960;
+const HEIGHT: u32 = 540;
+const FONT: &str = "sans-serif";
+
+// Eine Schwelle als waagrechte Linie: (Wert, Beschriftung, Maximum?)
+pub struct Line<'a> {
+    pub value: f64,
+    pub label: &'a str,
+    pub max: bool,
+}
+
+// Liniendiagramm als PNG aus (Zeitstempel, Wert). `x_label` beschriftet die
+// Zeitachse, damit die Zeitzone der Einstellungen gilt. Fehler kommen z.B.
+// von einer fehlenden Systemschrift; dann bleibt nur der Text.
+pub fn render(title: &str, unit: &str, points: &[(i64, f64)], lines: &[Line], x_label: &dyn Fn(i64) -> String) -> Result<Vec<u8>, String> {
+    let (Some(&(start, _)), Some(&(end, _))) = (points.first(), points.last()) else {
+        return Err("keine Messwerte".to_string());
+    };
+    // Wertebereich mit etwas Luft, Schwellen immer sichtbar
+    let values = points.iter().map(|(_, v)| *v).chain(lines.iter().map(|l| l.value));
+    let (low, high) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    let pad = ((high - low) * 0.1).max(0.5);
+    let (low, high) = (low - pad, high + pad);
+    let end = end.max(start + 1);
+
+    let mut buffer = vec![0u8; (WIDTH * HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&WHITE).map_err(|e| e.to_string())?;
+        let mut chart = ChartBuilder::on(&root)
+            .caption(title, (FONT, 26))
+            .margin(16)
+            .x_label_area_size(40)
+            .y_label_area_size(64)
+            .build_cartesian_2d(start..end, low..high)
+            .map_err(|e| e.to_string())?;
+        chart
+            .configure_mesh()
+            .x_labels(6)
+            .x_label_formatter(&|ts| x_label(*ts))
+            .y_label_formatter(&|v| format!("{:.1}", v))
+            .y_desc(unit)
+            .label_style((FONT, 16))
+            .draw()
+            .map_err(|e| e.to_string())?;
+        chart.draw_series(LineSeries::new(points.iter().copied(), BLUE.stroke_width(2))).map_err(|e| e.to_string())?;
+        for line in lines {
+            let color = if line.max { RED } else { CYAN };
+            chart
+                .draw_series(LineSeries::new([(start, line.value), (end, line.value)], color.stroke_width(2)))
+                .map_err(|e| e.to_string())?
+                .label(format!("{} {:.1} {}", line.label, line.value, unit))
+                .legend(move |(x, y)| PathElement::new([(x, y), (x + 20, y)], color.stroke_width(2)));
+        }
+        if !lines.is_empty() {
+            chart.configure_series_labels().background_style(WHITE.mix(0.8)).border_style(BLACK).label_font((FONT, 16)).draw().map_err(|e| e.to_string())?;
+        }
+        root.present().map_err(|e| e.to_string())?;
+    }
+
+    let image = image::RgbImage::from_raw(WIDTH, HEIGHT, buffer).ok_or("Bildpuffer hat die falsche Größe")?;
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), image::ImageOutputFormat::Png).map_err(|e| e.to_string())?;
+    Ok(png)
+}
+
+// Mehrere Messreihen übereinander, je Reihe (Beschriftung, Punkte) in
+// eigener Farbe mit Legende; z.B. die Temperaturen aller Räume einer Woche
+pub fn render_overlay(title: &str, unit: &str, series: &[(String, Vec<(i64, f64)>)], x_label: &dyn Fn(i64) -> String) -> Result<Vec<u8>, String> {
+    let points = || series.iter().flat_map(|(_, points)| points.iter().copied());
+    let (Some(start), Some(end)) = (points().map(|(ts, _)| ts).min(), points().map(|(ts, _)| ts).max()) else {
+        return Err("keine Messwerte".to_string());
+    };
+    let (low, high) = points().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (_, v)| (lo.min(v), hi.max(v)));
+    let pad = ((high - low) * 0.1).max(0.5);
+    let (low, high) = (low - pad, high + pad);
+    let end = end.max(start + 1);
+
+    let mut buffer = vec![0u8; (WIDTH * HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&WHITE).map_err(|e| e.to_string())?;
+        let mut chart = ChartBuilder::on(&root)
+            .caption(title, (FONT, 26))
+            .margin(16)
+            .x_label_area_size(40)
+            .y_label_area_size(64)
+            .build_cartesian_2d(start..end, low..high)
+            .map_err(|e| e.to_string())?;
+        chart
+            .configure_mesh()
+            .x_labels(8)
+            .x_label_formatter(&|ts| x_label(*ts))
+            .y_label_formatter(&|v| format!("{:.1}", v))
+            .y_desc(unit)
+            .label_style((FONT, 16))
+            .draw()
+            .map_err(|e| e.to_string())?;
+        for (index, (label, points)) in series.iter().enumerate() {
+            let color = Palette99::pick(index).to_rgba();
+            chart
+                .draw_series(LineSeries::new(points.iter().copied(), color.stroke_width(2)))
+                .map_err(|e| e.to_string())?
+                .label(label.as_str())
+                .legend(move |(x, y)| PathElement::new([(x, y), (x + 20, y)], color.stroke_width(2)));
+        }
+        chart.configure_series_labels().background_style(WHITE.mix(0.8)).border_style(BLACK).label_font((FONT, 16)).draw().map_err(|e| e.to_string())?;
+        root.present().map_err(|e| e.to_string())?;
+    }
+
+    let image = image::RgbImage::from_raw(WIDTH, HEIGHT, buffer).ok_or("Bildpuffer hat die falsche Größe")?;
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), image::ImageOutputFormat::Png).map_err(|e| e.to_string())?;
+    Ok(png)
+}
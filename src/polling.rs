@@ -0,0 +1,43 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use tokio::time::{Duration, Instant};
+
+// Nächste reguläre Abfrage je Quelle (Nummer in der Liste der Quellen), die
+// früheste oben. Jede Quelle hat ihr eigenes Intervall; es wird bei jeder
+// Abfrage neu gelesen, damit /set-interval gleich gilt.
+pub struct PollSchedule {
+    due: BinaryHeap<Reverse<(Instant, usize)>>,
+    last: Vec<Option<Instant>>,
+}
+
+impl PollSchedule {
+    // Beim Start sind alle Quellen sofort fällig
+    pub fn new(sources: usize, now: Instant) -> PollSchedule {
+        PollSchedule { due: (0..sources).map(|index| Reverse((now, index))).collect(), last: vec![None; sources] }
+    }
+
+    // Fällige Quellen herausnehmen und mit ihrem Intervall neu einplanen
+    pub fn take_due(&mut self, now: Instant, interval: impl Fn(usize) -> Duration) -> Vec<usize> {
+        let mut taken = Vec::new();
+        while let Some(&Reverse((at, index))) = self.due.peek()
+            && at <= now
+        {
+            self.due.pop();
+            taken.push(index);
+        }
+        for &index in &taken {
+            self.last[index] = Some(now);
+            self.due.push(Reverse((now + interval(index), index)));
+        }
+        taken
+    }
+
+    pub fn next_due(&self) -> Option<Instant> {
+        self.due.peek().map(|Reverse((at, _))| *at)
+    }
+
+    // Nach geänderten Intervallen alle Termine ab der letzten Abfrage neu setzen
+    pub fn reschedule(&mut self, now: Instant, interval: impl Fn(usize) -> Duration) {
+        self.due = self.last.iter().enumerate().map(|(index, last)| Reverse((last.map_or(now, |at| at + interval(index)), index))).collect();
+    }
+}
@@ -0,0 +1,83 @@
+use crate::i18n::{self, Lang};
+use crate::sensor::ThresholdKey;
+use crate::storage;
+use crate::thresholds::TimeWindow;
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+pub const MAX_PROFILES: usize = 10;
+const MAX_NAME_CHARS: usize = 32;
+
+pub fn usage(lang: Lang) -> &'static str {
+    i18n::message(lang, "profile_usage")
+}
+
+// Benannter Satz von Schwellen (/profile). Solange er aktiv ist, gelten
+// seine Werte statt der gewöhnlichen Schwellen; None schaltet eine Schwelle
+// so lange ab. Was er nicht enthält, bleibt wie es ist.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+#[serde(default)]
+pub struct Profile {
+    #[serde(with = "storage::keyed_map")]
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::keyed(proptest::prelude::any::<Option<f64>>())"))]
+    pub thresholds: HashMap<(String, ThresholdKey), Option<f64>>,
+    pub window: Option<TimeWindow>, // in diesem Zeitfenster automatisch aktiv
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+#[serde(default)]
+pub struct Profiles {
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::few::<(String, Profile), _>()"))]
+    pub profiles: BTreeMap<String, Profile>,
+    pub manual: Option<String>, // mit /profile activate gewählt, geht den Zeitfenstern vor
+    pub active: Option<String>, // gerade angewendet; umgeschaltet wird mit `switch`
+}
+
+impl Profiles {
+    // Profil, das jetzt gelten soll: das gewählte, sonst das mit passendem Zeitfenster
+    pub fn due(&self, local: NaiveTime) -> Option<&str> {
+        if let Some(name) = self.manual.as_deref().filter(|name| self.profiles.contains_key(*name)) {
+            return Some(name);
+        }
+        self.profiles.iter().find(|(_, profile)| profile.window.is_some_and(|w| w.contains(local))).map(|(name, _)| name.as_str())
+    }
+
+    // Auf das fällige Profil umschalten; liefert das vorige, wenn sich etwas ändert
+    pub fn switch(&mut self, local: NaiveTime) -> Option<Option<String>> {
+        let due = self.due(local).map(str::to_string);
+        (due != self.active).then(|| std::mem::replace(&mut self.active, due))
+    }
+
+    pub fn active(&self) -> Option<(&str, &Profile)> {
+        let name = self.active.as_deref()?;
+        Some((name, self.profiles.get(name)?))
+    }
+
+    // Wert einer Schwelle laut aktivem Profil: None, wenn es sie nicht
+    // regelt, Some(None), wenn es sie abschaltet
+    pub fn override_for(&self, key: &(String, ThresholdKey)) -> Option<(&str, Option<f64>)> {
+        let (name, profile) = self.active()?;
+        profile.thresholds.get(key).map(|value| (name, *value))
+    }
+
+    // Zeitfenster eines anderen Profils, das sich mit `window` überschneidet
+    pub fn overlapping(&self, name: &str, window: &TimeWindow) -> Option<&str> {
+        self.profiles
+            .iter()
+            .find(|(other, profile)| other.as_str() != name && profile.window.is_some_and(|w| w.overlaps(window)))
+            .map(|(other, _)| other.as_str())
+    }
+}
+
+// Kleinbuchstaben, Ziffern, - und _; "auto" ist für /profile activate vergeben
+pub fn parse_name(name: &str, lang: Lang) -> Result<String, String> {
+    let name = name.trim().to_lowercase();
+    let valid = !name.is_empty() && name.chars().count() <= MAX_NAME_CHARS && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    if !valid || name == "auto" {
+        return Err(i18n::message_with(lang, "profile_name_invalid", &[("name", &name), ("max", &MAX_NAME_CHARS.to_string())]));
+    }
+    Ok(name)
+}
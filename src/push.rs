@@ -0,0 +1,23 @@
+// Messwerte, die Geräte selbst schicken (POST /readings, MQTT). Sie laufen beim
+// nächsten Durchlauf der Überwachung wie abgefragte Messwerte durch Verlauf,
+// Rekorde und Schwellen; der Durchlauf startet sofort.
+use crate::SensorData;
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+static QUEUE: Mutex<Vec<SensorData>> = Mutex::new(Vec::new());
+static WAKE: Notify = Notify::const_new();
+
+#[cfg(any(feature = "http-api", feature = "mqtt"))]
+pub fn push(readings: Vec<SensorData>) {
+    QUEUE.lock().unwrap_or_else(|e| e.into_inner()).extend(readings);
+    WAKE.notify_one();
+}
+
+pub fn take() -> Vec<SensorData> {
+    std::mem::take(&mut *QUEUE.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+pub async fn notified() {
+    WAKE.notified().await
+}
@@ -0,0 +1,34 @@
+use crate::sensor::SensorKind;
+
+// Anzeige einer bekannten Messgröße. Ein neuer Typ braucht nur einen Eintrag
+// in QUANTITIES und, ohne feste Bezeichnung, einen Text in i18n.
+pub struct Quantity {
+    pub sensor_type: &'static str,
+    pub unit: &'static str,
+    pub label: Option<&'static str>, // in allen Sprachen gleich, sonst der Text zu `sensor_type`
+    pub short: Option<&'static str>, // Spaltenkopf der Tabelle in /status
+    pub icon: Option<&'static str>,  // statt 📍 in /status
+    pub decimals: usize,
+}
+
+const fn quantity(sensor_type: &'static str, unit: &'static str) -> Quantity {
+    Quantity { sensor_type, unit, label: None, short: None, icon: None, decimals: 1 }
+}
+
+pub const QUANTITIES: &[Quantity] = &[
+    Quantity { short: Some("Temp."), ..quantity("temperature", "°C") },
+    Quantity { short: Some("Feuchte"), ..quantity("humidity", "%") },
+    quantity("pressure", "hPa"),
+    Quantity { label: Some("CO₂"), ..quantity("co2", "ppm") },
+    quantity("dewpoint", "°C"),
+    quantity("heatindex", "°C"),
+    quantity("abshumidity", "g/m³"),
+    Quantity { short: Some("Batt."), icon: Some("🔋"), decimals: 0, ..quantity("battery", "%") },
+    Quantity { icon: Some("⚡"), decimals: 2, ..quantity("voltage", "V") },
+];
+
+// Groß-/Kleinschreibung egal wie bei SensorKind
+pub fn lookup(sensor_type: &str) -> Option<&'static Quantity> {
+    let kind = SensorKind::from(sensor_type);
+    QUANTITIES.iter().find(|quantity| quantity.sensor_type == kind.as_str())
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+// Längstes Zeitfenster für /rate; Rohwerte gibt es ohnehin nur 7 Tage
+pub const MAX_WINDOW_MINUTES: i64 = 24 * 60;
+
+// /rate: Alarm, wenn sich der Wert innerhalb von `minutes` um mindestens
+// `delta` ändert. Negativ = Abfall (offenes Fenster), positiv = Anstieg.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+pub struct RateRule {
+    pub delta: f64,
+    pub minutes: i64,
+}
+
+impl RateRule {
+    pub fn violated(&self, change: f64) -> bool {
+        if self.delta < 0.0 { change <= self.delta } else { change >= self.delta }
+    }
+}
+
+// Änderung vom Messwert, der `minutes` vor dem neuesten am nächsten liegt,
+// bis zum neuesten. None, solange es dafür keinen Wert gibt: Er muss älter
+// als der neueste sein und höchstens ein halbes Fenster vom Ziel abweichen,
+// sonst vergleicht man über eine Lücke hinweg.
+pub fn change(samples: &VecDeque<(i64, f64)>, minutes: i64) -> Option<f64> {
+    let &(newest_ts, newest) = samples.back()?;
+    let window = minutes * 60;
+    let target = newest_ts - window;
+    let (_, before) = samples
+        .iter()
+        .rev()
+        .skip(1)
+        .take_while(|(ts, _)| *ts >= target - window / 2)
+        .min_by_key(|(ts, _)| (ts - target).abs())
+        .filter(|(ts, _)| (ts - target).abs() <= window / 2)?;
+    Some(newest - before)
+}
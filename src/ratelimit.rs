@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// So oft werden volle, also ruhende Chats aus der Tabelle entfernt
+const CLEANUP_EVERY: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    Allowed,
+    Warn,   // erster Befehl über der Grenze: einmal "bitte langsamer"
+    Ignore, // jeder weitere, bis wieder ein Befehl erlaubt ist
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    warned: bool,
+}
+
+/// Befehle je Chat als Token-Bucket: `per_minute` Befehle am Stück, danach
+/// einer je 60 / `per_minute` Sekunden.
+pub struct RateLimiter {
+    per_minute: u32,
+    buckets: HashMap<i64, Bucket>,
+    cleaned: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(per_minute: u32) -> RateLimiter {
+        RateLimiter { per_minute, buckets: HashMap::new(), cleaned: Instant::now() }
+    }
+
+    fn capacity(&self) -> f64 {
+        self.per_minute as f64
+    }
+
+    // Aufgefüllter Stand eines Buckets zum Zeitpunkt `now`
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        (bucket.tokens + elapsed * self.capacity() / 60.0).min(self.capacity())
+    }
+
+    pub fn check(&mut self, chat_id: i64, now: Instant) -> Verdict {
+        if now.saturating_duration_since(self.cleaned) >= CLEANUP_EVERY {
+            self.cleanup(now);
+        }
+        let capacity = self.capacity();
+        let tokens = self.buckets.get(&chat_id).map_or(capacity, |bucket| self.refilled(bucket, now));
+        let bucket = self.buckets.entry(chat_id).or_insert(Bucket { tokens, updated: now, warned: false });
+        bucket.tokens = tokens;
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            bucket.warned = false;
+            Verdict::Allowed
+        } else if bucket.warned {
+            Verdict::Ignore
+        } else {
+            bucket.warned = true;
+            Verdict::Warn
+        }
+    }
+
+    // Chats, deren Bucket wieder voll ist, verhalten sich wie unbekannte
+    fn cleanup(&mut self, now: Instant) {
+        let capacity = self.capacity();
+        let full: Vec<i64> = self.buckets.iter().filter(|(_, bucket)| self.refilled(bucket, now) >= capacity).map(|(&chat_id, _)| chat_id).collect();
+        for chat_id in full {
+            self.buckets.remove(&chat_id);
+        }
+        self.cleaned = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn burst_then_one_warning_then_silence() {
+        let mut limiter = RateLimiter::new(5);
+        let now = Instant::now();
+        let verdicts: Vec<Verdict> = (0..8).map(|_| limiter.check(1, now)).collect();
+        assert_eq!(verdicts[..5], [Verdict::Allowed; 5]);
+        assert_eq!(verdicts[5..], [Verdict::Warn, Verdict::Ignore, Verdict::Ignore]);
+    }
+
+    #[test]
+    fn refills_one_command_per_interval() {
+        let mut limiter = RateLimiter::new(6);
+        let start = Instant::now();
+        for _ in 0..6 {
+            limiter.check(1, start);
+        }
+        assert_eq!(limiter.check(1, start + Duration::from_secs(5)), Verdict::Warn);
+        assert_eq!(limiter.check(1, start + Duration::from_secs(10)), Verdict::Allowed);
+        assert_eq!(limiter.check(1, start + Duration::from_secs(11)), Verdict::Warn);
+    }
+
+    #[test]
+    fn chats_are_limited_separately() {
+        let mut limiter = RateLimiter::new(1);
+        let now = Instant::now();
+        assert_eq!(limiter.check(1, now), Verdict::Allowed);
+        assert_eq!(limiter.check(1, now), Verdict::Warn);
+        assert_eq!(limiter.check(2, now), Verdict::Allowed);
+    }
+
+    #[test]
+    fn idle_chats_are_cleaned_up() {
+        let mut limiter = RateLimiter::new(5);
+        let start = Instant::now();
+        limiter.check(1, start);
+        limiter.check(2, start + CLEANUP_EVERY);
+        assert!(!limiter.buckets.contains_key(&1));
+        assert!(limiter.buckets.contains_key(&2));
+    }
+}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Zuletzt fehlgeschlagene Zustellung an einen Chat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Failure {
+    pub since: DateTime<Utc>, // erster Fehlschlag seit der letzten Zustellung
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatReach {
+    #[serde(default)]
+    pub reached: Option<DateTime<Utc>>, // letzte erfolgreiche Zustellung
+    #[serde(default)]
+    pub failure: Option<Failure>,
+}
+
+// Ob der Bot einen Chat erreichen kann, aus den tatsächlichen Zustellungen
+// von Warnungen, Berichten und Testnachrichten. Fehler, die sich nur im Chat
+// selbst beheben lassen (blockiert, entfernt), zählen; Drosselung und
+// Netzwerkfehler nicht.
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Reachability {
+    chats: BTreeMap<i64, ChatReach>,
+}
+
+impl Reachability {
+    // Noch nie erfolgreich zugestellt oder danach gescheitert
+    pub fn needs_check(&self, chat_id: i64) -> bool {
+        self.chats.get(&chat_id).is_none_or(|chat| chat.reached.is_none() || chat.failure.is_some())
+    }
+
+    pub fn failure(&self, chat_id: i64) -> Option<&Failure> {
+        self.chats.get(&chat_id).and_then(|chat| chat.failure.as_ref())
+    }
+
+    // true, wenn sich damit etwas ändert, das gespeichert werden sollte. Der
+    // Zeitpunkt je Nachricht allein zählt nicht, sonst würde jede Zustellung
+    // die Datei schreiben.
+    pub fn reached(&mut self, chat_id: i64, now: DateTime<Utc>) -> bool {
+        let chat = self.chats.entry(chat_id).or_default();
+        let changed = chat.reached.is_none() || chat.failure.is_some();
+        chat.reached = Some(now);
+        chat.failure = None;
+        changed
+    }
+
+    pub fn failed(&mut self, chat_id: i64, reason: &str, now: DateTime<Utc>) -> bool {
+        let chat = self.chats.entry(chat_id).or_default();
+        match &mut chat.failure {
+            Some(failure) if failure.reason == reason => false,
+            Some(failure) => {
+                failure.reason = reason.to_string();
+                true
+            }
+            None => {
+                chat.failure = Some(Failure { since: now, reason: reason.to_string() });
+                true
+            }
+        }
+    }
+
+    // Chats, die seit vor `before` mit diesem Grund scheitern
+    pub fn failing_since(&self, reason: &str, before: DateTime<Utc>) -> Vec<i64> {
+        self.chats
+            .iter()
+            .filter(|(_, chat)| chat.failure.as_ref().is_some_and(|failure| failure.reason == reason && failure.since < before))
+            .map(|(&chat_id, _)| chat_id)
+            .collect()
+    }
+
+    // Chat vergessen (/forgetme)
+    pub fn forget(&mut self, chat_id: i64) -> bool {
+        self.chats.remove(&chat_id).is_some()
+    }
+}
@@ -0,0 +1,133 @@
+use log::{info, warn};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+use teloxide::prelude::*;
+use teloxide::stop::StopToken;
+use teloxide::types::{AllowedUpdate, UpdateKind};
+use teloxide::update_listeners::{AsUpdateStream, Polling, UpdateListener};
+
+// 🔇 auf einer Warnung schaltet deren Schwellen so lange stumm
+pub const SNOOZE_SECONDS: i64 = 60 * 60;
+
+// Update-Typen, die der Bot von Telegram anfordert
+const ALLOWED_UPDATES: [&str; 4] = ["message", "callback_query", "inline_query", "message_reaction"];
+
+// Reaktion auf eine Nachricht (Bot-API "message_reaction"). teloxide 0.12
+// kennt diesen Typ noch nicht und reicht ihn roh als UpdateKind::Error durch.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReactionUpdate {
+    pub chat: ReactionChat,
+    pub message_id: i32,
+    pub user: Option<ReactionUser>,
+    // anonyme Gruppen-Admins reagieren im Namen des Chats
+    pub actor_chat: Option<ReactionChat>,
+    #[serde(default)]
+    old_reaction: Vec<ReactionType>,
+    #[serde(default)]
+    new_reaction: Vec<ReactionType>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReactionChat {
+    pub id: i64,
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReactionUser {
+    pub id: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+enum ReactionType {
+    #[serde(rename = "emoji")]
+    Emoji { emoji: String },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reaction {
+    Acknowledge, // 👍 oder ✅, wie /ack
+    Snooze,      // 🔇, eine Stunde
+}
+
+pub fn parse(update: &Update) -> Option<ReactionUpdate> {
+    let UpdateKind::Error(value) = &update.kind else { return None };
+    serde_json::from_value(value.get("message_reaction")?.clone()).ok()
+}
+
+impl ReactionUpdate {
+    // Nur neu gesetzte Reaktionen zählen, nicht das Zurücknehmen
+    pub fn added(&self) -> Vec<Reaction> {
+        self.new_reaction
+            .iter()
+            .filter(|reaction| !self.old_reaction.contains(reaction))
+            .filter_map(|reaction| match reaction {
+                ReactionType::Emoji { emoji } => match emoji.as_str() {
+                    "👍" | "✅" => Some(Reaction::Acknowledge),
+                    "🔇" => Some(Reaction::Snooze),
+                    _ => None,
+                },
+                ReactionType::Other => None,
+            })
+            .collect()
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.chat.kind == "private"
+    }
+}
+
+// Polling wie `polling_default`, aber mit Reaktionen. Der Dispatcher würde
+// seine eigene Liste erlaubter Updates setzen, in der Reaktionen fehlen; daher
+// wird sie einmal direkt bei Telegram gesetzt und danach nicht mehr mitgeschickt.
+// Klappt das nicht, läuft der Bot ohne Reaktionen weiter.
+pub async fn listener(bot: Bot) -> impl UpdateListener<Err = teloxide::RequestError> {
+    let polling = Polling::builder(bot.clone()).timeout(Duration::from_secs(10)).delete_webhook().await.build();
+    match enable(&bot).await {
+        Ok(()) => info!("Reaktionen auf Warnungen aktiviert"),
+        Err(err) => warn!("Reaktionen auf Warnungen nicht verfügbar: {}", err),
+    }
+    KeepAllowedUpdates(polling)
+}
+
+// getUpdates ohne Offset bestätigt nichts, setzt aber die erlaubten Updates
+async fn enable(bot: &Bot) -> Result<(), String> {
+    let mut url = bot.api_url();
+    url.set_path(&format!("bot{}/getUpdates", bot.token()));
+    let body = json!({ "limit": 1, "timeout": 0, "allowed_updates": ALLOWED_UPDATES });
+    let response = reqwest::Client::new().post(url).json(&body).send().await.map_err(|err| err.without_url().to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Telegram antwortet mit {}", response.status()));
+    }
+    Ok(())
+}
+
+struct KeepAllowedUpdates<L>(L);
+
+impl<L: UpdateListener> UpdateListener for KeepAllowedUpdates<L> {
+    type Err = L::Err;
+
+    fn stop_token(&mut self) -> StopToken {
+        self.0.stop_token()
+    }
+
+    fn hint_allowed_updates(&mut self, _hint: &mut dyn Iterator<Item = AllowedUpdate>) {}
+
+    fn timeout_hint(&self) -> Option<Duration> {
+        self.0.timeout_hint()
+    }
+}
+
+impl<'a, L: AsUpdateStream<'a>> AsUpdateStream<'a> for KeepAllowedUpdates<L> {
+    type StreamErr = L::StreamErr;
+    type Stream = L::Stream;
+
+    fn as_stream(&'a mut self) -> Self::Stream {
+        self.0.as_stream()
+    }
+}
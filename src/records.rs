@@ -0,0 +1,148 @@
+use crate::history::History;
+use crate::sensor::SensorKind;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+pub const USAGE: &str = "Verwendung: rebuild-records";
+
+/// Extremwert mit Zeitpunkt der Messung
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Extreme {
+    pub value: f64,
+    pub timestamp: i64,
+    /// Hat einen früheren Rekord abgelöst (nicht nur der erste Wert)
+    #[serde(default)]
+    pub broke_record: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Extremes {
+    pub min: Option<Extreme>,
+    pub max: Option<Extreme>,
+}
+
+impl Extremes {
+    // true, wenn ein bestehender Rekord gebrochen wurde
+    fn observe(&mut self, timestamp: i64, value: f64) -> (bool, bool) {
+        let lower = self.min.is_none_or(|min| value < min.value);
+        let higher = self.max.is_none_or(|max| value > max.value);
+        let broke_min = lower && self.min.is_some();
+        let broke_max = higher && self.max.is_some();
+        if lower {
+            self.min = Some(Extreme { value, timestamp, broke_record: broke_min });
+        }
+        if higher {
+            self.max = Some(Extreme { value, timestamp, broke_record: broke_max });
+        }
+        (broke_min, broke_max)
+    }
+}
+
+/// Rekorde eines (Gerät, Typ): insgesamt und je Kalenderjahr
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SeriesRecords {
+    pub all_time: Extremes,
+    pub years: BTreeMap<i32, Extremes>,
+}
+
+// Tiefst- und Höchstwerte je (Gerät, Typ), fortlaufend aus der Überwachung.
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Records {
+    #[serde(with = "crate::storage::keyed_map")]
+    series: HashMap<(String, String), SeriesRecords>,
+}
+
+// Neuer Rekord über alle Jahre
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewRecord {
+    pub device_id: String,
+    pub sensor_type: String,
+    pub low: bool,
+    pub extreme: Extreme,
+}
+
+// Physikalisch unmögliche Werte (Sensorfehler) dürfen keinen Rekord setzen,
+// sonst steht er für immer. Die Prüfstufe (plausibility) nimmt sie an, wenn
+// sie lange genug anhalten; ein Rekord wird daraus trotzdem nicht.
+pub fn plausible(sensor_type: &str, value: f64) -> bool {
+    value.is_finite()
+        && match SensorKind::from(sensor_type) {
+            SensorKind::Temperature => (-40.0..=80.0).contains(&value),
+            SensorKind::Humidity => (0.0..=100.0).contains(&value),
+            _ => true,
+        }
+}
+
+impl Records {
+    // Messwert einrechnen; `year` ist das Kalenderjahr in der Zeitzone des Bots
+    pub fn observe(&mut self, device_id: &str, sensor_type: &str, timestamp: i64, value: f64, year: i32) -> Vec<NewRecord> {
+        if !plausible(sensor_type, value) {
+            return Vec::new();
+        }
+        let series = self.series.entry((device_id.to_string(), sensor_type.to_string())).or_default();
+        series.years.entry(year).or_default().observe(timestamp, value);
+        let (broke_min, broke_max) = series.all_time.observe(timestamp, value);
+
+        let new = |low: bool, extreme: Option<Extreme>| NewRecord {
+            device_id: device_id.to_string(),
+            sensor_type: sensor_type.to_string(),
+            low,
+            extreme: extreme.unwrap_or(Extreme { value, timestamp, broke_record: true }),
+        };
+        let mut records = Vec::new();
+        if broke_min {
+            records.push(new(true, series.all_time.min));
+        }
+        if broke_max {
+            records.push(new(false, series.all_time.max));
+        }
+        records
+    }
+
+    // Neu aufbauen aus dem Verlauf (reicht nur so weit zurück wie dieser).
+    // Dabei gilt nichts als neu gebrochener Rekord.
+    pub fn from_history(history: &History, year: impl Fn(i64) -> i32) -> Records {
+        let mut records = Records::default();
+        // Verdichtete Intervalle tragen ihren Tiefst- und Höchstwert bei
+        for (device_id, sensor_type, bucket) in history.points_since(i64::MIN) {
+            for value in [bucket.min, bucket.max] {
+                records.observe(device_id, sensor_type, bucket.start, value, year(bucket.start));
+            }
+        }
+        for series in records.series.values_mut() {
+            for extremes in std::iter::once(&mut series.all_time).chain(series.years.values_mut()) {
+                for extreme in [&mut extremes.min, &mut extremes.max].into_iter().flatten() {
+                    extreme.broke_record = false;
+                }
+            }
+        }
+        records
+    }
+
+    pub fn get(&self, device_id: &str, sensor_type: &str) -> Option<&SeriesRecords> {
+        self.series.get(&(device_id.to_string(), sensor_type.to_string()))
+    }
+
+    // (Gerät, Typ) mit Rekorden, sortiert
+    pub fn keys(&self) -> Vec<(&str, &str)> {
+        let mut keys: Vec<(&str, &str)> = self.series.keys().map(|(d, t)| (d.as_str(), t.as_str())).collect();
+        keys.sort();
+        keys
+    }
+
+    // Rekorde über alle Jahre, die seit `since` gebrochen wurden
+    pub fn broken_since(&self, since: i64) -> Vec<NewRecord> {
+        let mut broken = Vec::new();
+        for ((device_id, sensor_type), series) in &self.series {
+            for (low, extreme) in [(true, series.all_time.min), (false, series.all_time.max)] {
+                if let Some(extreme) = extreme.filter(|e| e.broke_record && e.timestamp >= since) {
+                    broken.push(NewRecord { device_id: device_id.clone(), sensor_type: sensor_type.clone(), low, extreme });
+                }
+            }
+        }
+        broken.sort_by_key(|record| record.extreme.timestamp);
+        broken
+    }
+}
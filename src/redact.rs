@@ -0,0 +1,63 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::OnceLock;
+
+// Zufälliges Salz je Prozesslauf, None = keine Schwärzung (LOG_REDACT)
+static SALT: OnceLock<Option<u64>> = OnceLock::new();
+
+pub fn init(enabled: bool) {
+    SALT.set(enabled.then(rand::random)).ok();
+}
+
+fn salt() -> Option<u64> {
+    SALT.get().copied().flatten()
+}
+
+// Chat-ID für Logs: bei LOG_REDACT ein kurzer Hash, der innerhalb eines
+// Laufs gleich bleibt, damit sich Zeilen weiterhin zuordnen lassen
+pub fn chat(id: i64) -> String {
+    match salt() {
+        Some(salt) => hashed(salt, id),
+        None => id.to_string(),
+    }
+}
+
+fn hashed(salt: u64, id: i64) -> String {
+    let mut hasher = DefaultHasher::new();
+    salt.hash(&mut hasher);
+    id.hash(&mut hasher);
+    format!("#{:06x}", hasher.finish() & 0xff_ffff)
+}
+
+// Benutzer- oder Vorname für Logs; bei LOG_REDACT entfernt
+pub fn name(name: &str) -> String {
+    match salt() {
+        Some(_) => "[Name entfernt]".to_string(),
+        None => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Das Salz gilt prozessweit; die Tests nehmen deshalb eigene Salze statt init
+    #[test]
+    fn same_chat_same_hash_within_a_run() {
+        let salt = 0x5eed;
+        assert_eq!(hashed(salt, 123_456_789), hashed(salt, 123_456_789));
+        assert_ne!(hashed(salt, 123_456_789), hashed(salt, 123_456_790));
+        assert_eq!(hashed(salt, -1_001_234_567_890).len(), 7);
+    }
+
+    #[test]
+    fn hash_does_not_contain_the_chat_id() {
+        for id in [4711, 123_456_789, -1_001_234_567_890] {
+            for salt in [0, 1, u64::MAX] {
+                let text = hashed(salt, id);
+                assert!(text.starts_with('#'), "{}", text);
+                assert!(!text.contains(&id.to_string()) && !text.contains(&id.abs().to_string()), "{} in {}", id, text);
+            }
+        }
+    }
+}
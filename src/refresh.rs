@@ -0,0 +1,43 @@
+use crate::i18n::Text;
+use std::sync::Mutex;
+use tokio::sync::{Notify, oneshot};
+
+// Ergebnis einer Sofortabfrage: Geräte der neuen Messwerte (je Messwert
+// einmal), sonst warum es keine Abfrage gab
+pub type Outcome = Result<Vec<String>, Text>;
+
+// Wartende /refresh-Anfragen. Alle, die bis zum nächsten Durchlauf der
+// Überwachung eingehen, teilen sich eine Abfrage, statt den Sensor-Webserver
+// jede für sich zu fragen.
+static WAITING: Mutex<Vec<oneshot::Sender<Outcome>>> = Mutex::new(Vec::new());
+static WAKE: Notify = Notify::const_new();
+
+// Sofortabfrage anfordern und auf ihr Ergebnis warten
+pub async fn request() -> Outcome {
+    let (tx, rx) = oneshot::channel();
+    let first = {
+        let mut waiting = WAITING.lock().unwrap_or_else(|e| e.into_inner());
+        waiting.push(tx);
+        waiting.len() == 1
+    };
+    if first {
+        WAKE.notify_one();
+    }
+    rx.await.unwrap_or_else(|_| Err(Text::new("refresh_not_running", &[])))
+}
+
+// Alle bisher wartenden Anfragen für diesen Durchlauf übernehmen
+pub fn take() -> Vec<oneshot::Sender<Outcome>> {
+    std::mem::take(&mut *WAITING.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+pub fn answer(waiting: Vec<oneshot::Sender<Outcome>>, outcome: Outcome) {
+    for tx in waiting {
+        tx.send(outcome.clone()).ok();
+    }
+}
+
+// Wartet, bis eine Sofortabfrage angefordert wird
+pub async fn notified() {
+    WAKE.notified().await
+}
@@ -0,0 +1,37 @@
+use crate::sensor::{SensorKind, ThresholdDirection};
+use crate::timeutil::seconds_between;
+use serde::{Deserialize, Serialize};
+
+// /set <gerät> <typ> max-rel|min-rel <gerät> <typ> <abstand>: Alarm, wenn der
+// Wert über (max) bzw. unter (min) dem Wert eines anderen Sensors plus
+// `offset` liegt, z.B. Keller-Feuchte mehr als 10 Punkte über draußen
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+pub struct RelativeRule {
+    pub other_device: String,
+    pub other_kind: SensorKind,
+    pub offset: f64,
+}
+
+impl RelativeRule {
+    // Grenze für den eigenen Wert bei diesem Vergleichswert
+    pub fn limit(&self, other: f64) -> f64 {
+        other + self.offset
+    }
+}
+
+// "max-rel" oder "min-rel"
+pub fn parse_direction(s: &str) -> Option<ThresholdDirection> {
+    match s.to_lowercase().as_str() {
+        "max-rel" => Some(ThresholdDirection::Max),
+        "min-rel" => Some(ThresholdDirection::Min),
+        _ => None,
+    }
+}
+
+// Beide Messwerte müssen aus demselben Abruf stammen und frisch sein;
+// max_age 0 prüft nur, dass es sie gibt
+pub fn comparable(own: Option<(i64, f64)>, other: Option<(i64, f64)>, now: i64, max_age: i64) -> Option<(f64, f64)> {
+    let fresh = |(ts, _): &(i64, f64)| max_age <= 0 || seconds_between(*ts, now) <= max_age;
+    Some((own.filter(fresh)?.1, other.filter(fresh)?.1))
+}
@@ -0,0 +1,115 @@
+use crate::i18n::{self, Lang};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// Eingebaute Antworten, falls REPLIES_FILE fehlt oder fehlerhaft ist
+const DEFAULT_KEYWORDS: &[(&str, &str)] =
+    &[("hallo", "👋 Hallo {name}! Wie kann ich helfen?"), ("wie geht's?", "Mir geht es super! 🤖"), ("ich liebe dich", "Ich liebe dich auch")];
+// Kurzformen für Befehle ("/t" -> "/status") und für Wörter in Befehlen ("wz")
+const DEFAULT_ALIASES: &[(&str, &str)] = &[("t", "status"), ("temp", "status")];
+const DEFAULT_ABBREVIATIONS: &[(&str, &str)] = &[("wz", "wohnzimmer")];
+
+#[derive(Deserialize)]
+struct RepliesFile {
+    #[serde(default)]
+    keywords: BTreeMap<String, String>,
+    fallback: Option<String>,
+    #[serde(default)]
+    aliases: BTreeMap<String, String>,
+    #[serde(default)]
+    abbreviations: BTreeMap<String, String>,
+}
+
+// Antworten auf Freitext ohne Befehl, geladen aus REPLIES_FILE (TOML).
+// Schlüsselwörter werden wie die Eingabe getrimmt und klein geschrieben
+// verglichen; {name} im Text wird durch den Vornamen ersetzt. Dazu Kurzformen:
+// ein Alias steht für einen Befehl samt Angaben ("wz" = "status wohnzimmer"),
+// eine Abkürzung für ein Wort in den Angaben eines Befehls.
+#[derive(Debug, Clone)]
+pub struct Replies {
+    keywords: BTreeMap<String, String>,
+    fallback: Option<String>, // ohne eigenen Text der aus dem Katalog
+    aliases: BTreeMap<String, String>,
+    abbreviations: BTreeMap<String, String>,
+}
+
+fn normalize(text: &str) -> String {
+    text.trim().to_lowercase()
+}
+
+fn pairs(table: &[(&str, &str)]) -> BTreeMap<String, String> {
+    table.iter().map(|(short, long)| (short.to_string(), long.to_string())).collect()
+}
+
+impl Default for Replies {
+    fn default() -> Self {
+        Replies {
+            keywords: DEFAULT_KEYWORDS.iter().map(|(keyword, reply)| (keyword.to_string(), reply.to_string())).collect(),
+            fallback: None,
+            aliases: pairs(DEFAULT_ALIASES),
+            abbreviations: pairs(DEFAULT_ABBREVIATIONS),
+        }
+    }
+}
+
+impl Replies {
+    pub fn load(path: &Path) -> Result<Replies, String> {
+        let content = fs::read_to_string(path).map_err(|err| format!("{} kann nicht gelesen werden: {}", path.display(), err))?;
+        let file: RepliesFile = toml::from_str(&content).map_err(|err| format!("{} ist ungültig: {}", path.display(), err))?;
+        let mut keywords = BTreeMap::new();
+        for (keyword, reply) in file.keywords {
+            let key = normalize(&keyword);
+            if key.is_empty() || reply.trim().is_empty() {
+                return Err(format!("{}: leeres Schlüsselwort oder leere Antwort ('{}')", path.display(), keyword));
+            }
+            if keywords.insert(key, reply).is_some() {
+                return Err(format!("{}: '{}' ist mehrfach eingetragen", path.display(), keyword));
+            }
+        }
+        let shortcuts = |table: BTreeMap<String, String>, name: &str| -> Result<BTreeMap<String, String>, String> {
+            let mut shortcuts = BTreeMap::new();
+            for (short, long) in table {
+                let key = normalize(short.trim_start_matches('/'));
+                let long = long.trim().trim_start_matches('/').to_string();
+                if key.is_empty() || key.contains(char::is_whitespace) || long.is_empty() {
+                    return Err(format!("{}: [{}] '{}' muss ein einzelnes Wort für einen nicht leeren Text sein", path.display(), name, short));
+                }
+                if shortcuts.insert(key, long).is_some() {
+                    return Err(format!("{}: [{}] '{}' ist mehrfach eingetragen", path.display(), name, short));
+                }
+            }
+            Ok(shortcuts)
+        };
+        // Ohne eigene Tabelle gelten die eingebauten Kurzformen
+        let aliases = if file.aliases.is_empty() { pairs(DEFAULT_ALIASES) } else { shortcuts(file.aliases, "aliases")? };
+        let abbreviations = if file.abbreviations.is_empty() { pairs(DEFAULT_ABBREVIATIONS) } else { shortcuts(file.abbreviations, "abbreviations")? };
+        Ok(Replies { keywords, fallback: file.fallback, aliases, abbreviations })
+    }
+
+    pub fn len(&self) -> usize {
+        self.keywords.len()
+    }
+
+    // Befehl samt Angaben zu einer Kurzform, ohne Schrägstrich
+    pub fn alias(&self, name: &str) -> Option<&str> {
+        self.aliases.get(&normalize(name)).map(String::as_str)
+    }
+
+    pub fn aliases(&self) -> impl Iterator<Item = &str> {
+        self.aliases.keys().map(String::as_str)
+    }
+
+    pub fn abbreviation(&self, word: &str) -> Option<&str> {
+        self.abbreviations.get(&normalize(word)).map(String::as_str)
+    }
+
+    // Passende Antwort oder der Fallback
+    pub fn reply(&self, text: &str, lang: Lang) -> &str {
+        match self.keywords.get(&normalize(text)).or(self.fallback.as_ref()) {
+            Some(reply) => reply,
+            None => i18n::message(lang, "fallback"),
+        }
+    }
+}
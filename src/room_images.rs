@@ -0,0 +1,151 @@
+use crate::backup;
+use crate::messenger::OutgoingMessage;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use teloxide::net::Download;
+use teloxide::prelude::*;
+use teloxide::types::{InputFile, ParseMode};
+use teloxide::{ApiError, RequestError};
+
+// Telegram erlaubt in Bildunterschriften höchstens 1024 Zeichen
+pub const MAX_CAPTION_CHARS: usize = 1024;
+
+const INDEX_FILE: &str = "images.json";
+
+// Bild eines Raums: file_id bei Telegram und lokale Kopie, falls die ID
+// ungültig wird (z.B. nach Wechsel des Bot-Tokens)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomImage {
+    pub file_id: String,
+    pub cached: PathBuf,
+}
+
+struct Images {
+    dir: PathBuf,
+    images: BTreeMap<String, RoomImage>, // Gerät -> Bild
+}
+
+// Einmal beim Start gesetzt; ohne `init` gibt es keine Raumbilder
+static IMAGES: Mutex<Option<Images>> = Mutex::new(None);
+
+pub fn init(dir: PathBuf) {
+    let images = fs::read_to_string(dir.join(INDEX_FILE)).ok().and_then(|content| serde_json::from_str(&content).ok()).unwrap_or_default();
+    *IMAGES.lock().unwrap_or_else(|e| e.into_inner()) = Some(Images { dir, images });
+}
+
+fn with_images<T>(f: impl FnOnce(&mut Images) -> T) -> Option<T> {
+    IMAGES.lock().unwrap_or_else(|e| e.into_inner()).as_mut().map(f)
+}
+
+fn save(images: &Images) -> Result<(), String> {
+    let path = images.dir.join(INDEX_FILE);
+    let json = serde_json::to_string_pretty(&images.images).map_err(|err| err.to_string())?;
+    backup::write_atomic(&path, json.as_bytes()).map_err(|err| format!("{} kann nicht gespeichert werden: {}", path.display(), err))
+}
+
+pub fn get(device: &str) -> Option<RoomImage> {
+    with_images(|images| images.images.get(device).cloned()).flatten()
+}
+
+// Geräte-IDs als Dateiname: alles außer Buchstaben, Ziffern, - und _ ersetzen
+fn file_name(device: &str) -> String {
+    let name: String = device.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect();
+    format!("{}.jpg", name)
+}
+
+// Foto herunterladen, lokal ablegen und dem Gerät zuordnen
+pub async fn store(bot: &Bot, device: &str, file_id: &str) -> Result<(), String> {
+    let dir = with_images(|images| images.dir.clone()).ok_or("Raumbilder sind nicht eingerichtet")?;
+    fs::create_dir_all(&dir).map_err(|err| format!("{} kann nicht angelegt werden: {}", dir.display(), err))?;
+    let file = bot.get_file(file_id).await.map_err(|err| err.to_string())?;
+    let cached = dir.join(file_name(device));
+    let partial = cached.with_extension("part");
+    let mut dst = tokio::fs::File::create(&partial).await.map_err(|err| err.to_string())?;
+    bot.download_file(&file.path, &mut dst).await.map_err(|err| err.to_string())?;
+    drop(dst);
+    fs::rename(&partial, &cached).map_err(|err| err.to_string())?;
+    with_images(|images| {
+        images.images.insert(device.to_string(), RoomImage { file_id: file_id.to_string(), cached });
+        save(images)
+    })
+    .unwrap_or(Ok(()))
+}
+
+// true, wenn es ein Bild gab
+pub fn remove(device: &str) -> Result<bool, String> {
+    with_images(|images| match images.images.remove(device) {
+        Some(image) => {
+            fs::remove_file(&image.cached).ok();
+            save(images).map(|()| true)
+        }
+        None => Ok(false),
+    })
+    .unwrap_or(Ok(false))
+}
+
+fn set_file_id(device: &str, file_id: String) {
+    let result = with_images(|images| match images.images.get_mut(device) {
+        Some(image) => {
+            image.file_id = file_id;
+            save(images)
+        }
+        None => Ok(()),
+    });
+    if let Some(Err(err)) = result {
+        warn!("Raumbild: {}", err);
+    }
+}
+
+// Abgelaufene oder fremde file_id (sie gilt nur für dasselbe Bot-Token)
+fn file_id_invalid(err: &ApiError) -> bool {
+    match err {
+        ApiError::WrongFileId | ApiError::WrongFileIdOrUrl | ApiError::FileIdInvalid => true,
+        ApiError::Unknown(text) => text.contains("wrong file identifier") || text.contains("file reference"),
+        _ => false,
+    }
+}
+
+// Nachricht als Foto des Raums mit dem Text als Bildunterschrift.
+// None, wenn kein Bild hinterlegt ist oder der Text zu lang ist; dann
+// sendet der Aufrufer den Text allein. Ist die file_id ungültig, wird die
+// lokale Kopie hochgeladen und die neue ID gemerkt.
+#[allow(deprecated)]
+pub async fn send_photo(bot: &Bot, message: &OutgoingMessage, device: &str) -> Option<Result<Message, RequestError>> {
+    if message.text.chars().count() > MAX_CAPTION_CHARS {
+        return None;
+    }
+    let image = get(device)?;
+    let send = |photo: InputFile| {
+        let mut request = bot.send_photo(ChatId(message.chat_id), photo).caption(&message.text);
+        if message.html {
+            request = request.parse_mode(ParseMode::Html);
+        } else if message.markdown {
+            request = request.parse_mode(ParseMode::Markdown);
+        }
+        if let Some(buttons) = &message.buttons {
+            request = request.reply_markup(buttons.clone());
+        }
+        if message.silent {
+            request = request.disable_notification(true);
+        }
+        if let Some(reply_to) = message.reply_to {
+            request = request.reply_to_message_id(teloxide::types::MessageId(reply_to));
+        }
+        request
+    };
+    match send(InputFile::file_id(image.file_id)).await {
+        Err(RequestError::Api(err)) if file_id_invalid(&err) && image.cached.exists() => {
+            info!("Raumbild von {} nicht mehr bei Telegram, lade lokale Kopie hoch", device);
+            let result = send(InputFile::file(image.cached)).await;
+            if let Some(photo) = result.as_ref().ok().and_then(|sent| sent.photo()).and_then(|sizes| sizes.last()) {
+                set_file_id(device, photo.file.id.clone());
+            }
+            Some(result)
+        }
+        result => Some(result),
+    }
+}
@@ -0,0 +1,572 @@
+use crate::correlation::{Rule, default_rules};
+use crate::routing::{Routing, RoutingEntry, default_margins};
+use crate::sensor::ThresholdKey;
+use crate::settings::MIN_POLL_INTERVAL_SECONDS;
+use reqwest::Url;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// Basis-URL für Diagramme, die als ThingSpeak-Kanal/Feld angegeben sind
+const THINGSPEAK_URL: &str = "https://thingspeak.mathworks.com";
+// Kanal des eingebauten Wohnzimmers, per THINGSPEAK_CHANNEL änderbar
+const DEFAULT_CHANNEL: u64 = 1115568;
+
+// Ein Raum mit seinem Sensor-Gerät und optionalen Diagramm-Links je Messgröße
+#[derive(Debug, Clone)]
+pub struct Room {
+    pub device: String,
+    pub name: String,
+    // Wird auch ohne Feature charts eingelesen, damit rooms.toml gültig bleibt
+    #[cfg_attr(not(feature = "charts"), allow(dead_code))]
+    pub charts: BTreeMap<String, Url>, // sensor_type -> Diagramm-URL
+    #[cfg_attr(not(feature = "charts"), allow(dead_code))]
+    pub feeds: BTreeMap<String, Feed>, // sensor_type -> ThingSpeak-Feld für Diagramme ohne eigenen Verlauf
+    pub rules: Option<Vec<String>>, // nur diese Korrelationsregeln, sonst alle
+    pub tenant: Option<String>,     // Haushalt; None ist der Haupthaushalt
+    pub outdoor: bool,              // Garten/Balkon: eigene Tipps, Frost- und Gießhinweise
+    pub position: Option<i64>,      // Reihenfolge in /status, sonst wie in der Datei
+    pub emoji: Option<String>,      // statt 📍 in /status
+}
+
+// Feld eines ThingSpeak-Kanals; ohne eigenen Lese-Schlüssel gilt THINGSPEAK_READ_KEY
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "charts"), allow(dead_code))]
+pub struct Feed {
+    pub channel: u64,
+    pub field: u8,
+    pub read_key: Option<String>,
+}
+
+// Weiterer Haushalt auf derselben Bot-Instanz mit eigener Quelle und eigenen
+// Räumen. Seine Geräte-IDs tragen intern das Präfix "<id>/", damit Verlauf,
+// Rekorde und Schwellen nie mit denen anderer Haushalte zusammenfallen.
+#[derive(Debug, Clone)]
+pub struct Tenant {
+    pub id: String,
+    pub members: Vec<i64>,                  // Chats dieses Haushalts
+    pub source: String,                     // URL des Sensor-Webservers
+    pub poll_interval_seconds: Option<u64>, // eigener Abfrageabstand, sonst POLL_INTERVAL_SECONDS
+}
+
+// Gerät eines Haushalts, wie es intern geführt wird
+pub fn qualify(tenant: &str, device: &str) -> String {
+    format!("{}/{}", tenant, device)
+}
+
+// Ergebnis der Zuordnung von Freitext zu einem Raum
+pub enum RoomMatch<'a> {
+    None,
+    One(&'a Room),
+    Ambiguous(Vec<&'a Room>),
+}
+
+// Kürzere Eingaben gelten nicht als Präfix eines Raumnamens
+const MIN_PREFIX_CHARS: usize = 2;
+
+// Für den Vergleich: klein, Umlaute ausgeschrieben, ohne Satzzeichen am Ende
+fn normalize(text: &str) -> String {
+    text.trim().trim_end_matches(['?', '!', '.']).trim().to_lowercase().replace('ä', "ae").replace('ö', "oe").replace('ü', "ue").replace('ß', "ss")
+}
+
+// Raumverzeichnis, geladen aus ROOMS_FILE (TOML).
+// Enthält außerdem die Tipps für Alarmtexte je "<typ>_<min|max>", die
+// Regeln für zusammengehörige Alarme, die zusätzlichen Empfänger je Stufe
+// und weitere Haushalte.
+#[derive(Debug, Clone)]
+pub struct RoomRegistry {
+    rooms: Vec<Room>,
+    tenants: Vec<Tenant>,
+    tips: BTreeMap<ThresholdKey, String>,
+    rules: Vec<Rule>,
+    routing: Routing,
+}
+
+// Eingebaute Tipps, einzeln per [tips] in der Raumdatei überschreibbar
+const DEFAULT_TIPS: &[(&str, &str)] = &[
+    ("temperature_min", "Heizung prüfen, Fenster schließen"),
+    ("temperature_max", "Tagsüber beschatten, nachts lüften"),
+    ("humidity_min", "Wäsche drinnen trocknen oder Luftbefeuchter nutzen"),
+    ("humidity_max", "Stoßlüften"),
+];
+
+// Für Räume mit outdoor = true; Lüften hilft draußen nicht
+const OUTDOOR_TIPS: &[(&str, &str)] =
+    &[("temperature_min", "Frostempfindliche Pflanzen reinholen oder abdecken"), ("temperature_max", "Pflanzen morgens oder abends gießen")];
+
+// --- Dateiformat ---
+
+#[derive(Deserialize)]
+struct RoomsFile {
+    #[serde(default, rename = "room")]
+    rooms: Vec<RoomEntry>,
+    #[serde(default)]
+    tips: BTreeMap<String, String>,
+    #[serde(default, rename = "rule")]
+    rules: Vec<RuleEntry>,
+    #[serde(default)]
+    routing: RoutingEntry,
+    #[serde(default, rename = "tenant")]
+    tenants: Vec<TenantEntry>,
+}
+
+#[derive(Deserialize)]
+struct TenantEntry {
+    id: String,
+    #[serde(default)]
+    members: Vec<i64>,
+    source: String,
+    #[serde(default)]
+    poll_interval_seconds: Option<u64>,
+    #[serde(default, rename = "room")]
+    rooms: Vec<RoomEntry>,
+}
+
+#[derive(Deserialize)]
+struct RuleEntry {
+    name: String,
+    #[serde(default, rename = "match")]
+    keys: Vec<String>,
+    #[serde(default = "default_window_minutes")]
+    window_minutes: i64,
+    hint: String,
+}
+
+fn default_window_minutes() -> i64 {
+    10
+}
+
+#[derive(Deserialize)]
+struct RoomEntry {
+    device: String,
+    name: String,
+    #[serde(default)]
+    charts: BTreeMap<String, ChartEntry>,
+    rules: Option<Vec<String>>,
+    #[serde(default)]
+    outdoor: bool,
+    position: Option<i64>,
+    emoji: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ChartEntry {
+    Url {
+        url: String,
+    },
+    ThingSpeak {
+        channel: ChannelId,
+        field: u8,
+        #[serde(default)]
+        read_key: Option<String>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ChannelId {
+    Number(u64),
+    Text(String),
+}
+
+fn parse_channel(channel: &ChannelId) -> Result<u64, String> {
+    match channel {
+        ChannelId::Number(id) => Ok(*id),
+        ChannelId::Text(text) => text.trim().parse().map_err(|_| format!("Kanal-ID '{}' ist nicht numerisch", text)),
+    }
+}
+
+// Diagramm-URL und, wenn es ein ThingSpeak-Diagramm ist, das Feld dahinter
+fn parse_chart(entry: &ChartEntry) -> Result<(Url, Option<Feed>), String> {
+    match entry {
+        ChartEntry::Url { url } => {
+            let parsed = Url::parse(url).map_err(|err| format!("Ungültige URL '{}': {}", url, err))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err(format!("URL '{}' muss mit http:// oder https:// beginnen", url));
+            }
+            // Bei ThingSpeak-Links muss die Kanal-ID numerisch sein
+            let mut segments = parsed.path_segments().into_iter().flatten();
+            let mut feed = None;
+            if segments.by_ref().any(|s| s == "channels") {
+                let id = segments.next().unwrap_or_default();
+                if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+                    return Err(format!("Kanal-ID '{}' in URL '{}' ist nicht numerisch", id, url));
+                }
+                // .../channels/<id>/charts/<feld>: Werte lassen sich auch direkt abfragen
+                if let (Some("charts"), Some(field)) = (segments.next(), segments.next().and_then(|f| f.parse().ok()))
+                    && (1..=8).contains(&field)
+                {
+                    feed = Some(Feed { channel: id.parse().map_err(|_| format!("Kanal-ID '{}' zu groß", id))?, field, read_key: None });
+                }
+            }
+            Ok((parsed, feed))
+        }
+        ChartEntry::ThingSpeak { channel, field, read_key } => {
+            let channel = parse_channel(channel)?;
+            if !(1..=8).contains(field) {
+                return Err(format!("Feld {} gibt es nicht (1 bis 8)", field));
+            }
+            let url = Url::parse(&format!("{}/channels/{}/charts/{}", THINGSPEAK_URL, channel, field)).map_err(|err| err.to_string())?;
+            let read_key = read_key.as_ref().map(|key| key.trim().to_string()).filter(|key| !key.is_empty());
+            Ok((url, Some(Feed { channel, field: *field, read_key })))
+        }
+    }
+}
+
+// Räume eines Abschnitts übernehmen; Geräte weiterer Haushalte bekommen das
+// Präfix, Namen müssen nur innerhalb eines Haushalts eindeutig sein
+fn parse_rooms(entries: Vec<RoomEntry>, tenant: Option<&str>, rooms: &mut Vec<Room>, errors: &mut Vec<String>) {
+    for entry in entries {
+        let device = match tenant {
+            Some(tenant) => qualify(tenant, &entry.device),
+            None => entry.device,
+        };
+        if rooms.iter().any(|r| r.device == device) {
+            errors.push(format!("Gerät '{}' ist mehrfach eingetragen", device));
+        }
+        if rooms.iter().any(|r| r.tenant.as_deref() == tenant && r.name.eq_ignore_ascii_case(&entry.name)) {
+            errors.push(format!("Raumname '{}' ist mehrfach vergeben", entry.name));
+        }
+
+        let mut charts = BTreeMap::new();
+        let mut feeds = BTreeMap::new();
+        for (sensor_type, chart) in &entry.charts {
+            match parse_chart(chart) {
+                Ok((url, feed)) => {
+                    charts.insert(sensor_type.to_lowercase(), url);
+                    if let Some(feed) = feed {
+                        feeds.insert(sensor_type.to_lowercase(), feed);
+                    }
+                }
+                Err(err) => errors.push(format!("Raum '{}', Diagramm '{}': {}", entry.name, sensor_type, err)),
+            }
+        }
+        rooms.push(Room {
+            device,
+            name: entry.name,
+            charts,
+            feeds,
+            rules: entry.rules,
+            tenant: tenant.map(str::to_string),
+            outdoor: entry.outdoor,
+            position: entry.position,
+            emoji: entry.emoji.map(|emoji| emoji.trim().to_string()).filter(|emoji| !emoji.is_empty()),
+        });
+    }
+}
+
+// Geräte-ID ohne Präfix des Haushalts, wie die Quelle sie liefert
+fn local_device(room: &Room) -> &str {
+    match &room.tenant {
+        Some(tenant) => room.device.strip_prefix(tenant.as_str()).and_then(|d| d.strip_prefix('/')).unwrap_or(&room.device),
+        None => &room.device,
+    }
+}
+
+fn default_tips() -> BTreeMap<ThresholdKey, String> {
+    DEFAULT_TIPS.iter().map(|(key, tip)| (key.parse().expect("eingebauter Tipp-Schlüssel ist gültig"), tip.to_string())).collect()
+}
+
+impl Default for RoomRegistry {
+    // Eingebaute Zuordnung, falls keine Raumdatei konfiguriert ist
+    fn default() -> Self {
+        let channel = env::var("THINGSPEAK_CHANNEL").ok().and_then(|id| id.trim().parse().ok()).unwrap_or(DEFAULT_CHANNEL);
+        let chart = |field: u8| {
+            parse_chart(&ChartEntry::ThingSpeak { channel: ChannelId::Number(channel), field, read_key: None }).expect("eingebaute Diagramm-URL ist gültig")
+        };
+        let (temperature, humidity) = (chart(1), chart(2));
+        RoomRegistry {
+            rooms: vec![Room {
+                device: "sensor1".into(),
+                name: "Wohnzimmer".into(),
+                charts: BTreeMap::from([("temperature".to_string(), temperature.0), ("humidity".to_string(), humidity.0)]),
+                feeds: BTreeMap::from([
+                    ("temperature".to_string(), temperature.1.expect("ThingSpeak-Diagramm hat ein Feld")),
+                    ("humidity".to_string(), humidity.1.expect("ThingSpeak-Diagramm hat ein Feld")),
+                ]),
+                rules: None,
+                tenant: None,
+                outdoor: false,
+                position: None,
+                emoji: None,
+            }],
+            tenants: Vec::new(),
+            tips: default_tips(),
+            rules: default_rules(),
+            routing: Routing { critical_margin: default_margins(), ..Routing::default() },
+        }
+    }
+}
+
+impl RoomRegistry {
+    // Lädt und prüft die Raumdatei. Alle Fehler werden gesammelt gemeldet.
+    pub fn load(path: &Path) -> Result<RoomRegistry, Vec<String>> {
+        let content = fs::read_to_string(path).map_err(|err| vec![format!("{} kann nicht gelesen werden: {}", path.display(), err)])?;
+        let file: RoomsFile = toml::from_str(&content).map_err(|err| vec![format!("{} ist ungültig: {}", path.display(), err)])?;
+
+        let mut errors = Vec::new();
+        let mut rooms: Vec<Room> = Vec::new();
+        parse_rooms(file.rooms, None, &mut rooms, &mut errors);
+
+        let mut tenants: Vec<Tenant> = Vec::new();
+        for entry in file.tenants {
+            let label = format!("Haushalt '{}'", entry.id);
+            if entry.id.is_empty() || !entry.id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_') {
+                errors.push(format!("{}: id darf nur aus a-z, 0-9, - und _ bestehen", label));
+            }
+            if tenants.iter().any(|t| t.id == entry.id) {
+                errors.push(format!("{} ist mehrfach eingetragen", label));
+            }
+            for member in &entry.members {
+                if let Some(other) = tenants.iter().find(|t| t.members.contains(member)) {
+                    errors.push(format!("{}: Chat {} gehört schon zu Haushalt '{}'", label, member, other.id));
+                }
+            }
+            match Url::parse(&entry.source) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                _ => errors.push(format!("{}: source '{}' ist keine http(s)-URL", label, entry.source)),
+            }
+            if let Some(seconds) = entry.poll_interval_seconds.filter(|s| *s < MIN_POLL_INTERVAL_SECONDS) {
+                errors.push(format!("{}: poll_interval_seconds {} unter {} Sekunden", label, seconds, MIN_POLL_INTERVAL_SECONDS));
+            }
+            parse_rooms(entry.rooms, Some(&entry.id), &mut rooms, &mut errors);
+            tenants.push(Tenant { id: entry.id, members: entry.members, source: entry.source, poll_interval_seconds: entry.poll_interval_seconds });
+        }
+
+        let mut tips = default_tips();
+        for (key, tip) in file.tips {
+            match key.parse::<ThresholdKey>() {
+                Err(_) => errors.push(format!("Tipp '{}': Schlüssel muss die Form <typ>_min oder <typ>_max haben", key)),
+                // leerer Tipp schaltet den eingebauten ab
+                Ok(key) if tip.trim().is_empty() => {
+                    tips.remove(&key);
+                }
+                Ok(key) => {
+                    tips.insert(key, tip.trim().to_string());
+                }
+            }
+        }
+
+        let mut rules = default_rules();
+        for entry in file.rules {
+            let label = format!("Regel '{}'", entry.name);
+            if entry.keys.len() < 2 {
+                errors.push(format!("{}: 'match' braucht mindestens zwei Schwellen", label));
+            }
+            let mut keys = Vec::new();
+            for key in &entry.keys {
+                match key.parse::<ThresholdKey>() {
+                    Ok(key) => keys.push(key),
+                    Err(_) => errors.push(format!("{}: '{}' muss die Form <typ>_min oder <typ>_max haben", label, key)),
+                }
+            }
+            if entry.window_minutes <= 0 {
+                errors.push(format!("{}: window_minutes muss positiv sein", label));
+            }
+            rules.retain(|rule| rule.name != entry.name);
+            // leerer Hinweis schaltet die Regel (auch eine eingebaute) ab
+            if !entry.hint.trim().is_empty() {
+                rules.push(Rule { name: entry.name, keys, window: entry.window_minutes * 60, hint: entry.hint.trim().to_string() });
+            }
+        }
+        for room in &rooms {
+            for name in room.rules.iter().flatten() {
+                if !rules.iter().any(|rule| rule.name == *name) {
+                    errors.push(format!("Raum '{}': unbekannte Regel '{}'", room.name, name));
+                }
+            }
+        }
+
+        let routing = Routing::from_entry(file.routing, &mut errors);
+
+        if errors.is_empty() { Ok(RoomRegistry { rooms, tenants, tips, rules, routing }) } else { Err(errors) }
+    }
+
+    pub fn rooms(&self) -> &[Room] {
+        &self.rooms
+    }
+
+    // Räume eines Haushalts (None: Haupthaushalt)
+    pub fn rooms_in<'a>(&'a self, tenant: Option<&'a str>) -> impl Iterator<Item = &'a Room> {
+        self.rooms.iter().filter(move |r| r.tenant.as_deref() == tenant)
+    }
+
+    pub fn tenants(&self) -> &[Tenant] {
+        &self.tenants
+    }
+
+    // Haushalt eines Chats; Chats ohne Eintrag gehören zum Haupthaushalt
+    pub fn tenant_of(&self, chat_id: i64) -> Option<&Tenant> {
+        self.tenants.iter().find(|t| t.members.contains(&chat_id))
+    }
+
+    // Haushalt eines Geräts anhand des Präfixes
+    pub fn device_tenant(&self, device_id: &str) -> Option<&str> {
+        let (prefix, _) = device_id.split_once('/')?;
+        self.tenants.iter().find(|t| t.id == prefix).map(|t| t.id.as_str())
+    }
+
+    // Darf ein Chat des Haushalts `tenant` das Gerät sehen?
+    pub fn visible(&self, tenant: Option<&str>, device_id: &str) -> bool {
+        self.device_tenant(device_id) == tenant
+    }
+
+    pub fn room_name<'a>(&'a self, device_id: &'a str) -> &'a str {
+        self.rooms.iter().find(|r| r.device == device_id).map(|r| r.name.as_str()).unwrap_or(device_id)
+    }
+
+    // Sortierung für /status: erst nach `position`, dann wie in der Datei;
+    // Geräte ohne Raum zuletzt
+    pub fn order(&self, device_id: &str) -> (i64, usize) {
+        match self.rooms.iter().position(|r| r.device == device_id) {
+            Some(index) => (self.rooms[index].position.unwrap_or(i64::MAX), index),
+            None => (i64::MAX, usize::MAX),
+        }
+    }
+
+    pub fn emoji(&self, device_id: &str) -> Option<&str> {
+        self.rooms.iter().find(|r| r.device == device_id).and_then(|r| r.emoji.as_deref())
+    }
+
+    pub fn is_outdoor(&self, device_id: &str) -> bool {
+        self.rooms.iter().any(|r| r.device == device_id && r.outdoor)
+    }
+
+    // Tipp für Alarm zu einer Schwelle, z.B. humidity_max. Räume im Freien
+    // haben eigene Tipps, [tips] gilt nur für Innenräume.
+    pub fn tip(&self, device_id: &str, key: &ThresholdKey) -> Option<&str> {
+        if self.is_outdoor(device_id) {
+            let key = key.to_string();
+            return OUTDOOR_TIPS.iter().find(|(k, _)| *k == key).map(|(_, tip)| *tip);
+        }
+        self.tips.get(key).map(String::as_str)
+    }
+
+    pub fn routing(&self) -> &Routing {
+        &self.routing
+    }
+
+    // Korrelationsregeln, die für ein Gerät gelten. Räume im Freien haben
+    // keine, solange sie nicht ausdrücklich welche nennen: die eingebauten
+    // Hinweise (Fenster, Lüften) ergeben dort keinen Sinn.
+    pub fn rules_for(&self, device_id: &str) -> Vec<&Rule> {
+        let room = self.rooms.iter().find(|r| r.device == device_id);
+        if room.is_some_and(|r| r.outdoor && r.rules.is_none()) {
+            return Vec::new();
+        }
+        let allowed = room.and_then(|r| r.rules.as_ref());
+        self.rules.iter().filter(|rule| allowed.is_none_or(|names| names.contains(&rule.name))).collect()
+    }
+
+    // Raum über Namen oder Geräte-ID finden (ohne Groß-/Kleinschreibung).
+    // Namen gelten im Haupthaushalt, Räume weiterer Haushalte nur über ihre
+    // vollständige Geräte-ID; für Befehle aus Chats siehe `find_in`.
+    pub fn find(&self, name: &str) -> Option<&Room> {
+        self.find_in(None, name).or_else(|| self.rooms.iter().find(|r| r.device.eq_ignore_ascii_case(name)))
+    }
+
+    // Raum eines Haushalts über Namen oder Geräte-ID, auch ohne Präfix
+    pub fn find_in(&self, tenant: Option<&str>, name: &str) -> Option<&Room> {
+        self.rooms.iter().find(|r| {
+            r.tenant.as_deref() == tenant
+                && (r.name.eq_ignore_ascii_case(name) || r.device.eq_ignore_ascii_case(name) || local_device(r).eq_ignore_ascii_case(name))
+        })
+    }
+
+    // Freitext wie "Schlafzimmer?" oder "schlafz" einem Raum des Haushalts
+    // zuordnen. Exakte Treffer (Name oder Geräte-ID) gehen vor eindeutigen Präfixen.
+    pub fn match_text(&self, tenant: Option<&str>, text: &str) -> RoomMatch<'_> {
+        let text = normalize(text);
+        if text.is_empty() {
+            return RoomMatch::None;
+        }
+        let tenant = tenant.map(str::to_string);
+        let rooms = || self.rooms.iter().filter(|r| r.tenant == tenant);
+        if let Some(room) = rooms().find(|r| normalize(&r.name) == text || normalize(local_device(r)) == text) {
+            return RoomMatch::One(room);
+        }
+        if text.chars().count() < MIN_PREFIX_CHARS {
+            return RoomMatch::None;
+        }
+        let mut found: Vec<&Room> = rooms().filter(|r| normalize(&r.name).starts_with(&text)).collect();
+        match found.len() {
+            0 => RoomMatch::None,
+            1 => RoomMatch::One(found.remove(0)),
+            _ => RoomMatch::Ambiguous(found),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(device: &str, name: &str, tenant: Option<&str>) -> Room {
+        Room {
+            device: device.to_string(),
+            name: name.to_string(),
+            charts: BTreeMap::new(),
+            feeds: BTreeMap::new(),
+            rules: None,
+            tenant: tenant.map(str::to_string),
+            outdoor: false,
+            position: None,
+            emoji: None,
+        }
+    }
+
+    fn registry() -> RoomRegistry {
+        RoomRegistry {
+            rooms: vec![
+                room("sensor1", "Wohnzimmer", None),
+                room("sensor2", "Schlafzimmer", None),
+                room("sensor3", "Schlafzimmer Gast", None),
+                room("sensor4", "Küche", None),
+                room("nachbar/sensor1", "Werkstatt", Some("nachbar")),
+            ],
+            ..RoomRegistry::default()
+        }
+    }
+
+    fn names(found: RoomMatch<'_>) -> Vec<&str> {
+        match found {
+            RoomMatch::None => Vec::new(),
+            RoomMatch::One(room) => vec![room.name.as_str()],
+            RoomMatch::Ambiguous(rooms) => rooms.iter().map(|room| room.name.as_str()).collect(),
+        }
+    }
+
+    #[test]
+    fn prefix_of_two_rooms_is_ambiguous() {
+        let registry = registry();
+        let found = registry.match_text(None, "schlaf");
+        assert!(matches!(found, RoomMatch::Ambiguous(_)));
+        assert_eq!(names(found), ["Schlafzimmer", "Schlafzimmer Gast"]);
+    }
+
+    // Ein genauer Treffer geht dem Präfix weiterer Räume vor
+    #[test]
+    fn exact_name_or_unique_prefix_resolves() {
+        let registry = registry();
+        for text in ["Schlafzimmer?", "schlafzimmer", "sensor2"] {
+            assert!(matches!(registry.match_text(None, text), RoomMatch::One(room) if room.device == "sensor2"), "{}", text);
+        }
+        assert_eq!(names(registry.match_text(None, "wohn")), ["Wohnzimmer"]);
+        assert_eq!(names(registry.match_text(None, "kue")), ["Küche"]);
+        assert_eq!(names(registry.match_text(None, "schlafzimmer g")), ["Schlafzimmer Gast"]);
+    }
+
+    #[test]
+    fn no_match_too_short_or_another_household() {
+        let registry = registry();
+        for text in ["Bad", "w", "", "?", "werk"] {
+            assert!(matches!(registry.match_text(None, text), RoomMatch::None), "{}", text);
+        }
+        assert_eq!(names(registry.match_text(Some("nachbar"), "werk")), ["Werkstatt"]);
+        assert_eq!(names(registry.match_text(Some("nachbar"), "sensor1")), ["Werkstatt"]);
+        assert!(matches!(registry.match_text(Some("nachbar"), "wohn"), RoomMatch::None));
+    }
+}
@@ -0,0 +1,129 @@
+use crate::i18n::{self, Lang};
+use crate::sensor::SensorKind;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+// Ab dieser Überschreitung der Schwelle gilt eine Warnung als kritisch,
+// einzeln per [routing.critical_margin] überschreibbar
+const DEFAULT_CRITICAL_MARGINS: &[(&str, f64)] = &[("temperature", 3.0), ("humidity", 10.0)];
+
+// Info kommt nur aus einer eigenen Stufe je Schwelle (/setmin … info) und
+// geht lautlos ohne Kopien raus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "info" => Ok(Severity::Info),
+            "warn" | "warning" | "warnung" => Ok(Severity::Warning),
+            "critical" | "kritisch" => Ok(Severity::Critical),
+            _ => Err(format!("Unbekannte Stufe '{}', erlaubt: info, warn, critical", s)),
+        }
+    }
+}
+
+impl Severity {
+    pub fn label(self, lang: Lang) -> &'static str {
+        match self {
+            Severity::Info => i18n::message(lang, "severity_info"),
+            Severity::Warning => i18n::message(lang, "severity_warning"),
+            Severity::Critical => i18n::message(lang, "severity_critical"),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "Info"),
+            Severity::Warning => write!(f, "Warnung"),
+            Severity::Critical => write!(f, "kritisch"),
+        }
+    }
+}
+
+// Zusätzliche Empfänger je Stufe, z.B. ein Familien-Kanal für kritische
+// Alarme. Der Besitzer der Schwelle bekommt seine Warnung wie bisher;
+// Kopien gehen an `warning` (jede Warnung) und `critical` (nur kritische).
+#[derive(Debug, Clone, Default)]
+pub struct Routing {
+    pub warning: Vec<i64>,
+    pub critical: Vec<i64>,
+    pub critical_margin: BTreeMap<String, f64>,
+}
+
+// [routing] in der Raumdatei
+#[derive(Deserialize, Default)]
+pub struct RoutingEntry {
+    #[serde(default)]
+    warning: Vec<i64>,
+    #[serde(default)]
+    critical: Vec<i64>,
+    #[serde(default)]
+    critical_margin: BTreeMap<String, f64>,
+}
+
+pub fn default_margins() -> BTreeMap<String, f64> {
+    DEFAULT_CRITICAL_MARGINS.iter().map(|(typ, margin)| (typ.to_string(), *margin)).collect()
+}
+
+impl Routing {
+    pub fn from_entry(entry: RoutingEntry, errors: &mut Vec<String>) -> Routing {
+        let mut critical_margin = default_margins();
+        for (typ, margin) in entry.critical_margin {
+            if !(margin.is_finite() && margin > 0.0) {
+                errors.push(format!("routing.critical_margin.{}: muss positiv sein", typ));
+            }
+            critical_margin.insert(typ, margin);
+        }
+        for id in entry.warning.iter().chain(&entry.critical).filter(|id| **id == 0) {
+            errors.push(format!("routing: ungültige Chat-ID {}", id));
+        }
+        Routing { warning: entry.warning, critical: entry.critical, critical_margin }
+    }
+
+    // Kritisch, wenn der Wert die Schwelle um mindestens die Marge überschreitet
+    pub fn severity(&self, sensor_type: &SensorKind, value: f64, threshold: f64) -> Severity {
+        match self.critical_margin.get(sensor_type.as_str()) {
+            Some(margin) if (value - threshold).abs() >= *margin => Severity::Critical,
+            _ => Severity::Warning,
+        }
+    }
+
+    // Zusätzliche Empfänger einer Warnung dieser Stufe, ohne Doppelte
+    pub fn targets(&self, severity: Severity) -> Vec<i64> {
+        if severity == Severity::Info {
+            return Vec::new();
+        }
+        let mut targets = self.warning.clone();
+        if severity == Severity::Critical {
+            targets.extend(&self.critical);
+        }
+        targets.sort_unstable();
+        targets.dedup();
+        targets
+    }
+
+    // Alle Ziele mit ihrer niedrigsten Stufe, zum Prüfen beim Start
+    pub fn all_targets(&self) -> BTreeMap<i64, Severity> {
+        let mut all = BTreeMap::new();
+        for id in &self.critical {
+            all.insert(*id, Severity::Critical);
+        }
+        for id in &self.warning {
+            all.insert(*id, Severity::Warning);
+        }
+        all
+    }
+}
@@ -0,0 +1,343 @@
+use crate::i18n::{Lang, Text};
+use chrono::{DateTime, Datelike, Duration, LocalResult, NaiveTime, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+const DAYS: [(Weekday, &str); 7] =
+    [(Weekday::Mon, "mo"), (Weekday::Tue, "di"), (Weekday::Wed, "mi"), (Weekday::Thu, "do"), (Weekday::Fri, "fr"), (Weekday::Sat, "sa"), (Weekday::Sun, "so")];
+
+pub fn weekday_name(day: Weekday, lang: Lang) -> &'static str {
+    match (day, lang) {
+        (Weekday::Mon, Lang::De) => "Mo",
+        (Weekday::Tue, Lang::De) => "Di",
+        (Weekday::Wed, Lang::De) => "Mi",
+        (Weekday::Thu, Lang::De) => "Do",
+        (Weekday::Fri, Lang::De) => "Fr",
+        (Weekday::Sat, Lang::De) => "Sa",
+        (Weekday::Sun, Lang::De) => "So",
+        (Weekday::Mon, Lang::En) => "Mon",
+        (Weekday::Tue, Lang::En) => "Tue",
+        (Weekday::Wed, Lang::En) => "Wed",
+        (Weekday::Thu, Lang::En) => "Thu",
+        (Weekday::Fri, Lang::En) => "Fri",
+        (Weekday::Sat, Lang::En) => "Sat",
+        (Weekday::Sun, Lang::En) => "Sun",
+    }
+}
+
+fn parse_day(s: &str) -> Result<Weekday, Text> {
+    DAYS.iter().find(|(_, name)| *name == s).map(|(day, _)| *day).ok_or_else(|| Text::new("unknown_weekday", &[("day", s)]))
+}
+
+// "mo-fr", "sa,so", "mo,mi-fr" -> Wochentage
+fn parse_days(s: &str) -> Result<Vec<Weekday>, Text> {
+    let mut days = Vec::new();
+    for part in s.split(',') {
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let (from, to) = (parse_day(from)?, parse_day(to)?);
+                // Bereiche über das Wochenende (fr-mo) sind erlaubt
+                let mut day = from;
+                loop {
+                    days.push(day);
+                    if day == to {
+                        break;
+                    }
+                    day = day.succ();
+                }
+            }
+            None => days.push(parse_day(part)?),
+        }
+    }
+    Ok(days)
+}
+
+fn parse_time(s: &str) -> Result<NaiveTime, Text> {
+    NaiveTime::parse_from_str(s, "%H:%M").map_err(|_| Text::new("invalid_time", &[("time", s)]))
+}
+
+// Wöchentlicher Zeitplan, z.B. "mo-fr 06:30; sa,so 09:00".
+// Eine einzelne Uhrzeit ("07:00") gilt für jeden Tag.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary), proptest(filter = "|s| s.days.iter().any(|d| !d.is_empty())"))]
+pub struct WeeklySchedule {
+    // Uhrzeiten je Wochentag, Index 0 = Montag, sortiert
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::week()"))]
+    days: [Vec<NaiveTime>; 7],
+}
+
+// Liegt `other` höchstens `window` nach `at`? Dann werden die beiden
+// Nachrichten zu einer zusammengelegt, statt kurz hintereinander zu kommen.
+pub fn merges_with(at: DateTime<Utc>, other: Option<DateTime<Utc>>, window: Duration) -> bool {
+    other.is_some_and(|other| other >= at && other - at <= window)
+}
+
+impl WeeklySchedule {
+    // Jeden Tag zur selben Uhrzeit
+    pub fn daily(time: NaiveTime) -> Self {
+        WeeklySchedule { days: std::array::from_fn(|_| vec![time]) }
+    }
+
+    pub fn times(&self, day: Weekday) -> &[NaiveTime] {
+        &self.days[day.num_days_from_monday() as usize]
+    }
+
+    // Ausgeschriebener Wochenplan, ein Eintrag pro Wochentag
+    pub fn weekly_plan(&self) -> Vec<(Weekday, &[NaiveTime])> {
+        DAYS.iter().map(|(day, _)| (*day, self.times(*day))).collect()
+    }
+
+    fn add(&mut self, day: Weekday, time: NaiveTime) {
+        let times = &mut self.days[day.num_days_from_monday() as usize];
+        if !times.contains(&time) {
+            times.push(time);
+            times.sort();
+        }
+    }
+
+    // Nächster Termin strikt nach `after`, in dessen Zeitzone.
+    // Fällt eine Uhrzeit in die Sommerzeit-Lücke, wird sie um die Lücke verschoben;
+    // doppelte Uhrzeiten bei Winterzeit-Umstellung feuern nur einmal (frühester Zeitpunkt).
+    pub fn next_after<Z: TimeZone>(&self, after: &DateTime<Z>) -> Option<DateTime<Z>> {
+        let tz = after.timezone();
+        let start = after.date_naive();
+
+        for offset in 0..=7 {
+            let date = start + Duration::days(offset);
+            for time in self.times(date.weekday()) {
+                let local = date.and_time(*time);
+                let candidate = match tz.from_local_datetime(&local) {
+                    LocalResult::Single(dt) => dt,
+                    LocalResult::Ambiguous(earliest, _) => earliest,
+                    LocalResult::None => match tz.from_local_datetime(&(local + Duration::hours(1))) {
+                        LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => dt,
+                        LocalResult::None => continue,
+                    },
+                };
+                if candidate > *after {
+                    return Some(candidate);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl FromStr for WeeklySchedule {
+    type Err = Text;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut schedule = WeeklySchedule::default();
+
+        for group in s.split(';').map(str::trim).filter(|g| !g.is_empty()) {
+            let parts: Vec<&str> = group.split_whitespace().collect();
+            // Erstes Element ohne ':' sind die Wochentage, sonst gilt die Gruppe täglich
+            let (days, times) = match parts.first() {
+                Some(first) if !first.contains(':') => (parse_days(&first.to_lowercase())?, &parts[1..]),
+                _ => (DAYS.iter().map(|(day, _)| *day).collect(), &parts[..]),
+            };
+            if times.is_empty() {
+                return Err(Text::new("schedule_no_time", &[("group", group)]));
+            }
+            for time in times {
+                let time = parse_time(time)?;
+                for day in &days {
+                    schedule.add(*day, time);
+                }
+            }
+        }
+
+        if schedule.days.iter().all(Vec::is_empty) {
+            return Err(Text::new("schedule_empty", &[]));
+        }
+        Ok(schedule)
+    }
+}
+
+impl fmt::Display for WeeklySchedule {
+    // Fasst aufeinanderfolgende Tage mit gleichen Uhrzeiten zusammen ("mo-fr 06:30; sa-so 09:00")
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut groups: Vec<(usize, usize)> = Vec::new();
+        for i in 0..7 {
+            match groups.last_mut() {
+                Some((_, end)) if self.days[*end] == self.days[i] => *end = i,
+                _ => groups.push((i, i)),
+            }
+        }
+
+        let mut first = true;
+        for (start, end) in groups {
+            if self.days[start].is_empty() {
+                continue;
+            }
+            if !first {
+                write!(f, "; ")?;
+            }
+            first = false;
+            let times: Vec<String> = self.days[start].iter().map(|t| t.format("%H:%M").to_string()).collect();
+            if start == 0 && end == 6 {
+                write!(f, "{}", times.join(" "))?;
+            } else if start == end {
+                write!(f, "{} {}", DAYS[start].1, times.join(" "))?;
+            } else {
+                write!(f, "{}-{} {}", DAYS[start].1, DAYS[end].1, times.join(" "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Gespeichert in der Schreibweise von /schedule
+impl Serialize for WeeklySchedule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for WeeklySchedule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe::Berlin;
+
+    fn time(text: &str) -> NaiveTime {
+        parse_time(text).unwrap()
+    }
+
+    fn berlin(local: &str) -> DateTime<chrono_tz::Tz> {
+        Berlin.from_local_datetime(&chrono::NaiveDateTime::parse_from_str(local, "%Y-%m-%d %H:%M").unwrap()).earliest().unwrap()
+    }
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn weekday_and_weekend_groups() {
+        let schedule: WeeklySchedule = "mo-fr 06:30; sa,so 09:00".parse().unwrap();
+        assert_eq!(schedule.times(Weekday::Mon), [time("06:30")]);
+        assert_eq!(schedule.times(Weekday::Fri), [time("06:30")]);
+        assert_eq!(schedule.times(Weekday::Sat), [time("09:00")]);
+        assert_eq!(schedule.times(Weekday::Sun), [time("09:00")]);
+    }
+
+    #[test]
+    fn single_time_applies_to_every_day() {
+        let schedule: WeeklySchedule = "07:00".parse().unwrap();
+        assert_eq!(schedule, WeeklySchedule::daily(time("07:00")));
+        assert!(schedule.weekly_plan().iter().all(|(_, times)| *times == [time("07:00")]));
+    }
+
+    #[test]
+    fn times_are_sorted_deduplicated_and_days_case_insensitive() {
+        let schedule: WeeklySchedule = "MO 18:00 07:00 18:00; Mo 12:00".parse().unwrap();
+        assert_eq!(schedule.times(Weekday::Mon), [time("07:00"), time("12:00"), time("18:00")]);
+        assert!(schedule.times(Weekday::Tue).is_empty());
+    }
+
+    #[test]
+    fn ranges_may_wrap_over_the_weekend() {
+        let schedule: WeeklySchedule = "fr-mo 10:00".parse().unwrap();
+        let days: Vec<Weekday> = schedule.weekly_plan().into_iter().filter(|(_, times)| !times.is_empty()).map(|(day, _)| day).collect();
+        assert_eq!(days, [Weekday::Mon, Weekday::Fri, Weekday::Sat, Weekday::Sun]);
+    }
+
+    #[test]
+    fn invalid_schedules_are_rejected_with_a_reason() {
+        for (input, reason) in [
+            ("", "Zeitplan ist leer"),
+            (" ; ", "Zeitplan ist leer"),
+            ("mo-fr", "Keine Uhrzeit in 'mo-fr'"),
+            ("xy 07:00", "Unbekannter Wochentag 'xy'"),
+            ("mo-xy 07:00", "Unbekannter Wochentag 'xy'"),
+            ("mo,,di 07:00", "Unbekannter Wochentag ''"),
+            ("mo 25:00", "Ungültige Uhrzeit '25:00'"),
+            ("mo 7", "Ungültige Uhrzeit '7'"),
+            ("06:30 sa", "Ungültige Uhrzeit 'sa'"),
+        ] {
+            let err = input.parse::<WeeklySchedule>().unwrap_err().to_string();
+            assert!(err.contains(reason), "{:?}: {}", input, err);
+        }
+    }
+
+    #[test]
+    fn display_merges_days_and_parses_back() {
+        for (input, shown) in [
+            ("mo-fr 06:30; sa,so 09:00", "mo-fr 06:30; sa-so 09:00"),
+            ("07:00 19:00", "07:00 19:00"),
+            ("mi 08:00", "mi 08:00"),
+            ("fr-mo 10:00", "mo 10:00; fr-so 10:00"),
+            ("mo,mi,fr 07:15; di 07:15", "mo-mi 07:15; fr 07:15"),
+        ] {
+            let schedule: WeeklySchedule = input.parse().unwrap();
+            assert_eq!(schedule.to_string(), shown, "{}", input);
+            assert_eq!(shown.parse::<WeeklySchedule>().unwrap(), schedule, "{}", input);
+        }
+    }
+
+    #[test]
+    fn serde_uses_the_schedule_syntax() {
+        let schedule: WeeklySchedule = "mo-fr 06:30; sa,so 09:00".parse().unwrap();
+        let json = serde_json::to_string(&schedule).unwrap();
+        assert_eq!(json, "\"mo-fr 06:30; sa-so 09:00\"");
+        assert_eq!(serde_json::from_str::<WeeklySchedule>(&json).unwrap(), schedule);
+        assert!(serde_json::from_str::<WeeklySchedule>("\"mo 99:00\"").is_err());
+    }
+
+    #[test]
+    fn next_after_is_strictly_later() {
+        let schedule: WeeklySchedule = "mo-fr 06:30; sa,so 09:00".parse().unwrap();
+        // Fr 16.10.2026 06:30 genau: nächster Termin Sa 09:00
+        assert_eq!(schedule.next_after(&berlin("2026-10-16 06:30")), Some(berlin("2026-10-17 09:00")));
+        assert_eq!(schedule.next_after(&berlin("2026-10-16 06:29")), Some(berlin("2026-10-16 06:30")));
+        assert_eq!(schedule.next_after(&berlin("2026-10-18 09:00")), Some(berlin("2026-10-19 06:30")));
+    }
+
+    #[test]
+    fn next_after_shifts_a_time_in_the_march_gap() {
+        // So 29.03.2026: 02:30 gibt es nicht, der Termin rückt auf 03:30 MESZ
+        let schedule = WeeklySchedule::daily(time("02:30"));
+        let fire = schedule.next_after(&berlin("2026-03-29 00:00")).unwrap();
+        assert_eq!(fire.with_timezone(&Utc), utc("2026-03-29T01:30:00Z"));
+        let next = schedule.next_after(&fire).unwrap();
+        assert_eq!(next.with_timezone(&Utc), utc("2026-03-30T00:30:00Z"));
+    }
+
+    #[test]
+    fn next_after_fires_the_repeated_october_hour_once() {
+        // So 25.10.2026: 02:30 gibt es zweimal, gefeuert wird nur beim ersten
+        let schedule = WeeklySchedule::daily(time("02:30"));
+        let fire = schedule.next_after(&berlin("2026-10-25 00:00")).unwrap();
+        assert_eq!(fire.with_timezone(&Utc), utc("2026-10-25T00:30:00Z"));
+        let next = schedule.next_after(&fire).unwrap();
+        assert_eq!(next.with_timezone(&Utc), utc("2026-10-26T01:30:00Z"));
+    }
+
+    #[test]
+    fn next_after_keeps_the_wall_clock_time_on_transition_days() {
+        let schedule: WeeklySchedule = "mo-fr 06:30; sa,so 09:00".parse().unwrap();
+        let saturday = berlin("2026-03-28 09:00");
+        let sunday = schedule.next_after(&saturday).unwrap();
+        assert_eq!(sunday, berlin("2026-03-29 09:00"));
+        assert_eq!((sunday - saturday).num_hours(), 23);
+
+        let saturday = berlin("2026-10-24 09:00");
+        let sunday = schedule.next_after(&saturday).unwrap();
+        assert_eq!(sunday, berlin("2026-10-25 09:00"));
+        assert_eq!((sunday - saturday).num_hours(), 25);
+    }
+
+    #[test]
+    fn empty_days_are_skipped_for_up_to_a_week() {
+        let schedule: WeeklySchedule = "mi 08:00".parse().unwrap();
+        // Mi 14.10.2026 08:00 genau: eine Woche später
+        assert_eq!(schedule.next_after(&berlin("2026-10-14 08:00")), Some(berlin("2026-10-21 08:00")));
+        assert_eq!(WeeklySchedule::default().next_after(&berlin("2026-10-14 08:00")), None);
+    }
+}
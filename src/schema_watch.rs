@@ -0,0 +1,161 @@
+use crate::i18n::{self, Lang};
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+// Felder, die SensorData erwartet
+const EXPECTED_FIELDS: [&str; 4] = ["device_id", "sensor_type", "value", "timestamp"];
+// So viel von der Antwort kommt in die Meldung
+const PAYLOAD_PREVIEW_CHARS: usize = 500;
+
+// Fehlertext ohne Positionsangabe, damit gleiche Ursachen gleich aussehen:
+// "missing field `value` at line 1 column 57" -> "missing field `value`"
+pub fn signature(error: &str) -> String {
+    match error.rsplit_once(" at line ") {
+        Some((message, _)) => message.to_string(),
+        None => error.to_string(),
+    }
+}
+
+// Schlüssel der obersten Ebene: bei einer Liste die Felder ihrer Objekte
+fn top_level_keys(value: &Value) -> BTreeSet<String> {
+    match value {
+        Value::Object(map) => map.keys().cloned().collect(),
+        Value::Array(items) => items.iter().filter_map(Value::as_object).flat_map(|map| map.keys().cloned()).collect(),
+        _ => BTreeSet::new(),
+    }
+}
+
+// Erkennt, wenn die Sensordaten wiederholt mit demselben Fehler nicht
+// lesbar sind (z.B. umbenanntes Feld nach Firmware-Update), und meldet das
+// einmal, statt alle 10 Minuten dasselbe zu protokollieren.
+pub struct SchemaWatch {
+    alarm_after: u32,
+    signature: Option<String>,
+    count: u32,
+}
+
+impl SchemaWatch {
+    pub fn new(alarm_after: u32) -> SchemaWatch {
+        SchemaWatch { alarm_after: alarm_after.max(1), signature: None, count: 0 }
+    }
+
+    // Erfolgreich gelesen: Zähler zurücksetzen
+    pub fn success(&mut self) {
+        self.signature = None;
+        self.count = 0;
+    }
+
+    // Fehlgeschlagenes Parsen. Liefert genau einmal je Fehlerbild die Meldung
+    // für den Admin, sobald es `alarm_after` Mal in Folge auftrat.
+    pub fn failure(&mut self, error: &str, payload: &str, lang: Lang) -> Option<String> {
+        let signature = signature(error);
+        if self.signature.as_deref() == Some(signature.as_str()) {
+            self.count += 1;
+        } else {
+            self.signature = Some(signature);
+            self.count = 1;
+        }
+        if self.count != self.alarm_after {
+            return None;
+        }
+        Some(self.report(error, payload, lang))
+    }
+
+    fn report(&self, error: &str, payload: &str, lang: Lang) -> String {
+        let line = |key: &str, values: &[(&str, &str)]| format!("{}\n", i18n::message_with(lang, key, values));
+        let mut text = line("schema_unreadable", &[("count", &self.count.to_string()), ("error", error)]);
+
+        // Gültiges JSON mit falscher Form: Felder gegenüberstellen
+        if let Ok(value) = serde_json::from_str::<Value>(payload) {
+            let found = top_level_keys(&value);
+            let expected: BTreeSet<String> = EXPECTED_FIELDS.iter().map(|f| f.to_string()).collect();
+            let join = |keys: &mut dyn Iterator<Item = &String>| keys.cloned().collect::<Vec<_>>().join(", ");
+            text.push_str(&line("schema_found", &[("fields", &join(&mut found.iter()))]));
+            text.push_str(&line("schema_expected", &[("fields", &join(&mut expected.iter()))]));
+            let missing = join(&mut expected.difference(&found));
+            if !missing.is_empty() {
+                text.push_str(&line("schema_missing", &[("fields", &missing)]));
+            }
+            let unknown = join(&mut found.difference(&expected));
+            if !unknown.is_empty() {
+                text.push_str(&line("schema_unknown", &[("fields", &unknown)]));
+            }
+        }
+
+        let preview: String = payload.chars().take(PAYLOAD_PREVIEW_CHARS).collect();
+        let ellipsis = if payload.chars().count() > PAYLOAD_PREVIEW_CHARS { " …" } else { "" };
+        text.push_str(&format!("{}\n{}{}", i18n::message(lang, "schema_payload"), preview, ellipsis));
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAYLOAD: &str = r#"[{"device": "sensor1", "sensor_type": "temperature", "value": 21.5, "timestamp": 1700000000}]"#;
+
+    #[test]
+    fn reports_once_after_the_threshold() {
+        let mut watch = SchemaWatch::new(3);
+        assert_eq!(watch.failure("missing field `device_id` at line 1 column 80", PAYLOAD, Lang::De), None);
+        assert_eq!(watch.failure("missing field `device_id` at line 1 column 80", PAYLOAD, Lang::De), None);
+        let report = watch.failure("missing field `device_id` at line 1 column 80", PAYLOAD, Lang::De).unwrap();
+        assert!(report.starts_with("🧩 Sensordaten 3 Mal in Folge nicht lesbar"), "{}", report);
+        // Danach bleibt dasselbe Fehlerbild still
+        for _ in 0..5 {
+            assert_eq!(watch.failure("missing field `device_id` at line 1 column 80", PAYLOAD, Lang::De), None);
+        }
+    }
+
+    // Nur die Position unterscheidet sich: dasselbe Fehlerbild, ein anderes
+    // beginnt die Zählung neu
+    #[test]
+    fn same_signature_counts_together() {
+        assert_eq!(signature("missing field `value` at line 1 column 57"), "missing field `value`");
+        assert_eq!(signature("expected value"), "expected value");
+
+        let mut watch = SchemaWatch::new(2);
+        assert_eq!(watch.failure("missing field `value` at line 1 column 57", PAYLOAD, Lang::De), None);
+        assert!(watch.failure("missing field `value` at line 3 column 12", PAYLOAD, Lang::De).is_some());
+
+        let mut watch = SchemaWatch::new(2);
+        assert_eq!(watch.failure("missing field `value` at line 1 column 57", PAYLOAD, Lang::De), None);
+        assert_eq!(watch.failure("missing field `device_id` at line 1 column 57", PAYLOAD, Lang::De), None);
+        assert!(watch.failure("missing field `device_id` at line 2 column 3", PAYLOAD, Lang::De).is_some());
+    }
+
+    #[test]
+    fn success_resets_the_count() {
+        let mut watch = SchemaWatch::new(2);
+        assert_eq!(watch.failure("expected value", "<html>", Lang::De), None);
+        watch.success();
+        assert_eq!(watch.failure("expected value", "<html>", Lang::De), None);
+        assert!(watch.failure("expected value", "<html>", Lang::De).is_some());
+        // Nach erneutem Erfolg wird dasselbe Fehlerbild wieder gemeldet
+        watch.success();
+        assert_eq!(watch.failure("expected value", "<html>", Lang::De), None);
+        assert!(watch.failure("expected value", "<html>", Lang::De).is_some());
+    }
+
+    #[test]
+    fn report_compares_the_fields() {
+        let report = SchemaWatch::new(1).failure("missing field `device_id`", PAYLOAD, Lang::En).unwrap();
+        assert!(report.contains("Found fields: device, sensor_type, timestamp, value\n"), "{}", report);
+        assert!(report.contains("Missing: device_id\n"), "{}", report);
+        assert!(report.contains("Unknown: device\n"), "{}", report);
+        assert!(report.ends_with(&format!("Response:\n{}", PAYLOAD)), "{}", report);
+    }
+
+    #[test]
+    fn long_payload_is_cut_in_the_report() {
+        let payload = "ä".repeat(PAYLOAD_PREVIEW_CHARS + 20);
+        let report = SchemaWatch::new(1).failure("expected value", &payload, Lang::En).unwrap();
+        let preview = report.rsplit_once("Response:\n").unwrap().1;
+        assert_eq!(preview, format!("{} …", "ä".repeat(PAYLOAD_PREVIEW_CHARS)));
+
+        let exact = "x".repeat(PAYLOAD_PREVIEW_CHARS);
+        let report = SchemaWatch::new(1).failure("expected value", &exact, Lang::En).unwrap();
+        assert!(report.ends_with(&format!("Response:\n{}", exact)), "{}", report);
+    }
+}
@@ -0,0 +1,81 @@
+use crate::i18n::{self, Lang, Text};
+use crate::source::BoxFuture;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+// Längste Dauer je Prüfung; ein Sensor-Webserver braucht mit allen
+// Wiederholungen bis zu etwa 26 s
+const CHECK_TIMEOUT: Duration = Duration::from_secs(30);
+
+type CheckFn = Box<dyn Fn() -> BoxFuture<'static, Result<Text, Text>> + Send + Sync>;
+
+// Eine benannte Prüfung. Schlägt eine unverzichtbare fehl, arbeitet der Bot
+// nicht richtig; ohne die übrigen läuft er eingeschränkt weiter.
+struct Check {
+    name: Text,
+    essential: bool,
+    run: CheckFn,
+}
+
+// Ergebnis einer Prüfung: Ok mit kurzer Angabe, was gefunden wurde
+pub struct Outcome {
+    pub name: Text,
+    pub essential: bool,
+    pub result: Result<Text, Text>,
+    pub latency: Duration,
+}
+
+// Selbsttest beim Start und mit /selftest. Jedes Teilsystem meldet seine
+// Prüfungen mit `register` an; sie laufen nacheinander.
+#[derive(Default)]
+pub struct SelfTest {
+    checks: Vec<Check>,
+}
+
+impl SelfTest {
+    pub fn register<F, Fut>(&mut self, name: Text, essential: bool, check: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Text, Text>> + Send + 'static,
+    {
+        self.checks.push(Check { name, essential, run: Box::new(move || Box::pin(check())) });
+    }
+
+    pub async fn run(&self) -> Vec<Outcome> {
+        let mut outcomes = Vec::new();
+        for check in &self.checks {
+            let started = Instant::now();
+            let result = match tokio::time::timeout(CHECK_TIMEOUT, (check.run)()).await {
+                Ok(result) => result,
+                Err(_) => Err(Text::new("selftest_timeout", &[("seconds", &CHECK_TIMEOUT.as_secs().to_string())])),
+            };
+            outcomes.push(Outcome { name: check.name.clone(), essential: check.essential, result, latency: started.elapsed() });
+        }
+        outcomes
+    }
+}
+
+// Zusammenfassung für den Admin-Chat, eine Zeile je Prüfung
+pub fn summary(outcomes: &[Outcome], lang: Lang) -> String {
+    let failed = outcomes.iter().filter(|o| o.result.is_err()).count();
+    let broken = outcomes.iter().any(|o| o.essential && o.result.is_err());
+    let key = match (failed, broken) {
+        (0, _) => "selftest_passed",
+        (_, true) => "selftest_broken",
+        (_, false) => "selftest_degraded",
+    };
+    let mut text = i18n::message_with(lang, key, &[("failed", &failed.to_string()), ("count", &outcomes.len().to_string())]);
+    for outcome in outcomes {
+        let (symbol, detail) = match &outcome.result {
+            Ok(detail) => ("✅", detail),
+            Err(err) if outcome.essential => ("❌", err),
+            Err(err) => ("⚠️", err),
+        };
+        text.push_str(&format!("\n{} {} ({}): {}", symbol, outcome.name.in_lang(lang), latency(outcome.latency), detail.in_lang(lang)));
+    }
+    text
+}
+
+fn latency(elapsed: Duration) -> String {
+    if elapsed < Duration::from_secs(1) { format!("{} ms", elapsed.as_millis()) } else { format!("{:.1} s", elapsed.as_secs_f64()) }
+}
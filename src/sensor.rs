@@ -0,0 +1,183 @@
+use crate::i18n::Text;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+/// Messgröße eines Sensors. Unbekannte Typen bleiben als `Other` erhalten,
+/// damit neue Sensoren ohne Codeänderung durchlaufen.
+///
+/// Im JSON weiterhin als Zeichenkette ("temperature", "humidity", …).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+#[serde(from = "String", into = "String")]
+pub enum SensorKind {
+    Temperature,
+    Humidity,
+    Pressure,
+    Co2,
+    /// Aus Temperatur und Luftfeuchtigkeit berechnet, siehe `metrics`
+    DewPoint,
+    HeatIndex,
+    AbsoluteHumidity, // g/m³
+    /// Ladestand in Prozent
+    Battery,
+    // In Tests mit Unterstrich, so kommt kein eingebauter Typ heraus
+    Other(#[cfg_attr(test, proptest(regex = "[a-z]{2,8}_[a-z0-9]{1,6}"))] String),
+}
+
+impl SensorKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            SensorKind::Temperature => "temperature",
+            SensorKind::Humidity => "humidity",
+            SensorKind::Pressure => "pressure",
+            SensorKind::Co2 => "co2",
+            SensorKind::DewPoint => "dewpoint",
+            SensorKind::HeatIndex => "heatindex",
+            SensorKind::AbsoluteHumidity => "abshumidity",
+            SensorKind::Battery => "battery",
+            SensorKind::Other(other) => other,
+        }
+    }
+}
+
+impl From<&str> for SensorKind {
+    // Groß-/Kleinschreibung egal, damit "Temperature" und "temperature"
+    // nicht als zwei Typen nebeneinander stehen
+    fn from(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "temperature" => SensorKind::Temperature,
+            "humidity" => SensorKind::Humidity,
+            "pressure" => SensorKind::Pressure,
+            "co2" => SensorKind::Co2,
+            "dewpoint" => SensorKind::DewPoint,
+            "heatindex" => SensorKind::HeatIndex,
+            "abshumidity" => SensorKind::AbsoluteHumidity,
+            "battery" => SensorKind::Battery,
+            other => SensorKind::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for SensorKind {
+    fn from(value: String) -> Self {
+        SensorKind::from(value.as_str())
+    }
+}
+
+impl FromStr for SensorKind {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(SensorKind::from(s))
+    }
+}
+
+impl From<SensorKind> for String {
+    fn from(kind: SensorKind) -> Self {
+        kind.as_str().to_string()
+    }
+}
+
+impl fmt::Display for SensorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Richtung einer Schwelle: Alarm unter (`Min`) oder über (`Max`) dem Wert
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+#[serde(rename_all = "lowercase")]
+pub enum ThresholdDirection {
+    Min,
+    Max,
+}
+
+impl ThresholdDirection {
+    pub const ALL: [ThresholdDirection; 2] = [ThresholdDirection::Min, ThresholdDirection::Max];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ThresholdDirection::Min => "min",
+            ThresholdDirection::Max => "max",
+        }
+    }
+
+    pub fn opposite(self) -> ThresholdDirection {
+        match self {
+            ThresholdDirection::Min => ThresholdDirection::Max,
+            ThresholdDirection::Max => ThresholdDirection::Min,
+        }
+    }
+
+    pub fn is_min(self) -> bool {
+        self == ThresholdDirection::Min
+    }
+}
+
+impl FromStr for ThresholdDirection {
+    type Err = Text;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "min" => Ok(ThresholdDirection::Min),
+            "max" => Ok(ThresholdDirection::Max),
+            other => Err(Text::new("unknown_direction", &[("direction", other)])),
+        }
+    }
+}
+
+impl fmt::Display for ThresholdDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Schwelle eines Geräts: Messgröße und Richtung. Gespeichert wie bisher
+/// als "<typ>_<min|max>", z.B. "humidity_max".
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+pub struct ThresholdKey {
+    pub kind: SensorKind,
+    pub direction: ThresholdDirection,
+}
+
+impl ThresholdKey {
+    pub fn new(kind: SensorKind, direction: ThresholdDirection) -> ThresholdKey {
+        ThresholdKey { kind, direction }
+    }
+
+    pub fn opposite(&self) -> ThresholdKey {
+        ThresholdKey::new(self.kind.clone(), self.direction.opposite())
+    }
+}
+
+impl FromStr for ThresholdKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, direction) =
+            s.rsplit_once('_').filter(|(kind, _)| !kind.trim().is_empty()).ok_or_else(|| format!("Schwelle '{}' ungültig (<typ>_min oder <typ>_max)", s))?;
+        Ok(ThresholdKey::new(SensorKind::from(kind), direction.parse().map_err(|err: Text| err.to_string())?))
+    }
+}
+
+impl fmt::Display for ThresholdKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}_{}", self.kind, self.direction)
+    }
+}
+
+impl Serialize for ThresholdKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ThresholdKey {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
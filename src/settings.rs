@@ -0,0 +1,356 @@
+use crate::plausibility::Limits;
+use crate::sensor::SensorKind;
+use crate::weather::Location;
+use chrono::NaiveTime;
+use chrono_tz::Tz;
+use log::warn;
+use std::env;
+use std::path::PathBuf;
+
+// Liegen Ende der Ruhezeit und geplanter Bericht so nah beieinander,
+// kommen verpasste Warnungen und Bericht in einer Nachricht (DIGEST_MERGE_MINUTES)
+const DEFAULT_DIGEST_MERGE_MINUTES: i64 = 15;
+// Admin-Warnung, wenn ein Gerät länger als Faktor × üblicher Abstand schweigt (CADENCE_FACTOR)
+const DEFAULT_CADENCE_FACTOR: f64 = 3.0;
+// Längste erlaubte Stummschaltung per /mute-all (MUTE_MAX_HOURS)
+const DEFAULT_MUTE_MAX_HOURS: i64 = 48;
+// Nach so vielen gleichen Parse-Fehlern in Folge wird der Admin benachrichtigt (SCHEMA_ALARM_AFTER)
+const DEFAULT_SCHEMA_ALARM_AFTER: u32 = 3;
+// Nach einem Alarm wird das Gerät so oft abgefragt (ESCALATE_INTERVAL_SECONDS) …
+const DEFAULT_ESCALATE_INTERVAL_SECONDS: u64 = 2 * 60;
+// … und zwar höchstens so lange (ESCALATE_MINUTES); 0 schaltet das ab
+const DEFAULT_ESCALATE_MINUTES: u64 = 30;
+// So viele nächtliche Sicherungen bleiben erhalten (BACKUP_KEEP)
+const DEFAULT_BACKUP_KEEP: usize = 7;
+// Schwellen ohne Messwerte werden nach so vielen Stunden gemeldet (UNMONITORED_GRACE_HOURS)
+const DEFAULT_UNMONITORED_GRACE_HOURS: i64 = 24;
+// Gültigkeit der Links auf Diagramme in voller Auflösung (CHART_TTL_MINUTES) …
+const DEFAULT_CHART_TTL_MINUTES: u64 = 60;
+// … und Obergrenze ihres Zwischenspeichers (CHART_CACHE_MB)
+const DEFAULT_CHART_CACHE_MB: usize = 32;
+// Vorschlag für eine neue Schwelle nach so vielen Tagen Dauerverletzung (LONG_VIOLATION_DAYS)
+const DEFAULT_LONG_VIOLATION_DAYS: i64 = 14;
+// /status ruft die Quelle nur ab, wenn der letzte Stand älter ist (STATUS_MAX_AGE_SECONDS)
+const DEFAULT_STATUS_MAX_AGE_SECONDS: i64 = 120;
+// Warnung, wenn der neueste Messwert eines Geräts älter ist (STALE_AFTER_MINUTES)
+const DEFAULT_STALE_AFTER_MINUTES: i64 = 30;
+// Abfrageintervall der Überwachung (POLL_INTERVAL_SECONDS), zur Laufzeit per /set-interval
+pub(crate) const DEFAULT_POLL_INTERVAL_SECONDS: u64 = 10 * 60;
+// Kürzer nicht, das schont den Sensor-Webserver
+pub(crate) const MIN_POLL_INTERVAL_SECONDS: u64 = 5;
+// Ein Alarm endet erst, wenn der Wert so weit zurück im Bereich ist (HYSTERESIS)
+const DEFAULT_HYSTERESIS: f64 = 0.5;
+// Zustellversuche je Hintergrund-Nachricht, bevor sie verworfen wird (OUTBOX_MAX_ATTEMPTS)
+const DEFAULT_OUTBOX_MAX_ATTEMPTS: u32 = 5;
+// Tage, nach denen die Daten eines Chats gelöscht werden, der den Bot blockiert hat (BLOCKED_PURGE_DAYS)
+const DEFAULT_BLOCKED_PURGE_DAYS: i64 = 30;
+// Befehle je Chat und Minute, darüber bremst der Bot (COMMAND_RATE_PER_MINUTE)
+const DEFAULT_COMMAND_RATE_PER_MINUTE: u32 = 5;
+// Nach so vielen Abfragen ohne Wert gilt ein Sensor als verschwunden (SENSOR_MISSING_AFTER)
+const DEFAULT_SENSOR_MISSING_AFTER: u32 = 3;
+// Batterie in Prozent, unter der gewarnt wird, bis ein Chat per /battery etwas anderes wählt (BATTERY_LOW)
+const DEFAULT_BATTERY_LOW: f64 = 20.0;
+// Zweifelhafte Messwerte gelten nach so vielen in Folge als echt (PLAUSIBILITY_CONFIRM_AFTER)
+const DEFAULT_PLAUSIBILITY_CONFIRM_AFTER: u32 = 3;
+
+/// Einstellungen des Bots.
+///
+/// `from_env` liest die bekannten Umgebungsvariablen, `from_env_checked`
+/// lehnt dabei fehlerhafte ab, `Default` entspricht einer leeren Umgebung. Stabilität: Felder können in neuen Versionen
+/// hinzukommen, deshalb mit `..Settings::default()` aufbauen.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// Zeitzone für Schwellen-Zeitfenster und Berichte (DEFAULT_TZ), sonst Systemzeit
+    pub timezone: Option<Tz>,
+    /// Raumverzeichnis (ROOMS_FILE), sonst eingebaute Zuordnung
+    pub rooms_file: Option<PathBuf>,
+    /// Antworten auf Freitext (REPLIES_FILE), sonst eingebaute Antworten
+    pub replies_file: Option<PathBuf>,
+    /// Chat für Betriebsmeldungen (ADMIN_CHAT_ID)
+    pub admin_chat: Option<i64>,
+    /// Chats, die den Bot benutzen dürfen (ALLOWED_CHAT_IDS, durch Komma
+    /// getrennt). Leer und ohne /allow ist der Bot für alle offen.
+    pub allowed_chats: Vec<i64>,
+    /// Sperrdatei gegen doppelte Instanzen (LOCK_FILE); None = keine Sperre
+    pub lock_file: Option<PathBuf>,
+    pub digest_merge_minutes: i64,
+    pub cadence_factor: f64,
+    pub mute_max_hours: i64,
+    pub schema_alarm_after: u32,
+    /// Abfrageintervall eines Geräts nach einem Alarm
+    pub escalate_interval_seconds: u64,
+    /// Dauer der häufigeren Abfrage, 0 = aus
+    pub escalate_minutes: u64,
+    /// Verzeichnis für nächtliche Sicherungen (BACKUP_DIR), None = keine
+    pub backup_dir: Option<PathBuf>,
+    pub backup_keep: usize,
+    /// Uhrzeit der Sicherung in der konfigurierten Zeitzone (BACKUP_AT, HH:MM)
+    pub backup_at: NaiveTime,
+    /// Adresse des eingebauten HTTP-Servers (HTTP_ADDR), None = aus
+    pub http_addr: Option<String>,
+    /// Öffentliche Basis-URL für Links auf den HTTP-Server (HTTP_PUBLIC_URL)
+    pub http_public_url: String,
+    /// Token für POST /readings (PUSH_TOKEN), None = Endpunkt aus
+    pub push_token: Option<String>,
+    /// Port für /metrics im Format von Prometheus (METRICS_PORT), None = aus
+    pub metrics_port: Option<u16>,
+    /// Lese-Schlüssel für private ThingSpeak-Kanäle (THINGSPEAK_READ_KEY)
+    pub thingspeak_read_key: Option<String>,
+    /// Standort für den Vergleich mit dem Wetter draußen in /status
+    /// (WEATHER_LOCATION, "<breite>,<länge>"), None = aus
+    pub weather_location: Option<Location>,
+    /// Chat-IDs und Namen in Logs schwärzen (LOG_REDACT)
+    pub log_redact: bool,
+    /// Wartezeit, bevor eine Schwelle ohne Messwerte dem Besitzer gemeldet wird
+    pub unmonitored_grace_hours: i64,
+    /// Gültigkeit der Diagramm-Links unter /charts
+    pub chart_ttl_minutes: u64,
+    pub chart_cache_mb: usize,
+    /// Raumbilder und ihre lokalen Kopien (ROOM_IMAGES_DIR)
+    pub room_images_dir: PathBuf,
+    /// Messwerte zusätzlich in SQLite (DATABASE_PATH, Feature `sqlite`)
+    pub database_path: Option<PathBuf>,
+    /// Ab so vielen Tagen Dauerverletzung schlägt der Bot eine neue Schwelle vor
+    /// (LONG_VIOLATION_DAYS), 0 = aus
+    pub long_violation_days: i64,
+    /// Abstand zur Schwelle, ab dem ein Alarm als erholt gilt, sofern die
+    /// Schwelle keinen eigenen hat (HYSTERESIS, in der Einheit des Messwerts)
+    pub hysteresis: f64,
+    /// Höchstalter der zwischengespeicherten Messwerte für /status; 0 = immer abrufen
+    pub status_max_age_seconds: i64,
+    /// Höchstalter des neuesten Messwerts eines Geräts, bevor die Besitzer
+    /// seiner Schwellen gewarnt werden; 0 = aus
+    pub stale_after_minutes: i64,
+    /// Abstand der regulären Abfragen aller Quellen ohne eigenes Intervall
+    /// (POLL_INTERVAL_SECONDS), mindestens 5 Sekunden
+    pub poll_interval_seconds: u64,
+    /// Zustellversuche für Warnungen und Berichte, mindestens 1
+    pub outbox_max_attempts: u32,
+    /// Tage, nach denen Schwellen und Einstellungen eines Chats gelöscht
+    /// werden, der den Bot blockiert hat; 0 = nie
+    pub blocked_purge_days: i64,
+    /// Befehle je Chat und Minute, bevor der Bot bremst; 0 = unbegrenzt.
+    /// Der Admin-Chat ist ausgenommen.
+    pub command_rate_per_minute: u32,
+    /// Reguläre Abfragen in Folge ohne Wert, nach denen ein Sensor als
+    /// verschwunden gemeldet wird; 0 = keine Meldungen zu neuen und
+    /// verschwundenen Sensoren
+    pub sensor_missing_after: u32,
+    /// Ladestand in Prozent, unter dem vor schwacher Batterie gewarnt wird,
+    /// solange ein Chat mit /battery nichts anderes gewählt hat
+    pub battery_low: f64,
+    /// Eigene Grenzen je Messgröße statt der eingebauten (PLAUSIBLE_RANGES,
+    /// z.B. "temperature=-30..60:8,humidity=0..100"); Werte außerhalb oder mit
+    /// einem größeren Sprung zum letzten Wert gelten als Ausreißer
+    pub plausible_ranges: Vec<(SensorKind, Limits)>,
+    /// Ausreißer in Folge, nach denen der Wert doch angenommen wird; 0 = keine Prüfung
+    pub plausibility_confirm_after: u32,
+    /// Admin benachrichtigen, wenn ein zweifelhafter Wert angenommen wird (PLAUSIBILITY_NOTIFY)
+    pub plausibility_notify: bool,
+    /// Laufende Verletzungen nach einem Neustart erneut melden (ANNOUNCE_ON_START
+    /// oder --announce-on-start); sonst gelten sie als schon gemeldet
+    pub announce_on_start: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            timezone: None,
+            rooms_file: None,
+            replies_file: None,
+            admin_chat: None,
+            allowed_chats: Vec::new(),
+            lock_file: Some("bot.lock".into()),
+            digest_merge_minutes: DEFAULT_DIGEST_MERGE_MINUTES,
+            cadence_factor: DEFAULT_CADENCE_FACTOR,
+            mute_max_hours: DEFAULT_MUTE_MAX_HOURS,
+            schema_alarm_after: DEFAULT_SCHEMA_ALARM_AFTER,
+            escalate_interval_seconds: DEFAULT_ESCALATE_INTERVAL_SECONDS,
+            escalate_minutes: DEFAULT_ESCALATE_MINUTES,
+            backup_dir: None,
+            backup_keep: DEFAULT_BACKUP_KEEP,
+            backup_at: NaiveTime::from_hms_opt(3, 0, 0).unwrap_or_default(),
+            http_addr: None,
+            http_public_url: String::new(),
+            push_token: None,
+            metrics_port: None,
+            thingspeak_read_key: None,
+            weather_location: None,
+            log_redact: false,
+            unmonitored_grace_hours: DEFAULT_UNMONITORED_GRACE_HOURS,
+            chart_ttl_minutes: DEFAULT_CHART_TTL_MINUTES,
+            chart_cache_mb: DEFAULT_CHART_CACHE_MB,
+            room_images_dir: "room-images".into(),
+            database_path: None,
+            long_violation_days: DEFAULT_LONG_VIOLATION_DAYS,
+            hysteresis: DEFAULT_HYSTERESIS,
+            status_max_age_seconds: DEFAULT_STATUS_MAX_AGE_SECONDS,
+            stale_after_minutes: DEFAULT_STALE_AFTER_MINUTES,
+            poll_interval_seconds: DEFAULT_POLL_INTERVAL_SECONDS,
+            outbox_max_attempts: DEFAULT_OUTBOX_MAX_ATTEMPTS,
+            blocked_purge_days: DEFAULT_BLOCKED_PURGE_DAYS,
+            command_rate_per_minute: DEFAULT_COMMAND_RATE_PER_MINUTE,
+            sensor_missing_after: DEFAULT_SENSOR_MISSING_AFTER,
+            battery_low: DEFAULT_BATTERY_LOW,
+            plausible_ranges: Vec::new(),
+            plausibility_confirm_after: DEFAULT_PLAUSIBILITY_CONFIRM_AFTER,
+            plausibility_notify: true,
+            announce_on_start: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Wie [`Settings::from_env_checked`], aber fehlerhafte Angaben werden
+    /// nur protokolliert und durch den Standardwert ersetzt.
+    pub fn from_env() -> Settings {
+        let mut vars = Env::default();
+        let settings = Settings::read(&mut vars);
+        for problem in vars.problems {
+            warn!("{}, verwende den Standardwert", problem);
+        }
+        settings
+    }
+
+    /// Liest die Umgebungsvariablen und meldet alle gesetzten, aber
+    /// unlesbaren oder unzulässigen auf einmal, je eine Zeile.
+    pub fn from_env_checked() -> Result<Settings, Vec<String>> {
+        let mut vars = Env::default();
+        let settings = Settings::read(&mut vars);
+        if vars.problems.is_empty() { Ok(settings) } else { Err(vars.problems) }
+    }
+
+    fn read(vars: &mut Env) -> Settings {
+        let defaults = Settings::default();
+        Settings {
+            timezone: vars.parsed("DEFAULT_TZ"),
+            rooms_file: env::var("ROOMS_FILE").ok().map(PathBuf::from),
+            replies_file: env::var("REPLIES_FILE").ok().map(PathBuf::from),
+            admin_chat: vars.parsed("ADMIN_CHAT_ID"),
+            allowed_chats: allowed_chats(vars),
+            lock_file: env::var("LOCK_FILE").ok().map(PathBuf::from).or(defaults.lock_file),
+            digest_merge_minutes: vars.parsed("DIGEST_MERGE_MINUTES").unwrap_or(defaults.digest_merge_minutes),
+            cadence_factor: vars.parsed("CADENCE_FACTOR").unwrap_or(defaults.cadence_factor),
+            mute_max_hours: vars.parsed("MUTE_MAX_HOURS").unwrap_or(defaults.mute_max_hours),
+            schema_alarm_after: vars.parsed("SCHEMA_ALARM_AFTER").unwrap_or(defaults.schema_alarm_after),
+            escalate_interval_seconds: vars.parsed("ESCALATE_INTERVAL_SECONDS").unwrap_or(defaults.escalate_interval_seconds),
+            escalate_minutes: vars.parsed("ESCALATE_MINUTES").unwrap_or(defaults.escalate_minutes),
+            backup_dir: env::var("BACKUP_DIR").ok().map(PathBuf::from),
+            backup_keep: vars.parsed("BACKUP_KEEP").unwrap_or(defaults.backup_keep),
+            backup_at: vars.with("BACKUP_AT", "keine Uhrzeit (HH:MM)", |at| NaiveTime::parse_from_str(at, "%H:%M").ok()).unwrap_or(defaults.backup_at),
+            http_addr: env::var("HTTP_ADDR").ok(),
+            http_public_url: env::var("HTTP_PUBLIC_URL").unwrap_or_default(),
+            push_token: env::var("PUSH_TOKEN").ok().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()),
+            metrics_port: vars.checked("METRICS_PORT", |port| *port > 0, "1 bis 65535"),
+            thingspeak_read_key: env::var("THINGSPEAK_READ_KEY").ok().map(|k| k.trim().to_string()).filter(|k| !k.is_empty()),
+            weather_location: vars.with("WEATHER_LOCATION", "kein Standort (<breite>,<länge>)", |value| value.parse().ok()),
+            log_redact: env::var("LOG_REDACT").is_ok_and(|v| v.eq_ignore_ascii_case("true") || v == "1"),
+            unmonitored_grace_hours: vars.parsed("UNMONITORED_GRACE_HOURS").unwrap_or(defaults.unmonitored_grace_hours),
+            chart_ttl_minutes: vars.parsed("CHART_TTL_MINUTES").unwrap_or(defaults.chart_ttl_minutes),
+            chart_cache_mb: vars.parsed("CHART_CACHE_MB").unwrap_or(defaults.chart_cache_mb),
+            room_images_dir: env::var("ROOM_IMAGES_DIR").ok().map(PathBuf::from).unwrap_or(defaults.room_images_dir),
+            database_path: env::var("DATABASE_PATH").ok().filter(|path| !path.is_empty()).map(PathBuf::from),
+            long_violation_days: vars.parsed("LONG_VIOLATION_DAYS").unwrap_or(defaults.long_violation_days),
+            stale_after_minutes: vars.parsed("STALE_AFTER_MINUTES").unwrap_or(defaults.stale_after_minutes),
+            poll_interval_seconds: match vars.parsed::<u64>("POLL_INTERVAL_SECONDS") {
+                // Zu kurz gilt das Minimum, nicht der Standardwert
+                Some(seconds) if seconds < MIN_POLL_INTERVAL_SECONDS => {
+                    vars.problems.push(format!("POLL_INTERVAL_SECONDS: {} unter {} Sekunden", seconds, MIN_POLL_INTERVAL_SECONDS));
+                    MIN_POLL_INTERVAL_SECONDS
+                }
+                Some(seconds) => seconds,
+                None => defaults.poll_interval_seconds,
+            },
+            status_max_age_seconds: vars.parsed("STATUS_MAX_AGE_SECONDS").unwrap_or(defaults.status_max_age_seconds),
+            hysteresis: vars.checked("HYSTERESIS", |h: &f64| h.is_finite() && *h >= 0.0, "mindestens 0").unwrap_or(defaults.hysteresis),
+            outbox_max_attempts: vars.parsed::<u32>("OUTBOX_MAX_ATTEMPTS").map(|n| n.max(1)).unwrap_or(defaults.outbox_max_attempts),
+            blocked_purge_days: vars.parsed::<i64>("BLOCKED_PURGE_DAYS").map(|days| days.max(0)).unwrap_or(defaults.blocked_purge_days),
+            command_rate_per_minute: vars.parsed::<u32>("COMMAND_RATE_PER_MINUTE").unwrap_or(defaults.command_rate_per_minute),
+            sensor_missing_after: vars.parsed::<u32>("SENSOR_MISSING_AFTER").unwrap_or(defaults.sensor_missing_after),
+            battery_low: vars.checked("BATTERY_LOW", |low: &f64| (0.0..=100.0).contains(low), "0 bis 100").unwrap_or(defaults.battery_low),
+            plausible_ranges: plausible_ranges(vars),
+            plausibility_confirm_after: vars.parsed("PLAUSIBILITY_CONFIRM_AFTER").unwrap_or(defaults.plausibility_confirm_after),
+            plausibility_notify: vars.parsed("PLAUSIBILITY_NOTIFY").unwrap_or(defaults.plausibility_notify),
+            announce_on_start: vars.parsed("ANNOUNCE_ON_START").unwrap_or(defaults.announce_on_start),
+        }
+    }
+}
+
+// Ungültige Einträge werden gemeldet und übersprungen
+fn allowed_chats(vars: &mut Env) -> Vec<i64> {
+    let Ok(list) = env::var("ALLOWED_CHAT_IDS") else { return Vec::new() };
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| match entry.parse() {
+            Ok(id) => Some(id),
+            Err(_) => {
+                vars.problems.push(format!("ALLOWED_CHAT_IDS: '{}' ist keine Chat-ID", entry));
+                None
+            }
+        })
+        .collect()
+}
+
+// "<typ>=<von>..<bis>[:<sprung>]" durch Komma getrennt; ungültige Einträge
+// werden gemeldet und übersprungen
+fn plausible_ranges(vars: &mut Env) -> Vec<(SensorKind, Limits)> {
+    let Ok(list) = env::var("PLAUSIBLE_RANGES") else { return Vec::new() };
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let parsed = entry
+                .split_once('=')
+                .ok_or_else(|| "erwartet <typ>=<von>..<bis>".to_string())
+                .and_then(|(kind, limits)| Ok((SensorKind::from(kind.trim()), limits.parse()?)));
+            parsed.map_err(|err| vars.problems.push(format!("PLAUSIBLE_RANGES: '{}': {}", entry, err))).ok()
+        })
+        .collect()
+}
+
+// Sammelt fehlerhafte Angaben, statt beim ersten Fehler abzubrechen.
+// Nicht gesetzte Variablen sind kein Fehler.
+#[derive(Default)]
+struct Env {
+    problems: Vec<String>,
+}
+
+impl Env {
+    fn with<T>(&mut self, name: &str, expected: &str, parse: impl Fn(&str) -> Option<T>) -> Option<T> {
+        let value = env::var(name).ok()?;
+        let parsed = parse(value.trim());
+        if parsed.is_none() {
+            self.problems.push(format!("{}: '{}' ist {}", name, value, expected));
+        }
+        parsed
+    }
+
+    fn parsed<T: std::str::FromStr>(&mut self, name: &str) -> Option<T> {
+        self.with(name, "kein gültiger Wert", |value| value.parse().ok())
+    }
+
+    fn checked<T: std::str::FromStr + std::fmt::Display>(&mut self, name: &str, valid: impl Fn(&T) -> bool, range: &str) -> Option<T> {
+        let value = self.parsed(name)?;
+        if valid(&value) {
+            return Some(value);
+        }
+        self.problems.push(format!("{}: {} außerhalb des erlaubten Bereichs ({})", name, value, range));
+        None
+    }
+}
+
+/// Bot-Token aus TELEGRAMBOT_TOKEN oder, etwa für Docker-Secrets, aus der
+/// Datei in TELEGRAMBOT_TOKEN_FILE.
+pub fn token_from_env() -> Result<String, String> {
+    let token = match (env::var("TELEGRAMBOT_TOKEN"), env::var("TELEGRAMBOT_TOKEN_FILE")) {
+        (Ok(token), _) => token,
+        (Err(_), Ok(path)) => std::fs::read_to_string(&path).map_err(|err| format!("TELEGRAMBOT_TOKEN_FILE: {} nicht lesbar: {}", path, err))?,
+        (Err(_), Err(_)) => return Err("TELEGRAMBOT_TOKEN oder TELEGRAMBOT_TOKEN_FILE muss gesetzt sein".to_string()),
+    };
+    let token = token.trim();
+    if token.is_empty() {
+        return Err("Bot-Token ist leer".to_string());
+    }
+    Ok(token.to_string())
+}
@@ -0,0 +1,102 @@
+// Fragen in Freitext, die wie ein Befehl behandelt werden: (Wendung, Befehl).
+// Verglichen wird klein geschrieben irgendwo im Text; die erste passende gilt.
+const INTENTS: &[(&str, &str)] = &[
+    ("wie warm", "status"),
+    ("wie kalt", "status"),
+    ("wie feucht", "status"),
+    ("wie ist die temperatur", "status"),
+    ("wie ist die luftfeuchtigkeit", "status"),
+    ("how warm", "status"),
+    ("how cold", "status"),
+    ("how humid", "status"),
+    ("welche schwellen", "thresholds"),
+    ("welche grenzwerte", "thresholds"),
+    ("which thresholds", "thresholds"),
+    ("welche alarme", "alarms"),
+    ("was war los", "alarms"),
+    ("any alarms", "alarms"),
+    ("was kannst du", "help"),
+    ("what can you do", "help"),
+];
+
+// Nur für den Befehl "status" wird ein Raum im Text gesucht
+pub const ROOM_INTENT: &str = "status";
+
+// Höchstens so viele Vorschläge für einen unbekannten Befehl
+const MAX_SUGGESTIONS: usize = 3;
+// Tippfehler mit höchstens so vielen geänderten Zeichen
+const MAX_TYPO_DISTANCE: usize = 2;
+
+pub fn intent(text: &str) -> Option<&'static str> {
+    let text = text.to_lowercase();
+    INTENTS.iter().find(|(phrase, _)| text.contains(phrase)).map(|(_, command)| *command)
+}
+
+// Befehle, die zu einer unbekannten Eingabe passen könnten: erst solche, die
+// mit ihr beginnen, sonst solche mit wenigen Tippfehlern, die nächsten zuerst
+pub fn suggest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let name = name.to_lowercase();
+    let candidates: Vec<&str> = candidates.into_iter().collect();
+    let mut found: Vec<&str> = candidates.iter().copied().filter(|c| c.starts_with(&name)).collect();
+    if found.is_empty() {
+        let mut close: Vec<(usize, &str)> = candidates
+            .iter()
+            .map(|c| (distance(&name, c), *c))
+            .filter(|(distance, _)| *distance <= MAX_TYPO_DISTANCE && *distance < name.chars().count())
+            .collect();
+        close.sort();
+        found = close.into_iter().map(|(_, c)| c).collect();
+    }
+    found.dedup();
+    found.truncate(MAX_SUGGESTIONS);
+    found
+}
+
+// Levenshtein-Abstand in Zeichen
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            current.push((previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMANDS: [&str; 6] = ["status", "schedule", "schedules", "snooze", "unsnooze", "thresholds"];
+
+    #[test]
+    fn prefix_of_several_commands_lists_them() {
+        assert_eq!(suggest("sch", COMMANDS), ["schedule", "schedules"]);
+        assert_eq!(suggest("S", COMMANDS), ["status", "schedule", "schedules"]);
+    }
+
+    #[test]
+    fn unique_prefix_or_typo_gives_one() {
+        assert_eq!(suggest("thres", COMMANDS), ["thresholds"]);
+        assert_eq!(suggest("stauts", COMMANDS), ["status"]);
+        assert_eq!(suggest("snoze", COMMANDS), ["snooze"]);
+    }
+
+    #[test]
+    fn nothing_close_gives_none() {
+        assert!(suggest("wetter", COMMANDS).is_empty());
+        // Zwei Tippfehler in zwei Zeichen wären jeder Befehl
+        assert!(suggest("xy", COMMANDS).is_empty());
+    }
+
+    #[test]
+    fn intents_match_anywhere_in_the_text() {
+        assert_eq!(intent("Sag mal, wie warm ist es im Bad?"), Some("status"));
+        assert_eq!(intent("WHICH THRESHOLDS do I have"), Some("thresholds"));
+        assert_eq!(intent("hallo"), None);
+    }
+}
@@ -0,0 +1,180 @@
+// Offline-Auswertung: Hätten meine Schwellwerte im Verlauf ausgelöst?
+//
+//   telegrambot simulate --chat-id 123 --set "wohnzimmer temperature min 19" --since 14d
+//
+// Liest gespeicherte Konfiguration und Verlauf, ändert nichts und sendet nichts.
+use crate::monitor::{self, EventKind};
+use crate::sensor::{SensorKind, ThresholdDirection, ThresholdKey};
+use crate::storage::{JsonStore, Store};
+use crate::thresholds::ThresholdArgs;
+use crate::timeutil;
+use crate::uptime;
+use crate::{SensorData, UserConfig, format_duration, format_timestamp, local_time_of, parse_duration, resolve_device, room_name, type_label};
+use chrono::{Duration, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+pub const USAGE: &str = "Verwendung: simulate --chat-id <id> [--set \"<gerät> <typ> <min|max> <wert> [HH:MM-HH:MM]\"]... [--since 14d]";
+
+const DEFAULT_SINCE: &str = "7d";
+
+struct Override {
+    spec: String,
+    direction: ThresholdDirection,
+    args: ThresholdArgs,
+}
+
+struct Options {
+    chat_id: i64,
+    overrides: Vec<Override>,
+    since: Duration,
+}
+
+// "<gerät> <typ> <min|max> <wert> [HH:MM-HH:MM]"
+fn parse_override(spec: &str) -> Result<Override, String> {
+    let parts: Vec<&str> = spec.split_whitespace().collect();
+    let direction = parts.get(2).and_then(|d| d.parse::<ThresholdDirection>().ok());
+    let (Some(direction), true) = (direction, parts.len() >= 4) else {
+        return Err(format!("--set \"{}\": erwartet <gerät> <typ> <min|max> <wert> [HH:MM-HH:MM]", spec));
+    };
+    let rest: Vec<&str> = parts.iter().enumerate().filter(|(i, _)| *i != 2).map(|(_, p)| *p).collect();
+    let args = rest.join(" ").parse::<ThresholdArgs>().map_err(|err| format!("--set \"{}\": {}", spec, err))?;
+    Ok(Override { spec: spec.to_string(), direction, args })
+}
+
+// "14d", "36h", "90m"
+fn parse_since(s: &str) -> Result<Duration, String> {
+    parse_duration(s).ok_or_else(|| format!("--since '{}': erwartet z.B. 14d, 36h oder 90m", s))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut chat_id = None;
+    let mut overrides = Vec::new();
+    let mut since = parse_since(DEFAULT_SINCE)?;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} ohne Wert", arg));
+        match arg.as_str() {
+            "--chat-id" => {
+                let id = value()?;
+                chat_id = Some(id.parse::<i64>().map_err(|_| format!("--chat-id '{}' ist keine Zahl", id))?);
+            }
+            "--set" => overrides.push(parse_override(value()?)?),
+            "--since" => since = parse_since(value()?)?,
+            _ => return Err(format!("Unbekanntes Argument '{}'", arg)),
+        }
+    }
+
+    let chat_id = chat_id.ok_or("--chat-id fehlt")?;
+    Ok(Options { chat_id, overrides, since })
+}
+
+fn threshold_name(device_id: &str, sensor_type: &SensorKind, direction: ThresholdDirection) -> String {
+    format!("{} {} {}", room_name(device_id), type_label(sensor_type.as_str()).0, direction.as_str().to_uppercase())
+}
+
+#[derive(Default)]
+struct Summary {
+    alarms: usize,
+    violation_seconds: i64,
+    open_since: Option<i64>,
+}
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let options = parse_options(args)?;
+    let storage = JsonStore::from_env();
+
+    // Echte Konfiguration des Chats, darüber die Überschreibungen
+    let mut config = storage.load_users().remove(&options.chat_id).unwrap_or_default();
+    for o in &options.overrides {
+        let device = resolve_device(&o.args.device);
+        let key = (device, ThresholdKey::new(o.args.sensor_type.clone(), o.direction));
+        config
+            .thresholds
+            .entry(key)
+            .or_default()
+            .set(o.args.value, o.args.window, format!("simulate --set \"{}\"", o.spec))
+            .map_err(|err| format!("--set \"{}\": {}", o.spec, err))?;
+    }
+    if config.thresholds.is_empty() {
+        return Err(format!("Chat {} hat keine Schwellwerte. Mit --set angeben.", options.chat_id));
+    }
+    let configs: HashMap<i64, UserConfig> = HashMap::from([(options.chat_id, config)]);
+
+    let now = Utc::now();
+    let since = now.checked_sub_signed(options.since).ok_or_else(|| "--since reicht zu weit zurück".to_string())?.timestamp();
+    let history = storage.load_history();
+    // Ältere Zeiträume nur als 5-Minuten- bzw. Stundenmittel
+    let samples: Vec<(&str, &str, i64, f64)> =
+        history.points_since(since).into_iter().map(|(device, typ, bucket)| (device, typ, bucket.start, bucket.mean)).collect();
+    // Während der Bot nicht lief, zählt eine Verletzung nicht mit
+    let offline: Vec<(i64, i64)> = storage.load_uptime().downtime().iter().map(|d| (d.start, d.end)).collect();
+    let violation = |start: i64, end: i64| (end - start) - uptime::overlap(start, end, &offline);
+
+    println!(
+        "Simulation für Chat {}: {} – {} ({} Messwerte)",
+        options.chat_id,
+        format_timestamp(since, "%d.%m.%Y %H:%M"),
+        format_timestamp(now.timestamp(), "%d.%m.%Y %H:%M"),
+        samples.len()
+    );
+    for o in &options.overrides {
+        println!("Überschrieben: {}", o.spec);
+    }
+    println!();
+
+    let mut flags = monitor::Flags::new();
+    let mut summaries: BTreeMap<String, Summary> = BTreeMap::new();
+    let mut last_timestamp = since;
+
+    for (device_id, sensor_type, timestamp, value) in samples {
+        last_timestamp = timestamp;
+        let reading = SensorData { device_id: device_id.to_string(), sensor_type: SensorKind::from(sensor_type), value, timestamp };
+        let local = local_time_of(timeutil::from_timestamp(timestamp));
+
+        for event in monitor::evaluate(&configs, &[reading], &mut flags, local) {
+            let name = threshold_name(&event.device_id, &event.sensor_type, event.direction);
+            let unit = type_label(event.sensor_type.as_str()).1;
+            let zeit = format_timestamp(event.timestamp, "%d.%m. %H:%M");
+            let summary = summaries.entry(name.clone()).or_default();
+
+            match event.kind {
+                EventKind::Alarm => {
+                    summary.alarms += 1;
+                    summary.open_since = Some(event.timestamp);
+                    println!("{}  ⚠ {}: {:.1} {} (Schwelle {:.1} {})", zeit, name, event.value, unit, event.threshold.unwrap_or_default(), unit);
+                }
+                EventKind::Recovered | EventKind::Deactivated => {
+                    let dauer = summary.open_since.take().map(|start| violation(start, event.timestamp)).unwrap_or_default();
+                    summary.violation_seconds += dauer;
+                    let grund = if event.kind == EventKind::Recovered { format!("{:.1} {}", event.value, unit) } else { "Zeitfenster verlassen".to_string() };
+                    println!("{}  ✅ {}: {} (nach {})", zeit, name, grund, format_duration(dauer));
+                }
+            }
+        }
+    }
+
+    println!();
+    let offline_total = uptime::overlap(since, now.timestamp(), &offline);
+    if offline_total > 0 {
+        println!("Bot offline im Zeitraum: {} (nicht als Verletzung gezählt)", format_duration(offline_total));
+    }
+    if summaries.is_empty() {
+        println!("Keine Alarme im Zeitraum.");
+        return Ok(());
+    }
+    println!("Zusammenfassung:");
+    for (name, summary) in &mut summaries {
+        // Noch offene Alarme zählen bis zum letzten Messwert
+        let open = summary.open_since.map(|start| violation(start, last_timestamp));
+        summary.violation_seconds += open.unwrap_or_default();
+        println!(
+            "{}: {} Alarm(e), {} verletzt{}",
+            name,
+            summary.alarms,
+            format_duration(summary.violation_seconds),
+            if open.is_some() { " (dauert an)" } else { "" }
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use crate::SensorData;
+use crate::sensor::SensorKind;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+// Zuletzt per /status oder /diff angesehene Werte eines Chats
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+pub struct Snapshot {
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::datetime()"))]
+    pub at: DateTime<Utc>,
+    #[serde(with = "crate::storage::keyed_map")]
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::keyed(proptest::prelude::any::<f64>())"))]
+    pub values: HashMap<(String, SensorKind), f64>, // (Gerät, Typ) -> Wert
+}
+
+impl Snapshot {
+    pub fn capture(sensor_data: &[SensorData], at: DateTime<Utc>) -> Snapshot {
+        let values = sensor_data.iter().map(|e| ((e.device_id.clone(), e.sensor_type.clone()), e.value)).collect();
+        Snapshot { at, values }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Changed { before: f64, after: f64 },
+    Appeared { value: f64 },
+    Disappeared { value: f64 },
+}
+
+// Unterschiede je (Gerät, Typ), sortiert
+pub fn diff(previous: &Snapshot, current: &Snapshot) -> BTreeMap<(String, SensorKind), Change> {
+    let mut changes = BTreeMap::new();
+    for (key, &after) in &current.values {
+        let change = match previous.values.get(key) {
+            Some(&before) => Change::Changed { before, after },
+            None => Change::Appeared { value: after },
+        };
+        changes.insert(key.clone(), change);
+    }
+    for (key, &value) in &previous.values {
+        if !current.values.contains_key(key) {
+            changes.insert(key.clone(), Change::Disappeared { value });
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(values: &[(&str, SensorKind, f64)]) -> Snapshot {
+        let readings: Vec<SensorData> = values
+            .iter()
+            .map(|(device, kind, value)| SensorData { device_id: device.to_string(), sensor_type: kind.clone(), value: *value, timestamp: 0 })
+            .collect();
+        Snapshot::capture(&readings, DateTime::from_timestamp(1_700_000_000, 0).unwrap())
+    }
+
+    fn key(device: &str, kind: SensorKind) -> (String, SensorKind) {
+        (device.to_string(), kind)
+    }
+
+    #[test]
+    fn added_removed_and_changed_values_are_sorted() {
+        let before =
+            snapshot(&[("sensor1", SensorKind::Temperature, 21.0), ("sensor1", SensorKind::Humidity, 55.0), ("sensor2", SensorKind::Temperature, 19.5)]);
+        let after = snapshot(&[("sensor1", SensorKind::Temperature, 22.5), ("sensor2", SensorKind::Temperature, 19.5), ("sensor3", SensorKind::Co2, 800.0)]);
+
+        let changes: Vec<_> = diff(&before, &after).into_iter().collect();
+        assert_eq!(
+            changes,
+            vec![
+                (key("sensor1", SensorKind::Temperature), Change::Changed { before: 21.0, after: 22.5 }),
+                (key("sensor1", SensorKind::Humidity), Change::Disappeared { value: 55.0 }),
+                (key("sensor2", SensorKind::Temperature), Change::Changed { before: 19.5, after: 19.5 }),
+                (key("sensor3", SensorKind::Co2), Change::Appeared { value: 800.0 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_snapshots_have_no_changes() {
+        assert!(diff(&snapshot(&[]), &snapshot(&[])).is_empty());
+        let only = snapshot(&[("sensor1", SensorKind::Temperature, 21.0)]);
+        assert!(matches!(diff(&snapshot(&[]), &only).into_values().next(), Some(Change::Appeared { value: 21.0 })));
+        assert!(matches!(diff(&only, &snapshot(&[])).into_values().next(), Some(Change::Disappeared { value: 21.0 })));
+    }
+}
@@ -0,0 +1,163 @@
+use crate::i18n::{self, Lang};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Höchstens so viele Schwellen je Chat gleichzeitig stumm; darüber endet
+// die älteste Stummschaltung vorzeitig
+pub const MAX_ACTIVE: usize = 10;
+
+// Wie eine Schwelle stummgeschaltet wurde
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+#[serde(rename_all = "lowercase")]
+pub enum Origin {
+    Reaction, // 😴 auf eine Warnung
+    Command,  // /snooze
+    Button,   // "😴" unter einer Warnung
+}
+
+impl Origin {
+    pub fn label(self, lang: Lang) -> &'static str {
+        match self {
+            Origin::Reaction => i18n::message(lang, "snooze_origin_reaction"),
+            Origin::Command => i18n::message(lang, "snooze_origin_command"),
+            Origin::Button => i18n::message(lang, "snooze_origin_button"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+#[serde(from = "Stored")]
+pub struct Snooze {
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::datetime()"))]
+    pub until: DateTime<Utc>,
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::datetime()"))]
+    pub since: DateTime<Utc>,
+    pub origin: Origin,
+}
+
+// Früher wurde nur das Ende gespeichert; das ging nur per Reaktion
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Stored {
+    Full { until: DateTime<Utc>, since: DateTime<Utc>, origin: Origin },
+    Until(DateTime<Utc>),
+}
+
+impl From<Stored> for Snooze {
+    fn from(stored: Stored) -> Snooze {
+        match stored {
+            Stored::Full { until, since, origin } => Snooze { until, since, origin },
+            Stored::Until(until) => Snooze { until, since: until, origin: Origin::Reaction },
+        }
+    }
+}
+
+// Stummschaltung eintragen und die Obergrenze einhalten; liefert die
+// Schwellen, deren Stummschaltung dafür vorzeitig endet
+pub fn insert<K: Eq + Hash + Clone>(snoozes: &mut HashMap<K, Snooze>, key: K, snooze: Snooze) -> Vec<K> {
+    let now = snooze.since;
+    snoozes.retain(|_, s| s.until > now);
+    snoozes.insert(key.clone(), snooze);
+    let mut ended = Vec::new();
+    while snoozes.len() > MAX_ACTIVE {
+        let Some(oldest) = snoozes.iter().filter(|(k, _)| **k != key).min_by_key(|(_, s)| s.since).map(|(k, _)| k.clone()) else {
+            break;
+        };
+        snoozes.remove(&oldest);
+        ended.push(oldest);
+    }
+    ended
+}
+
+// Abgelaufene Stummschaltungen entfernen; liefert die Geräte, deren per
+// /snooze gesetzte Stummschaltung damit endet (je Gerät einmal)
+pub fn expire<K>(snoozes: &mut HashMap<(String, K), Snooze>, now: DateTime<Utc>) -> Vec<String> {
+    let mut ended: Vec<String> = Vec::new();
+    snoozes.retain(|(device, _), snooze| {
+        let active = snooze.until > now;
+        if !active && snooze.origin == Origin::Command && !ended.contains(device) {
+            ended.push(device.clone());
+        }
+        active
+    });
+    ended
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap() + Duration::minutes(minutes)
+    }
+
+    fn snooze(since: i64, until: i64, origin: Origin) -> Snooze {
+        Snooze { until: at(until), since: at(since), origin }
+    }
+
+    #[test]
+    fn cap_ends_the_oldest_snooze_early() {
+        let mut snoozes = HashMap::new();
+        for i in 0..MAX_ACTIVE {
+            assert!(insert(&mut snoozes, i, snooze(i as i64, 600, Origin::Button)).is_empty());
+        }
+        let ended = insert(&mut snoozes, 99, snooze(50, 60, Origin::Command));
+        assert_eq!(ended, [0]);
+        assert_eq!(snoozes.len(), MAX_ACTIVE);
+        assert!(snoozes.contains_key(&99) && !snoozes.contains_key(&0));
+    }
+
+    #[test]
+    fn the_new_snooze_is_never_the_one_ended() {
+        let mut snoozes = HashMap::new();
+        for i in 0..MAX_ACTIVE {
+            insert(&mut snoozes, i, snooze(10, 600, Origin::Button));
+        }
+        // Älter als alle anderen, bleibt trotzdem
+        let ended = insert(&mut snoozes, 99, snooze(10, 600, Origin::Reaction));
+        assert_eq!(ended.len(), 1);
+        assert!(snoozes.contains_key(&99));
+    }
+
+    #[test]
+    fn expired_snoozes_do_not_count_against_the_cap() {
+        let mut snoozes = HashMap::new();
+        for i in 0..MAX_ACTIVE {
+            insert(&mut snoozes, i, snooze(0, 30, Origin::Button));
+        }
+        assert!(insert(&mut snoozes, 99, snooze(40, 100, Origin::Command)).is_empty());
+        assert_eq!(snoozes.len(), 1);
+    }
+
+    #[test]
+    fn expire_reports_command_snoozes_once_per_device() {
+        let mut snoozes = HashMap::from([
+            (("sensor1".to_string(), "max"), snooze(0, 30, Origin::Command)),
+            (("sensor1".to_string(), "min"), snooze(0, 30, Origin::Command)),
+            (("sensor2".to_string(), "max"), snooze(0, 30, Origin::Button)),
+            (("sensor3".to_string(), "max"), snooze(0, 90, Origin::Command)),
+        ]);
+        assert_eq!(expire(&mut snoozes, at(60)), ["sensor1"]);
+        assert_eq!(snoozes.keys().collect::<Vec<_>>(), [&("sensor3".to_string(), "max")]);
+        assert!(expire(&mut snoozes, at(60)).is_empty());
+    }
+
+    #[test]
+    fn stored_with_expiry_and_origin() {
+        let stored = snooze(5, 125, Origin::Button);
+        let json = serde_json::to_string(&stored).unwrap();
+        let restored: Snooze = serde_json::from_str(&json).unwrap();
+        assert_eq!((restored.since, restored.until, restored.origin), (at(5), at(125), Origin::Button));
+    }
+
+    #[test]
+    fn reads_the_old_format_with_only_an_expiry() {
+        let restored: Snooze = serde_json::from_value(serde_json::json!(at(45))).unwrap();
+        assert_eq!((restored.since, restored.until, restored.origin), (at(45), at(45), Origin::Reaction));
+    }
+}
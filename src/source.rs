@@ -0,0 +1,321 @@
+use crate::SensorData;
+use crate::i18n::Text;
+use crate::settings::MIN_POLL_INTERVAL_SECONDS;
+use crate::timeutil;
+use log::{debug, warn};
+use serde_json::Value;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+// Ein hängender Sensor-Webserver darf weder die Überwachung noch /status
+// aufhalten
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(8);
+// Versuche je Abruf; dazwischen 0,5 s, dann 1 s Pause
+const ATTEMPTS: u32 = 3;
+const FIRST_BACKOFF: Duration = Duration::from_millis(500);
+// Unlesbare Antworten landen nur gekürzt im Debug-Log
+const PAYLOAD_LOG_CHARS: usize = 500;
+
+/// Future, wie sie die Traits dieser Crate zurückgeben
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Fehler beim Abruf einer Messwert-Quelle
+#[derive(Debug, Clone)]
+pub enum FetchError {
+    /// Quelle nicht erreichbar; wird nicht weiter gemeldet
+    Request(String),
+    /// Antwort passt nicht zum erwarteten Format; der Admin wird nach
+    /// mehreren gleichen Fehlern benachrichtigt
+    Parse { error: String, payload: String },
+}
+
+impl FetchError {
+    /// Fehler für den Chat, in dessen Sprache
+    pub(crate) fn text(&self) -> Text {
+        match self {
+            FetchError::Request(error) => Text::new("fetch_unreachable", &[("error", error)]),
+            FetchError::Parse { error, .. } => Text::new("fetch_unreadable", &[("error", error)]),
+        }
+    }
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.text().fmt(f)
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+/// Liefert die aktuellen Messwerte, z.B. vom Sensor-Webserver.
+///
+/// Stabilität: Teil der öffentlichen Schnittstelle, Änderungen nur mit
+/// neuer Hauptversion.
+pub trait SensorSource: Send + Sync {
+    fn fetch(&self) -> BoxFuture<'_, Result<Vec<SensorData>, FetchError>>;
+
+    /// Einträge, die beim letzten Abruf unlesbar waren und fehlen
+    fn skipped(&self) -> usize {
+        0
+    }
+
+    /// Eigener Abstand der regulären Abfragen; ohne Angabe gilt
+    /// POLL_INTERVAL_SECONDS bzw. /set-interval
+    fn poll_interval(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Sensor-Webserver, der eine JSON-Liste von `SensorData` ausliefert
+pub struct HttpSource {
+    url: String,
+    interval: Option<Duration>,
+    skipped: AtomicUsize,
+}
+
+impl HttpSource {
+    pub fn new(url: impl Into<String>) -> HttpSource {
+        HttpSource { url: url.into(), interval: None, skipped: AtomicUsize::new(0) }
+    }
+
+    /// Eigener Abstand der Abfragen, z.B. für Sensoren, die nur stündlich messen
+    pub fn with_interval(mut self, interval: Duration) -> HttpSource {
+        self.interval = Some(interval);
+        self
+    }
+}
+
+// Jeden Eintrag der Liste einzeln lesen, damit ein kaputter Eintrag nicht
+// alle anderen mitnimmt. Fehler nur, wenn die Antwort weder Liste noch
+// einzelner Messwert ist oder kein einziger Eintrag lesbar war – dann mit dem
+// Fehler des ersten, damit die Formatüberwachung ihn zählt. Liefert die
+// lesbaren Messwerte und die Zahl verworfener Einträge.
+pub(crate) fn parse_readings(text: &str) -> Result<(Vec<SensorData>, usize), serde_json::Error> {
+    let entries = match serde_json::from_str::<Value>(text)? {
+        Value::Array(entries) => entries,
+        entry @ Value::Object(_) => vec![entry],
+        other => serde_json::from_value::<Vec<Value>>(other)?,
+    };
+    let received_at = chrono::Utc::now().timestamp();
+    let mut readings = Vec::with_capacity(entries.len());
+    let mut skipped = 0;
+    let mut first_error = None;
+    for (index, mut entry) in entries.into_iter().enumerate() {
+        lenient_entry(&mut entry, received_at);
+        match serde_json::from_value::<SensorData>(entry) {
+            Ok(mut reading) => {
+                reading.timestamp = timeutil::normalize_timestamp(reading.timestamp);
+                readings.push(reading);
+            }
+            Err(err) => {
+                warn!("Eintrag {} der Sensordaten unlesbar, übersprungen: {}", index, err);
+                skipped += 1;
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+    match first_error {
+        Some(err) if readings.is_empty() => Err(err),
+        _ => Ok((readings, skipped)),
+    }
+}
+
+// Was manche Gateways anders schicken: "value" als Zahl in Anführungszeichen
+// (auch mit Komma), "timestamp" fehlt oder ist null – dann gilt der Empfang
+fn lenient_entry(entry: &mut Value, received_at: i64) {
+    let Value::Object(fields) = entry else { return };
+    if let Some(Value::String(text)) = fields.get("value")
+        && let Some(number) = text.trim().replace(',', ".").parse::<f64>().ok().and_then(serde_json::Number::from_f64)
+    {
+        fields.insert("value".to_string(), Value::Number(number));
+    }
+    if fields.get("timestamp").is_none_or(Value::is_null) {
+        fields.insert("timestamp".to_string(), Value::from(received_at));
+    }
+}
+
+// Ein Client für alle Abrufe, damit Verbindungen wiederverwendet werden
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder().connect_timeout(CONNECT_TIMEOUT).timeout(REQUEST_TIMEOUT).build().unwrap_or_else(|err| {
+            warn!("HTTP-Client mit Zeitlimits nicht verfügbar, verwende Standard: {}", err);
+            reqwest::Client::new()
+        })
+    })
+}
+
+// Antwort als Text. Zeitüberschreitungen, Verbindungsfehler und 5xx werden
+// mit wachsender Pause wiederholt, andere Fehler nicht.
+async fn fetch_text(url: &str) -> Result<String, FetchError> {
+    let mut backoff = FIRST_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        debug!("Abruf {} (Versuch {} von {})", url, attempt, ATTEMPTS);
+        let result = match client().get(url).send().await.and_then(|resp| resp.error_for_status()) {
+            Ok(resp) => resp.text().await,
+            Err(err) => Err(err),
+        };
+        let err = match result {
+            Ok(text) => return Ok(text),
+            Err(err) => err,
+        };
+        let transient = err.is_timeout() || err.is_connect() || err.status().is_some_and(|s| s.is_server_error());
+        if !transient || attempt >= ATTEMPTS {
+            warn!("Abruf von {} fehlgeschlagen nach {} Versuch(en): {}", url, attempt, err);
+            return Err(FetchError::Request(err.to_string()));
+        }
+        debug!("Abruf von {} fehlgeschlagen, neuer Versuch in {:?}: {}", url, backoff, err);
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+        attempt += 1;
+    }
+}
+
+/// Ein Sensor-Webserver aus SENSOR_ENDPOINTS
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoint {
+    pub url: String,
+    /// Eigener Abstand der Abfragen, sonst POLL_INTERVAL_SECONDS
+    pub interval: Option<Duration>,
+}
+
+/// Liste von Sensor-Webservern, durch Komma getrennt (SENSOR_ENDPOINTS).
+/// Jeder Eintrag muss eine http(s)-URL sein, wahlweise mit eigenem
+/// Abfrageintervall dahinter ("http://gateway:8080/sensors 1h"); doppelte
+/// URLs zählen einmal.
+pub fn parse_endpoints(list: &str) -> Result<Vec<Endpoint>, String> {
+    let mut endpoints: Vec<Endpoint> = Vec::new();
+    for entry in list.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let (url, interval) = match entry.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [url] => (*url, None),
+            [url, interval] => (*url, Some(parse_interval(interval)?)),
+            _ => return Err(format!("'{}': nach der URL nur ein Abfrageintervall angeben, z.B. 5m", entry)),
+        };
+        match reqwest::Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+            _ => return Err(format!("'{}' ist keine http(s)-URL", url)),
+        }
+        if !endpoints.iter().any(|known| known.url == url) {
+            endpoints.push(Endpoint { url: url.to_string(), interval });
+        }
+    }
+    if endpoints.is_empty() {
+        return Err("keine URL angegeben".into());
+    }
+    Ok(endpoints)
+}
+
+// Sekunden, wahlweise mit s, m oder h; nicht unter MIN_POLL_INTERVAL_SECONDS
+fn parse_interval(spec: &str) -> Result<Duration, String> {
+    let spec = spec.to_lowercase();
+    let (number, factor) = match spec.char_indices().last() {
+        Some((at, 's')) => (&spec[..at], 1),
+        Some((at, 'm')) => (&spec[..at], 60),
+        Some((at, 'h')) => (&spec[..at], 60 * 60),
+        _ => (spec.as_str(), 1),
+    };
+    match number.parse::<u64>().ok().and_then(|number| number.checked_mul(factor)) {
+        Some(seconds) if seconds >= MIN_POLL_INTERVAL_SECONDS => Ok(Duration::from_secs(seconds)),
+        Some(_) => Err(format!("Abfrageintervall '{}' unter {} Sekunden", spec, MIN_POLL_INTERVAL_SECONDS)),
+        None => Err(format!("Abfrageintervall '{}' ungültig, z.B. 90s, 5m oder 1h", spec)),
+    }
+}
+
+impl Default for HttpSource {
+    fn default() -> Self {
+        HttpSource::new("http://localhost:8080/sensors")
+    }
+}
+
+impl SensorSource for HttpSource {
+    fn skipped(&self) -> usize {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    fn poll_interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<Vec<SensorData>, FetchError>> {
+        Box::pin(async move {
+            let text = fetch_text(&self.url).await?;
+            match parse_readings(&text) {
+                Ok((data, skipped)) => {
+                    if skipped > 0 {
+                        warn!("{} von {} Einträgen von {} übersprungen", skipped, skipped + data.len(), self.url);
+                    }
+                    self.skipped.store(skipped, Ordering::Relaxed);
+                    Ok(data)
+                }
+                Err(err) => {
+                    warn!("Sensordaten von {} unlesbar: {}", self.url, err);
+                    let cut = text.char_indices().nth(PAYLOAD_LOG_CHARS).map_or(text.len(), |(i, _)| i);
+                    debug!("Antwort von {} ({} Bytes): {}{}", self.url, text.len(), &text[..cut], if cut < text.len() { " …" } else { "" });
+                    Err(FetchError::Parse { error: err.to_string(), payload: text })
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interval_units() {
+        assert_eq!(parse_interval("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_interval("5M"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_interval("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_interval("120"), Ok(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn interval_rejects_overflow_and_garbage() {
+        let huge = format!("{}h", u64::MAX / 60);
+        assert!(parse_interval(&huge).unwrap_err().contains("ungültig"));
+        assert!(parse_interval("zehn").unwrap_err().contains("ungültig"));
+        assert!(parse_interval("1s").unwrap_err().contains("unter"));
+    }
+
+    #[test]
+    fn broken_entries_are_skipped_and_counted() {
+        let text = r#"[
+            {"device_id": "sensor1", "sensor_type": "temperature", "value": 21.5, "timestamp": 1700000000},
+            {"device_id": "sensor1", "sensor_type": "humidity"},
+            "kaputt",
+            {"device_id": "sensor2", "sensor_type": "humidity", "value": "48,5", "timestamp": 1700000000}
+        ]"#;
+        let (readings, skipped) = parse_readings(text).unwrap();
+        assert_eq!(skipped, 2);
+        let values: Vec<(&str, f64)> = readings.iter().map(|r| (r.device_id.as_str(), r.value)).collect();
+        assert_eq!(values, [("sensor1", 21.5), ("sensor2", 48.5)]);
+    }
+
+    #[test]
+    fn single_object_and_missing_timestamp() {
+        let before = chrono::Utc::now().timestamp();
+        let (readings, skipped) = parse_readings(r#"{"device_id": "sensor1", "sensor_type": "temperature", "value": 20, "timestamp": null}"#).unwrap();
+        assert_eq!((readings.len(), skipped), (1, 0));
+        assert!(readings[0].timestamp >= before);
+    }
+
+    #[test]
+    fn neither_list_nor_reading_is_an_error() {
+        assert!(parse_readings("42").is_err());
+        assert!(parse_readings("<html>").is_err());
+        assert_eq!(parse_readings("[]").unwrap().1, 0);
+    }
+
+    #[test]
+    fn no_readable_entry_is_an_error() {
+        let err = parse_readings(r#"[{"device_id": "sensor1", "sensor_type": "temperature", "temp": 21.5}, "kaputt"]"#).unwrap_err();
+        assert_eq!(err.to_string(), "missing field `value`");
+    }
+}
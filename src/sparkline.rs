@@ -0,0 +1,102 @@
+use std::collections::VecDeque;
+
+const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+// Zeitraum und Breite des Mini-Verlaufs in /status
+const WINDOW_SECONDS: i64 = 3 * 60 * 60;
+const WIDTH: i64 = 8;
+// Weniger Abschnitte mit Messwerten ergeben keinen sinnvollen Verlauf
+const MIN_POINTS: usize = 3;
+// Der Pfeil vergleicht mit dem Wert von vor einer Stunde
+const ARROW_SECONDS: i64 = 60 * 60;
+
+// Werte als Blockzeichen, vom kleinsten (▁) bis zum größten (█).
+// NaN und Unendlich fallen weg; ohne Schwankung bleibt die Linie in der Mitte.
+pub fn sparkline(values: &[f64]) -> String {
+    let values: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let span = max - min;
+    values
+        .iter()
+        .map(|value| {
+            if span <= f64::EPSILON {
+                return BLOCKS[BLOCKS.len() / 2 - 1];
+            }
+            let level = ((value - min) / span * (BLOCKS.len() - 1) as f64).round() as usize;
+            BLOCKS[level.min(BLOCKS.len() - 1)]
+        })
+        .collect()
+}
+
+// Mini-Verlauf der letzten Stunden aus den Rohwerten einer Messreihe und
+// Pfeil zum Wert von vor einer Stunde; `step` ist die kleinste angezeigte
+// Änderung (eine Nachkommastelle: 0.1). None bei zu wenig Verlauf.
+pub fn trend(series: &VecDeque<(i64, f64)>, now: i64, step: f64) -> Option<String> {
+    let from = now - WINDOW_SECONDS;
+    let slice = WINDOW_SECONDS / WIDTH;
+    let mut sums = vec![(0.0, 0usize); WIDTH as usize];
+    for &(ts, value) in series.iter().filter(|(ts, value)| *ts > from && *ts <= now && value.is_finite()) {
+        let index = (((ts - from - 1) / slice) as usize).min(sums.len() - 1);
+        sums[index].0 += value;
+        sums[index].1 += 1;
+    }
+    let means: Vec<f64> = sums.iter().filter(|(_, n)| *n > 0).map(|(sum, n)| sum / *n as f64).collect();
+    if means.len() < MIN_POINTS {
+        return None;
+    }
+    let line = sparkline(&means);
+    let latest = series.iter().rev().find(|(ts, value)| *ts <= now && value.is_finite())?.1;
+    let hour_ago = series.iter().rev().find(|(ts, value)| *ts <= now - ARROW_SECONDS && value.is_finite());
+    Some(match hour_ago {
+        Some(&(_, before)) => format!("{} {}", line, arrow(latest - before, step)),
+        None => line,
+    })
+}
+
+fn arrow(change: f64, step: f64) -> char {
+    if change >= step {
+        '↗'
+    } else if change <= -step {
+        '↘'
+    } else {
+        '→'
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_series_stays_in_the_middle() {
+        assert_eq!(sparkline(&[21.0, 21.0, 21.0]), "▄▄▄");
+    }
+
+    #[test]
+    fn single_value_and_empty_input() {
+        assert_eq!(sparkline(&[21.0]), "▄");
+        assert_eq!(sparkline(&[]), "");
+        assert_eq!(sparkline(&[f64::NAN, f64::INFINITY]), "");
+    }
+
+    #[test]
+    fn minimum_and_maximum_map_to_the_outer_blocks() {
+        assert_eq!(sparkline(&[18.0, 25.0, 21.5]), "▁█▅");
+        assert_eq!(sparkline(&[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]), "▁▂▃▄▅▆▇█");
+        // Ausreißer ohne Zahlenwert verschieben die Skala nicht
+        assert_eq!(sparkline(&[1.0, f64::NAN, 2.0]), "▁█");
+    }
+
+    #[test]
+    fn trend_needs_enough_slices_and_shows_the_hourly_change() {
+        let now = 1_700_000_000;
+        let slice = WINDOW_SECONDS / WIDTH;
+        let series: VecDeque<(i64, f64)> = (0..WIDTH).map(|n| (now - WINDOW_SECONDS + (n + 1) * slice, 20.0 + n as f64 * 0.5)).collect();
+        assert_eq!(trend(&series, now, 0.1).as_deref(), Some("▁▂▃▄▅▆▇█ ↗"));
+
+        let flat: VecDeque<(i64, f64)> = series.iter().map(|&(ts, _)| (ts, 21.0)).collect();
+        assert_eq!(trend(&flat, now, 0.1).as_deref(), Some("▄▄▄▄▄▄▄▄ →"));
+        let short: VecDeque<(i64, f64)> = series.iter().rev().take(2).copied().collect();
+        assert_eq!(trend(&short, now, 0.1), None);
+    }
+}
@@ -0,0 +1,52 @@
+use crate::timeutil::seconds_between;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StaleEvent {
+    // Der neueste Messwert ist älter als erlaubt, seit `since` nichts Neues
+    Stale { device_id: String, since: i64 },
+    // Nach einer Meldung kam wieder ein frischer Messwert
+    Fresh { device_id: String, gap: i64 },
+}
+
+// Erkennt Geräte, deren Webserver weiter den letzten Wert ausliefert, obwohl
+// der Sensor längst nichts Neues mehr misst. Maßgeblich ist der Zeitstempel
+// im Messwert, nicht der Zeitpunkt des Abrufs. Je Gerät wird einmal gemeldet,
+// bis wieder ein frischer Wert kommt.
+pub struct StaleWatch {
+    max_age: i64,
+    stale: BTreeMap<String, i64>, // Gerät -> Zeitstempel des letzten frischen Werts
+}
+
+impl StaleWatch {
+    // max_age 0 schaltet die Prüfung ab
+    pub fn new(max_age: i64) -> StaleWatch {
+        StaleWatch { max_age, stale: BTreeMap::new() }
+    }
+
+    // Neueste Zeitstempel je Gerät aus dem aktuellen Abruf
+    pub fn observe(&mut self, newest: &HashMap<&str, i64>, now: i64) -> Vec<StaleEvent> {
+        if self.max_age <= 0 {
+            return Vec::new();
+        }
+        let mut events = Vec::new();
+        for (&device_id, &timestamp) in newest {
+            if seconds_between(timestamp, now) > self.max_age {
+                if !self.stale.contains_key(device_id) {
+                    self.stale.insert(device_id.to_string(), timestamp);
+                    events.push(StaleEvent::Stale { device_id: device_id.to_string(), since: timestamp });
+                }
+            } else if let Some(since) = self.stale.remove(device_id) {
+                events.push(StaleEvent::Fresh { device_id: device_id.to_string(), gap: seconds_between(since, timestamp) });
+            }
+        }
+        events.sort_by(|a, b| event_device(a).cmp(event_device(b)));
+        events
+    }
+}
+
+fn event_device(event: &StaleEvent) -> &str {
+    match event {
+        StaleEvent::Stale { device_id, .. } | StaleEvent::Fresh { device_id, .. } => device_id,
+    }
+}
@@ -0,0 +1,310 @@
+use crate::alarm_log::AlarmLog;
+use crate::charts::ChartCache;
+use crate::coverage::Unmonitored;
+use crate::digest::{AlertMode, DigestBuffer};
+use crate::episode::Episode;
+use crate::escalation::Escalation;
+use crate::handover::Handover;
+use crate::history::History;
+use crate::i18n::Lang;
+use crate::layout::Layout;
+use crate::messenger::Messenger;
+use crate::notify::NotificationMode;
+use crate::outdoor::Watering;
+use crate::profiles::Profiles;
+use crate::rate::RateRule;
+use crate::records::Records;
+use crate::relative::RelativeRule;
+use crate::schedule::WeeklySchedule;
+use crate::sensor::{SensorKind, ThresholdKey};
+use crate::snapshot::Snapshot;
+use crate::snooze::Snooze;
+use crate::storage::Store;
+use crate::thresholds::{AlertStyle, ThresholdEntry, ThresholdSchedule, TimeWindow};
+use crate::timeformat::TimeFormat;
+use crate::units::TempUnit;
+use crate::uptime::UptimeLog;
+use crate::{monitor, settings, storage, threshold_in};
+use chrono::{DateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Benutzerkonfiguration eines Chats (Schwellen, Berichte, Ruhezeit, …).
+///
+/// Stabilität: der Inhalt ist intern; stabil ist nur das serde-Format, damit
+/// eigene `Store`-Implementierungen ihn unverändert ablegen können.
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(Debug, proptest_derive::Arbitrary))]
+#[serde(default)]
+pub struct UserConfig {
+    #[serde(with = "storage::keyed_map")]
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::keyed(proptest::prelude::any::<ThresholdSchedule>())"))]
+    pub(crate) thresholds: HashMap<(String, ThresholdKey), ThresholdSchedule>, // (sensor_id, typ und Richtung) -> threshold
+    pub(crate) report_schedule: Option<WeeklySchedule>, // geplanter Statusbericht
+    #[serde(deserialize_with = "daily_summary")]
+    pub(crate) daily_summary: Option<WeeklySchedule>, // Tageszusammenfassung nach Wochenplan (/subscribe-daily)
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::optional_datetime()"))]
+    pub(crate) last_summary: Option<DateTime<Utc>>, // letzte Tageszusammenfassung, auch nach Neustarts höchstens eine am Tag
+    pub(crate) weekly_report: Option<WeeklySchedule>,   // Wochenbericht an einem Wochentag (/subscribe-weekly)
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::optional_datetime()"))]
+    pub(crate) last_weekly: Option<DateTime<Utc>>, // letzter Wochenbericht, auch nach Neustarts höchstens einer je Termin
+    pub(crate) quiet_hours: Option<TimeWindow>,         // Warnungen werden in dieser Zeit gesammelt
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::optional_datetime()"))]
+    pub(crate) muted_until: Option<DateTime<Utc>>, // /mute-all: keine Benachrichtigungen bis dahin
+    pub(crate) muted_missed: usize,                     // während der Stummschaltung unterdrückte Warnungen
+    pub(crate) layout: Layout,                          // Darstellung von /status
+    pub(crate) time_format: TimeFormat,                 // Zeitangaben in /status und Warnungen (/timeformat)
+    pub(crate) lang: Lang,                              // Sprache der Antworten (/language)
+    pub(crate) units: TempUnit,                         // Temperaturen in °C oder °F anzeigen und eingeben (/units)
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::timezone()"))]
+    pub(crate) timezone: Option<Tz>, // Zeitzone für angezeigte Zeiten (/timezone), sonst DEFAULT_TZ
+    pub(crate) last_viewed: Option<Snapshot>,           // Werte beim letzten /status oder /diff
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::few::<UndoEntry, _>()"))]
+    pub(crate) undo: Vec<UndoEntry>, // frühere Stände geänderter Schwellen, neueste zuletzt
+    pub(crate) api_token: Option<String>,               // Zugang zum HTTP-Server (/api-token)
+    pub(crate) notes: BTreeMap<String, String>,         // Gerät -> Notiz (/note), erscheint bei /status und Warnungen
+    #[serde(with = "storage::keyed_map")]
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::keyed(proptest::prelude::any::<Unmonitored>())"))]
+    pub(crate) unmonitored: HashMap<(String, ThresholdKey), Unmonitored>, // Schwellen ohne passende Messwerte
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::few::<AlertRecord, _>()"))]
+    pub(crate) alerts: VecDeque<AlertRecord>, // zugestellte Warnungen, neueste zuletzt
+    #[serde(with = "storage::keyed_map")]
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::keyed(crate::arbitrary::datetime())"))]
+    pub(crate) acknowledged: HashMap<(String, ThresholdKey), DateTime<Utc>>, // bestätigte Alarme bis zur Erholung
+    #[serde(with = "storage::keyed_map")]
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::keyed(proptest::prelude::any::<Setter>())"))]
+    pub(crate) configured_by: HashMap<(String, ThresholdKey), Setter>, // nur in Gruppen
+    #[serde(with = "storage::keyed_map")]
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::keyed(proptest::prelude::any::<Snooze>())"))]
+    pub(crate) snoozed: HashMap<(String, ThresholdKey), Snooze>, // keine Warnungen dieser Schwelle bis dahin
+    #[serde(with = "storage::keyed_map")]
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::keyed(proptest::prelude::any::<Episode>())"))]
+    pub(crate) episodes: HashMap<(String, ThresholdKey), Episode>, // bestehende Alarme bis zur Erholung
+    #[serde(with = "storage::keyed_map")]
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::keyed(proptest::prelude::any::<f64>())"))]
+    pub(crate) hysteresis: HashMap<(String, ThresholdKey), f64>, // eigene Hysterese je Schwelle (/hysteresis), sonst HYSTERESIS
+    #[serde(with = "storage::keyed_map")]
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::keyed(proptest::prelude::any::<i64>())"))]
+    pub(crate) repeat: HashMap<(String, ThresholdKey), i64>, // Erinnerung alle so viele Minuten, solange der Alarm besteht (/repeat)
+    #[serde(with = "storage::keyed_map")]
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::keyed(proptest::prelude::any::<AlertStyle>())"))]
+    pub(crate) alert_styles: HashMap<(String, ThresholdKey), AlertStyle>, // eigene Stufe und eigener Text (/setmin … critical "Text")
+    pub(crate) alert_mode: AlertMode,                   // Warnungen sofort oder gesammelt (/alert-mode)
+    pub(crate) notifications: NotificationMode,         // Benachrichtigungston je Art der Nachricht (/notifications)
+    pub(crate) digest: DigestBuffer,                    // gesammelte Meldungen bis zur nächsten Sammelmeldung
+    pub(crate) room_images: bool,                       // kritische Warnungen und /status <raum> mit Raumbild
+    pub(crate) fleet_report: bool,                      // nur Admin: Geräte-Wochenbericht am Sonntag
+    pub(crate) watering: Option<Watering>,              // Gießerinnerung für Räume im Freien (/watering)
+    #[serde(with = "storage::keyed_map")]
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::keyed(proptest::prelude::any::<RateRule>())"))]
+    pub(crate) rates: HashMap<(String, SensorKind), RateRule>, // Alarm bei schneller Änderung (/rate)
+    #[serde(with = "storage::keyed_map")]
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::keyed(proptest::prelude::any::<RelativeRule>())"))]
+    pub(crate) relations: HashMap<(String, ThresholdKey), RelativeRule>, // Alarm im Vergleich mit einem anderen Sensor (/set … max-rel)
+    pub(crate) battery_low: Option<f64>,                // Warngrenze für Batterien (/battery), sonst BATTERY_LOW; 0 = keine Warnungen
+    pub(crate) battery_warned: BTreeSet<String>,        // Geräte mit gemeldeter schwacher Batterie, bis sie gewechselt ist
+    pub(crate) alarm_log: AlarmLog,                     // Alarme und Entwarnungen für /alarms, auch zurückgehaltene
+    pub(crate) escalate_to: Option<Handover>,           // unbestätigte Warnungen an einen zweiten Chat (/escalate-to)
+    pub(crate) escalations_from: BTreeSet<i64>,         // Chats, deren Warnungen hierher weitergegeben werden dürfen (/accept-escalations-from)
+    pub(crate) profiles: Profiles,                      // benannte Schwellen-Sätze, manuell oder nach Uhrzeit aktiv (/profile)
+    pub(crate) mold_warned: BTreeMap<String, i64>,      // Gerät -> Beginn der gemeldeten Schimmelgefahr, bis sie endet
+    pub(crate) live: Option<LiveStatus>,                // angeheftete Nachricht, die nach jedem Abruf bearbeitet wird (/live)
+    pub(crate) first_name: Option<String>,              // für die Anrede, aus /start und jedem Befehl im Einzelchat
+    pub(crate) username: Option<String>,
+}
+
+// Früher eine einzelne Uhrzeit ("21:00:00"), jetzt ein Wochenplan wie bei
+// /schedule; alte Stände gelten als täglich
+fn daily_summary<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<WeeklySchedule>, D::Error> {
+    let Some(text) = Option::<String>::deserialize(deserializer)? else { return Ok(None) };
+    match NaiveTime::parse_from_str(&text, "%H:%M:%S") {
+        Ok(at) => Ok(Some(WeeklySchedule::daily(at))),
+        Err(_) => text.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+// In Gruppen: wer eine Schwelle eingerichtet hat, für die Erwähnung in Warnungen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+pub struct Setter {
+    pub user_id: u64,
+    pub name: String,
+    pub username: Option<String>,
+}
+
+impl Setter {
+    pub fn of(user: &teloxide::types::User) -> Setter {
+        Setter { user_id: user.id.0, name: user.first_name.clone(), username: user.username.clone() }
+    }
+
+    // Mit Benutzername als @-Erwähnung, die auch benachrichtigt
+    pub fn mention(&self) -> String {
+        match &self.username {
+            Some(username) => format!("@{}", username),
+            None => self.name.clone(),
+        }
+    }
+}
+
+// Zugestellte Warnung, damit Reaktionen auf die Nachricht ihre Schwellen finden
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(Debug, proptest_derive::Arbitrary))]
+pub struct AlertRecord {
+    #[serde(default)]
+    pub chat_id: Option<i64>, // Chat der Nachricht, falls nicht der eigene (Kopie per [routing])
+    pub message_id: i32,
+    pub device_id: String,
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::few::<ThresholdKey, _>()"))]
+    pub keys: Vec<ThresholdKey>, // mehrere bei zusammengefassten Warnungen
+    // Ältere Einträge haben beides nicht und zählen für die Verzögerung nicht mit
+    #[serde(default)]
+    pub observed_at: Option<i64>, // Zeitpunkt des auslösenden Messwerts
+    #[serde(default)]
+    pub delivered_at: Option<i64>, // tatsächliche Zustellung
+}
+
+// Live-Nachricht eines Chats mit den zuletzt angezeigten Werten; bearbeitet
+// wird nur, wenn sie sich ändern
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(Debug, proptest_derive::Arbitrary))]
+pub struct LiveStatus {
+    pub message_id: i32,
+    pub values: String,
+}
+
+// Stand einer Schwelle vor einer Änderung, für /undo
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(Debug, proptest_derive::Arbitrary))]
+pub struct UndoEntry {
+    pub device_id: String,
+    pub key: ThresholdKey,
+    pub previous: Option<ThresholdSchedule>,
+}
+
+impl UserConfig {
+    pub fn is_muted(&self, now: DateTime<Utc>) -> bool {
+        self.muted_until.is_some_and(|until| until > now)
+    }
+
+    pub fn battery_low(&self) -> f64 {
+        self.battery_low.unwrap_or(settings().battery_low)
+    }
+
+    pub fn hysteresis_for(&self, key: &(String, ThresholdKey)) -> f64 {
+        self.hysteresis.get(key).copied().unwrap_or(settings().hysteresis)
+    }
+
+    // Vor- und Benutzername des Absenders übernehmen; true, wenn geändert
+    pub fn remember_name(&mut self, user: &teloxide::types::User) -> bool {
+        let first_name = Some(user.first_name.trim().to_string()).filter(|n| !n.is_empty());
+        let username = user.username.clone();
+        let changed = self.first_name != first_name || self.username != username;
+        self.first_name = first_name;
+        self.username = username;
+        changed
+    }
+
+    // Geltender Eintrag einer Schwelle; das aktive Profil (/profile) geht vor
+    pub fn active_threshold(&self, key: &(String, ThresholdKey), local: NaiveTime) -> Option<ThresholdEntry> {
+        threshold_in(&self.thresholds, &self.profiles, key, local)
+    }
+
+    pub fn is_snoozed(&self, device_id: &str, key: &ThresholdKey, now: DateTime<Utc>) -> bool {
+        self.snoozed.get(&(device_id.to_string(), key.clone())).is_some_and(|snooze| snooze.until > now)
+    }
+
+    // Einstellungen von self, laufende Zustände (Alarme, Stummschaltung,
+    // Rückgängig-Liste, API-Token, Name) von `state`
+    pub fn with_state_of(self, state: UserConfig) -> UserConfig {
+        UserConfig {
+            last_summary: state.last_summary,
+            last_weekly: state.last_weekly,
+            muted_until: state.muted_until,
+            muted_missed: state.muted_missed,
+            last_viewed: state.last_viewed,
+            undo: state.undo,
+            api_token: state.api_token,
+            unmonitored: state.unmonitored,
+            alerts: state.alerts,
+            acknowledged: state.acknowledged,
+            snoozed: state.snoozed,
+            episodes: state.episodes,
+            battery_warned: state.battery_warned,
+            live: state.live,
+            alarm_log: state.alarm_log,
+            first_name: state.first_name,
+            username: state.username,
+            ..self
+        }
+    }
+
+    // Stand aus /restore übernehmen. Zustände bleiben, soweit ihre Schwelle
+    // auch in der Sicherung vorkommt.
+    pub fn replaced_by(self, backup: UserConfig) -> UserConfig {
+        let mut restored = backup.with_state_of(self);
+        let thresholds: Vec<(String, ThresholdKey)> = restored.thresholds.keys().cloned().collect();
+        restored.unmonitored.retain(|key, _| thresholds.contains(key));
+        restored.acknowledged.retain(|key, _| thresholds.contains(key));
+        restored.snoozed.retain(|key, _| thresholds.contains(key));
+        restored.episodes.retain(|key, _| thresholds.contains(key));
+        restored
+    }
+
+    // Archivierten Stand zurückholen (/undo-clear). Was seit dem Löschen neu
+    // eingerichtet wurde (Schwellen, Notizen, Bericht, Ruhezeit, Name), bleibt;
+    // alles andere kommt aus dem Archiv.
+    pub fn restore_from(self, archived: UserConfig) -> UserConfig {
+        let mut restored = archived;
+        restored.thresholds.extend(self.thresholds);
+        restored.notes.extend(self.notes);
+        restored.hysteresis.extend(self.hysteresis);
+        restored.repeat.extend(self.repeat);
+        restored.alert_styles.extend(self.alert_styles);
+        restored.rates.extend(self.rates);
+        restored.relations.extend(self.relations);
+        restored.report_schedule = self.report_schedule.or(restored.report_schedule);
+        restored.daily_summary = self.daily_summary.or(restored.daily_summary);
+        restored.weekly_report = self.weekly_report.or(restored.weekly_report);
+        restored.quiet_hours = self.quiet_hours.or(restored.quiet_hours);
+        restored.profiles.profiles.extend(self.profiles.profiles);
+        restored.escalate_to = self.escalate_to.or(restored.escalate_to);
+        restored.escalations_from.extend(self.escalations_from);
+        restored.first_name = self.first_name.or(restored.first_name);
+        restored.username = self.username.or(restored.username);
+        // Alarmzustände stammen von vor dem Löschen und gelten nicht mehr
+        restored.episodes.clear();
+        restored.acknowledged.clear();
+        restored.digest = DigestBuffer::default();
+        restored
+    }
+}
+
+pub type UserConfigs = Arc<Mutex<HashMap<i64, UserConfig>>>;
+pub type ThresholdFlags = Arc<Mutex<monitor::Flags>>;
+pub type SharedHistory = Arc<Mutex<History>>;
+pub type SharedRecords = Arc<Mutex<Records>>;
+pub type SharedUptime = Arc<Mutex<UptimeLog>>;
+pub type SharedEscalation = Arc<Mutex<Escalation>>;
+pub type SharedCharts = Arc<Mutex<ChartCache>>;
+// Während der Ruhezeit zurückgehaltene Warnungen eines Chats: (Zeitpunkt, Text)
+pub type Held = Vec<(DateTime<Utc>, String)>;
+pub type QuietQueue = Arc<Mutex<HashMap<i64, Held>>>;
+
+// Geteilte Handles des laufenden Bots. Befehle und Überwachung arbeiten auf
+// denselben; geklont werden nur die Zeiger.
+#[derive(Clone)]
+pub struct Shared {
+    pub configs: UserConfigs,
+    pub flags: ThresholdFlags,
+    pub history: SharedHistory,
+    pub records: SharedRecords,
+    pub storage: Arc<dyn Store>,
+    pub uptime: SharedUptime,
+    pub escalation: SharedEscalation,
+    pub charts: SharedCharts,
+    // Antworten auf Befehle (Telegram), in Tests mitgeschrieben
+    pub replies: Arc<dyn Messenger>,
+}
@@ -0,0 +1,54 @@
+// Kennzahlen eines Zeitraums für /stats
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aggregate {
+    pub count: usize,
+    pub first: i64, // ältester Zeitpunkt im Zeitraum
+    pub min: (i64, f64),
+    pub max: (i64, f64),
+    pub mean: f64,
+}
+
+// None ohne Messwerte. Kommt ein Extremwert mehrmals vor, gilt der jüngste
+// Zeitpunkt, das ist für "wann zuletzt so kalt" die nützlichere Angabe.
+pub fn aggregate(samples: &[(i64, f64)]) -> Option<Aggregate> {
+    let &(first_ts, first) = samples.first()?;
+    let mut aggregate = Aggregate { count: 0, first: first_ts, min: (first_ts, first), max: (first_ts, first), mean: 0.0 };
+    let mut sum = 0.0;
+    for &(ts, value) in samples {
+        aggregate.count += 1;
+        aggregate.first = aggregate.first.min(ts);
+        sum += value;
+        if value < aggregate.min.1 || (value == aggregate.min.1 && ts > aggregate.min.0) {
+            aggregate.min = (ts, value);
+        }
+        if value > aggregate.max.1 || (value == aggregate.max.1 && ts > aggregate.max.0) {
+            aggregate.max = (ts, value);
+        }
+    }
+    aggregate.mean = sum / aggregate.count as f64;
+    Some(aggregate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_series() {
+        let samples = [(400, 21.0), (100, 19.5), (200, 23.0), (300, 18.5)];
+        assert_eq!(aggregate(&samples), Some(Aggregate { count: 4, first: 100, min: (300, 18.5), max: (200, 23.0), mean: 20.5 }));
+    }
+
+    #[test]
+    fn repeated_extreme_keeps_the_latest_time() {
+        let samples = [(100, 18.0), (200, 25.0), (300, 18.0), (150, 25.0)];
+        let stats = aggregate(&samples).unwrap();
+        assert_eq!((stats.min, stats.max), ((300, 18.0), (200, 25.0)));
+    }
+
+    #[test]
+    fn single_value_and_empty_series() {
+        assert_eq!(aggregate(&[(100, 21.5)]), Some(Aggregate { count: 1, first: 100, min: (100, 21.5), max: (100, 21.5), mean: 21.5 }));
+        assert_eq!(aggregate(&[]), None);
+    }
+}
@@ -0,0 +1,740 @@
+use crate::UserConfig;
+use crate::access::AccessList;
+use crate::archive::Archive;
+use crate::backup;
+use crate::history::History;
+use crate::ignored::IgnoreList;
+use crate::known_chats::KnownChats;
+use crate::reachability::Reachability;
+use crate::records::Records;
+use crate::uptime::UptimeLog;
+use log::warn;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+
+// Frühere Stände je Datei (<name>.1, <name>.2) als Rückfall, wenn die
+// Datei selbst defekt ist
+const GENERATIONS: usize = 2;
+
+// Beim Laden aus einem früheren Stand wiederhergestellte Dateien, bis sie
+// dem Admin gemeldet sind
+static RECOVERED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+// Speichervorgänge eines Speichers: je Datei die Nummer des neuesten
+// Auftrags, damit ein älterer, der erst nach einem neueren drankommt,
+// entfällt. Geschrieben wird immer nur eine Datei zugleich.
+#[derive(Default)]
+struct Writes {
+    latest: Mutex<BTreeMap<PathBuf, u64>>,
+    writing: Mutex<()>,
+    pending: Mutex<usize>,
+    idle: Condvar,
+}
+
+/// Speicher für Benutzerkonfiguration, Messwertverlauf und Laufzeiten.
+///
+/// Laden liefert bei fehlenden Daten den leeren Zustand; Speicherfehler
+/// protokolliert die Implementierung selbst. Stabilität: Teil der
+/// öffentlichen Schnittstelle, Änderungen nur mit neuer Hauptversion.
+pub trait Store: Send + Sync {
+    fn load_users(&self) -> HashMap<i64, UserConfig>;
+    fn save_users(&self, users: &HashMap<i64, UserConfig>);
+    fn load_history(&self) -> History;
+    fn save_history(&self, history: &History);
+    fn load_uptime(&self) -> UptimeLog;
+    fn save_uptime(&self, uptime: &UptimeLog);
+
+    /// Rekordwerte (/records); ohne eigene Implementierung nicht gespeichert
+    fn load_records(&self) -> Records {
+        Records::default()
+    }
+
+    fn save_records(&self, records: &Records) {
+        let _ = records;
+    }
+
+    /// Mit /clear all gelöschte Konfigurationen; ohne eigene Implementierung
+    /// nicht gespeichert, dann lässt sich das Löschen nicht zurücknehmen
+    fn load_archive(&self) -> Archive {
+        Archive::default()
+    }
+
+    fn save_archive(&self, archive: &Archive) {
+        let _ = archive;
+    }
+
+    /// Erreichbarkeit der Chats; ohne eigene Implementierung nicht
+    /// gespeichert, dann prüft der Bot nach jedem Start erneut
+    fn load_reachability(&self) -> Reachability {
+        Reachability::default()
+    }
+
+    fn save_reachability(&self, reachability: &Reachability) {
+        let _ = reachability;
+    }
+
+    /// Ignorierte Geräte (/ignore); ohne eigene Implementierung nicht
+    /// gespeichert, dann gilt die Liste nur bis zum Neustart
+    fn load_ignored(&self) -> IgnoreList {
+        IgnoreList::default()
+    }
+
+    fn save_ignored(&self, ignored: &IgnoreList) {
+        let _ = ignored;
+    }
+
+    /// Freigegebene und gesperrte Chats (/allow, /deny); ohne eigene
+    /// Implementierung nicht gespeichert, dann gilt nur ALLOWED_CHAT_IDS
+    fn load_access(&self) -> AccessList {
+        AccessList::default()
+    }
+
+    fn save_access(&self, access: &AccessList) {
+        let _ = access;
+    }
+
+    /// Chats, die den Bot benutzt haben (/broadcast, /users); ohne eigene
+    /// Implementierung nicht gespeichert, dann kennt der Bot nach einem
+    /// Neustart nur die Chats mit Konfiguration
+    fn load_known_chats(&self) -> KnownChats {
+        KnownChats::default()
+    }
+
+    fn save_known_chats(&self, chats: &KnownChats) {
+        let _ = chats;
+    }
+
+    /// Für den Selbsttest: lässt sich der Speicher beschreiben? Ohne eigene
+    /// Implementierung ungeprüft (None)
+    fn check_writable(&self) -> Option<Result<(), String>> {
+        None
+    }
+
+    /// Für /debug: Name und Größe in Bytes je Datei, None wenn sie fehlt.
+    /// Ohne eigene Implementierung keine Angaben.
+    fn file_sizes(&self) -> Vec<(String, Option<u64>)> {
+        Vec::new()
+    }
+
+    /// Warten, bis alle Änderungen tatsächlich gespeichert sind, etwa vor
+    /// dem Beenden. Ohne eigene Implementierung speichert `save_*` sofort.
+    /// Blockiert; aus asynchronem Code über [`flush`].
+    fn flush(&self) {}
+
+    /// Sicherung in `dir` anlegen und auf `keep` Stück rotieren; liefert den
+    /// Pfad der neuen Sicherung. Ohne eigene Implementierung nicht unterstützt.
+    /// Blockiert wie `flush`; aus asynchronem Code über [`backup`].
+    fn backup(&self, dir: &Path, keep: usize) -> Result<PathBuf, String> {
+        let _ = (dir, keep);
+        Err("Dieser Speicher unterstützt keine Sicherungen".to_string())
+    }
+}
+
+/// JSON-Dateien für Benutzerkonfiguration (STATE_FILE), Messwertverlauf (HISTORY_FILE),
+/// Laufzeiten des Bots (UPTIME_FILE), Rekordwerte (RECORDS_FILE), ignorierte Geräte
+/// (IGNORED_FILE), freigegebene Chats (ACCESS_FILE), bekannte Chats (KNOWN_CHATS_FILE)
+/// und gelöschte Konfigurationen (ARCHIVE_FILE) sowie Erreichbarkeit der Chats
+/// (REACHABILITY_FILE)
+pub struct JsonStore {
+    users_path: PathBuf,
+    history_path: PathBuf,
+    uptime_path: PathBuf,
+    records_path: PathBuf,
+    archive_path: PathBuf,
+    reachability_path: PathBuf,
+    ignored_path: PathBuf,
+    access_path: PathBuf,
+    known_chats_path: PathBuf,
+    writes: Arc<Writes>,
+}
+
+impl JsonStore {
+    pub fn from_env() -> JsonStore {
+        JsonStore {
+            users_path: env::var("STATE_FILE").unwrap_or_else(|_| "state.json".into()).into(),
+            history_path: env::var("HISTORY_FILE").unwrap_or_else(|_| "history.json".into()).into(),
+            uptime_path: env::var("UPTIME_FILE").unwrap_or_else(|_| "uptime.json".into()).into(),
+            records_path: env::var("RECORDS_FILE").unwrap_or_else(|_| "records.json".into()).into(),
+            archive_path: env::var("ARCHIVE_FILE").unwrap_or_else(|_| "archive.json".into()).into(),
+            reachability_path: env::var("REACHABILITY_FILE").unwrap_or_else(|_| "reachability.json".into()).into(),
+            ignored_path: env::var("IGNORED_FILE").unwrap_or_else(|_| "ignored.json".into()).into(),
+            access_path: env::var("ACCESS_FILE").unwrap_or_else(|_| "access.json".into()).into(),
+            known_chats_path: env::var("KNOWN_CHATS_FILE").unwrap_or_else(|_| "known_chats.json".into()).into(),
+            writes: Arc::default(),
+        }
+    }
+
+    // Dateien mit ihrem Namen in einer Sicherung. Das Archiv gehört nicht
+    // dazu: was gelöscht wurde, soll nicht über Sicherungen zurückkommen.
+    fn files(&self) -> [(&'static str, &Path); 7] {
+        [
+            ("state.json", &self.users_path),
+            ("history.json", &self.history_path),
+            ("uptime.json", &self.uptime_path),
+            ("records.json", &self.records_path),
+            ("ignored.json", &self.ignored_path),
+            ("access.json", &self.access_path),
+            ("known_chats.json", &self.known_chats_path),
+        ]
+    }
+
+    /// Geprüfte Sicherung über die aktuellen Dateien spielen; der Bot darf
+    /// dabei nicht laufen
+    pub fn restore(&self, backup: &Path) -> Result<Vec<PathBuf>, String> {
+        backup::restore(backup, &self.files())
+    }
+
+    /// Alle Dateien mit ihren Standardnamen in einem Verzeichnis
+    pub fn in_dir(dir: impl Into<PathBuf>) -> JsonStore {
+        let dir = dir.into();
+        JsonStore {
+            users_path: dir.join("state.json"),
+            history_path: dir.join("history.json"),
+            uptime_path: dir.join("uptime.json"),
+            records_path: dir.join("records.json"),
+            archive_path: dir.join("archive.json"),
+            reachability_path: dir.join("reachability.json"),
+            ignored_path: dir.join("ignored.json"),
+            access_path: dir.join("access.json"),
+            known_chats_path: dir.join("known_chats.json"),
+            writes: Arc::default(),
+        }
+    }
+}
+
+impl Store for JsonStore {
+    fn load_users(&self) -> HashMap<i64, UserConfig> {
+        load(&self.users_path)
+    }
+
+    fn save_users(&self, users: &HashMap<i64, UserConfig>) {
+        save(&self.writes, &self.users_path, users);
+    }
+
+    fn load_history(&self) -> History {
+        load(&self.history_path)
+    }
+
+    fn save_history(&self, history: &History) {
+        save(&self.writes, &self.history_path, history);
+    }
+
+    fn load_uptime(&self) -> UptimeLog {
+        load(&self.uptime_path)
+    }
+
+    fn save_uptime(&self, uptime: &UptimeLog) {
+        save(&self.writes, &self.uptime_path, uptime);
+    }
+
+    fn load_records(&self) -> Records {
+        load(&self.records_path)
+    }
+
+    fn save_records(&self, records: &Records) {
+        save(&self.writes, &self.records_path, records);
+    }
+
+    fn load_archive(&self) -> Archive {
+        load(&self.archive_path)
+    }
+
+    fn save_archive(&self, archive: &Archive) {
+        save(&self.writes, &self.archive_path, archive);
+    }
+
+    fn load_reachability(&self) -> Reachability {
+        load(&self.reachability_path)
+    }
+
+    fn save_reachability(&self, reachability: &Reachability) {
+        save(&self.writes, &self.reachability_path, reachability);
+    }
+
+    fn load_ignored(&self) -> IgnoreList {
+        load(&self.ignored_path)
+    }
+
+    fn save_ignored(&self, ignored: &IgnoreList) {
+        save(&self.writes, &self.ignored_path, ignored);
+    }
+
+    fn load_access(&self) -> AccessList {
+        load(&self.access_path)
+    }
+
+    fn save_access(&self, access: &AccessList) {
+        save(&self.writes, &self.access_path, access);
+    }
+
+    fn load_known_chats(&self) -> KnownChats {
+        load(&self.known_chats_path)
+    }
+
+    fn save_known_chats(&self, chats: &KnownChats) {
+        save(&self.writes, &self.known_chats_path, chats);
+    }
+
+    fn flush(&self) {
+        self.writes.wait();
+    }
+
+    fn backup(&self, dir: &Path, keep: usize) -> Result<PathBuf, String> {
+        self.writes.wait();
+        backup::create(&self.files(), dir, keep, chrono::Utc::now())
+    }
+
+    fn check_writable(&self) -> Option<Result<(), String>> {
+        let extra = [&self.archive_path, &self.reachability_path];
+        Some(self.files().into_iter().map(|(_, path)| path).chain(extra.map(PathBuf::as_path)).try_for_each(writable))
+    }
+
+    fn file_sizes(&self) -> Vec<(String, Option<u64>)> {
+        let extra = [("archive.json", self.archive_path.as_path()), ("reachability.json", self.reachability_path.as_path())];
+        self.files().into_iter().chain(extra).map(|(name, path)| (name.to_string(), fs::metadata(path).ok().map(|m| m.len()))).collect()
+    }
+}
+
+/// [`Store::flush`] auf einem Blocking-Thread, damit das Warten keinen
+/// Worker von Tokio belegt
+pub async fn flush(storage: &Arc<dyn Store>) {
+    let storage = storage.clone();
+    tokio::task::spawn_blocking(move || storage.flush()).await.ok();
+}
+
+/// [`Store::backup`] auf einem Blocking-Thread, wie [`flush`]
+pub async fn backup(storage: &Arc<dyn Store>, dir: &Path, keep: usize) -> Result<PathBuf, String> {
+    let (storage, dir) = (storage.clone(), dir.to_path_buf());
+    tokio::task::spawn_blocking(move || storage.backup(&dir, keep)).await.unwrap_or_else(|err| Err(err.to_string()))
+}
+
+/// Meldungen über Dateien, die beim Laden aus einem früheren Stand
+/// wiederhergestellt wurden; jede wird nur einmal geliefert
+pub fn take_recoveries() -> Vec<String> {
+    std::mem::take(&mut *RECOVERED.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+fn generation(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", n));
+    path.with_file_name(name)
+}
+
+enum Read<T> {
+    Missing,
+    Broken(String),
+    Loaded(T),
+}
+
+fn read<T: DeserializeOwned>(path: &Path) -> Read<T> {
+    match fs::read_to_string(path) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(value) => Read::Loaded(value),
+            Err(err) => Read::Broken(err.to_string()),
+        },
+        Err(err) if err.kind() == ErrorKind::NotFound => Read::Missing,
+        Err(err) => Read::Broken(err.to_string()),
+    }
+}
+
+// Fehlende Datei: leerer Zustand. Defekte Datei: der neueste lesbare
+// frühere Stand; die defekte Datei wird als <name>.corrupt beiseitegelegt,
+// damit sie nicht in die früheren Stände rotiert. Ohne lesbaren Stand
+// bleibt es beim leeren Zustand. Fehlt die Datei, obwohl es frühere Stände
+// gibt (etwa von Hand gelöscht), gilt das ebenfalls als defekt.
+fn load<T: DeserializeOwned + Default>(path: &Path) -> T {
+    let problem = match read(path) {
+        Read::Loaded(value) => return value,
+        Read::Missing if !generation(path, 1).exists() => return T::default(),
+        Read::Missing => "fehlt".to_string(),
+        Read::Broken(err) => {
+            let mut aside = path.as_os_str().to_os_string();
+            aside.push(".corrupt");
+            fs::rename(path, &aside).ok();
+            format!("ist fehlerhaft ({})", err)
+        }
+    };
+    for n in 1..=GENERATIONS {
+        let previous = generation(path, n);
+        match read(&previous) {
+            Read::Loaded(value) => {
+                warn!("{} {}, verwende früheren Stand {}", path.display(), problem, previous.display());
+                RECOVERED.lock().unwrap_or_else(|e| e.into_inner()).push(format!(
+                    "{} {}; früherer Stand {} geladen",
+                    path.display(),
+                    problem,
+                    previous.display()
+                ));
+                return value;
+            }
+            Read::Broken(err) => warn!("Früherer Stand {} ist ebenfalls fehlerhaft: {}", previous.display(), err),
+            Read::Missing => {}
+        }
+    }
+    warn!("{} {} und es gibt keinen lesbaren früheren Stand, starte leer", path.display(), problem);
+    RECOVERED.lock().unwrap_or_else(|e| e.into_inner()).push(format!("{} {}; kein lesbarer früherer Stand, leer gestartet", path.display(), problem));
+    T::default()
+}
+
+// Atomar ersetzt, damit Sicherungen und Absturz nie eine halbe Datei sehen.
+// Der bisherige Stand rückt dabei zu <name>.1, der davor zu <name>.2. In der
+// Laufzeit von Tokio schreibt ein Blocking-Thread, damit fsync keinen
+// Befehl aufhält; `flush` wartet auf ihn.
+fn save<T: Serialize>(writes: &Arc<Writes>, path: &Path, value: &T) {
+    let json = match serde_json::to_string_pretty(value) {
+        Ok(json) => json,
+        Err(err) => {
+            warn!("{} kann nicht gespeichert werden: {}", path.display(), err);
+            return;
+        }
+    };
+    let path = path.to_path_buf();
+    let seq = {
+        let mut latest = writes.latest.lock().unwrap_or_else(|e| e.into_inner());
+        let seq = latest.get(&path).map_or(0, |n| n + 1);
+        latest.insert(path.clone(), seq);
+        seq
+    };
+    match tokio::runtime::Handle::try_current() {
+        Ok(runtime) => {
+            *writes.pending.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+            let writes = writes.clone();
+            runtime.spawn_blocking(move || {
+                write_generation(&writes, &path, &json, seq);
+                let mut pending = writes.pending.lock().unwrap_or_else(|e| e.into_inner());
+                *pending -= 1;
+                if *pending == 0 {
+                    writes.idle.notify_all();
+                }
+            });
+        }
+        Err(_) => write_generation(writes, &path, &json, seq),
+    }
+}
+
+// Schreibt `json` als neuen Stand, außer ein neuerer Auftrag für dieselbe
+// Datei wartet schon. Der bisherige Stand wird zuerst als <name>.1
+// verlinkt (oder kopiert) und erst dann ersetzt, die Datei selbst fehlt
+// also zu keinem Zeitpunkt.
+fn write_generation(writes: &Writes, path: &Path, json: &str, seq: u64) {
+    let _writing = writes.writing.lock().unwrap_or_else(|e| e.into_inner());
+    if writes.latest.lock().unwrap_or_else(|e| e.into_inner()).get(path) != Some(&seq) {
+        return;
+    }
+    let result = backup::write_synced(path, json.as_bytes()).map_err(|err| err.to_string()).and_then(|tmp| {
+        for n in (1..GENERATIONS).rev() {
+            let older = generation(path, n);
+            if older.exists() {
+                fs::rename(&older, generation(path, n + 1)).map_err(|err| err.to_string())?;
+            }
+        }
+        if path.exists() {
+            let previous = generation(path, 1);
+            fs::remove_file(&previous).ok();
+            if fs::hard_link(path, &previous).is_err() {
+                fs::copy(path, &previous).map_err(|err| err.to_string())?;
+            }
+        }
+        fs::rename(&tmp, path).map_err(|err| err.to_string())?;
+        backup::sync_dir(path);
+        Ok(())
+    });
+    if let Err(err) = result {
+        warn!("{} kann nicht gespeichert werden: {}", path.display(), err);
+    }
+}
+
+impl Writes {
+    // Wartet, bis alle Speichervorgänge aus `save` geschrieben sind; nur
+    // außerhalb der Worker von Tokio aufrufen
+    fn wait(&self) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        while *pending > 0 {
+            pending = self.idle.wait(pending).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+// Datei zum Schreiben öffnen und daneben eine Zwischendatei anlegen, wie
+// beim Speichern
+fn writable(path: &Path) -> Result<(), String> {
+    let fail = |err: std::io::Error| format!("{}: {}", path.display(), err);
+    if path.exists() {
+        fs::OpenOptions::new().append(true).open(path).map_err(fail)?;
+    }
+    let tmp = backup::write_synced(path, b"").map_err(fail)?;
+    fs::remove_file(tmp).map_err(fail)
+}
+
+// Serde-Hilfe für Maps mit (Gerät, Typ)-Schlüssel, die in JSON keine
+// Objektschlüssel sein können: gespeichert als Liste von Einträgen.
+// Der Typ liegt als Zeichenkette vor (auch `SensorKind`/`ThresholdKey`);
+// Einträge, die sich nicht mehr lesen lassen, werden beim Laden verworfen.
+pub mod keyed_map {
+    use log::warn;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+    use std::fmt::Display;
+    use std::hash::Hash;
+    use std::str::FromStr;
+
+    #[derive(Serialize)]
+    struct EntryRef<'a, V> {
+        device_id: &'a str,
+        sensor_type: String,
+        value: &'a V,
+    }
+
+    #[derive(Deserialize)]
+    struct Entry<V> {
+        device_id: String,
+        sensor_type: String,
+        value: V,
+    }
+
+    pub fn serialize<S, K, V>(map: &HashMap<(String, K), V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        K: Display,
+        V: Serialize,
+    {
+        let mut entries: Vec<EntryRef<V>> = map.iter().map(|((device_id, key), value)| EntryRef { device_id, sensor_type: key.to_string(), value }).collect();
+        entries.sort_by(|a, b| (a.device_id, &a.sensor_type).cmp(&(b.device_id, &b.sensor_type)));
+        entries.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D, K, V>(deserializer: D) -> Result<HashMap<(String, K), V>, D::Error>
+    where
+        D: Deserializer<'de>,
+        K: FromStr + Eq + Hash,
+        K::Err: Display,
+        V: Deserialize<'de>,
+    {
+        let entries = Vec::<Entry<V>>::deserialize(deserializer)?;
+        Ok(entries
+            .into_iter()
+            .filter_map(|e| match e.sensor_type.parse() {
+                Ok(key) => Some(((e.device_id, key), e.value)),
+                Err(err) => {
+                    warn!("Eintrag für {} verworfen: {}", e.device_id, err);
+                    None
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::WeeklySchedule;
+    use crate::snooze::Origin;
+    use proptest::prelude::*;
+
+    // Eigenes leeres Verzeichnis je Test
+    fn dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("telegrambot-storage-{}-{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn recovered(path: &Path) -> Vec<String> {
+        let path = path.display().to_string();
+        RECOVERED.lock().unwrap().iter().filter(|m| m.starts_with(&format!("{} ", path))).cloned().collect()
+    }
+
+    #[test]
+    fn save_keeps_two_generations() {
+        let path = dir("generations").join("state.json");
+        for n in 1..=3u32 {
+            save(&Arc::default(), &path, &vec![n]);
+        }
+        assert_eq!(load::<Vec<u32>>(&path), vec![3]);
+        assert_eq!(read::<Vec<u32>>(&generation(&path, 1)).loaded(), Some(vec![2]));
+        assert_eq!(read::<Vec<u32>>(&generation(&path, 2)).loaded(), Some(vec![1]));
+        assert!(!generation(&path, 3).exists());
+    }
+
+    #[test]
+    fn truncated_file_falls_back_to_previous_generation() {
+        let path = dir("truncated").join("state.json");
+        save(&Arc::default(), &path, &vec![1u32]);
+        save(&Arc::default(), &path, &vec![1u32, 2]);
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, &content[..content.len() / 2]).unwrap();
+
+        assert_eq!(load::<Vec<u32>>(&path), vec![1]);
+        assert!(path.with_file_name("state.json.corrupt").exists());
+        assert!(recovered(&path).iter().any(|m| m.contains("state.json.1 geladen")));
+    }
+
+    #[test]
+    fn garbage_in_file_and_first_generation_uses_the_second() {
+        let path = dir("garbage").join("state.json");
+        for n in 1..=3u32 {
+            save(&Arc::default(), &path, &vec![n]);
+        }
+        fs::write(&path, "\u{0}\u{1}kein json").unwrap();
+        fs::write(generation(&path, 1), "{]").unwrap();
+
+        assert_eq!(load::<Vec<u32>>(&path), vec![1]);
+        assert!(recovered(&path).iter().any(|m| m.contains("state.json.2 geladen")));
+    }
+
+    #[test]
+    fn nothing_readable_starts_empty_instead_of_inventing_data() {
+        let path = dir("nothing").join("state.json");
+        fs::write(&path, "[1, 2").unwrap();
+        fs::write(generation(&path, 1), "").unwrap();
+
+        assert_eq!(load::<Vec<u32>>(&path), Vec::<u32>::new());
+        assert!(recovered(&path).iter().any(|m| m.contains("leer gestartet")));
+    }
+
+    #[test]
+    fn missing_file_without_generations_is_a_fresh_start() {
+        let path = dir("fresh").join("state.json");
+        assert_eq!(load::<Vec<u32>>(&path), Vec::<u32>::new());
+        assert!(recovered(&path).is_empty());
+    }
+
+    #[test]
+    fn missing_file_with_generations_counts_as_broken() {
+        let path = dir("missing").join("state.json");
+        save(&Arc::default(), &path, &vec![1u32]);
+        save(&Arc::default(), &path, &vec![2u32]);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(load::<Vec<u32>>(&path), vec![1]);
+        assert!(recovered(&path).iter().any(|m| m.contains("fehlt")));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn flush_waits_for_writes_of_its_own_store_only() {
+        let (busy, idle) = (JsonStore::in_dir(dir("busy")), JsonStore::in_dir(dir("idle")));
+        // Ein fremder Thread hält das Schreiben an, bis `release` kommt
+        let (writes, (locked, held), (release, wait)) = (busy.writes.clone(), std::sync::mpsc::channel(), std::sync::mpsc::channel::<()>());
+        std::thread::spawn(move || {
+            let _blocked = writes.writing.lock().unwrap();
+            locked.send(()).unwrap();
+            wait.recv().ok();
+        });
+        held.recv().unwrap();
+        busy.save_records(&Records::default());
+        let (busy, idle): (Arc<dyn Store>, Arc<dyn Store>) = (Arc::new(busy), Arc::new(idle));
+
+        // Der andere Speicher hat nichts offen und wartet nicht mit
+        tokio::time::timeout(std::time::Duration::from_secs(5), flush(&idle)).await.expect("flush wartet auf fremden Speicher");
+        let waiting = tokio::spawn({
+            let busy = busy.clone();
+            async move { flush(&busy).await }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+
+        release.send(()).unwrap();
+        waiting.await.unwrap();
+        assert!(busy.file_sizes().iter().any(|(name, size)| name == "records.json" && size.is_some()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn backup_sees_the_latest_save() {
+        let root = dir("backup-latest");
+        let store: Arc<dyn Store> = Arc::new(JsonStore::in_dir(&root));
+        let mut chats = KnownChats::default();
+        for n in 1..=5 {
+            chats.command(n, chrono::Utc::now());
+            store.save_known_chats(&chats);
+        }
+        let path = backup(&store, &root.join("sicherungen"), 1).await.unwrap();
+        let copy: KnownChats = load(&path.join("known_chats.json"));
+        assert_eq!(copy.iter().count(), 5);
+    }
+
+    // Gespeicherter Stand als JSON; UserConfig selbst ist nicht vergleichbar
+    fn stored<T: Serialize>(value: &T) -> serde_json::Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    fn users() -> impl Strategy<Value = HashMap<i64, UserConfig>> {
+        proptest::collection::hash_map(any::<i64>(), any::<UserConfig>(), 0..3)
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn users_survive_a_round_trip(users in users()) {
+            let store = JsonStore::in_dir(dir("roundtrip"));
+            store.save_users(&users);
+            prop_assert_eq!(stored(&store.load_users()), stored(&users));
+        }
+
+        // Schwellen unter einem Raumnamen wandern zur Geräte-ID; eine dort
+        // schon gesetzte geht vor. Der umgestellte Stand übersteht das
+        // Speichern, und ein zweiter Lauf beim nächsten Start ändert nichts.
+        #[test]
+        fn migrated_users_survive_a_round_trip(users in users()) {
+            let mut migrated = users.clone();
+            crate::migrate_configs(&mut migrated);
+            for (chat, config) in &users {
+                let after = &migrated[chat];
+                for ((device, key), schedule) in &config.thresholds {
+                    let kept = after.thresholds.get(&(device.clone(), key.clone()));
+                    if device.eq_ignore_ascii_case("wohnzimmer") {
+                        prop_assert!(kept.is_none());
+                        prop_assert!(after.thresholds.contains_key(&("sensor1".to_string(), key.clone())), "{} verloren", key);
+                    } else {
+                        prop_assert_eq!(kept, Some(schedule));
+                    }
+                }
+                let (mut before, mut now) = (stored(config), stored(after));
+                before["thresholds"] = serde_json::Value::Null;
+                now["thresholds"] = serde_json::Value::Null;
+                prop_assert_eq!(now, before);
+            }
+
+            let store = JsonStore::in_dir(dir("migrated"));
+            store.save_users(&migrated);
+            let mut loaded = store.load_users();
+            prop_assert_eq!(crate::migrate_configs(&mut loaded), 0);
+            prop_assert_eq!(stored(&loaded), stored(&migrated));
+        }
+
+        // Alte Schreibweisen: Tageszusammenfassung als einzelne Uhrzeit,
+        // Stummschaltung nur mit ihrem Ende
+        #[test]
+        fn legacy_fields_load_in_their_current_form(config in any::<UserConfig>(), at in crate::arbitrary::minute()) {
+            let mut value = stored(&config);
+            value["daily_summary"] = at.format("%H:%M:%S").to_string().into();
+            for entry in value["snoozed"].as_array_mut().unwrap() {
+                entry["value"] = entry["value"]["until"].clone();
+            }
+            let path = dir("legacy").join("state.json");
+            fs::write(&path, serde_json::json!({ "1": value }).to_string()).unwrap();
+            let loaded = &load::<HashMap<i64, UserConfig>>(&path)[&1];
+            prop_assert_eq!(loaded.daily_summary.as_ref(), Some(&WeeklySchedule::daily(at)));
+            prop_assert_eq!(loaded.snoozed.len(), config.snoozed.len());
+            for (key, snooze) in &config.snoozed {
+                let legacy = &loaded.snoozed[key];
+                prop_assert_eq!((legacy.until, legacy.since, legacy.origin), (snooze.until, snooze.until, Origin::Reaction));
+            }
+        }
+    }
+
+    impl<T> Read<T> {
+        fn loaded(self) -> Option<T> {
+            match self {
+                Read::Loaded(value) => Some(value),
+                _ => None,
+            }
+        }
+    }
+}
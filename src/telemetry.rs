@@ -0,0 +1,243 @@
+// Kennzahlen des Bots selbst im Textformat von Prometheus (METRICS_PORT).
+// Ohne METRICS_PORT zählt nichts mit.
+#![cfg_attr(not(feature = "http-api"), allow(dead_code))]
+use crate::settings;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{LazyLock, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+// Obergrenzen der Abrufdauer in Sekunden, dazu +Inf
+const FETCH_SECONDS_BUCKETS: [f64; 8] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+// Obergrenzen der Wartezeit in der Ausgangswarteschlange; Sammelsendungen
+// und Wiederholungen nach Fehlern brauchen Minuten
+const OUTBOX_WAIT_SECONDS_BUCKETS: [f64; 9] = [0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0];
+
+// Histogramm mit festen Obergrenzen; gezählt wird kumulativ wie bei Prometheus
+struct Histogram<const N: usize> {
+    buckets: [u64; N],
+    sum: f64,
+    count: u64,
+}
+
+impl<const N: usize> Default for Histogram<N> {
+    fn default() -> Self {
+        Histogram { buckets: [0; N], sum: 0.0, count: 0 }
+    }
+}
+
+impl<const N: usize> Histogram<N> {
+    fn observe(&mut self, bounds: &[f64; N], seconds: f64) {
+        self.sum += seconds;
+        self.count += 1;
+        for (bucket, _) in self.buckets.iter_mut().zip(bounds).filter(|(_, le)| seconds <= **le) {
+            *bucket += 1;
+        }
+    }
+
+    // Zeilen mit den weiteren Labels (z.B. tenant="x"), `le` kommt dahinter
+    fn samples(&self, bounds: &[f64; N], labels: &str) -> Vec<(String, String)> {
+        let prefix = if labels.is_empty() { String::new() } else { format!("{},", labels) };
+        let braces = if labels.is_empty() { String::new() } else { format!("{{{}}}", labels) };
+        let mut samples: Vec<(String, String)> =
+            bounds.iter().zip(self.buckets).map(|(le, count)| (format!("_bucket{{{}le=\"{}\"}}", prefix, le), count.to_string())).collect();
+        samples.push((format!("_bucket{{{}le=\"+Inf\"}}", prefix), self.count.to_string()));
+        samples.push((format!("_sum{}", braces), self.sum.to_string()));
+        samples.push((format!("_count{}", braces), self.count.to_string()));
+        samples
+    }
+}
+
+// Haushalt als Label-Wert; der Haupthaushalt hat den leeren, wie ein
+// fehlendes Label bei Prometheus
+type TenantLabel = String;
+
+fn tenant_label(tenant: Option<&str>) -> TenantLabel {
+    tenant.unwrap_or_default().to_string()
+}
+
+// Anführungszeichen, Backslash und Zeilenumbruch maskieren (Textformat)
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[derive(Default)]
+struct Registry {
+    fetch_attempts: BTreeMap<TenantLabel, u64>,
+    fetch_failures: BTreeMap<TenantLabel, u64>,
+    alerts_sent: BTreeMap<TenantLabel, u64>,
+    send_errors: BTreeMap<TenantLabel, u64>,
+    handler_panics: u64,
+    commands: BTreeMap<(TenantLabel, String), u64>,
+    sensors: BTreeMap<TenantLabel, usize>,
+    thresholds: BTreeMap<TenantLabel, usize>,
+    last_success: BTreeMap<TenantLabel, Instant>,
+    fetch_seconds: BTreeMap<TenantLabel, Histogram<{ FETCH_SECONDS_BUCKETS.len() }>>,
+    outbox_wait_seconds: Histogram<{ OUTBOX_WAIT_SECONDS_BUCKETS.len() }>,
+}
+
+static REGISTRY: LazyLock<Mutex<Registry>> = LazyLock::new(Default::default);
+
+fn registry() -> Option<MutexGuard<'static, Registry>> {
+    settings().metrics_port?;
+    Some(REGISTRY.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+// Ein Abruf einer Quelle des Haushalts `tenant`
+pub fn fetch(tenant: Option<&str>, duration: Duration, ok: bool) {
+    let Some(mut registry) = registry() else { return };
+    let tenant = tenant_label(tenant);
+    *registry.fetch_attempts.entry(tenant.clone()).or_default() += 1;
+    if ok {
+        registry.last_success.insert(tenant.clone(), Instant::now());
+    } else {
+        *registry.fetch_failures.entry(tenant.clone()).or_default() += 1;
+    }
+    registry.fetch_seconds.entry(tenant).or_default().observe(&FETCH_SECONDS_BUCKETS, duration.as_secs_f64());
+}
+
+// Nachricht verlässt die Ausgangswarteschlange: Zeit vom Einreihen bis zum
+// letzten Zustellversuch
+pub fn outbox_wait(wait: Duration) {
+    if let Some(mut registry) = registry() {
+        registry.outbox_wait_seconds.observe(&OUTBOX_WAIT_SECONDS_BUCKETS, wait.as_secs_f64());
+    }
+}
+
+pub fn alerts_sent(tenant: Option<&str>, count: usize) {
+    if let Some(mut registry) = registry() {
+        *registry.alerts_sent.entry(tenant_label(tenant)).or_default() += count as u64;
+    }
+}
+
+// Nachricht endgültig nicht zugestellt
+pub fn send_error(tenant: Option<&str>) {
+    if let Some(mut registry) = registry() {
+        *registry.send_errors.entry(tenant_label(tenant)).or_default() += 1;
+    }
+}
+
+// Panik in einem Befehl oder Button; die Anfrage wurde trotzdem beantwortet
+pub fn handler_panic() {
+    if let Some(mut registry) = registry() {
+        registry.handler_panics += 1;
+    }
+}
+
+// Befehl ohne Schrägstrich und Bot-Namen, z.B. "status"
+pub fn command(tenant: Option<&str>, name: &str) {
+    if let Some(mut registry) = registry() {
+        *registry.commands.entry((tenant_label(tenant), name.to_string())).or_default() += 1;
+    }
+}
+
+// Stand nach jeder Abfrage der Überwachung, je Haushalt; Haushalte ohne
+// Sensoren oder Schwellen mit 0
+pub fn gauges(sensors: BTreeMap<Option<String>, usize>, thresholds: BTreeMap<Option<String>, usize>) {
+    if let Some(mut registry) = registry() {
+        let labelled = |counts: BTreeMap<Option<String>, usize>| counts.into_iter().map(|(tenant, count)| (tenant_label(tenant.as_deref()), count)).collect();
+        registry.sensors = labelled(sensors);
+        registry.thresholds = labelled(thresholds);
+    }
+}
+
+pub fn render(now: Instant) -> String {
+    render_registry(&REGISTRY.lock().unwrap_or_else(|e| e.into_inner()), now)
+}
+
+fn render_registry(registry: &Registry, now: Instant) -> String {
+    let mut text = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+        let _ = writeln!(text, "# HELP telegrambot_{} {}", name, help);
+        let _ = writeln!(text, "# TYPE telegrambot_{} {}", name, kind);
+        for (suffix, value) in samples {
+            let _ = writeln!(text, "telegrambot_{}{} {}", name, suffix, value);
+        }
+    };
+    let plain = |value: String| vec![(String::new(), value)];
+    let tenant = |tenant: &str| format!("tenant=\"{}\"", escape(tenant));
+    let per_tenant =
+        |counts: Vec<(&TenantLabel, String)>| -> Vec<(String, String)> { counts.into_iter().map(|(t, value)| (format!("{{{}}}", tenant(t)), value)).collect() };
+    let counts = |counts: &BTreeMap<TenantLabel, u64>| per_tenant(counts.iter().map(|(t, count)| (t, count.to_string())).collect());
+    let sizes = |counts: &BTreeMap<TenantLabel, usize>| per_tenant(counts.iter().map(|(t, count)| (t, count.to_string())).collect());
+
+    metric("fetch_attempts_total", "counter", "Abrufe der Sensorquellen je Haushalt", &counts(&registry.fetch_attempts));
+    metric("fetch_failures_total", "counter", "Abrufe ohne Antwort der Quelle je Haushalt", &counts(&registry.fetch_failures));
+    metric("alerts_sent_total", "counter", "Zugestellte Warnungen je Haushalt", &counts(&registry.alerts_sent));
+    metric("telegram_send_errors_total", "counter", "Endgültig nicht zugestellte Nachrichten je Haushalt", &counts(&registry.send_errors));
+    metric("handler_panics_total", "counter", "Abgefangene Paniken in Befehlen und Buttons", &plain(registry.handler_panics.to_string()));
+    let commands: Vec<(String, String)> =
+        registry.commands.iter().map(|((t, name), count)| (format!("{{{},command=\"{}\"}}", tenant(t), escape(name)), count.to_string())).collect();
+    metric("commands_total", "counter", "Bearbeitete Befehle je Haushalt und Befehl", &commands);
+    metric("sensors", "gauge", "Messgrößen der letzten Abfrage je Haushalt", &sizes(&registry.sensors));
+    metric("thresholds", "gauge", "Eingerichtete Schwellen der Chats je Haushalt", &sizes(&registry.thresholds));
+    // Noch kein erfolgreicher Abruf: kein Wert statt einer erfundenen Zahl
+    let since = per_tenant(registry.last_success.iter().map(|(t, at)| (t, format!("{:.0}", now.saturating_duration_since(*at).as_secs_f64()))).collect());
+    metric("seconds_since_last_fetch", "gauge", "Sekunden seit dem letzten erfolgreichen Abruf je Haushalt", &since);
+
+    let durations: Vec<(String, String)> =
+        registry.fetch_seconds.iter().flat_map(|(t, histogram)| histogram.samples(&FETCH_SECONDS_BUCKETS, &tenant(t))).collect();
+    metric("fetch_duration_seconds", "histogram", "Dauer der Abrufe je Haushalt", &durations);
+    metric(
+        "outbox_wait_seconds",
+        "histogram",
+        "Wartezeit der Nachrichten vom Einreihen bis zum Versand",
+        &registry.outbox_wait_seconds.samples(&OUTBOX_WAIT_SECONDS_BUCKETS, ""),
+    );
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_counts_cumulatively() {
+        let bounds = [0.5, 1.0, 5.0];
+        let mut histogram = Histogram::<3>::default();
+        for seconds in [0.2, 0.5, 3.0, 60.0] {
+            histogram.observe(&bounds, seconds);
+        }
+        let samples: Vec<String> = histogram.samples(&bounds, "").into_iter().map(|(suffix, value)| format!("{} {}", suffix, value)).collect();
+        assert_eq!(samples, ["_bucket{le=\"0.5\"} 2", "_bucket{le=\"1\"} 2", "_bucket{le=\"5\"} 3", "_bucket{le=\"+Inf\"} 4", "_sum 63.7", "_count 4"]);
+    }
+
+    #[test]
+    fn outbox_wait_is_exposed_next_to_the_fetch_duration() {
+        let text = render(Instant::now());
+        assert!(text.contains("# TYPE telegrambot_fetch_duration_seconds histogram\n"), "{}", text);
+        assert!(text.contains("# TYPE telegrambot_outbox_wait_seconds histogram\n"), "{}", text);
+        assert!(text.contains("telegrambot_outbox_wait_seconds_bucket{le=\"300\"} "), "{}", text);
+        assert!(text.contains("telegrambot_outbox_wait_seconds_bucket{le=\"+Inf\"} "), "{}", text);
+    }
+
+    #[test]
+    fn counters_and_gauges_carry_the_tenant() {
+        let now = Instant::now();
+        let mut registry = Registry {
+            fetch_attempts: BTreeMap::from([(String::new(), 3), ("nachbar".to_string(), 2)]),
+            alerts_sent: BTreeMap::from([("nachbar".to_string(), 1)]),
+            commands: BTreeMap::from([((String::new(), "status".to_string()), 4), (("nachbar".to_string(), "sta\"tus".to_string()), 1)]),
+            sensors: BTreeMap::from([(String::new(), 2), ("nachbar".to_string(), 0)]),
+            last_success: BTreeMap::from([("nachbar".to_string(), now - Duration::from_secs(42))]),
+            ..Registry::default()
+        };
+        registry.fetch_seconds.entry("nachbar".to_string()).or_default().observe(&FETCH_SECONDS_BUCKETS, 0.3);
+        let text = render_registry(&registry, now);
+        for line in [
+            "telegrambot_fetch_attempts_total{tenant=\"\"} 3\n",
+            "telegrambot_fetch_attempts_total{tenant=\"nachbar\"} 2\n",
+            "telegrambot_alerts_sent_total{tenant=\"nachbar\"} 1\n",
+            "telegrambot_commands_total{tenant=\"\",command=\"status\"} 4\n",
+            "telegrambot_commands_total{tenant=\"nachbar\",command=\"sta\\\"tus\"} 1\n",
+            "telegrambot_sensors{tenant=\"\"} 2\n",
+            "telegrambot_sensors{tenant=\"nachbar\"} 0\n",
+            "telegrambot_seconds_since_last_fetch{tenant=\"nachbar\"} 42\n",
+            "telegrambot_fetch_duration_seconds_bucket{tenant=\"nachbar\",le=\"0.5\"} 1\n",
+            "telegrambot_fetch_duration_seconds_count{tenant=\"nachbar\"} 1\n",
+        ] {
+            assert!(text.contains(line), "{} fehlt in\n{}", line.trim_end(), text);
+        }
+        assert!(!text.contains("alerts_sent_total{tenant=\"\"}"), "{}", text);
+    }
+}
@@ -0,0 +1,311 @@
+use crate::backup;
+use crate::history::History;
+use crate::records::Records;
+use crate::sensor::SensorKind;
+use chrono::{DateTime, Duration, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Verwendung: import-thingspeak --channel <id> [--api-key <schlüssel>, sonst THINGSPEAK_READ_KEY] --map field<n>=<gerät>:<typ>...";
+
+const API_URL: &str = "https://api.thingspeak.com";
+
+// Je Abruf ein Tag: ThingSpeak liefert höchstens 8000 Einträge, ein Kanal
+// schreibt bei 15 s Mindestabstand höchstens 5760 am Tag
+const PAGE_DAYS: i64 = 1;
+const MAX_RESULTS: usize = 8000;
+
+// Drosselung (429) und Serverfehler: Wartezeit verdoppeln, dann aufgeben
+const FIRST_BACKOFF_SECONDS: u64 = 2;
+const MAX_ATTEMPTS: u32 = 6;
+
+// Abfrage für ein Diagramm: ein Versuch, der Chat wartet darauf
+#[cfg_attr(not(feature = "charts"), allow(dead_code))]
+const FEED_TIMEOUT_SECONDS: u64 = 10;
+
+// Ein Feld des Kanals als Messreihe, z.B. field1=sensor1:temperature
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldMapping {
+    pub field: u8,
+    pub device_id: String,
+    pub sensor_type: SensorKind,
+}
+
+pub fn parse_mapping(spec: &str) -> Result<FieldMapping, String> {
+    let invalid = || format!("--map '{}': erwartet field<1-8>=<gerät>:<typ>", spec);
+    let (field, target) = spec.split_once('=').ok_or_else(invalid)?;
+    let field = field.trim().to_lowercase();
+    let field: u8 = field.strip_prefix("field").and_then(|n| n.parse().ok()).ok_or_else(invalid)?;
+    if !(1..=8).contains(&field) {
+        return Err(invalid());
+    }
+    let (device_id, sensor_type) = target.split_once(':').ok_or_else(invalid)?;
+    let (device_id, sensor_type) = (device_id.trim(), sensor_type.trim());
+    if device_id.is_empty() || sensor_type.is_empty() {
+        return Err(invalid());
+    }
+    Ok(FieldMapping { field, device_id: device_id.to_string(), sensor_type: SensorKind::from(sensor_type) })
+}
+
+struct Options {
+    channel: u64,
+    api_key: Option<String>,
+    mappings: Vec<FieldMapping>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut channel = None;
+    let mut api_key = None;
+    let mut mappings: Vec<FieldMapping> = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} ohne Wert", arg));
+        match arg.as_str() {
+            "--channel" => {
+                let id = value()?;
+                channel = Some(id.parse::<u64>().map_err(|_| format!("--channel '{}' ist keine Zahl", id))?);
+            }
+            "--api-key" => api_key = Some(value()?.clone()),
+            "--map" => {
+                let mapping = parse_mapping(value()?)?;
+                if mappings.iter().any(|m| m.field == mapping.field) {
+                    return Err(format!("field{} ist mehrfach zugeordnet", mapping.field));
+                }
+                mappings.push(mapping);
+            }
+            _ => return Err(format!("Unbekanntes Argument '{}'", arg)),
+        }
+    }
+
+    let channel = channel.ok_or("--channel fehlt")?;
+    if mappings.is_empty() {
+        return Err("Mindestens ein --map angeben".to_string());
+    }
+    let api_key = api_key.or_else(|| env::var("THINGSPEAK_READ_KEY").ok().filter(|key| !key.trim().is_empty()));
+    Ok(Options { channel, api_key, mappings })
+}
+
+// Stand des Imports je Kanal, damit ein abgebrochener Lauf dort weitermacht
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Progress {
+    last_entry_id: u64,
+    last_created_at: DateTime<Utc>,
+}
+
+fn progress_path() -> PathBuf {
+    env::var("THINGSPEAK_IMPORT_FILE").unwrap_or_else(|_| "thingspeak-import.json".into()).into()
+}
+
+fn load_progress() -> BTreeMap<u64, Progress> {
+    fs::read_to_string(progress_path()).ok().and_then(|content| serde_json::from_str(&content).ok()).unwrap_or_default()
+}
+
+fn save_progress(progress: &BTreeMap<u64, Progress>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(progress).map_err(|err| err.to_string())?;
+    backup::write_atomic(&progress_path(), json.as_bytes()).map_err(|err| format!("{} kann nicht gespeichert werden: {}", progress_path().display(), err))
+}
+
+#[derive(Deserialize)]
+struct Feeds {
+    channel: ChannelInfo,
+    #[serde(default)]
+    feeds: Vec<Entry>,
+}
+
+#[derive(Deserialize)]
+struct ChannelInfo {
+    created_at: DateTime<Utc>,
+    #[serde(default)]
+    last_entry_id: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    created_at: DateTime<Utc>,
+    entry_id: u64,
+    #[serde(flatten)]
+    fields: BTreeMap<String, serde_json::Value>,
+}
+
+impl Entry {
+    // Felder sind Zeichenketten oder null; alles Nicht-Numerische entfällt
+    fn value(&self, field: u8) -> Option<f64> {
+        let value = self.fields.get(&format!("field{}", field))?;
+        let value = match value {
+            serde_json::Value::String(text) => text.trim().replace(',', ".").parse().ok()?,
+            serde_json::Value::Number(number) => number.as_f64()?,
+            _ => return None,
+        };
+        Some(value).filter(|v: &f64| v.is_finite())
+    }
+}
+
+async fn fetch(client: &reqwest::Client, path: &str, api_key: Option<&str>, query: &[(&str, String)], attempts: u32) -> Result<Feeds, String> {
+    let url = format!("{}{}", API_URL, path);
+    let mut query = query.to_vec();
+    if let Some(key) = api_key {
+        query.push(("api_key", key.to_string()));
+    }
+    let mut wait = std::time::Duration::from_secs(FIRST_BACKOFF_SECONDS);
+    for attempt in 1..=attempts {
+        let result = client.get(&url).query(&query).send().await;
+        let retry = match result {
+            Ok(response) if response.status().is_success() => {
+                let body = response.text().await.map_err(|err| format!("Antwort unlesbar: {}", err.without_url()))?;
+                // Private Kanäle ohne passenden Schlüssel liefern 200 mit "-1"
+                if body.trim() == "-1" {
+                    return Err("Kanal nicht öffentlich, Lese-Schlüssel fehlt oder ist falsch".to_string());
+                }
+                return serde_json::from_str::<Feeds>(&body).map_err(|err| format!("Antwort unlesbar: {}", err));
+            }
+            Ok(response) if response.status().as_u16() == 429 || response.status().is_server_error() => {
+                format!("ThingSpeak antwortet mit {}", response.status())
+            }
+            Ok(response) => return Err(format!("ThingSpeak antwortet mit {}", response.status())),
+            Err(err) => err.without_url().to_string(),
+        };
+        if attempt == attempts {
+            return Err(if attempts > 1 { format!("{} (nach {} Versuchen)", retry, attempts) } else { retry });
+        }
+        warn!("{}, neuer Versuch in {} s", retry, wait.as_secs());
+        tokio::time::sleep(wait).await;
+        wait *= 2;
+    }
+    unreachable!("Schleife endet mit return")
+}
+
+fn thingspeak_time(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// Werte eines Felds seit `since` (Unix-Sekunden), älteste zuerst. Einträge
+// ohne Zahl im Feld fallen weg; ein leerer Kanal ergibt eine leere Liste.
+#[cfg_attr(not(feature = "charts"), allow(dead_code))]
+pub async fn field_feed(channel: u64, field: u8, api_key: Option<&str>, since: i64) -> Result<Vec<(i64, f64)>, String> {
+    let client = reqwest::Client::builder().timeout(std::time::Duration::from_secs(FEED_TIMEOUT_SECONDS)).build().map_err(|err| err.to_string())?;
+    let start = DateTime::from_timestamp(since, 0).unwrap_or_default();
+    let query = [("start", thingspeak_time(start)), ("results", MAX_RESULTS.to_string())];
+    let path = format!("/channels/{}/fields/{}.json", channel, field);
+    let mut points: Vec<(i64, f64)> =
+        fetch(&client, &path, api_key, &query, 1).await?.feeds.iter().filter_map(|entry| Some((entry.created_at.timestamp(), entry.value(field)?))).collect();
+    points.sort_by_key(|(ts, _)| *ts);
+    Ok(points)
+}
+
+// Ergebnis eines Imports
+pub struct Imported {
+    pub entries: usize,
+    pub samples: usize,
+    pub stored: usize, // davon in den Verlauf eingefügt (ohne Doppelte, innerhalb der Aufbewahrung)
+}
+
+// Alle Einträge seit dem letzten Lauf tageweise abholen und in Verlauf und
+// Rekorde übernehmen. Der Fortschritt wird nach jedem Tag gesichert.
+pub async fn run(
+    args: &[String],
+    history: &mut History,
+    records: &mut Records,
+    year: impl Fn(i64) -> i32,
+    mut save: impl FnMut(&History, &Records),
+) -> Result<Imported, String> {
+    let options = parse_options(args)?;
+    let client = reqwest::Client::new();
+    let mut all_progress = load_progress();
+    let resume = all_progress.get(&options.channel).copied();
+
+    let path = format!("/channels/{}/feeds.json", options.channel);
+    let api_key = options.api_key.as_deref();
+    let info = fetch(&client, &path, api_key, &[("results", "0".to_string())], MAX_ATTEMPTS).await?.channel;
+    let last_entry_id = info.last_entry_id.unwrap_or_default();
+    let mut cursor = resume.map_or(info.created_at, |p| p.last_created_at);
+    let mut done = resume.map_or(0, |p| p.last_entry_id);
+    match resume {
+        Some(p) => println!("Kanal {}: weiter ab Eintrag {} ({})", options.channel, p.last_entry_id, thingspeak_time(p.last_created_at)),
+        None => println!("Kanal {}: {} Einträge seit {}", options.channel, last_entry_id, thingspeak_time(info.created_at)),
+    }
+
+    let mut imported = Imported { entries: 0, samples: 0, stored: 0 };
+    let now = Utc::now();
+    while cursor <= now && done < last_entry_id {
+        let end = cursor + Duration::days(PAGE_DAYS);
+        let query = [("start", thingspeak_time(cursor)), ("end", thingspeak_time(end)), ("results", MAX_RESULTS.to_string())];
+        let mut page = fetch(&client, &path, api_key, &query, MAX_ATTEMPTS).await?.feeds;
+        if page.len() >= MAX_RESULTS {
+            warn!("{} – {}: {} Einträge, möglicherweise unvollständig", thingspeak_time(cursor), thingspeak_time(end), page.len());
+        }
+        page.sort_by_key(|entry| entry.entry_id);
+
+        let mut samples = Vec::new();
+        let resume_after = done;
+        for entry in page.iter().filter(|entry| entry.entry_id > resume_after) {
+            let timestamp = entry.created_at.timestamp();
+            for mapping in &options.mappings {
+                if let Some(value) = entry.value(mapping.field) {
+                    samples.push((mapping.device_id.clone(), mapping.sensor_type.as_str().to_string(), timestamp, value));
+                }
+            }
+            done = entry.entry_id;
+            imported.entries += 1;
+        }
+        for (device_id, sensor_type, timestamp, value) in &samples {
+            records.observe(device_id, sensor_type, *timestamp, *value, year(*timestamp));
+        }
+        imported.samples += samples.len();
+        imported.stored += history.import(samples);
+        history.compact(Utc::now().timestamp());
+
+        // Nächster Tag beginnt am Ende dieses; Einträge genau auf der Grenze
+        // schließt die Eintragsnummer aus
+        cursor = end;
+        if let Some(last) = page.last().filter(|entry| entry.entry_id == done) {
+            all_progress.insert(options.channel, Progress { last_entry_id: done, last_created_at: last.created_at });
+            save(history, records);
+            save_progress(&all_progress)?;
+            println!("{}: Eintrag {} von {}", thingspeak_time(last.created_at), done, last_entry_id);
+        }
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn mapping_names_field_device_and_type() {
+        let mapping = parse_mapping(" Field3 = sensor1 : Humidity ").unwrap();
+        assert_eq!(mapping, FieldMapping { field: 3, device_id: "sensor1".into(), sensor_type: SensorKind::Humidity });
+        // Unbekannte Typen bleiben erhalten wie bei den Messwerten
+        assert_eq!(parse_mapping("field8=keller:radon").unwrap().sensor_type, SensorKind::Other("radon".into()));
+    }
+
+    #[test]
+    fn unknown_channel_field_is_rejected() {
+        for spec in ["field0=sensor1:temperature", "field9=sensor1:temperature", "feld1=sensor1:temperature", "field=sensor1:temperature"] {
+            assert!(parse_mapping(spec).unwrap_err().contains(spec), "{}", spec);
+        }
+        for spec in ["field1", "field1=sensor1", "field1=:temperature", "field1=sensor1: "] {
+            assert!(parse_mapping(spec).is_err(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn field_mapped_twice_is_rejected() {
+        let twice = args(&["--channel", "42", "--map", "field1=sensor1:temperature", "--map", "FIELD1=sensor2:humidity"]);
+        assert_eq!(parse_options(&twice).err().unwrap(), "field1 ist mehrfach zugeordnet");
+
+        // Zwei Felder auf dasselbe Gerät sind erlaubt
+        let both = args(&["--channel", "42", "--map", "field1=sensor1:temperature", "--map", "field2=sensor1:humidity", "--api-key", "k"]);
+        let options = parse_options(&both).unwrap();
+        assert_eq!((options.channel, options.mappings.len(), options.api_key.as_deref()), (42, 2, Some("k")));
+    }
+}
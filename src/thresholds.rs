@@ -0,0 +1,315 @@
+use crate::i18n::Text;
+use crate::routing::Severity;
+use crate::sensor::{SensorKind, ThresholdDirection};
+use chrono::NaiveTime;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+// Zeitfenster in lokaler Uhrzeit, z.B. 07:00-22:00.
+// Das Ende ist exklusiv. Liegt das Ende vor dem Start (22:00-07:00),
+// reicht das Fenster über Mitternacht.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary), proptest(filter = "|w| w.start != w.end"))]
+pub struct TimeWindow {
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::minute()"))]
+    pub start: NaiveTime,
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::minute()"))]
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    pub fn contains(&self, t: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= t && t < self.end
+        } else {
+            // über Mitternacht
+            t >= self.start || t < self.end
+        }
+    }
+
+    pub fn overlaps(&self, other: &TimeWindow) -> bool {
+        self.contains(other.start) || other.contains(self.start)
+    }
+}
+
+impl FromStr for TimeWindow {
+    type Err = Text;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s.split_once('-').ok_or_else(|| Text::new("window_format", &[("window", s)]))?;
+        let parse = |t: &str| NaiveTime::parse_from_str(t.trim(), "%H:%M").map_err(|_| Text::new("invalid_time", &[("time", t.trim())]));
+        let window = TimeWindow { start: parse(start)?, end: parse(end)? };
+        if window.start == window.end {
+            return Err(Text::new("window_empty", &[]));
+        }
+        Ok(window)
+    }
+}
+
+impl fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start.format("%H:%M"), self.end.format("%H:%M"))
+    }
+}
+
+// Gespeichert als "HH:MM-HH:MM"
+impl Serialize for TimeWindow {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeWindow {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// Schwellen außerhalb dieses Bereichs sind Tippfehler, kein Raum erreicht sie
+pub fn plausible_range(kind: &SensorKind) -> Option<(f64, f64)> {
+    match kind {
+        SensorKind::Temperature | SensorKind::DewPoint | SensorKind::HeatIndex => Some((-50.0, 80.0)),
+        SensorKind::Humidity | SensorKind::AbsoluteHumidity => Some((0.0, 100.0)),
+        _ => None,
+    }
+}
+
+// Vorlage für /apply-default: übliche Grenzen für Wohnräume in °C und %
+pub const DEFAULT_TEMPLATE: &[(SensorKind, ThresholdDirection, f64)] = &[
+    (SensorKind::Temperature, ThresholdDirection::Min, 18.0),
+    (SensorKind::Temperature, ThresholdDirection::Max, 26.0),
+    (SensorKind::Humidity, ThresholdDirection::Min, 35.0),
+    (SensorKind::Humidity, ThresholdDirection::Max, 65.0),
+];
+
+// Ein Schwellwert, optional nur innerhalb eines Zeitfensters gültig
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+pub struct ThresholdEntry {
+    pub value: f64,
+    pub window: Option<TimeWindow>,
+    #[serde(default)]
+    pub source: String, // Befehl, mit dem der Eintrag gesetzt wurde
+}
+
+// Alle Einträge für eine Schwelle (Gerät + Typ + Richtung).
+// Einträge mit Zeitfenster haben Vorrang, der Eintrag ohne Fenster gilt sonst.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+#[serde(transparent)]
+pub struct ThresholdSchedule {
+    #[cfg_attr(test, proptest(strategy = "crate::arbitrary::few::<ThresholdEntry, _>()"))]
+    entries: Vec<ThresholdEntry>,
+}
+
+impl ThresholdSchedule {
+    // Setzt den Wert für das Fenster (oder den Standardwert ohne Fenster).
+    // Ein bestehender Eintrag mit gleichem Fenster wird ersetzt,
+    // überlappende andere Fenster werden abgelehnt.
+    pub fn set(&mut self, value: f64, window: Option<TimeWindow>, source: String) -> Result<(), Text> {
+        if let Some(w) = window
+            && let Some(other) = self.entries.iter().filter_map(|e| e.window).find(|other| *other != w && other.overlaps(&w))
+        {
+            return Err(Text::new("window_overlap", &[("window", &w.to_string()), ("other", &other.to_string())]));
+        }
+        self.upsert(value, window, source);
+        Ok(())
+    }
+
+    // Setzt den Wert, der außerhalb aller Zeitfenster gilt
+    pub fn set_default(&mut self, value: f64, source: String) {
+        self.upsert(value, None, source);
+    }
+
+    fn upsert(&mut self, value: f64, window: Option<TimeWindow>, source: String) {
+        match self.entries.iter_mut().find(|e| e.window == window) {
+            Some(entry) => {
+                entry.value = value;
+                entry.source = source;
+            }
+            None => self.entries.push(ThresholdEntry { value, window, source }),
+        }
+        self.entries.sort_by_key(|e| e.window.map(|w| w.start));
+    }
+
+    pub fn entries(&self) -> &[ThresholdEntry] {
+        &self.entries
+    }
+
+    // Gültiger Eintrag zur lokalen Uhrzeit `t`
+    pub fn active_entry(&self, t: NaiveTime) -> Option<&ThresholdEntry> {
+        self.entries.iter().find(|e| e.window.is_some_and(|w| w.contains(t))).or_else(|| self.entries.iter().find(|e| e.window.is_none()))
+    }
+}
+
+// Eigene Stufe und eigener Text für die Warnungen einer Schwelle, ohne
+// Stufe gilt die Einstufung nach [routing]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+pub struct AlertStyle {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+impl AlertStyle {
+    pub fn is_empty(&self) -> bool {
+        self.severity.is_none() && self.text.is_none()
+    }
+}
+
+// Argumente für /setmin und /setmax:
+// <gerät> <typ> <wert> [HH:MM-HH:MM] [info|warn|critical] ["Text"]
+#[derive(Debug, Clone)]
+pub struct ThresholdArgs {
+    pub device: String,
+    pub sensor_type: SensorKind,
+    pub value: f64,
+    pub window: Option<TimeWindow>,
+    pub style: AlertStyle,
+}
+
+// Auch typografische Anführungszeichen, die Telegram auf dem Handy einsetzt
+const QUOTES: &[char] = &['"', '„', '“', '”'];
+
+impl FromStr for ThresholdArgs {
+    type Err = Text;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let usage = || Text::new("threshold_args_usage", &[]);
+
+        // Der Text in Anführungszeichen darf Leerzeichen enthalten und steht am Ende
+        let (s, text) = match s.split_once(QUOTES) {
+            Some((before, text)) => {
+                let text = text.trim().trim_end_matches(QUOTES).trim();
+                if text.is_empty() {
+                    return Err(Text::new("threshold_args_text_empty", &[]).and(usage()));
+                }
+                (before, Some(text.to_string()))
+            }
+            None => (s, None),
+        };
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        if parts.len() < 3 || parts.len() > 5 {
+            return Err(usage());
+        }
+        let value = parts[2].replace(',', ".").parse::<f64>().map_err(|_| Text::new("threshold_args_not_a_number", &[("value", parts[2])]).and(usage()))?;
+        let mut window = None;
+        let mut severity = None;
+        for part in &parts[3..] {
+            if severity.is_none() && part.parse::<Severity>().is_ok() {
+                severity = part.parse().ok();
+            } else if window.is_none() && severity.is_none() {
+                window = Some(part.parse()?);
+            } else {
+                return Err(usage());
+            }
+        }
+
+        Ok(ThresholdArgs { device: parts[0].to_string(), sensor_type: SensorKind::from(parts[1]), value, window, style: AlertStyle { severity, text } })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(text: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(text, "%H:%M").unwrap()
+    }
+
+    fn w(text: &str) -> TimeWindow {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn window_includes_start_and_excludes_end() {
+        let day = w("07:00-22:00");
+        assert!(day.contains(t("07:00")));
+        assert!(day.contains(t("21:59")));
+        assert!(!day.contains(t("22:00")));
+        assert!(!day.contains(t("06:59")));
+    }
+
+    #[test]
+    fn window_wraps_over_midnight() {
+        let night = w("22:00-07:00");
+        assert!(night.contains(t("22:00")));
+        assert!(night.contains(t("23:59")));
+        assert!(night.contains(t("00:00")));
+        assert!(night.contains(t("06:59")));
+        assert!(!night.contains(t("07:00")));
+        assert!(!night.contains(t("12:00")));
+        assert!(!night.contains(t("21:59")));
+    }
+
+    #[test]
+    fn overlaps_is_symmetric_and_adjacent_windows_do_not_overlap() {
+        for (a, b, expected) in [
+            ("07:00-12:00", "11:00-18:00", true),
+            ("07:00-12:00", "12:00-18:00", false),
+            ("07:00-18:00", "09:00-10:00", true),
+            ("22:00-07:00", "06:00-08:00", true),
+            ("22:00-07:00", "07:00-22:00", false),
+            ("22:00-07:00", "23:00-01:00", true),
+            ("20:00-02:00", "01:00-03:00", true),
+            ("20:00-02:00", "02:00-20:00", false),
+        ] {
+            assert_eq!(w(a).overlaps(&w(b)), expected, "{} / {}", a, b);
+            assert_eq!(w(b).overlaps(&w(a)), expected, "{} / {}", b, a);
+        }
+    }
+
+    #[test]
+    fn window_parsing_and_display() {
+        assert_eq!(w(" 22:00 - 07:00 ").to_string(), "22:00-07:00");
+        for (input, reason) in [
+            ("22:00", "muss die Form HH:MM-HH:MM haben"),
+            ("22:00-25:00", "Ungültige Uhrzeit '25:00'"),
+            ("abends-07:00", "Ungültige Uhrzeit 'abends'"),
+            ("07:00-07:00", "dürfen nicht gleich sein"),
+        ] {
+            let err = input.parse::<TimeWindow>().unwrap_err().to_string();
+            assert!(err.contains(reason), "{}: {}", input, err);
+        }
+        let json = serde_json::to_string(&w("22:00-07:00")).unwrap();
+        assert_eq!(json, "\"22:00-07:00\"");
+        assert_eq!(serde_json::from_str::<TimeWindow>(&json).unwrap(), w("22:00-07:00"));
+    }
+
+    #[test]
+    fn overlapping_windows_are_rejected_and_the_same_window_is_replaced() {
+        let mut schedule = ThresholdSchedule::default();
+        schedule.set(18.0, Some(w("22:00-06:00")), "/setmin".into()).unwrap();
+        let err = schedule.set(16.0, Some(w("05:00-08:00")), "/setmin".into()).unwrap_err();
+        assert_eq!(err.to_string(), "Zeitfenster 05:00-08:00 überschneidet sich mit 22:00-06:00");
+        schedule.set(17.0, Some(w("22:00-06:00")), "/setmin neu".into()).unwrap();
+        schedule.set(20.0, Some(w("06:00-09:00")), "/setmin".into()).unwrap();
+        schedule.set(19.0, None, "/setmin".into()).unwrap();
+
+        let entries = schedule.entries();
+        assert_eq!(entries.len(), 3);
+        // Ohne Fenster zuerst, dann nach Beginn sortiert
+        assert_eq!(entries.iter().map(|e| e.window).collect::<Vec<_>>(), [None, Some(w("06:00-09:00")), Some(w("22:00-06:00"))]);
+        assert_eq!(entries[2].value, 17.0);
+        assert_eq!(entries[2].source, "/setmin neu");
+    }
+
+    #[test]
+    fn active_entry_prefers_a_window_and_falls_back_to_the_default() {
+        let mut schedule = ThresholdSchedule::default();
+        assert!(schedule.active_entry(t("12:00")).is_none());
+
+        schedule.set(16.0, Some(w("22:00-06:00")), "/setmin".into()).unwrap();
+        assert!(schedule.active_entry(t("12:00")).is_none());
+        assert_eq!(schedule.active_entry(t("23:00")).map(|e| e.value), Some(16.0));
+
+        schedule.set_default(19.0, "/setmin".into());
+        assert_eq!(schedule.active_entry(t("12:00")).map(|e| e.value), Some(19.0));
+        assert_eq!(schedule.active_entry(t("22:00")).map(|e| e.value), Some(16.0));
+        assert_eq!(schedule.active_entry(t("05:59")).map(|e| e.value), Some(16.0));
+        assert_eq!(schedule.active_entry(t("06:00")).map(|e| e.value), Some(19.0));
+    }
+}
@@ -0,0 +1,137 @@
+use crate::i18n::{Lang, Text};
+use crate::timeutil;
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+// Bis hierhin gilt ein Messwert als "gerade eben", auch knapp in der Zukunft
+// (Uhr der Quelle geht etwas vor)
+const JUST_NOW_SECONDS: i64 = 10;
+
+// Zeitangaben in /status und Warnungen je Chat (/timeformat)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+#[serde(rename_all = "lowercase")]
+pub enum TimeFormat {
+    #[default]
+    Absolute,
+    Relative,
+    Both, // "vor 3 Minuten (14:32)"
+}
+
+impl FromStr for TimeFormat {
+    type Err = Text;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "absolute" | "absolut" => Ok(TimeFormat::Absolute),
+            "relative" | "relativ" => Ok(TimeFormat::Relative),
+            "both" | "beides" => Ok(TimeFormat::Both),
+            _ => Err(Text::new("timeformat_usage", &[])),
+        }
+    }
+}
+
+// Alter in Worten: "vor 3 Minuten", "2 hours ago"; negativ (Uhr der Quelle
+// geht vor) als "in 5 Minuten"
+pub fn ago(age: Duration, lang: Lang) -> String {
+    let seconds = age.num_seconds();
+    if seconds.abs() < JUST_NOW_SECONDS {
+        return match lang {
+            Lang::De => "gerade eben".to_string(),
+            Lang::En => "just now".to_string(),
+        };
+    }
+    let amount = seconds.abs();
+    // (Anzahl, Einheit Singular, Plural); deutsch nach "vor" und "in" im Dativ
+    let (n, one, many) = match (amount, lang) {
+        (0..60, Lang::De) => (amount, "Sekunde", "Sekunden"),
+        (60..3600, Lang::De) => (amount / 60, "Minute", "Minuten"),
+        (3600..86400, Lang::De) => (amount / 3600, "Stunde", "Stunden"),
+        (_, Lang::De) => (amount / 86400, "Tag", "Tagen"),
+        (0..60, Lang::En) => (amount, "second", "seconds"),
+        (60..3600, Lang::En) => (amount / 60, "minute", "minutes"),
+        (3600..86400, Lang::En) => (amount / 3600, "hour", "hours"),
+        (_, Lang::En) => (amount / 86400, "day", "days"),
+    };
+    let unit = if n == 1 { one } else { many };
+    match (lang, seconds < 0) {
+        (Lang::De, false) => format!("vor {} {}", n, unit),
+        (Lang::De, true) => format!("in {} {}", n, unit),
+        (Lang::En, false) => format!("{} {} ago", n, unit),
+        (Lang::En, true) => format!("in {} {}", n, unit),
+    }
+}
+
+// Wie `ago`, aber ab dem Vortag (Ortszeit) mit Uhrzeit: "gestern 14:32"
+pub fn relative(at: DateTime<Utc>, now: DateTime<Utc>, tz: Option<Tz>, lang: Lang) -> String {
+    let age = now - at;
+    let yesterday = timeutil::local_date_of(now, tz).pred_opt();
+    if age >= Duration::hours(1) && Some(timeutil::local_date_of(at, tz)) == yesterday {
+        let time = timeutil::format_local(at, tz, "%H:%M");
+        return match lang {
+            Lang::De => format!("gestern {}", time),
+            Lang::En => format!("yesterday {}", time),
+        };
+    }
+    ago(age, lang)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn de(seconds: i64) -> String {
+        ago(Duration::seconds(seconds), Lang::De)
+    }
+
+    fn en(seconds: i64) -> String {
+        ago(Duration::seconds(seconds), Lang::En)
+    }
+
+    #[test]
+    fn boundaries_between_units() {
+        assert_eq!(de(9), "gerade eben");
+        assert_eq!(de(10), "vor 10 Sekunden");
+        assert_eq!(de(59), "vor 59 Sekunden");
+        assert_eq!(de(60), "vor 1 Minute");
+        assert_eq!(de(3_599), "vor 59 Minuten");
+        assert_eq!(de(3_600), "vor 1 Stunde");
+        assert_eq!(de(86_399), "vor 23 Stunden");
+        assert_eq!(de(86_400), "vor 1 Tag");
+        assert_eq!(de(3 * 86_400), "vor 3 Tagen");
+    }
+
+    #[test]
+    fn english_singular_and_plural() {
+        assert_eq!(en(0), "just now");
+        assert_eq!(en(1), "just now");
+        assert_eq!(en(61), "1 minute ago");
+        assert_eq!(en(2 * 3_600), "2 hours ago");
+        assert_eq!(en(86_400), "1 day ago");
+    }
+
+    // Uhr der Quelle geht vor
+    #[test]
+    fn future_timestamps() {
+        assert_eq!(de(-9), "gerade eben");
+        assert_eq!(de(-10), "in 10 Sekunden");
+        assert_eq!(de(-5 * 60), "in 5 Minuten");
+        assert_eq!(en(-3_600), "in 1 hour");
+        assert_eq!(en(-2 * 86_400), "in 2 days");
+    }
+
+    #[test]
+    fn yesterday_with_local_time() {
+        let tz = Some(chrono_tz::Europe::Berlin);
+        let now = Utc.with_ymd_and_hms(2024, 3, 2, 8, 0, 0).unwrap();
+        assert_eq!(relative(Utc.with_ymd_and_hms(2024, 3, 1, 13, 32, 0).unwrap(), now, tz, Lang::De), "gestern 14:32");
+        assert_eq!(relative(Utc.with_ymd_and_hms(2024, 3, 1, 13, 32, 0).unwrap(), now, tz, Lang::En), "yesterday 14:32");
+        // Unter einer Stunde bleibt es relativ, auch über Mitternacht
+        let after_midnight = Utc.with_ymd_and_hms(2024, 3, 1, 23, 10, 0).unwrap();
+        assert_eq!(relative(Utc.with_ymd_and_hms(2024, 3, 1, 22, 50, 0).unwrap(), after_midnight, tz, Lang::De), "vor 20 Minuten");
+        assert_eq!(relative(Utc.with_ymd_and_hms(2024, 2, 28, 8, 0, 0).unwrap(), now, tz, Lang::De), "vor 3 Tagen");
+    }
+}
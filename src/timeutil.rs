@@ -0,0 +1,182 @@
+// Zeitrechnung an einer Stelle. Regeln:
+// - Takt der Schleifen: monotone `Instant`s (tokio::time), nie die Uhrzeit
+// - gespeicherte Daten und Abstände: Unix-Sekunden bzw. `DateTime<Utc>`
+// - Ortszeit (konfigurierte Zeitzone, sonst die des Systems) nur für Anzeige
+//   und für Zeitpläne/Zeitfenster, die sich auf die Wanduhr beziehen
+// So zählt die Zeitumstellung keine Stunde doppelt und lässt keine aus.
+use crate::schedule::WeeklySchedule;
+use crate::thresholds::TimeWindow;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+
+// Ab hier sind Zeitstempel einer Quelle Millisekunden: 10^11 Sekunden wären
+// das Jahr 5138, 10^11 Millisekunden der März 1973
+const MILLIS_FROM: i64 = 100_000_000_000;
+// Ältere Zeitstempel gelten als ungesetzt (0, negativ, Uhr ohne Zeitabgleich)
+const EARLIEST_VALID: i64 = 946_684_800; // 01.01.2000
+
+pub fn from_timestamp(timestamp: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(timestamp, 0).unwrap_or_default()
+}
+
+// Zeitstempel einer Quelle in Sekunden, egal ob sie Sekunden oder
+// Millisekunden schickt
+pub fn normalize_timestamp(raw: i64) -> i64 {
+    if raw.abs() >= MILLIS_FROM { raw / 1000 } else { raw }
+}
+
+// Taugt der Zeitstempel für die Anzeige? Sonst "Zeit unbekannt" statt 01.01.1970
+pub fn is_known(timestamp: i64) -> bool {
+    timestamp >= EARLIEST_VALID && DateTime::from_timestamp(timestamp, 0).is_some()
+}
+
+// Wanduhrzeit eines Zeitpunkts
+pub fn local_time_of(dt: DateTime<Utc>, tz: Option<Tz>) -> NaiveTime {
+    match tz {
+        Some(tz) => dt.with_timezone(&tz).time(),
+        None => dt.with_timezone(&Local).time(),
+    }
+}
+
+pub fn local_date_of(dt: DateTime<Utc>, tz: Option<Tz>) -> NaiveDate {
+    match tz {
+        Some(tz) => dt.with_timezone(&tz).date_naive(),
+        None => dt.with_timezone(&Local).date_naive(),
+    }
+}
+
+pub fn local_year_of(dt: DateTime<Utc>, tz: Option<Tz>) -> i32 {
+    local_date_of(dt, tz).year()
+}
+
+pub fn format_local(dt: DateTime<Utc>, tz: Option<Tz>, fmt: &str) -> String {
+    match tz {
+        Some(tz) => dt.with_timezone(&tz).format(fmt).to_string(),
+        None => dt.with_timezone(&Local).format(fmt).to_string(),
+    }
+}
+
+// Liegt der Zeitpunkt im Wanduhr-Fenster? Gilt auch in der Nacht der
+// Umstellung: 02:30 gibt es im März nicht, im Oktober zweimal.
+pub fn within_local_window(window: &TimeWindow, at: DateTime<Utc>, tz: Option<Tz>) -> bool {
+    window.contains(local_time_of(at, tz))
+}
+
+// Nächster Termin eines Wochenplans nach `after`, als UTC
+pub fn next_fire(schedule: &WeeklySchedule, after: DateTime<Utc>, tz: Option<Tz>) -> Option<DateTime<Utc>> {
+    match tz {
+        Some(tz) => schedule.next_after(&after.with_timezone(&tz)).map(|dt| dt.with_timezone(&Utc)),
+        None => schedule.next_after(&after.with_timezone(&Local)).map(|dt| dt.with_timezone(&Utc)),
+    }
+}
+
+// Tatsächlich vergangene Sekunden zwischen zwei Messwerten, unabhängig von
+// der Wanduhr (eine Umstellung verlängert oder verkürzt nichts)
+pub fn seconds_between(earlier: i64, later: i64) -> i64 {
+    (later - earlier).max(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seconds_and_milliseconds() {
+        assert_eq!(normalize_timestamp(1_700_000_000), 1_700_000_000);
+        assert_eq!(normalize_timestamp(1_700_000_000_123), 1_700_000_000);
+        assert_eq!(normalize_timestamp(0), 0);
+        assert_eq!(normalize_timestamp(-5), -5);
+    }
+
+    #[test]
+    fn zero_and_negative_are_unknown() {
+        assert!(is_known(1_700_000_000));
+        assert!(!is_known(0));
+        assert!(!is_known(-1_700_000_000));
+        assert!(!is_known(i64::MAX));
+        assert_eq!(from_timestamp(i64::MAX), DateTime::<Utc>::default());
+    }
+
+    // Europe/Berlin 2026: Sommerzeit ab So 29.03. 02:00 MEZ (01:00 UTC),
+    // Winterzeit ab So 25.10. 03:00 MESZ (01:00 UTC)
+    const BERLIN: Option<Tz> = Some(chrono_tz::Europe::Berlin);
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)
+    }
+
+    fn window(start: (u32, u32), end: (u32, u32)) -> TimeWindow {
+        TimeWindow { start: NaiveTime::from_hms_opt(start.0, start.1, 0).unwrap(), end: NaiveTime::from_hms_opt(end.0, end.1, 0).unwrap() }
+    }
+
+    // Jede Minute von `from` bis ausschließlich `to`
+    fn minutes(from: &str, to: &str) -> impl Iterator<Item = DateTime<Utc>> {
+        let (from, to) = (utc(from), utc(to));
+        (0..).map(move |n| from + chrono::Duration::minutes(n)).take_while(move |at| *at < to)
+    }
+
+    #[test]
+    fn window_inside_the_march_gap_never_matches() {
+        let gap = window((2, 0), (3, 0));
+        assert!(minutes("2026-03-28T23:00:00Z", "2026-03-29T03:00:00Z").all(|at| !within_local_window(&gap, at, BERLIN)));
+        // Am Tag davor und danach gibt es die Stunde
+        assert!(within_local_window(&gap, utc("2026-03-28T01:30:00Z"), BERLIN));
+        assert!(within_local_window(&gap, utc("2026-03-30T00:30:00Z"), BERLIN));
+    }
+
+    #[test]
+    fn window_spanning_the_march_gap_is_one_hour_shorter() {
+        let night = window((1, 0), (4, 0));
+        let inside = minutes("2026-03-28T22:00:00Z", "2026-03-29T04:00:00Z").filter(|at| within_local_window(&night, *at, BERLIN)).count();
+        assert_eq!(inside, 2 * 60);
+        assert!(within_local_window(&night, utc("2026-03-29T00:59:00Z"), BERLIN)); // 01:59 MEZ
+        assert!(within_local_window(&night, utc("2026-03-29T01:00:00Z"), BERLIN)); // 03:00 MESZ
+        assert!(!within_local_window(&night, utc("2026-03-29T02:00:00Z"), BERLIN)); // 04:00 MESZ
+    }
+
+    #[test]
+    fn window_in_the_repeated_october_hour_matches_both_times() {
+        let repeated = window((2, 0), (3, 0));
+        assert!(within_local_window(&repeated, utc("2026-10-25T00:30:00Z"), BERLIN)); // 02:30 MESZ
+        assert!(within_local_window(&repeated, utc("2026-10-25T01:30:00Z"), BERLIN)); // 02:30 MEZ
+        let inside = minutes("2026-10-24T22:00:00Z", "2026-10-25T04:00:00Z").filter(|at| within_local_window(&repeated, *at, BERLIN)).count();
+        assert_eq!(inside, 2 * 60);
+        assert!(!within_local_window(&repeated, utc("2026-10-25T02:00:00Z"), BERLIN)); // 03:00 MEZ
+    }
+
+    #[test]
+    fn window_over_midnight_on_transition_nights() {
+        let quiet = window((22, 0), (6, 0));
+        for (night, hours) in [("2026-03-28T20:00:00Z", 7), ("2026-10-24T19:00:00Z", 9)] {
+            let from = utc(night);
+            let to = (from + chrono::Duration::hours(12)).to_rfc3339();
+            let inside = minutes(&from.to_rfc3339(), &to).filter(|at| within_local_window(&quiet, *at, BERLIN)).count();
+            assert_eq!(inside, hours * 60, "{}", night);
+        }
+    }
+
+    #[test]
+    fn durations_count_real_seconds_across_transitions() {
+        // 01:30 MEZ bis 03:30 MESZ: auf der Uhr zwei Stunden, vergangen eine
+        let spring = (utc("2026-03-29T00:30:00Z").timestamp(), utc("2026-03-29T01:30:00Z").timestamp());
+        assert_eq!(format_local(from_timestamp(spring.0), BERLIN, "%H:%M"), "01:30");
+        assert_eq!(format_local(from_timestamp(spring.1), BERLIN, "%H:%M"), "03:30");
+        assert_eq!(seconds_between(spring.0, spring.1), 3600);
+
+        // Zweimal 02:30: auf der Uhr nichts, vergangen eine Stunde
+        let autumn = (utc("2026-10-25T00:30:00Z").timestamp(), utc("2026-10-25T01:30:00Z").timestamp());
+        assert_eq!(format_local(from_timestamp(autumn.0), BERLIN, "%H:%M"), "02:30");
+        assert_eq!(format_local(from_timestamp(autumn.1), BERLIN, "%H:%M"), "02:30");
+        assert_eq!(seconds_between(autumn.0, autumn.1), 3600);
+        assert_eq!(seconds_between(autumn.1, autumn.0), 0);
+    }
+
+    #[test]
+    fn daily_schedule_keeps_the_wall_clock_time_across_transitions() {
+        let daily = WeeklySchedule::daily(NaiveTime::from_hms_opt(8, 0, 0).unwrap());
+        let sunday = next_fire(&daily, utc("2026-03-28T07:00:00Z"), BERLIN).unwrap();
+        assert_eq!(sunday, utc("2026-03-29T06:00:00Z"));
+        assert_eq!(local_date_of(sunday, BERLIN), NaiveDate::from_ymd_opt(2026, 3, 29).unwrap());
+        assert_eq!(next_fire(&daily, utc("2026-10-24T06:00:00Z"), BERLIN), Some(utc("2026-10-25T07:00:00Z")));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::sensor::SensorKind;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Temperatureinheit eines Chats (/units). Gespeichert und verglichen wird
+/// immer in °C; umgerechnet wird nur bei Anzeige und Eingabe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+#[serde(rename_all = "lowercase")]
+pub enum TempUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+// Messgrößen in °C, die mit umgerechnet werden
+pub fn is_temperature(kind: &SensorKind) -> bool {
+    matches!(kind, SensorKind::Temperature | SensorKind::DewPoint | SensorKind::HeatIndex)
+}
+
+impl TempUnit {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            TempUnit::Celsius => "°C",
+            TempUnit::Fahrenheit => "°F",
+        }
+    }
+
+    // Gespeicherter Wert (°C) für die Anzeige
+    pub fn show(self, kind: &SensorKind, celsius: f64) -> f64 {
+        match self {
+            TempUnit::Fahrenheit if is_temperature(kind) => celsius * 9.0 / 5.0 + 32.0,
+            _ => celsius,
+        }
+    }
+
+    // Eingabe des Benutzers in den gespeicherten Wert (°C)
+    pub fn parse(self, kind: &SensorKind, shown: f64) -> f64 {
+        match self {
+            TempUnit::Fahrenheit if is_temperature(kind) => (shown - 32.0) * 5.0 / 9.0,
+            _ => shown,
+        }
+    }
+
+    // Abstände (Hysterese, Änderung je Zeitraum) ohne Nullpunkt
+    pub fn show_delta(self, kind: &SensorKind, celsius: f64) -> f64 {
+        match self {
+            TempUnit::Fahrenheit if is_temperature(kind) => celsius * 9.0 / 5.0,
+            _ => celsius,
+        }
+    }
+
+    pub fn parse_delta(self, kind: &SensorKind, shown: f64) -> f64 {
+        match self {
+            TempUnit::Fahrenheit if is_temperature(kind) => shown * 5.0 / 9.0,
+            _ => shown,
+        }
+    }
+}
+
+impl FromStr for TempUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "celsius" | "c" | "°c" => Ok(TempUnit::Celsius),
+            "fahrenheit" | "f" | "°f" => Ok(TempUnit::Fahrenheit),
+            other => Err(format!("Einheit '{}' unbekannt (celsius oder fahrenheit)", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KIND: SensorKind = SensorKind::Temperature;
+
+    #[test]
+    fn fixed_points() {
+        assert_eq!(TempUnit::Fahrenheit.show(&KIND, 0.0), 32.0);
+        assert_eq!(TempUnit::Fahrenheit.show(&KIND, 100.0), 212.0);
+        assert_eq!(TempUnit::Fahrenheit.show(&KIND, -40.0), -40.0);
+        assert_eq!(TempUnit::Fahrenheit.parse(&KIND, 77.0), 25.0);
+    }
+
+    #[test]
+    fn round_trip_keeps_precision() {
+        for tenths in -500..=1000 {
+            let celsius = tenths as f64 / 10.0;
+            let shown = TempUnit::Fahrenheit.show(&KIND, celsius);
+            assert!((TempUnit::Fahrenheit.parse(&KIND, shown) - celsius).abs() < 1e-9, "{} °C", celsius);
+            let delta = TempUnit::Fahrenheit.show_delta(&KIND, celsius);
+            assert!((TempUnit::Fahrenheit.parse_delta(&KIND, delta) - celsius).abs() < 1e-9, "Δ {} °C", celsius);
+        }
+    }
+
+    #[test]
+    fn other_quantities_unchanged() {
+        for unit in [TempUnit::Celsius, TempUnit::Fahrenheit] {
+            assert_eq!(unit.show(&SensorKind::Humidity, 55.5), 55.5);
+            assert_eq!(unit.parse(&SensorKind::Humidity, 55.5), 55.5);
+        }
+        assert_eq!(TempUnit::Celsius.show(&KIND, 21.3), 21.3);
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!("°F".parse::<TempUnit>(), Ok(TempUnit::Fahrenheit));
+        assert_eq!(" Celsius ".parse::<TempUnit>(), Ok(TempUnit::Celsius));
+        assert!("kelvin".parse::<TempUnit>().is_err());
+    }
+}
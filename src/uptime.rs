@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+
+// Ausfälle, die älter sind, werden vergessen (wie der Messwertverlauf)
+const MAX_AGE_SECONDS: i64 = 14 * 24 * 60 * 60;
+
+// Zeitraum, in dem der Bot nicht lief
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Downtime {
+    pub start: i64,
+    pub end: i64,
+    pub clean: bool, // sauber beendet oder aus dem letzten Lebenszeichen geschlossen
+}
+
+// Start, Lebenszeichen und sauberes Beenden des Bots, gespeichert in UPTIME_FILE.
+// Daraus ergeben sich die Ausfallzeiten, die in Auswertungen nicht als
+// "alles in Ordnung" zählen dürfen.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UptimeLog {
+    started_at: Option<i64>,
+    heartbeat: Option<i64>,
+    shutdown_at: Option<i64>,
+    downtime: Vec<Downtime>,
+}
+
+impl UptimeLog {
+    // Prozessstart. Der Ausfall seit dem letzten Lauf wird eingetragen:
+    // nach sauberem Beenden ab dessen Zeitpunkt, sonst ab dem letzten Lebenszeichen.
+    pub fn start(&mut self, now: i64) -> Option<Downtime> {
+        let last = match (self.shutdown_at, self.heartbeat) {
+            (Some(shutdown), heartbeat) if heartbeat.is_none_or(|h| shutdown >= h) => Some((shutdown, true)),
+            (_, Some(heartbeat)) => Some((heartbeat, false)),
+            _ => None,
+        };
+        let downtime = last.filter(|(start, _)| *start < now).map(|(start, clean)| Downtime { start, end: now, clean });
+
+        self.downtime.extend(downtime);
+        self.downtime.retain(|d| d.end >= now - MAX_AGE_SECONDS);
+        self.started_at = Some(now);
+        self.heartbeat = Some(now);
+        self.shutdown_at = None;
+        downtime
+    }
+
+    // Nach jedem Überwachungsdurchlauf
+    pub fn heartbeat(&mut self, now: i64) {
+        self.heartbeat = Some(now);
+    }
+
+    pub fn shutdown(&mut self, now: i64) {
+        self.shutdown_at = Some(now);
+    }
+
+    pub fn started_at(&self) -> Option<i64> {
+        self.started_at
+    }
+
+    pub fn last_heartbeat(&self) -> Option<i64> {
+        self.heartbeat
+    }
+
+    pub fn downtime(&self) -> &[Downtime] {
+        &self.downtime
+    }
+}
+
+// Zusammengeführte, sortierte Intervalle; überlappende verschmelzen
+pub fn merge(intervals: impl IntoIterator<Item = (i64, i64)>) -> Vec<(i64, i64)> {
+    let mut sorted: Vec<(i64, i64)> = intervals.into_iter().filter(|(s, e)| s < e).collect();
+    sorted.sort_unstable();
+    let mut merged: Vec<(i64, i64)> = Vec::new();
+    for (start, end) in sorted {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+// Wie viele Sekunden von [start, end) in die Intervalle fallen
+pub fn overlap(start: i64, end: i64, intervals: &[(i64, i64)]) -> i64 {
+    merge(intervals.iter().copied()).into_iter().map(|(s, e)| (e.min(end) - s.max(start)).max(0)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_part_inside_the_window_is_excluded() {
+        // Fenster [100, 200): teils davor, ganz darin, teils danach
+        assert_eq!(overlap(100, 200, &[(50, 120), (150, 160), (190, 250)]), 20 + 10 + 10);
+        assert_eq!(overlap(100, 200, &[(0, 500)]), 100);
+    }
+
+    #[test]
+    fn intervals_outside_or_touching_the_window_count_nothing() {
+        assert_eq!(overlap(100, 200, &[(0, 100), (200, 300), (20, 50)]), 0);
+        assert_eq!(overlap(100, 200, &[]), 0);
+    }
+
+    #[test]
+    fn overlapping_intervals_are_counted_once() {
+        assert_eq!(merge([(150, 180), (100, 160), (180, 190), (300, 300), (250, 240)]), [(100, 190)]);
+        assert_eq!(overlap(100, 200, &[(110, 150), (120, 170)]), 60);
+    }
+
+    #[test]
+    fn downtime_since_shutdown_or_last_heartbeat() {
+        let mut log = UptimeLog::default();
+        assert_eq!(log.start(1_000), None);
+        log.heartbeat(1_500);
+        log.shutdown(1_600);
+        assert_eq!(log.start(2_000), Some(Downtime { start: 1_600, end: 2_000, clean: true }));
+
+        // Ohne sauberes Beenden ab dem letzten Lebenszeichen
+        log.heartbeat(2_500);
+        assert_eq!(log.start(3_000), Some(Downtime { start: 2_500, end: 3_000, clean: false }));
+        assert_eq!(log.downtime().len(), 2);
+
+        // Ältere Ausfälle werden vergessen
+        log.start(3_000 + MAX_AGE_SECONDS + 1);
+        assert_eq!(log.downtime().iter().map(|d| d.start).collect::<Vec<_>>(), [3_000]);
+    }
+}
@@ -0,0 +1,130 @@
+// Aktuelles Wetter am Standort (WEATHER_LOCATION oder /weather-location)
+// von Open-Meteo, ohne API-Schlüssel. Nur für den Vergleich drinnen/draußen
+// in /status: ist der Dienst nicht erreichbar, fehlt die Zeile einfach.
+use crate::i18n::Text;
+use crate::settings;
+use log::warn;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const API_URL: &str = "https://api.open-meteo.com/v1/forecast";
+const CACHE_FOR: Duration = Duration::from_secs(15 * 60);
+// Nach einem Fehler erst wieder fragen, wenn diese Zeit um ist
+const RETRY_AFTER: Duration = Duration::from_secs(2 * 60);
+// /status wartet höchstens so lange auf das Wetter
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+// "49.79,9.95"
+impl FromStr for Location {
+    type Err = Text;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Text::new("location_invalid", &[("value", s.trim())]);
+        let (latitude, longitude) = s.split_once(',').ok_or_else(invalid)?;
+        let latitude: f64 = latitude.trim().parse().map_err(|_| invalid())?;
+        let longitude: f64 = longitude.trim().parse().map_err(|_| invalid())?;
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(invalid());
+        }
+        Ok(Location { latitude, longitude })
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.4},{:.4}", self.latitude, self.longitude)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weather {
+    pub temperature: f64, // °C
+    pub humidity: f64,    // %
+}
+
+#[derive(Deserialize)]
+struct Response {
+    current: Current,
+}
+
+#[derive(Deserialize)]
+struct Current {
+    temperature_2m: f64,
+    relative_humidity_2m: f64,
+}
+
+// Letzte Antwort je Standort; None nach einem Fehler
+struct Cached {
+    location: Location,
+    at: Instant,
+    weather: Option<Weather>,
+}
+
+// Standort bis zum Neustart, vorbelegt mit WEATHER_LOCATION
+static LOCATION: LazyLock<Mutex<Option<Location>>> = LazyLock::new(|| Mutex::new(settings().weather_location));
+static CACHE: Mutex<Option<Cached>> = Mutex::new(None);
+
+pub fn location() -> Option<Location> {
+    *LOCATION.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// None schaltet den Vergleich ab
+pub fn set_location(location: Option<Location>) {
+    *LOCATION.lock().unwrap_or_else(|e| e.into_inner()) = location;
+    *CACHE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder().timeout(TIMEOUT).build().unwrap_or_else(|err| {
+            warn!("HTTP-Client mit Zeitlimit nicht verfügbar, verwende Standard: {}", err);
+            reqwest::Client::new()
+        })
+    })
+}
+
+async fn fetch(location: Location) -> Result<Weather, String> {
+    let query = [
+        ("latitude", location.latitude.to_string()),
+        ("longitude", location.longitude.to_string()),
+        ("current", "temperature_2m,relative_humidity_2m".to_string()),
+    ];
+    let response = client().get(API_URL).query(&query).send().await.map_err(|err| err.without_url().to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Open-Meteo antwortet mit {}", response.status()));
+    }
+    let response: Response = response.json().await.map_err(|err| err.without_url().to_string())?;
+    Ok(Weather { temperature: response.current.temperature_2m, humidity: response.current.relative_humidity_2m })
+}
+
+// Wetter am Standort, höchstens CACHE_FOR alt; None ohne Standort oder
+// wenn Open-Meteo gerade nicht antwortet
+pub async fn current() -> Option<Weather> {
+    let location = location()?;
+    let now = Instant::now();
+    if let Some(cached) = CACHE.lock().unwrap_or_else(|e| e.into_inner()).as_ref().filter(|c| c.location == location) {
+        let valid = if cached.weather.is_some() { CACHE_FOR } else { RETRY_AFTER };
+        if now.duration_since(cached.at) < valid {
+            return cached.weather;
+        }
+    }
+    let weather = match fetch(location).await {
+        Ok(weather) => Some(weather),
+        Err(err) => {
+            warn!("Wetter für {} nicht verfügbar: {}", location, err);
+            None
+        }
+    };
+    *CACHE.lock().unwrap_or_else(|e| e.into_inner()) = Some(Cached { location, at: now, weather });
+    weather
+}
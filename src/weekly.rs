@@ -0,0 +1,44 @@
+use crate::history::Bucket;
+
+// Zeitraum des Wochenberichts
+pub const WEEK_SECONDS: i64 = 7 * 24 * 60 * 60;
+// Liegen zwei Messwerte weiter auseinander, gilt die Zeit dazwischen als
+// Lücke: sie zählt weder als innerhalb noch als außerhalb der Schwellen
+pub const MAX_GAP_SECONDS: i64 = 30 * 60;
+
+// Auswertung einer Messreihe über die Woche
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeekStats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub outside: i64, // Sekunden außerhalb der Schwellen
+    pub covered: i64, // Sekunden mit Messwerten, ohne Lücken
+}
+
+// Aus den Intervallen des Verlaufs (zeitlich sortiert) bis `end`. Ein
+// Messwert gilt bis zum nächsten, höchstens MAX_GAP_SECONDS lang; `outside`
+// sagt, ob ein Wert zu seinem Zeitpunkt eine Schwelle verletzt. None ohne
+// Messwerte.
+pub fn stats(points: &[Bucket], end: i64, outside: impl Fn(i64, f64) -> bool) -> Option<WeekStats> {
+    let total: u64 = points.iter().map(|b| b.count as u64).sum();
+    if total == 0 {
+        return None;
+    }
+    let min = points.iter().map(|b| b.min).fold(f64::INFINITY, f64::min);
+    let max = points.iter().map(|b| b.max).fold(f64::NEG_INFINITY, f64::max);
+    let mean = points.iter().map(|b| b.mean * b.count as f64).sum::<f64>() / total as f64;
+    let (mut outside_seconds, mut covered) = (0, 0);
+    for (index, bucket) in points.iter().enumerate() {
+        let next = points.get(index + 1).map_or(end, |b| b.start);
+        let span = next - bucket.start;
+        if span <= 0 || span > MAX_GAP_SECONDS {
+            continue;
+        }
+        covered += span;
+        if outside(bucket.start, bucket.mean) {
+            outside_seconds += span;
+        }
+    }
+    Some(WeekStats { min, max, mean, outside: outside_seconds, covered })
+}